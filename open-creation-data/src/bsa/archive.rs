@@ -0,0 +1,248 @@
+use super::{archive_flags, Version, COMPRESSION_TOGGLE, HEADER_SIZE, MAGIC, SIZE_MASK};
+use crate::path;
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Debug)]
+pub struct ArchiveEntry {
+    pub path: String,
+    size: u32,
+    offset: u64,
+    compressed: bool,
+}
+
+impl ArchiveEntry {
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// The number of bytes the entry occupies in the archive, including any compression header.
+    pub fn stored_size(&self) -> u32 {
+        self.size
+    }
+}
+
+/// A read-only view of a `.bsa` archive's directory. File contents are only read on extraction.
+#[derive(Debug)]
+pub struct Archive {
+    source: Option<PathBuf>,
+    version: Version,
+    flags: u32,
+    entries: Vec<ArchiveEntry>,
+}
+
+impl Archive {
+    pub fn open(source: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(source)?);
+        let mut archive = Self::read(&mut reader)?;
+        archive.source = Some(source.to_path_buf());
+
+        Ok(archive)
+    }
+
+    pub fn read<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
+        let length = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(invalid("not a BSA archive"));
+        }
+
+        let version = read_u32(reader)?;
+        let version =
+            Version::from_number(version).ok_or_else(|| invalid(&format!("unsupported BSA version {}", version)))?;
+
+        let offset = read_u32(reader)?;
+        let flags = read_u32(reader)?;
+        let folder_count = read_u32(reader)?;
+        let _file_count = read_u32(reader)?;
+        let _total_folder_name_length = read_u32(reader)?;
+        let total_file_name_length = read_u32(reader)?;
+        let _file_flags = read_u32(reader)?;
+
+        let position = reader.seek(SeekFrom::Start(offset.max(HEADER_SIZE) as u64))?;
+
+        if folder_count as u64 * version.folder_record_size() as u64 > length.saturating_sub(position) {
+            return Err(invalid("folder records run past the end of the archive"));
+        }
+
+        let mut folder_file_counts = Vec::with_capacity(folder_count as usize);
+
+        for _ in 0..folder_count {
+            let _hash = read_u64(reader)?;
+            folder_file_counts.push(read_u32(reader)?);
+
+            match version {
+                Version::Skyrim => {
+                    read_u32(reader)?;
+                }
+                Version::SkyrimSpecialEdition => {
+                    read_u32(reader)?;
+                    read_u64(reader)?;
+                }
+            }
+        }
+
+        let compressed_by_default = flags & archive_flags::COMPRESSED != 0;
+        let mut entries = vec![];
+
+        for file_count in folder_file_counts {
+            let folder = if flags & archive_flags::INCLUDE_DIRECTORY_NAMES != 0 {
+                read_bstring(reader)?
+            } else {
+                String::new()
+            };
+
+            for _ in 0..file_count {
+                let _hash = read_u64(reader)?;
+                let size = read_u32(reader)?;
+                let offset = read_u32(reader)?;
+
+                entries.push(ArchiveEntry {
+                    path: folder.clone(),
+                    size: size & SIZE_MASK,
+                    offset: offset as u64,
+                    compressed: compressed_by_default != (size & COMPRESSION_TOGGLE != 0),
+                });
+            }
+        }
+
+        if flags & archive_flags::INCLUDE_FILE_NAMES != 0 {
+            let names = read_bytes(reader, total_file_name_length as usize)?;
+
+            let names = names.split(|&c| c == 0).map(|name| String::from_utf8_lossy(name));
+
+            for (entry, name) in entries.iter_mut().zip(names) {
+                entry.path = if entry.path.is_empty() {
+                    path::normalize(&name)
+                } else {
+                    path::normalize(&format!("{}\\{}", entry.path, name))
+                };
+            }
+        }
+
+        Ok(Self {
+            source: None,
+            version,
+            flags,
+            entries,
+        })
+    }
+
+    pub fn source(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn entries(&self) -> &[ArchiveEntry] {
+        &self.entries
+    }
+
+    pub fn find(&self, path: &str) -> Option<&ArchiveEntry> {
+        let path = path::normalize(path);
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+
+    /// Reads and decompresses an entry from the archive file this was opened from.
+    pub fn extract(&self, entry: &ArchiveEntry) -> io::Result<Vec<u8>> {
+        let source = self
+            .source
+            .as_ref()
+            .ok_or_else(|| invalid("archive was not opened from a file"))?;
+
+        self.extract_from(&mut BufReader::new(File::open(source)?), entry)
+    }
+
+    pub fn extract_from<R: Read + Seek>(&self, reader: &mut R, entry: &ArchiveEntry) -> io::Result<Vec<u8>> {
+        reader.seek(SeekFrom::Start(entry.offset))?;
+        let mut remaining = entry.size as usize;
+
+        if self.flags & archive_flags::EMBED_FILE_NAMES != 0 {
+            let mut length = [0u8; 1];
+            reader.read_exact(&mut length)?;
+            reader.seek(SeekFrom::Current(length[0] as i64))?;
+            remaining = remaining.saturating_sub(1 + length[0] as usize);
+        }
+
+        if !entry.compressed {
+            return read_bytes(reader, remaining);
+        }
+
+        let original_size = read_u32(reader)? as u64;
+        let compressed = read_bytes(reader, remaining.saturating_sub(4))?;
+
+        // The decoders grow the buffer as they go, so a bogus original size can't reserve memory up front.
+        let mut data = vec![];
+
+        match self.version {
+            Version::Skyrim => {
+                flate2::read::ZlibDecoder::new(&compressed[..])
+                    .take(original_size)
+                    .read_to_end(&mut data)?;
+            }
+            Version::SkyrimSpecialEdition => {
+                lz4_flex::frame::FrameDecoder::new(&compressed[..])
+                    .take(original_size)
+                    .read_to_end(&mut data)?;
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buffer = [0u8; 8];
+    reader.read_exact(&mut buffer)?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+/// Reads `length` bytes, growing the buffer as data arrives rather than trusting a length read from the file.
+fn read_bytes<R: Read>(reader: &mut R, length: usize) -> io::Result<Vec<u8>> {
+    let mut buffer = vec![];
+    reader.take(length as u64).read_to_end(&mut buffer)?;
+
+    if buffer.len() < length {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "unexpected end of archive",
+        ));
+    }
+
+    Ok(buffer)
+}
+
+/// Reads a length-prefixed, null-terminated string.
+fn read_bstring<R: Read>(reader: &mut R) -> io::Result<String> {
+    let mut length = [0u8; 1];
+    reader.read_exact(&mut length)?;
+
+    let mut buffer = vec![0u8; length[0] as usize];
+    reader.read_exact(&mut buffer)?;
+
+    if buffer.last() == Some(&0) {
+        buffer.pop();
+    }
+
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
@@ -0,0 +1,51 @@
+//! The hashing scheme used to sort and look up folders and files in Skyrim archives.
+//!
+//! Inputs are expected to be normalised (lowercase, backslash-separated); see [`crate::path::normalize`].
+
+const MULTIPLIER: u32 = 0x1003F;
+
+pub fn hash_folder(folder: &str) -> u64 {
+    hash_parts(folder.as_bytes(), b"")
+}
+
+pub fn hash_file(name: &str) -> u64 {
+    match name.rfind('.') {
+        Some(index) => hash_parts(&name.as_bytes()[..index], &name.as_bytes()[index..]),
+        None => hash_parts(name.as_bytes(), b""),
+    }
+}
+
+fn hash_parts(stem: &[u8], extension: &[u8]) -> u64 {
+    let len = stem.len();
+    let mut low = 0u32;
+
+    if len > 0 {
+        low = stem[len - 1] as u32 | ((len as u32) << 16) | ((stem[0] as u32) << 24);
+
+        if len > 2 {
+            low |= (stem[len - 2] as u32) << 8;
+        }
+    }
+
+    low |= match extension {
+        b".kf" => 0x80,
+        b".nif" => 0x8000,
+        b".dds" => 0x8080,
+        b".wav" => 0x8000_0000,
+        _ => 0,
+    };
+
+    let mut high = 0u32;
+
+    if len > 2 {
+        for &c in &stem[1..len - 2] {
+            high = high.wrapping_mul(MULTIPLIER).wrapping_add(c as u32);
+        }
+    }
+
+    let extension_hash = extension
+        .iter()
+        .fold(0u32, |acc, &c| acc.wrapping_mul(MULTIPLIER).wrapping_add(c as u32));
+
+    ((high.wrapping_add(extension_hash) as u64) << 32) | low as u64
+}
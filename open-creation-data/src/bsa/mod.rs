@@ -0,0 +1,161 @@
+pub mod archive;
+pub mod hash;
+pub mod pack;
+pub mod writer;
+
+pub use archive::{Archive, ArchiveEntry};
+pub use pack::{archive_name_for_plugin, pack_directory, PackOptions, PackSummary};
+pub use writer::ArchiveWriter;
+
+const MAGIC: &[u8; 4] = b"BSA\0";
+const HEADER_SIZE: u32 = 36;
+
+/// Set on a file record's size when the file's compression differs from the archive default.
+const COMPRESSION_TOGGLE: u32 = 0x4000_0000;
+const SIZE_MASK: u32 = 0x3FFF_FFFF;
+
+pub mod archive_flags {
+    pub const INCLUDE_DIRECTORY_NAMES: u32 = 0x001;
+    pub const INCLUDE_FILE_NAMES: u32 = 0x002;
+    pub const COMPRESSED: u32 = 0x004;
+    pub const RETAIN_DIRECTORY_NAMES: u32 = 0x008;
+    pub const RETAIN_FILE_NAMES: u32 = 0x010;
+    pub const RETAIN_FILE_NAME_OFFSETS: u32 = 0x020;
+    pub const XBOX: u32 = 0x040;
+    pub const RETAIN_STRINGS_DURING_STARTUP: u32 = 0x080;
+    pub const EMBED_FILE_NAMES: u32 = 0x100;
+    pub const XMEM_CODEC: u32 = 0x200;
+}
+
+pub mod file_flags {
+    pub const MESHES: u32 = 0x001;
+    pub const TEXTURES: u32 = 0x002;
+    pub const MENUS: u32 = 0x004;
+    pub const SOUNDS: u32 = 0x008;
+    pub const VOICES: u32 = 0x010;
+    pub const SHADERS: u32 = 0x020;
+    pub const TREES: u32 = 0x040;
+    pub const FONTS: u32 = 0x080;
+    pub const MISC: u32 = 0x100;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Version {
+    /// Skyrim Legendary Edition archives, compressed with zlib.
+    Skyrim,
+    /// Skyrim Special Edition archives, compressed with LZ4 frames.
+    SkyrimSpecialEdition,
+}
+
+impl Version {
+    pub fn number(self) -> u32 {
+        match self {
+            Version::Skyrim => 104,
+            Version::SkyrimSpecialEdition => 105,
+        }
+    }
+
+    pub fn from_number(number: u32) -> Option<Self> {
+        match number {
+            104 => Some(Version::Skyrim),
+            105 => Some(Version::SkyrimSpecialEdition),
+            _ => None,
+        }
+    }
+
+    fn folder_record_size(self) -> u32 {
+        match self {
+            Version::Skyrim => 16,
+            Version::SkyrimSpecialEdition => 24,
+        }
+    }
+}
+
+/// Returns the archive file-type flag for a normalised asset path, based on its extension.
+pub fn file_flags_for(path: &str) -> u32 {
+    let extension = path.rsplit('.').next().unwrap_or("");
+
+    match extension {
+        "nif" | "kf" | "hkx" | "tri" | "egm" | "btr" | "bto" => file_flags::MESHES,
+        "dds" | "png" | "tga" => file_flags::TEXTURES,
+        "swf" | "xml" | "txt" if path.starts_with("interface\\") => file_flags::MENUS,
+        "wav" | "xwm" => file_flags::SOUNDS,
+        "fuz" | "lip" => file_flags::VOICES,
+        "fxp" => file_flags::SHADERS,
+        "spt" => file_flags::TREES,
+        "fnt" | "tex" => file_flags::FONTS,
+        _ => file_flags::MISC,
+    }
+}
+
+/// The game cannot stream compressed audio, so sounds and voices are always stored uncompressed.
+fn may_compress(flags: u32) -> bool {
+    flags & (file_flags::SOUNDS | file_flags::VOICES) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn round_trip(version: Version, compress: bool) {
+        let mesh = b"mesh data mesh data mesh data mesh data".to_vec();
+        let texture = vec![7u8; 4096];
+        let sound = b"RIFF....WAVEfmt ".to_vec();
+
+        let mut writer = ArchiveWriter::new(version).compress(compress);
+        writer.add_data("Meshes/Clutter/Bucket.nif", mesh.clone());
+        writer.add_data("textures\\clutter\\bucket.dds", texture.clone());
+        writer.add_data("sound/fx/drip.wav", sound.clone());
+
+        let mut buffer = Cursor::new(vec![]);
+        writer.write(&mut buffer).unwrap();
+        buffer.set_position(0);
+
+        let archive = Archive::read(&mut buffer).unwrap();
+        assert_eq!(archive.version(), version);
+        assert_eq!(archive.entries().len(), 3);
+
+        let entry = archive.find("meshes\\clutter\\bucket.nif").unwrap();
+        assert_eq!(archive.extract_from(&mut buffer, entry).unwrap(), mesh);
+
+        let entry = archive.find("textures\\clutter\\bucket.dds").unwrap();
+        assert_eq!(entry.is_compressed(), compress);
+        assert_eq!(archive.extract_from(&mut buffer, entry).unwrap(), texture);
+
+        let entry = archive.find("sound\\fx\\drip.wav").unwrap();
+        assert!(!entry.is_compressed());
+        assert_eq!(archive.extract_from(&mut buffer, entry).unwrap(), sound);
+    }
+
+    #[test]
+    fn round_trip_skyrim() {
+        round_trip(Version::Skyrim, false);
+        round_trip(Version::Skyrim, true);
+    }
+
+    #[test]
+    fn round_trip_special_edition() {
+        round_trip(Version::SkyrimSpecialEdition, false);
+        round_trip(Version::SkyrimSpecialEdition, true);
+    }
+
+    fn header(flags: u32, folder_count: u32, total_file_name_length: u32) -> Cursor<Vec<u8>> {
+        let mut bytes = MAGIC.to_vec();
+
+        for value in &[105, HEADER_SIZE, flags, folder_count, 0, 0, total_file_name_length, 0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        Cursor::new(bytes)
+    }
+
+    #[test]
+    fn rejects_sizes_past_the_end_of_the_archive() {
+        let error = Archive::read(&mut header(0, u32::MAX, 0)).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        let error = Archive::read(&mut header(archive_flags::INCLUDE_FILE_NAMES, 0, u32::MAX)).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
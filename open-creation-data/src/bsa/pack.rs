@@ -0,0 +1,190 @@
+use super::{ArchiveWriter, Version};
+use crate::path;
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Debug)]
+pub struct PackOptions {
+    pub version: Version,
+    pub compress: bool,
+    /// Wildcard patterns a file must match one of to be packed. An empty list includes everything.
+    pub include: Vec<String>,
+    /// Wildcard patterns that exclude a file, even if it is included.
+    pub exclude: Vec<String>,
+}
+
+impl Default for PackOptions {
+    fn default() -> Self {
+        Self {
+            version: Version::SkyrimSpecialEdition,
+            compress: false,
+            include: vec![],
+            exclude: vec![],
+        }
+    }
+}
+
+impl PackOptions {
+    pub fn accepts(&self, relative_path: &str) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|pattern| path::matches(pattern, relative_path));
+        included && !self.exclude.iter().any(|pattern| path::matches(pattern, relative_path))
+    }
+}
+
+#[derive(Debug)]
+pub struct PackSummary {
+    pub archive: PathBuf,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Returns the archive file name the game loads alongside a plugin, e.g. `MyMod.esp` -> `MyMod.bsa`.
+pub fn archive_name_for_plugin(plugin: &str) -> String {
+    let stem = Path::new(plugin)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| plugin.to_string());
+
+    format!("{}.bsa", stem)
+}
+
+/// Walks `source` and returns the data-relative path and on-disk location of every file the options accept.
+pub fn collect_files(source: &Path, options: &PackOptions) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut files = vec![];
+    let mut pending = vec![source.to_path_buf()];
+
+    while let Some(directory) = pending.pop() {
+        for entry in fs::read_dir(&directory)? {
+            let entry = entry?;
+            let file_path = entry.path();
+
+            if entry.file_type()?.is_dir() {
+                pending.push(file_path);
+                continue;
+            }
+
+            let relative = file_path.strip_prefix(source).unwrap_or(&file_path);
+            let relative = path::normalize(&relative.to_string_lossy());
+
+            if options.accepts(&relative) {
+                files.push((relative, file_path));
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Packs the accepted files under `source` into `archive`. An archive written inside `source` is never packed into
+/// itself.
+pub fn pack_directory(source: &Path, archive: &Path, options: &PackOptions) -> io::Result<PackSummary> {
+    let mut writer = ArchiveWriter::new(options.version).compress(options.compress);
+    let output = comparable_path(archive);
+
+    for (relative, file_path) in collect_files(source, options)? {
+        if comparable_path(&file_path) == output {
+            continue;
+        }
+
+        writer.add_file(&relative, file_path);
+    }
+
+    if writer.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no files matched the include list",
+        ));
+    }
+
+    let files = writer.len();
+    writer.write(&mut BufWriter::new(File::create(archive)?))?;
+
+    Ok(PackSummary {
+        archive: archive.to_path_buf(),
+        files,
+        bytes: fs::metadata(archive)?.len(),
+    })
+}
+
+/// Resolves a path through its existing parent folder and lowercases it, so relative, differently cased and
+/// not-yet-created paths to the same file compare equal.
+fn comparable_path(path: &Path) -> String {
+    let resolved = path.canonicalize().unwrap_or_else(|_| {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        match (parent.canonicalize(), path.file_name()) {
+            (Ok(parent), Some(name)) => parent.join(name),
+            _ => path.to_path_buf(),
+        }
+    });
+
+    resolved.to_string_lossy().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsa::Archive;
+
+    /// A scratch folder under the system temp directory, removed when dropped.
+    struct ScratchDir(PathBuf);
+
+    impl ScratchDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("open_creation_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for ScratchDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn packs_a_folder_without_its_own_output() {
+        let scratch = ScratchDir::new("pack");
+        let source = scratch.0.join("Data");
+        fs::create_dir_all(source.join("meshes").join("clutter")).unwrap();
+        fs::create_dir_all(source.join("textures")).unwrap();
+
+        let mesh = b"mesh data mesh data mesh data".to_vec();
+        let texture = vec![7u8; 4096];
+        fs::write(source.join("meshes").join("clutter").join("Bucket.nif"), &mesh).unwrap();
+        fs::write(source.join("textures").join("bucket.dds"), &texture).unwrap();
+        fs::write(source.join("MyMod.BSA"), b"an archive from an earlier run").unwrap();
+
+        let options = PackOptions {
+            compress: true,
+            ..Default::default()
+        };
+        let archive_path = source.join("..").join("Data").join("MyMod.bsa");
+        let summary = pack_directory(&source, &archive_path, &options).unwrap();
+        assert_eq!(summary.files, 2);
+
+        let archive = Archive::open(&archive_path).unwrap();
+        assert_eq!(archive.entries().len(), 2);
+        assert!(archive.find("mymod.bsa").is_none());
+
+        for (path, contents) in &[
+            ("meshes\\clutter\\bucket.nif", &mesh),
+            ("textures\\bucket.dds", &texture),
+        ] {
+            let entry = archive.find(path).unwrap();
+            assert!(entry.is_compressed());
+            assert_eq!(&archive.extract(entry).unwrap(), *contents);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use super::{
+    archive_flags, file_flags_for,
+    hash::{hash_file, hash_folder},
+    may_compress, Version, COMPRESSION_TOGGLE, HEADER_SIZE, MAGIC,
+};
+use crate::path;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+enum Source {
+    File(PathBuf),
+    Data(Vec<u8>),
+}
+
+struct Folder {
+    name: String,
+    hash: u64,
+    files: Vec<(String, u64, Source)>,
+}
+
+/// Builds a `.bsa` archive from loose files or in-memory data.
+pub struct ArchiveWriter {
+    version: Version,
+    compress: bool,
+    files: BTreeMap<String, Source>,
+}
+
+impl ArchiveWriter {
+    pub fn new(version: Version) -> Self {
+        Self {
+            version,
+            compress: false,
+            files: BTreeMap::new(),
+        }
+    }
+
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Adds a file to be read from disk when the archive is written. Adding the same archive path twice
+    /// replaces the earlier entry.
+    pub fn add_file(&mut self, archive_path: &str, source: PathBuf) {
+        self.files.insert(path::normalize(archive_path), Source::File(source));
+    }
+
+    pub fn add_data(&mut self, archive_path: &str, data: Vec<u8>) {
+        self.files.insert(path::normalize(archive_path), Source::Data(data));
+    }
+
+    pub fn write<W: Write + Seek>(self, writer: &mut W) -> io::Result<()> {
+        let version = self.version;
+        let compress = self.compress;
+
+        let mut file_flags = 0;
+        let mut folders: BTreeMap<String, Folder> = BTreeMap::new();
+
+        for (archive_path, source) in self.files {
+            file_flags |= file_flags_for(&archive_path);
+
+            let (folder, name) = path::split(&archive_path);
+            let folder = folders.entry(folder.to_string()).or_insert_with(|| Folder {
+                name: folder.to_string(),
+                hash: hash_folder(folder),
+                files: vec![],
+            });
+
+            folder.files.push((name.to_string(), hash_file(name), source));
+        }
+
+        let mut folders: Vec<Folder> = folders.into_values().collect();
+        folders.sort_by_key(|folder| folder.hash);
+        folders
+            .iter_mut()
+            .for_each(|folder| folder.files.sort_by_key(|(_, hash, _)| *hash));
+
+        let file_count: usize = folders.iter().map(|folder| folder.files.len()).sum();
+        let total_folder_name_length: usize = folders.iter().map(|folder| folder.name.len() + 1).sum();
+        let total_file_name_length: usize = folders
+            .iter()
+            .flat_map(|folder| folder.files.iter())
+            .map(|(name, _, _)| name.len() + 1)
+            .sum();
+
+        let mut flags = archive_flags::INCLUDE_DIRECTORY_NAMES | archive_flags::INCLUDE_FILE_NAMES;
+
+        if compress {
+            flags |= archive_flags::COMPRESSED;
+        }
+
+        let start = writer.stream_position()?;
+
+        writer.write_all(MAGIC)?;
+        write_u32(writer, version.number())?;
+        write_u32(writer, HEADER_SIZE)?;
+        write_u32(writer, flags)?;
+        write_u32(writer, folders.len() as u32)?;
+        write_u32(writer, file_count as u32)?;
+        write_u32(writer, total_folder_name_length as u32)?;
+        write_u32(writer, total_file_name_length as u32)?;
+        write_u32(writer, file_flags)?;
+
+        // Folder offsets point at the folder's file record block, skewed by the file name table length.
+        let mut block_offset = HEADER_SIZE as u64 + folders.len() as u64 * version.folder_record_size() as u64;
+
+        for folder in &folders {
+            let offset = block_offset + total_file_name_length as u64;
+
+            writer.write_all(&folder.hash.to_le_bytes())?;
+            write_u32(writer, folder.files.len() as u32)?;
+
+            match version {
+                Version::Skyrim => write_u32(writer, offset as u32)?,
+                Version::SkyrimSpecialEdition => {
+                    write_u32(writer, 0)?;
+                    writer.write_all(&offset.to_le_bytes())?;
+                }
+            }
+
+            block_offset += 1 + folder.name.len() as u64 + 1 + folder.files.len() as u64 * 16;
+        }
+
+        let mut record_positions = Vec::with_capacity(file_count);
+
+        for folder in &folders {
+            writer.write_all(&[(folder.name.len() + 1) as u8])?;
+            writer.write_all(folder.name.as_bytes())?;
+            writer.write_all(&[0])?;
+
+            for (_, hash, _) in &folder.files {
+                record_positions.push(writer.stream_position()?);
+                writer.write_all(&hash.to_le_bytes())?;
+                write_u32(writer, 0)?;
+                write_u32(writer, 0)?;
+            }
+        }
+
+        for (name, _, _) in folders.iter().flat_map(|folder| folder.files.iter()) {
+            writer.write_all(name.as_bytes())?;
+            writer.write_all(&[0])?;
+        }
+
+        let mut records = Vec::with_capacity(file_count);
+
+        for folder in &folders {
+            for (name, _, source) in &folder.files {
+                let data = match source {
+                    Source::File(path) => fs::read(path)?,
+                    Source::Data(data) => data.clone(),
+                };
+
+                let offset = writer.stream_position()? - start;
+
+                if offset > u32::MAX as u64 {
+                    return Err(io::Error::other("archive exceeds 4GB"));
+                }
+
+                let full_path = format!("{}\\{}", folder.name, name);
+                let compressed = compress && may_compress(file_flags_for(&full_path));
+
+                let mut size = if compressed {
+                    let packed = compress_data(version, &data)?;
+                    write_u32(writer, data.len() as u32)?;
+                    writer.write_all(&packed)?;
+                    packed.len() as u32 + 4
+                } else {
+                    writer.write_all(&data)?;
+                    data.len() as u32
+                };
+
+                if compressed != compress {
+                    size |= COMPRESSION_TOGGLE;
+                }
+
+                records.push((size, offset as u32));
+            }
+        }
+
+        let end = writer.stream_position()?;
+
+        for (position, (size, offset)) in record_positions.into_iter().zip(records) {
+            writer.seek(SeekFrom::Start(position + 8))?;
+            write_u32(writer, size)?;
+            write_u32(writer, offset)?;
+        }
+
+        writer.seek(SeekFrom::Start(end))?;
+        writer.flush()
+    }
+}
+
+fn compress_data(version: Version, data: &[u8]) -> io::Result<Vec<u8>> {
+    match version {
+        Version::Skyrim => {
+            let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        Version::SkyrimSpecialEdition => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(vec![]);
+            encoder.write_all(data)?;
+            encoder.finish().map_err(|e| io::Error::other(e.to_string()))
+        }
+    }
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
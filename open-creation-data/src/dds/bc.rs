@@ -0,0 +1,124 @@
+//! Decoders for the 4x4 block formats BC1-BC5. Each writes 16 RGBA pixels in row-major order.
+
+pub type Block = [[u8; 4]; 16];
+
+fn rgb565(value: u16) -> [u8; 3] {
+    let r = ((value >> 11) & 0x1f) as u32;
+    let g = ((value >> 5) & 0x3f) as u32;
+    let b = (value & 0x1f) as u32;
+    [(r * 255 / 31) as u8, (g * 255 / 63) as u8, (b * 255 / 31) as u8]
+}
+
+fn mix(a: u8, b: u8, weight_a: u32, weight_b: u32) -> u8 {
+    ((a as u32 * weight_a + b as u32 * weight_b) / (weight_a + weight_b)) as u8
+}
+
+/// Decodes the colour half of a BC1-BC3 block. BC1 alone uses the three-colour mode with transparent black.
+pub fn color(bytes: &[u8], punch_through: bool, pixels: &mut Block) {
+    let c0 = u16::from_le_bytes([bytes[0], bytes[1]]);
+    let c1 = u16::from_le_bytes([bytes[2], bytes[3]]);
+    let [r0, g0, b0] = rgb565(c0);
+    let [r1, g1, b1] = rgb565(c1);
+
+    let palette = if c0 > c1 || !punch_through {
+        [
+            [r0, g0, b0, 255],
+            [r1, g1, b1, 255],
+            [mix(r0, r1, 2, 1), mix(g0, g1, 2, 1), mix(b0, b1, 2, 1), 255],
+            [mix(r0, r1, 1, 2), mix(g0, g1, 1, 2), mix(b0, b1, 1, 2), 255],
+        ]
+    } else {
+        [
+            [r0, g0, b0, 255],
+            [r1, g1, b1, 255],
+            [mix(r0, r1, 1, 1), mix(g0, g1, 1, 1), mix(b0, b1, 1, 1), 255],
+            [0, 0, 0, 0],
+        ]
+    };
+
+    let indices = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = palette[((indices >> (i * 2)) & 3) as usize];
+    }
+}
+
+/// Decodes an interpolated 8-bit channel, as used for BC3 alpha and the channels of BC4 and BC5.
+pub fn channel(bytes: &[u8], values: &mut [u8; 16]) {
+    let a0 = bytes[0] as u32;
+    let a1 = bytes[1] as u32;
+    let mut palette = [0u8; 8];
+    palette[0] = a0 as u8;
+    palette[1] = a1 as u8;
+
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i as u32) * a0 + i as u32 * a1) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i as u32) * a0 + i as u32 * a1) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    let mut indices = 0u64;
+    for (i, &byte) in bytes[2..8].iter().enumerate() {
+        indices |= (byte as u64) << (i * 8);
+    }
+
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = palette[((indices >> (i * 3)) & 7) as usize];
+    }
+}
+
+pub fn bc1(bytes: &[u8], pixels: &mut Block) {
+    color(bytes, true, pixels);
+}
+
+pub fn bc2(bytes: &[u8], pixels: &mut Block) {
+    color(&bytes[8..16], false, pixels);
+
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let alpha = (bytes[i / 2] >> ((i % 2) * 4)) & 0xf;
+        pixel[3] = alpha * 17;
+    }
+}
+
+pub fn bc3(bytes: &[u8], pixels: &mut Block) {
+    color(&bytes[8..16], false, pixels);
+
+    let mut alpha = [0; 16];
+    channel(&bytes[..8], &mut alpha);
+
+    for (pixel, alpha) in pixels.iter_mut().zip(alpha.iter()) {
+        pixel[3] = *alpha;
+    }
+}
+
+/// Decodes a single-channel block as greyscale.
+pub fn bc4(bytes: &[u8], pixels: &mut Block) {
+    let mut values = [0; 16];
+    channel(bytes, &mut values);
+
+    for (pixel, &value) in pixels.iter_mut().zip(values.iter()) {
+        *pixel = [value, value, value, 255];
+    }
+}
+
+/// Decodes a two-channel block. These hold tangent-space normals in practice, so blue is rebuilt from red and
+/// green to give a usable normal map.
+pub fn bc5(bytes: &[u8], pixels: &mut Block) {
+    let mut red = [0; 16];
+    let mut green = [0; 16];
+    channel(&bytes[..8], &mut red);
+    channel(&bytes[8..16], &mut green);
+
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let x = red[i] as f32 / 127.5 - 1.0;
+        let y = green[i] as f32 / 127.5 - 1.0;
+        let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+        *pixel = [red[i], green[i], ((z + 1.0) * 127.5) as u8, 255];
+    }
+}
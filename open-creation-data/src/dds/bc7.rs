@@ -0,0 +1,327 @@
+//! BC7 block decoding, following the BPTC format in the Direct3D 11 specification.
+
+use super::bc::Block;
+
+struct Mode {
+    subsets: usize,
+    partition_bits: usize,
+    rotation_bits: usize,
+    index_selection_bits: usize,
+    color_bits: usize,
+    alpha_bits: usize,
+    /// One p-bit per endpoint.
+    endpoint_p_bits: bool,
+    /// One p-bit per subset, shared by both of its endpoints.
+    shared_p_bits: bool,
+    index_bits: usize,
+    secondary_index_bits: usize,
+}
+
+#[allow(clippy::too_many_arguments)]
+const fn mode(
+    subsets: usize,
+    partition_bits: usize,
+    rotation_bits: usize,
+    index_selection_bits: usize,
+    color_bits: usize,
+    alpha_bits: usize,
+    endpoint_p_bits: bool,
+    shared_p_bits: bool,
+    index_bits: usize,
+    secondary_index_bits: usize,
+) -> Mode {
+    Mode {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_p_bits,
+        shared_p_bits,
+        index_bits,
+        secondary_index_bits,
+    }
+}
+
+const MODES: [Mode; 8] = [
+    mode(3, 4, 0, 0, 4, 0, true, false, 3, 0),
+    mode(2, 6, 0, 0, 6, 0, false, true, 3, 0),
+    mode(3, 6, 0, 0, 5, 0, false, false, 2, 0),
+    mode(2, 6, 0, 0, 7, 0, true, false, 2, 0),
+    mode(1, 0, 2, 1, 5, 6, false, false, 2, 3),
+    mode(1, 0, 2, 0, 7, 8, false, false, 2, 2),
+    mode(1, 0, 0, 0, 7, 7, true, false, 4, 0),
+    mode(2, 6, 0, 0, 5, 5, true, false, 2, 0),
+];
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// Two-subset partitions as masks, where bit `i` set means pixel `i` belongs to the second subset.
+const PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80, 0xc800, 0xffec, 0xfe80, 0xe800, 0xffe8, 0xff00,
+    0xfff0, 0xf000, 0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce, 0x088c, 0x3110, 0x6666, 0x366c,
+    0x17e8, 0x0ff0, 0x718e, 0x399c, 0xaaaa, 0xf0f0, 0x5a5a, 0x33cc, 0x3c3c, 0x55aa, 0x9696, 0xa55a, 0x73ce, 0x13c8,
+    0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660, 0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c,
+    0x9336, 0x9cc6, 0x817e, 0xe718, 0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+const PARTITIONS_3: [[u8; 16]; 64] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+    [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2],
+    [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+    [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0],
+    [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0],
+    [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+    [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+    [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2],
+    [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0],
+    [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+    [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0],
+    [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1],
+    [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1],
+    [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+    [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2],
+    [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2],
+    [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+    [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+    [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1],
+    [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0],
+];
+
+/// The anchor pixel of the second subset in two-subset partitions.
+const ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// The anchor pixels of the second and third subsets in three-subset partitions.
+const ANCHORS_3: [[u8; 64]; 2] = [
+    [
+        3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5, 15, 15, 8, 15,
+        3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8, 5, 10, 5, 10, 8, 13, 15, 12, 3, 3,
+    ],
+    [
+        15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6, 10, 15, 15, 10,
+        8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 3, 15,
+        15, 8,
+    ],
+];
+
+/// Reads a block's fields, least significant bit first.
+struct Bits<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Bits<'a> {
+    fn read(&mut self, count: usize) -> u32 {
+        let mut value = 0;
+
+        for i in 0..count {
+            let bit = (self.bytes[self.position / 8] >> (self.position % 8)) & 1;
+            value |= (bit as u32) << i;
+            self.position += 1;
+        }
+
+        value
+    }
+}
+
+fn weights(bits: usize) -> &'static [u32] {
+    match bits {
+        2 => &WEIGHTS_2,
+        3 => &WEIGHTS_3,
+        _ => &WEIGHTS_4,
+    }
+}
+
+fn interpolate(e0: u32, e1: u32, weight: u32) -> u8 {
+    (((64 - weight) * e0 + weight * e1 + 32) >> 6) as u8
+}
+
+/// Expands a `bits`-wide endpoint value to 8 bits by replicating its high bits.
+fn unquantize(value: u32, bits: usize) -> u32 {
+    if bits >= 8 {
+        value
+    } else {
+        (value << (8 - bits)) | (value >> (2 * bits - 8))
+    }
+}
+
+pub fn bc7(bytes: &[u8], pixels: &mut Block) {
+    let mode_index = match (0..8).find(|&mode| bytes[0] & (1 << mode) != 0) {
+        Some(mode) => mode,
+        None => {
+            // Reserved mode: the specification says to decode as transparent black.
+            *pixels = [[0; 4]; 16];
+            return;
+        }
+    };
+
+    let mode = &MODES[mode_index];
+    let mut bits = Bits { bytes, position: 0 };
+    bits.read(mode_index + 1);
+
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+    let endpoint_count = mode.subsets * 2;
+
+    let mut endpoints = [[0u32; 4]; 6];
+
+    for channel in 0..3 {
+        for endpoint in endpoints.iter_mut().take(endpoint_count) {
+            endpoint[channel] = bits.read(mode.color_bits);
+        }
+    }
+
+    if mode.alpha_bits > 0 {
+        for endpoint in endpoints.iter_mut().take(endpoint_count) {
+            endpoint[3] = bits.read(mode.alpha_bits);
+        }
+    }
+
+    let mut p_bits = [0u32; 6];
+
+    if mode.endpoint_p_bits {
+        for p_bit in p_bits.iter_mut().take(endpoint_count) {
+            *p_bit = bits.read(1);
+        }
+    } else if mode.shared_p_bits {
+        for subset in 0..mode.subsets {
+            let p_bit = bits.read(1);
+            p_bits[subset * 2] = p_bit;
+            p_bits[subset * 2 + 1] = p_bit;
+        }
+    }
+
+    let has_p_bits = mode.endpoint_p_bits || mode.shared_p_bits;
+
+    for (endpoint, p_bit) in endpoints.iter_mut().zip(p_bits.iter()).take(endpoint_count) {
+        for (channel, value) in endpoint.iter_mut().enumerate() {
+            let mut channel_bits = if channel == 3 { mode.alpha_bits } else { mode.color_bits };
+
+            if channel_bits == 0 {
+                *value = 255;
+                continue;
+            }
+
+            if has_p_bits {
+                *value = (*value << 1) | p_bit;
+                channel_bits += 1;
+            }
+
+            *value = unquantize(*value, channel_bits);
+        }
+    }
+
+    let subset_of = |pixel: usize| -> usize {
+        match mode.subsets {
+            2 => ((PARTITIONS_2[partition] >> pixel) & 1) as usize,
+            3 => PARTITIONS_3[partition][pixel] as usize,
+            _ => 0,
+        }
+    };
+
+    let is_anchor = |pixel: usize| -> bool {
+        pixel == 0
+            || match mode.subsets {
+                2 => pixel == ANCHORS_2[partition] as usize,
+                3 => pixel == ANCHORS_3[0][partition] as usize || pixel == ANCHORS_3[1][partition] as usize,
+                _ => false,
+            }
+    };
+
+    let mut indices = [0u32; 16];
+    for (pixel, index) in indices.iter_mut().enumerate() {
+        *index = bits.read(mode.index_bits - is_anchor(pixel) as usize);
+    }
+
+    let mut secondary_indices = [0u32; 16];
+    if mode.secondary_index_bits > 0 {
+        for (pixel, index) in secondary_indices.iter_mut().enumerate() {
+            *index = bits.read(mode.secondary_index_bits - (pixel == 0) as usize);
+        }
+    }
+
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let subset = subset_of(i);
+        let e0 = endpoints[subset * 2];
+        let e1 = endpoints[subset * 2 + 1];
+
+        let (color_weight, alpha_weight) = if mode.secondary_index_bits == 0 {
+            let weight = weights(mode.index_bits)[indices[i] as usize];
+            (weight, weight)
+        } else {
+            let primary = weights(mode.index_bits)[indices[i] as usize];
+            let secondary = weights(mode.secondary_index_bits)[secondary_indices[i] as usize];
+
+            if index_selection == 0 {
+                (primary, secondary)
+            } else {
+                (secondary, primary)
+            }
+        };
+
+        *pixel = [
+            interpolate(e0[0], e1[0], color_weight),
+            interpolate(e0[1], e1[1], color_weight),
+            interpolate(e0[2], e1[2], color_weight),
+            interpolate(e0[3], e1[3], alpha_weight),
+        ];
+
+        match rotation {
+            1 => pixel.swap(0, 3),
+            2 => pixel.swap(1, 3),
+            3 => pixel.swap(2, 3),
+            _ => {}
+        }
+    }
+}
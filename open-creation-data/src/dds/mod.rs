@@ -0,0 +1,430 @@
+//! Reading DirectDraw Surface textures (`.dds`) and decoding them to RGBA8 on the CPU.
+//!
+//! Supports the block-compressed formats BC1-BC5 and BC7 (through the legacy FourCC codes or a DX10 header)
+//! and uncompressed formats described by bit masks. Cubemaps and texture arrays only expose their first face.
+
+mod bc;
+mod bc7;
+
+use crate::esp::invalid;
+
+use std::{fmt, io};
+
+const MAGIC: &[u8; 4] = b"DDS ";
+const HEADER_SIZE: usize = 124;
+const DX10_HEADER_SIZE: usize = 20;
+
+const DDSD_MIPMAPCOUNT: u32 = 0x2_0000;
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_ALPHA: u32 = 0x2;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_LUMINANCE: u32 = 0x2_0000;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+
+/// The layout of an uncompressed pixel, as channel bit masks within a little-endian integer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelFormat {
+    pub bits_per_pixel: u32,
+    pub red_mask: u32,
+    pub green_mask: u32,
+    pub blue_mask: u32,
+    pub alpha_mask: u32,
+    /// Whether the red mask holds a luminance value for all three colour channels.
+    pub luminance: bool,
+}
+
+impl PixelFormat {
+    const RGBA8: PixelFormat = PixelFormat::new(32, 0xff, 0xff00, 0xff_0000, 0xff00_0000);
+    const BGRA8: PixelFormat = PixelFormat::new(32, 0xff_0000, 0xff00, 0xff, 0xff00_0000);
+    const BGRX8: PixelFormat = PixelFormat::new(32, 0xff_0000, 0xff00, 0xff, 0);
+    const R8: PixelFormat = PixelFormat {
+        luminance: true,
+        ..PixelFormat::new(8, 0xff, 0, 0, 0)
+    };
+
+    const fn new(bits_per_pixel: u32, red_mask: u32, green_mask: u32, blue_mask: u32, alpha_mask: u32) -> Self {
+        Self {
+            bits_per_pixel,
+            red_mask,
+            green_mask,
+            blue_mask,
+            alpha_mask,
+            luminance: false,
+        }
+    }
+
+    fn decode(&self, bytes: &[u8], pixel: &mut [u8]) {
+        let mut value = 0u32;
+        for (i, &byte) in bytes.iter().enumerate() {
+            value |= (byte as u32) << (i * 8);
+        }
+
+        let channel = |mask: u32, default: u8| -> u8 {
+            if mask == 0 {
+                return default;
+            }
+
+            let shift = mask.trailing_zeros();
+            let max = (mask >> shift) as u64;
+            (((value & mask) >> shift) as u64 * 255 / max) as u8
+        };
+
+        let red = channel(self.red_mask, 0);
+
+        if self.luminance {
+            pixel.copy_from_slice(&[red, red, red, channel(self.alpha_mask, 255)]);
+        } else {
+            pixel.copy_from_slice(&[
+                red,
+                channel(self.green_mask, 0),
+                channel(self.blue_mask, 0),
+                channel(self.alpha_mask, 255),
+            ]);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Bc1,
+    Bc2,
+    Bc3,
+    Bc4,
+    Bc5,
+    Bc7,
+    Uncompressed(PixelFormat),
+}
+
+impl Format {
+    fn from_four_cc(code: &[u8]) -> Option<Self> {
+        match code {
+            b"DXT1" => Some(Self::Bc1),
+            b"DXT2" | b"DXT3" => Some(Self::Bc2),
+            b"DXT4" | b"DXT5" => Some(Self::Bc3),
+            b"ATI1" | b"BC4U" => Some(Self::Bc4),
+            b"ATI2" | b"BC5U" => Some(Self::Bc5),
+            _ => None,
+        }
+    }
+
+    fn from_dxgi(format: u32) -> Option<Self> {
+        match format {
+            28 | 29 => Some(Self::Uncompressed(PixelFormat::RGBA8)),
+            61 => Some(Self::Uncompressed(PixelFormat::R8)),
+            70..=72 => Some(Self::Bc1),
+            73..=75 => Some(Self::Bc2),
+            76..=78 => Some(Self::Bc3),
+            79 | 80 => Some(Self::Bc4),
+            82 | 83 => Some(Self::Bc5),
+            87 | 91 => Some(Self::Uncompressed(PixelFormat::BGRA8)),
+            88 | 93 => Some(Self::Uncompressed(PixelFormat::BGRX8)),
+            97..=99 => Some(Self::Bc7),
+            _ => None,
+        }
+    }
+
+    /// Bytes per 4x4 block, for block-compressed formats.
+    fn block_size(&self) -> Option<usize> {
+        match self {
+            Self::Bc1 | Self::Bc4 => Some(8),
+            Self::Bc2 | Self::Bc3 | Self::Bc5 | Self::Bc7 => Some(16),
+            Self::Uncompressed(_) => None,
+        }
+    }
+
+    /// The bytes in one surface, or `None` if that is too large to address.
+    fn surface_size(&self, width: u32, height: u32) -> Option<usize> {
+        match (self.block_size(), self) {
+            (Some(block_size), _) => {
+                let blocks_wide = width.div_ceil(4) as usize;
+                let blocks_high = height.div_ceil(4) as usize;
+                blocks_wide.checked_mul(blocks_high)?.checked_mul(block_size)
+            }
+            (None, Self::Uncompressed(pixel)) => (width as usize)
+                .checked_mul(height as usize)?
+                .checked_mul(pixel.bits_per_pixel as usize / 8),
+            (None, _) => Some(0),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bc1 => write!(f, "BC1 (DXT1)"),
+            Self::Bc2 => write!(f, "BC2 (DXT3)"),
+            Self::Bc3 => write!(f, "BC3 (DXT5)"),
+            Self::Bc4 => write!(f, "BC4 (ATI1)"),
+            Self::Bc5 => write!(f, "BC5 (ATI2)"),
+            Self::Bc7 => write!(f, "BC7"),
+            Self::Uncompressed(pixel) => {
+                let channels = if pixel.luminance { "L" } else { "RGB" };
+                let alpha = if pixel.alpha_mask != 0 { "A" } else { "" };
+                write!(f, "Uncompressed {}{} {}-bit", channels, alpha, pixel.bits_per_pixel)
+            }
+        }
+    }
+}
+
+pub struct Dds {
+    pub width: u32,
+    pub height: u32,
+    pub format: Format,
+    pub is_cubemap: bool,
+    /// The mip chain of the first surface, largest first.
+    mips: Vec<Vec<u8>>,
+}
+
+impl Dds {
+    pub fn parse(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < 4 + HEADER_SIZE || &bytes[..4] != MAGIC {
+            return Err(invalid("not a DDS file"));
+        }
+
+        let u32_at = |offset: usize| {
+            u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+        };
+
+        let flags = u32_at(8);
+        let height = u32_at(12);
+        let width = u32_at(16);
+        // Halving a 32-bit dimension reaches 1 within 32 levels, so no chain is longer.
+        let mip_count = if flags & DDSD_MIPMAPCOUNT != 0 {
+            u32_at(28).clamp(1, 32)
+        } else {
+            1
+        };
+
+        // The pixel format structure starts at offset 76.
+        let pixel_flags = u32_at(80);
+        let four_cc = &bytes[84..88];
+        let caps2 = u32_at(112);
+        let mut offset = 4 + HEADER_SIZE;
+
+        let format = if pixel_flags & DDPF_FOURCC != 0 && four_cc == b"DX10" {
+            if bytes.len() < offset + DX10_HEADER_SIZE {
+                return Err(invalid("truncated DX10 header"));
+            }
+
+            let dxgi_format = u32_at(offset);
+            offset += DX10_HEADER_SIZE;
+
+            Format::from_dxgi(dxgi_format)
+                .ok_or_else(|| invalid(&format!("unsupported DXGI format {}", dxgi_format)))?
+        } else if pixel_flags & DDPF_FOURCC != 0 {
+            Format::from_four_cc(four_cc)
+                .ok_or_else(|| invalid(&format!("unsupported FourCC {}", String::from_utf8_lossy(four_cc))))?
+        } else {
+            let bits_per_pixel = u32_at(88);
+
+            if bits_per_pixel == 0 || bits_per_pixel > 32 || bits_per_pixel % 8 != 0 {
+                return Err(invalid(&format!("unsupported {}-bit pixel format", bits_per_pixel)));
+            }
+
+            let has_alpha = pixel_flags & (DDPF_ALPHAPIXELS | DDPF_ALPHA) != 0;
+
+            Format::Uncompressed(PixelFormat {
+                bits_per_pixel,
+                red_mask: u32_at(92),
+                green_mask: u32_at(96),
+                blue_mask: u32_at(100),
+                alpha_mask: if has_alpha { u32_at(104) } else { 0 },
+                luminance: pixel_flags & DDPF_LUMINANCE != 0,
+            })
+        };
+
+        if width == 0 || height == 0 {
+            return Err(invalid("DDS has no pixels"));
+        }
+
+        let mut mips = vec![];
+
+        for level in 0..mip_count {
+            let (mip_width, mip_height) = mip_dimensions(width, height, level as usize);
+            let end = format
+                .surface_size(mip_width, mip_height)
+                .and_then(|size| offset.checked_add(size))
+                .ok_or_else(|| invalid("invalid DDS: its dimensions are too large"))?;
+
+            // Some tools write a mip count larger than the chain they store; keep what is there.
+            match bytes.get(offset..end) {
+                Some(data) => mips.push(data.to_vec()),
+                None if level > 0 => break,
+                None => return Err(invalid("truncated DDS pixel data")),
+            }
+
+            offset = end;
+        }
+
+        Ok(Self {
+            width,
+            height,
+            format,
+            is_cubemap: caps2 & DDSCAPS2_CUBEMAP != 0,
+            mips,
+        })
+    }
+
+    pub fn mip_count(&self) -> usize {
+        self.mips.len()
+    }
+
+    pub fn mip_dimensions(&self, level: usize) -> (u32, u32) {
+        mip_dimensions(self.width, self.height, level)
+    }
+
+    /// Decodes one mip level to tightly packed RGBA8 pixels.
+    pub fn decode(&self, level: usize) -> io::Result<Vec<u8>> {
+        let data = self
+            .mips
+            .get(level)
+            .ok_or_else(|| invalid(&format!("mip level {} does not exist", level)))?;
+        let (width, height) = self.mip_dimensions(level);
+        let (width, height) = (width as usize, height as usize);
+        let size = width
+            .checked_mul(height)
+            .and_then(|size| size.checked_mul(4))
+            .ok_or_else(|| invalid("invalid DDS: its dimensions are too large"))?;
+        let mut pixels = vec![0u8; size];
+
+        let decode_block: fn(&[u8], &mut bc::Block) = match self.format {
+            Format::Bc1 => bc::bc1,
+            Format::Bc2 => bc::bc2,
+            Format::Bc3 => bc::bc3,
+            Format::Bc4 => bc::bc4,
+            Format::Bc5 => bc::bc5,
+            Format::Bc7 => bc7::bc7,
+            Format::Uncompressed(pixel_format) => {
+                let bytes_per_pixel = pixel_format.bits_per_pixel as usize / 8;
+
+                for (source, pixel) in data.chunks_exact(bytes_per_pixel).zip(pixels.chunks_exact_mut(4)) {
+                    pixel_format.decode(source, pixel);
+                }
+
+                return Ok(pixels);
+            }
+        };
+
+        let block_size = self.format.block_size().unwrap_or(16);
+        let blocks_wide = width.div_ceil(4);
+        let mut block = [[0u8; 4]; 16];
+
+        for (index, source) in data.chunks_exact(block_size).enumerate() {
+            decode_block(source, &mut block);
+
+            let block_x = (index % blocks_wide) * 4;
+            let block_y = (index / blocks_wide) * 4;
+
+            for (i, texel) in block.iter().enumerate() {
+                let x = block_x + i % 4;
+                let y = block_y + i / 4;
+
+                if x < width && y < height {
+                    let start = (y * width + x) * 4;
+                    pixels[start..start + 4].copy_from_slice(texel);
+                }
+            }
+        }
+
+        Ok(pixels)
+    }
+}
+
+fn mip_dimensions(width: u32, height: u32, level: usize) -> (u32, u32) {
+    let halve = |size: u32| size.checked_shr(level.min(32) as u32).unwrap_or(0).max(1);
+    (halve(width), halve(height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(width: u32, height: u32, mip_count: u32, four_cc: &[u8; 4]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        let mut fields = [0u32; 31];
+        fields[0] = HEADER_SIZE as u32;
+        fields[1] = 0x1007 | DDSD_MIPMAPCOUNT;
+        fields[2] = height;
+        fields[3] = width;
+        fields[6] = mip_count;
+        fields[18] = 32;
+        fields[19] = DDPF_FOURCC;
+        fields[20] = u32::from_le_bytes(*four_cc);
+
+        for field in fields.iter() {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn decodes_bc1_mip_chain() {
+        let mut bytes = header(8, 4, 3, b"DXT1");
+
+        // Level 0: two blocks, pure red then pure blue (colour 0 with all indices zero).
+        bytes.extend_from_slice(&[0x00, 0xf8, 0, 0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&[0x1f, 0x00, 0, 0, 0, 0, 0, 0]);
+        // Levels 1 and 2: one block each, using colour 1 (white) everywhere.
+        bytes.extend_from_slice(&[0, 0, 0xff, 0xff, 0x55, 0x55, 0x55, 0x55]);
+        bytes.extend_from_slice(&[0, 0, 0xff, 0xff, 0x55, 0x55, 0x55, 0x55]);
+
+        let dds = Dds::parse(&bytes).unwrap();
+        assert_eq!(dds.format, Format::Bc1);
+        assert_eq!(dds.mip_count(), 3);
+        assert_eq!(dds.mip_dimensions(2), (2, 1));
+
+        let pixels = dds.decode(0).unwrap();
+        assert_eq!(&pixels[..4], &[255, 0, 0, 255]);
+        assert_eq!(&pixels[(8 * 3 + 7) * 4..], &[0, 0, 255, 255]);
+
+        // Colour 0 is less than colour 1, so this block uses the three-colour mode. Index 1 is still opaque.
+        assert_eq!(dds.decode(2).unwrap(), vec![255; 8]);
+    }
+
+    #[test]
+    fn caps_the_mip_count() {
+        let mut bytes = header(1, 1, u32::MAX, b"DXT1");
+        bytes.extend_from_slice(&[0; 8 * 40]);
+
+        let dds = Dds::parse(&bytes).unwrap();
+        assert_eq!(dds.mip_count(), 32);
+        assert_eq!(dds.mip_dimensions(40), (1, 1));
+    }
+
+    #[test]
+    fn rejects_dimensions_too_large_to_address() {
+        let bytes = header(u32::MAX, u32::MAX, 1, b"DXT5");
+        let error = Dds::parse(&bytes).err().unwrap();
+        assert!(error.to_string().starts_with("invalid DDS"));
+
+        // An uncompressed 32-bit format, with no FourCC.
+        let mut bytes = header(u32::MAX, u32::MAX - 1, 1, b"DXT5");
+        bytes[80..84].copy_from_slice(&0u32.to_le_bytes());
+        bytes[88..92].copy_from_slice(&32u32.to_le_bytes());
+        let error = Dds::parse(&bytes).err().unwrap();
+        assert!(error.to_string().starts_with("invalid DDS"));
+    }
+
+    #[test]
+    fn decodes_bc7_mode_6() {
+        // Mode 6: 7 mode bits, 7-bit RGBA endpoints, one p-bit per endpoint and 4-bit indices.
+        let mut fields: Vec<(u32, usize)> = vec![(1 << 6, 7)];
+        fields.extend_from_slice(&[(127, 7), (127, 7), (0, 7), (0, 7), (0, 7), (127, 7), (127, 7), (127, 7)]);
+        fields.extend_from_slice(&[(1, 1), (1, 1)]);
+
+        let mut block = [0u8; 16];
+        let mut position = 0;
+
+        for (value, count) in fields {
+            for i in 0..count {
+                block[position / 8] |= (((value >> i) & 1) as u8) << (position % 8);
+                position += 1;
+            }
+        }
+
+        let mut pixels = [[0u8; 4]; 16];
+        bc7::bc7(&block, &mut pixels);
+        assert_eq!(pixels, [[255, 1, 1, 255]; 16]);
+    }
+}
@@ -0,0 +1,93 @@
+use super::Record;
+
+/// DATA flags of a CELL record.
+pub mod cell_flags {
+    pub const INTERIOR: u16 = 0x0001;
+    pub const HAS_WATER: u16 = 0x0002;
+}
+
+/// The parts of a CELL record needed to list and find it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cell {
+    pub form_id: u32,
+    pub editor_id: Option<String>,
+    /// The worldspace an exterior cell belongs to, from the group it is stored in.
+    pub world: Option<u32>,
+    /// Exterior grid coordinates `(x, y)` from XCLC. A worldspace's persistent cell has none.
+    pub grid: Option<(i32, i32)>,
+    pub flags: u16,
+}
+
+impl Cell {
+    pub fn from_record(record: &Record, world: Option<u32>) -> Self {
+        let subrecords = record.subrecords().unwrap_or_default();
+        let find = |code: &[u8; 4]| subrecords.iter().find(|subrecord| &subrecord.code == code);
+
+        let grid = find(b"XCLC").and_then(|subrecord| {
+            let data = subrecord.data.get(..8)?;
+            let x = i32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+            let y = i32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+            Some((x, y))
+        });
+
+        // DATA is a single byte in some older plugins.
+        let flags = find(b"DATA")
+            .map(|subrecord| match subrecord.data[..] {
+                [low, high, ..] => u16::from_le_bytes([low, high]),
+                [low] => low as u16,
+                [] => 0,
+            })
+            .unwrap_or(0);
+
+        Self {
+            form_id: record.form_id,
+            editor_id: find(b"EDID").map(|subrecord| subrecord.as_string()),
+            world,
+            grid,
+            flags,
+        }
+    }
+
+    pub fn is_interior(&self) -> bool {
+        self.flags & cell_flags::INTERIOR != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esp::Subrecord;
+
+    #[test]
+    fn from_record() {
+        let mut xclc = (-3i32).to_le_bytes().to_vec();
+        xclc.extend_from_slice(&12i32.to_le_bytes());
+        xclc.extend_from_slice(&0u32.to_le_bytes());
+
+        let exterior = Record::new(
+            *b"CELL",
+            0x0000_9000,
+            &[
+                Subrecord::new(*b"DATA", vec![0x02, 0x00]),
+                Subrecord::new(*b"XCLC", xclc),
+            ],
+        );
+        let cell = Cell::from_record(&exterior, Some(0x0000_003C));
+        assert_eq!(cell.grid, Some((-3, 12)));
+        assert_eq!(cell.world, Some(0x0000_003C));
+        assert!(!cell.is_interior());
+
+        let interior = Record::new(
+            *b"CELL",
+            0x0000_9001,
+            &[
+                Subrecord::string(*b"EDID", "TestCell"),
+                Subrecord::new(*b"DATA", vec![0x01]),
+            ],
+        );
+        let cell = Cell::from_record(&interior, None);
+        assert_eq!(cell.editor_id.as_deref(), Some("TestCell"));
+        assert_eq!(cell.grid, None);
+        assert!(cell.is_interior());
+    }
+}
@@ -0,0 +1,268 @@
+use super::{
+    condition_function,
+    fields::{put_u16, put_u32, Fields},
+    Subrecord,
+};
+
+use std::io;
+
+/// Flags in the low bits of a condition's first byte. The comparison is in the top three bits.
+pub mod condition_flags {
+    /// The condition is ORed with the next one rather than ANDed.
+    pub const OR: u8 = 0x01;
+    pub const USE_ALIASES: u8 = 0x02;
+    /// The value is a GLOB form ID rather than a number.
+    pub const USE_GLOBAL: u8 = 0x04;
+    pub const USE_PACKAGE_DATA: u8 = 0x08;
+    pub const SWAP_SUBJECT_AND_TARGET: u8 = 0x10;
+}
+
+/// What a condition is run on, indexed by [`Condition::run_on`].
+pub const RUN_ON_NAMES: [&str; 8] = [
+    "Subject",
+    "Target",
+    "Reference",
+    "Combat Target",
+    "Linked Reference",
+    "Quest Alias",
+    "Package Data",
+    "Event Data",
+];
+
+/// Where [`Condition::run_on`] picks [`Condition::reference`] as the reference to run on.
+pub const RUN_ON_REFERENCE: u32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Comparison {
+    pub const ALL: [Comparison; 6] = [
+        Comparison::Equal,
+        Comparison::NotEqual,
+        Comparison::Greater,
+        Comparison::GreaterOrEqual,
+        Comparison::Less,
+        Comparison::LessOrEqual,
+    ];
+
+    fn from_bits(bits: u8) -> Self {
+        Self::ALL.get(bits as usize).copied().unwrap_or(Comparison::Equal)
+    }
+
+    fn bits(self) -> u8 {
+        Self::ALL.iter().position(|&comparison| comparison == self).unwrap_or(0) as u8
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+        }
+    }
+}
+
+/// What a condition's function result is compared with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConditionValue {
+    Number(f32),
+    /// The value of a GLOB record.
+    Global(u32),
+}
+
+/// A condition from a CTDA subrecord, with the strings from the CIS1 and CIS2 subrecords that may follow it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    pub comparison: Comparison,
+    /// [`condition_flags`], apart from `USE_GLOBAL`, which follows from [`Self::value`].
+    pub flags: u8,
+    pub value: ConditionValue,
+    /// The index of the condition function.
+    pub function: u16,
+    /// The function's parameters, as form IDs or numbers depending on the function.
+    pub params: [u32; 2],
+    pub run_on: u32,
+    /// The reference run on when [`Self::run_on`] is [`RUN_ON_REFERENCE`].
+    pub reference: u32,
+    pub param3: i32,
+    pub strings: [Option<String>; 2],
+}
+
+impl Default for Condition {
+    fn default() -> Self {
+        Self {
+            comparison: Comparison::Equal,
+            flags: 0,
+            value: ConditionValue::Number(1.0),
+            function: 0,
+            params: [0; 2],
+            run_on: 0,
+            reference: 0,
+            param3: -1,
+            strings: [None, None],
+        }
+    }
+}
+
+impl Condition {
+    pub fn parse(ctda: &Subrecord) -> io::Result<Self> {
+        let mut fields = Fields::new(&ctda.data, "CTDA");
+
+        let kind = fields.u8()?;
+        fields.bytes(3)?;
+        let value = fields.u32()?;
+        let function = fields.u16()?;
+        fields.bytes(2)?;
+        let params = [fields.u32()?, fields.u32()?];
+        let run_on = fields.u32()?;
+        let reference = fields.u32()?;
+        // Older conditions end before the third parameter.
+        let param3 = if fields.is_empty() { -1 } else { fields.i32()? };
+
+        let flags = kind & 0x1f;
+
+        Ok(Self {
+            comparison: Comparison::from_bits(kind >> 5),
+            flags: flags & !condition_flags::USE_GLOBAL,
+            value: match flags & condition_flags::USE_GLOBAL {
+                0 => ConditionValue::Number(f32::from_bits(value)),
+                _ => ConditionValue::Global(value),
+            },
+            function,
+            params,
+            run_on,
+            reference,
+            param3,
+            strings: [None, None],
+        })
+    }
+
+    /// Reads a list of conditions from CTDA subrecords and the CIS1 and CIS2 subrecords following each of them.
+    /// Other subrecords are skipped.
+    pub fn read_all<'a>(subrecords: impl IntoIterator<Item = &'a Subrecord>) -> io::Result<Vec<Self>> {
+        let mut conditions: Vec<Self> = vec![];
+
+        for subrecord in subrecords {
+            match &subrecord.code {
+                b"CTDA" => conditions.push(Self::parse(subrecord)?),
+                b"CIS1" | b"CIS2" => {
+                    if let Some(condition) = conditions.last_mut() {
+                        condition.add_string(subrecord);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(conditions)
+    }
+
+    /// Takes the string from a CIS1 or CIS2 subrecord following this condition's CTDA.
+    pub fn add_string(&mut self, subrecord: &Subrecord) {
+        let index = match &subrecord.code {
+            b"CIS1" => 0,
+            _ => 1,
+        };
+
+        self.strings[index] = Some(subrecord.as_string());
+    }
+
+    pub fn is_or(&self) -> bool {
+        self.flags & condition_flags::OR != 0
+    }
+
+    /// The CTDA subrecord and any CIS1 and CIS2 subrecords after it.
+    pub fn subrecords(&self) -> Vec<Subrecord> {
+        let (global, value) = match self.value {
+            ConditionValue::Number(value) => (0, value.to_bits()),
+            ConditionValue::Global(form_id) => (condition_flags::USE_GLOBAL, form_id),
+        };
+
+        let mut data = vec![self.comparison.bits() << 5 | (self.flags & 0x1f) | global, 0, 0, 0];
+        put_u32(&mut data, value);
+        put_u16(&mut data, self.function);
+        put_u16(&mut data, 0);
+        put_u32(&mut data, self.params[0]);
+        put_u32(&mut data, self.params[1]);
+        put_u32(&mut data, self.run_on);
+        put_u32(&mut data, self.reference);
+        data.extend_from_slice(&self.param3.to_le_bytes());
+
+        let mut subrecords = vec![Subrecord::new(*b"CTDA", data)];
+
+        for (code, string) in [*b"CIS1", *b"CIS2"].iter().zip(&self.strings) {
+            if let Some(string) = string {
+                subrecords.push(Subrecord::string(*code, string));
+            }
+        }
+
+        subrecords
+    }
+
+    /// The name of the condition's function, or its index if it is not in the function table.
+    pub fn function_name(&self) -> String {
+        match condition_function(self.function) {
+            Some(function) => function.name.to_string(),
+            None => format!("Function {}", self.function),
+        }
+    }
+
+    /// A one-line description, with form IDs in hex.
+    pub fn summary(&self) -> String {
+        let value = match self.value {
+            ConditionValue::Number(value) => format!("{}", value),
+            ConditionValue::Global(form_id) => format!("global {:08X}", form_id),
+        };
+
+        format!(
+            "{}: {} ({:08X}, {:08X}) {} {}{}",
+            RUN_ON_NAMES.get(self.run_on as usize).unwrap_or(&"?"),
+            self.function_name(),
+            self.params[0],
+            self.params[1],
+            self.comparison.symbol(),
+            value,
+            if self.is_or() { "  OR" } else { "" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let condition = Condition {
+            comparison: Comparison::GreaterOrEqual,
+            flags: condition_flags::OR,
+            value: ConditionValue::Global(0x0000_0039),
+            function: 58,
+            params: [0x0001_2345, 0],
+            run_on: RUN_ON_REFERENCE,
+            reference: 0x0000_0014,
+            param3: -1,
+            strings: [None, Some("Variable01".to_string())],
+        };
+
+        let subrecords = condition.subrecords();
+        assert_eq!(subrecords[0].data.len(), 32);
+        assert_eq!(
+            subrecords[0].data[0],
+            3 << 5 | condition_flags::OR | condition_flags::USE_GLOBAL
+        );
+        assert_eq!(&subrecords[1].code, b"CIS2");
+
+        assert_eq!(Condition::read_all(&subrecords).unwrap(), vec![condition]);
+    }
+}
@@ -0,0 +1,646 @@
+//! The functions a condition can call, by the index stored in its CTDA subrecord, and the types of their
+//! parameters.
+//!
+//! The table follows the function list of Skyrim's editor, but may still miss functions. Indexes without an entry
+//! are shown by number and have their parameters edited as plain numbers.
+
+use super::{code_name, Code, PLACEABLE_CODES, REFERENCE_CODES};
+use ParamType::{Alias, Float, Integer, None as NONE, PackageData, VariableName};
+
+/// The form ID of the player's reference, which is not stored in any plugin.
+pub const PLAYER_REFERENCE: u32 = 0x0000_0014;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamType {
+    None,
+    Integer,
+    Float,
+    /// A number naming one of a list of values, which may not cover every valid number.
+    Enum(&'static str, &'static [&'static str]),
+    /// A form ID of a record with one of the given codes, or of any record if there are none.
+    Form(&'static str, &'static [Code]),
+    /// The ID of an alias of the quest the condition belongs to.
+    Alias,
+    /// A Papyrus variable, named by the condition's CIS1 or CIS2 string for the first or second parameter.
+    VariableName,
+    /// An index into the data of the package the condition belongs to.
+    PackageData,
+}
+
+impl ParamType {
+    pub fn name(self) -> &'static str {
+        match self {
+            ParamType::None => "None",
+            ParamType::Integer => "Integer",
+            ParamType::Float => "Float",
+            ParamType::Enum(name, _) | ParamType::Form(name, _) => name,
+            ParamType::Alias => "Alias",
+            ParamType::VariableName => "Variable Name",
+            ParamType::PackageData => "Package Data",
+        }
+    }
+
+    /// Checks a form ID parameter against the code of the record it refers to, or `None` if there is no such
+    /// record. Other kinds of parameter are not checked.
+    pub fn check(self, value: u32, code: Option<Code>) -> Result<(), String> {
+        let (name, codes) = match self {
+            ParamType::Form(name, codes) => (name, codes),
+            _ => return Ok(()),
+        };
+
+        if value == 0 {
+            return Err(format!("needs a {}", name));
+        }
+
+        if value == PLAYER_REFERENCE && codes.contains(b"ACHR") {
+            return Ok(());
+        }
+
+        match code {
+            None => Err(format!("{:08X} is not a loaded record", value)),
+            Some(code) if !codes.is_empty() && !codes.contains(&code) => {
+                Err(format!("{:08X} is a {} record, not a {}", value, code_name(code), name))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+pub struct ConditionFunction {
+    pub index: u16,
+    pub name: &'static str,
+    pub params: [ParamType; 2],
+}
+
+const fn function(index: u16, name: &'static str, param1: ParamType, param2: ParamType) -> ConditionFunction {
+    ConditionFunction {
+        index,
+        name,
+        params: [param1, param2],
+    }
+}
+
+/// The condition function with an index, if it is in the table.
+pub fn condition_function(index: u16) -> Option<&'static ConditionFunction> {
+    CONDITION_FUNCTIONS
+        .binary_search_by_key(&index, |function| function.index)
+        .ok()
+        .map(|found| &CONDITION_FUNCTIONS[found])
+}
+
+const AXIS: ParamType = ParamType::Enum("Axis", &["X", "Y", "Z"]);
+const SEX: ParamType = ParamType::Enum("Sex", &["Male", "Female"]);
+const CRIME_TYPE: ParamType = ParamType::Enum(
+    "Crime Type",
+    &[
+        "Steal",
+        "Pickpocket",
+        "Trespass",
+        "Attack",
+        "Murder",
+        "Escape Jail",
+        "Werewolf Transformation",
+    ],
+);
+const CASTING_SOURCE: ParamType = ParamType::Enum("Casting Source", &["Left", "Right", "Voice", "Instant"]);
+const WARD_STATE: ParamType = ParamType::Enum("Ward State", &["None", "Absorb", "Break"]);
+const CRITICAL_STAGE: ParamType = ParamType::Enum(
+    "Critical Stage",
+    &["None", "Goo Start", "Goo End", "Disintegrate Start", "Disintegrate End"],
+);
+const ALIGNMENT: ParamType = ParamType::Enum("Alignment", &["Good", "Neutral", "Evil", "Very Good", "Very Evil"]);
+/// Only the actor values before the resistances are named.
+const ACTOR_VALUE: ParamType = ParamType::Enum(
+    "Actor Value",
+    &[
+        "Aggression",
+        "Confidence",
+        "Energy",
+        "Morality",
+        "Mood",
+        "Assistance",
+        "OneHanded",
+        "TwoHanded",
+        "Marksman",
+        "Block",
+        "Smithing",
+        "HeavyArmor",
+        "LightArmor",
+        "Pickpocket",
+        "Lockpicking",
+        "Sneak",
+        "Alchemy",
+        "Speechcraft",
+        "Alteration",
+        "Conjuration",
+        "Destruction",
+        "Illusion",
+        "Restoration",
+        "Enchanting",
+        "Health",
+        "Magicka",
+        "Stamina",
+        "HealRate",
+        "MagickaRate",
+        "StaminaRate",
+        "SpeedMult",
+        "InventoryWeight",
+        "CarryWeight",
+        "CritChance",
+        "MeleeDamage",
+        "UnarmedDamage",
+        "Mass",
+        "VoicePoints",
+        "VoiceRate",
+        "DamageResist",
+        "PoisonResist",
+        "FireResist",
+        "ElectricResist",
+        "FrostResist",
+        "MagicResist",
+        "DiseaseResist",
+    ],
+);
+
+// Named numbers whose values the table does not name.
+const FORM_TYPE: ParamType = Integer;
+const MISC_STAT: ParamType = Integer;
+const FURNITURE_ANIM: ParamType = Integer;
+const FURNITURE_ENTRY: ParamType = Integer;
+const EQUIP_TYPE: ParamType = Integer;
+const CREATURE_TYPE: ParamType = Integer;
+const SKILL_ACTION: ParamType = Integer;
+const GRAPH_VARIABLE: ParamType = VariableName;
+
+const ANY_FORM: ParamType = ParamType::Form("Form", &[]);
+const OBJECT_REFERENCE: ParamType = ParamType::Form("Object Reference", &REFERENCE_CODES);
+const ACTOR: ParamType = ParamType::Form("Actor", &[*b"ACHR"]);
+const ACTOR_BASE: ParamType = ParamType::Form("Actor Base", &[*b"NPC_"]);
+const REFERENCEABLE_OBJECT: ParamType = ParamType::Form("Referenceable Object", &PLACEABLE_CODES);
+const INVENTORY_OBJECT: ParamType = ParamType::Form(
+    "Inventory Object",
+    &[
+        *b"ALCH", *b"AMMO", *b"ARMO", *b"BOOK", *b"FLST", *b"INGR", *b"KEYM", *b"LIGH", *b"LVLI", *b"MISC", *b"SCRL",
+        *b"SLGM", *b"WEAP",
+    ],
+);
+const MAGIC_ITEM: ParamType = ParamType::Form("Magic Item", &[*b"SPEL", *b"ENCH", *b"ALCH", *b"INGR", *b"SCRL"]);
+const OWNER: ParamType = ParamType::Form("Owner", &[*b"NPC_", *b"FACT"]);
+const ASSOCIATION_TYPE: ParamType = ParamType::Form("Association Type", &[*b"ASTP"]);
+const CELL: ParamType = ParamType::Form("Cell", &[*b"CELL"]);
+const CLASS: ParamType = ParamType::Form("Class", &[*b"CLAS"]);
+const ENCOUNTER_ZONE: ParamType = ParamType::Form("Encounter Zone", &[*b"ECZN"]);
+const FACTION: ParamType = ParamType::Form("Faction", &[*b"FACT"]);
+const FORM_LIST: ParamType = ParamType::Form("Form List", &[*b"FLST"]);
+const FURNITURE: ParamType = ParamType::Form("Furniture", &[*b"FURN"]);
+const GLOBAL: ParamType = ParamType::Form("Global", &[*b"GLOB"]);
+const IDLE: ParamType = ParamType::Form("Idle", &[*b"IDLE"]);
+const IMAGE_SPACE_MODIFIER: ParamType = ParamType::Form("Image Space Modifier", &[*b"IMAD"]);
+const KEYWORD: ParamType = ParamType::Form("Keyword", &[*b"KYWD"]);
+const LOCATION: ParamType = ParamType::Form("Location", &[*b"LCTN"]);
+const LOCATION_REF_TYPE: ParamType = ParamType::Form("Location Ref Type", &[*b"LCRT"]);
+const MAGIC_EFFECT: ParamType = ParamType::Form("Magic Effect", &[*b"MGEF"]);
+const PACKAGE: ParamType = ParamType::Form("Package", &[*b"PACK"]);
+const PERK: ParamType = ParamType::Form("Perk", &[*b"PERK"]);
+const QUEST: ParamType = ParamType::Form("Quest", &[*b"QUST"]);
+const RACE: ParamType = ParamType::Form("Race", &[*b"RACE"]);
+const REGION: ParamType = ParamType::Form("Region", &[*b"REGN"]);
+const SCENE: ParamType = ParamType::Form("Scene", &[*b"SCEN"]);
+const SHOUT: ParamType = ParamType::Form("Shout", &[*b"SHOU"]);
+const SPELL: ParamType = ParamType::Form("Spell", &[*b"SPEL"]);
+const VOICE_TYPE: ParamType = ParamType::Form("Voice Type", &[*b"VTYP"]);
+const WEATHER: ParamType = ParamType::Form("Weather", &[*b"WTHR"]);
+const WORLDSPACE: ParamType = ParamType::Form("Worldspace", &[*b"WRLD"]);
+
+/// Every known condition function, sorted by index.
+pub static CONDITION_FUNCTIONS: &[ConditionFunction] = &[
+    function(0, "GetWantBlocking", NONE, NONE),
+    function(1, "GetDistance", OBJECT_REFERENCE, NONE),
+    function(5, "GetLocked", NONE, NONE),
+    function(6, "GetPos", AXIS, NONE),
+    function(8, "GetAngle", AXIS, NONE),
+    function(10, "GetStartingPos", AXIS, NONE),
+    function(11, "GetStartingAngle", AXIS, NONE),
+    function(12, "GetSecondsPassed", NONE, NONE),
+    function(14, "GetActorValue", ACTOR_VALUE, NONE),
+    function(18, "GetCurrentTime", NONE, NONE),
+    function(24, "GetScale", NONE, NONE),
+    function(25, "IsMoving", NONE, NONE),
+    function(26, "IsTurning", NONE, NONE),
+    function(27, "GetLineOfSight", OBJECT_REFERENCE, NONE),
+    function(32, "GetInSameCell", OBJECT_REFERENCE, NONE),
+    function(35, "GetDisabled", NONE, NONE),
+    function(36, "MenuMode", Integer, NONE),
+    function(39, "GetDisease", NONE, NONE),
+    function(41, "GetClothingValue", NONE, NONE),
+    function(42, "SameFaction", ACTOR, NONE),
+    function(43, "SameRace", ACTOR, NONE),
+    function(44, "SameSex", ACTOR, NONE),
+    function(45, "GetDetected", ACTOR, NONE),
+    function(46, "GetDead", NONE, NONE),
+    function(47, "GetItemCount", INVENTORY_OBJECT, NONE),
+    function(48, "GetGold", NONE, NONE),
+    function(49, "GetSleeping", NONE, NONE),
+    function(50, "GetTalkedToPC", NONE, NONE),
+    function(53, "GetScriptVariable", OBJECT_REFERENCE, VariableName),
+    function(56, "GetQuestRunning", QUEST, NONE),
+    function(58, "GetStage", QUEST, NONE),
+    function(59, "GetStageDone", QUEST, Integer),
+    function(60, "GetFactionRankDifference", FACTION, ACTOR),
+    function(61, "GetAlarmed", NONE, NONE),
+    function(62, "IsRaining", NONE, NONE),
+    function(63, "GetAttacked", NONE, NONE),
+    function(64, "GetIsCreature", NONE, NONE),
+    function(65, "GetLockLevel", NONE, NONE),
+    function(66, "GetShouldAttack", ACTOR, NONE),
+    function(67, "GetInCell", CELL, NONE),
+    function(68, "GetIsClass", CLASS, NONE),
+    function(69, "GetIsRace", RACE, NONE),
+    function(70, "GetIsSex", SEX, NONE),
+    function(71, "GetInFaction", FACTION, NONE),
+    function(72, "GetIsID", REFERENCEABLE_OBJECT, NONE),
+    function(73, "GetFactionRank", FACTION, NONE),
+    function(74, "GetGlobalValue", GLOBAL, NONE),
+    function(75, "IsSnowing", NONE, NONE),
+    function(77, "GetRandomPercent", NONE, NONE),
+    function(79, "GetQuestVariable", QUEST, VariableName),
+    function(80, "GetLevel", NONE, NONE),
+    function(81, "IsRotating", NONE, NONE),
+    function(84, "GetDeadCount", ACTOR_BASE, NONE),
+    function(91, "GetIsAlerted", NONE, NONE),
+    function(98, "GetPlayerControlsDisabled", Integer, Integer),
+    function(99, "GetHeadingAngle", OBJECT_REFERENCE, NONE),
+    function(101, "IsWeaponMagicOut", NONE, NONE),
+    function(102, "IsTorchOut", NONE, NONE),
+    function(103, "IsShieldOut", NONE, NONE),
+    function(106, "IsFacingUp", NONE, NONE),
+    function(107, "GetKnockedState", NONE, NONE),
+    function(108, "GetWeaponAnimType", NONE, NONE),
+    function(109, "IsWeaponSkillType", ACTOR_VALUE, NONE),
+    function(110, "GetCurrentAIPackage", NONE, NONE),
+    function(111, "IsWaiting", NONE, NONE),
+    function(112, "IsIdlePlaying", NONE, NONE),
+    function(116, "IsIntimidatedbyPlayer", NONE, NONE),
+    function(117, "IsPlayerInRegion", REGION, NONE),
+    function(118, "GetActorAggroRadiusViolated", NONE, NONE),
+    function(122, "GetCrime", ACTOR, CRIME_TYPE),
+    function(123, "IsGreetingPlayer", NONE, NONE),
+    function(125, "IsGuard", NONE, NONE),
+    function(127, "HasBeenEaten", NONE, NONE),
+    function(128, "GetStaminaPercentage", NONE, NONE),
+    function(129, "GetPCIsClass", CLASS, NONE),
+    function(130, "GetPCIsRace", RACE, NONE),
+    function(131, "GetPCIsSex", SEX, NONE),
+    function(132, "GetPCInFaction", FACTION, NONE),
+    function(133, "SameFactionAsPC", NONE, NONE),
+    function(134, "SameRaceAsPC", NONE, NONE),
+    function(135, "SameSexAsPC", NONE, NONE),
+    function(136, "GetIsReference", OBJECT_REFERENCE, NONE),
+    function(141, "IsTalking", NONE, NONE),
+    function(142, "GetWalkSpeed", NONE, NONE),
+    function(143, "GetCurrentAIProcedure", NONE, NONE),
+    function(144, "GetTrespassWarningLevel", NONE, NONE),
+    function(145, "IsTrespassing", NONE, NONE),
+    function(146, "IsInMyOwnedCell", NONE, NONE),
+    function(147, "GetWindSpeed", NONE, NONE),
+    function(148, "GetCurrentWeatherPercent", NONE, NONE),
+    function(149, "GetIsCurrentWeather", WEATHER, NONE),
+    function(150, "IsContinuingPackagePCNear", NONE, NONE),
+    function(152, "GetIsCrimeFaction", FACTION, NONE),
+    function(153, "CanHaveFlames", NONE, NONE),
+    function(154, "HasFlames", NONE, NONE),
+    function(157, "GetOpenState", NONE, NONE),
+    function(159, "GetSitting", NONE, NONE),
+    function(161, "GetIsCurrentPackage", PACKAGE, NONE),
+    function(162, "IsCurrentFurnitureRef", OBJECT_REFERENCE, NONE),
+    function(163, "IsCurrentFurnitureObj", FURNITURE, NONE),
+    function(170, "GetDayOfWeek", NONE, NONE),
+    function(172, "GetTalkedToPCParam", ACTOR, NONE),
+    function(175, "IsPCSleeping", NONE, NONE),
+    function(176, "IsPCAMurderer", NONE, NONE),
+    function(180, "HasSameEditorLocAsRef", OBJECT_REFERENCE, KEYWORD),
+    function(181, "HasSameEditorLocAsRefAlias", Alias, KEYWORD),
+    function(182, "GetEquipped", INVENTORY_OBJECT, NONE),
+    function(185, "IsSwimming", NONE, NONE),
+    function(190, "GetAmountSoldStolen", NONE, NONE),
+    function(192, "GetIgnoreCrime", NONE, NONE),
+    function(193, "GetPCExpelled", FACTION, NONE),
+    function(195, "GetPCFactionMurder", FACTION, NONE),
+    function(197, "GetPCEnemyofFaction", FACTION, NONE),
+    function(199, "GetPCFactionAttack", FACTION, NONE),
+    function(203, "GetDestroyed", NONE, NONE),
+    function(214, "HasMagicEffect", MAGIC_EFFECT, NONE),
+    function(215, "GetDefaultOpen", NONE, NONE),
+    function(219, "GetAnimAction", NONE, NONE),
+    function(223, "IsSpellTarget", MAGIC_ITEM, NONE),
+    function(224, "GetVATSMode", NONE, NONE),
+    function(225, "GetPersuasionNumber", NONE, NONE),
+    function(226, "GetVampireFeed", NONE, NONE),
+    function(227, "GetCannibal", NONE, NONE),
+    function(228, "GetIsClassDefault", CLASS, NONE),
+    function(229, "GetClassDefaultMatch", NONE, NONE),
+    function(230, "GetInCellParam", CELL, OBJECT_REFERENCE),
+    function(235, "GetVatsTargetHeight", NONE, NONE),
+    function(237, "GetIsGhost", NONE, NONE),
+    function(242, "GetUnconscious", NONE, NONE),
+    function(244, "GetRestrained", NONE, NONE),
+    function(246, "GetIsUsedItem", REFERENCEABLE_OBJECT, NONE),
+    function(247, "GetIsUsedItemType", FORM_TYPE, NONE),
+    function(248, "IsScenePlaying", SCENE, NONE),
+    function(249, "IsInDialogueWithPlayer", NONE, NONE),
+    function(250, "GetLocationCleared", LOCATION, NONE),
+    function(254, "GetIsPlayableRace", NONE, NONE),
+    function(255, "GetOffersServicesNow", NONE, NONE),
+    function(258, "HasAssociationType", ACTOR, ASSOCIATION_TYPE),
+    function(259, "HasFamilyRelationship", ACTOR, NONE),
+    function(261, "HasParentRelationship", ACTOR, NONE),
+    function(262, "IsWarningAbout", FORM_LIST, NONE),
+    function(263, "IsWeaponOut", NONE, NONE),
+    function(264, "HasSpell", SPELL, NONE),
+    function(265, "IsTimePassing", NONE, NONE),
+    function(266, "IsPleasant", NONE, NONE),
+    function(267, "IsCloudy", NONE, NONE),
+    function(274, "IsSmallBump", NONE, NONE),
+    function(277, "GetBaseActorValue", ACTOR_VALUE, NONE),
+    function(278, "IsOwner", OWNER, NONE),
+    function(280, "IsCellOwner", CELL, OWNER),
+    function(282, "IsHorseStolen", NONE, NONE),
+    function(285, "IsLeftUp", NONE, NONE),
+    function(286, "IsSneaking", NONE, NONE),
+    function(287, "IsRunning", NONE, NONE),
+    function(288, "GetFriendHit", NONE, NONE),
+    function(289, "IsInCombat", Integer, NONE),
+    function(300, "IsInInterior", NONE, NONE),
+    function(304, "IsWaterObject", NONE, NONE),
+    function(305, "GetPlayerAction", NONE, NONE),
+    function(306, "IsActorUsingATorch", NONE, NONE),
+    function(309, "IsXBox", NONE, NONE),
+    function(310, "GetInWorldspace", WORLDSPACE, NONE),
+    function(312, "GetPCMiscStat", MISC_STAT, NONE),
+    function(313, "GetPairedAnimation", NONE, NONE),
+    function(314, "IsActorAVictim", NONE, NONE),
+    function(315, "GetTotalPersuasionNumber", NONE, NONE),
+    function(318, "GetIdleDoneOnce", NONE, NONE),
+    function(320, "GetNoRumors", NONE, NONE),
+    function(323, "GetCombatState", NONE, NONE),
+    function(325, "GetWithinPackageLocation", PackageData, NONE),
+    function(327, "IsRidingMount", NONE, NONE),
+    function(329, "IsFleeing", NONE, NONE),
+    function(332, "IsInDangerousWater", NONE, NONE),
+    function(338, "GetIgnoreFriendlyHits", NONE, NONE),
+    function(339, "IsPlayersLastRiddenMount", NONE, NONE),
+    function(353, "IsActor", NONE, NONE),
+    function(354, "IsEssential", NONE, NONE),
+    function(358, "IsPlayerMovingIntoNewSpace", NONE, NONE),
+    function(359, "GetInCurrentLoc", LOCATION, NONE),
+    function(360, "GetInCurrentLocAlias", Alias, NONE),
+    function(361, "GetTimeDead", NONE, NONE),
+    function(362, "HasLinkedRef", KEYWORD, NONE),
+    function(365, "IsChild", NONE, NONE),
+    function(366, "GetStolenItemValueNoCrime", FACTION, NONE),
+    function(367, "GetLastPlayerAction", NONE, NONE),
+    function(368, "IsPlayerActionActive", Integer, NONE),
+    function(370, "IsTalkingActivatorActor", ACTOR, NONE),
+    function(372, "IsInList", FORM_LIST, NONE),
+    function(373, "GetStolenItemValue", FACTION, NONE),
+    function(375, "GetCrimeGoldViolent", FACTION, NONE),
+    function(376, "GetCrimeGoldNonviolent", FACTION, NONE),
+    function(378, "HasShout", SHOUT, NONE),
+    function(381, "GetHasNote", ANY_FORM, NONE),
+    function(390, "GetHitLocation", NONE, NONE),
+    function(391, "IsPC1stPerson", NONE, NONE),
+    function(396, "GetCauseofDeath", NONE, NONE),
+    function(397, "IsLimbGone", Integer, NONE),
+    function(398, "IsWeaponInList", FORM_LIST, NONE),
+    function(402, "IsBribedbyPlayer", NONE, NONE),
+    function(403, "GetRelationshipRank", ACTOR, NONE),
+    function(407, "GetVATSValue", Integer, Integer),
+    function(408, "IsKiller", ACTOR, NONE),
+    function(409, "IsKillerObject", FORM_LIST, NONE),
+    function(410, "GetFactionCombatReaction", FACTION, FACTION),
+    function(414, "Exists", OBJECT_REFERENCE, NONE),
+    function(415, "GetGroupMemberCount", NONE, NONE),
+    function(416, "GetGroupTargetCount", NONE, NONE),
+    function(426, "GetIsVoiceType", VOICE_TYPE, NONE),
+    function(427, "GetPlantedExplosive", NONE, NONE),
+    function(429, "IsScenePackageRunning", NONE, NONE),
+    function(430, "GetHealthPercentage", NONE, NONE),
+    function(432, "GetIsObjectType", FORM_TYPE, NONE),
+    function(434, "GetDialogueEmotion", NONE, NONE),
+    function(435, "GetDialogueEmotionValue", NONE, NONE),
+    function(437, "GetIsCreatureType", CREATURE_TYPE, NONE),
+    function(444, "GetInCurrentLocFormList", FORM_LIST, NONE),
+    function(445, "GetInZone", ENCOUNTER_ZONE, NONE),
+    function(446, "GetVelocity", AXIS, NONE),
+    function(447, "GetGraphVariableFloat", GRAPH_VARIABLE, NONE),
+    function(448, "HasPerk", PERK, NONE),
+    function(449, "GetFactionRelation", ACTOR, NONE),
+    function(450, "IsLastIdlePlayed", IDLE, NONE),
+    function(453, "GetPlayerTeammate", NONE, NONE),
+    function(454, "GetPlayerTeammateCount", NONE, NONE),
+    function(458, "GetActorCrimePlayerEnemy", NONE, NONE),
+    function(459, "GetCrimeGold", FACTION, NONE),
+    function(462, "IsPlayerGrabbedRef", OBJECT_REFERENCE, NONE),
+    function(463, "GetKeywordItemCount", KEYWORD, NONE),
+    function(467, "GetBroadcastState", NONE, NONE),
+    function(470, "GetDestructionStage", NONE, NONE),
+    function(473, "GetIsAlignment", ALIGNMENT, NONE),
+    function(476, "IsProtected", NONE, NONE),
+    function(477, "GetThreatRatio", ACTOR, NONE),
+    function(479, "GetIsUsedItemEquipType", EQUIP_TYPE, NONE),
+    function(480, "GetPlayerActivated", NONE, NONE),
+    function(483, "GetFullyEnabledActorsInHigh", NONE, NONE),
+    function(487, "IsCarryable", NONE, NONE),
+    function(488, "GetConcussed", NONE, NONE),
+    function(491, "GetMapMarkerVisible", NONE, NONE),
+    function(493, "PlayerKnows", ANY_FORM, NONE),
+    function(494, "GetPermanentActorValue", ACTOR_VALUE, NONE),
+    function(495, "GetKillingBlowLimb", NONE, NONE),
+    function(497, "CanPayCrimeGold", FACTION, NONE),
+    function(499, "GetDaysInJail", NONE, NONE),
+    function(500, "EPAlchemyGetMakingPoison", NONE, NONE),
+    function(501, "EPAlchemyEffectHasKeyword", KEYWORD, NONE),
+    function(503, "GetAllowWorldInteractions", NONE, NONE),
+    function(508, "GetLastHitCritical", NONE, NONE),
+    function(513, "IsCombatTarget", ACTOR, NONE),
+    function(515, "GetVATSRightAreaFree", OBJECT_REFERENCE, NONE),
+    function(516, "GetVATSLeftAreaFree", OBJECT_REFERENCE, NONE),
+    function(517, "GetVATSBackAreaFree", OBJECT_REFERENCE, NONE),
+    function(518, "GetVATSFrontAreaFree", OBJECT_REFERENCE, NONE),
+    function(519, "GetIsLockBroken", NONE, NONE),
+    function(520, "IsPS3", NONE, NONE),
+    function(521, "IsWin32", NONE, NONE),
+    function(522, "GetVATSRightTargetVisible", OBJECT_REFERENCE, NONE),
+    function(523, "GetVATSLeftTargetVisible", OBJECT_REFERENCE, NONE),
+    function(524, "GetVATSBackTargetVisible", OBJECT_REFERENCE, NONE),
+    function(525, "GetVATSFrontTargetVisible", OBJECT_REFERENCE, NONE),
+    function(528, "IsInCriticalStage", CRITICAL_STAGE, NONE),
+    function(530, "GetXPForNextLevel", NONE, NONE),
+    function(533, "GetInfamy", FACTION, NONE),
+    function(534, "GetInfamyViolent", FACTION, NONE),
+    function(535, "GetInfamyNonViolent", FACTION, NONE),
+    function(543, "GetQuestCompleted", QUEST, NONE),
+    function(547, "IsGoreDisabled", NONE, NONE),
+    function(550, "IsSceneActionComplete", SCENE, Integer),
+    function(552, "GetSpellUsageNum", SPELL, NONE),
+    function(554, "GetActorsInHigh", NONE, NONE),
+    function(555, "HasLoaded3D", NONE, NONE),
+    function(559, "IsImageSpaceActive", IMAGE_SPACE_MODIFIER, NONE),
+    function(560, "HasKeyword", KEYWORD, NONE),
+    function(561, "HasRefType", LOCATION_REF_TYPE, NONE),
+    function(562, "LocationHasKeyword", KEYWORD, NONE),
+    function(563, "LocationHasRefType", LOCATION_REF_TYPE, NONE),
+    function(565, "GetIsEditorLocation", LOCATION, NONE),
+    function(566, "GetIsAliasRef", Alias, NONE),
+    function(567, "GetIsEditorLocAlias", Alias, NONE),
+    function(568, "IsSprinting", NONE, NONE),
+    function(569, "IsBlocking", NONE, NONE),
+    function(570, "HasEquippedSpell", CASTING_SOURCE, NONE),
+    function(571, "GetCurrentCastingType", CASTING_SOURCE, NONE),
+    function(572, "GetCurrentDeliveryType", CASTING_SOURCE, NONE),
+    function(574, "GetAttackState", NONE, NONE),
+    function(576, "GetEventData", Integer, Integer),
+    function(577, "IsCloserToAThanB", OBJECT_REFERENCE, OBJECT_REFERENCE),
+    function(579, "GetEquippedShout", SHOUT, NONE),
+    function(580, "IsBleedingOut", NONE, NONE),
+    function(584, "GetRelativeAngle", OBJECT_REFERENCE, AXIS),
+    function(589, "GetMovementDirection", NONE, NONE),
+    function(590, "IsInScene", NONE, NONE),
+    function(591, "GetRefTypeDeadCount", LOCATION, LOCATION_REF_TYPE),
+    function(592, "GetRefTypeAliveCount", LOCATION, LOCATION_REF_TYPE),
+    function(594, "GetIsFlying", NONE, NONE),
+    function(595, "IsCurrentSpell", SPELL, CASTING_SOURCE),
+    function(596, "SpellHasKeyword", CASTING_SOURCE, KEYWORD),
+    function(597, "GetEquippedItemType", CASTING_SOURCE, NONE),
+    function(598, "GetLocationAliasCleared", Alias, NONE),
+    function(600, "GetLocAliasRefTypeDeadCount", Alias, LOCATION_REF_TYPE),
+    function(601, "GetLocAliasRefTypeAliveCount", Alias, LOCATION_REF_TYPE),
+    function(602, "IsWardState", WARD_STATE, NONE),
+    function(603, "IsInSameCurrentLocAsRef", OBJECT_REFERENCE, KEYWORD),
+    function(604, "IsInSameCurrentLocAsRefAlias", Alias, KEYWORD),
+    function(605, "LocAliasIsLocation", Alias, LOCATION),
+    function(606, "GetKeywordDataForLocation", LOCATION, KEYWORD),
+    function(608, "GetKeywordDataForAlias", Alias, KEYWORD),
+    function(610, "LocAliasHasKeyword", Alias, KEYWORD),
+    function(611, "IsNullPackageData", PackageData, NONE),
+    function(612, "GetNumericPackageData", PackageData, NONE),
+    function(613, "IsFurnitureAnimType", FURNITURE_ANIM, NONE),
+    function(614, "IsFurnitureEntryType", FURNITURE_ENTRY, NONE),
+    function(615, "GetHighestRelationshipRank", NONE, NONE),
+    function(616, "GetLowestRelationshipRank", NONE, NONE),
+    function(617, "HasAssociationTypeAny", ASSOCIATION_TYPE, NONE),
+    function(618, "HasFamilyRelationshipAny", NONE, NONE),
+    function(619, "GetPathingTargetOffset", AXIS, NONE),
+    function(620, "GetPathingTargetAngleOffset", AXIS, NONE),
+    function(621, "GetPathingTargetSpeed", NONE, NONE),
+    function(622, "GetPathingTargetSpeedAngle", AXIS, NONE),
+    function(623, "GetMovementSpeed", NONE, NONE),
+    function(624, "GetInContainer", OBJECT_REFERENCE, NONE),
+    function(625, "IsLocationLoaded", LOCATION, NONE),
+    function(626, "IsLocAliasLoaded", Alias, NONE),
+    function(627, "IsDualCasting", NONE, NONE),
+    function(629, "GetVMQuestVariable", QUEST, VariableName),
+    function(630, "GetVMScriptVariable", OBJECT_REFERENCE, VariableName),
+    function(631, "IsEnteringInteractionQuick", NONE, NONE),
+    function(632, "IsCasting", NONE, NONE),
+    function(633, "GetFlyingState", NONE, NONE),
+    function(635, "IsInFavorState", NONE, NONE),
+    function(636, "HasTwoHandedWeaponEquipped", NONE, NONE),
+    function(637, "IsExitingInstant", NONE, NONE),
+    function(638, "IsInFriendStatewithPlayer", NONE, NONE),
+    function(639, "GetWithinDistance", OBJECT_REFERENCE, Float),
+    function(640, "GetActorValuePercent", ACTOR_VALUE, NONE),
+    function(641, "IsUnique", NONE, NONE),
+    function(642, "GetLastBumpDirection", NONE, NONE),
+    function(644, "IsInFurnitureState", FURNITURE_ANIM, NONE),
+    function(645, "GetIsInjured", NONE, NONE),
+    function(646, "GetIsCrashLandRequest", NONE, NONE),
+    function(647, "GetIsHastyLandRequest", NONE, NONE),
+    function(650, "IsLinkedTo", OBJECT_REFERENCE, KEYWORD),
+    function(651, "GetKeywordDataForCurrentLocation", KEYWORD, NONE),
+    function(652, "GetInSharedCrimeFaction", OBJECT_REFERENCE, NONE),
+    function(654, "GetBribeSuccess", NONE, NONE),
+    function(655, "GetIntimidateSuccess", NONE, NONE),
+    function(656, "GetArrestedState", NONE, NONE),
+    function(657, "GetArrestingActor", NONE, NONE),
+    function(659, "EPTemperingItemIsEnchanted", NONE, NONE),
+    function(660, "EPTemperingItemHasKeyword", KEYWORD, NONE),
+    function(664, "GetReplacedItemType", CASTING_SOURCE, NONE),
+    function(672, "IsAttacking", NONE, NONE),
+    function(673, "IsPowerAttacking", NONE, NONE),
+    function(674, "IsLastHostileActor", NONE, NONE),
+    function(675, "GetGraphVariableInt", GRAPH_VARIABLE, NONE),
+    function(676, "GetCurrentShoutVariation", NONE, NONE),
+    function(678, "ShouldAttackKill", ACTOR, NONE),
+    function(680, "GetActivatorHeight", NONE, NONE),
+    function(681, "EPMagic_IsAdvanceSkill", ACTOR_VALUE, NONE),
+    function(682, "WornHasKeyword", KEYWORD, NONE),
+    function(683, "GetPathingCurrentSpeed", NONE, NONE),
+    function(684, "GetPathingCurrentSpeedAngle", AXIS, NONE),
+    function(691, "EPModSkillUsage_AdvanceObjectHasKeyword", KEYWORD, NONE),
+    function(692, "EPModSkillUsage_IsAdvanceAction", SKILL_ACTION, NONE),
+    function(693, "EPMagic_SpellHasKeyword", KEYWORD, NONE),
+    function(694, "GetNoBleedoutRecovery", NONE, NONE),
+    function(696, "EPMagic_SpellHasSkill", ACTOR_VALUE, NONE),
+    function(697, "IsAttackType", KEYWORD, NONE),
+    function(698, "IsAllowedToFly", NONE, NONE),
+    function(699, "HasMagicEffectKeyword", KEYWORD, NONE),
+    function(700, "IsCommandedActor", NONE, NONE),
+    function(701, "IsStaggered", NONE, NONE),
+    function(702, "IsRecoiling", NONE, NONE),
+    function(703, "IsExitingInteractionQuick", NONE, NONE),
+    function(704, "IsPathing", NONE, NONE),
+    function(705, "GetShouldHelp", ACTOR, NONE),
+    function(706, "HasBoundWeaponEquipped", CASTING_SOURCE, NONE),
+    function(707, "GetCombatTargetHasKeyword", KEYWORD, NONE),
+    function(709, "GetCombatGroupMemberCount", NONE, NONE),
+    function(710, "IsIgnoringCombat", NONE, NONE),
+    function(711, "GetLightLevel", NONE, NONE),
+    function(713, "SpellHasCastingPerk", PERK, NONE),
+    function(714, "IsBeingRidden", NONE, NONE),
+    function(715, "IsUndead", NONE, NONE),
+    function(716, "GetRealHoursPassed", NONE, NONE),
+    function(718, "IsUnlockedDoor", NONE, NONE),
+    function(719, "IsHostileToActor", ACTOR, NONE),
+    function(720, "GetTargetHeight", OBJECT_REFERENCE, NONE),
+    function(721, "IsPoison", NONE, NONE),
+    function(722, "WornApparelHasKeywordCount", KEYWORD, NONE),
+    function(723, "GetItemHealthPercent", NONE, NONE),
+    function(724, "EffectWasDualCast", NONE, NONE),
+    function(725, "GetKnockStateEnum", NONE, NONE),
+    function(726, "DoesNotExist", NONE, NONE),
+    function(730, "IsOnFlyingMount", NONE, NONE),
+    function(731, "CanFlyHere", NONE, NONE),
+    function(732, "IsFlyingMountPatrolQueud", NONE, NONE),
+    function(733, "IsFlyingMountFastTravelling", NONE, NONE),
+    function(734, "IsOverEncumbered", NONE, NONE),
+    function(735, "GetActorWarmth", NONE, NONE),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_is_sorted() {
+        let sorted = CONDITION_FUNCTIONS.windows(2).all(|pair| pair[0].index < pair[1].index);
+        assert!(sorted);
+
+        assert_eq!(condition_function(72).map(|function| function.name), Some("GetIsID"));
+        assert!(condition_function(2).is_none());
+    }
+
+    #[test]
+    fn checks_form_parameters() {
+        let quest = condition_function(58).unwrap().params[0];
+        assert!(quest.check(0x0001_2345, Some(*b"QUST")).is_ok());
+        assert!(quest.check(0x0001_2345, Some(*b"NPC_")).is_err());
+        assert!(quest.check(0x0001_2345, None).is_err());
+        assert!(quest.check(0, None).is_err());
+
+        let actor = condition_function(42).unwrap().params[0];
+        assert!(actor.check(PLAYER_REFERENCE, None).is_ok());
+
+        assert!(Integer.check(5, None).is_ok());
+    }
+}
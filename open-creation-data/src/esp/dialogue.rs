@@ -0,0 +1,689 @@
+//! Dialogue from DLBR, DIAL and INFO records: branches, the topics in them and the responses to each topic.
+//!
+//! INFO records are not stored in their topic's record but in the topic's children group, and are ordered by
+//! the previous INFO each one names in its PNAM subrecord rather than by their place in the group.
+
+use super::{invalid, Code, Condition, LoadOrder, Record, Subrecord, Text, Vmad};
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+};
+
+pub mod branch_flags {
+    pub const TOP_LEVEL: u32 = 0x01;
+    pub const BLOCKING: u32 = 0x02;
+    pub const EXCLUSIVE: u32 = 0x04;
+}
+
+pub mod info_flags {
+    pub const GOODBYE: u16 = 0x0001;
+    pub const RANDOM: u16 = 0x0002;
+    pub const SAY_ONCE: u16 = 0x0004;
+    pub const REQUIRES_PLAYER_ACTIVATION: u16 = 0x0008;
+    pub const INFO_REFUSAL: u16 = 0x0010;
+    pub const RANDOM_END: u16 = 0x0020;
+    pub const INVISIBLE_CONTINUE: u16 = 0x0040;
+    pub const WALK_AWAY: u16 = 0x0080;
+    pub const WALK_AWAY_INVISIBLE_IN_MENU: u16 = 0x0100;
+    pub const FORCE_SUBTITLE: u16 = 0x0200;
+    pub const CAN_MOVE_WHILE_GREETING: u16 = 0x0400;
+    pub const NO_LIP_FILE: u16 = 0x0800;
+    pub const REQUIRES_POST_PROCESSING: u16 = 0x1000;
+    pub const AUDIO_OUTPUT_OVERRIDE: u16 = 0x2000;
+    pub const SPENDS_FAVOR_POINTS: u16 = 0x4000;
+}
+
+pub mod response_flags {
+    pub const USE_EMOTION_ANIMATION: u8 = 0x01;
+}
+
+/// Emotions, indexed by [`Response::emotion`].
+pub const EMOTION_NAMES: [&str; 8] = [
+    "Neutral", "Anger", "Disgust", "Fear", "Sad", "Happy", "Surprise", "Puzzled",
+];
+
+/// Topic categories, indexed by [`Topic::category`].
+pub const TOPIC_CATEGORY_NAMES: [&str; 8] = [
+    "Topic",
+    "Favor",
+    "Scene",
+    "Combat",
+    "Favors",
+    "Detection",
+    "Service",
+    "Miscellaneous",
+];
+
+/// Branch categories, indexed by [`Branch::category`].
+pub const BRANCH_CATEGORY_NAMES: [&str; 2] = ["Player", "Command"];
+
+/// A dialogue branch, which groups the topics the player can reach from its starting topic.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Branch {
+    pub form_id: u32,
+    pub editor_id: String,
+    pub quest: u32,
+    /// An index into [`BRANCH_CATEGORY_NAMES`].
+    pub category: u32,
+    /// [`branch_flags`]
+    pub flags: u32,
+    pub starting_topic: u32,
+    pub other: Vec<Subrecord>,
+}
+
+impl Branch {
+    pub fn from_record(record: &Record) -> io::Result<Self> {
+        if &record.code != b"DLBR" {
+            return Err(invalid("not a DLBR record"));
+        }
+
+        let mut branch = Self {
+            form_id: record.form_id,
+            ..Self::default()
+        };
+
+        for subrecord in record.subrecords()? {
+            match &subrecord.code {
+                b"EDID" => branch.editor_id = subrecord.as_string(),
+                b"QNAM" => branch.quest = subrecord.as_u32().unwrap_or(0),
+                b"TNAM" => branch.category = subrecord.as_u32().unwrap_or(0),
+                b"DNAM" => branch.flags = subrecord.as_u32().unwrap_or(0),
+                b"SNAM" => branch.starting_topic = subrecord.as_u32().unwrap_or(0),
+                _ => branch.other.push(subrecord),
+            }
+        }
+
+        Ok(branch)
+    }
+
+    pub fn subrecords(&self) -> Vec<Subrecord> {
+        let mut subrecords = vec![
+            Subrecord::string(*b"EDID", &self.editor_id),
+            u32_subrecord(b"QNAM", self.quest),
+            u32_subrecord(b"TNAM", self.category),
+            u32_subrecord(b"DNAM", self.flags),
+        ];
+
+        if self.starting_topic != 0 {
+            subrecords.push(u32_subrecord(b"SNAM", self.starting_topic));
+        }
+
+        subrecords.extend(self.other.iter().cloned());
+        subrecords
+    }
+
+    pub fn write(&self, record: &mut Record) {
+        record.set_subrecords(&self.subrecords());
+    }
+}
+
+/// A dialogue topic and the INFO records answering it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Topic {
+    pub form_id: u32,
+    pub editor_id: String,
+    /// The text the player picks the topic by.
+    pub name: Option<Text>,
+    pub priority: f32,
+    pub branch: u32,
+    pub quest: u32,
+    pub flags: u8,
+    /// An index into [`TOPIC_CATEGORY_NAMES`].
+    pub category: u8,
+    pub subtype: u16,
+    /// The subtype as a four-letter code, e.g. `CUST` for custom topics.
+    pub subtype_code: Code,
+    /// The number of INFO records in the topic, which is stored in the topic's record.
+    pub info_count: u32,
+    pub other: Vec<Subrecord>,
+    /// The topic's INFO records in the order they are used. They are read and written separately from the
+    /// topic's own record.
+    pub infos: Vec<Info>,
+}
+
+impl Topic {
+    /// A new custom player topic in a quest and branch, without any INFO records.
+    pub fn new(editor_id: &str, quest: u32, branch: u32) -> Self {
+        Self {
+            form_id: 0,
+            editor_id: editor_id.to_string(),
+            name: None,
+            priority: 50.0,
+            branch,
+            quest,
+            flags: 0,
+            category: 0,
+            subtype: 0,
+            subtype_code: *b"CUST",
+            info_count: 0,
+            other: vec![],
+            infos: vec![],
+        }
+    }
+
+    /// Reads a DIAL record, without its INFO records. `localized` is whether the plugin it comes from stores
+    /// its text in string tables.
+    pub fn from_record(record: &Record, localized: bool) -> io::Result<Self> {
+        if &record.code != b"DIAL" {
+            return Err(invalid("not a DIAL record"));
+        }
+
+        let mut topic = Self::new("", 0, 0);
+        topic.form_id = record.form_id;
+        topic.subtype_code = [0; 4];
+
+        for subrecord in record.subrecords()? {
+            match &subrecord.code {
+                b"EDID" => topic.editor_id = subrecord.as_string(),
+                b"FULL" => topic.name = Some(Text::read(&subrecord, localized)),
+                b"PNAM" => topic.priority = subrecord.as_f32().unwrap_or(50.0),
+                b"BNAM" => topic.branch = subrecord.as_u32().unwrap_or(0),
+                b"QNAM" => topic.quest = subrecord.as_u32().unwrap_or(0),
+                b"DATA" => {
+                    let data = &subrecord.data;
+                    if data.len() < 4 {
+                        return Err(invalid("DIAL DATA is truncated"));
+                    }
+                    topic.flags = data[0];
+                    topic.category = data[1];
+                    topic.subtype = u16::from_le_bytes([data[2], data[3]]);
+                }
+                b"SNAM" if subrecord.data.len() == 4 => topic.subtype_code.copy_from_slice(&subrecord.data),
+                b"TIFC" => topic.info_count = subrecord.as_u32().unwrap_or(0),
+                _ => topic.other.push(subrecord),
+            }
+        }
+
+        Ok(topic)
+    }
+
+    /// The topic's name, for resolving string table IDs. Its INFOs resolve their own.
+    pub fn texts_mut(&mut self) -> impl Iterator<Item = &mut Text> {
+        self.name.iter_mut()
+    }
+
+    pub fn subrecords(&self) -> Vec<Subrecord> {
+        let mut subrecords = vec![Subrecord::string(*b"EDID", &self.editor_id)];
+
+        subrecords.extend(self.name.as_ref().map(|name| name.subrecord(*b"FULL")));
+        subrecords.push(Subrecord::new(*b"PNAM", self.priority.to_le_bytes().to_vec()));
+        subrecords.push(u32_subrecord(b"BNAM", self.branch));
+        subrecords.push(u32_subrecord(b"QNAM", self.quest));
+
+        let mut data = vec![self.flags, self.category];
+        data.extend_from_slice(&self.subtype.to_le_bytes());
+        subrecords.push(Subrecord::new(*b"DATA", data));
+        subrecords.push(Subrecord::new(*b"SNAM", self.subtype_code.to_vec()));
+        subrecords.push(u32_subrecord(b"TIFC", self.info_count));
+        subrecords.extend(self.other.iter().cloned());
+        subrecords
+    }
+
+    /// Writes the topic's own record. Its INFO records are written separately.
+    pub fn write(&self, record: &mut Record) {
+        record.set_subrecords(&self.subrecords());
+    }
+
+    /// Points each INFO's [`Info::previous`] at the one before it, after they have been reordered. The first
+    /// keeps a previous INFO from outside the topic. Returns the form IDs of the INFOs that were changed.
+    pub fn link_infos(&mut self) -> Vec<u32> {
+        let form_ids: HashSet<u32> = self.infos.iter().map(|info| info.form_id).collect();
+        let mut changed = vec![];
+        let mut previous = None;
+
+        for info in &mut self.infos {
+            let link = match previous {
+                Some(form_id) => form_id,
+                None if form_ids.contains(&info.previous) => 0,
+                None => info.previous,
+            };
+
+            if info.previous != link {
+                info.previous = link;
+                changed.push(info.form_id);
+            }
+
+            previous = Some(info.form_id);
+        }
+
+        changed
+    }
+}
+
+/// One line of an INFO, spoken with its own emotion and voice file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Response {
+    /// An index into [`EMOTION_NAMES`].
+    pub emotion: u32,
+    /// How strongly the emotion is shown, from 0 to 100.
+    pub emotion_value: u32,
+    pub unknown: u32,
+    /// The response's number within its INFO, which names its voice file.
+    pub number: u8,
+    pub sound: u32,
+    /// [`response_flags`]
+    pub flags: u8,
+    pub text: Option<Text>,
+    /// Notes for the voice actor.
+    pub notes: String,
+    pub edits: String,
+    pub speaker_idle: Option<u32>,
+    pub listener_idle: Option<u32>,
+}
+
+impl Response {
+    fn parse_trdt(trdt: &Subrecord) -> io::Result<Self> {
+        let data = &trdt.data;
+        if data.len() < 21 {
+            return Err(invalid("INFO TRDT is truncated"));
+        }
+
+        let u32_at = |at: usize| u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]);
+
+        Ok(Self {
+            emotion: u32_at(0),
+            emotion_value: u32_at(4),
+            unknown: u32_at(8),
+            number: data[12],
+            sound: u32_at(16),
+            flags: data[20],
+            ..Self::default()
+        })
+    }
+
+    fn subrecords(&self) -> Vec<Subrecord> {
+        let mut trdt = vec![];
+        trdt.extend_from_slice(&self.emotion.to_le_bytes());
+        trdt.extend_from_slice(&self.emotion_value.to_le_bytes());
+        trdt.extend_from_slice(&self.unknown.to_le_bytes());
+        trdt.extend_from_slice(&[self.number, 0, 0, 0]);
+        trdt.extend_from_slice(&self.sound.to_le_bytes());
+        trdt.extend_from_slice(&[self.flags, 0, 0, 0]);
+
+        let mut subrecords = vec![
+            Subrecord::new(*b"TRDT", trdt),
+            self.text.clone().unwrap_or_default().subrecord(*b"NAM1"),
+            Subrecord::string(*b"NAM2", &self.notes),
+            Subrecord::string(*b"NAM3", &self.edits),
+        ];
+
+        subrecords.extend(self.speaker_idle.map(|idle| u32_subrecord(b"SNAM", idle)));
+        subrecords.extend(self.listener_idle.map(|idle| u32_subrecord(b"LNAM", idle)));
+        subrecords
+    }
+
+    pub fn emotion_name(&self) -> &'static str {
+        EMOTION_NAMES.get(self.emotion as usize).copied().unwrap_or("?")
+    }
+}
+
+/// An INFO record: what is said in answer to a topic, and when.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Info {
+    pub form_id: u32,
+    pub editor_id: String,
+    pub scripts: Option<Vmad>,
+    /// [`info_flags`]
+    pub flags: u16,
+    /// Hours until a Say Once INFO can be said again, or 0 for never.
+    pub reset_hours: u16,
+    /// The INFO before this one in its topic, or 0 for the first.
+    pub previous: u32,
+    pub favor_level: Option<u8>,
+    /// The topics the player can choose from after this INFO.
+    pub links: Vec<u32>,
+    /// Subrecords before the responses that are not decoded.
+    pub other: Vec<Subrecord>,
+    pub responses: Vec<Response>,
+    pub conditions: Vec<Condition>,
+    /// The text the player says to get this INFO, in place of the topic's name.
+    pub prompt: Option<Text>,
+    pub speaker: Option<u32>,
+    /// Subrecords after the conditions that are not decoded.
+    pub other_after: Vec<Subrecord>,
+}
+
+/// The part of the INFO the subrecords being read belong to.
+enum Section {
+    Head,
+    Responses,
+    Tail,
+}
+
+impl Info {
+    /// Reads an INFO record. `localized` is whether the plugin it comes from stores its text in string tables.
+    pub fn from_record(record: &Record, localized: bool) -> io::Result<Self> {
+        if &record.code != b"INFO" {
+            return Err(invalid("not an INFO record"));
+        }
+
+        let mut info = Self {
+            form_id: record.form_id,
+            ..Self::default()
+        };
+
+        let mut section = Section::Head;
+
+        for subrecord in record.subrecords()? {
+            let code = &subrecord.code;
+
+            match code {
+                b"TRDT" => {
+                    info.responses.push(Response::parse_trdt(&subrecord)?);
+                    section = Section::Responses;
+                    continue;
+                }
+                b"CTDA" => {
+                    info.conditions.push(Condition::parse(&subrecord)?);
+                    section = Section::Tail;
+                    continue;
+                }
+                b"CIS1" | b"CIS2" => {
+                    if let Some(condition) = info.conditions.last_mut() {
+                        condition.add_string(&subrecord);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            match section {
+                Section::Head => match code {
+                    b"EDID" => info.editor_id = subrecord.as_string(),
+                    b"VMAD" => info.scripts = Some(Vmad::parse(&subrecord, *b"INFO")?),
+                    b"ENAM" if subrecord.data.len() >= 4 => {
+                        let data = &subrecord.data;
+                        info.flags = u16::from_le_bytes([data[0], data[1]]);
+                        info.reset_hours = u16::from_le_bytes([data[2], data[3]]);
+                    }
+                    b"PNAM" => info.previous = subrecord.as_u32().unwrap_or(0),
+                    b"CNAM" => info.favor_level = subrecord.data.first().copied(),
+                    b"TCLT" => info.links.extend(subrecord.as_u32()),
+                    b"RNAM" | b"ANAM" => {
+                        section = Section::Tail;
+                        info.read_tail(subrecord, localized);
+                    }
+                    _ => info.other.push(subrecord),
+                },
+                Section::Responses => {
+                    let response = match info.responses.last_mut() {
+                        Some(response) => response,
+                        None => continue,
+                    };
+
+                    match code {
+                        b"NAM1" => response.text = Some(Text::read(&subrecord, localized)),
+                        b"NAM2" => response.notes = subrecord.as_string(),
+                        b"NAM3" => response.edits = subrecord.as_string(),
+                        b"SNAM" => response.speaker_idle = subrecord.as_u32(),
+                        b"LNAM" => response.listener_idle = subrecord.as_u32(),
+                        _ => {
+                            section = Section::Tail;
+                            info.read_tail(subrecord, localized);
+                        }
+                    }
+                }
+                Section::Tail => info.read_tail(subrecord, localized),
+            }
+        }
+
+        Ok(info)
+    }
+
+    fn read_tail(&mut self, subrecord: Subrecord, localized: bool) {
+        match &subrecord.code {
+            b"RNAM" => self.prompt = Some(Text::read(&subrecord, localized)),
+            b"ANAM" => self.speaker = subrecord.as_u32(),
+            _ => self.other_after.push(subrecord),
+        }
+    }
+
+    /// Numbers the responses from 1 in their current order, after they have been added, removed or moved.
+    pub fn number_responses(&mut self) {
+        for (index, response) in self.responses.iter_mut().enumerate() {
+            response.number = index as u8 + 1;
+        }
+    }
+
+    /// The text of the first response, for listing the INFO.
+    pub fn summary(&self) -> String {
+        match self.responses.first().and_then(|response| response.text.as_ref()) {
+            Some(text) => text.to_string(),
+            None => "(no text)".to_string(),
+        }
+    }
+
+    /// The INFO's prompt and response texts, for resolving string table IDs.
+    pub fn texts_mut(&mut self) -> impl Iterator<Item = &mut Text> {
+        self.prompt
+            .iter_mut()
+            .chain(self.responses.iter_mut().filter_map(|response| response.text.as_mut()))
+    }
+
+    pub fn subrecords(&self) -> Vec<Subrecord> {
+        let mut subrecords = vec![];
+
+        if !self.editor_id.is_empty() {
+            subrecords.push(Subrecord::string(*b"EDID", &self.editor_id));
+        }
+
+        subrecords.extend(self.scripts.as_ref().map(Vmad::subrecord));
+
+        let mut enam = self.flags.to_le_bytes().to_vec();
+        enam.extend_from_slice(&self.reset_hours.to_le_bytes());
+        subrecords.push(Subrecord::new(*b"ENAM", enam));
+        subrecords.push(u32_subrecord(b"PNAM", self.previous));
+        subrecords.extend(self.favor_level.map(|level| Subrecord::new(*b"CNAM", vec![level])));
+        subrecords.extend(self.links.iter().map(|&topic| u32_subrecord(b"TCLT", topic)));
+        subrecords.extend(self.other.iter().cloned());
+        subrecords.extend(self.responses.iter().flat_map(Response::subrecords));
+        subrecords.extend(self.conditions.iter().flat_map(Condition::subrecords));
+        subrecords.extend(self.prompt.as_ref().map(|prompt| prompt.subrecord(*b"RNAM")));
+        subrecords.extend(self.speaker.map(|speaker| u32_subrecord(b"ANAM", speaker)));
+        subrecords.extend(self.other_after.iter().cloned());
+        subrecords
+    }
+
+    pub fn write(&self, record: &mut Record) {
+        record.set_subrecords(&self.subrecords());
+    }
+}
+
+/// Orders a topic's INFOs by the chain of previous INFOs they name. INFOs whose previous INFO is not in the topic
+/// start a chain, and any left over in a loop are put at the end.
+pub fn order_infos(mut infos: Vec<Info>) -> Vec<Info> {
+    infos.sort_by_key(|info| info.form_id);
+
+    let form_ids: HashSet<u32> = infos.iter().map(|info| info.form_id).collect();
+    let mut next: HashMap<u32, Vec<usize>> = HashMap::new();
+    let mut starts = vec![];
+
+    for (index, info) in infos.iter().enumerate() {
+        if form_ids.contains(&info.previous) && info.previous != info.form_id {
+            next.entry(info.previous).or_default().push(index);
+        } else {
+            starts.push(index);
+        }
+    }
+
+    let mut order = vec![];
+    let mut placed = vec![false; infos.len()];
+    let mut stack: Vec<usize> = starts.into_iter().rev().collect();
+
+    while let Some(index) = stack.pop() {
+        if placed[index] {
+            continue;
+        }
+        placed[index] = true;
+        order.push(index);
+
+        if let Some(following) = next.get(&infos[index].form_id) {
+            stack.extend(following.iter().rev());
+        }
+    }
+
+    order.extend((0..infos.len()).filter(|&index| !placed[index]));
+
+    let mut infos: Vec<Option<Info>> = infos.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|index| infos[index].take()).collect()
+}
+
+/// A quest's dialogue: the branches and topics belonging to it, and their INFOs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dialogue {
+    pub quest: u32,
+    pub branches: Vec<Branch>,
+    pub topics: Vec<Topic>,
+}
+
+impl Dialogue {
+    /// Reads the winning versions of a quest's branches and topics, sorted by editor ID, and their INFOs.
+    pub fn load(load_order: &LoadOrder, quest: u32) -> io::Result<Self> {
+        let mut branches = vec![];
+
+        for record in load_order.records_by_code(*b"DLBR") {
+            if record.is_deleted() {
+                continue;
+            }
+
+            let branch = Branch::from_record(record)?;
+            if branch.quest == quest {
+                branches.push(branch);
+            }
+        }
+
+        let mut topics = vec![];
+
+        for record in load_order.records_by_code(*b"DIAL") {
+            if record.is_deleted() {
+                continue;
+            }
+
+            let mut topic = Topic::from_record(record, load_order.is_localized(record.form_id))?;
+            load_order.resolve_texts(topic.form_id, topic.texts_mut());
+
+            if topic.quest == quest {
+                topics.push(topic);
+            }
+        }
+
+        let form_ids: HashSet<u32> = topics.iter().map(|topic| topic.form_id).collect();
+        let mut records = load_order.topic_infos(&form_ids);
+
+        for topic in &mut topics {
+            let mut infos = vec![];
+
+            for record in records.remove(&topic.form_id).unwrap_or_default() {
+                let mut info = Info::from_record(record, load_order.is_localized(record.form_id))?;
+                load_order.resolve_texts(info.form_id, info.texts_mut());
+                infos.push(info);
+            }
+
+            topic.infos = order_infos(infos);
+        }
+
+        branches.sort_by(|a, b| a.editor_id.cmp(&b.editor_id));
+        topics.sort_by(|a, b| a.editor_id.cmp(&b.editor_id));
+
+        Ok(Self {
+            quest,
+            branches,
+            topics,
+        })
+    }
+
+    pub fn topic(&self, form_id: u32) -> Option<&Topic> {
+        self.topics.iter().find(|topic| topic.form_id == form_id)
+    }
+
+    /// Every link from a topic to a topic the player can choose after one of its INFOs, without duplicates.
+    pub fn links(&self) -> Vec<(u32, u32)> {
+        let mut links = vec![];
+
+        for topic in &self.topics {
+            for &link in topic.infos.iter().flat_map(|info| &info.links) {
+                if !links.contains(&(topic.form_id, link)) {
+                    links.push((topic.form_id, link));
+                }
+            }
+        }
+
+        links
+    }
+}
+
+fn u32_subrecord(code: &Code, value: u32) -> Subrecord {
+    Subrecord::new(*code, value.to_le_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esp::{Comparison, ConditionValue};
+
+    fn info(form_id: u32, previous: u32) -> Info {
+        Info {
+            form_id,
+            previous,
+            ..Info::default()
+        }
+    }
+
+    #[test]
+    fn info_round_trip() {
+        let info = Info {
+            form_id: 0x0100_0801,
+            flags: info_flags::GOODBYE | info_flags::SAY_ONCE,
+            reset_hours: 24,
+            previous: 0x0100_0800,
+            links: vec![0x0100_0900, 0x0100_0901],
+            other: vec![Subrecord::new(*b"DNAM", vec![0; 4])],
+            responses: vec![
+                Response {
+                    emotion: 5,
+                    emotion_value: 50,
+                    number: 1,
+                    text: Some(Text::Inline("Well met.".to_string())),
+                    notes: "Cheerful".to_string(),
+                    ..Response::default()
+                },
+                Response {
+                    number: 2,
+                    text: Some(Text::Inline("Need something?".to_string())),
+                    speaker_idle: Some(0x0001_0203),
+                    ..Response::default()
+                },
+            ],
+            conditions: vec![Condition {
+                comparison: Comparison::Equal,
+                value: ConditionValue::Number(1.0),
+                function: 72,
+                ..Condition::default()
+            }],
+            prompt: Some(Text::Inline("Hello.".to_string())),
+            speaker: Some(0x0001_3BBD),
+            other_after: vec![Subrecord::new(*b"TWAT", vec![0; 4])],
+            ..Info::default()
+        };
+
+        let record = Record::new(*b"INFO", info.form_id, &info.subrecords());
+        assert_eq!(Info::from_record(&record, false).unwrap(), info);
+    }
+
+    #[test]
+    fn orders_and_relinks_infos() {
+        let infos = order_infos(vec![info(3, 2), info(1, 0), info(2, 1), info(4, 0x0001_0000)]);
+        let order: Vec<u32> = infos.iter().map(|info| info.form_id).collect();
+        assert_eq!(order, [1, 2, 3, 4]);
+
+        let mut topic = Topic::new("Test", 0, 0);
+        topic.infos = infos;
+        topic.infos.swap(0, 3);
+
+        // 4 now comes first and keeps its link out of the topic; the others follow it.
+        assert_eq!(topic.link_infos(), [2, 1]);
+        let previous: Vec<u32> = topic.infos.iter().map(|info| info.previous).collect();
+        assert_eq!(previous, [0x0001_0000, 4, 2, 3]);
+    }
+}
@@ -0,0 +1,247 @@
+//! Factions from FACT records and how they react to each other, with their members from the SNAM entries of NPCs.
+//!
+//! Each XNAM of a faction is its reaction to another faction. Reactions are one-sided: a faction may be friends
+//! with another that is its enemy. The relations follow the name, before the faction's other data.
+
+use super::{invalid, LoadOrder, Record, Subrecord};
+
+use std::{collections::HashMap, io};
+
+/// How a faction's members treat another faction's in combat.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Reaction {
+    Neutral,
+    Enemy,
+    Ally,
+    Friend,
+}
+
+impl Reaction {
+    pub const ALL: [Reaction; 4] = [Reaction::Neutral, Reaction::Enemy, Reaction::Ally, Reaction::Friend];
+
+    fn from_value(value: u32) -> Self {
+        Self::ALL.get(value as usize).copied().unwrap_or(Reaction::Neutral)
+    }
+
+    fn value(self) -> u32 {
+        Self::ALL.iter().position(|&reaction| reaction == self).unwrap_or(0) as u32
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Reaction::Neutral => "Neutral",
+            Reaction::Enemy => "Enemy",
+            Reaction::Ally => "Ally",
+            Reaction::Friend => "Friend",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FactionRelation {
+    /// The faction reacted to. Races can be given here too.
+    pub faction: u32,
+    /// Added to the disposition of the faction's members towards the other's.
+    pub modifier: i32,
+    pub reaction: Reaction,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Faction {
+    pub form_id: u32,
+    pub editor_id: String,
+    pub relations: Vec<FactionRelation>,
+    /// Subrecords before the relations that are not decoded: the name.
+    pub other: Vec<Subrecord>,
+    /// Subrecords after the relations that are not decoded: the flags, ranks, crime and vendor data.
+    pub trailing: Vec<Subrecord>,
+}
+
+impl Faction {
+    pub fn from_record(record: &Record) -> io::Result<Self> {
+        if &record.code != b"FACT" {
+            return Err(invalid("not a FACT record"));
+        }
+
+        let mut faction = Self {
+            form_id: record.form_id,
+            editor_id: String::new(),
+            relations: vec![],
+            other: vec![],
+            trailing: vec![],
+        };
+
+        for subrecord in record.subrecords()? {
+            match &subrecord.code {
+                b"EDID" => faction.editor_id = subrecord.as_string(),
+                b"XNAM" => faction.relations.push(read_relation(&subrecord.data)?),
+                b"FULL" => faction.other.push(subrecord),
+                // The relations come before the flags, whether or not the faction has any.
+                _ => faction.trailing.push(subrecord),
+            }
+        }
+
+        Ok(faction)
+    }
+
+    /// The faction's reaction to another, if it has one.
+    pub fn relation(&self, faction: u32) -> Option<&FactionRelation> {
+        self.relations.iter().find(|relation| relation.faction == faction)
+    }
+
+    pub fn subrecords(&self) -> Vec<Subrecord> {
+        let mut subrecords = vec![Subrecord::string(*b"EDID", &self.editor_id)];
+        subrecords.extend(self.other.iter().cloned());
+
+        for relation in &self.relations {
+            let mut xnam = relation.faction.to_le_bytes().to_vec();
+            xnam.extend_from_slice(&relation.modifier.to_le_bytes());
+            xnam.extend_from_slice(&relation.reaction.value().to_le_bytes());
+            subrecords.push(Subrecord::new(*b"XNAM", xnam));
+        }
+
+        subrecords.extend(self.trailing.iter().cloned());
+        subrecords
+    }
+
+    /// Replaces the record's subrecords with the faction's.
+    pub fn write(&self, record: &mut Record) {
+        record.set_subrecords(&self.subrecords());
+    }
+}
+
+fn read_relation(data: &[u8]) -> io::Result<FactionRelation> {
+    if data.len() < 12 {
+        return Err(invalid("XNAM is truncated"));
+    }
+
+    let u32_at =
+        |offset: usize| u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+
+    Ok(FactionRelation {
+        faction: u32_at(0),
+        modifier: u32_at(4) as i32,
+        reaction: Reaction::from_value(u32_at(8)),
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FactionMember {
+    pub npc: u32,
+    pub rank: i8,
+}
+
+/// Every faction of a load order, for seeing how they react to each other.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FactionGraph {
+    /// The winning version of each faction, sorted by editor ID.
+    pub factions: Vec<Faction>,
+    /// The NPCs in each faction, by the faction's form ID.
+    pub members: HashMap<u32, Vec<FactionMember>>,
+}
+
+impl FactionGraph {
+    /// Reads the winning versions of the load order's factions, and which NPCs belong to each.
+    pub fn load(load_order: &LoadOrder) -> io::Result<Self> {
+        let mut factions = vec![];
+
+        for record in load_order.records_by_code(*b"FACT") {
+            if !record.is_deleted() {
+                factions.push(Faction::from_record(record)?);
+            }
+        }
+
+        factions.sort_by(|a, b| a.editor_id.cmp(&b.editor_id));
+
+        let mut members: HashMap<u32, Vec<FactionMember>> = HashMap::new();
+
+        for record in load_order.records_by_code(*b"NPC_") {
+            if record.is_deleted() {
+                continue;
+            }
+
+            for subrecord in record.subrecords()? {
+                if &subrecord.code != b"SNAM" || subrecord.data.len() < 5 {
+                    continue;
+                }
+
+                let data = &subrecord.data;
+                members
+                    .entry(u32::from_le_bytes([data[0], data[1], data[2], data[3]]))
+                    .or_default()
+                    .push(FactionMember {
+                        npc: record.form_id,
+                        rank: data[4] as i8,
+                    });
+            }
+        }
+
+        Ok(Self { factions, members })
+    }
+
+    pub fn faction(&self, form_id: u32) -> Option<&Faction> {
+        self.factions.iter().find(|faction| faction.form_id == form_id)
+    }
+
+    pub fn faction_mut(&mut self, form_id: u32) -> Option<&mut Faction> {
+        self.factions.iter_mut().find(|faction| faction.form_id == form_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esp::{group_types, Plugin};
+
+    const BANDITS: u32 = 0x0000_0800;
+    const GUARDS: u32 = 0x0000_0801;
+    const BANDIT: u32 = 0x0000_0900;
+
+    fn faction(form_id: u32, editor_id: &str, relations: Vec<FactionRelation>) -> Faction {
+        Faction {
+            form_id,
+            editor_id: editor_id.to_string(),
+            relations,
+            other: vec![Subrecord::string(*b"FULL", editor_id)],
+            trailing: vec![Subrecord::new(*b"DATA", vec![0; 4])],
+        }
+    }
+
+    #[test]
+    fn loads_relations_and_members() {
+        let bandits = faction(
+            BANDITS,
+            "BanditFaction",
+            vec![FactionRelation {
+                faction: GUARDS,
+                modifier: -100,
+                reaction: Reaction::Enemy,
+            }],
+        );
+        let guards = faction(GUARDS, "GuardFaction", vec![]);
+
+        let mut plugin = Plugin::new("Master.esm", &[]);
+        for faction in &[&guards, &bandits] {
+            let record = Record::new(*b"FACT", faction.form_id, &faction.subrecords());
+            assert_eq!(&Faction::from_record(&record).unwrap(), *faction);
+            plugin.add_record(&[(*b"FACT", group_types::TOP)], record, &mut |_| None);
+        }
+
+        let mut snam = BANDITS.to_le_bytes().to_vec();
+        snam.extend_from_slice(&[2, 0, 0, 0]);
+        let npc = Record::new(
+            *b"NPC_",
+            BANDIT,
+            &[Subrecord::string(*b"EDID", "Bandit"), Subrecord::new(*b"SNAM", snam)],
+        );
+        plugin.add_record(&[(*b"NPC_", group_types::TOP)], npc, &mut |_| None);
+
+        let graph = FactionGraph::load(&LoadOrder::new(vec![plugin], None)).unwrap();
+        assert_eq!(graph.factions, vec![bandits, guards]);
+        assert_eq!(graph.members[&BANDITS], vec![FactionMember { npc: BANDIT, rank: 2 }]);
+        assert_eq!(
+            graph.faction(BANDITS).unwrap().relation(GUARDS).unwrap().reaction,
+            Reaction::Enemy
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use super::invalid;
+
+use std::io;
+
+/// A little-endian cursor over a subrecord's data. `what` names the subrecord in errors, e.g. `NAVM NVNM`.
+pub(crate) struct Fields<'a> {
+    data: &'a [u8],
+    position: usize,
+    what: &'static str,
+}
+
+impl<'a> Fields<'a> {
+    pub fn new(data: &'a [u8], what: &'static str) -> Self {
+        Self {
+            data,
+            position: 0,
+            what,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    /// The data not read yet.
+    pub fn rest(&self) -> &'a [u8] {
+        &self.data[self.position.min(self.data.len())..]
+    }
+
+    pub fn bytes(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or_else(|| invalid(&format!("{} is truncated", self.what)))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> io::Result<u8> {
+        self.bytes(1).map(|bytes| bytes[0])
+    }
+
+    pub fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn i16(&mut self) -> io::Result<i16> {
+        self.u16().map(|value| value as i16)
+    }
+
+    pub fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn i32(&mut self) -> io::Result<i32> {
+        self.u32().map(|value| value as i32)
+    }
+
+    pub fn f32(&mut self) -> io::Result<f32> {
+        self.u32().map(f32::from_bits)
+    }
+
+    /// A string prefixed with its length as a u16, as used by script data.
+    pub fn string16(&mut self) -> io::Result<String> {
+        let length = self.u16()? as usize;
+        Ok(self.bytes(length)?.iter().map(|&c| c as char).collect())
+    }
+
+    /// A count followed by that many items.
+    pub fn array<T>(&mut self, mut item: impl FnMut(&mut Self) -> io::Result<T>) -> io::Result<Vec<T>> {
+        let count = self.u32()? as usize;
+
+        // Guard against corrupt counts before allocating.
+        if count > self.data.len() - self.position {
+            return Err(invalid(&format!("{} has an impossible count", self.what)));
+        }
+
+        (0..count).map(|_| item(self)).collect()
+    }
+}
+
+pub(crate) fn put_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn put_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Writes a string prefixed with its length as a u16.
+pub(crate) fn put_string16(data: &mut Vec<u8>, value: &str) {
+    let bytes: Vec<u8> = value.chars().map(|c| c as u8).collect();
+    put_u16(data, bytes.len() as u16);
+    data.extend_from_slice(&bytes);
+}
@@ -0,0 +1,667 @@
+//! Where form IDs are stored in records, so they can be translated between numberings.
+//!
+//! A plugin numbers form IDs by the index of the plugin that owns them in its MAST list in the top byte, with
+//! its own records numbered after its last master. The editor numbers them by the owning plugin's position in
+//! the load order instead, so plugins whose masters are not exactly the plugins before them are translated on
+//! load and translated back on write.
+//!
+//! Form IDs are found in record headers, in the labels of groups of children, and in the subrecord fields in
+//! [`FIELDS`], which covers every field the editor's decoders and tools read, along with the other form ID fields
+//! of the records they edit.
+
+use super::{
+    condition::RUN_ON_REFERENCE, condition_flags, condition_function, Code, Fragments, ObjectValue, ParamType,
+    PropertyValue, Record, Script, Subrecord, Vmad,
+};
+
+use std::collections::BTreeSet;
+
+/// How form IDs are laid out in a subrecord's data.
+enum Layout {
+    /// A form ID at each of these offsets.
+    At(&'static [usize]),
+    /// An array of structures `stride` bytes long, each with a form ID `offset` bytes into it.
+    Every { offset: usize, stride: usize },
+    /// A CTDA, whose value, parameters and reference may be form IDs.
+    Condition,
+    /// A VMAD, whose object properties are form IDs.
+    Scripts,
+    /// A NAVM NVNM, with its cell or worldspace, edge links and doors.
+    Navmesh,
+}
+
+/// The subrecords holding form IDs, for records with the given code or for every record when it is `None`.
+const FIELDS: &[(Option<Code>, Code, Layout)] = &[
+    (None, *b"CTDA", Layout::Condition),
+    (None, *b"VMAD", Layout::Scripts),
+    (None, *b"KWDA", Layout::Every { offset: 0, stride: 4 }),
+    (None, *b"CNTO", Layout::At(&[0])),
+    (None, *b"COED", Layout::At(&[0])),
+    (None, *b"DSTD", Layout::At(&[8, 12])),
+    (Some(*b"REFR"), *b"NAME", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XESP", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XOWN", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XLKR", Layout::At(&[0, 4])),
+    (Some(*b"REFR"), *b"XEZN", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XLCN", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XAPR", Layout::Every { offset: 0, stride: 8 }),
+    (Some(*b"REFR"), *b"XLRT", Layout::Every { offset: 0, stride: 4 }),
+    (Some(*b"REFR"), *b"XLRL", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XEMI", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XPOD", Layout::Every { offset: 0, stride: 4 }),
+    (Some(*b"REFR"), *b"XLRM", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"LNAM", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"INAM", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XTEL", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XTNM", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XCZR", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XCZC", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XSPC", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XLIB", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XLOC", Layout::At(&[4])),
+    (Some(*b"REFR"), *b"XNDP", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XPWR", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XATR", Layout::At(&[0])),
+    (Some(*b"REFR"), *b"XLTW", Layout::At(&[0])),
+    (Some(*b"ACHR"), *b"NAME", Layout::At(&[0])),
+    (Some(*b"ACHR"), *b"XESP", Layout::At(&[0])),
+    (Some(*b"ACHR"), *b"XOWN", Layout::At(&[0])),
+    (Some(*b"ACHR"), *b"XLKR", Layout::At(&[0, 4])),
+    (Some(*b"ACHR"), *b"XEZN", Layout::At(&[0])),
+    (Some(*b"ACHR"), *b"XLCN", Layout::At(&[0])),
+    (Some(*b"ACHR"), *b"XAPR", Layout::Every { offset: 0, stride: 8 }),
+    (Some(*b"ACHR"), *b"XLRT", Layout::Every { offset: 0, stride: 4 }),
+    (Some(*b"ACHR"), *b"XLRL", Layout::At(&[0])),
+    (Some(*b"ACHR"), *b"XHOR", Layout::At(&[0])),
+    (Some(*b"ACHR"), *b"XMRC", Layout::At(&[0])),
+    (Some(*b"PGRE"), *b"NAME", Layout::At(&[0])),
+    (Some(*b"PGRE"), *b"XESP", Layout::At(&[0])),
+    (Some(*b"CELL"), *b"LTMP", Layout::At(&[0])),
+    (Some(*b"CELL"), *b"XLCN", Layout::At(&[0])),
+    (Some(*b"CELL"), *b"XCWT", Layout::At(&[0])),
+    (Some(*b"CELL"), *b"XOWN", Layout::At(&[0])),
+    (Some(*b"CELL"), *b"XEZN", Layout::At(&[0])),
+    (Some(*b"CELL"), *b"XCAS", Layout::At(&[0])),
+    (Some(*b"CELL"), *b"XCIM", Layout::At(&[0])),
+    (Some(*b"CELL"), *b"XCMO", Layout::At(&[0])),
+    (Some(*b"CELL"), *b"XCLR", Layout::Every { offset: 0, stride: 4 }),
+    (Some(*b"CELL"), *b"XILL", Layout::At(&[0])),
+    (Some(*b"CELL"), *b"XCCM", Layout::At(&[0])),
+    (Some(*b"WRLD"), *b"WNAM", Layout::At(&[0])),
+    (Some(*b"WRLD"), *b"CNAM", Layout::At(&[0])),
+    (Some(*b"WRLD"), *b"NAM2", Layout::At(&[0])),
+    (Some(*b"WRLD"), *b"NAM3", Layout::At(&[0])),
+    (Some(*b"WRLD"), *b"XLCN", Layout::At(&[0])),
+    (Some(*b"WRLD"), *b"ZNAM", Layout::At(&[0])),
+    (Some(*b"CLMT"), *b"WLST", Layout::Every { offset: 0, stride: 12 }),
+    (Some(*b"CLMT"), *b"WLST", Layout::Every { offset: 8, stride: 12 }),
+    (Some(*b"REGN"), *b"WNAM", Layout::At(&[0])),
+    (Some(*b"REGN"), *b"RDMO", Layout::At(&[0])),
+    (Some(*b"REGN"), *b"RDSA", Layout::Every { offset: 0, stride: 12 }),
+    (Some(*b"REGN"), *b"RDWT", Layout::Every { offset: 0, stride: 12 }),
+    (Some(*b"REGN"), *b"RDWT", Layout::Every { offset: 8, stride: 12 }),
+    (Some(*b"REGN"), *b"RDGS", Layout::Every { offset: 0, stride: 8 }),
+    (Some(*b"FLST"), *b"LNAM", Layout::At(&[0])),
+    (Some(*b"LAND"), *b"BTXT", Layout::At(&[0])),
+    (Some(*b"LAND"), *b"ATXT", Layout::At(&[0])),
+    (Some(*b"NAVM"), *b"NVNM", Layout::Navmesh),
+    (Some(*b"LVLI"), *b"LVLO", Layout::At(&[4])),
+    (Some(*b"LVLI"), *b"LVLG", Layout::At(&[0])),
+    (Some(*b"LVLN"), *b"LVLO", Layout::At(&[4])),
+    (Some(*b"LVLN"), *b"LVLG", Layout::At(&[0])),
+    (Some(*b"LVSP"), *b"LVLO", Layout::At(&[4])),
+    (Some(*b"LVSP"), *b"LVLG", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"SNAM", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"INAM", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"VTCK", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"TPLT", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"RNAM", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"SPLO", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"WNAM", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"ANAM", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"ATKR", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"SPOR", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"OCOR", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"GWOR", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"ECOR", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"PRKR", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"PKID", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"CNAM", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"PNAM", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"HCLF", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"ZNAM", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"GNAM", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"DOFT", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"SOFT", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"DPLT", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"CRIF", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"FTST", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"CSDI", Layout::At(&[0])),
+    (Some(*b"NPC_"), *b"CSCR", Layout::At(&[0])),
+    (Some(*b"FACT"), *b"XNAM", Layout::At(&[0])),
+    (Some(*b"FACT"), *b"JAIL", Layout::At(&[0])),
+    (Some(*b"FACT"), *b"WAIT", Layout::At(&[0])),
+    (Some(*b"FACT"), *b"STOL", Layout::At(&[0])),
+    (Some(*b"FACT"), *b"PLCN", Layout::At(&[0])),
+    (Some(*b"FACT"), *b"CRGR", Layout::At(&[0])),
+    (Some(*b"FACT"), *b"JOUT", Layout::At(&[0])),
+    (Some(*b"FACT"), *b"VENC", Layout::At(&[0])),
+    (Some(*b"DLBR"), *b"QNAM", Layout::At(&[0])),
+    (Some(*b"DLBR"), *b"SNAM", Layout::At(&[0])),
+    (Some(*b"DIAL"), *b"BNAM", Layout::At(&[0])),
+    (Some(*b"DIAL"), *b"QNAM", Layout::At(&[0])),
+    (Some(*b"INFO"), *b"PNAM", Layout::At(&[0])),
+    (Some(*b"INFO"), *b"TCLT", Layout::At(&[0])),
+    (Some(*b"INFO"), *b"DNAM", Layout::At(&[0])),
+    (Some(*b"INFO"), *b"ANAM", Layout::At(&[0])),
+    (Some(*b"INFO"), *b"SNAM", Layout::At(&[0])),
+    (Some(*b"INFO"), *b"LNAM", Layout::At(&[0])),
+    (Some(*b"INFO"), *b"TRDT", Layout::At(&[16])),
+    (Some(*b"INFO"), *b"TWAT", Layout::At(&[0])),
+    (Some(*b"INFO"), *b"ONAM", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"QTGL", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"NAM0", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"ALFR", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"ALFL", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"ALUA", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"ALEQ", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"ALCO", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"KNAM", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"ALDN", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"ALSP", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"ALFC", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"ALPC", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"ALRT", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"VTCK", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"SPOR", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"OCOR", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"GWOR", Layout::At(&[0])),
+    (Some(*b"QUST"), *b"ECOR", Layout::At(&[0])),
+];
+
+/// Calls `map` with every form ID in a record's subrecords that [`FIELDS`] lists, replacing each with the
+/// result. Null form IDs are kept. Returns whether any subrecord changed.
+pub(crate) fn map_subrecords(code: Code, subrecords: &mut [Subrecord], map: &mut dyn FnMut(u32) -> u32) -> bool {
+    let mut changed = false;
+
+    for subrecord in subrecords.iter_mut() {
+        let subrecord_code = subrecord.code;
+        let layouts = FIELDS
+            .iter()
+            .filter(|(record, field, _)| *field == subrecord_code && record.iter().all(|&record| record == code));
+
+        for (_, _, layout) in layouts {
+            changed |= map_layout(code, subrecord, layout, map);
+        }
+    }
+
+    changed
+}
+
+/// Adds the top byte of the record's form ID and of every form ID in its subrecords to `indices`: the plugins
+/// the record belongs to and refers to.
+pub(crate) fn plugin_indices(record: &Record, indices: &mut BTreeSet<u8>) {
+    let mut add = |form_id: u32| {
+        indices.insert((form_id >> 24) as u8);
+        form_id
+    };
+
+    add(record.form_id);
+
+    if let Ok(mut subrecords) = record.subrecords() {
+        map_subrecords(record.code, &mut subrecords, &mut add);
+    }
+}
+
+fn map_layout(code: Code, subrecord: &mut Subrecord, layout: &Layout, map: &mut dyn FnMut(u32) -> u32) -> bool {
+    let data = &mut subrecord.data;
+
+    match layout {
+        Layout::At(offsets) => offsets
+            .iter()
+            .fold(false, |changed, &offset| map_at(data, offset, map) | changed),
+        Layout::Every { offset, stride } => (*offset..data.len())
+            .step_by(*stride)
+            .fold(false, |changed, offset| map_at(data, offset, map) | changed),
+        Layout::Condition => map_condition(data, map),
+        Layout::Scripts => map_scripts(code, subrecord, map),
+        Layout::Navmesh => map_navmesh(data, map),
+    }
+}
+
+/// Maps the form ID at `offset`, if the data is long enough to hold one there.
+fn map_at(data: &mut [u8], offset: usize, map: &mut dyn FnMut(u32) -> u32) -> bool {
+    let bytes = match data.get_mut(offset..offset + 4) {
+        Some(bytes) => bytes,
+        None => return false,
+    };
+
+    let form_id = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    if form_id == 0 {
+        return false;
+    }
+
+    let mapped = map(form_id);
+    bytes.copy_from_slice(&mapped.to_le_bytes());
+    mapped != form_id
+}
+
+fn map_condition(data: &mut [u8], map: &mut dyn FnMut(u32) -> u32) -> bool {
+    if data.len() < 28 {
+        return false;
+    }
+
+    let mut changed = false;
+
+    if data[0] & condition_flags::USE_GLOBAL != 0 {
+        changed |= map_at(data, 4, map);
+    }
+
+    let function = u16::from_le_bytes([data[8], data[9]]);
+
+    if let Some(function) = condition_function(function) {
+        for (index, param) in function.params.iter().enumerate() {
+            if let ParamType::Form(..) = param {
+                changed |= map_at(data, 12 + index * 4, map);
+            }
+        }
+    }
+
+    if u32::from_le_bytes([data[20], data[21], data[22], data[23]]) == RUN_ON_REFERENCE {
+        changed |= map_at(data, 24, map);
+    }
+
+    changed
+}
+
+fn map_scripts(code: Code, subrecord: &mut Subrecord, map: &mut dyn FnMut(u32) -> u32) -> bool {
+    let mut vmad = match Vmad::parse(subrecord, code) {
+        Ok(vmad) => vmad,
+        Err(_) => return false,
+    };
+
+    let mut changed = false;
+    let mut map_object = |object: &mut ObjectValue| {
+        let mapped = match object.form_id {
+            0 => 0,
+            form_id => map(form_id),
+        };
+        changed |= mapped != object.form_id;
+        object.form_id = mapped;
+    };
+
+    let mut scripts: Vec<&mut Script> = vmad.scripts.iter_mut().collect();
+
+    if let Fragments::Quest(fragments) = &mut vmad.fragments {
+        for alias in &mut fragments.aliases {
+            map_object(&mut alias.alias);
+            scripts.extend(alias.scripts.iter_mut());
+        }
+    }
+
+    for property in scripts.into_iter().flat_map(|script| script.properties.iter_mut()) {
+        match &mut property.value {
+            PropertyValue::Object(object) => map_object(object),
+            PropertyValue::ObjectArray(objects) => objects.iter_mut().for_each(&mut map_object),
+            _ => {}
+        }
+    }
+
+    if changed {
+        *subrecord = vmad.subrecord();
+    }
+
+    changed
+}
+
+fn map_navmesh(data: &mut [u8], map: &mut dyn FnMut(u32) -> u32) -> bool {
+    let u32_at = |data: &[u8], offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+
+    // A worldspace and grid position for exterior navmeshes, or a null worldspace and the cell for interiors.
+    let mut changed = match u32_at(data, 8) {
+        Some(0) => map_at(data, 12, map),
+        Some(_) => map_at(data, 8, map),
+        None => return false,
+    };
+
+    // The vertices and triangles hold no form IDs, so they are skipped by their sizes.
+    let mut offset = 16;
+    for size in &[12, 16] {
+        match u32_at(data, offset) {
+            Some(count) => offset += 4 + count * size,
+            None => return changed,
+        }
+    }
+
+    // Edge links have the navmesh they lead into after their type, and doors have the door after the triangle
+    // and type.
+    for &(form_id_offset, size) in &[(4, 10), (6, 10)] {
+        let count = match u32_at(data, offset) {
+            Some(count) => count,
+            None => return changed,
+        };
+        offset += 4;
+
+        for _ in 0..count {
+            changed |= map_at(data, offset + form_id_offset, map);
+            offset += size;
+        }
+    }
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esp::{
+        navmesh::NavmeshParent, AliasFill, Branch, Climate, Condition, ConditionValue, Faction, Info, Land,
+        LeveledList, Navmesh, Npc, Property, Quest, Reference, Topic, LEVELED_CODES,
+    };
+
+    /// A form ID of the plugin at index 1, and the same form ID once that plugin has moved to index 5.
+    fn local(object: u32) -> u32 {
+        0x0100_0000 | object
+    }
+
+    fn moved(object: u32) -> u32 {
+        0x0500_0000 | object
+    }
+
+    fn field(code: &[u8; 4], values: &[u32]) -> Subrecord {
+        Subrecord::new(
+            *code,
+            values.iter().flat_map(|value| value.to_le_bytes().to_vec()).collect(),
+        )
+    }
+
+    /// The record with its form IDs moved from plugin 1 to plugin 5, as they are when its plugin is loaded.
+    fn renumbered(code: &[u8; 4], mut subrecords: Vec<Subrecord>) -> Record {
+        map_subrecords(*code, &mut subrecords, &mut |form_id| match form_id >> 24 {
+            1 => (form_id & 0x00FF_FFFF) | 0x0500_0000,
+            _ => form_id,
+        });
+        Record::new(*code, moved(0x800), &subrecords)
+    }
+
+    #[test]
+    fn npc() {
+        let record = renumbered(
+            b"NPC_",
+            vec![
+                field(b"SNAM", &[local(1), 0]),
+                field(b"TPLT", &[local(2)]),
+                field(b"RNAM", &[local(3)]),
+                field(b"VTCK", &[local(4)]),
+                field(b"CNAM", &[local(5)]),
+                field(b"SPLO", &[local(6)]),
+                field(b"PRKR", &[local(7), 1]),
+                field(b"CNTO", &[local(8), 1]),
+                field(b"DOFT", &[local(9)]),
+                field(b"ZNAM", &[local(10)]),
+                field(b"PKID", &[local(11)]),
+            ],
+        );
+        let npc = Npc::from_record(&record, false).unwrap();
+
+        assert_eq!(npc.factions[0].faction, moved(1));
+        assert_eq!(npc.template, Some(moved(2)));
+        assert_eq!(npc.race, moved(3));
+        assert_eq!(npc.voice, Some(moved(4)));
+        assert_eq!(npc.class, moved(5));
+        assert_eq!(npc.spells, vec![moved(6)]);
+        assert_eq!(npc.perks[0].perk, moved(7));
+        assert_eq!(npc.inventory[0].item, moved(8));
+        assert_eq!(npc.outfit, Some(moved(9)));
+        assert_eq!(npc.combat_style, Some(moved(10)));
+        assert_eq!(npc.packages, vec![moved(11)]);
+    }
+
+    #[test]
+    fn quest() {
+        let record = renumbered(
+            b"QUST",
+            vec![
+                field(b"QTGL", &[local(1)]),
+                field(b"INDX", &[10]),
+                field(b"QSDT", &[0]),
+                field(b"NAM0", &[local(2)]),
+                field(b"ALST", &[0]),
+                field(b"ALFR", &[local(3)]),
+                field(b"ALST", &[1]),
+                field(b"ALUA", &[local(4)]),
+                field(b"ALLS", &[2]),
+                field(b"ALFL", &[local(5)]),
+                field(b"ALLS", &[3]),
+                field(b"ALFA", &[2]),
+                field(b"KNAM", &[local(6)]),
+                field(b"ALST", &[4]),
+                field(b"ALEQ", &[local(7)]),
+                field(b"ALEA", &[0]),
+                field(b"ALST", &[5]),
+                field(b"ALCO", &[local(8)]),
+                field(b"ALCA", &[0]),
+                field(b"ALCL", &[0]),
+            ],
+        );
+        let quest = Quest::from_record(&record, false).unwrap();
+
+        assert_eq!(quest.text_globals, vec![moved(1)]);
+        assert_eq!(quest.stages[0].log_entries[0].next_quest, Some(moved(2)));
+
+        let fills: Vec<_> = quest.aliases.iter().map(|alias| alias.fill.clone()).collect();
+        assert_eq!(
+            fills,
+            vec![
+                AliasFill::ForcedReference(moved(3)),
+                AliasFill::UniqueActor(moved(4)),
+                AliasFill::ForcedLocation(moved(5)),
+                AliasFill::LocationAlias {
+                    alias: 2,
+                    ref_type: moved(6)
+                },
+                AliasFill::ExternalAlias {
+                    quest: moved(7),
+                    alias: 0
+                },
+                AliasFill::CreateReference {
+                    object: moved(8),
+                    at: 0,
+                    level: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn dialogue() {
+        let branch = renumbered(b"DLBR", vec![field(b"QNAM", &[local(1)]), field(b"SNAM", &[local(2)])]);
+        let branch = Branch::from_record(&branch).unwrap();
+        assert_eq!((branch.quest, branch.starting_topic), (moved(1), moved(2)));
+
+        let topic = renumbered(b"DIAL", vec![field(b"BNAM", &[local(1)]), field(b"QNAM", &[local(2)])]);
+        let topic = Topic::from_record(&topic, false).unwrap();
+        assert_eq!((topic.branch, topic.quest), (moved(1), moved(2)));
+
+        let info = renumbered(
+            b"INFO",
+            vec![
+                field(b"PNAM", &[local(1)]),
+                field(b"TCLT", &[local(2)]),
+                field(b"TRDT", &[0, 50, 0, 1, local(3), 0]),
+                field(b"SNAM", &[local(4)]),
+                field(b"LNAM", &[local(5)]),
+                field(b"ANAM", &[local(6)]),
+            ],
+        );
+        let info = Info::from_record(&info, false).unwrap();
+        assert_eq!(info.previous, moved(1));
+        assert_eq!(info.links, vec![moved(2)]);
+        assert_eq!(info.responses[0].sound, moved(3));
+        assert_eq!(info.responses[0].speaker_idle, Some(moved(4)));
+        assert_eq!(info.responses[0].listener_idle, Some(moved(5)));
+        assert_eq!(info.speaker, Some(moved(6)));
+    }
+
+    #[test]
+    fn faction() {
+        let record = renumbered(b"FACT", vec![field(b"XNAM", &[local(1), 0, 1])]);
+        let faction = Faction::from_record(&record).unwrap();
+
+        assert_eq!(faction.relations[0].faction, moved(1));
+    }
+
+    #[test]
+    fn leveled_list() {
+        for code in &LEVELED_CODES {
+            let record = renumbered(
+                code,
+                vec![field(b"LVLG", &[local(1)]), field(b"LVLO", &[1, local(2), 1])],
+            );
+            let list = LeveledList::from_record(&record).unwrap();
+
+            assert_eq!(list.chance_none_global, Some(moved(1)));
+            assert_eq!(list.entries[0].form_id, moved(2));
+        }
+    }
+
+    #[test]
+    fn reference() {
+        for code in &[b"REFR", b"ACHR"] {
+            let record = renumbered(code, vec![field(b"NAME", &[local(1)]), field(b"XLCN", &[local(2)])]);
+
+            assert_eq!(Reference::from_record(&record).unwrap().base, moved(1));
+            assert_eq!(
+                record.subrecord(*b"XLCN").and_then(|xlcn| xlcn.as_u32()),
+                Some(moved(2))
+            );
+        }
+    }
+
+    #[test]
+    fn land() {
+        let record = renumbered(
+            b"LAND",
+            vec![field(b"BTXT", &[local(1), 0]), field(b"ATXT", &[local(2), 1])],
+        );
+        let land = Land::from_record(&record).unwrap();
+
+        assert_eq!(land.base_textures[0], Some(moved(1)));
+        assert_eq!(land.layers[0].texture, moved(2));
+    }
+
+    #[test]
+    fn climate() {
+        let record = renumbered(
+            b"CLMT",
+            vec![field(b"WLST", &[local(1), 60, local(2), local(3), 40, 0])],
+        );
+        let climate = Climate::from_record(&record).unwrap();
+
+        assert_eq!(climate.weathers, vec![(moved(1), 60), (moved(3), 40)]);
+        assert_eq!(record.subrecord(*b"WLST").unwrap().data[8..12], moved(2).to_le_bytes());
+    }
+
+    /// Fields read by the map, lighting and voice type tools without a decoder of their own.
+    #[test]
+    fn single_fields() {
+        let fields: &[(&[u8; 4], &[u8; 4])] = &[
+            (b"CELL", b"LTMP"),
+            (b"CELL", b"XLCN"),
+            (b"REGN", b"WNAM"),
+            (b"FLST", b"LNAM"),
+            (b"WRLD", b"CNAM"),
+        ];
+
+        for (code, field_code) in fields {
+            let record = renumbered(code, vec![field(field_code, &[local(1)])]);
+
+            assert_eq!(
+                record.subrecord(**field_code).and_then(|field| field.as_u32()),
+                Some(moved(1))
+            );
+        }
+    }
+
+    #[test]
+    fn navmesh() {
+        let mut nvnm = vec![];
+        for value in &[12, 0, 0, local(1), 0, 0, 1, 0] {
+            nvnm.extend_from_slice(&value.to_le_bytes());
+        }
+        nvnm.extend_from_slice(&local(2).to_le_bytes());
+        nvnm.extend_from_slice(&0i16.to_le_bytes());
+        nvnm.extend_from_slice(&1u32.to_le_bytes());
+        nvnm.extend_from_slice(&0i16.to_le_bytes());
+        nvnm.extend_from_slice(&0u32.to_le_bytes());
+        nvnm.extend_from_slice(&local(3).to_le_bytes());
+        nvnm.extend_from_slice(&[0; 4 + 4 + 32]);
+
+        let record = renumbered(b"NAVM", vec![Subrecord::new(*b"NVNM", nvnm)]);
+        let navmesh = Navmesh::from_record(&record).unwrap();
+
+        assert_eq!(navmesh.parent, NavmeshParent::Interior { cell: moved(1) });
+        assert_eq!(navmesh.edge_links[0].navmesh, moved(2));
+        assert_eq!(navmesh.doors[0].door, moved(3));
+    }
+
+    #[test]
+    fn condition() {
+        let condition = Condition {
+            value: ConditionValue::Global(local(1)),
+            function: 72,
+            params: [local(2), 0],
+            run_on: RUN_ON_REFERENCE,
+            reference: local(3),
+            ..Condition::default()
+        };
+        let record = renumbered(b"PACK", condition.subrecords());
+        let condition = Condition::parse(&record.subrecord(*b"CTDA").unwrap()).unwrap();
+
+        assert_eq!(condition.value, ConditionValue::Global(moved(1)));
+        assert_eq!(condition.params, [moved(2), 0]);
+        assert_eq!(condition.reference, moved(3));
+    }
+
+    #[test]
+    fn scripts() {
+        let object = |form_id| ObjectValue { form_id, alias: -1 };
+        let vmad = Vmad {
+            scripts: vec![Script {
+                name: "Example".to_string(),
+                status: 0,
+                properties: vec![
+                    Property {
+                        name: "Object".to_string(),
+                        status: 1,
+                        value: PropertyValue::Object(object(local(1))),
+                    },
+                    Property {
+                        name: "Objects".to_string(),
+                        status: 1,
+                        value: PropertyValue::ObjectArray(vec![object(local(2)), object(0)]),
+                    },
+                ],
+            }],
+            ..Vmad::default()
+        };
+        let record = renumbered(b"ACTI", vec![vmad.subrecord()]);
+        let vmad = Vmad::parse(&record.subrecord(*b"VMAD").unwrap(), *b"ACTI").unwrap();
+        let values: Vec<_> = vmad.scripts[0]
+            .properties
+            .iter()
+            .map(|property| property.value.clone())
+            .collect();
+
+        assert_eq!(
+            values,
+            vec![
+                PropertyValue::Object(object(moved(1))),
+                PropertyValue::ObjectArray(vec![object(moved(2)), object(0)]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,480 @@
+use super::{invalid, Record, Subrecord};
+
+use std::io;
+
+/// Vertices along each side of a cell's terrain.
+pub const LAND_SIZE: usize = 33;
+/// Vertices along each side of one quadrant's texture layers. Neighbouring quadrants share their edge vertices.
+pub const QUADRANT_SIZE: usize = 17;
+/// The width of an exterior cell in game units.
+pub const CELL_SIZE: f32 = 4096.0;
+/// The distance between neighbouring terrain vertices in game units.
+pub const VERTEX_SPACING: f32 = CELL_SIZE / (LAND_SIZE - 1) as f32;
+
+/// Painting stops adding layers to a quadrant past this many, to stay within what the game draws.
+pub const MAX_QUADRANT_LAYERS: usize = 8;
+
+/// VHGT stores heights in steps of this many game units.
+const HEIGHT_SCALE: f32 = 8.0;
+
+/// DATA flags of a LAND record, saying which parts it has.
+pub mod land_flags {
+    pub const HAS_NORMALS_AND_HEIGHTS: u32 = 0x0000_0001;
+    pub const HAS_COLORS: u32 = 0x0000_0002;
+    pub const HAS_LAYERS: u32 = 0x0000_0004;
+}
+
+/// A texture blended over a quadrant's base texture, with an opacity for each of the quadrant's vertices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LandLayer {
+    /// The LTEX record to draw.
+    pub texture: u32,
+    /// 0 is the south-west quadrant, 1 south-east, 2 north-west and 3 north-east.
+    pub quadrant: usize,
+    /// Layers are drawn in ascending order over the base texture.
+    pub layer: u16,
+    /// Opacities for the quadrant's vertices, row by row from the south-west corner. Vertices without a VTXT
+    /// entry are transparent.
+    pub opacity: Vec<f32>,
+}
+
+/// The terrain of an exterior cell, from a LAND record.
+///
+/// Per-vertex data is stored row by row from the cell's south-west corner, [`LAND_SIZE`] vertices to a row, with
+/// rows running west to east. Any part missing from the record is `None` or empty.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Land {
+    pub form_id: u32,
+    pub flags: u32,
+    /// Heights in game units, relative to the worldspace rather than the cell.
+    pub heights: Option<Vec<f32>>,
+    /// Unit normals in game axes.
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub colors: Option<Vec<[u8; 3]>>,
+    /// The LTEX record covering each quadrant, if the quadrant does not use the default texture.
+    pub base_textures: [Option<u32>; 4],
+    /// Additional texture layers, sorted by quadrant and then by layer.
+    pub layers: Vec<LandLayer>,
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+fn vertex_data(data: &[u8], code: &str) -> io::Result<Vec<[u8; 3]>> {
+    if data.len() < LAND_SIZE * LAND_SIZE * 3 {
+        return Err(invalid(&format!("LAND {} is too short", code)));
+    }
+
+    Ok(data
+        .chunks_exact(3)
+        .take(LAND_SIZE * LAND_SIZE)
+        .map(|chunk| [chunk[0], chunk[1], chunk[2]])
+        .collect())
+}
+
+/// Decodes VHGT: a float offset followed by one signed byte per vertex. The first byte of each row is the
+/// change from the start of the previous row, and every other byte the change from the previous vertex.
+fn heights(data: &[u8]) -> io::Result<Vec<f32>> {
+    if data.len() < 4 + LAND_SIZE * LAND_SIZE {
+        return Err(invalid("LAND VHGT is too short"));
+    }
+
+    let offset = f32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    let gradients = &data[4..4 + LAND_SIZE * LAND_SIZE];
+    let mut heights = Vec::with_capacity(LAND_SIZE * LAND_SIZE);
+    let mut row_start = offset;
+
+    for row in gradients.chunks_exact(LAND_SIZE) {
+        row_start += row[0] as i8 as f32;
+        let mut height = row_start;
+        heights.push(height * HEIGHT_SCALE);
+
+        for &gradient in &row[1..] {
+            height += gradient as i8 as f32;
+            heights.push(height * HEIGHT_SCALE);
+        }
+    }
+
+    Ok(heights)
+}
+
+/// Encodes heights as VHGT, rounding them to its height steps. A change too steep for one signed byte is cut
+/// short, and later vertices carry on from where it ended.
+fn encode_heights(heights: &[f32]) -> Vec<u8> {
+    let steps: Vec<i32> = heights
+        .iter()
+        .map(|height| (height / HEIGHT_SCALE).round() as i32)
+        .collect();
+    let gradient = |change: i32| change.clamp(i8::MIN as i32, i8::MAX as i32);
+
+    let offset = steps[0];
+    let mut data = (offset as f32).to_le_bytes().to_vec();
+    let mut row_start = offset;
+
+    for row in steps.chunks_exact(LAND_SIZE) {
+        let change = gradient(row[0] - row_start);
+        row_start += change;
+        data.push(change as i8 as u8);
+
+        let mut height = row_start;
+        for &step in &row[1..] {
+            let change = gradient(step - height);
+            height += change;
+            data.push(change as i8 as u8);
+        }
+    }
+
+    // VHGT ends with three unused bytes.
+    data.extend_from_slice(&[0; 3]);
+    data
+}
+
+impl Land {
+    pub fn from_record(record: &Record) -> io::Result<Self> {
+        let mut land = Land {
+            form_id: record.form_id,
+            ..Land::default()
+        };
+
+        // VTXT belongs to the ATXT before it.
+        let mut layer: Option<LandLayer> = None;
+
+        for subrecord in record.subrecords()? {
+            let data = &subrecord.data[..];
+
+            match &subrecord.code {
+                b"DATA" if data.len() >= 4 => land.flags = u32_at(data, 0),
+                b"VHGT" => land.heights = Some(heights(data)?),
+                b"VNML" => {
+                    let normals = vertex_data(data, "VNML")?
+                        .into_iter()
+                        .map(|normal| {
+                            let [x, y, z] = normal;
+                            let normal = [x as i8 as f32, y as i8 as f32, z as i8 as f32];
+                            let length = normal.iter().map(|value| value * value).sum::<f32>().sqrt();
+
+                            if length > 0.0 {
+                                [normal[0] / length, normal[1] / length, normal[2] / length]
+                            } else {
+                                [0.0, 0.0, 1.0]
+                            }
+                        })
+                        .collect();
+
+                    land.normals = Some(normals);
+                }
+                b"VCLR" => land.colors = Some(vertex_data(data, "VCLR")?),
+                b"BTXT" | b"ATXT" if data.len() >= 8 => {
+                    let texture = u32_at(data, 0);
+                    let quadrant = data[4] as usize;
+
+                    if quadrant > 3 {
+                        return Err(invalid("LAND texture quadrant is out of range"));
+                    }
+
+                    land.layers.extend(layer.take());
+
+                    if &subrecord.code == b"BTXT" {
+                        land.base_textures[quadrant] = Some(texture).filter(|&texture| texture != 0);
+                    } else {
+                        layer = Some(LandLayer {
+                            texture,
+                            quadrant,
+                            layer: u16_at(data, 6),
+                            opacity: vec![0.0; QUADRANT_SIZE * QUADRANT_SIZE],
+                        });
+                    }
+                }
+                b"VTXT" => {
+                    let layer = layer
+                        .as_mut()
+                        .ok_or_else(|| invalid("LAND VTXT does not follow an ATXT"))?;
+
+                    // Each entry is a vertex index within the quadrant, two unused bytes and an opacity.
+                    for entry in data.chunks_exact(8) {
+                        let position = u16_at(entry, 0) as usize;
+                        let opacity = f32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]);
+
+                        if let Some(value) = layer.opacity.get_mut(position) {
+                            *value = opacity.clamp(0.0, 1.0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        land.layers.extend(layer);
+        land.layers.sort_by_key(|layer| (layer.quadrant, layer.layer));
+
+        Ok(land)
+    }
+
+    /// Writes the terrain into a LAND record, replacing its vertex and texture subrecords and keeping any others.
+    /// Layers that are transparent everywhere are left out.
+    pub fn write(&self, record: &mut Record) -> io::Result<()> {
+        const REPLACED: [[u8; 4]; 7] = [*b"DATA", *b"VNML", *b"VHGT", *b"VCLR", *b"BTXT", *b"ATXT", *b"VTXT"];
+
+        let mut flags = self.flags;
+        let mut subrecords = vec![];
+        let vertex_bytes = |vertices: Vec<[u8; 3]>| vertices.iter().flatten().cloned().collect();
+
+        if let Some(normals) = &self.normals {
+            let byte = |value: f32| (value * 127.0).round().clamp(-127.0, 127.0) as i8 as u8;
+            let normals = normals
+                .iter()
+                .map(|normal| [byte(normal[0]), byte(normal[1]), byte(normal[2])])
+                .collect();
+            subrecords.push(Subrecord::new(*b"VNML", vertex_bytes(normals)));
+        }
+
+        if let Some(heights) = &self.heights {
+            subrecords.push(Subrecord::new(*b"VHGT", encode_heights(heights)));
+            flags |= land_flags::HAS_NORMALS_AND_HEIGHTS;
+        }
+
+        if let Some(colors) = &self.colors {
+            subrecords.push(Subrecord::new(*b"VCLR", vertex_bytes(colors.clone())));
+            flags |= land_flags::HAS_COLORS;
+        }
+
+        for (quadrant, texture) in self.base_textures.iter().enumerate() {
+            if let Some(texture) = texture {
+                let mut data = texture.to_le_bytes().to_vec();
+                data.extend_from_slice(&[quadrant as u8, 0]);
+                data.extend_from_slice(&(-1i16).to_le_bytes());
+                subrecords.push(Subrecord::new(*b"BTXT", data));
+            }
+        }
+
+        for layer in &self.layers {
+            let entries: Vec<u8> = layer
+                .opacity
+                .iter()
+                .enumerate()
+                .filter(|(_, &opacity)| opacity > 0.0)
+                .flat_map(|(position, opacity)| {
+                    let mut entry = (position as u16).to_le_bytes().to_vec();
+                    entry.extend_from_slice(&[0, 0]);
+                    entry.extend_from_slice(&opacity.to_le_bytes());
+                    entry
+                })
+                .collect();
+
+            if entries.is_empty() {
+                continue;
+            }
+
+            let mut data = layer.texture.to_le_bytes().to_vec();
+            data.extend_from_slice(&[layer.quadrant as u8, 0]);
+            data.extend_from_slice(&layer.layer.to_le_bytes());
+            subrecords.push(Subrecord::new(*b"ATXT", data));
+            subrecords.push(Subrecord::new(*b"VTXT", entries));
+            flags |= land_flags::HAS_LAYERS;
+        }
+
+        subrecords.insert(0, Subrecord::new(*b"DATA", flags.to_le_bytes().to_vec()));
+
+        // VTEX and anything else this editor does not understand stays after the terrain data.
+        subrecords.extend(
+            record
+                .subrecords()?
+                .into_iter()
+                .filter(|subrecord| !REPLACED.contains(&subrecord.code)),
+        );
+
+        record.set_subrecords(&subrecords);
+        Ok(())
+    }
+
+    /// Blends a texture over one vertex of a quadrant by `amount`, from 0 to 1. Painting the quadrant's base
+    /// texture fades out every layer; painting any other texture fades in its layer, adding one on top if the
+    /// quadrant has none for it, and fades out the layers above it.
+    ///
+    /// Returns `false` if the quadrant already has [`MAX_QUADRANT_LAYERS`] layers and none for the texture.
+    pub fn paint_layer(&mut self, quadrant: usize, position: usize, texture: u32, amount: f32) -> bool {
+        let amount = amount.clamp(0.0, 1.0);
+
+        if self.base_textures[quadrant] == Some(texture) {
+            for layer in self.layers.iter_mut().filter(|layer| layer.quadrant == quadrant) {
+                layer.opacity[position] *= 1.0 - amount;
+            }
+
+            return true;
+        }
+
+        let existing = self
+            .layers
+            .iter()
+            .find(|layer| layer.quadrant == quadrant && layer.texture == texture)
+            .map(|layer| layer.layer);
+
+        let painted = match existing {
+            Some(painted) => painted,
+            None => {
+                let in_quadrant = self.layers.iter().filter(|layer| layer.quadrant == quadrant);
+
+                if in_quadrant.clone().count() >= MAX_QUADRANT_LAYERS {
+                    return false;
+                }
+
+                let top = in_quadrant.map(|layer| layer.layer + 1).max().unwrap_or(0);
+
+                self.layers.push(LandLayer {
+                    texture,
+                    quadrant,
+                    layer: top,
+                    opacity: vec![0.0; QUADRANT_SIZE * QUADRANT_SIZE],
+                });
+                self.layers.sort_by_key(|layer| (layer.quadrant, layer.layer));
+                top
+            }
+        };
+
+        for layer in self.layers.iter_mut().filter(|layer| layer.quadrant == quadrant) {
+            let opacity = &mut layer.opacity[position];
+
+            if layer.layer == painted {
+                *opacity += (1.0 - *opacity) * amount;
+            } else if layer.layer > painted {
+                *opacity *= 1.0 - amount;
+            }
+        }
+
+        true
+    }
+
+    /// The quadrant containing a vertex. Vertices on the middle row and column count towards the north and east
+    /// quadrants.
+    pub fn quadrant(x: usize, y: usize) -> usize {
+        let half = LAND_SIZE / 2;
+        (x >= half) as usize + 2 * (y >= half) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esp::Subrecord;
+
+    fn record() -> Record {
+        // Each row starts 1 step above the last, and rises 2 steps along its length.
+        let mut vhgt = 10.0f32.to_le_bytes().to_vec();
+        for _ in 0..LAND_SIZE {
+            vhgt.push(1);
+            vhgt.push(2);
+            vhgt.extend_from_slice(&[0; LAND_SIZE - 2]);
+        }
+        vhgt.extend_from_slice(&[0; 3]);
+
+        let vnml = [0u8, 0, 127].repeat(LAND_SIZE * LAND_SIZE);
+        let mut vclr = [255u8, 255, 255].repeat(LAND_SIZE * LAND_SIZE);
+        vclr[0] = 128;
+
+        let mut btxt = 0x0000_0A00u32.to_le_bytes().to_vec();
+        btxt.extend_from_slice(&[3, 0, 0xFF, 0xFF]);
+        let mut atxt = 0x0000_0A01u32.to_le_bytes().to_vec();
+        atxt.extend_from_slice(&[3, 0, 1, 0]);
+        let mut vtxt = 18u16.to_le_bytes().to_vec();
+        vtxt.extend_from_slice(&[0, 0]);
+        vtxt.extend_from_slice(&0.5f32.to_le_bytes());
+
+        Record::new(
+            *b"LAND",
+            0x0000_0B00,
+            &[
+                Subrecord::new(*b"DATA", vec![0x1F, 0, 0, 0]),
+                Subrecord::new(*b"VNML", vnml),
+                Subrecord::new(*b"VHGT", vhgt),
+                Subrecord::new(*b"VCLR", vclr),
+                Subrecord::new(*b"BTXT", btxt),
+                Subrecord::new(*b"ATXT", atxt),
+                Subrecord::new(*b"VTXT", vtxt),
+                Subrecord::new(*b"VTEX", vec![0; 4]),
+            ],
+        )
+    }
+
+    #[test]
+    fn from_record() {
+        let land = Land::from_record(&record()).unwrap();
+        let heights = land.heights.unwrap();
+        assert_eq!(heights[0], 88.0);
+        assert_eq!(heights[1], 104.0);
+        assert_eq!(heights[LAND_SIZE - 1], 104.0);
+        assert_eq!(heights[LAND_SIZE], 96.0);
+        assert_eq!(heights[LAND_SIZE * LAND_SIZE - 1], (10.0 + 33.0 + 2.0) * 8.0);
+
+        assert_eq!(land.normals.unwrap()[0], [0.0, 0.0, 1.0]);
+        assert_eq!(land.colors.unwrap()[0], [128, 255, 255]);
+        assert_eq!(land.base_textures, [None, None, None, Some(0x0000_0A00)]);
+
+        assert_eq!(land.layers.len(), 1);
+        let layer = &land.layers[0];
+        assert_eq!((layer.texture, layer.quadrant, layer.layer), (0x0000_0A01, 3, 1));
+        assert_eq!(layer.opacity[18], 0.5);
+        assert_eq!(layer.opacity[0], 0.0);
+
+        assert_eq!(Land::quadrant(0, 0), 0);
+        assert_eq!(Land::quadrant(16, 0), 1);
+        assert_eq!(Land::quadrant(32, 32), 3);
+    }
+
+    #[test]
+    fn write() {
+        let mut record = record();
+        let mut land = Land::from_record(&record).unwrap();
+        let heights = land.heights.as_mut().unwrap();
+        heights[40] += 80.0;
+        // Too steep for one step each way, so these are cut short and the row catches up after them.
+        heights[41] += 4000.0;
+
+        assert!(land.paint_layer(0, 5, 0x0000_0A02, 0.5));
+        land.write(&mut record).unwrap();
+
+        let written = Land::from_record(&record).unwrap();
+        let written_heights = written.heights.as_ref().unwrap();
+        assert_eq!(written_heights[40], land.heights.as_ref().unwrap()[40]);
+        assert_eq!(written_heights[41], written_heights[40] + 127.0 * 8.0);
+        assert_eq!(written_heights[42], written_heights[41] - 128.0 * 8.0);
+        assert_eq!(written_heights[43], land.heights.as_ref().unwrap()[43]);
+        assert_eq!(written_heights[100], land.heights.as_ref().unwrap()[100]);
+
+        assert_eq!(written.normals, land.normals);
+        assert_eq!(written.colors, land.colors);
+        assert_eq!(written.base_textures, land.base_textures);
+        assert_eq!(written.layers, land.layers);
+        assert_eq!(written.flags & land_flags::HAS_LAYERS, land_flags::HAS_LAYERS);
+        assert!(record.subrecord(*b"VTEX").is_some());
+    }
+
+    #[test]
+    fn paint_layer() {
+        let mut land = Land::from_record(&record()).unwrap();
+
+        // Painting a new texture adds a layer above the existing one and fades that one out.
+        land.layers[0].opacity[0] = 1.0;
+        assert!(land.paint_layer(3, 0, 0x0000_0A02, 0.25));
+        assert_eq!(land.layers.len(), 2);
+        assert_eq!((land.layers[1].texture, land.layers[1].layer), (0x0000_0A02, 2));
+        assert_eq!(land.layers[1].opacity[0], 0.25);
+
+        // Painting the lower layer fades out the one above it.
+        assert!(land.paint_layer(3, 0, 0x0000_0A01, 1.0));
+        assert_eq!(land.layers[1].opacity[0], 0.0);
+
+        // Painting the base texture fades out every layer.
+        assert!(land.paint_layer(3, 0, 0x0000_0A00, 1.0));
+        assert_eq!(land.layers[0].opacity[0], 0.0);
+
+        for texture in 0..MAX_QUADRANT_LAYERS as u32 {
+            land.paint_layer(0, 0, 0x0000_0C00 + texture, 1.0);
+        }
+        assert!(!land.paint_layer(0, 0, 0x0000_0D00, 1.0));
+    }
+}
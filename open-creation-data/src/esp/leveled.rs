@@ -0,0 +1,219 @@
+//! Leveled lists from LVLI, LVLN and LVSP records: items, actors and spells picked by the player's level.
+//!
+//! The entries (LVLO, each optionally followed by COED extra data) come after the list's settings. Subrecords
+//! that are not decoded are kept before or after the entries, where they were found.
+
+use super::{invalid, Code, Record, Subrecord};
+
+use std::io;
+
+/// The codes of the leveled list records.
+pub const LEVELED_CODES: [Code; 3] = [*b"LVLI", *b"LVLN", *b"LVSP"];
+
+/// The most entries a list can have, as its LLCT count is a single byte.
+pub const MAX_LEVELED_ENTRIES: usize = 255;
+
+pub mod leveled_flags {
+    /// Entries of any level up to the player's can be picked, not only those of the highest such level.
+    pub const CALCULATE_FROM_ALL_LEVELS: u8 = 0x01;
+    /// An entry is picked for each of the count, rather than one entry given count times.
+    pub const CALCULATE_FOR_EACH_ITEM: u8 = 0x02;
+    /// Every entry up to the player's level is given (LVLI and LVSP).
+    pub const USE_ALL: u8 = 0x04;
+    /// LVLI only.
+    pub const SPECIAL_LOOT: u8 = 0x08;
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LeveledEntry {
+    pub level: u16,
+    pub form_id: u32,
+    pub count: u16,
+    /// The padding after the level and count, as stored.
+    pub unknown: [u16; 2],
+    /// The COED data following the entry: its owner, the global or rank required, and the item's condition.
+    pub extra: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeveledList {
+    pub form_id: u32,
+    /// One of [`LEVELED_CODES`].
+    pub code: Code,
+    pub editor_id: String,
+    /// The percent chance of giving nothing.
+    pub chance_none: u8,
+    /// [`leveled_flags`]
+    pub flags: u8,
+    /// A global whose value is used as the chance of giving nothing instead of `chance_none`.
+    pub chance_none_global: Option<u32>,
+    pub entries: Vec<LeveledEntry>,
+    /// Subrecords before the entries that are not decoded, such as OBND.
+    pub other: Vec<Subrecord>,
+    /// Subrecords after the entries that are not decoded, such as the model of an LVLN.
+    pub trailing: Vec<Subrecord>,
+}
+
+impl LeveledList {
+    /// An empty list of the type given by `code`.
+    pub fn new(form_id: u32, code: Code) -> Self {
+        Self {
+            form_id,
+            code,
+            editor_id: String::new(),
+            chance_none: 0,
+            flags: 0,
+            chance_none_global: None,
+            entries: vec![],
+            other: vec![],
+            trailing: vec![],
+        }
+    }
+
+    pub fn from_record(record: &Record) -> io::Result<Self> {
+        if !LEVELED_CODES.contains(&record.code) {
+            return Err(invalid("not a leveled list record"));
+        }
+
+        let mut list = Self::new(record.form_id, record.code);
+
+        for subrecord in record.subrecords()? {
+            match &subrecord.code {
+                b"EDID" => list.editor_id = subrecord.as_string(),
+                b"LVLD" => list.chance_none = subrecord.data.first().copied().unwrap_or(0),
+                b"LVLF" => list.flags = subrecord.data.first().copied().unwrap_or(0),
+                b"LVLG" => list.chance_none_global = subrecord.as_u32().filter(|&global| global != 0),
+                // The entry count is written from the entries.
+                b"LLCT" => {}
+                b"LVLO" => list.entries.push(read_entry(&subrecord.data)?),
+                b"COED" if !list.entries.is_empty() && list.trailing.is_empty() => {
+                    list.entries.last_mut().unwrap().extra = Some(subrecord.data);
+                }
+                _ if list.entries.is_empty() => list.other.push(subrecord),
+                _ => list.trailing.push(subrecord),
+            }
+        }
+
+        Ok(list)
+    }
+
+    /// The chance of giving nothing, as the game works it out: from the global if one is set.
+    pub fn chance_none(&self, global_value: impl Fn(u32) -> Option<f32>) -> f32 {
+        self.chance_none_global
+            .and_then(global_value)
+            .unwrap_or(self.chance_none as f32)
+    }
+
+    /// The list's subrecords. Fails if it has more than [`MAX_LEVELED_ENTRIES`] entries.
+    pub fn subrecords(&self) -> io::Result<Vec<Subrecord>> {
+        if self.entries.len() > MAX_LEVELED_ENTRIES {
+            return Err(invalid(&format!(
+                "{} has {} entries, more than the {} a leveled list can hold",
+                self.editor_id,
+                self.entries.len(),
+                MAX_LEVELED_ENTRIES
+            )));
+        }
+
+        let mut subrecords = vec![Subrecord::string(*b"EDID", &self.editor_id)];
+
+        subrecords.extend(self.other.iter().cloned());
+        subrecords.push(Subrecord::new(*b"LVLD", vec![self.chance_none]));
+        subrecords.push(Subrecord::new(*b"LVLF", vec![self.flags]));
+        subrecords.extend(
+            self.chance_none_global
+                .map(|global| Subrecord::new(*b"LVLG", global.to_le_bytes().to_vec())),
+        );
+        subrecords.push(Subrecord::new(*b"LLCT", vec![self.entries.len() as u8]));
+
+        for entry in &self.entries {
+            let mut lvlo = entry.level.to_le_bytes().to_vec();
+            lvlo.extend_from_slice(&entry.unknown[0].to_le_bytes());
+            lvlo.extend_from_slice(&entry.form_id.to_le_bytes());
+            lvlo.extend_from_slice(&entry.count.to_le_bytes());
+            lvlo.extend_from_slice(&entry.unknown[1].to_le_bytes());
+            subrecords.push(Subrecord::new(*b"LVLO", lvlo));
+            subrecords.extend(entry.extra.clone().map(|extra| Subrecord::new(*b"COED", extra)));
+        }
+
+        subrecords.extend(self.trailing.iter().cloned());
+        Ok(subrecords)
+    }
+
+    /// Replaces the record's subrecords with the list's. Fails, leaving the record as it was, if the list has
+    /// too many entries.
+    pub fn write(&self, record: &mut Record) -> io::Result<()> {
+        record.set_subrecords(&self.subrecords()?);
+        Ok(())
+    }
+}
+
+fn read_entry(data: &[u8]) -> io::Result<LeveledEntry> {
+    if data.len() < 8 {
+        return Err(invalid("LVLO is truncated"));
+    }
+
+    let u16_at = |offset: usize| {
+        data.get(offset..offset + 2)
+            .map_or(0, |bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+    };
+
+    Ok(LeveledEntry {
+        level: u16_at(0),
+        form_id: u32::from_le_bytes([data[4], data[5], data[6], data[7]]),
+        count: u16_at(8),
+        unknown: [u16_at(2), u16_at(10)],
+        extra: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let list = LeveledList {
+            form_id: 0x0000_0800,
+            code: *b"LVLN",
+            editor_id: "LCharBandit".to_string(),
+            chance_none: 25,
+            flags: leveled_flags::CALCULATE_FROM_ALL_LEVELS,
+            chance_none_global: Some(0x0000_0900),
+            entries: vec![
+                LeveledEntry {
+                    level: 1,
+                    form_id: 0x0000_0A00,
+                    count: 1,
+                    ..LeveledEntry::default()
+                },
+                LeveledEntry {
+                    level: 12,
+                    form_id: 0x0000_0A01,
+                    count: 2,
+                    unknown: [0, 0],
+                    extra: Some(vec![0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0x80, 0x3F]),
+                },
+            ],
+            other: vec![Subrecord::new(*b"OBND", vec![0; 12])],
+            trailing: vec![Subrecord::string(*b"MODL", "Actors\\Character\\Bandit.nif")],
+        };
+
+        let record = Record::new(*b"LVLN", list.form_id, &list.subrecords().unwrap());
+        assert_eq!(LeveledList::from_record(&record).unwrap(), list);
+        assert_eq!(list.chance_none(|_| Some(40.0)), 40.0);
+        assert_eq!(list.chance_none(|_| None), 25.0);
+    }
+
+    #[test]
+    fn refuses_too_many_entries() {
+        let mut list = LeveledList::new(0x0000_0800, *b"LVLI");
+        list.entries = vec![LeveledEntry::default(); MAX_LEVELED_ENTRIES];
+        assert!(list.subrecords().is_ok());
+
+        list.entries.push(LeveledEntry::default());
+        let mut record = Record::new(*b"LVLI", list.form_id, &[]);
+        assert_eq!(list.write(&mut record).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(record.subrecords().unwrap().is_empty());
+    }
+}
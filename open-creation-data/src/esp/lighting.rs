@@ -0,0 +1,590 @@
+use super::{LoadOrder, Record, Subrecord};
+
+use std::f32::consts::PI;
+
+/// Which parts of a cell's XCLL lighting come from its lighting template (LTMP) instead.
+pub mod inherit_flags {
+    pub const AMBIENT_COLOR: u32 = 0x0001;
+    pub const DIRECTIONAL_COLOR: u32 = 0x0002;
+    pub const FOG_COLOR: u32 = 0x0004;
+    pub const FOG_NEAR: u32 = 0x0008;
+    pub const FOG_FAR: u32 = 0x0010;
+    pub const DIRECTIONAL_ROTATION: u32 = 0x0020;
+    pub const DIRECTIONAL_FADE: u32 = 0x0040;
+    pub const CLIP_DISTANCE: u32 = 0x0080;
+    pub const FOG_POWER: u32 = 0x0100;
+    pub const FOG_MAX: u32 = 0x0200;
+    pub const LIGHT_FADE_DISTANCES: u32 = 0x0400;
+}
+
+/// DATA flags of a LIGH record.
+pub mod light_flags {
+    pub const NEGATIVE: u32 = 0x0004;
+    pub const OFF_BY_DEFAULT: u32 = 0x0020;
+}
+
+/// Weather colours are given for each of these times of day, in this order.
+pub const SUNRISE: usize = 0;
+pub const DAY: usize = 1;
+pub const SUNSET: usize = 2;
+pub const NIGHT: usize = 3;
+
+// Indices of the colour types in a weather's NAM0.
+const SKY_UPPER: usize = 0;
+const FOG_NEAR: usize = 1;
+const AMBIENT: usize = 3;
+const SUNLIGHT: usize = 4;
+const HORIZON: usize = 8;
+const FOG_FAR: usize = 12;
+
+/// Sunrise and sunset times used when a worldspace has no climate, in hours.
+const DEFAULT_SUN_TIMES: [f32; 4] = [5.0, 7.0, 18.0, 20.0];
+
+/// Colours are in sRGB as stored.
+type Rgb = [u8; 3];
+
+fn f32_at(data: &[u8], offset: usize) -> Option<f32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn rgb_at(data: &[u8], offset: usize) -> Option<Rgb> {
+    let bytes = data.get(offset..offset + 3)?;
+    Some([bytes[0], bytes[1], bytes[2]])
+}
+
+fn mix(colors: &[Rgb; 4], weights: [f32; 4]) -> Rgb {
+    let mut mixed = [0.0f32; 3];
+
+    for (color, weight) in colors.iter().zip(weights.iter()) {
+        for (channel, &value) in mixed.iter_mut().zip(color.iter()) {
+            *channel += value as f32 * weight;
+        }
+    }
+
+    [mixed[0].round() as u8, mixed[1].round() as u8, mixed[2].round() as u8]
+}
+
+/// Normalizes a vector, falling back to straight up for a zero vector.
+fn normalize(vector: [f32; 3]) -> [f32; 3] {
+    let length = (vector[0] * vector[0] + vector[1] * vector[1] + vector[2] * vector[2]).sqrt();
+
+    if length > f32::EPSILON {
+        [vector[0] / length, vector[1] / length, vector[2] / length]
+    } else {
+        [0.0, 0.0, 1.0]
+    }
+}
+
+/// The lighting of a scene, from an interior cell's XCLL and lighting template or from an exterior weather.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lighting {
+    pub ambient: Rgb,
+    pub directional: Rgb,
+    /// A unit vector pointing towards the directional light, in game axes.
+    pub direction: [f32; 3],
+    pub fog_near_color: Rgb,
+    pub fog_far_color: Rgb,
+    /// Fog distances in game units.
+    pub fog_near: f32,
+    pub fog_far: f32,
+    pub fog_power: f32,
+    /// The most the fog covers, from 0 to 1.
+    pub fog_max: f32,
+    /// The colour behind everything: the horizon outdoors and the near fog colour indoors.
+    pub sky: Rgb,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            ambient: [128; 3],
+            directional: [255; 3],
+            direction: normalize([0.5, -0.5, 1.0]),
+            fog_near_color: [166; 3],
+            fog_far_color: [166; 3],
+            fog_near: 0.0,
+            fog_far: 0.0,
+            fog_power: 1.0,
+            fog_max: 1.0,
+            sky: [166; 3],
+        }
+    }
+}
+
+/// The fields of XCLL, or of a lighting template's DATA, which shares its layout up to the inherit flags.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct LightingData {
+    ambient: Rgb,
+    directional: Rgb,
+    fog_near_color: Rgb,
+    fog_near: f32,
+    fog_far: f32,
+    /// Rotation of the directional light in degrees: its elevation and then its heading.
+    rotation: [f32; 2],
+    fog_power: f32,
+    fog_far_color: Option<Rgb>,
+    fog_max: f32,
+    inherit: u32,
+}
+
+impl LightingData {
+    fn parse(data: &[u8]) -> Option<Self> {
+        let i32_at = |offset: usize| u32_at(data, offset).map(|value| value as i32 as f32);
+
+        Some(Self {
+            ambient: rgb_at(data, 0)?,
+            directional: rgb_at(data, 4)?,
+            fog_near_color: rgb_at(data, 8)?,
+            fog_near: f32_at(data, 12)?,
+            fog_far: f32_at(data, 16)?,
+            rotation: [i32_at(20)?, i32_at(24)?],
+            fog_power: f32_at(data, 36).unwrap_or(1.0),
+            fog_far_color: rgb_at(data, 72),
+            fog_max: f32_at(data, 76).unwrap_or(1.0),
+            inherit: u32_at(data, 88).unwrap_or(0),
+        })
+    }
+
+    /// Takes the parts the inherit flags name from a template.
+    fn inherit(mut self, template: &LightingData) -> Self {
+        let inherit = self.inherit;
+        let inherits = |flag: u32| inherit & flag != 0;
+
+        if inherits(inherit_flags::AMBIENT_COLOR) {
+            self.ambient = template.ambient;
+        }
+
+        if inherits(inherit_flags::DIRECTIONAL_COLOR) {
+            self.directional = template.directional;
+        }
+
+        if inherits(inherit_flags::FOG_COLOR) {
+            self.fog_near_color = template.fog_near_color;
+            self.fog_far_color = template.fog_far_color;
+        }
+
+        if inherits(inherit_flags::FOG_NEAR) {
+            self.fog_near = template.fog_near;
+        }
+
+        if inherits(inherit_flags::FOG_FAR) {
+            self.fog_far = template.fog_far;
+        }
+
+        if inherits(inherit_flags::DIRECTIONAL_ROTATION) {
+            self.rotation = template.rotation;
+        }
+
+        if inherits(inherit_flags::FOG_POWER) {
+            self.fog_power = template.fog_power;
+        }
+
+        if inherits(inherit_flags::FOG_MAX) {
+            self.fog_max = template.fog_max;
+        }
+
+        self
+    }
+
+    fn lighting(&self) -> Lighting {
+        let (elevation, heading) = (self.rotation[0].to_radians(), self.rotation[1].to_radians());
+
+        Lighting {
+            ambient: self.ambient,
+            directional: self.directional,
+            direction: normalize([
+                heading.sin() * elevation.cos(),
+                heading.cos() * elevation.cos(),
+                elevation.sin(),
+            ]),
+            fog_near_color: self.fog_near_color,
+            fog_far_color: self.fog_far_color.unwrap_or(self.fog_near_color),
+            fog_near: self.fog_near,
+            fog_far: self.fog_far,
+            fog_power: self.fog_power,
+            fog_max: self.fog_max,
+            sky: self.fog_near_color,
+        }
+    }
+}
+
+/// The lighting of an interior cell: its XCLL, with the parts it inherits taken from its lighting template, or
+/// the template alone if the cell has no XCLL. `None` if the cell has neither.
+pub fn interior_lighting(load_order: &LoadOrder, cell: u32) -> Option<Lighting> {
+    let record = load_order.record(cell)?;
+    let own = record
+        .subrecord(*b"XCLL")
+        .and_then(|xcll| LightingData::parse(&xcll.data));
+    let template = record
+        .subrecord(*b"LTMP")
+        .and_then(|ltmp| ltmp.as_u32())
+        .and_then(|form_id| load_order.record(form_id))
+        .filter(|template| &template.code == b"LGTM")
+        .and_then(|template| template.subrecord(*b"DATA"))
+        .and_then(|data| LightingData::parse(&data.data));
+
+    let data = match (own, template) {
+        (Some(own), Some(template)) => own.inherit(&template),
+        (Some(own), None) => own,
+        (None, Some(template)) => template,
+        (None, None) => return None,
+    };
+
+    Some(data.lighting())
+}
+
+/// The colours and fog of a WTHR record.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Weather {
+    pub form_id: u32,
+    /// Each colour type from NAM0, at sunrise, day, sunset and night.
+    pub colors: Vec<[Rgb; 4]>,
+    /// Near and far fog distances, fog power and maximum fog by day, then the same by night.
+    pub day_fog: [f32; 4],
+    pub night_fog: [f32; 4],
+}
+
+impl Weather {
+    pub fn from_record(record: &Record) -> Option<Self> {
+        if &record.code != b"WTHR" {
+            return None;
+        }
+
+        let subrecords = record.subrecords().ok()?;
+        let find = |code: &[u8; 4]| subrecords.iter().find(|subrecord| &subrecord.code == code);
+
+        let colors = find(b"NAM0")
+            .map(|nam0| {
+                nam0.data
+                    .chunks_exact(16)
+                    .map(|times| {
+                        let time = |index: usize| [times[index * 4], times[index * 4 + 1], times[index * 4 + 2]];
+                        [time(SUNRISE), time(DAY), time(SUNSET), time(NIGHT)]
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let fog = |indices: [usize; 4]| {
+            let fnam = find(b"FNAM").map(|fnam| &fnam.data[..]).unwrap_or(&[]);
+            let value = |index: usize, default: f32| f32_at(fnam, index * 4).unwrap_or(default);
+            [
+                value(indices[0], 0.0),
+                value(indices[1], 0.0),
+                value(indices[2], 1.0),
+                value(indices[3], 1.0),
+            ]
+        };
+
+        Some(Self {
+            form_id: record.form_id,
+            colors,
+            // FNAM holds day near, day far, night near, night far, day power, night power, day max, night max.
+            day_fog: fog([0, 1, 4, 6]),
+            night_fog: fog([2, 3, 5, 7]),
+        })
+    }
+
+    fn color(&self, index: usize, weights: [f32; 4]) -> Rgb {
+        self.colors
+            .get(index)
+            .map(|colors| mix(colors, weights))
+            .unwrap_or([128; 3])
+    }
+
+    /// The weather's lighting at a time of day.
+    pub fn lighting(&self, climate: &Climate, hour: f32) -> Lighting {
+        let weights = climate.time_weights(hour);
+        let night = weights[NIGHT];
+        let fog = |index: usize| self.day_fog[index] * (1.0 - night) + self.night_fog[index] * night;
+
+        Lighting {
+            ambient: self.color(AMBIENT, weights),
+            directional: self.color(SUNLIGHT, weights),
+            direction: climate.sun_direction(hour),
+            fog_near_color: self.color(FOG_NEAR, weights),
+            fog_far_color: self.color(FOG_FAR, weights),
+            fog_near: fog(0),
+            fog_far: fog(1),
+            fog_power: fog(2),
+            fog_max: fog(3),
+            sky: if self.colors.len() > HORIZON {
+                self.color(HORIZON, weights)
+            } else {
+                self.color(SKY_UPPER, weights)
+            },
+        }
+    }
+}
+
+/// The weathers and sun times of a CLMT record.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Climate {
+    pub form_id: u32,
+    /// Weathers with their chances.
+    pub weathers: Vec<(u32, i32)>,
+    /// When sunrise begins and ends and sunset begins and ends, in hours.
+    pub sun_times: [f32; 4],
+}
+
+impl Default for Climate {
+    fn default() -> Self {
+        Self {
+            form_id: 0,
+            weathers: vec![],
+            sun_times: DEFAULT_SUN_TIMES,
+        }
+    }
+}
+
+impl Climate {
+    pub fn from_record(record: &Record) -> Option<Self> {
+        if &record.code != b"CLMT" {
+            return None;
+        }
+
+        let subrecords = record.subrecords().ok()?;
+        let find = |code: &[u8; 4]| subrecords.iter().find(|subrecord| &subrecord.code == code);
+
+        // Entries gained a global for the chance in later games, growing from 8 bytes to 12.
+        let weathers = find(b"WLST")
+            .map(|wlst: &Subrecord| {
+                let size = match wlst.data.len() % 12 {
+                    0 => 12,
+                    _ => 8,
+                };
+                wlst.data
+                    .chunks_exact(size)
+                    .filter_map(|entry| Some((u32_at(entry, 0)?, u32_at(entry, 4)? as i32)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // TNAM times are in units of ten minutes.
+        let sun_times = match find(b"TNAM").map(|tnam| &tnam.data[..]) {
+            Some([a, b, c, d, ..]) => [*a as f32 / 6.0, *b as f32 / 6.0, *c as f32 / 6.0, *d as f32 / 6.0],
+            _ => DEFAULT_SUN_TIMES,
+        };
+
+        Some(Self {
+            form_id: record.form_id,
+            weathers,
+            sun_times,
+        })
+    }
+
+    /// The weather with the highest chance.
+    pub fn likeliest_weather(&self) -> Option<u32> {
+        self.weathers
+            .iter()
+            .max_by_key(|(_, chance)| *chance)
+            .map(|(weather, _)| *weather)
+    }
+
+    /// How much each of sunrise, day, sunset and night contributes at an hour. Night gives way to sunrise over the
+    /// first half of sunrise and sunrise to day over the second, and likewise for sunset.
+    pub fn time_weights(&self, hour: f32) -> [f32; 4] {
+        let [sunrise_begin, sunrise_end, sunset_begin, sunset_end] = self.sun_times;
+        let keys = [
+            (sunrise_begin, NIGHT),
+            ((sunrise_begin + sunrise_end) / 2.0, SUNRISE),
+            (sunrise_end, DAY),
+            (sunset_begin, DAY),
+            ((sunset_begin + sunset_end) / 2.0, SUNSET),
+            (sunset_end, NIGHT),
+        ];
+        let mut weights = [0.0; 4];
+
+        for pair in keys.windows(2) {
+            let ((start, from), (end, to)) = (pair[0], pair[1]);
+
+            if hour >= start && hour < end {
+                let t = (hour - start) / (end - start);
+                weights[from] += 1.0 - t;
+                weights[to] += t;
+                return weights;
+            }
+        }
+
+        weights[NIGHT] = 1.0;
+        weights
+    }
+
+    /// A unit vector pointing towards the sun in game axes. The sun rises in the east and sets in the west,
+    /// passing a little to the south; at night the moon lights the scene from high above.
+    pub fn sun_direction(&self, hour: f32) -> [f32; 3] {
+        let [sunrise_begin, _, _, sunset_end] = self.sun_times;
+
+        if hour <= sunrise_begin || hour >= sunset_end || sunset_end <= sunrise_begin {
+            return normalize([0.3, -0.3, 1.0]);
+        }
+
+        let angle = PI * (hour - sunrise_begin) / (sunset_end - sunrise_begin);
+        normalize([angle.cos(), -0.3, angle.sin()])
+    }
+}
+
+/// The light a LIGH record gives off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightBase {
+    /// Radius in game units.
+    pub radius: f32,
+    pub color: Rgb,
+    pub flags: u32,
+    pub falloff: f32,
+    /// A multiplier on the light's brightness, from FNAM.
+    pub fade: f32,
+}
+
+impl LightBase {
+    pub fn from_record(record: &Record) -> Option<Self> {
+        if &record.code != b"LIGH" {
+            return None;
+        }
+
+        let data = record.subrecord(*b"DATA")?;
+        let data = &data.data;
+
+        Some(Self {
+            radius: u32_at(data, 4)? as f32,
+            color: rgb_at(data, 8)?,
+            flags: u32_at(data, 12).unwrap_or(0),
+            falloff: f32_at(data, 16).unwrap_or(1.0),
+            fade: record.subrecord(*b"FNAM").and_then(|fnam| fnam.as_f32()).unwrap_or(1.0),
+        })
+    }
+
+    /// Whether the light adds light to the scene when the game starts.
+    pub fn is_lit(&self) -> bool {
+        self.flags & (light_flags::NEGATIVE | light_flags::OFF_BY_DEFAULT) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esp::{group_types, Plugin};
+
+    fn lighting_data(ambient: u8, fog_near: f32, inherit: u32) -> Vec<u8> {
+        let mut data = vec![ambient, ambient, ambient, 0, 200, 190, 180, 0, 10, 20, 30, 0];
+        data.extend_from_slice(&fog_near.to_le_bytes());
+        data.extend_from_slice(&8000.0f32.to_le_bytes());
+        data.extend_from_slice(&90i32.to_le_bytes());
+        data.extend_from_slice(&0i32.to_le_bytes());
+        data.resize(88, 0);
+        data.extend_from_slice(&inherit.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn interior_lighting() {
+        const CELL: u32 = 0x0000_1000;
+        const TEMPLATE: u32 = 0x0000_2000;
+
+        let template = Record::new(
+            *b"LGTM",
+            TEMPLATE,
+            &[Subrecord::new(*b"DATA", lighting_data(40, 500.0, 0)[..88].to_vec())],
+        );
+        let cell = Record::new(
+            *b"CELL",
+            CELL,
+            &[
+                Subrecord::new(*b"XCLL", lighting_data(90, 100.0, inherit_flags::FOG_NEAR)),
+                Subrecord::new(*b"LTMP", TEMPLATE.to_le_bytes().to_vec()),
+            ],
+        );
+
+        let mut plugin = Plugin::new("Master.esm", &[]);
+        plugin.add_record(&[(*b"LGTM", group_types::TOP)], template, &mut |_| None);
+        plugin.add_record(&[(*b"CELL", group_types::TOP)], cell, &mut |_| None);
+        let load_order = LoadOrder::new(vec![plugin], None);
+
+        let lighting = super::interior_lighting(&load_order, CELL).unwrap();
+        assert_eq!(lighting.ambient, [90; 3]);
+        assert_eq!(lighting.fog_near, 500.0);
+        assert_eq!(lighting.fog_far, 8000.0);
+        assert_eq!(lighting.directional, [200, 190, 180]);
+        // An elevation of 90 degrees points straight up.
+        assert!((lighting.direction[2] - 1.0).abs() < 1e-5);
+
+        assert!(super::interior_lighting(&load_order, TEMPLATE).is_none());
+    }
+
+    #[test]
+    fn weather_lighting() {
+        // Only the ambient colour varies, being 0, 100, 200 and 40 at sunrise, day, sunset and night.
+        let mut nam0 = vec![0; 13 * 16];
+        for (time, value) in [0u8, 100, 200, 40].iter().enumerate() {
+            for channel in 0..3 {
+                nam0[AMBIENT * 16 + time * 4 + channel] = *value;
+            }
+        }
+
+        let mut fnam = vec![];
+        for value in &[1000.0f32, 50000.0, 200.0, 10000.0, 1.0, 2.0, 1.0, 0.5] {
+            fnam.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let weather = Record::new(
+            *b"WTHR",
+            0x0000_3000,
+            &[Subrecord::new(*b"NAM0", nam0), Subrecord::new(*b"FNAM", fnam)],
+        );
+        let weather = Weather::from_record(&weather).unwrap();
+
+        let mut wlst = vec![];
+        for (form_id, chance) in &[(0x0000_3000u32, 20i32), (0x0000_3001, 80)] {
+            wlst.extend_from_slice(&form_id.to_le_bytes());
+            wlst.extend_from_slice(&chance.to_le_bytes());
+            wlst.extend_from_slice(&0u32.to_le_bytes());
+        }
+
+        let climate = Record::new(
+            *b"CLMT",
+            0x0000_4000,
+            &[
+                Subrecord::new(*b"WLST", wlst),
+                Subrecord::new(*b"TNAM", vec![30, 42, 108, 120, 0, 0]),
+            ],
+        );
+        let climate = Climate::from_record(&climate).unwrap();
+        assert_eq!(climate.sun_times, [5.0, 7.0, 18.0, 20.0]);
+        assert_eq!(climate.likeliest_weather(), Some(0x0000_3001));
+
+        assert_eq!(weather.lighting(&climate, 12.0).ambient, [100; 3]);
+        assert_eq!(weather.lighting(&climate, 6.0).ambient, [0; 3]);
+        assert_eq!(weather.lighting(&climate, 19.0).ambient, [200; 3]);
+        assert_eq!(weather.lighting(&climate, 23.0).ambient, [40; 3]);
+        // Halfway between the end of day and the middle of sunset.
+        assert_eq!(weather.lighting(&climate, 18.5).ambient, [150; 3]);
+
+        let noon = weather.lighting(&climate, 12.5);
+        assert_eq!((noon.fog_near, noon.fog_far), (1000.0, 50000.0));
+        assert!(noon.direction[2] > 0.9);
+        assert_eq!(weather.lighting(&climate, 2.0).fog_far, 10000.0);
+    }
+
+    #[test]
+    fn light_base() {
+        let mut data = 0i32.to_le_bytes().to_vec();
+        data.extend_from_slice(&512u32.to_le_bytes());
+        data.extend_from_slice(&[255, 180, 90, 0]);
+        data.extend_from_slice(&light_flags::OFF_BY_DEFAULT.to_le_bytes());
+        data.extend_from_slice(&1.5f32.to_le_bytes());
+
+        let record = Record::new(*b"LIGH", 0x0000_5000, &[Subrecord::new(*b"DATA", data)]);
+        let light = LightBase::from_record(&record).unwrap();
+        assert_eq!(light.radius, 512.0);
+        assert_eq!(light.color, [255, 180, 90]);
+        assert_eq!(light.falloff, 1.5);
+        assert_eq!(light.fade, 1.0);
+        assert!(!light.is_lit());
+    }
+}
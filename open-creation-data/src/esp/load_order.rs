@@ -0,0 +1,662 @@
+use super::{
+    form_ids, group_types, invalid, plugin::has_owner, Cell, Code, Placement, Plugin, Record, StringTable, Subrecord,
+    Text, MAX_PLUGINS, REFERENCE_CODES,
+};
+use crate::vfs::VirtualFileSystem;
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// The plugins loaded into the editor, lowest priority first, and the one edits are written to.
+#[derive(Default)]
+pub struct LoadOrder {
+    plugins: Vec<Plugin>,
+    active: Option<usize>,
+    /// The string tables of localized plugins, by their index in the load order.
+    strings: HashMap<usize, StringTable>,
+    /// Whether the active plugin has been edited since it was loaded or saved.
+    modified: bool,
+}
+
+impl LoadOrder {
+    pub fn new(plugins: Vec<Plugin>, active: Option<usize>) -> Self {
+        Self {
+            plugins,
+            active,
+            strings: HashMap::new(),
+            modified: false,
+        }
+    }
+
+    /// Loads each plugin from the data folder, skipping any that fail to load or whose masters are not loaded
+    /// before them. Form IDs are translated into the load order's numbering. An active plugin missing from
+    /// `names` is loaded last from the data folder, or created with every other loaded plugin as a master if it
+    /// does not exist yet.
+    pub fn load(data_path: &Path, names: &[String], active: Option<&str>) -> Self {
+        let mut load_order = Self::default();
+
+        for name in names {
+            let loaded = Plugin::open(&data_path.join(name)).and_then(|plugin| load_order.push(plugin));
+
+            match loaded {
+                Ok(records) => log::info!("Loaded {} ({} records)", name, records),
+                Err(e) => log::error!("Error loading plugin {}: {}", name, e),
+            }
+        }
+
+        if let Some(active) = active {
+            let existing = load_order
+                .plugins
+                .iter()
+                .position(|plugin| plugin.name.eq_ignore_ascii_case(active));
+
+            load_order.active = match existing {
+                Some(index) => Some(index),
+                None => load_order.load_active(data_path, active),
+            };
+        }
+
+        load_order
+    }
+
+    /// Loads the active plugin from the data folder after the others, or creates it if there is no such file. An
+    /// active plugin that exists but fails to load is left out rather than replaced with an empty one, which
+    /// saving would write over the file.
+    fn load_active(&mut self, data_path: &Path, name: &str) -> Option<usize> {
+        let path = data_path.join(name);
+
+        let loaded = if path.exists() {
+            Plugin::open(&path).and_then(|plugin| self.push(plugin)).map(|records| {
+                log::info!("Loaded {} ({} records)", name, records);
+                self.plugins.len() - 1
+            })
+        } else {
+            self.add_plugin(name)
+        };
+
+        match loaded {
+            Ok(index) => Some(index),
+            Err(e) => {
+                log::error!("Error loading the active plugin {}: {}", name, e);
+                None
+            }
+        }
+    }
+
+    /// Adds a plugin read from its file to the end of the load order, translating its form IDs into the load
+    /// order's numbering through its masters. Fails if a master is not loaded, as the game would, or if the load
+    /// order is full. Returns the plugin's number of records.
+    fn push(&mut self, mut plugin: Plugin) -> io::Result<usize> {
+        self.check_room()?;
+        let mut indices = vec![];
+
+        for master in plugin.masters() {
+            let index = self
+                .plugins
+                .iter()
+                .position(|loaded| loaded.name.eq_ignore_ascii_case(&master))
+                .ok_or_else(|| invalid(&format!("its master {} is not loaded before it", master)))?;
+            indices.push(index as u8);
+        }
+
+        indices.push(self.plugins.len() as u8);
+        plugin.number_by_load_order(indices);
+        self.plugins.push(plugin);
+        Ok(self.plugins[self.plugins.len() - 1].records.len())
+    }
+
+    /// Creates a new, empty plugin at the end of the load order, returning its index. Fails if the load order is
+    /// full.
+    pub fn add_plugin(&mut self, name: &str) -> io::Result<usize> {
+        self.check_room()?;
+        let masters: Vec<String> = self.plugins.iter().map(|plugin| plugin.name.clone()).collect();
+        self.plugins.push(Plugin::new(name, &masters));
+        Ok(self.plugins.len() - 1)
+    }
+
+    fn check_room(&self) -> io::Result<()> {
+        if self.plugins.len() >= MAX_PLUGINS {
+            return Err(invalid(&format!("the load order is full at {} plugins", MAX_PLUGINS)));
+        }
+
+        Ok(())
+    }
+
+    pub fn plugins(&self) -> &[Plugin] {
+        &self.plugins
+    }
+
+    pub fn plugin_names(&self) -> Vec<String> {
+        self.plugins.iter().map(|plugin| plugin.name.clone()).collect()
+    }
+
+    pub fn active_index(&self) -> Option<usize> {
+        self.active
+    }
+
+    pub fn active(&self) -> Option<&Plugin> {
+        self.active.map(|index| &self.plugins[index])
+    }
+
+    /// The active plugin, to edit. Borrowing it, or a record from [`Self::override_record`], marks it modified.
+    pub fn active_mut(&mut self) -> Option<&mut Plugin> {
+        match self.active {
+            Some(index) => {
+                self.modified = true;
+                Some(&mut self.plugins[index])
+            }
+            None => None,
+        }
+    }
+
+    /// Whether the active plugin has been edited since it was loaded or last saved.
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Writes the active plugin to its file in the data folder, returning the file's path. Fails without an active
+    /// plugin.
+    pub fn save_active(&mut self, data_path: &Path) -> io::Result<PathBuf> {
+        let active = self
+            .active()
+            .ok_or_else(|| invalid("there is no active plugin to save"))?;
+        let path = data_path.join(&active.name);
+        active.save(&path)?;
+
+        self.modified = false;
+        Ok(path)
+    }
+
+    /// The winning version of a record: the one from the last plugin that contains it.
+    pub fn record(&self, form_id: u32) -> Option<&Record> {
+        self.plugins.iter().rev().find_map(|plugin| plugin.record(form_id))
+    }
+
+    /// Whether the winning version of a record comes from a localized plugin, whose text fields hold string
+    /// table IDs rather than text.
+    pub fn is_localized(&self, form_id: u32) -> bool {
+        self.plugins
+            .iter()
+            .rev()
+            .find(|plugin| plugin.contains(form_id))
+            .map(|plugin| plugin.is_localized())
+            .unwrap_or(false)
+    }
+
+    /// Reads the string tables of every localized plugin in a language, such as `English`, skipping plugins
+    /// whose tables are missing.
+    pub fn load_strings(&mut self, vfs: &VirtualFileSystem, language: &str) {
+        for (index, plugin) in self.plugins.iter().enumerate() {
+            if !plugin.is_localized() {
+                continue;
+            }
+
+            match StringTable::load(vfs, &plugin.name, language) {
+                Ok(strings) => {
+                    log::info!("Loaded {} strings for {}", strings.len(), plugin.name);
+                    self.strings.insert(index, strings);
+                }
+                Err(e) => log::error!("Error loading the {} string tables of {}: {}", language, plugin.name, e),
+            }
+        }
+    }
+
+    /// Replaces the string table IDs in a record's text fields with their text, from the string tables of the
+    /// plugin the winning version of the record comes from. IDs missing from the tables are kept.
+    pub fn resolve_texts<'a>(&self, form_id: u32, texts: impl IntoIterator<Item = &'a mut Text>) {
+        let winner = self.plugins.iter().rposition(|plugin| plugin.contains(form_id));
+
+        if let Some(strings) = winner.and_then(|winner| self.strings.get(&winner)) {
+            for text in texts {
+                text.resolve(strings);
+            }
+        }
+    }
+
+    /// Every version of a record, lowest priority first, with the index of the plugin it comes from.
+    pub fn overrides(&self, form_id: u32) -> Vec<(usize, &Record)> {
+        self.plugins
+            .iter()
+            .enumerate()
+            .filter_map(|(index, plugin)| plugin.record(form_id).map(|record| (index, record)))
+            .collect()
+    }
+
+    /// The winning version of every record with the given code.
+    pub fn records_by_code(&self, code: Code) -> Vec<&Record> {
+        let mut seen = HashSet::new();
+        let mut records = vec![];
+
+        for plugin in self.plugins.iter().rev() {
+            for record in plugin.records.iter().filter(|record| record.code == code) {
+                if seen.insert(record.form_id) {
+                    records.push(record);
+                }
+            }
+        }
+
+        records.reverse();
+        records
+    }
+
+    pub fn find_by_editor_id(&self, code: Code, editor_id: &str) -> Option<&Record> {
+        self.records_by_code(code).into_iter().find(|record| {
+            record
+                .editor_id()
+                .map(|id| id.eq_ignore_ascii_case(editor_id))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Gives the active plugin its own copy of a record to edit, copying the winning version into the same
+    /// groups. Records those groups belong to, like a reference's CELL and WRLD, are copied too when the active
+    /// plugin does not override them yet. Returns `None` without an active plugin or if the record is not found.
+    pub fn override_record(&mut self, form_id: u32) -> Option<&mut Record> {
+        let active = self.active?;
+
+        if self.plugins[active].contains(form_id) {
+            self.modified = true;
+            return self.plugins[active].record_mut(form_id);
+        }
+
+        let winner = self.plugins.iter().rposition(|plugin| plugin.contains(form_id))?;
+        let path = self.plugins[winner].group_path(form_id)?;
+        let record = self.plugins[winner].record(form_id)?.clone();
+
+        self.add_to_active(&path, record)
+    }
+
+    /// Places a new reference to `base` in a cell of the active plugin, with a form ID from that plugin.
+    /// Actors are placed as ACHR records and everything else as REFR. Returns the new reference's form ID, or
+    /// `None` without an active plugin or if the cell is not found.
+    pub fn add_reference(&mut self, cell: u32, base: u32, placement: &Placement) -> Option<u32> {
+        let code = match self.record(base)?.code {
+            code if &code == b"NPC_" => *b"ACHR",
+            _ => *b"REFR",
+        };
+
+        let winner = self.plugins.iter().rposition(|plugin| plugin.contains(cell))?;
+        let mut path = self.plugins[winner].group_path(cell)?;
+        let label = cell.to_le_bytes();
+        path.push((label, group_types::CELL_CHILDREN));
+        path.push((label, group_types::CELL_TEMPORARY_CHILDREN));
+
+        let form_id = self.active_mut()?.next_form_id();
+        let mut record = Record::new(code, form_id, &[Subrecord::new(*b"NAME", base.to_le_bytes().to_vec())]);
+        placement.write(&mut record).ok()?;
+
+        self.add_to_active(&path, record)?;
+        Some(form_id)
+    }
+
+    /// Adds a new top-level record to the active plugin, with a form ID from that plugin. Returns the new form ID,
+    /// or `None` without an active plugin.
+    pub fn add_record(&mut self, code: Code, subrecords: &[Subrecord]) -> Option<u32> {
+        let form_id = self.active_mut()?.next_form_id();
+        self.add_to_active(&[(code, group_types::TOP)], Record::new(code, form_id, subrecords))?;
+        Some(form_id)
+    }
+
+    /// Adds a new INFO to a topic in the active plugin, with a form ID from that plugin. Returns the new form ID,
+    /// or `None` without an active plugin or if the topic is not found.
+    pub fn add_topic_info(&mut self, topic: u32, subrecords: &[Subrecord]) -> Option<u32> {
+        let winner = self.plugins.iter().rposition(|plugin| plugin.contains(topic))?;
+        let mut path = self.plugins[winner].group_path(topic)?;
+        path.push((topic.to_le_bytes(), group_types::TOPIC_CHILDREN));
+
+        let form_id = self.active_mut()?.next_form_id();
+        self.add_to_active(&path, Record::new(*b"INFO", form_id, subrecords))?;
+        Some(form_id)
+    }
+
+    /// Adds a record to the active plugin within the groups on `path`, copying the winning versions of the
+    /// records those groups belong to.
+    fn add_to_active(&mut self, path: &[([u8; 4], i32)], record: Record) -> Option<&mut Record> {
+        let active = self.active?;
+
+        let owners: HashMap<u32, Record> = path
+            .iter()
+            .filter(|(_, group_type)| has_owner(*group_type))
+            .map(|(label, _)| u32::from_le_bytes(*label))
+            .filter_map(|owner| self.record(owner).map(|record| (owner, record.clone())))
+            .collect();
+
+        // The active plugin needs every plugin the records belong to or refer to as a master.
+        let mut indices = BTreeSet::new();
+        for record in owners.values().chain(Some(&record)) {
+            form_ids::plugin_indices(record, &mut indices);
+        }
+
+        for index in indices {
+            if let Some(master) = self.plugins.get(index as usize).filter(|_| index as usize != active) {
+                let name = master.name.clone();
+                self.plugins[active].add_master(&name, index);
+            }
+        }
+
+        self.modified = true;
+        let plugin = &mut self.plugins[active];
+        Some(plugin.add_record(path, record, &mut |owner| owners.get(&owner).cloned()))
+    }
+
+    pub fn editor_id(&self, form_id: u32) -> Option<String> {
+        self.record(form_id).and_then(|record| record.editor_id())
+    }
+
+    /// The winning version of every cell that is not deleted, sorted by form ID.
+    pub fn cells(&self) -> Vec<Cell> {
+        let mut seen = HashSet::new();
+        let mut cells = vec![];
+
+        for plugin in self.plugins.iter().rev() {
+            for (world, record) in plugin.cells() {
+                if seen.insert(record.form_id) && !record.is_deleted() {
+                    cells.push(Cell::from_record(record, world));
+                }
+            }
+        }
+
+        cells.sort_by_key(|cell| cell.form_id);
+        cells
+    }
+
+    /// The winning version of every reference placed in a cell, skipping deleted ones.
+    ///
+    /// A plugin can move a reference to another cell by overriding it there, so a reference only counts if the
+    /// last plugin containing it also has it in this cell.
+    pub fn cell_references(&self, cell: u32) -> Vec<&Record> {
+        self.cell_children(cell, &REFERENCE_CODES)
+    }
+
+    /// The winning version of every navmesh in a cell, skipping deleted ones.
+    pub fn cell_navmeshes(&self, cell: u32) -> Vec<&Record> {
+        self.cell_children(cell, &[*b"NAVM"])
+    }
+
+    /// The winning records with the given codes in a cell's children groups, as for [`Self::cell_references`].
+    fn cell_children(&self, cell: u32, codes: &[Code]) -> Vec<&Record> {
+        let per_plugin: Vec<HashSet<u32>> = self
+            .plugins
+            .iter()
+            .map(|plugin| {
+                plugin
+                    .cell_references(cell)
+                    .iter()
+                    .filter(|record| codes.contains(&record.code))
+                    .map(|record| record.form_id)
+                    .collect()
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        let mut children = vec![];
+
+        for form_ids in &per_plugin {
+            for &form_id in form_ids {
+                if !seen.insert(form_id) {
+                    continue;
+                }
+
+                let winner = self.plugins.iter().rposition(|plugin| plugin.contains(form_id));
+
+                if let Some(winner) = winner {
+                    let record = self.plugins[winner].record(form_id);
+
+                    match record {
+                        Some(record) if per_plugin[winner].contains(&form_id) && !record.is_deleted() => {
+                            children.push(record)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        children.sort_by_key(|record| record.form_id);
+        children
+    }
+
+    /// The winning version of every INFO of the given topics, keyed by topic, skipping deleted ones. An INFO
+    /// belongs to the topic the last plugin containing it puts it under.
+    pub fn topic_infos(&self, topics: &HashSet<u32>) -> HashMap<u32, Vec<&Record>> {
+        let mut winners: HashMap<u32, (u32, &Record)> = HashMap::new();
+
+        for plugin in &self.plugins {
+            for (topic, record) in plugin.topic_children() {
+                if &record.code == b"INFO" && topics.contains(&topic) {
+                    winners.insert(record.form_id, (topic, record));
+                }
+            }
+        }
+
+        let mut infos: HashMap<u32, Vec<&Record>> = HashMap::new();
+
+        for (topic, record) in winners.into_values() {
+            if !record.is_deleted() {
+                infos.entry(topic).or_default().push(record);
+            }
+        }
+
+        for records in infos.values_mut() {
+            records.sort_by_key(|record| record.form_id);
+        }
+
+        infos
+    }
+
+    /// The winning LAND record of an exterior cell, unless it is deleted.
+    pub fn cell_land(&self, cell: u32) -> Option<&Record> {
+        self.plugins
+            .iter()
+            .rev()
+            .find_map(|plugin| {
+                plugin
+                    .cell_references(cell)
+                    .into_iter()
+                    .find(|record| &record.code == b"LAND")
+            })
+            .filter(|record| !record.is_deleted())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esp::Reference;
+
+    const CELL: u32 = 0x0000_1000;
+    const REFERENCE: u32 = 0x0000_1001;
+
+    fn master() -> Plugin {
+        let cell = Record::new(*b"CELL", CELL, &[Subrecord::new(*b"DATA", vec![1, 0])]);
+        let reference = Record::new(*b"REFR", REFERENCE, &[Subrecord::new(*b"NAME", vec![0, 0x0F, 0, 0])]);
+        let label = CELL.to_le_bytes();
+        let path = [
+            (*b"CELL", group_types::TOP),
+            ([0; 4], group_types::INTERIOR_CELL_BLOCK),
+            ([0; 4], group_types::INTERIOR_CELL_SUB_BLOCK),
+            (label, group_types::CELL_CHILDREN),
+            (label, group_types::CELL_TEMPORARY_CHILDREN),
+        ];
+
+        let mut plugin = Plugin::new("Master.esm", &[]);
+        plugin.add_record(&path, reference, &mut |_| Some(cell.clone()));
+        plugin
+    }
+
+    #[test]
+    fn override_record() {
+        let active = Plugin::new("Test.esp", &["Master.esm".to_string()]);
+        let mut load_order = LoadOrder::new(vec![master(), active], Some(1));
+
+        let record = load_order.override_record(REFERENCE).unwrap();
+        record.flags |= crate::esp::record_flags::INITIALLY_DISABLED;
+
+        let mut bytes = vec![];
+        load_order.active().unwrap().write(&mut bytes).unwrap();
+        let active = Plugin::parse("Test.esp", &bytes).unwrap();
+
+        assert!(active.contains(CELL));
+        assert_eq!(
+            active.group_path(REFERENCE),
+            load_order.plugins()[0].group_path(REFERENCE)
+        );
+
+        let references = load_order.cell_references(CELL);
+        assert_eq!(references.len(), 1);
+        assert_ne!(references[0].flags & crate::esp::record_flags::INITIALLY_DISABLED, 0);
+    }
+
+    #[test]
+    fn add_reference() {
+        let mut master = master();
+        let base = Record::new(*b"STAT", 0x0000_0F00, &[]);
+        master.add_record(&[(*b"STAT", group_types::TOP)], base, &mut |_| None);
+
+        let active = Plugin::new("Test.esp", &["Master.esm".to_string()]);
+        let mut load_order = LoadOrder::new(vec![master, active], Some(1));
+
+        let placement = Placement {
+            position: [1.0, 2.0, 3.0],
+            ..Placement::default()
+        };
+        let first = load_order.add_reference(CELL, 0x0000_0F00, &placement).unwrap();
+        let second = load_order.add_reference(CELL, 0x0000_0F00, &placement).unwrap();
+        assert_eq!(first, 0x0100_0800);
+        assert_eq!(second, 0x0100_0801);
+
+        let references = load_order.cell_references(CELL);
+        assert_eq!(references.len(), 3);
+
+        let reference = Reference::from_record(load_order.record(first).unwrap()).unwrap();
+        assert_eq!(reference.code, *b"REFR");
+        assert_eq!(reference.placement, placement);
+    }
+
+    fn lvlo(form_id: u32) -> Subrecord {
+        let mut data = vec![1, 0, 0, 0];
+        data.extend_from_slice(&form_id.to_le_bytes());
+        data.extend_from_slice(&[1, 0, 0, 0]);
+        Subrecord::new(*b"LVLO", data)
+    }
+
+    /// Writes a plugin and loads it back at the end of the load order.
+    fn reload(load_order: &mut LoadOrder, plugin: &Plugin) {
+        let mut bytes = vec![];
+        plugin.write(&mut bytes).unwrap();
+        load_order.push(Plugin::parse(&plugin.name, &bytes).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn translates_form_ids_through_masters() {
+        let mut load_order = LoadOrder::default();
+        reload(&mut load_order, &master());
+        reload(&mut load_order, &Plugin::new("First.esp", &["Master.esm".to_string()]));
+
+        // Second.esp skips First.esp, so its own records are 01 in the file but 02 in the load order.
+        let mut second = Plugin::new("Second.esp", &["Master.esm".to_string()]);
+        let list = Record::new(*b"LVLI", 0x0100_0900, &[lvlo(0x0000_1001), lvlo(0x0100_0800)]);
+        second.add_record(&[(*b"LVLI", group_types::TOP)], list, &mut |_| None);
+        reload(&mut load_order, &second);
+
+        let list = load_order.record(0x0200_0900).unwrap();
+        let entries: Vec<Subrecord> = list.subrecords().unwrap();
+        assert_eq!(entries, vec![lvlo(0x0000_1001), lvlo(0x0200_0800)]);
+
+        reload(&mut load_order, &Plugin::new("Test.esp", &["Master.esm".to_string()]));
+        load_order.active = Some(3);
+        load_order.override_record(0x0200_0900).unwrap();
+        let added = load_order.add_record(*b"STAT", &[]).unwrap();
+        assert_eq!(added, 0x0300_0800);
+
+        let active = load_order.active().unwrap();
+        assert_eq!(active.masters(), vec!["Master.esm", "Second.esp"]);
+
+        let mut bytes = vec![];
+        active.write(&mut bytes).unwrap();
+        let written = Plugin::parse("Test.esp", &bytes).unwrap();
+        assert_eq!(
+            written.record(0x0100_0900).unwrap().subrecords().unwrap(),
+            vec![lvlo(0x0000_1001), lvlo(0x0100_0800)]
+        );
+        assert!(written.contains(0x0200_0800));
+    }
+
+    #[test]
+    fn loads_an_unlisted_active_plugin_from_the_data_folder() {
+        let data_path = std::env::temp_dir().join(format!("open_creation_load_order_{}", std::process::id()));
+        std::fs::create_dir_all(&data_path).unwrap();
+
+        let mut active = Plugin::new("Mine.esp", &["Master.esm".to_string()]);
+        let record = Record::new(*b"STAT", 0x0100_0800, &[]);
+        active.add_record(&[(*b"STAT", group_types::TOP)], record, &mut |_| None);
+
+        let saved = master()
+            .save(&data_path.join("Master.esm"))
+            .and_then(|()| active.save(&data_path.join("Mine.esp")));
+        let existing = LoadOrder::load(&data_path, &["Master.esm".to_string()], Some("Mine.esp"));
+        let created = LoadOrder::load(&data_path, &["Master.esm".to_string()], Some("New.esp"));
+        let _ = std::fs::remove_dir_all(&data_path);
+        saved.unwrap();
+
+        assert_eq!(existing.plugin_names(), vec!["Master.esm", "Mine.esp"]);
+        assert_eq!(existing.active_index(), Some(1));
+        assert!(existing.active().unwrap().contains(0x0100_0800));
+
+        assert_eq!(created.plugin_names(), vec!["Master.esm", "New.esp"]);
+        assert!(created.active().unwrap().records.is_empty());
+        assert_eq!(created.active().unwrap().masters(), vec!["Master.esm"]);
+    }
+
+    #[test]
+    fn refuses_more_plugins_than_form_ids_can_number() {
+        let mut load_order = LoadOrder::new(vec![master()], None);
+
+        for index in 1..MAX_PLUGINS {
+            load_order.add_plugin(&format!("Plugin{}.esp", index)).unwrap();
+        }
+
+        assert!(load_order.add_plugin("Overflow.esp").is_err());
+        assert!(load_order.push(Plugin::new("Overflow.esp", &[])).is_err());
+        assert_eq!(load_order.plugins().len(), MAX_PLUGINS);
+    }
+
+    #[test]
+    fn saves_edits_to_the_active_plugin() {
+        let data_path = std::env::temp_dir().join(format!("open_creation_save_{}", std::process::id()));
+        std::fs::create_dir_all(&data_path).unwrap();
+        let names = ["Master.esm".to_string()];
+
+        let saved = master().save(&data_path.join("Master.esm")).and_then(|()| {
+            let mut load_order = LoadOrder::load(&data_path, &names, Some("Test.esp"));
+            assert!(!load_order.is_modified());
+
+            let record = load_order.override_record(REFERENCE).unwrap();
+            record.flags |= crate::esp::record_flags::INITIALLY_DISABLED;
+            let added = load_order.add_record(*b"STAT", &[Subrecord::new(*b"EDID", b"Added\0".to_vec())]);
+            assert!(load_order.is_modified());
+
+            let path = load_order.save_active(&data_path)?;
+            assert_eq!(path, data_path.join("Test.esp"));
+            assert!(!load_order.is_modified());
+            Ok((load_order, added.unwrap()))
+        });
+        let reloaded = LoadOrder::load(&data_path, &names, Some("Test.esp"));
+        let _ = std::fs::remove_dir_all(&data_path);
+        let (edited, added) = saved.unwrap();
+
+        let contents = |load_order: &LoadOrder, form_id: u32| {
+            let record = load_order.record(form_id).unwrap();
+            (record.code, record.flags, record.subrecords().unwrap())
+        };
+        for &form_id in &[CELL, REFERENCE, added] {
+            assert_eq!(contents(&reloaded, form_id), contents(&edited, form_id));
+        }
+        assert_eq!(reloaded.active().unwrap().masters(), vec!["Master.esm"]);
+        assert_eq!(reloaded.cell_references(CELL).len(), 1);
+        assert!(!reloaded.is_modified());
+    }
+}
@@ -0,0 +1,80 @@
+//! Raw access to plugin files (`.esm`/`.esp`): groups, records and their subrecords.
+//!
+//! Records are kept as stored and only decompressed when their subrecords are read, so whole load orders can
+//! be held in memory. The exception is form IDs, which are held in the load order's numbering: their top byte is
+//! the position in the load order of the plugin they belong to. The `form_ids` module translates plugins whose
+//! masters are not exactly the plugins before them.
+
+pub mod cell;
+pub mod condition;
+pub mod condition_functions;
+pub mod dialogue;
+pub mod faction;
+mod fields;
+pub(crate) mod form_ids;
+pub mod land;
+pub mod leveled;
+pub mod lighting;
+pub mod load_order;
+pub mod navmesh;
+pub mod npc;
+pub mod plugin;
+pub mod quest;
+pub mod record;
+pub mod reference;
+pub mod text;
+pub mod vmad;
+
+pub use cell::{cell_flags, Cell};
+pub use condition::{condition_flags, Comparison, Condition, ConditionValue};
+pub use condition_functions::{condition_function, ConditionFunction, ParamType};
+pub use dialogue::{branch_flags, info_flags, Branch, Dialogue, Info, Response, Topic};
+pub use faction::{Faction, FactionGraph, FactionMember, FactionRelation, Reaction};
+pub use land::{land_flags, Land, LandLayer};
+pub use leveled::{leveled_flags, LeveledEntry, LeveledList, LEVELED_CODES, MAX_LEVELED_ENTRIES};
+pub use lighting::{interior_lighting, Climate, LightBase, Lighting, Weather};
+pub use load_order::LoadOrder;
+pub use navmesh::{Navmesh, NavmeshReport};
+pub use npc::{npc_flags, template_flags, AiData, InventoryItem, Npc, NpcFaction, NpcPerk, NpcStats, TemplateSource};
+pub use plugin::{Entry, Group, Plugin, MAX_PLUGINS};
+pub use quest::{Alias, AliasFill, LogEntry, Objective, ObjectiveTarget, Quest, Stage};
+pub use record::{Record, Subrecord};
+pub use reference::{Placement, Reference, PLACEABLE_CODES, REFERENCE_CODES};
+pub use text::{StringTable, Text};
+pub use vmad::{
+    AliasScripts, EventFragments, Fragment, Fragments, ObjectValue, PerkFragment, PerkFragments, Property,
+    PropertyValue, QuestFragments, Script, StageFragment, Vmad,
+};
+
+pub type Code = [u8; 4];
+
+pub mod group_types {
+    pub const TOP: i32 = 0;
+    pub const WORLD_CHILDREN: i32 = 1;
+    pub const INTERIOR_CELL_BLOCK: i32 = 2;
+    pub const INTERIOR_CELL_SUB_BLOCK: i32 = 3;
+    pub const EXTERIOR_CELL_BLOCK: i32 = 4;
+    pub const EXTERIOR_CELL_SUB_BLOCK: i32 = 5;
+    pub const CELL_CHILDREN: i32 = 6;
+    pub const TOPIC_CHILDREN: i32 = 7;
+    pub const CELL_PERSISTENT_CHILDREN: i32 = 8;
+    pub const CELL_TEMPORARY_CHILDREN: i32 = 9;
+}
+
+pub mod record_flags {
+    pub const MASTER: u32 = 0x0000_0001;
+    pub const DELETED: u32 = 0x0000_0020;
+    pub const LOCALIZED: u32 = 0x0000_0080;
+    pub const PERSISTENT: u32 = 0x0000_0400;
+    pub const INITIALLY_DISABLED: u32 = 0x0000_0800;
+    pub const COMPRESSED: u32 = 0x0004_0000;
+}
+
+/// Formats a record or subrecord code for display, e.g. `b"STAT"` -> `STAT`.
+pub fn code_name(code: Code) -> String {
+    String::from_utf8_lossy(&code).into_owned()
+}
+
+pub(crate) fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
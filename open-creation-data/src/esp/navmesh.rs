@@ -0,0 +1,629 @@
+use super::{
+    fields::{put_u32, Fields},
+    invalid, LoadOrder, Record, Subrecord,
+};
+
+use std::{collections::HashSet, io};
+
+/// Flags of a navmesh triangle.
+pub mod triangle_flags {
+    /// The triangle's first edge leads into another navmesh through an edge link.
+    pub const EDGE_LINK_0_1: u16 = 0x0001;
+    pub const EDGE_LINK_1_2: u16 = 0x0002;
+    pub const EDGE_LINK_2_0: u16 = 0x0004;
+    pub const NO_LARGE_CREATURES: u16 = 0x0010;
+    pub const OVERLAPPING: u16 = 0x0020;
+    pub const PREFERRED: u16 = 0x0040;
+    pub const WATER: u16 = 0x0200;
+    pub const DOOR: u16 = 0x0400;
+    pub const FOUND: u16 = 0x0800;
+}
+
+/// A navmesh triangle. Each edge runs from one of its vertices to the next, and holds the index of the triangle
+/// across it in this navmesh, or of an edge link if the edge's link flag is set. Negative edges lead nowhere.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Triangle {
+    pub vertices: [u16; 3],
+    pub edges: [i16; 3],
+    pub flags: u16,
+    /// Cover markings along the triangle's edges. Their individual bits are not decoded.
+    pub cover: u16,
+}
+
+impl Triangle {
+    /// Whether an edge leads into another navmesh through an edge link.
+    pub fn is_edge_link(&self, edge: usize) -> bool {
+        self.flags & (triangle_flags::EDGE_LINK_0_1 << edge) != 0
+    }
+
+    /// The triangle across an edge in the same navmesh.
+    pub fn neighbour(&self, edge: usize) -> Option<usize> {
+        if self.is_edge_link(edge) || self.edges[edge] < 0 {
+            None
+        } else {
+            Some(self.edges[edge] as usize)
+        }
+    }
+
+    /// The vertices at either end of an edge.
+    pub fn edge_vertices(&self, edge: usize) -> (u16, u16) {
+        (self.vertices[edge], self.vertices[(edge + 1) % 3])
+    }
+}
+
+/// A triangle edge's connection to a triangle in another navmesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EdgeLink {
+    pub kind: u32,
+    pub navmesh: u32,
+    pub triangle: i16,
+}
+
+/// A triangle that leads through a door, with the door's reference.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DoorTriangle {
+    pub triangle: i16,
+    pub kind: u32,
+    pub door: u32,
+}
+
+/// The cell a navmesh belongs to: an exterior grid position in a worldspace, or an interior cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NavmeshParent {
+    Exterior { world: u32, grid: (i16, i16) },
+    Interior { cell: u32 },
+}
+
+/// The geometry of a NAVM record, from its NVNM subrecord. Positions are in game units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Navmesh {
+    pub form_id: u32,
+    pub version: u32,
+    pub crc: u32,
+    pub parent: NavmeshParent,
+    pub vertices: Vec<[f32; 3]>,
+    pub triangles: Vec<Triangle>,
+    pub edge_links: Vec<EdgeLink>,
+    pub doors: Vec<DoorTriangle>,
+    pub cover_triangles: Vec<i16>,
+    /// How many cells the grid used to look up triangles by position has along each side.
+    pub grid_divisor: u32,
+    /// The triangles overlapping each grid cell.
+    pub grid: Vec<Vec<i16>>,
+}
+
+/// Problems found in a navmesh by [`Navmesh::validate`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NavmeshReport {
+    /// Groups of triangles connected to each other by shared edges, largest first. More than one means NPCs
+    /// cannot walk between them within this navmesh.
+    pub islands: Vec<Vec<usize>>,
+    pub problems: Vec<String>,
+}
+
+impl Navmesh {
+    pub fn from_record(record: &Record) -> io::Result<Self> {
+        let nvnm = record.subrecord(*b"NVNM").ok_or_else(|| invalid("NAVM has no NVNM"))?;
+        let mut fields = Fields::new(&nvnm.data, "NAVM NVNM");
+
+        let version = fields.u32()?;
+        let crc = fields.u32()?;
+        let world = fields.u32()?;
+        let parent = if world != 0 {
+            let y = fields.i16()?;
+            let x = fields.i16()?;
+            NavmeshParent::Exterior { world, grid: (x, y) }
+        } else {
+            NavmeshParent::Interior { cell: fields.u32()? }
+        };
+
+        let vertices = fields.array(|fields| Ok([fields.f32()?, fields.f32()?, fields.f32()?]))?;
+        let triangles = fields.array(|fields| {
+            Ok(Triangle {
+                vertices: [fields.u16()?, fields.u16()?, fields.u16()?],
+                edges: [fields.i16()?, fields.i16()?, fields.i16()?],
+                flags: fields.u16()?,
+                cover: fields.u16()?,
+            })
+        })?;
+        let edge_links = fields.array(|fields| {
+            Ok(EdgeLink {
+                kind: fields.u32()?,
+                navmesh: fields.u32()?,
+                triangle: fields.i16()?,
+            })
+        })?;
+        let doors = fields.array(|fields| {
+            Ok(DoorTriangle {
+                triangle: fields.i16()?,
+                kind: fields.u32()?,
+                door: fields.u32()?,
+            })
+        })?;
+        let cover_triangles = fields.array(Fields::i16)?;
+
+        let grid_divisor = fields.u32()?;
+        // The grid's cell size and bounds are worked out again when it is rebuilt.
+        fields.bytes(8 * 4)?;
+
+        let mut grid = vec![];
+        while !fields.is_empty() {
+            grid.push(fields.array(Fields::i16)?);
+        }
+
+        Ok(Self {
+            form_id: record.form_id,
+            version,
+            crc,
+            parent,
+            vertices,
+            triangles,
+            edge_links,
+            doors,
+            cover_triangles,
+            grid_divisor,
+            grid,
+        })
+    }
+
+    /// Writes the geometry into the record's NVNM subrecord, rebuilding the grid.
+    pub fn write(&self, record: &mut Record) -> io::Result<()> {
+        let mut data = vec![];
+        put_u32(&mut data, self.version);
+        put_u32(&mut data, self.crc);
+
+        match self.parent {
+            NavmeshParent::Exterior { world, grid: (x, y) } => {
+                put_u32(&mut data, world);
+                data.extend_from_slice(&y.to_le_bytes());
+                data.extend_from_slice(&x.to_le_bytes());
+            }
+            NavmeshParent::Interior { cell } => {
+                put_u32(&mut data, 0);
+                put_u32(&mut data, cell);
+            }
+        }
+
+        put_u32(&mut data, self.vertices.len() as u32);
+        for value in self.vertices.iter().flatten() {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        put_u32(&mut data, self.triangles.len() as u32);
+        for triangle in &self.triangles {
+            for vertex in &triangle.vertices {
+                data.extend_from_slice(&vertex.to_le_bytes());
+            }
+            for edge in &triangle.edges {
+                data.extend_from_slice(&edge.to_le_bytes());
+            }
+            data.extend_from_slice(&triangle.flags.to_le_bytes());
+            data.extend_from_slice(&triangle.cover.to_le_bytes());
+        }
+
+        put_u32(&mut data, self.edge_links.len() as u32);
+        for link in &self.edge_links {
+            put_u32(&mut data, link.kind);
+            put_u32(&mut data, link.navmesh);
+            data.extend_from_slice(&link.triangle.to_le_bytes());
+        }
+
+        put_u32(&mut data, self.doors.len() as u32);
+        for door in &self.doors {
+            data.extend_from_slice(&door.triangle.to_le_bytes());
+            put_u32(&mut data, door.kind);
+            put_u32(&mut data, door.door);
+        }
+
+        put_u32(&mut data, self.cover_triangles.len() as u32);
+        for triangle in &self.cover_triangles {
+            data.extend_from_slice(&triangle.to_le_bytes());
+        }
+
+        let (min, max) = self.bounds();
+        let divisor = self.grid_divisor.max(1);
+        let cell_size = [(max[0] - min[0]) / divisor as f32, (max[1] - min[1]) / divisor as f32];
+
+        put_u32(&mut data, divisor);
+        for value in cell_size.iter().chain(min.iter()).chain(max.iter()) {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        for cell in self.build_grid(divisor, min, cell_size) {
+            put_u32(&mut data, cell.len() as u32);
+            for triangle in cell {
+                data.extend_from_slice(&triangle.to_le_bytes());
+            }
+        }
+
+        let mut subrecords = record.subrecords()?;
+        let nvnm = Subrecord::new(*b"NVNM", data);
+
+        match subrecords.iter().position(|subrecord| &subrecord.code == b"NVNM") {
+            Some(position) => subrecords[position] = nvnm,
+            None => subrecords.push(nvnm),
+        }
+
+        record.set_subrecords(&subrecords);
+        Ok(())
+    }
+
+    /// The smallest and largest vertex coordinates.
+    pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        if self.vertices.is_empty() {
+            return ([0.0; 3], [0.0; 3]);
+        }
+
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+
+        for vertex in &self.vertices {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(vertex[axis]);
+                max[axis] = max[axis].max(vertex[axis]);
+            }
+        }
+
+        (min, max)
+    }
+
+    /// The triangles overlapping each grid cell, judged by their bounding boxes. Cells are assumed to be stored
+    /// row by row from the navmesh's south-west corner.
+    fn build_grid(&self, divisor: u32, min: [f32; 3], cell_size: [f32; 2]) -> Vec<Vec<i16>> {
+        let divisor = divisor as usize;
+        let mut grid = vec![vec![]; divisor * divisor];
+        let cell = |value: f32, axis: usize| {
+            if cell_size[axis] > 0.0 {
+                (((value - min[axis]) / cell_size[axis]) as usize).min(divisor - 1)
+            } else {
+                0
+            }
+        };
+
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            let corners: Vec<[f32; 3]> = triangle
+                .vertices
+                .iter()
+                .filter_map(|&vertex| self.vertices.get(vertex as usize).copied())
+                .collect();
+
+            if corners.is_empty() {
+                continue;
+            }
+
+            let low = |axis: usize| corners.iter().map(|corner| corner[axis]).fold(f32::MAX, f32::min);
+            let high = |axis: usize| corners.iter().map(|corner| corner[axis]).fold(f32::MIN, f32::max);
+
+            for y in cell(low(1), 1)..=cell(high(1), 1) {
+                for x in cell(low(0), 0)..=cell(high(0), 0) {
+                    grid[y * divisor + x].push(index as i16);
+                }
+            }
+        }
+
+        grid
+    }
+
+    /// Removes triangles, renumbering every reference to the triangles after them. Edges that led to a removed
+    /// triangle lead nowhere afterwards, and door and cover entries for removed triangles are dropped. Vertices
+    /// no triangle uses any more are kept.
+    pub fn delete_triangles(&mut self, removed: &HashSet<usize>) {
+        let mut new_index = vec![-1i16; self.triangles.len()];
+        let mut next = 0;
+
+        for (index, new) in new_index.iter_mut().enumerate() {
+            if !removed.contains(&index) {
+                *new = next;
+                next += 1;
+            }
+        }
+
+        let renumber = |triangle: i16| {
+            if triangle < 0 {
+                -1
+            } else {
+                new_index.get(triangle as usize).copied().unwrap_or(-1)
+            }
+        };
+
+        let mut triangles = vec![];
+
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            if removed.contains(&index) {
+                continue;
+            }
+
+            let mut triangle = *triangle;
+
+            for edge in 0..3 {
+                if !triangle.is_edge_link(edge) {
+                    triangle.edges[edge] = renumber(triangle.edges[edge]);
+                }
+            }
+
+            triangles.push(triangle);
+        }
+
+        self.triangles = triangles;
+
+        self.doors.retain(|door| renumber(door.triangle) >= 0);
+        for door in &mut self.doors {
+            door.triangle = renumber(door.triangle);
+        }
+
+        self.cover_triangles = self
+            .cover_triangles
+            .iter()
+            .map(|&triangle| renumber(triangle))
+            .filter(|&triangle| triangle >= 0)
+            .collect();
+    }
+
+    /// Adds a triangle between three vertices, connecting it to any triangles it shares an edge with. Returns its
+    /// index.
+    pub fn add_triangle(&mut self, vertices: [u16; 3]) -> usize {
+        let index = self.triangles.len();
+        let mut triangle = Triangle {
+            vertices,
+            edges: [-1; 3],
+            flags: 0,
+            cover: 0,
+        };
+
+        for edge in 0..3 {
+            let (start, end) = triangle.edge_vertices(edge);
+
+            for (other_index, other) in self.triangles.iter_mut().enumerate() {
+                let shared = (0..3).find(|&other_edge| {
+                    let (other_start, other_end) = other.edge_vertices(other_edge);
+                    (other_start, other_end) == (end, start) || (other_start, other_end) == (start, end)
+                });
+
+                if let Some(other_edge) = shared {
+                    if !other.is_edge_link(other_edge) {
+                        other.edges[other_edge] = index as i16;
+                        triangle.edges[edge] = other_index as i16;
+                    }
+                }
+            }
+        }
+
+        self.triangles.push(triangle);
+        index
+    }
+
+    /// Finds islands of connected triangles and problems with the navmesh's own references: vertices, edges,
+    /// edge links, doors and cover entries that point at nothing, and edges whose neighbour does not point back.
+    pub fn validate(&self) -> NavmeshReport {
+        let mut problems = vec![];
+        let triangle_count = self.triangles.len();
+
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            for &vertex in &triangle.vertices {
+                if vertex as usize >= self.vertices.len() {
+                    problems.push(format!("Triangle {} uses missing vertex {}", index, vertex));
+                }
+            }
+
+            for edge in 0..3 {
+                let target = triangle.edges[edge];
+
+                if triangle.is_edge_link(edge) {
+                    if target < 0 || target as usize >= self.edge_links.len() {
+                        problems.push(format!(
+                            "Triangle {} edge {} uses missing edge link {}",
+                            index, edge, target
+                        ));
+                    }
+                } else if let Some(neighbour) = triangle.neighbour(edge) {
+                    let points_back = self
+                        .triangles
+                        .get(neighbour)
+                        .map(|other| (0..3).any(|other_edge| other.neighbour(other_edge) == Some(index)))
+                        .unwrap_or(false);
+
+                    if neighbour >= triangle_count {
+                        problems.push(format!(
+                            "Triangle {} edge {} leads to missing triangle {}",
+                            index, edge, neighbour
+                        ));
+                    } else if !points_back {
+                        problems.push(format!(
+                            "Triangle {} edge {} leads to triangle {}, which does not lead back",
+                            index, edge, neighbour
+                        ));
+                    }
+                }
+            }
+        }
+
+        for door in &self.doors {
+            if door.triangle < 0 || door.triangle as usize >= triangle_count {
+                problems.push(format!(
+                    "Door {:08X} is on missing triangle {}",
+                    door.door, door.triangle
+                ));
+            }
+        }
+
+        for &triangle in &self.cover_triangles {
+            if triangle < 0 || triangle as usize >= triangle_count {
+                problems.push(format!("Cover is on missing triangle {}", triangle));
+            }
+        }
+
+        NavmeshReport {
+            islands: self.islands(),
+            problems,
+        }
+    }
+
+    fn islands(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.triangles.len()];
+        let mut islands = vec![];
+
+        for start in 0..self.triangles.len() {
+            if visited[start] {
+                continue;
+            }
+
+            visited[start] = true;
+            let mut island = vec![];
+            let mut stack = vec![start];
+
+            while let Some(index) = stack.pop() {
+                island.push(index);
+
+                for edge in 0..3 {
+                    if let Some(neighbour) = self.triangles[index].neighbour(edge) {
+                        if neighbour < visited.len() && !visited[neighbour] {
+                            visited[neighbour] = true;
+                            stack.push(neighbour);
+                        }
+                    }
+                }
+            }
+
+            island.sort_unstable();
+            islands.push(island);
+        }
+
+        islands.sort_by_key(|island| std::cmp::Reverse(island.len()));
+        islands
+    }
+
+    /// Problems with references to other records: edge links to navmeshes or triangles that do not exist, and
+    /// doors that are not placed references.
+    pub fn check_links(&self, load_order: &LoadOrder) -> Vec<String> {
+        let mut problems = vec![];
+
+        for (index, link) in self.edge_links.iter().enumerate() {
+            let other = match load_order.record(link.navmesh) {
+                Some(record) if &record.code == b"NAVM" && !record.is_deleted() => Navmesh::from_record(record),
+                _ => {
+                    problems.push(format!(
+                        "Edge link {} leads to missing navmesh {:08X}",
+                        index, link.navmesh
+                    ));
+                    continue;
+                }
+            };
+
+            match other {
+                Ok(other) if link.triangle >= 0 && (link.triangle as usize) < other.triangles.len() => {}
+                Ok(_) => problems.push(format!(
+                    "Edge link {} leads to missing triangle {} in navmesh {:08X}",
+                    index, link.triangle, link.navmesh
+                )),
+                Err(e) => problems.push(format!(
+                    "Edge link {} leads to navmesh {:08X}, which cannot be read: {}",
+                    index, link.navmesh, e
+                )),
+            }
+        }
+
+        for door in &self.doors {
+            let placed = load_order
+                .record(door.door)
+                .map(|record| &record.code == b"REFR" && !record.is_deleted())
+                .unwrap_or(false);
+
+            if !placed {
+                problems.push(format!(
+                    "Door triangle {} uses missing door {:08X}",
+                    door.triangle, door.door
+                ));
+            }
+        }
+
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two triangles sharing an edge, a third on its own, and a door on the first.
+    fn navmesh() -> Navmesh {
+        Navmesh {
+            form_id: 0x0000_2000,
+            version: 12,
+            crc: 0,
+            parent: NavmeshParent::Interior { cell: 0x0000_1000 },
+            vertices: vec![
+                [0.0, 0.0, 0.0],
+                [100.0, 0.0, 0.0],
+                [0.0, 100.0, 0.0],
+                [100.0, 100.0, 0.0],
+                [500.0, 500.0, 0.0],
+                [600.0, 500.0, 0.0],
+                [500.0, 600.0, 0.0],
+            ],
+            triangles: vec![
+                Triangle {
+                    vertices: [0, 1, 2],
+                    edges: [-1, 1, -1],
+                    flags: triangle_flags::DOOR,
+                    cover: 0,
+                },
+                Triangle {
+                    vertices: [1, 3, 2],
+                    edges: [-1, -1, 0],
+                    flags: 0,
+                    cover: 0,
+                },
+                Triangle {
+                    vertices: [4, 5, 6],
+                    edges: [-1, -1, -1],
+                    flags: 0,
+                    cover: 0x0003,
+                },
+            ],
+            edge_links: vec![],
+            doors: vec![DoorTriangle {
+                triangle: 0,
+                kind: 0,
+                door: 0x0000_3000,
+            }],
+            cover_triangles: vec![2],
+            grid_divisor: 2,
+            grid: vec![],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut record = Record::new(*b"NAVM", 0x0000_2000, &[]);
+        let navmesh = navmesh();
+        navmesh.write(&mut record).unwrap();
+
+        let read = Navmesh::from_record(&record).unwrap();
+        assert_eq!(read.grid, vec![vec![0, 1], vec![], vec![], vec![2]]);
+        assert_eq!(Navmesh { grid: vec![], ..read }, navmesh);
+    }
+
+    #[test]
+    fn edit_and_validate() {
+        let mut navmesh = navmesh();
+        let report = navmesh.validate();
+        assert_eq!(report.islands, vec![vec![0, 1], vec![2]]);
+        assert!(report.problems.is_empty());
+
+        navmesh.delete_triangles(&[0].iter().cloned().collect());
+        assert_eq!(navmesh.triangles[0].edges, [-1, -1, -1]);
+        assert!(navmesh.doors.is_empty());
+        assert_eq!(navmesh.cover_triangles, vec![1]);
+
+        // Rebuilding the triangle reconnects it to its old neighbour, which is now triangle 0.
+        let index = navmesh.add_triangle([0, 1, 2]);
+        assert_eq!(index, 2);
+        assert_eq!(navmesh.triangles[2].edges, [-1, 0, -1]);
+        assert_eq!(navmesh.triangles[0].edges, [-1, -1, 2]);
+
+        navmesh.triangles[1].edges[0] = 0;
+        let report = navmesh.validate();
+        assert_eq!(report.islands.len(), 2);
+        assert_eq!(report.problems.len(), 1);
+    }
+}
@@ -0,0 +1,682 @@
+//! Actors from NPC_ records: their traits, stats, factions, inventory, spells and AI.
+//!
+//! NPC_ records hold many subrecords the editor does not decode, such as face data and models. The record is
+//! kept as read and written back with the decoded subrecords replaced where they were found, or, for those the
+//! record did not have, placed in the order the Creation Kit writes them.
+
+use super::{
+    fields::{put_u16, put_u32, Fields},
+    invalid, Code, Record, Subrecord, Text,
+};
+
+use std::io;
+
+/// Templates inheriting from templates are followed this far, in case of a loop.
+const MAX_TEMPLATE_DEPTH: usize = 16;
+
+/// NPC_ subrecords in the order the Creation Kit writes them.
+const ORDER: [Code; 64] = [
+    *b"EDID", *b"VMAD", *b"OBND", *b"ACBS", *b"SNAM", *b"INAM", *b"VTCK", *b"TPLT", *b"RNAM", *b"SPCT", *b"SPLO",
+    *b"DEST", *b"DSTD", *b"DMDL", *b"DMDT", *b"DMDS", *b"DSTF", *b"WNAM", *b"ANAM", *b"ATKR", *b"ATKD", *b"ATKE",
+    *b"SPOR", *b"OCOR", *b"GWOR", *b"ECOR", *b"PRKZ", *b"PRKR", *b"COCT", *b"CNTO", *b"COED", *b"AIDT", *b"PKID",
+    *b"KSIZ", *b"KWDA", *b"CNAM", *b"FULL", *b"SHRT", *b"DATA", *b"DNAM", *b"PNAM", *b"HCLF", *b"ZNAM", *b"GNAM",
+    *b"NAM5", *b"NAM6", *b"NAM7", *b"NAM8", *b"CSDT", *b"CSDI", *b"CSDC", *b"DOFT", *b"SOFT", *b"DPLT", *b"CRIF",
+    *b"FTST", *b"QNAM", *b"NAM9", *b"NAMA", *b"TINI", *b"TINC", *b"TINV", *b"TIAS", *b"MRSV",
+];
+
+pub mod npc_flags {
+    pub const FEMALE: u32 = 0x0000_0001;
+    pub const ESSENTIAL: u32 = 0x0000_0002;
+    pub const IS_CHARGEN_FACE_PRESET: u32 = 0x0000_0004;
+    pub const RESPAWN: u32 = 0x0000_0008;
+    pub const AUTO_CALC_STATS: u32 = 0x0000_0010;
+    pub const UNIQUE: u32 = 0x0000_0020;
+    pub const DOES_NOT_AFFECT_STEALTH: u32 = 0x0000_0040;
+    /// The level is a multiple of the player's, stored times 1000.
+    pub const PC_LEVEL_MULT: u32 = 0x0000_0080;
+    pub const PROTECTED: u32 = 0x0000_0800;
+    pub const SUMMONABLE: u32 = 0x0000_4000;
+    pub const DOES_NOT_BLEED: u32 = 0x0001_0000;
+    pub const BLEEDOUT_OVERRIDE: u32 = 0x0004_0000;
+    pub const OPPOSITE_GENDER_ANIMS: u32 = 0x0008_0000;
+    pub const SIMPLE_ACTOR: u32 = 0x0010_0000;
+    pub const IS_GHOST: u32 = 0x2000_0000;
+    pub const INVULNERABLE: u32 = 0x8000_0000;
+}
+
+/// What an NPC inherits from its template.
+pub mod template_flags {
+    pub const USE_TRAITS: u16 = 0x0001;
+    pub const USE_STATS: u16 = 0x0002;
+    pub const USE_FACTIONS: u16 = 0x0004;
+    pub const USE_SPELL_LIST: u16 = 0x0008;
+    pub const USE_AI_DATA: u16 = 0x0010;
+    pub const USE_AI_PACKAGES: u16 = 0x0020;
+    pub const USE_BASE_DATA: u16 = 0x0080;
+    pub const USE_INVENTORY: u16 = 0x0100;
+    pub const USE_SCRIPT: u16 = 0x0200;
+    pub const USE_DEF_PACK_LIST: u16 = 0x0400;
+    pub const USE_ATTACK_DATA: u16 = 0x0800;
+    pub const USE_KEYWORDS: u16 = 0x1000;
+}
+
+/// The template flags with the names the Creation Kit gives them.
+pub const TEMPLATE_FLAG_NAMES: [(u16, &str); 12] = [
+    (template_flags::USE_TRAITS, "Traits"),
+    (template_flags::USE_STATS, "Stats"),
+    (template_flags::USE_FACTIONS, "Factions"),
+    (template_flags::USE_SPELL_LIST, "Spell List"),
+    (template_flags::USE_AI_DATA, "AI Data"),
+    (template_flags::USE_AI_PACKAGES, "AI Packages"),
+    (template_flags::USE_BASE_DATA, "Base Data"),
+    (template_flags::USE_INVENTORY, "Inventory"),
+    (template_flags::USE_SCRIPT, "Script"),
+    (template_flags::USE_DEF_PACK_LIST, "Def Pack List"),
+    (template_flags::USE_ATTACK_DATA, "Attack Data"),
+    (template_flags::USE_KEYWORDS, "Keywords"),
+];
+
+/// Skills, indexed as in [`NpcStats::skills`].
+pub const SKILL_NAMES: [&str; 18] = [
+    "One-handed",
+    "Two-handed",
+    "Archery",
+    "Block",
+    "Smithing",
+    "Heavy Armor",
+    "Light Armor",
+    "Pickpocket",
+    "Lockpicking",
+    "Sneak",
+    "Alchemy",
+    "Speech",
+    "Alteration",
+    "Conjuration",
+    "Destruction",
+    "Illusion",
+    "Restoration",
+    "Enchanting",
+];
+
+/// Indexed by [`AiData::aggression`].
+pub const AGGRESSION_NAMES: [&str; 4] = ["Unaggressive", "Aggressive", "Very Aggressive", "Frenzied"];
+/// Indexed by [`AiData::confidence`].
+pub const CONFIDENCE_NAMES: [&str; 5] = ["Cowardly", "Cautious", "Average", "Brave", "Foolhardy"];
+/// Indexed by [`AiData::morality`].
+pub const MORALITY_NAMES: [&str; 4] = [
+    "Any Crime",
+    "Violence Against Enemies",
+    "Property Crime Only",
+    "No Crime",
+];
+/// Indexed by [`AiData::mood`].
+pub const MOOD_NAMES: [&str; 8] = [
+    "Neutral",
+    "Angry",
+    "Fear",
+    "Happy",
+    "Sad",
+    "Surprised",
+    "Puzzled",
+    "Disgusted",
+];
+/// Indexed by [`AiData::assistance`].
+pub const ASSISTANCE_NAMES: [&str; 3] = ["Helps Nobody", "Helps Allies", "Helps Friends and Allies"];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NpcFaction {
+    pub faction: u32,
+    pub rank: i8,
+    pub unknown: [u8; 3],
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NpcPerk {
+    pub perk: u32,
+    pub rank: u8,
+    pub unknown: [u8; 3],
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InventoryItem {
+    pub item: u32,
+    pub count: i32,
+    /// The COED data following the item: its owner, the global or rank required, and the item's condition.
+    pub extra: Option<Vec<u8>>,
+}
+
+/// AIDT
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AiData {
+    pub aggression: u8,
+    pub confidence: u8,
+    pub energy: u8,
+    pub morality: u8,
+    pub mood: u8,
+    pub assistance: u8,
+    /// Whether the aggro radius values below are used.
+    pub aggro_radius_behavior: bool,
+    pub unknown: u8,
+    pub warn: u32,
+    pub warn_or_attack: u32,
+    pub attack: u32,
+}
+
+/// DNAM
+#[derive(Clone, Debug, PartialEq)]
+pub struct NpcStats {
+    /// Indexed as in [`SKILL_NAMES`].
+    pub skills: [u8; 18],
+    pub skill_offsets: [u8; 18],
+    pub health: u16,
+    pub magicka: u16,
+    pub stamina: u16,
+    /// The rest of DNAM as stored: the far away model distance and geared up weapons, with padding.
+    pub unknown: Vec<u8>,
+}
+
+impl Default for NpcStats {
+    fn default() -> Self {
+        Self {
+            skills: [0; 18],
+            skill_offsets: [0; 18],
+            health: 0,
+            magicka: 0,
+            stamina: 0,
+            unknown: vec![0; 10],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Npc {
+    pub form_id: u32,
+    pub editor_id: String,
+    pub name: Option<Text>,
+    /// [`npc_flags`]
+    pub flags: u32,
+    pub magicka_offset: i16,
+    pub stamina_offset: i16,
+    /// The level, or with [`npc_flags::PC_LEVEL_MULT`] the multiple of the player's level times 1000.
+    pub level: u16,
+    pub calc_min_level: u16,
+    pub calc_max_level: u16,
+    pub speed_multiplier: u16,
+    pub disposition_base: i16,
+    /// [`template_flags`]
+    pub template_flags: u16,
+    pub health_offset: i16,
+    pub bleedout_override: u16,
+    pub factions: Vec<NpcFaction>,
+    /// An NPC or leveled list of NPCs to inherit from, as chosen by the template flags.
+    pub template: Option<u32>,
+    pub race: u32,
+    pub voice: Option<u32>,
+    pub class: u32,
+    pub spells: Vec<u32>,
+    pub perks: Vec<NpcPerk>,
+    pub inventory: Vec<InventoryItem>,
+    pub outfit: Option<u32>,
+    pub ai_data: AiData,
+    pub combat_style: Option<u32>,
+    pub packages: Vec<u32>,
+    pub stats: NpcStats,
+    pub height: Option<f32>,
+    pub weight: Option<f32>,
+    /// The subrecords as read, for writing those that are not decoded back in place.
+    layout: Vec<Subrecord>,
+}
+
+/// Where the values covered by a template flag come from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TemplateSource {
+    /// The NPC's own values are used.
+    Own,
+    /// Those of the NPC the template, or a chain of templates, leads to.
+    Npc(Box<Npc>),
+    /// The template is not an NPC, or is missing. Leveled lists of NPCs have one picked from them in game.
+    Other(u32),
+}
+
+impl Npc {
+    /// Reads an NPC_ record. `localized` is whether the plugin it comes from stores its text in string tables.
+    pub fn from_record(record: &Record, localized: bool) -> io::Result<Self> {
+        if &record.code != b"NPC_" {
+            return Err(invalid("not an NPC_ record"));
+        }
+
+        let mut npc = Self {
+            form_id: record.form_id,
+            editor_id: String::new(),
+            name: None,
+            flags: 0,
+            magicka_offset: 0,
+            stamina_offset: 0,
+            level: 1,
+            calc_min_level: 0,
+            calc_max_level: 0,
+            speed_multiplier: 100,
+            disposition_base: 35,
+            template_flags: 0,
+            health_offset: 0,
+            bleedout_override: 0,
+            factions: vec![],
+            template: None,
+            race: 0,
+            voice: None,
+            class: 0,
+            spells: vec![],
+            perks: vec![],
+            inventory: vec![],
+            outfit: None,
+            ai_data: AiData::default(),
+            combat_style: None,
+            packages: vec![],
+            stats: NpcStats::default(),
+            height: None,
+            weight: None,
+            layout: record.subrecords()?,
+        };
+
+        for subrecord in &npc.layout.clone() {
+            let data = &subrecord.data;
+
+            match &subrecord.code {
+                b"EDID" => npc.editor_id = subrecord.as_string(),
+                b"FULL" => npc.name = Some(Text::read(subrecord, localized)),
+                b"ACBS" => npc.read_acbs(data)?,
+                b"SNAM" => {
+                    let mut fields = Fields::new(data, "NPC_ SNAM");
+                    npc.factions.push(NpcFaction {
+                        faction: fields.u32()?,
+                        rank: fields.u8()? as i8,
+                        unknown: unknown3(fields.rest()),
+                    });
+                }
+                b"TPLT" => npc.template = subrecord.as_u32().filter(|&template| template != 0),
+                b"RNAM" => npc.race = subrecord.as_u32().unwrap_or(0),
+                b"VTCK" => npc.voice = subrecord.as_u32(),
+                b"CNAM" => npc.class = subrecord.as_u32().unwrap_or(0),
+                b"SPLO" => npc.spells.extend(subrecord.as_u32()),
+                b"PRKR" => {
+                    let mut fields = Fields::new(data, "NPC_ PRKR");
+                    npc.perks.push(NpcPerk {
+                        perk: fields.u32()?,
+                        rank: fields.u8()?,
+                        unknown: unknown3(fields.rest()),
+                    });
+                }
+                b"CNTO" => {
+                    let mut fields = Fields::new(data, "NPC_ CNTO");
+                    npc.inventory.push(InventoryItem {
+                        item: fields.u32()?,
+                        count: fields.i32()?,
+                        extra: None,
+                    });
+                }
+                b"COED" => {
+                    if let Some(item) = npc.inventory.last_mut() {
+                        item.extra = Some(data.clone());
+                    }
+                }
+                b"DOFT" => npc.outfit = subrecord.as_u32(),
+                b"AIDT" => npc.read_aidt(data)?,
+                b"ZNAM" => npc.combat_style = subrecord.as_u32(),
+                b"PKID" => npc.packages.extend(subrecord.as_u32()),
+                b"DNAM" => npc.read_dnam(data)?,
+                b"NAM6" => npc.height = subrecord.as_f32(),
+                b"NAM7" => npc.weight = subrecord.as_f32(),
+                _ => {}
+            }
+        }
+
+        Ok(npc)
+    }
+
+    fn read_acbs(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut fields = Fields::new(data, "NPC_ ACBS");
+        self.flags = fields.u32()?;
+        self.magicka_offset = fields.i16()?;
+        self.stamina_offset = fields.i16()?;
+        self.level = fields.u16()?;
+        self.calc_min_level = fields.u16()?;
+        self.calc_max_level = fields.u16()?;
+        self.speed_multiplier = fields.u16()?;
+        self.disposition_base = fields.i16()?;
+        self.template_flags = fields.u16()?;
+        self.health_offset = fields.i16()?;
+        self.bleedout_override = fields.u16()?;
+        Ok(())
+    }
+
+    fn read_aidt(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut fields = Fields::new(data, "NPC_ AIDT");
+        self.ai_data = AiData {
+            aggression: fields.u8()?,
+            confidence: fields.u8()?,
+            energy: fields.u8()?,
+            morality: fields.u8()?,
+            mood: fields.u8()?,
+            assistance: fields.u8()?,
+            aggro_radius_behavior: fields.u8()? != 0,
+            unknown: fields.u8()?,
+            warn: fields.u32()?,
+            warn_or_attack: fields.u32()?,
+            attack: fields.u32()?,
+        };
+        Ok(())
+    }
+
+    fn read_dnam(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut fields = Fields::new(data, "NPC_ DNAM");
+        let mut stats = NpcStats::default();
+        stats.skills.copy_from_slice(fields.bytes(18)?);
+        stats.skill_offsets.copy_from_slice(fields.bytes(18)?);
+        stats.health = fields.u16()?;
+        stats.magicka = fields.u16()?;
+        stats.stamina = fields.u16()?;
+        stats.unknown = fields.rest().to_vec();
+        self.stats = stats;
+        Ok(())
+    }
+
+    /// The level shown as the Creation Kit does: a number, or a multiple of the player's level.
+    pub fn level_text(&self) -> String {
+        if self.flags & npc_flags::PC_LEVEL_MULT != 0 {
+            format!("{:.2} x player level", self.level as f32 / 1000.0)
+        } else {
+            self.level.to_string()
+        }
+    }
+
+    /// Where the values covered by the template flag `flag` come from, following templates that inherit them in
+    /// turn. `npcs` reads an NPC by form ID, or gives `None` if the form ID is not an NPC.
+    pub fn template_source(&self, flag: u16, npcs: impl Fn(u32) -> Option<Npc>) -> TemplateSource {
+        let mut template = match self.template {
+            Some(template) if self.template_flags & flag != 0 => template,
+            _ => return TemplateSource::Own,
+        };
+
+        for _ in 0..MAX_TEMPLATE_DEPTH {
+            let npc = match npcs(template) {
+                Some(npc) => npc,
+                None => return TemplateSource::Other(template),
+            };
+
+            match npc.template {
+                Some(next) if npc.template_flags & flag != 0 => template = next,
+                _ => return TemplateSource::Npc(Box::new(npc)),
+            }
+        }
+
+        TemplateSource::Other(template)
+    }
+
+    /// The decoded subrecords, in groups named by the codes they replace.
+    fn groups(&self) -> Vec<(&'static [&'static Code], Vec<Subrecord>)> {
+        let u32 = |code: &[u8; 4], value: u32| Subrecord::new(*code, value.to_le_bytes().to_vec());
+        let f32 = |code: &[u8; 4], value: f32| Subrecord::new(*code, value.to_le_bytes().to_vec());
+
+        let mut acbs = vec![];
+        put_u32(&mut acbs, self.flags);
+        put_u16(&mut acbs, self.magicka_offset as u16);
+        put_u16(&mut acbs, self.stamina_offset as u16);
+        put_u16(&mut acbs, self.level);
+        put_u16(&mut acbs, self.calc_min_level);
+        put_u16(&mut acbs, self.calc_max_level);
+        put_u16(&mut acbs, self.speed_multiplier);
+        put_u16(&mut acbs, self.disposition_base as u16);
+        put_u16(&mut acbs, self.template_flags);
+        put_u16(&mut acbs, self.health_offset as u16);
+        put_u16(&mut acbs, self.bleedout_override);
+
+        let factions = self.factions.iter().map(|faction| {
+            let mut snam = faction.faction.to_le_bytes().to_vec();
+            snam.push(faction.rank as u8);
+            snam.extend_from_slice(&faction.unknown);
+            Subrecord::new(*b"SNAM", snam)
+        });
+
+        let mut spells = vec![];
+        if !self.spells.is_empty() {
+            spells.push(u32(b"SPCT", self.spells.len() as u32));
+            spells.extend(self.spells.iter().map(|&spell| u32(b"SPLO", spell)));
+        }
+
+        let mut perks = vec![];
+        if !self.perks.is_empty() {
+            perks.push(u32(b"PRKZ", self.perks.len() as u32));
+            perks.extend(self.perks.iter().map(|perk| {
+                let mut prkr = perk.perk.to_le_bytes().to_vec();
+                prkr.push(perk.rank);
+                prkr.extend_from_slice(&perk.unknown);
+                Subrecord::new(*b"PRKR", prkr)
+            }));
+        }
+
+        let mut inventory = vec![];
+        if !self.inventory.is_empty() {
+            inventory.push(u32(b"COCT", self.inventory.len() as u32));
+
+            for item in &self.inventory {
+                let mut cnto = item.item.to_le_bytes().to_vec();
+                cnto.extend_from_slice(&item.count.to_le_bytes());
+                inventory.push(Subrecord::new(*b"CNTO", cnto));
+                inventory.extend(item.extra.clone().map(|extra| Subrecord::new(*b"COED", extra)));
+            }
+        }
+
+        let ai = &self.ai_data;
+        let mut aidt = vec![
+            ai.aggression,
+            ai.confidence,
+            ai.energy,
+            ai.morality,
+            ai.mood,
+            ai.assistance,
+            ai.aggro_radius_behavior as u8,
+            ai.unknown,
+        ];
+        put_u32(&mut aidt, ai.warn);
+        put_u32(&mut aidt, ai.warn_or_attack);
+        put_u32(&mut aidt, ai.attack);
+
+        let stats = &self.stats;
+        let mut dnam = stats.skills.to_vec();
+        dnam.extend_from_slice(&stats.skill_offsets);
+        put_u16(&mut dnam, stats.health);
+        put_u16(&mut dnam, stats.magicka);
+        put_u16(&mut dnam, stats.stamina);
+        dnam.extend_from_slice(&stats.unknown);
+
+        vec![
+            (&[b"EDID"], vec![Subrecord::string(*b"EDID", &self.editor_id)]),
+            (&[b"ACBS"], vec![Subrecord::new(*b"ACBS", acbs)]),
+            (&[b"SNAM"], factions.collect()),
+            (
+                &[b"VTCK"],
+                self.voice.map(|voice| u32(b"VTCK", voice)).into_iter().collect(),
+            ),
+            (
+                &[b"TPLT"],
+                self.template
+                    .map(|template| u32(b"TPLT", template))
+                    .into_iter()
+                    .collect(),
+            ),
+            (&[b"RNAM"], vec![u32(b"RNAM", self.race)]),
+            (&[b"SPCT", b"SPLO"], spells),
+            (&[b"PRKZ", b"PRKR"], perks),
+            (&[b"COCT", b"CNTO", b"COED"], inventory),
+            (&[b"AIDT"], vec![Subrecord::new(*b"AIDT", aidt)]),
+            (
+                &[b"PKID"],
+                self.packages.iter().map(|&package| u32(b"PKID", package)).collect(),
+            ),
+            (&[b"CNAM"], vec![u32(b"CNAM", self.class)]),
+            (
+                &[b"FULL"],
+                self.name.iter().map(|name| name.subrecord(*b"FULL")).collect(),
+            ),
+            (&[b"DNAM"], vec![Subrecord::new(*b"DNAM", dnam)]),
+            (
+                &[b"ZNAM"],
+                self.combat_style.map(|style| u32(b"ZNAM", style)).into_iter().collect(),
+            ),
+            (
+                &[b"NAM6"],
+                self.height.map(|height| f32(b"NAM6", height)).into_iter().collect(),
+            ),
+            (
+                &[b"NAM7"],
+                self.weight.map(|weight| f32(b"NAM7", weight)).into_iter().collect(),
+            ),
+            (
+                &[b"DOFT"],
+                self.outfit.map(|outfit| u32(b"DOFT", outfit)).into_iter().collect(),
+            ),
+        ]
+    }
+
+    /// The NPC's text fields, for resolving string table IDs.
+    pub fn texts_mut(&mut self) -> impl Iterator<Item = &mut Text> {
+        self.name.iter_mut()
+    }
+
+    pub fn subrecords(&self) -> Vec<Subrecord> {
+        let groups = self.groups();
+        let mut written = vec![false; groups.len()];
+        let mut subrecords = vec![];
+
+        for subrecord in &self.layout {
+            match groups.iter().position(|(codes, _)| codes.contains(&&subrecord.code)) {
+                Some(index) if !written[index] => {
+                    subrecords.extend(groups[index].1.iter().cloned());
+                    written[index] = true;
+                }
+                Some(_) => {}
+                None => subrecords.push(subrecord.clone()),
+            }
+        }
+
+        let rank = |code: &Code| ORDER.iter().position(|ordered| ordered == code);
+
+        for ((codes, group), written) in groups.iter().zip(written) {
+            if written || group.is_empty() {
+                continue;
+            }
+
+            let group_rank = rank(codes[0]);
+            let position = subrecords
+                .iter()
+                .position(|subrecord| matches!(rank(&subrecord.code), Some(rank) if Some(rank) > group_rank))
+                .unwrap_or(subrecords.len());
+            subrecords.splice(position..position, group.iter().cloned());
+        }
+
+        subrecords
+    }
+
+    /// Replaces the record's subrecords with the NPC's.
+    pub fn write(&self, record: &mut Record) {
+        record.set_subrecords(&self.subrecords());
+    }
+}
+
+fn unknown3(bytes: &[u8]) -> [u8; 3] {
+    let mut unknown = [0; 3];
+    for (byte, value) in unknown.iter_mut().zip(bytes) {
+        *byte = *value;
+    }
+    unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(form_id: u32, template: Option<u32>, template_flags: u16) -> Record {
+        let mut acbs = vec![0; 24];
+        acbs[8..10].copy_from_slice(&5u16.to_le_bytes());
+        acbs[18..20].copy_from_slice(&template_flags.to_le_bytes());
+
+        let mut subrecords = vec![
+            Subrecord::string(*b"EDID", &format!("Npc{:X}", form_id)),
+            Subrecord::new(*b"OBND", vec![0; 12]),
+            Subrecord::new(*b"ACBS", acbs),
+            Subrecord::new(*b"SNAM", vec![0x10, 0x20, 0, 0, 2, 0, 0, 0]),
+        ];
+        subrecords.extend(template.map(|template| Subrecord::new(*b"TPLT", template.to_le_bytes().to_vec())));
+        subrecords.push(Subrecord::new(*b"RNAM", vec![0x19, 0x3E, 0x01, 0]));
+        subrecords.push(Subrecord::new(*b"WNAM", vec![0; 4]));
+        subrecords.push(Subrecord::new(*b"AIDT", vec![0; 20]));
+        subrecords.push(Subrecord::new(*b"CNAM", vec![0; 4]));
+        subrecords.push(Subrecord::new(*b"DNAM", vec![15; 52]));
+        subrecords.push(Subrecord::new(*b"PNAM", vec![0; 4]));
+        Record::new(*b"NPC_", form_id, &subrecords)
+    }
+
+    #[test]
+    fn round_trip() {
+        let original = record(0x0000_0800, None, 0);
+        let mut npc = Npc::from_record(&original, false).unwrap();
+        assert_eq!(npc.level, 5);
+        assert_eq!(npc.race, 0x0001_3E19);
+        assert_eq!(
+            npc.factions,
+            vec![NpcFaction {
+                faction: 0x2010,
+                rank: 2,
+                unknown: [0; 3]
+            }]
+        );
+        assert_eq!(npc.subrecords(), original.subrecords().unwrap());
+
+        npc.inventory.push(InventoryItem {
+            item: 0x0000_000F,
+            count: 100,
+            extra: None,
+        });
+        npc.packages.push(0x0000_0900);
+
+        let codes: Vec<Code> = npc.subrecords().iter().map(|subrecord| subrecord.code).collect();
+        let expected: Vec<Code> = vec![
+            *b"EDID", *b"OBND", *b"ACBS", *b"SNAM", *b"RNAM", *b"WNAM", *b"COCT", *b"CNTO", *b"AIDT", *b"PKID",
+            *b"CNAM", *b"DNAM", *b"PNAM",
+        ];
+        assert_eq!(codes, expected);
+
+        let record = Record::new(*b"NPC_", npc.form_id, &npc.subrecords());
+        assert_eq!(Npc::from_record(&record, false).unwrap().subrecords(), npc.subrecords());
+    }
+
+    #[test]
+    fn follows_templates() {
+        let records = [
+            record(0x0000_0800, Some(0x0000_0801), template_flags::USE_STATS),
+            record(0x0000_0801, Some(0x0000_0802), template_flags::USE_STATS),
+            record(0x0000_0802, Some(0x0000_0803), template_flags::USE_TRAITS),
+        ];
+        let npcs = |form_id: u32| {
+            let record = records.iter().find(|record| record.form_id == form_id)?;
+            Npc::from_record(record, false).ok()
+        };
+
+        let npc = npcs(0x0000_0800).unwrap();
+        assert_eq!(
+            npc.template_source(template_flags::USE_TRAITS, npcs),
+            TemplateSource::Own
+        );
+
+        match npc.template_source(template_flags::USE_STATS, npcs) {
+            TemplateSource::Npc(template) => assert_eq!(template.form_id, 0x0000_0802),
+            source => panic!("unexpected {:?}", source),
+        }
+
+        let template = npcs(0x0000_0802).unwrap();
+        assert_eq!(
+            template.template_source(template_flags::USE_TRAITS, npcs),
+            TemplateSource::Other(0x0000_0803)
+        );
+    }
+}
@@ -0,0 +1,723 @@
+use super::{form_ids, group_types, invalid, record_flags, Code, Record, Subrecord};
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+const GROUP_HEADER_SIZE: usize = 24;
+
+/// The label and type of each group containing a record, outermost first.
+pub type GroupPath = Vec<([u8; 4], i32)>;
+
+/// Whether a group holds the children of the record its label refers to, which comes just before it.
+pub(crate) fn has_owner(group_type: i32) -> bool {
+    group_type == group_types::WORLD_CHILDREN
+        || group_type == group_types::CELL_CHILDREN
+        || group_type == group_types::TOPIC_CHILDREN
+}
+
+#[derive(Clone, Debug)]
+pub enum Entry {
+    /// An index into [`Plugin::records`].
+    Record(usize),
+    Group(Group),
+}
+
+#[derive(Clone, Debug)]
+pub struct Group {
+    pub label: [u8; 4],
+    pub group_type: i32,
+    pub stamp: u32,
+    pub unknown: u32,
+    pub entries: Vec<Entry>,
+}
+
+impl Group {
+    pub fn new(label: [u8; 4], group_type: i32) -> Self {
+        Self {
+            label,
+            group_type,
+            stamp: 0,
+            unknown: 0,
+            entries: vec![],
+        }
+    }
+
+    /// The label as a form ID, for groups of children belonging to a record.
+    pub fn label_form_id(&self) -> u32 {
+        u32::from_le_bytes(self.label)
+    }
+
+    /// The label as exterior grid coordinates `(x, y)`, for exterior cell blocks and sub-blocks.
+    pub fn label_grid(&self) -> (i16, i16) {
+        let y = i16::from_le_bytes([self.label[0], self.label[1]]);
+        let x = i16::from_le_bytes([self.label[2], self.label[3]]);
+        (x, y)
+    }
+
+    /// The index in `entries` of the child group with the given label and type, adding it if it is missing.
+    ///
+    /// A group of children is added straight after its owner's record, which `owner` provides when this
+    /// group does not contain it yet.
+    fn child_group(
+        &mut self,
+        label: [u8; 4],
+        group_type: i32,
+        records: &mut Vec<Record>,
+        owner: &mut dyn FnMut(u32) -> Option<Record>,
+    ) -> usize {
+        let existing = self.entries.iter().position(|entry| match entry {
+            Entry::Group(group) => group.label == label && group.group_type == group_type,
+            Entry::Record(_) => false,
+        });
+
+        if let Some(position) = existing {
+            return position;
+        }
+
+        let mut position = self.entries.len();
+
+        if has_owner(group_type) {
+            let form_id = u32::from_le_bytes(label);
+            let owner_position = self.entries.iter().position(|entry| match entry {
+                Entry::Record(index) => records[*index].form_id == form_id,
+                Entry::Group(_) => false,
+            });
+
+            position = match owner_position {
+                Some(owner_position) => owner_position + 1,
+                None => {
+                    if let Some(record) = owner(form_id) {
+                        self.entries.push(Entry::Record(records.len()));
+                        records.push(record);
+                    }
+
+                    self.entries.len()
+                }
+            };
+        } else if group_type == group_types::CELL_PERSISTENT_CHILDREN {
+            // Persistent children come before temporary ones.
+            position = self
+                .entries
+                .iter()
+                .position(|entry| match entry {
+                    Entry::Group(group) => group.group_type == group_types::CELL_TEMPORARY_CHILDREN,
+                    Entry::Record(_) => false,
+                })
+                .unwrap_or(position);
+        }
+
+        self.entries
+            .insert(position, Entry::Group(Group::new(label, group_type)));
+        position
+    }
+
+    fn parse(bytes: &[u8], records: &mut Vec<Record>) -> io::Result<(Self, usize)> {
+        if bytes.len() < GROUP_HEADER_SIZE || &bytes[..4] != b"GRUP" {
+            return Err(invalid("expected a group"));
+        }
+
+        let u32_at = |offset: usize| {
+            u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+        };
+        let size = u32_at(4) as usize;
+
+        if size < GROUP_HEADER_SIZE || bytes.len() < size {
+            return Err(invalid("truncated group"));
+        }
+
+        let mut group = Self {
+            label: [bytes[8], bytes[9], bytes[10], bytes[11]],
+            group_type: u32_at(12) as i32,
+            stamp: u32_at(16),
+            unknown: u32_at(20),
+            entries: vec![],
+        };
+
+        let mut offset = GROUP_HEADER_SIZE;
+
+        while offset < size {
+            let entry = &bytes[offset..size];
+
+            if entry.len() < 4 {
+                return Err(invalid("truncated group entry"));
+            }
+
+            if entry.starts_with(b"GRUP") {
+                let (child, consumed) = Self::parse(entry, records)?;
+                group.entries.push(Entry::Group(child));
+                offset += consumed;
+            } else {
+                let (record, consumed) = Record::parse(entry)?;
+                group.entries.push(Entry::Record(records.len()));
+                records.push(record);
+                offset += consumed;
+            }
+        }
+
+        Ok((group, size))
+    }
+
+    fn size(&self, records: &[Record]) -> usize {
+        GROUP_HEADER_SIZE
+            + self
+                .entries
+                .iter()
+                .map(|entry| match entry {
+                    Entry::Record(index) => records[*index].size(),
+                    Entry::Group(group) => group.size(records),
+                })
+                .sum::<usize>()
+    }
+
+    fn count(&self) -> usize {
+        1 + self
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                Entry::Record(_) => 1,
+                Entry::Group(group) => group.count(),
+            })
+            .sum::<usize>()
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, records: &[Record]) -> io::Result<()> {
+        writer.write_all(b"GRUP")?;
+        writer.write_all(&(self.size(records) as u32).to_le_bytes())?;
+        writer.write_all(&self.label)?;
+        writer.write_all(&self.group_type.to_le_bytes())?;
+        writer.write_all(&self.stamp.to_le_bytes())?;
+        writer.write_all(&self.unknown.to_le_bytes())?;
+
+        for entry in &self.entries {
+            match entry {
+                Entry::Record(index) => records[*index].write(writer)?,
+                Entry::Group(group) => group.write(writer, records)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn visit<'a>(
+        &'a self,
+        parents: &mut Vec<&'a Group>,
+        records: &'a [Record],
+        visitor: &mut dyn FnMut(&[&'a Group], &'a Record),
+    ) {
+        parents.push(self);
+
+        for entry in &self.entries {
+            match entry {
+                Entry::Record(index) => visitor(parents, &records[*index]),
+                Entry::Group(group) => group.visit(parents, records, visitor),
+            }
+        }
+
+        parents.pop();
+    }
+}
+
+/// The most plugins a load order can hold. A form ID names its plugin in its top byte, and `FF` is kept for
+/// forms created in game.
+pub const MAX_PLUGINS: usize = 0xFF;
+
+#[derive(Clone)]
+pub struct Plugin {
+    pub name: String,
+    pub header: Record,
+    /// Every record in the plugin, in file order. Groups refer to records by their index here.
+    pub records: Vec<Record>,
+    pub groups: Vec<Group>,
+    index: HashMap<u32, usize>,
+    /// The load-order index of each plugin a form ID's top byte can name in the file: each master in turn, then
+    /// this plugin. Records are held with load-order form IDs and translated back through this when written.
+    load_order_indices: Vec<u8>,
+}
+
+impl Plugin {
+    /// Creates an empty plugin. Panics with [`MAX_PLUGINS`] or more masters, which form IDs can't number.
+    pub fn new(name: &str, masters: &[String]) -> Self {
+        assert!(masters.len() < MAX_PLUGINS, "{} has too many masters", name);
+
+        let mut hedr = 1.7f32.to_le_bytes().to_vec();
+        hedr.extend_from_slice(&0u32.to_le_bytes());
+        hedr.extend_from_slice(&0x800u32.to_le_bytes());
+
+        let mut subrecords = vec![
+            Subrecord::new(*b"HEDR", hedr),
+            Subrecord::string(*b"CNAM", "Open Creation"),
+        ];
+
+        for master in masters {
+            subrecords.push(Subrecord::string(*b"MAST", master));
+            subrecords.push(Subrecord::new(*b"DATA", vec![0; 8]));
+        }
+
+        Self {
+            name: name.to_string(),
+            header: Record::new(*b"TES4", 0, &subrecords),
+            records: vec![],
+            groups: vec![],
+            index: HashMap::new(),
+            load_order_indices: (0..=masters.len() as u8).collect(),
+        }
+    }
+
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Self::parse(&name, &fs::read(path)?)
+    }
+
+    pub fn parse(name: &str, bytes: &[u8]) -> io::Result<Self> {
+        let (header, mut offset) = Record::parse(bytes)?;
+
+        if &header.code != b"TES4" {
+            return Err(invalid("plugin does not start with a TES4 header"));
+        }
+
+        let mut records = vec![];
+        let mut groups = vec![];
+
+        while offset < bytes.len() {
+            let (group, consumed) = Group::parse(&bytes[offset..], &mut records)?;
+            groups.push(group);
+            offset += consumed;
+        }
+
+        let mut plugin = Self {
+            name: name.to_string(),
+            header,
+            records,
+            groups,
+            index: HashMap::new(),
+            load_order_indices: vec![],
+        };
+
+        if plugin.masters().len() >= MAX_PLUGINS {
+            return Err(invalid("plugin has more masters than form IDs can number"));
+        }
+
+        plugin.load_order_indices = (0..=plugin.masters().len() as u8).collect();
+        plugin.reindex();
+        Ok(plugin)
+    }
+
+    fn reindex(&mut self) {
+        self.index = self
+            .records
+            .iter()
+            .enumerate()
+            .map(|(index, record)| (record.form_id, index))
+            .collect();
+    }
+
+    /// Writes the plugin, translating form IDs back from the load order's numbering into the plugin's own. Fails
+    /// if a record refers to a plugin that is not one of its masters.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.is_numbered_by_load_order() {
+            return self.write_as_stored(writer);
+        }
+
+        let local: HashMap<u8, u8> = self
+            .load_order_indices
+            .iter()
+            .enumerate()
+            .map(|(local, &index)| (index, local as u8))
+            .collect();
+        let mut missing = None;
+
+        let mut plugin = self.clone();
+        plugin.map_form_ids(&mut |form_id| match local.get(&((form_id >> 24) as u8)) {
+            Some(&local) => (local as u32) << 24 | (form_id & 0x00FF_FFFF),
+            None => {
+                missing.get_or_insert(form_id);
+                form_id
+            }
+        });
+
+        if let Some(form_id) = missing {
+            return Err(invalid(&format!(
+                "{} refers to {:08X}, which belongs to a plugin that is not one of its masters",
+                self.name, form_id
+            )));
+        }
+
+        plugin.write_as_stored(writer)
+    }
+
+    fn write_as_stored<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut header = self.header.clone();
+        let mut subrecords = header.subrecords()?;
+        let count: usize = self.groups.iter().map(|group| group.count()).sum();
+
+        if let Some(hedr) = subrecords.iter_mut().find(|subrecord| &subrecord.code == b"HEDR") {
+            if hedr.data.len() >= 8 {
+                hedr.data[4..8].copy_from_slice(&(count as u32).to_le_bytes());
+            }
+        }
+
+        header.set_subrecords(&subrecords);
+        header.write(writer)?;
+
+        for group in &self.groups {
+            group.write(writer, &self.records)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the plugin to a file. The whole plugin is written in memory first, so a plugin that fails to write
+    /// leaves the file as it was.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut bytes = vec![];
+        self.write(&mut bytes)?;
+        fs::write(path, bytes)
+    }
+
+    pub fn is_master(&self) -> bool {
+        self.header.flags & record_flags::MASTER != 0
+    }
+
+    pub fn is_localized(&self) -> bool {
+        self.header.flags & record_flags::LOCALIZED != 0
+    }
+
+    pub fn masters(&self) -> Vec<String> {
+        self.header
+            .subrecords()
+            .unwrap_or_default()
+            .iter()
+            .filter(|subrecord| &subrecord.code == b"MAST")
+            .map(|subrecord| subrecord.as_string())
+            .collect()
+    }
+
+    /// The plugin's own position in the load order, which is the top byte of the form IDs of its new records.
+    pub fn load_order_index(&self) -> u8 {
+        self.load_order_indices.last().copied().unwrap_or(0)
+    }
+
+    /// Moves the plugin to a position in the load order, for plugins that are created rather than loaded.
+    pub fn set_load_order_index(&mut self, index: u8) {
+        if let Some(own) = self.load_order_indices.last_mut() {
+            *own = index;
+        }
+    }
+
+    /// The load-order indices of the plugin's masters.
+    pub fn master_indices(&self) -> &[u8] {
+        &self.load_order_indices[..self.load_order_indices.len() - 1]
+    }
+
+    /// Whether the plugin's masters are exactly the plugins before it, so its form IDs need no translating.
+    fn is_numbered_by_load_order(&self) -> bool {
+        self.load_order_indices
+            .iter()
+            .enumerate()
+            .all(|(local, &index)| local == index as usize)
+    }
+
+    /// Translates the plugin's form IDs from its own numbering into the load order's, given the load-order index
+    /// of each of its masters and then of the plugin itself. Form IDs naming a plugin past the last master
+    /// belong to this plugin, as they do in the game.
+    pub(crate) fn number_by_load_order(&mut self, load_order_indices: Vec<u8>) {
+        self.load_order_indices = load_order_indices;
+
+        if self.is_numbered_by_load_order() {
+            return;
+        }
+
+        let indices = self.load_order_indices.clone();
+        let own = self.load_order_index();
+        self.map_form_ids(&mut |form_id| {
+            let index = indices.get((form_id >> 24) as usize).copied().unwrap_or(own);
+            (index as u32) << 24 | (form_id & 0x00FF_FFFF)
+        });
+    }
+
+    /// Adds a master at its position in the load order, unless the plugin has it already. Masters are kept in
+    /// load order.
+    pub fn add_master(&mut self, name: &str, index: u8) {
+        let masters = self.master_indices();
+
+        if masters.contains(&index) || index == self.load_order_index() {
+            return;
+        }
+
+        let position = masters.iter().filter(|&&master| master < index).count();
+        self.load_order_indices.insert(position, index);
+
+        let mut subrecords = self.header.subrecords().unwrap_or_default();
+        let mast: Vec<usize> = subrecords
+            .iter()
+            .enumerate()
+            .filter(|(_, subrecord)| &subrecord.code == b"MAST")
+            .map(|(at, _)| at)
+            .collect();
+
+        let at = match (mast.get(position), mast.last()) {
+            (Some(&at), _) => at,
+            // After the last master and the DATA following it.
+            (None, Some(&last)) => match subrecords.get(last + 1) {
+                Some(data) if &data.code == b"DATA" => last + 2,
+                _ => last + 1,
+            },
+            // Masters come before the overridden records, intervals and counts at the end of the header.
+            (None, None) => subrecords
+                .iter()
+                .position(|subrecord| [*b"ONAM", *b"INTV", *b"INCC"].contains(&subrecord.code))
+                .unwrap_or(subrecords.len()),
+        };
+
+        subrecords.insert(at, Subrecord::new(*b"DATA", vec![0; 8]));
+        subrecords.insert(at, Subrecord::string(*b"MAST", name));
+        self.header.set_subrecords(&subrecords);
+    }
+
+    /// Calls `map` with every form ID in the plugin's records and in the labels of its groups of children,
+    /// replacing each with the result.
+    fn map_form_ids(&mut self, map: &mut dyn FnMut(u32) -> u32) {
+        for record in &mut self.records {
+            record.form_id = map(record.form_id);
+
+            if let Ok(mut subrecords) = record.subrecords() {
+                if form_ids::map_subrecords(record.code, &mut subrecords, map) {
+                    record.set_subrecords(&subrecords);
+                }
+            }
+        }
+
+        let mut groups: Vec<&mut Group> = self.groups.iter_mut().collect();
+
+        while let Some(group) = groups.pop() {
+            let children = has_owner(group.group_type)
+                || group.group_type == group_types::CELL_PERSISTENT_CHILDREN
+                || group.group_type == group_types::CELL_TEMPORARY_CHILDREN;
+
+            if children {
+                group.label = map(group.label_form_id()).to_le_bytes();
+            }
+
+            for entry in &mut group.entries {
+                if let Entry::Group(child) = entry {
+                    groups.push(child);
+                }
+            }
+        }
+
+        self.reindex();
+    }
+
+    /// Allocates a form ID for a new record in this plugin, tracking the next free one in the header.
+    ///
+    /// The top byte is the plugin's position in the load order.
+    pub fn next_form_id(&mut self) -> u32 {
+        let prefix = (self.load_order_index() as u32) << 24;
+        let mut subrecords = self.header.subrecords().unwrap_or_default();
+
+        let header_next = subrecords
+            .iter()
+            .find(|subrecord| &subrecord.code == b"HEDR")
+            .and_then(|hedr| hedr.data.get(8..12))
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .unwrap_or(0);
+
+        let used_next = self
+            .records
+            .iter()
+            .filter(|record| record.form_id & 0xFF00_0000 == prefix)
+            .map(|record| (record.form_id & 0x00FF_FFFF) + 1)
+            .max()
+            .unwrap_or(0);
+
+        // Object IDs below 0x800 are reserved by the engine.
+        let next = header_next.max(used_next).max(0x800);
+
+        if let Some(hedr) = subrecords.iter_mut().find(|subrecord| &subrecord.code == b"HEDR") {
+            if hedr.data.len() >= 12 {
+                hedr.data[8..12].copy_from_slice(&(next + 1).to_le_bytes());
+                self.header.set_subrecords(&subrecords);
+            }
+        }
+
+        prefix | next
+    }
+
+    pub fn contains(&self, form_id: u32) -> bool {
+        self.index.contains_key(&form_id)
+    }
+
+    pub fn record(&self, form_id: u32) -> Option<&Record> {
+        self.index.get(&form_id).map(|&index| &self.records[index])
+    }
+
+    pub fn record_mut(&mut self, form_id: u32) -> Option<&mut Record> {
+        match self.index.get(&form_id) {
+            Some(&index) => Some(&mut self.records[index]),
+            None => None,
+        }
+    }
+
+    pub fn top_group(&self, code: Code) -> Option<&Group> {
+        self.groups.iter().find(|group| group.label == code)
+    }
+
+    /// Every CELL record in this plugin, with the worldspace of the group it is stored in for exterior cells.
+    pub fn cells(&self) -> Vec<(Option<u32>, &Record)> {
+        let mut cells = vec![];
+
+        self.visit(&mut |parents, record| {
+            if &record.code == b"CELL" {
+                let world = parents
+                    .iter()
+                    .rev()
+                    .find(|group| group.group_type == group_types::WORLD_CHILDREN)
+                    .map(|group| group.label_form_id());
+
+                cells.push((world, record));
+            }
+        });
+
+        cells
+    }
+
+    /// The records in a cell's persistent and temporary children groups in this plugin, including deleted ones.
+    pub fn cell_references(&self, cell: u32) -> Vec<&Record> {
+        let mut references = vec![];
+
+        self.visit(&mut |parents, record| {
+            if let Some(parent) = parents.last() {
+                let children = parent.group_type == group_types::CELL_PERSISTENT_CHILDREN
+                    || parent.group_type == group_types::CELL_TEMPORARY_CHILDREN;
+
+                if children && parent.label_form_id() == cell {
+                    references.push(record);
+                }
+            }
+        });
+
+        references
+    }
+
+    /// Every record in a topic's children group in this plugin, with the DIAL the group belongs to, including
+    /// deleted ones.
+    pub fn topic_children(&self) -> Vec<(u32, &Record)> {
+        let mut children = vec![];
+
+        self.visit(&mut |parents, record| {
+            if let Some(parent) = parents.last() {
+                if parent.group_type == group_types::TOPIC_CHILDREN {
+                    children.push((parent.label_form_id(), record));
+                }
+            }
+        });
+
+        children
+    }
+
+    /// The groups containing a record, or `None` if this plugin does not contain it.
+    pub fn group_path(&self, form_id: u32) -> Option<GroupPath> {
+        let mut path = None;
+
+        self.visit(&mut |parents, record| {
+            if path.is_none() && record.form_id == form_id {
+                path = Some(parents.iter().map(|group| (group.label, group.group_type)).collect());
+            }
+        });
+
+        path
+    }
+
+    /// Adds a record inside the groups on `path`, creating any that are missing, or replaces the plugin's
+    /// existing version of it. Missing owners of children groups, such as the CELL a reference is placed in,
+    /// are added from `owner`.
+    pub fn add_record(
+        &mut self,
+        path: &[([u8; 4], i32)],
+        record: Record,
+        owner: &mut dyn FnMut(u32) -> Option<Record>,
+    ) -> &mut Record {
+        if let Some(&index) = self.index.get(&record.form_id) {
+            self.records[index] = record;
+            return &mut self.records[index];
+        }
+
+        let records = &mut self.records;
+        let first_new = records.len();
+        let (top_label, _) = path.first().copied().unwrap_or((record.code, group_types::TOP));
+
+        let top = match self.groups.iter().position(|group| group.label == top_label) {
+            Some(position) => position,
+            None => {
+                self.groups.push(Group::new(top_label, group_types::TOP));
+                self.groups.len() - 1
+            }
+        };
+
+        let mut group = &mut self.groups[top];
+
+        for &(label, group_type) in path.iter().skip(1) {
+            let position = group.child_group(label, group_type, records, owner);
+
+            group = match &mut group.entries[position] {
+                Entry::Group(child) => child,
+                Entry::Record(_) => unreachable!("child_group returns the position of a group"),
+            };
+        }
+
+        group.entries.push(Entry::Record(records.len()));
+        records.push(record);
+
+        // Owners may have been added too, so every new record is indexed.
+        for (index, record) in records.iter().enumerate().skip(first_new) {
+            self.index.insert(record.form_id, index);
+        }
+
+        let index = records.len() - 1;
+        &mut self.records[index]
+    }
+
+    /// Calls `visitor` with every record and the chain of groups containing it, outermost first.
+    pub fn visit<'a>(&'a self, visitor: &mut dyn FnMut(&[&'a Group], &'a Record)) {
+        let mut parents = vec![];
+
+        for group in &self.groups {
+            group.visit(&mut parents, &self.records, visitor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_truncated_group_entries() {
+        let mut plugin = Plugin::new("Test.esp", &[]);
+        plugin.add_record(
+            &[(*b"STAT", group_types::TOP)],
+            Record::new(*b"STAT", 0x800, &[]),
+            &mut |_| None,
+        );
+
+        let mut bytes = vec![];
+        plugin.write(&mut bytes).unwrap();
+
+        // Two stray bytes at the end of the group, counted in its size.
+        let group = plugin.header.size();
+        bytes.extend_from_slice(&[0, 0]);
+        let size = u32::from_le_bytes([bytes[group + 4], bytes[group + 5], bytes[group + 6], bytes[group + 7]]) + 2;
+        bytes[group + 4..group + 8].copy_from_slice(&size.to_le_bytes());
+
+        let error = Plugin::parse("Test.esp", &bytes).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,728 @@
+//! Quests from QUST records: their data, stages, objectives and aliases.
+//!
+//! A QUST record is a flat list of subrecords in which stages, log entries, objectives and aliases each start
+//! with a particular subrecord and own those after it. Subrecords the editor does not decode are kept with the
+//! part of the quest they were found in and written back in place.
+
+use super::{invalid, Code, Condition, Record, Subrecord, Text, Vmad};
+
+use std::io;
+
+pub mod quest_flags {
+    pub const START_GAME_ENABLED: u16 = 0x0001;
+    pub const ALLOW_REPEATED_STAGES: u16 = 0x0008;
+    pub const RUN_ONCE: u16 = 0x0100;
+    pub const EXCLUDE_FROM_DIALOGUE_EXPORT: u16 = 0x0200;
+    pub const WARN_ON_ALIAS_FILL_FAILURE: u16 = 0x0400;
+}
+
+pub mod stage_flags {
+    pub const RUN_ON_START: u8 = 0x02;
+    pub const RUN_ON_STOP: u8 = 0x04;
+    pub const KEEP_INSTANCE_DATA: u8 = 0x08;
+}
+
+pub mod log_entry_flags {
+    pub const COMPLETE_QUEST: u8 = 0x01;
+    pub const FAIL_QUEST: u8 = 0x02;
+}
+
+pub mod objective_flags {
+    pub const ORED_WITH_PREVIOUS: u32 = 0x0001;
+}
+
+pub mod target_flags {
+    pub const COMPASS_MARKER_IGNORES_LOCKS: u8 = 0x01;
+}
+
+pub mod alias_flags {
+    pub const RESERVES: u32 = 0x0000_0001;
+    pub const OPTIONAL: u32 = 0x0000_0002;
+    pub const QUEST_OBJECT: u32 = 0x0000_0004;
+    pub const ALLOW_REUSE: u32 = 0x0000_0008;
+    pub const ALLOW_DEAD: u32 = 0x0000_0010;
+    pub const IN_LOADED_AREA: u32 = 0x0000_0020;
+    pub const ESSENTIAL: u32 = 0x0000_0040;
+    pub const ALLOW_DISABLED: u32 = 0x0000_0080;
+    pub const STORES_TEXT: u32 = 0x0000_0100;
+    pub const ALLOW_RESERVED: u32 = 0x0000_0200;
+    pub const PROTECTED: u32 = 0x0000_0400;
+    pub const ALLOW_DESTROYED: u32 = 0x0000_1000;
+    pub const CLOSEST: u32 = 0x0000_2000;
+    pub const USES_STORED_TEXT: u32 = 0x0000_4000;
+    pub const INITIALLY_DISABLED: u32 = 0x0000_8000;
+    pub const ALLOW_CLEARED: u32 = 0x0001_0000;
+    pub const CLEAR_NAMES_WHEN_REMOVED: u32 = 0x0002_0000;
+}
+
+/// Quest types, indexed by [`Quest::quest_type`].
+pub const QUEST_TYPE_NAMES: [&str; 12] = [
+    "None",
+    "Main Quest",
+    "Mages' Guild",
+    "Thieves' Guild",
+    "Dark Brotherhood",
+    "Companions",
+    "Miscellaneous",
+    "Daedric",
+    "Side Quest",
+    "Civil War",
+    "Vampire (Dawnguard)",
+    "Dragonborn",
+];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogEntry {
+    /// [`log_entry_flags`]
+    pub flags: u8,
+    pub conditions: Vec<Condition>,
+    pub text: Option<Text>,
+    /// The quest to start when this entry is used.
+    pub next_quest: Option<u32>,
+    pub other: Vec<Subrecord>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stage {
+    pub index: u16,
+    /// [`stage_flags`]
+    pub flags: u8,
+    pub unknown: u8,
+    pub log_entries: Vec<LogEntry>,
+}
+
+/// A place an objective's compass marker can point at.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjectiveTarget {
+    /// The alias ID of the target.
+    pub alias: i32,
+    /// [`target_flags`]
+    pub flags: u8,
+    pub unknown: [u8; 3],
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Objective {
+    pub index: u16,
+    /// [`objective_flags`]
+    pub flags: u32,
+    pub text: Option<Text>,
+    pub targets: Vec<ObjectiveTarget>,
+    pub other: Vec<Subrecord>,
+}
+
+/// How an alias is filled when the quest starts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AliasFill {
+    /// By the first reference or location matching the alias's conditions, or not at all if it is optional.
+    Conditions,
+    ForcedReference(u32),
+    ForcedLocation(u32),
+    UniqueActor(u32),
+    /// By a reference of a location reference type in the location held by another alias.
+    LocationAlias {
+        alias: u32,
+        ref_type: u32,
+    },
+    /// By whatever fills an alias of another quest.
+    ExternalAlias {
+        quest: u32,
+        alias: u32,
+    },
+    /// By a new reference to a base object created at or in another alias.
+    CreateReference {
+        object: u32,
+        at: u32,
+        level: u32,
+    },
+    /// By a reference linked to the one in another alias.
+    NearAlias {
+        alias: u32,
+        kind: u32,
+    },
+    /// By data from the Story Manager event that started the quest.
+    FromEvent {
+        event: Code,
+        data: Code,
+    },
+}
+
+impl AliasFill {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AliasFill::Conditions => "Find matching",
+            AliasFill::ForcedReference(_) => "Forced reference",
+            AliasFill::ForcedLocation(_) => "Specific location",
+            AliasFill::UniqueActor(_) => "Unique actor",
+            AliasFill::LocationAlias { .. } => "Location alias reference",
+            AliasFill::ExternalAlias { .. } => "External alias reference",
+            AliasFill::CreateReference { .. } => "Create reference to object",
+            AliasFill::NearAlias { .. } => "Find matching reference near alias",
+            AliasFill::FromEvent { .. } => "Find matching from event",
+        }
+    }
+
+    /// Updates the fill from one of the subrecords storing it, returning false if the subrecord is not one.
+    fn read(&mut self, subrecord: &Subrecord) -> bool {
+        let value = subrecord.as_u32().unwrap_or(0);
+        let code = || {
+            let mut code = [0; 4];
+            for (byte, value) in code.iter_mut().zip(&subrecord.data) {
+                *byte = *value;
+            }
+            code
+        };
+
+        match (&subrecord.code, &mut *self) {
+            (b"ALFR", _) => *self = AliasFill::ForcedReference(value),
+            (b"ALFL", _) => *self = AliasFill::ForcedLocation(value),
+            (b"ALUA", _) => *self = AliasFill::UniqueActor(value),
+            (b"ALFA", AliasFill::LocationAlias { alias, .. }) => *alias = value,
+            (b"ALFA", _) => {
+                *self = AliasFill::LocationAlias {
+                    alias: value,
+                    ref_type: 0,
+                }
+            }
+            (b"KNAM", AliasFill::LocationAlias { ref_type, .. }) => *ref_type = value,
+            (b"ALEQ", AliasFill::ExternalAlias { quest, .. }) => *quest = value,
+            (b"ALEQ", _) => *self = AliasFill::ExternalAlias { quest: value, alias: 0 },
+            (b"ALEA", AliasFill::ExternalAlias { alias, .. }) => *alias = value,
+            (b"ALEA", _) => *self = AliasFill::ExternalAlias { quest: 0, alias: value },
+            (b"ALCO", AliasFill::CreateReference { object, .. }) => *object = value,
+            (b"ALCO", _) => {
+                *self = AliasFill::CreateReference {
+                    object: value,
+                    at: 0,
+                    level: 0,
+                }
+            }
+            (b"ALCA", AliasFill::CreateReference { at, .. }) => *at = value,
+            (b"ALCL", AliasFill::CreateReference { level, .. }) => *level = value,
+            (b"ALNA", AliasFill::NearAlias { alias, .. }) => *alias = value,
+            (b"ALNA", _) => *self = AliasFill::NearAlias { alias: value, kind: 0 },
+            (b"ALNT", AliasFill::NearAlias { kind, .. }) => *kind = value,
+            (b"ALFE", AliasFill::FromEvent { event, .. }) => *event = code(),
+            (b"ALFE", _) => {
+                *self = AliasFill::FromEvent {
+                    event: code(),
+                    data: [0; 4],
+                }
+            }
+            (b"ALFD", AliasFill::FromEvent { data, .. }) => *data = code(),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn subrecords(&self) -> Vec<Subrecord> {
+        let u32 = |code: &[u8; 4], value: u32| Subrecord::new(*code, value.to_le_bytes().to_vec());
+
+        match *self {
+            AliasFill::Conditions => vec![],
+            AliasFill::ForcedReference(reference) => vec![u32(b"ALFR", reference)],
+            AliasFill::ForcedLocation(location) => vec![u32(b"ALFL", location)],
+            AliasFill::UniqueActor(actor) => vec![u32(b"ALUA", actor)],
+            AliasFill::LocationAlias { alias, ref_type } => vec![u32(b"ALFA", alias), u32(b"KNAM", ref_type)],
+            AliasFill::ExternalAlias { quest, alias } => vec![u32(b"ALEQ", quest), u32(b"ALEA", alias)],
+            AliasFill::CreateReference { object, at, level } => {
+                vec![u32(b"ALCO", object), u32(b"ALCA", at), u32(b"ALCL", level)]
+            }
+            AliasFill::NearAlias { alias, kind } => vec![u32(b"ALNA", alias), u32(b"ALNT", kind)],
+            AliasFill::FromEvent { event, data } => {
+                vec![
+                    Subrecord::new(*b"ALFE", event.to_vec()),
+                    Subrecord::new(*b"ALFD", data.to_vec()),
+                ]
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alias {
+    pub id: u32,
+    /// Whether this is a location alias (ALLS) rather than a reference alias (ALST).
+    pub is_location: bool,
+    pub name: String,
+    /// [`alias_flags`]
+    pub flags: u32,
+    /// The alias this one is forced into when it is filled.
+    pub forced_into: Option<u32>,
+    pub fill: AliasFill,
+    pub conditions: Vec<Condition>,
+    /// Keywords, items, spells, factions, packages and so on given to whatever fills the alias.
+    pub other: Vec<Subrecord>,
+}
+
+impl Alias {
+    pub fn new(id: u32, is_location: bool) -> Self {
+        Self {
+            id,
+            is_location,
+            name: String::new(),
+            flags: 0,
+            forced_into: None,
+            fill: AliasFill::Conditions,
+            conditions: vec![],
+            other: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quest {
+    pub form_id: u32,
+    pub editor_id: String,
+    pub name: Option<Text>,
+    pub scripts: Option<Vmad>,
+    /// [`quest_flags`]
+    pub flags: u16,
+    pub priority: u8,
+    pub form_version: u8,
+    pub unknown: u32,
+    /// An index into [`QUEST_TYPE_NAMES`].
+    pub quest_type: u32,
+    /// The Story Manager event that starts the quest.
+    pub event: Option<Code>,
+    /// Globals whose values can be shown in the quest's text.
+    pub text_globals: Vec<u32>,
+    /// The folder the quest is listed under in the editor.
+    pub filter: String,
+    /// Conditions on all of the quest's dialogue.
+    pub dialogue_conditions: Vec<Condition>,
+    /// Conditions on the Story Manager event starting the quest.
+    pub event_conditions: Vec<Condition>,
+    pub stages: Vec<Stage>,
+    pub objectives: Vec<Objective>,
+    /// The ID the next new alias will get.
+    pub next_alias: u32,
+    pub aliases: Vec<Alias>,
+    /// Subrecords before the stages that are not decoded.
+    pub other: Vec<Subrecord>,
+}
+
+/// The part of the quest the subrecords being read belong to.
+enum Section {
+    DialogueConditions,
+    EventConditions,
+    Stage,
+    LogEntry,
+    Objective,
+    Target,
+    Alias,
+}
+
+impl Quest {
+    /// Reads a QUST record. `localized` is whether the plugin it comes from stores its text in string tables.
+    pub fn from_record(record: &Record, localized: bool) -> io::Result<Self> {
+        if &record.code != b"QUST" {
+            return Err(invalid("not a QUST record"));
+        }
+
+        let mut quest = Self {
+            form_id: record.form_id,
+            editor_id: String::new(),
+            name: None,
+            scripts: None,
+            flags: 0,
+            priority: 0,
+            form_version: 0,
+            unknown: 0,
+            quest_type: 0,
+            event: None,
+            text_globals: vec![],
+            filter: String::new(),
+            dialogue_conditions: vec![],
+            event_conditions: vec![],
+            stages: vec![],
+            objectives: vec![],
+            next_alias: 0,
+            aliases: vec![],
+            other: vec![],
+        };
+
+        let mut section = Section::DialogueConditions;
+
+        for subrecord in record.subrecords()? {
+            let code = &subrecord.code;
+
+            // Subrecords that start a new part of the quest, whatever part came before.
+            match code {
+                b"INDX" => {
+                    let index = subrecord
+                        .data
+                        .get(..2)
+                        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]));
+                    quest.stages.push(Stage {
+                        index: index.unwrap_or(0),
+                        flags: subrecord.data.get(2).copied().unwrap_or(0),
+                        unknown: subrecord.data.get(3).copied().unwrap_or(0),
+                        log_entries: vec![],
+                    });
+                    section = Section::Stage;
+                    continue;
+                }
+                b"QOBJ" => {
+                    let index = subrecord
+                        .data
+                        .get(..2)
+                        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]));
+                    quest.objectives.push(Objective {
+                        index: index.unwrap_or(0),
+                        ..Objective::default()
+                    });
+                    section = Section::Objective;
+                    continue;
+                }
+                b"ALST" | b"ALLS" => {
+                    let id = subrecord.as_u32().unwrap_or(0);
+                    quest.aliases.push(Alias::new(id, code == b"ALLS"));
+                    section = Section::Alias;
+                    continue;
+                }
+                b"ANAM" => {
+                    quest.next_alias = subrecord.as_u32().unwrap_or(0);
+                    continue;
+                }
+                _ => {}
+            }
+
+            match section {
+                Section::DialogueConditions | Section::EventConditions => match code {
+                    b"EDID" => quest.editor_id = subrecord.as_string(),
+                    b"VMAD" => quest.scripts = Some(Vmad::parse(&subrecord, *b"QUST")?),
+                    b"FULL" => quest.name = Some(Text::read(&subrecord, localized)),
+                    b"DNAM" => {
+                        let data = &subrecord.data;
+                        if data.len() < 12 {
+                            return Err(invalid("QUST DNAM is truncated"));
+                        }
+                        quest.flags = u16::from_le_bytes([data[0], data[1]]);
+                        quest.priority = data[2];
+                        quest.form_version = data[3];
+                        quest.unknown = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+                        quest.quest_type = u32::from_le_bytes([data[8], data[9], data[10], data[11]]);
+                    }
+                    b"ENAM" => {
+                        let mut event = [0; 4];
+                        for (byte, value) in event.iter_mut().zip(&subrecord.data) {
+                            *byte = *value;
+                        }
+                        quest.event = Some(event);
+                    }
+                    b"QTGL" => quest.text_globals.extend(subrecord.as_u32()),
+                    b"FLTR" => quest.filter = subrecord.as_string(),
+                    b"NEXT" => section = Section::EventConditions,
+                    b"CTDA" | b"CIS1" | b"CIS2" => {
+                        let conditions = match section {
+                            Section::EventConditions => &mut quest.event_conditions,
+                            _ => &mut quest.dialogue_conditions,
+                        };
+                        read_condition(conditions, &subrecord)?;
+                    }
+                    _ => quest.other.push(subrecord),
+                },
+                Section::Stage | Section::LogEntry => {
+                    let stage = quest.stages.last_mut().expect("stages start with INDX");
+
+                    if code == b"QSDT" {
+                        stage.log_entries.push(LogEntry {
+                            flags: subrecord.data.first().copied().unwrap_or(0),
+                            ..LogEntry::default()
+                        });
+                        section = Section::LogEntry;
+                        continue;
+                    }
+
+                    // Stages without log entries have nothing else to store.
+                    let entry = match stage.log_entries.last_mut() {
+                        Some(entry) => entry,
+                        None => {
+                            stage.log_entries.push(LogEntry::default());
+                            stage.log_entries.last_mut().unwrap()
+                        }
+                    };
+
+                    match code {
+                        b"CTDA" | b"CIS1" | b"CIS2" => read_condition(&mut entry.conditions, &subrecord)?,
+                        b"CNAM" => entry.text = Some(Text::read(&subrecord, localized)),
+                        b"NAM0" => entry.next_quest = subrecord.as_u32(),
+                        _ => entry.other.push(subrecord),
+                    }
+                }
+                Section::Objective | Section::Target => {
+                    let objective = quest.objectives.last_mut().expect("objectives start with QOBJ");
+
+                    match code {
+                        b"FNAM" => objective.flags = subrecord.as_u32().unwrap_or(0),
+                        b"NNAM" => objective.text = Some(Text::read(&subrecord, localized)),
+                        b"QSTA" => {
+                            let data = &subrecord.data;
+                            let byte = |index: usize| data.get(index).copied().unwrap_or(0);
+                            objective.targets.push(ObjectiveTarget {
+                                alias: subrecord.as_u32().unwrap_or(0) as i32,
+                                flags: byte(4),
+                                unknown: [byte(5), byte(6), byte(7)],
+                                conditions: vec![],
+                            });
+                            section = Section::Target;
+                        }
+                        b"CTDA" | b"CIS1" | b"CIS2" => match objective.targets.last_mut() {
+                            Some(target) => read_condition(&mut target.conditions, &subrecord)?,
+                            None => objective.other.push(subrecord),
+                        },
+                        _ => objective.other.push(subrecord),
+                    }
+                }
+                Section::Alias => {
+                    let alias = quest.aliases.last_mut().expect("aliases start with ALST or ALLS");
+
+                    match code {
+                        b"ALID" => alias.name = subrecord.as_string(),
+                        b"FNAM" => alias.flags = subrecord.as_u32().unwrap_or(0),
+                        b"ALFI" => alias.forced_into = subrecord.as_u32(),
+                        b"CTDA" | b"CIS1" | b"CIS2" => read_condition(&mut alias.conditions, &subrecord)?,
+                        b"ALED" => {}
+                        _ => {
+                            if !alias.fill.read(&subrecord) {
+                                alias.other.push(subrecord);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(quest)
+    }
+
+    /// The ID to give a new alias, which is then counted as used.
+    pub fn take_alias_id(&mut self) -> u32 {
+        let used = self.aliases.iter().map(|alias| alias.id + 1).max().unwrap_or(0);
+        let id = self.next_alias.max(used);
+        self.next_alias = id + 1;
+        id
+    }
+
+    pub fn alias_name(&self, id: i32) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|alias| alias.id as i32 == id)
+            .map(|alias| alias.name.as_str())
+    }
+
+    /// The quest's text fields: its name, log entries and objectives, for resolving string table IDs.
+    pub fn texts_mut(&mut self) -> impl Iterator<Item = &mut Text> {
+        let entries = self.stages.iter_mut().flat_map(|stage| stage.log_entries.iter_mut());
+
+        self.name
+            .iter_mut()
+            .chain(entries.filter_map(|entry| entry.text.as_mut()))
+            .chain(
+                self.objectives
+                    .iter_mut()
+                    .filter_map(|objective| objective.text.as_mut()),
+            )
+    }
+
+    pub fn subrecords(&self) -> Vec<Subrecord> {
+        let u32 = |code: &[u8; 4], value: u32| Subrecord::new(*code, value.to_le_bytes().to_vec());
+        let mut subrecords = vec![Subrecord::string(*b"EDID", &self.editor_id)];
+
+        subrecords.extend(self.scripts.as_ref().map(Vmad::subrecord));
+        subrecords.extend(self.name.as_ref().map(|name| name.subrecord(*b"FULL")));
+
+        let mut dnam = self.flags.to_le_bytes().to_vec();
+        dnam.extend_from_slice(&[self.priority, self.form_version]);
+        dnam.extend_from_slice(&self.unknown.to_le_bytes());
+        dnam.extend_from_slice(&self.quest_type.to_le_bytes());
+        subrecords.push(Subrecord::new(*b"DNAM", dnam));
+
+        subrecords.extend(self.event.map(|event| Subrecord::new(*b"ENAM", event.to_vec())));
+        subrecords.extend(self.text_globals.iter().map(|&global| u32(b"QTGL", global)));
+
+        if !self.filter.is_empty() {
+            subrecords.push(Subrecord::string(*b"FLTR", &self.filter));
+        }
+
+        subrecords.extend(self.other.iter().cloned());
+        subrecords.extend(self.dialogue_conditions.iter().flat_map(Condition::subrecords));
+        subrecords.push(Subrecord::new(*b"NEXT", vec![]));
+        subrecords.extend(self.event_conditions.iter().flat_map(Condition::subrecords));
+
+        for stage in &self.stages {
+            let mut indx = stage.index.to_le_bytes().to_vec();
+            indx.extend_from_slice(&[stage.flags, stage.unknown]);
+            subrecords.push(Subrecord::new(*b"INDX", indx));
+
+            for entry in &stage.log_entries {
+                subrecords.push(Subrecord::new(*b"QSDT", vec![entry.flags]));
+                subrecords.extend(entry.conditions.iter().flat_map(Condition::subrecords));
+                subrecords.extend(entry.text.as_ref().map(|text| text.subrecord(*b"CNAM")));
+                subrecords.extend(entry.next_quest.map(|quest| u32(b"NAM0", quest)));
+                subrecords.extend(entry.other.iter().cloned());
+            }
+        }
+
+        for objective in &self.objectives {
+            subrecords.push(Subrecord::new(*b"QOBJ", objective.index.to_le_bytes().to_vec()));
+            subrecords.push(u32(b"FNAM", objective.flags));
+            subrecords.extend(objective.text.as_ref().map(|text| text.subrecord(*b"NNAM")));
+
+            for target in &objective.targets {
+                let mut qsta = target.alias.to_le_bytes().to_vec();
+                qsta.push(target.flags);
+                qsta.extend_from_slice(&target.unknown);
+                subrecords.push(Subrecord::new(*b"QSTA", qsta));
+                subrecords.extend(target.conditions.iter().flat_map(Condition::subrecords));
+            }
+
+            subrecords.extend(objective.other.iter().cloned());
+        }
+
+        subrecords.push(u32(b"ANAM", self.next_alias));
+
+        for alias in &self.aliases {
+            subrecords.push(u32(if alias.is_location { b"ALLS" } else { b"ALST" }, alias.id));
+            subrecords.push(Subrecord::string(*b"ALID", &alias.name));
+            subrecords.push(u32(b"FNAM", alias.flags));
+            subrecords.extend(alias.forced_into.map(|forced_into| u32(b"ALFI", forced_into)));
+            subrecords.extend(alias.fill.subrecords());
+            subrecords.extend(alias.conditions.iter().flat_map(Condition::subrecords));
+            subrecords.extend(alias.other.iter().cloned());
+            subrecords.push(Subrecord::new(*b"ALED", vec![]));
+        }
+
+        subrecords
+    }
+
+    /// Replaces the record's subrecords with the quest's.
+    pub fn write(&self, record: &mut Record) {
+        record.set_subrecords(&self.subrecords());
+    }
+}
+
+fn read_condition(conditions: &mut Vec<Condition>, subrecord: &Subrecord) -> io::Result<()> {
+    match &subrecord.code {
+        b"CTDA" => conditions.push(Condition::parse(subrecord)?),
+        _ => {
+            if let Some(condition) = conditions.last_mut() {
+                condition.add_string(subrecord);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esp::{Comparison, ConditionValue};
+
+    fn condition(function: u16) -> Condition {
+        Condition {
+            comparison: Comparison::Equal,
+            value: ConditionValue::Number(1.0),
+            function,
+            ..Condition::default()
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut conditioned_alias = Alias::new(1, false);
+        conditioned_alias.name = "Courier".to_string();
+        conditioned_alias.flags = alias_flags::OPTIONAL | alias_flags::ALLOW_DEAD;
+        conditioned_alias.conditions = vec![condition(72)];
+        conditioned_alias.other = vec![Subrecord::new(*b"VTCK", vec![1, 2, 3, 4])];
+
+        let mut forced_alias = Alias::new(0, false);
+        forced_alias.name = "Chest".to_string();
+        forced_alias.fill = AliasFill::ForcedReference(0x0001_0C3A);
+
+        let mut created_alias = Alias::new(2, false);
+        created_alias.fill = AliasFill::CreateReference {
+            object: 0x0000_000F,
+            at: 0,
+            level: 4,
+        };
+
+        let quest = Quest {
+            form_id: 0x0000_0800,
+            editor_id: "TestQuest".to_string(),
+            name: Some(Text::Inline("A Test".to_string())),
+            scripts: Some(Vmad::default()),
+            flags: quest_flags::START_GAME_ENABLED | quest_flags::RUN_ONCE,
+            priority: 40,
+            form_version: 0,
+            unknown: 0,
+            quest_type: 6,
+            event: None,
+            text_globals: vec![0x0000_0039],
+            filter: "Tests\\".to_string(),
+            dialogue_conditions: vec![condition(58)],
+            event_conditions: vec![],
+            stages: vec![
+                Stage {
+                    index: 10,
+                    flags: stage_flags::RUN_ON_START,
+                    unknown: 0,
+                    log_entries: vec![LogEntry {
+                        text: Some(Text::Inline("I should find the courier.".to_string())),
+                        ..LogEntry::default()
+                    }],
+                },
+                Stage {
+                    index: 200,
+                    flags: 0,
+                    unknown: 0,
+                    log_entries: vec![LogEntry {
+                        flags: log_entry_flags::COMPLETE_QUEST,
+                        conditions: vec![condition(1)],
+                        text: Some(Text::Inline("Done.".to_string())),
+                        next_quest: Some(0x0000_0801),
+                        other: vec![],
+                    }],
+                },
+            ],
+            objectives: vec![Objective {
+                index: 10,
+                flags: 0,
+                text: Some(Text::Inline("Find the courier".to_string())),
+                targets: vec![ObjectiveTarget {
+                    alias: 1,
+                    conditions: vec![condition(42)],
+                    ..ObjectiveTarget::default()
+                }],
+                other: vec![],
+            }],
+            next_alias: 3,
+            aliases: vec![forced_alias, conditioned_alias, created_alias],
+            other: vec![],
+        };
+
+        let record = Record::new(*b"QUST", quest.form_id, &quest.subrecords());
+        assert_eq!(Quest::from_record(&record, false).unwrap(), quest);
+    }
+
+    #[test]
+    fn localized_text() {
+        let record = Record::new(
+            *b"QUST",
+            0x0000_0800,
+            &[
+                Subrecord::string(*b"EDID", "LocalizedQuest"),
+                Subrecord::new(*b"FULL", 0x0000_1234u32.to_le_bytes().to_vec()),
+                Subrecord::new(*b"DNAM", vec![0; 12]),
+            ],
+        );
+
+        let quest = Quest::from_record(&record, true).unwrap();
+        assert_eq!(quest.name, Some(Text::Localized(0x1234)));
+        assert_eq!(quest.subrecords()[1], record.subrecord(*b"FULL").unwrap());
+    }
+}
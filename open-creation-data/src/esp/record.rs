@@ -0,0 +1,270 @@
+use super::{invalid, record_flags, Code};
+
+use std::io::{self, Read, Write};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subrecord {
+    pub code: Code,
+    pub data: Vec<u8>,
+}
+
+impl Subrecord {
+    pub fn new(code: Code, data: Vec<u8>) -> Self {
+        Self { code, data }
+    }
+
+    /// Interprets the data as a null-terminated string.
+    pub fn as_string(&self) -> String {
+        let end = self.data.iter().position(|&c| c == 0).unwrap_or(self.data.len());
+        self.data[..end].iter().map(|&c| c as char).collect()
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        self.data
+            .get(..4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        self.as_u32().map(f32::from_bits)
+    }
+
+    /// Builds a null-terminated string subrecord.
+    pub fn string(code: Code, value: &str) -> Self {
+        let mut data: Vec<u8> = value.chars().map(|c| c as u8).collect();
+        data.push(0);
+        Self { code, data }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub code: Code,
+    pub flags: u32,
+    pub form_id: u32,
+    pub vc_info: u32,
+    pub version: u16,
+    pub unknown: u16,
+    /// Subrecord data as stored in the file, which is zlib compressed when the compressed flag is set.
+    data: Vec<u8>,
+}
+
+impl Record {
+    pub const HEADER_SIZE: usize = 24;
+
+    pub fn new(code: Code, form_id: u32, subrecords: &[Subrecord]) -> Self {
+        let mut record = Self {
+            code,
+            flags: 0,
+            form_id,
+            vc_info: 0,
+            version: 44,
+            unknown: 0,
+            data: vec![],
+        };
+
+        record.set_subrecords(subrecords);
+        record
+    }
+
+    /// Reads a record whose header starts at `bytes[0]`, returning it and the number of bytes consumed.
+    pub fn parse(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        if bytes.len() < Self::HEADER_SIZE {
+            return Err(invalid("truncated record header"));
+        }
+
+        let u32_at = |offset: usize| {
+            u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+        };
+        let u16_at = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+
+        let size = u32_at(4) as usize;
+        let end = Self::HEADER_SIZE + size;
+
+        if bytes.len() < end {
+            return Err(invalid("truncated record"));
+        }
+
+        let record = Self {
+            code: [bytes[0], bytes[1], bytes[2], bytes[3]],
+            flags: u32_at(8),
+            form_id: u32_at(12),
+            vc_info: u32_at(16),
+            version: u16_at(20),
+            unknown: u16_at(22),
+            data: bytes[Self::HEADER_SIZE..end].to_vec(),
+        };
+
+        Ok((record, end))
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.code)?;
+        writer.write_all(&(self.data.len() as u32).to_le_bytes())?;
+        writer.write_all(&self.flags.to_le_bytes())?;
+        writer.write_all(&self.form_id.to_le_bytes())?;
+        writer.write_all(&self.vc_info.to_le_bytes())?;
+        writer.write_all(&self.version.to_le_bytes())?;
+        writer.write_all(&self.unknown.to_le_bytes())?;
+        writer.write_all(&self.data)
+    }
+
+    pub fn size(&self) -> usize {
+        Self::HEADER_SIZE + self.data.len()
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.flags & record_flags::COMPRESSED != 0
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.flags & record_flags::DELETED != 0
+    }
+
+    fn field_data(&self) -> io::Result<Vec<u8>> {
+        if !self.is_compressed() {
+            return Ok(self.data.clone());
+        }
+
+        if self.data.len() < 4 {
+            return Err(invalid("truncated compressed record"));
+        }
+
+        let size = u32::from_le_bytes([self.data[0], self.data[1], self.data[2], self.data[3]]) as usize;
+        let mut data = Vec::with_capacity(size);
+        flate2::read::ZlibDecoder::new(&self.data[4..]).read_to_end(&mut data)?;
+
+        Ok(data)
+    }
+
+    pub fn subrecords(&self) -> io::Result<Vec<Subrecord>> {
+        let data = self.field_data()?;
+        let mut subrecords = vec![];
+        let mut offset = 0;
+        let mut large_size = None;
+
+        while offset + 6 <= data.len() {
+            let code = [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]];
+            let size = u16::from_le_bytes([data[offset + 4], data[offset + 5]]) as usize;
+            offset += 6;
+
+            // An XXXX subrecord carries the real size of the next one, whose own size field is zero.
+            let size = large_size.take().unwrap_or(size);
+
+            if offset + size > data.len() {
+                return Err(invalid("truncated subrecord"));
+            }
+
+            if &code == b"XXXX" && size == 4 {
+                large_size =
+                    Some(
+                        u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+                            as usize,
+                    );
+            } else {
+                subrecords.push(Subrecord::new(code, data[offset..offset + size].to_vec()));
+            }
+
+            offset += size;
+        }
+
+        Ok(subrecords)
+    }
+
+    /// Replaces the record's subrecords, recompressing them if the record is flagged as compressed.
+    pub fn set_subrecords(&mut self, subrecords: &[Subrecord]) {
+        let mut data = vec![];
+
+        for subrecord in subrecords {
+            if subrecord.data.len() > u16::MAX as usize {
+                data.extend_from_slice(b"XXXX");
+                data.extend_from_slice(&4u16.to_le_bytes());
+                data.extend_from_slice(&(subrecord.data.len() as u32).to_le_bytes());
+                data.extend_from_slice(&subrecord.code);
+                data.extend_from_slice(&0u16.to_le_bytes());
+            } else {
+                data.extend_from_slice(&subrecord.code);
+                data.extend_from_slice(&(subrecord.data.len() as u16).to_le_bytes());
+            }
+
+            data.extend_from_slice(&subrecord.data);
+        }
+
+        if self.is_compressed() {
+            let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+            let compressed = encoder.write_all(&data).and_then(|_| encoder.finish());
+
+            if let Ok(compressed) = compressed {
+                let mut stored = (data.len() as u32).to_le_bytes().to_vec();
+                stored.extend_from_slice(&compressed);
+                self.data = stored;
+                return;
+            }
+
+            self.flags &= !record_flags::COMPRESSED;
+        }
+
+        self.data = data;
+    }
+
+    pub fn subrecord(&self, code: Code) -> Option<Subrecord> {
+        self.subrecords()
+            .ok()?
+            .into_iter()
+            .find(|subrecord| subrecord.code == code)
+    }
+
+    pub fn editor_id(&self) -> Option<String> {
+        self.subrecord(*b"EDID").map(|subrecord| subrecord.as_string())
+    }
+
+    /// The model shown for this record, as a normalized data path (`meshes\...`).
+    ///
+    /// Most records store it in MODL. Armor and weapons keep their world models in MOD2-MOD4, and ARMO's MODL
+    /// is an addon form ID rather than a path, so only subrecords that hold a `.nif` path are considered.
+    pub fn model_path(&self) -> Option<String> {
+        let subrecords = self.subrecords().ok()?;
+
+        [*b"MODL", *b"MOD2", *b"MOD3", *b"MOD4"].iter().find_map(|&code| {
+            let path = subrecords.iter().find(|subrecord| subrecord.code == code)?.as_string();
+
+            let path = crate::path::normalize(&path);
+
+            if !path.ends_with(".nif") {
+                None
+            } else if path.starts_with("meshes\\") {
+                Some(path)
+            } else {
+                Some(format!("meshes\\{}", path))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let subrecords = vec![
+            Subrecord::string(*b"EDID", "TestStatic"),
+            Subrecord::string(*b"MODL", "Clutter\\Bucket01.nif"),
+            Subrecord::new(*b"DATA", vec![1; 70_000]),
+        ];
+
+        let mut record = Record::new(*b"STAT", 0x0100_0800, &subrecords);
+        record.flags |= record_flags::COMPRESSED;
+        record.set_subrecords(&subrecords);
+
+        let mut bytes = vec![];
+        record.write(&mut bytes).unwrap();
+
+        let (parsed, consumed) = Record::parse(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert!(parsed.is_compressed());
+        assert_eq!(parsed.form_id, 0x0100_0800);
+        assert_eq!(parsed.subrecords().unwrap(), subrecords);
+        assert_eq!(parsed.editor_id().as_deref(), Some("TestStatic"));
+    }
+}
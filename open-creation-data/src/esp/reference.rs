@@ -0,0 +1,185 @@
+use super::{record_flags, Code, Record, Subrecord};
+
+use std::io;
+
+/// Record types that place an object in a cell.
+pub const REFERENCE_CODES: [Code; 3] = [*b"REFR", *b"ACHR", *b"PGRE"];
+
+/// Base record types that can be placed in a cell. NPC_ is placed as an ACHR and the rest as REFR.
+pub const PLACEABLE_CODES: [Code; 23] = [
+    *b"ACTI", *b"ALCH", *b"AMMO", *b"ARMO", *b"BOOK", *b"CONT", *b"DOOR", *b"FLOR", *b"FURN", *b"IDLM", *b"INGR",
+    *b"KEYM", *b"LIGH", *b"MISC", *b"MSTT", *b"NPC_", *b"SCRL", *b"SLGM", *b"SOUN", *b"STAT", *b"TACT", *b"TREE",
+    *b"WEAP",
+];
+
+/// Where a placed reference sits, in game units and radians, as stored in its DATA subrecord.
+///
+/// Rotations are applied X, then Y, then Z, each clockwise when looking down the axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Placement {
+    pub position: [f32; 3],
+    pub rotation: [f32; 3],
+    pub scale: f32,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Self {
+            position: [0.0; 3],
+            rotation: [0.0; 3],
+            scale: 1.0,
+        }
+    }
+}
+
+impl Placement {
+    /// The DATA subrecord holding the position and rotation.
+    pub fn data_subrecord(&self) -> Subrecord {
+        let data = self
+            .position
+            .iter()
+            .chain(self.rotation.iter())
+            .flat_map(|value| value.to_le_bytes().to_vec())
+            .collect();
+
+        Subrecord::new(*b"DATA", data)
+    }
+
+    /// Writes the placement into a reference record's DATA and XSCL subrecords. XSCL is dropped at a scale of 1,
+    /// which is the default.
+    pub fn write(&self, record: &mut Record) -> io::Result<()> {
+        let mut subrecords = record.subrecords()?;
+
+        match subrecords.iter().position(|subrecord| &subrecord.code == b"DATA") {
+            Some(position) => subrecords[position] = self.data_subrecord(),
+            None => subrecords.push(self.data_subrecord()),
+        }
+
+        let scale = Subrecord::new(*b"XSCL", self.scale.to_le_bytes().to_vec());
+        let has_scale = (self.scale - 1.0).abs() > f32::EPSILON;
+
+        match subrecords.iter().position(|subrecord| &subrecord.code == b"XSCL") {
+            Some(position) if has_scale => subrecords[position] = scale,
+            Some(position) => {
+                subrecords.remove(position);
+            }
+            None if has_scale => {
+                let data = subrecords.iter().position(|subrecord| &subrecord.code == b"DATA");
+                subrecords.insert(data.unwrap_or(subrecords.len()), scale);
+            }
+            None => {}
+        }
+
+        record.set_subrecords(&subrecords);
+        Ok(())
+    }
+}
+
+/// The parts of a REFR, ACHR or PGRE record needed to show it in a cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reference {
+    pub form_id: u32,
+    pub code: Code,
+    /// The form ID of the placed object (NAME).
+    pub base: u32,
+    pub placement: Placement,
+    pub initially_disabled: bool,
+}
+
+impl Reference {
+    /// Reads a reference record, returning `None` for other record types or references without a base object.
+    pub fn from_record(record: &Record) -> Option<Self> {
+        if !REFERENCE_CODES.contains(&record.code) {
+            return None;
+        }
+
+        let subrecords = record.subrecords().ok()?;
+        let find = |code: &[u8; 4]| subrecords.iter().find(|subrecord| &subrecord.code == code);
+
+        let base = find(b"NAME")?.as_u32()?;
+        let mut placement = Placement::default();
+
+        if let Some(data) = find(b"DATA") {
+            let floats: Vec<f32> = data
+                .data
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect();
+
+            if floats.len() >= 6 {
+                placement.position.copy_from_slice(&floats[..3]);
+                placement.rotation.copy_from_slice(&floats[3..6]);
+            }
+        }
+
+        if let Some(scale) = find(b"XSCL").and_then(|subrecord| subrecord.as_f32()) {
+            placement.scale = scale;
+        }
+
+        Some(Self {
+            form_id: record.form_id,
+            code: record.code,
+            base,
+            placement,
+            initially_disabled: record.flags & record_flags::INITIALLY_DISABLED != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_record() {
+        let placement = Placement {
+            position: [1024.0, -512.5, 64.0],
+            rotation: [0.0, 0.1, 3.1],
+            scale: 1.0,
+        };
+
+        let record = Record::new(
+            *b"REFR",
+            0x0001_2345,
+            &[
+                Subrecord::new(*b"NAME", 0x0000_0F00u32.to_le_bytes().to_vec()),
+                Subrecord::new(*b"XSCL", 1.5f32.to_le_bytes().to_vec()),
+                placement.data_subrecord(),
+            ],
+        );
+
+        let reference = Reference::from_record(&record).unwrap();
+        assert_eq!(reference.base, 0x0000_0F00);
+        assert_eq!(reference.placement.position, placement.position);
+        assert_eq!(reference.placement.rotation, placement.rotation);
+        assert_eq!(reference.placement.scale, 1.5);
+        assert!(!reference.initially_disabled);
+
+        assert!(Reference::from_record(&Record::new(*b"STAT", 0x0000_0F00, &[])).is_none());
+    }
+
+    #[test]
+    fn write() {
+        let mut record = Record::new(
+            *b"REFR",
+            0x0001_2345,
+            &[Subrecord::new(*b"NAME", 0x0000_0F00u32.to_le_bytes().to_vec())],
+        );
+
+        let placement = Placement {
+            position: [10.0, 20.0, 30.0],
+            rotation: [0.5, 0.0, 1.0],
+            scale: 2.0,
+        };
+        placement.write(&mut record).unwrap();
+        assert_eq!(Reference::from_record(&record).unwrap().placement, placement);
+
+        let unscaled = Placement {
+            scale: 1.0,
+            ..placement
+        };
+        unscaled.write(&mut record).unwrap();
+        assert!(record.subrecord(*b"XSCL").is_none());
+        assert_eq!(Reference::from_record(&record).unwrap().placement, unscaled);
+    }
+}
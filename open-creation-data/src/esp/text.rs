@@ -0,0 +1,180 @@
+use super::{invalid, Code, Subrecord};
+use crate::vfs::VirtualFileSystem;
+
+use std::{collections::HashMap, fmt, io, path::Path};
+
+/// A text field such as a name or a journal entry. Localized plugins store an ID into their string tables in
+/// place of the text, which [`Text::resolve`] looks up. Resolved text is written inline, as plugins made by the
+/// editor are not localized.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Text {
+    Inline(String),
+    Localized(u32),
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Text::Inline(String::new())
+    }
+}
+
+impl Text {
+    /// Reads a text subrecord from a plugin that is localized or not.
+    pub fn read(subrecord: &Subrecord, localized: bool) -> Self {
+        match subrecord.as_u32() {
+            Some(id) if localized => Text::Localized(id),
+            _ => Text::Inline(subrecord.as_string()),
+        }
+    }
+
+    pub fn subrecord(&self, code: Code) -> Subrecord {
+        match self {
+            Text::Inline(text) => Subrecord::string(code, text),
+            Text::Localized(id) => Subrecord::new(code, id.to_le_bytes().to_vec()),
+        }
+    }
+
+    /// Replaces a string table ID with its text, if the table has it.
+    pub fn resolve(&mut self, strings: &StringTable) {
+        if let Text::Localized(id) = self {
+            if let Some(text) = strings.get(*id) {
+                *self = Text::Inline(text.to_string());
+            }
+        }
+    }
+}
+
+/// The strings of a localized plugin, from its `.STRINGS`, `.DLSTRINGS` and `.ILSTRINGS` files. IDs are unique
+/// across the three.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StringTable {
+    strings: HashMap<u32, String>,
+}
+
+impl StringTable {
+    /// The string table files of a plugin, with whether their strings are prefixed by their length. Names and
+    /// other short strings are in `.STRINGS`, descriptions and books in `.DLSTRINGS`, and dialogue in
+    /// `.ILSTRINGS`.
+    const FILES: [(&'static str, bool); 3] = [("STRINGS", false), ("DLSTRINGS", true), ("ILSTRINGS", true)];
+
+    /// Reads a plugin's string tables in a language, such as `English`, from `Strings/<plugin>_<language>.*`.
+    pub fn load(vfs: &VirtualFileSystem, plugin: &str, language: &str) -> io::Result<Self> {
+        let stem = Path::new(plugin)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut table = Self::default();
+
+        for (extension, length_prefixed) in &Self::FILES {
+            let path = format!("strings/{}_{}.{}", stem, language, extension);
+            table.read(&vfs.read(&path)?, *length_prefixed)?;
+        }
+
+        Ok(table)
+    }
+
+    /// Adds the strings of one string table file: a count and data size, a directory of IDs and offsets into the
+    /// data, and the data. Strings are null-terminated, and also prefixed by their length in `.DLSTRINGS` and
+    /// `.ILSTRINGS`.
+    pub fn read(&mut self, bytes: &[u8], length_prefixed: bool) -> io::Result<()> {
+        let u32_at = |offset: usize| {
+            bytes
+                .get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+                .ok_or_else(|| invalid("truncated string table"))
+        };
+
+        let count = u32_at(0)?;
+        let data_start = 8 + count * 8;
+        let data = bytes
+            .get(data_start..)
+            .ok_or_else(|| invalid("truncated string table"))?;
+
+        for entry in 0..count {
+            let id = u32_at(8 + entry * 8)? as u32;
+            let mut offset = u32_at(12 + entry * 8)?;
+
+            if length_prefixed {
+                offset += 4;
+            }
+
+            let string = data
+                .get(offset..)
+                .ok_or_else(|| invalid("string table offset is out of range"))?;
+            let end = string.iter().position(|&c| c == 0).unwrap_or(string.len());
+            self.strings
+                .insert(id, string[..end].iter().map(|&c| c as char).collect());
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, id: u32) -> Option<&str> {
+        self.strings.get(&id).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Text::Inline(text) => f.write_str(text),
+            Text::Localized(id) => write!(f, "<string {:08X}>", id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a string table file with the given strings, prefixing each with its length if asked.
+    fn table(strings: &[(u32, &str)], length_prefixed: bool) -> Vec<u8> {
+        let mut directory = vec![];
+        let mut data = vec![];
+
+        for (id, string) in strings {
+            directory.extend_from_slice(&id.to_le_bytes());
+            directory.extend_from_slice(&(data.len() as u32).to_le_bytes());
+
+            if length_prefixed {
+                data.extend_from_slice(&(string.len() as u32 + 1).to_le_bytes());
+            }
+            data.extend_from_slice(string.as_bytes());
+            data.push(0);
+        }
+
+        let mut bytes = (strings.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&directory);
+        bytes.extend_from_slice(&data);
+        bytes
+    }
+
+    #[test]
+    fn resolves_localized_text() {
+        let mut strings = StringTable::default();
+        strings
+            .read(&table(&[(1, "Iron Sword"), (2, "Whiterun")], false), false)
+            .unwrap();
+        strings.read(&table(&[(3, "Hello there.")], true), true).unwrap();
+        assert_eq!(strings.len(), 3);
+
+        let mut text = Text::Localized(3);
+        text.resolve(&strings);
+        assert_eq!(text, Text::Inline("Hello there.".to_string()));
+
+        let mut missing = Text::Localized(4);
+        missing.resolve(&strings);
+        assert_eq!(missing, Text::Localized(4));
+
+        assert!(strings.read(&[1, 0, 0, 0], false).is_err());
+    }
+}
@@ -0,0 +1,680 @@
+//! Papyrus scripts attached to records through their VMAD subrecord, and the values of their properties.
+//!
+//! Quests, INFOs, packages and perks follow their scripts with fragment data: the script file their fragments
+//! were compiled into and the fragment functions run on events. A quest's also holds the scripts attached to
+//! its aliases.
+
+use super::{
+    fields::{put_string16, put_u16, put_u32, Fields},
+    invalid, Code, Record, Subrecord,
+};
+
+use std::io;
+
+/// The events INFO fragments run on, in the order of their flags.
+pub const INFO_FRAGMENT_EVENTS: [&str; 2] = ["Begin", "End"];
+/// The events package fragments run on, in the order of their flags.
+pub const PACKAGE_FRAGMENT_EVENTS: [&str; 3] = ["Begin", "End", "Change"];
+
+/// A property's reference to a form, or to a quest alias when `alias` is not -1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ObjectValue {
+    pub form_id: u32,
+    pub alias: i16,
+}
+
+impl Default for ObjectValue {
+    fn default() -> Self {
+        Self { form_id: 0, alias: -1 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyValue {
+    None,
+    Object(ObjectValue),
+    String(String),
+    Int(i32),
+    Float(f32),
+    Bool(bool),
+    ObjectArray(Vec<ObjectValue>),
+    StringArray(Vec<String>),
+    IntArray(Vec<i32>),
+    FloatArray(Vec<f32>),
+    BoolArray(Vec<bool>),
+}
+
+impl PropertyValue {
+    fn kind(&self) -> u8 {
+        match self {
+            PropertyValue::None => 0,
+            PropertyValue::Object(_) => 1,
+            PropertyValue::String(_) => 2,
+            PropertyValue::Int(_) => 3,
+            PropertyValue::Float(_) => 4,
+            PropertyValue::Bool(_) => 5,
+            PropertyValue::ObjectArray(_) => 11,
+            PropertyValue::StringArray(_) => 12,
+            PropertyValue::IntArray(_) => 13,
+            PropertyValue::FloatArray(_) => 14,
+            PropertyValue::BoolArray(_) => 15,
+        }
+    }
+
+    /// The Papyrus name of the value's type.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PropertyValue::None => "None",
+            PropertyValue::Object(_) => "Object",
+            PropertyValue::String(_) => "String",
+            PropertyValue::Int(_) => "Int",
+            PropertyValue::Float(_) => "Float",
+            PropertyValue::Bool(_) => "Bool",
+            PropertyValue::ObjectArray(_) => "Object[]",
+            PropertyValue::StringArray(_) => "String[]",
+            PropertyValue::IntArray(_) => "Int[]",
+            PropertyValue::FloatArray(_) => "Float[]",
+            PropertyValue::BoolArray(_) => "Bool[]",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Property {
+    pub name: String,
+    /// 1 when the property has been edited, 3 when it has been reset to its default and is to be removed.
+    pub status: u8,
+    pub value: PropertyValue,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Script {
+    pub name: String,
+    /// 0 for a local script, 1 when it is inherited and edited, 3 when it is inherited and removed.
+    pub status: u8,
+    pub properties: Vec<Property>,
+}
+
+/// A fragment function: one function of the script file a record's fragments are compiled into.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fragment {
+    pub unknown: u8,
+    pub script: String,
+    pub function: String,
+}
+
+/// The fragments of an INFO or package, run on the events in [`INFO_FRAGMENT_EVENTS`] or
+/// [`PACKAGE_FRAGMENT_EVENTS`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventFragments {
+    pub version: u8,
+    pub file_name: String,
+    /// The fragment run on each event, if any.
+    pub events: Vec<Option<Fragment>>,
+}
+
+/// A quest fragment, run when the quest is set to a stage.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StageFragment {
+    pub stage: u16,
+    pub unknown: i16,
+    /// The index of the stage's log entry the fragment belongs to.
+    pub log_entry: i32,
+    pub fragment: Fragment,
+}
+
+/// The scripts attached to one of a quest's aliases.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AliasScripts {
+    /// The alias, as an object value whose form ID is the quest's.
+    pub alias: ObjectValue,
+    pub version: i16,
+    pub object_format: i16,
+    pub scripts: Vec<Script>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QuestFragments {
+    pub version: u8,
+    pub file_name: String,
+    pub stages: Vec<StageFragment>,
+    pub aliases: Vec<AliasScripts>,
+}
+
+/// A perk fragment, run by one of the perk's entry points.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PerkFragment {
+    pub index: u16,
+    pub unknown: i16,
+    pub fragment: Fragment,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PerkFragments {
+    pub version: u8,
+    pub file_name: String,
+    pub fragments: Vec<PerkFragment>,
+}
+
+/// The data following a VMAD's scripts, decoded for the record types that have fragments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fragments {
+    /// Data of other record types, or data that could not be decoded, as stored. Usually empty.
+    Raw(Vec<u8>),
+    Info(EventFragments),
+    Package(EventFragments),
+    Quest(QuestFragments),
+    Perk(PerkFragments),
+}
+
+impl Default for Fragments {
+    fn default() -> Self {
+        Fragments::Raw(vec![])
+    }
+}
+
+impl Fragments {
+    /// Decodes the fragment data of a record type, keeping it as stored if the type has none or it does not
+    /// decode exactly.
+    fn parse(data: &[u8], code: Code, object_format: i16) -> Self {
+        if data.is_empty() {
+            return Fragments::default();
+        }
+
+        let mut fields = Fields::new(data, "VMAD fragments");
+
+        let fragments = match &code {
+            b"INFO" => read_event_fragments(&mut fields, INFO_FRAGMENT_EVENTS.len()).map(Fragments::Info),
+            b"PACK" => read_event_fragments(&mut fields, PACKAGE_FRAGMENT_EVENTS.len()).map(Fragments::Package),
+            b"QUST" => read_quest_fragments(&mut fields, object_format).map(Fragments::Quest),
+            b"PERK" => read_perk_fragments(&mut fields).map(Fragments::Perk),
+            _ => return Fragments::Raw(data.to_vec()),
+        };
+
+        match fragments {
+            Ok(fragments) if fields.is_empty() => fragments,
+            _ => Fragments::Raw(data.to_vec()),
+        }
+    }
+
+    fn write(&self, data: &mut Vec<u8>, object_format: i16) {
+        match self {
+            Fragments::Raw(bytes) => data.extend_from_slice(bytes),
+            Fragments::Info(fragments) | Fragments::Package(fragments) => {
+                data.push(fragments.version);
+                let flags = fragments
+                    .events
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, fragment)| fragment.is_some())
+                    .fold(0, |flags, (index, _)| flags | 1 << index);
+                data.push(flags);
+                put_string16(data, &fragments.file_name);
+
+                for fragment in fragments.events.iter().flatten() {
+                    write_fragment(data, fragment);
+                }
+            }
+            Fragments::Quest(fragments) => {
+                data.push(fragments.version);
+                put_u16(data, fragments.stages.len() as u16);
+                put_string16(data, &fragments.file_name);
+
+                for stage in &fragments.stages {
+                    put_u16(data, stage.stage);
+                    data.extend_from_slice(&stage.unknown.to_le_bytes());
+                    data.extend_from_slice(&stage.log_entry.to_le_bytes());
+                    write_fragment(data, &stage.fragment);
+                }
+
+                put_u16(data, fragments.aliases.len() as u16);
+
+                for alias in &fragments.aliases {
+                    write_object(data, &alias.alias, object_format);
+                    data.extend_from_slice(&alias.version.to_le_bytes());
+                    data.extend_from_slice(&alias.object_format.to_le_bytes());
+                    write_scripts(data, &alias.scripts, alias.version, alias.object_format);
+                }
+            }
+            Fragments::Perk(fragments) => {
+                data.push(fragments.version);
+                put_string16(data, &fragments.file_name);
+                put_u16(data, fragments.fragments.len() as u16);
+
+                for fragment in &fragments.fragments {
+                    put_u16(data, fragment.index);
+                    data.extend_from_slice(&fragment.unknown.to_le_bytes());
+                    write_fragment(data, &fragment.fragment);
+                }
+            }
+        }
+    }
+}
+
+/// A decoded VMAD subrecord.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vmad {
+    pub version: i16,
+    /// 1 if object values store their form ID first, 2 if they store it last.
+    pub object_format: i16,
+    pub scripts: Vec<Script>,
+    pub fragments: Fragments,
+}
+
+impl Default for Vmad {
+    fn default() -> Self {
+        Self {
+            version: 5,
+            object_format: 2,
+            scripts: vec![],
+            fragments: Fragments::default(),
+        }
+    }
+}
+
+impl Vmad {
+    /// Reads the VMAD of a record of type `code`, which decides the kind of fragment data that follows the
+    /// scripts.
+    pub fn parse(vmad: &Subrecord, code: Code) -> io::Result<Self> {
+        let mut fields = Fields::new(&vmad.data, "VMAD");
+        let version = fields.i16()?;
+        let object_format = fields.i16()?;
+        let scripts = read_scripts(&mut fields, version, object_format)?;
+
+        Ok(Self {
+            version,
+            object_format,
+            scripts,
+            fragments: Fragments::parse(fields.rest(), code, object_format),
+        })
+    }
+
+    /// Reads a record's VMAD, if it has one.
+    pub fn read(record: &Record) -> io::Result<Option<Self>> {
+        match record.subrecord(*b"VMAD") {
+            Some(vmad) => Self::parse(&vmad, record.code).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn subrecord(&self) -> Subrecord {
+        let mut data = vec![];
+        data.extend_from_slice(&self.version.to_le_bytes());
+        data.extend_from_slice(&self.object_format.to_le_bytes());
+        write_scripts(&mut data, &self.scripts, self.version, self.object_format);
+        self.fragments.write(&mut data, self.object_format);
+        Subrecord::new(*b"VMAD", data)
+    }
+
+    /// Replaces a record's VMAD with this one, adding it after the editor ID if the record has none.
+    pub fn write(&self, record: &mut Record) -> io::Result<()> {
+        let mut subrecords = record.subrecords()?;
+
+        match subrecords.iter().position(|subrecord| &subrecord.code == b"VMAD") {
+            Some(index) => subrecords[index] = self.subrecord(),
+            None => {
+                let after_editor_id = subrecords
+                    .iter()
+                    .position(|subrecord| &subrecord.code == b"EDID")
+                    .map_or(0, |index| index + 1);
+                subrecords.insert(after_editor_id, self.subrecord());
+            }
+        }
+
+        record.set_subrecords(&subrecords);
+        Ok(())
+    }
+
+    /// The names of the attached scripts, including those attached to quest aliases.
+    pub fn script_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.scripts.iter().map(|script| script.name.as_str()).collect();
+
+        if let Fragments::Quest(fragments) = &self.fragments {
+            for alias in &fragments.aliases {
+                names.extend(alias.scripts.iter().map(|script| script.name.as_str()));
+            }
+        }
+
+        names
+    }
+}
+
+fn read_scripts(fields: &mut Fields, version: i16, object_format: i16) -> io::Result<Vec<Script>> {
+    let script_count = fields.u16()?;
+    let mut scripts = Vec::with_capacity(script_count as usize);
+
+    for _ in 0..script_count {
+        let name = fields.string16()?;
+        let status = if version >= 4 { fields.u8()? } else { 0 };
+        let property_count = fields.u16()?;
+        let mut properties = Vec::with_capacity(property_count as usize);
+
+        for _ in 0..property_count {
+            let name = fields.string16()?;
+            let kind = fields.u8()?;
+            let status = if version >= 4 { fields.u8()? } else { 1 };
+            let value = read_value(fields, kind, object_format)?;
+            properties.push(Property { name, status, value });
+        }
+
+        scripts.push(Script {
+            name,
+            status,
+            properties,
+        });
+    }
+
+    Ok(scripts)
+}
+
+fn write_scripts(data: &mut Vec<u8>, scripts: &[Script], version: i16, object_format: i16) {
+    put_u16(data, scripts.len() as u16);
+
+    for script in scripts {
+        put_string16(data, &script.name);
+        if version >= 4 {
+            data.push(script.status);
+        }
+        put_u16(data, script.properties.len() as u16);
+
+        for property in &script.properties {
+            put_string16(data, &property.name);
+            data.push(property.value.kind());
+            if version >= 4 {
+                data.push(property.status);
+            }
+            write_value(data, &property.value, object_format);
+        }
+    }
+}
+
+fn read_fragment(fields: &mut Fields) -> io::Result<Fragment> {
+    Ok(Fragment {
+        unknown: fields.u8()?,
+        script: fields.string16()?,
+        function: fields.string16()?,
+    })
+}
+
+fn write_fragment(data: &mut Vec<u8>, fragment: &Fragment) {
+    data.push(fragment.unknown);
+    put_string16(data, &fragment.script);
+    put_string16(data, &fragment.function);
+}
+
+/// Reads INFO or package fragments: a flag for each event that has one, then those fragments in flag order.
+fn read_event_fragments(fields: &mut Fields, event_count: usize) -> io::Result<EventFragments> {
+    let version = fields.u8()?;
+    let flags = fields.u8()?;
+    let file_name = fields.string16()?;
+
+    let mut events = vec![];
+    for index in 0..event_count {
+        events.push(if flags & 1 << index != 0 {
+            Some(read_fragment(fields)?)
+        } else {
+            None
+        });
+    }
+
+    Ok(EventFragments {
+        version,
+        file_name,
+        events,
+    })
+}
+
+fn read_quest_fragments(fields: &mut Fields, object_format: i16) -> io::Result<QuestFragments> {
+    let version = fields.u8()?;
+    let stage_count = fields.u16()?;
+    let file_name = fields.string16()?;
+
+    let mut stages = vec![];
+    for _ in 0..stage_count {
+        stages.push(StageFragment {
+            stage: fields.u16()?,
+            unknown: fields.i16()?,
+            log_entry: fields.i32()?,
+            fragment: read_fragment(fields)?,
+        });
+    }
+
+    let alias_count = fields.u16()?;
+    let mut aliases = vec![];
+    for _ in 0..alias_count {
+        let alias = read_object(fields, object_format)?;
+        let version = fields.i16()?;
+        let object_format = fields.i16()?;
+        let scripts = read_scripts(fields, version, object_format)?;
+
+        aliases.push(AliasScripts {
+            alias,
+            version,
+            object_format,
+            scripts,
+        });
+    }
+
+    Ok(QuestFragments {
+        version,
+        file_name,
+        stages,
+        aliases,
+    })
+}
+
+fn read_perk_fragments(fields: &mut Fields) -> io::Result<PerkFragments> {
+    let version = fields.u8()?;
+    let file_name = fields.string16()?;
+    let count = fields.u16()?;
+
+    let mut fragments = vec![];
+    for _ in 0..count {
+        fragments.push(PerkFragment {
+            index: fields.u16()?,
+            unknown: fields.i16()?,
+            fragment: read_fragment(fields)?,
+        });
+    }
+
+    Ok(PerkFragments {
+        version,
+        file_name,
+        fragments,
+    })
+}
+
+fn read_object(fields: &mut Fields, object_format: i16) -> io::Result<ObjectValue> {
+    match object_format {
+        1 => {
+            let form_id = fields.u32()?;
+            let alias = fields.i16()?;
+            fields.u16()?;
+            Ok(ObjectValue { form_id, alias })
+        }
+        _ => {
+            fields.u16()?;
+            let alias = fields.i16()?;
+            let form_id = fields.u32()?;
+            Ok(ObjectValue { form_id, alias })
+        }
+    }
+}
+
+fn write_object(data: &mut Vec<u8>, object: &ObjectValue, object_format: i16) {
+    match object_format {
+        1 => {
+            put_u32(data, object.form_id);
+            data.extend_from_slice(&object.alias.to_le_bytes());
+            put_u16(data, 0);
+        }
+        _ => {
+            put_u16(data, 0);
+            data.extend_from_slice(&object.alias.to_le_bytes());
+            put_u32(data, object.form_id);
+        }
+    }
+}
+
+fn read_value(fields: &mut Fields, kind: u8, object_format: i16) -> io::Result<PropertyValue> {
+    Ok(match kind {
+        0 => PropertyValue::None,
+        1 => PropertyValue::Object(read_object(fields, object_format)?),
+        2 => PropertyValue::String(fields.string16()?),
+        3 => PropertyValue::Int(fields.i32()?),
+        4 => PropertyValue::Float(fields.f32()?),
+        5 => PropertyValue::Bool(fields.u8()? != 0),
+        11 => PropertyValue::ObjectArray(fields.array(|fields| read_object(fields, object_format))?),
+        12 => PropertyValue::StringArray(fields.array(Fields::string16)?),
+        13 => PropertyValue::IntArray(fields.array(Fields::i32)?),
+        14 => PropertyValue::FloatArray(fields.array(Fields::f32)?),
+        15 => PropertyValue::BoolArray(fields.array(|fields| Ok(fields.u8()? != 0))?),
+        _ => return Err(invalid("VMAD has a property of an unknown type")),
+    })
+}
+
+fn write_value(data: &mut Vec<u8>, value: &PropertyValue, object_format: i16) {
+    match value {
+        PropertyValue::None => {}
+        PropertyValue::Object(object) => write_object(data, object, object_format),
+        PropertyValue::String(string) => put_string16(data, string),
+        PropertyValue::Int(int) => data.extend_from_slice(&int.to_le_bytes()),
+        PropertyValue::Float(float) => data.extend_from_slice(&float.to_le_bytes()),
+        PropertyValue::Bool(bool) => data.push(*bool as u8),
+        PropertyValue::ObjectArray(objects) => {
+            put_u32(data, objects.len() as u32);
+            for object in objects {
+                write_object(data, object, object_format);
+            }
+        }
+        PropertyValue::StringArray(strings) => {
+            put_u32(data, strings.len() as u32);
+            for string in strings {
+                put_string16(data, string);
+            }
+        }
+        PropertyValue::IntArray(ints) => {
+            put_u32(data, ints.len() as u32);
+            for int in ints {
+                data.extend_from_slice(&int.to_le_bytes());
+            }
+        }
+        PropertyValue::FloatArray(floats) => {
+            put_u32(data, floats.len() as u32);
+            for float in floats {
+                data.extend_from_slice(&float.to_le_bytes());
+            }
+        }
+        PropertyValue::BoolArray(bools) => {
+            put_u32(data, bools.len() as u32);
+            data.extend(bools.iter().map(|&bool| bool as u8));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let vmad = Vmad {
+            scripts: vec![Script {
+                name: "TestQuestScript".to_string(),
+                status: 0,
+                properties: vec![
+                    Property {
+                        name: "Target".to_string(),
+                        status: 1,
+                        value: PropertyValue::Object(ObjectValue {
+                            form_id: 0x0001_2EB7,
+                            alias: -1,
+                        }),
+                    },
+                    Property {
+                        name: "Counts".to_string(),
+                        status: 1,
+                        value: PropertyValue::IntArray(vec![1, -2, 3]),
+                    },
+                    Property {
+                        name: "Greeting".to_string(),
+                        status: 1,
+                        value: PropertyValue::String("Hello".to_string()),
+                    },
+                ],
+            }],
+            ..Vmad::default()
+        };
+
+        let subrecord = vmad.subrecord();
+        // Object values in format 2 end with the form ID.
+        let object = subrecord
+            .data
+            .windows(4)
+            .position(|bytes| bytes == [0xB7, 0x2E, 0x01, 0x00]);
+        assert!(object.is_some());
+
+        assert_eq!(Vmad::parse(&subrecord, *b"ACTI").unwrap(), vmad);
+    }
+
+    #[test]
+    fn fragments_round_trip() {
+        let fragment = |function: &str| Fragment {
+            unknown: 1,
+            script: "QF_TestQuest_01000D62".to_string(),
+            function: function.to_string(),
+        };
+
+        let quest = Vmad {
+            fragments: Fragments::Quest(QuestFragments {
+                version: 2,
+                file_name: "QF_TestQuest_01000D62".to_string(),
+                stages: vec![StageFragment {
+                    stage: 10,
+                    unknown: 0,
+                    log_entry: 0,
+                    fragment: fragment("Fragment_0"),
+                }],
+                aliases: vec![AliasScripts {
+                    alias: ObjectValue {
+                        form_id: 0x0100_0D62,
+                        alias: 3,
+                    },
+                    version: 5,
+                    object_format: 2,
+                    scripts: vec![Script {
+                        name: "TestAliasScript".to_string(),
+                        status: 0,
+                        properties: vec![Property {
+                            name: "Count".to_string(),
+                            status: 1,
+                            value: PropertyValue::Int(4),
+                        }],
+                    }],
+                }],
+            }),
+            ..Vmad::default()
+        };
+        assert_eq!(Vmad::parse(&quest.subrecord(), *b"QUST").unwrap(), quest);
+
+        let info = Vmad {
+            fragments: Fragments::Info(EventFragments {
+                version: 2,
+                file_name: "TIF__01000D63".to_string(),
+                events: vec![None, Some(fragment("Fragment_1"))],
+            }),
+            ..Vmad::default()
+        };
+        let subrecord = info.subrecord();
+        // Only the End flag is set.
+        assert_eq!(subrecord.data[7], 2);
+        assert_eq!(Vmad::parse(&subrecord, *b"INFO").unwrap(), info);
+
+        // Data that does not decode as the record type's fragments is kept as stored.
+        let mut subrecord = Vmad::default().subrecord();
+        subrecord.data.extend_from_slice(&[2, 7]);
+        let vmad = Vmad::parse(&subrecord, *b"PERK").unwrap();
+        assert_eq!(vmad.fragments, Fragments::Raw(vec![2, 7]));
+        assert_eq!(vmad.subrecord(), subrecord);
+    }
+}
@@ -0,0 +1,274 @@
+//! Merging leveled lists that several plugins edit, which would otherwise lose every edit but the last plugin's.
+//!
+//! Each plugin's edits are worked out against the version the list was defined with: the entries it adds and
+//! removes, and the settings it changes. The merged list has every plugin's additions and removals, and each
+//! setting from the last plugin to change it.
+
+use crate::esp::{form_ids, group_types, LeveledEntry, LeveledList, LoadOrder, Plugin, LEVELED_CODES, MAX_PLUGINS};
+
+use std::{collections::BTreeSet, io};
+
+/// The entries of `entries` left once each of `removed` has taken out one equal entry.
+fn difference(entries: &[LeveledEntry], removed: &[LeveledEntry]) -> Vec<LeveledEntry> {
+    let mut removed: Vec<&LeveledEntry> = removed.iter().collect();
+
+    entries
+        .iter()
+        .filter(|entry| match removed.iter().position(|removed| removed == entry) {
+            Some(index) => {
+                removed.swap_remove(index);
+                false
+            }
+            None => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Adds the entries of `added` that `entries` does not already have as many of, so that entries added by
+/// several plugins are only added once.
+fn union(entries: &mut Vec<LeveledEntry>, added: &[LeveledEntry]) {
+    let missing = difference(added, entries);
+    entries.extend(missing);
+}
+
+/// A setting from the last version to change it from the first, or the first's.
+fn latest<T: PartialEq>(versions: &[LeveledList], setting: impl Fn(&LeveledList) -> T) -> T {
+    let base = setting(&versions[0]);
+
+    versions[1..]
+        .iter()
+        .rev()
+        .map(&setting)
+        .find(|value| *value != base)
+        .unwrap_or(base)
+}
+
+/// Merges the versions of a list, the first being the one it was defined with and the rest its overrides in
+/// load order. The merged entries are sorted by level.
+pub fn merge(versions: &[LeveledList]) -> LeveledList {
+    let base = &versions[0];
+    let mut added = vec![];
+    let mut removed = vec![];
+
+    for version in &versions[1..] {
+        union(&mut added, &difference(&version.entries, &base.entries));
+        union(&mut removed, &difference(&base.entries, &version.entries));
+    }
+
+    let mut entries = difference(&base.entries, &removed);
+    entries.extend(added);
+    entries.sort_by_key(|entry| entry.level);
+
+    LeveledList {
+        form_id: base.form_id,
+        code: base.code,
+        editor_id: latest(versions, |list| list.editor_id.clone()),
+        chance_none: latest(versions, |list| list.chance_none),
+        flags: latest(versions, |list| list.flags),
+        chance_none_global: latest(versions, |list| list.chance_none_global),
+        entries,
+        other: latest(versions, |list| list.other.clone()),
+        trailing: latest(versions, |list| list.trailing.clone()),
+    }
+}
+
+/// Builds a plugin named `name` overriding each leveled list that two or more plugins edit with the merged list,
+/// leaving out lists whose winning version already is the merged one, and lists that would merge into more
+/// entries than a list can hold.
+///
+/// The patch's masters are the plugins that define or override a list it writes, or that an entry of one belongs
+/// to. A plugin already named `name`, from an earlier patch, is left out; it must come last in the load order. So
+/// is the active plugin, whose edits may not be saved yet. Fails if the load order is too full to number the
+/// patch's form IDs.
+pub fn merged_patch(load_order: &LoadOrder, name: &str) -> io::Result<Plugin> {
+    let plugins = load_order.plugins();
+    let count = match plugins.iter().position(|plugin| plugin.name.eq_ignore_ascii_case(name)) {
+        Some(index) if index + 1 == plugins.len() => index,
+        Some(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} must be last in the load order to be regenerated", name),
+            ))
+        }
+        None => plugins.len(),
+    };
+
+    if count >= MAX_PLUGINS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("the load order has no room left for {}", name),
+        ));
+    }
+
+    let active = load_order.active_index().filter(|&active| active < count);
+
+    if let Some(active) = active {
+        log::info!("Leaving the active plugin {} out of {}", plugins[active].name, name);
+    }
+
+    let mut patch = Plugin::new(name, &[]);
+    patch.set_load_order_index(count as u8);
+    let mut masters = BTreeSet::new();
+
+    for &code in &LEVELED_CODES {
+        for winner in load_order.records_by_code(code) {
+            let overrides: Vec<_> = load_order
+                .overrides(winner.form_id)
+                .into_iter()
+                .filter(|&(index, _)| index < count && Some(index) != active)
+                .collect();
+            let records: Vec<_> = overrides.iter().map(|&(_, record)| record).collect();
+
+            if records.len() < 3 || records.iter().any(|record| record.is_deleted()) {
+                continue;
+            }
+
+            let versions: io::Result<Vec<LeveledList>> =
+                records.iter().map(|record| LeveledList::from_record(record)).collect();
+
+            let versions = match versions {
+                Ok(versions) => versions,
+                Err(e) => {
+                    log::warn!("Error reading leveled list {:08X}: {}", winner.form_id, e);
+                    continue;
+                }
+            };
+
+            let merged = merge(&versions);
+            let mut last = versions[versions.len() - 1].clone();
+            last.entries.sort_by_key(|entry| entry.level);
+
+            if merged == last {
+                continue;
+            }
+
+            let mut record = records[records.len() - 1].clone();
+
+            if let Err(e) = merged.write(&mut record) {
+                log::error!("Not merging leveled list {:08X}: {}", winner.form_id, e);
+                continue;
+            }
+
+            masters.extend(overrides.iter().map(|&(index, _)| index as u8));
+            form_ids::plugin_indices(&record, &mut masters);
+            patch.add_record(&[(code, group_types::TOP)], record, &mut |_| None);
+        }
+    }
+
+    for index in masters {
+        if let Some(master) = plugins.get(index as usize).filter(|_| (index as usize) < count) {
+            patch.add_master(&master.name, index);
+        }
+    }
+
+    Ok(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esp::Record;
+
+    const LIST: u32 = 0x0000_0800;
+
+    fn entry(level: u16, form_id: u32) -> LeveledEntry {
+        LeveledEntry {
+            level,
+            form_id,
+            count: 1,
+            ..LeveledEntry::default()
+        }
+    }
+
+    fn plugin(name: &str, masters: &[&str], entries: &[LeveledEntry], chance_none: u8) -> Plugin {
+        let mut list = LeveledList::new(LIST, *b"LVLI");
+        list.editor_id = "LItemTest".to_string();
+        list.entries = entries.to_vec();
+        list.chance_none = chance_none;
+
+        let masters: Vec<String> = masters.iter().map(|master| master.to_string()).collect();
+        let mut plugin = Plugin::new(name, &masters);
+        let record = Record::new(*b"LVLI", LIST, &list.subrecords().unwrap());
+        plugin.add_record(&[(*b"LVLI", group_types::TOP)], record, &mut |_| None);
+        plugin
+    }
+
+    #[test]
+    fn merges_edits() {
+        let (a, b, c, d) = (entry(1, 0xA), entry(5, 0xB), entry(3, 0xC), entry(2, 0xD));
+
+        let load_order = LoadOrder::new(
+            vec![
+                plugin("Master.esm", &[], &[a.clone(), b.clone()], 0),
+                plugin("First.esp", &["Master.esm"], &[a.clone(), c.clone()], 0),
+                plugin(
+                    "Second.esp",
+                    &["Master.esm", "First.esp"],
+                    &[a.clone(), b, d.clone()],
+                    10,
+                ),
+            ],
+            None,
+        );
+
+        let patch = merged_patch(&load_order, "Merged Patch.esp").unwrap();
+        assert_eq!(patch.masters(), vec!["Master.esm", "First.esp", "Second.esp"]);
+
+        let merged = LeveledList::from_record(patch.record(LIST).unwrap()).unwrap();
+        assert_eq!(merged.entries, vec![a, d, c]);
+        assert_eq!(merged.chance_none, 10);
+        assert_eq!(merged.editor_id, "LItemTest");
+    }
+
+    #[test]
+    fn masters_are_only_the_plugins_written_from() {
+        let (a, b, c) = (entry(1, 0xA), entry(2, 0xB), entry(3, 0xC));
+
+        let load_order = LoadOrder::new(
+            vec![
+                plugin("Master.esm", &[], std::slice::from_ref(&a), 0),
+                Plugin::new("Unrelated.esp", &["Master.esm".to_string()]),
+                plugin("First.esp", &["Master.esm"], &[a.clone(), b.clone()], 0),
+                plugin("Second.esp", &["Master.esm"], &[a.clone(), c.clone()], 0),
+                plugin("Active.esp", &["Master.esm"], std::slice::from_ref(&a), 0),
+            ],
+            Some(4),
+        );
+
+        let patch = merged_patch(&load_order, "Merged Patch.esp").unwrap();
+        assert_eq!(patch.masters(), vec!["Master.esm", "First.esp", "Second.esp"]);
+
+        let merged = LeveledList::from_record(patch.record(LIST).unwrap()).unwrap();
+        assert_eq!(merged.entries, vec![a, b, c]);
+    }
+
+    #[test]
+    fn skips_lists_over_the_entry_limit() {
+        let first: Vec<_> = (0..200).map(|i| entry(1, 0x1000 + i)).collect();
+        let second: Vec<_> = (0..200).map(|i| entry(1, 0x2000 + i)).collect();
+
+        let load_order = LoadOrder::new(
+            vec![
+                plugin("Master.esm", &[], &[], 0),
+                plugin("First.esp", &["Master.esm"], &first, 0),
+                plugin("Second.esp", &["Master.esm"], &second, 0),
+            ],
+            None,
+        );
+
+        let patch = merged_patch(&load_order, "Merged Patch.esp").unwrap();
+        assert!(patch.record(LIST).is_none());
+        assert!(patch.masters().is_empty());
+    }
+
+    #[test]
+    fn needs_room_in_the_load_order() {
+        let plugins = (0..MAX_PLUGINS)
+            .map(|i| Plugin::new(&format!("Plugin{}.esp", i), &[]))
+            .collect();
+        let load_order = LoadOrder::new(plugins, None);
+
+        assert!(merged_patch(&load_order, "Merged Patch.esp").is_err());
+    }
+}
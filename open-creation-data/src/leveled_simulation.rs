@@ -0,0 +1,239 @@
+//! Rolling a leveled list many times at a player level, to see how often it gives each item.
+//!
+//! Lists are rolled the way the game does: the chance of giving nothing is checked, then one entry is picked at
+//! random from those at or below the player's level, or from those at the highest such level unless the list
+//! calculates from all levels. Entries that are themselves leveled lists are rolled in turn.
+
+use crate::esp::{leveled_flags, LeveledList, LoadOrder, LEVELED_CODES};
+
+use std::{collections::HashMap, rc::Rc};
+
+/// Lists nested deeper than this are given as they are rather than rolled, in case of a loop.
+const MAX_DEPTH: usize = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulatedItem {
+    pub form_id: u32,
+    /// How many rolls gave the item.
+    pub rolls: u32,
+    /// How many of the item all the rolls gave together.
+    pub count: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Simulation {
+    pub level: u16,
+    pub rolls: u32,
+    /// How many rolls gave nothing.
+    pub empty: u32,
+    /// The items given, most often given first.
+    pub items: Vec<SimulatedItem>,
+}
+
+impl Simulation {
+    /// Rolls `list` as if the player were at `level`. Nested lists and globals are read from `load_order`, while
+    /// `list` itself may hold unsaved edits. The same `seed` gives the same results.
+    pub fn run(load_order: &LoadOrder, list: &LeveledList, level: u16, rolls: u32, seed: u64) -> Self {
+        let mut roller = Roller {
+            load_order,
+            level,
+            lists: HashMap::new(),
+            globals: HashMap::new(),
+            random: Random(seed | 1),
+        };
+
+        let mut empty = 0;
+        let mut totals: HashMap<u32, (u32, u64)> = HashMap::new();
+
+        for _ in 0..rolls {
+            let mut given = HashMap::new();
+            roller.roll(list, 1, 0, &mut given);
+
+            if given.is_empty() {
+                empty += 1;
+            }
+
+            for (form_id, count) in given {
+                let total = totals.entry(form_id).or_default();
+                total.0 += 1;
+                total.1 += count;
+            }
+        }
+
+        let mut items: Vec<SimulatedItem> = totals
+            .into_iter()
+            .map(|(form_id, (rolls, count))| SimulatedItem { form_id, rolls, count })
+            .collect();
+        items.sort_by(|a, b| b.rolls.cmp(&a.rolls).then(a.form_id.cmp(&b.form_id)));
+
+        Self {
+            level,
+            rolls,
+            empty,
+            items,
+        }
+    }
+}
+
+struct Roller<'a> {
+    load_order: &'a LoadOrder,
+    level: u16,
+    /// Nested lists read so far, or `None` for form IDs that are not leveled lists.
+    lists: HashMap<u32, Option<Rc<LeveledList>>>,
+    globals: HashMap<u32, Option<f32>>,
+    random: Random,
+}
+
+impl<'a> Roller<'a> {
+    /// Rolls a list used `count` times, adding what it gives to `given`.
+    fn roll(&mut self, list: &LeveledList, count: u32, depth: usize, given: &mut HashMap<u32, u64>) {
+        let global_value = list.chance_none_global.and_then(|global| self.global(global));
+        let chance_none = list.chance_none(|_| global_value);
+
+        let mut eligible: Vec<_> = list.entries.iter().filter(|entry| entry.level <= self.level).collect();
+
+        if list.flags & leveled_flags::CALCULATE_FROM_ALL_LEVELS == 0 {
+            let highest = eligible.iter().map(|entry| entry.level).max();
+            eligible.retain(|entry| Some(entry.level) == highest);
+        }
+
+        if eligible.is_empty() {
+            return;
+        }
+
+        // Either an entry is picked for each use of the list, or one is picked and given for all of them.
+        let (picks, uses) = if list.flags & leveled_flags::CALCULATE_FOR_EACH_ITEM != 0 {
+            (count, 1)
+        } else {
+            (1, count)
+        };
+
+        for _ in 0..picks {
+            if (self.random.below(100) as f32) < chance_none {
+                continue;
+            }
+
+            if list.flags & leveled_flags::USE_ALL != 0 && &list.code != b"LVLN" {
+                for entry in &eligible {
+                    self.give(entry.form_id, (entry.count as u32).saturating_mul(uses), depth, given);
+                }
+            } else {
+                let entry = eligible[self.random.below(eligible.len())];
+                self.give(entry.form_id, (entry.count as u32).saturating_mul(uses), depth, given);
+            }
+        }
+    }
+
+    fn give(&mut self, form_id: u32, count: u32, depth: usize, given: &mut HashMap<u32, u64>) {
+        match self.list(form_id) {
+            Some(list) if depth < MAX_DEPTH => self.roll(&list, count, depth + 1, given),
+            _ => *given.entry(form_id).or_default() += count as u64,
+        }
+    }
+
+    fn list(&mut self, form_id: u32) -> Option<Rc<LeveledList>> {
+        let load_order = self.load_order;
+
+        self.lists
+            .entry(form_id)
+            .or_insert_with(|| {
+                let record = load_order
+                    .record(form_id)
+                    .filter(|record| LEVELED_CODES.contains(&record.code))?;
+                LeveledList::from_record(record).ok().map(Rc::new)
+            })
+            .clone()
+    }
+
+    fn global(&mut self, form_id: u32) -> Option<f32> {
+        let load_order = self.load_order;
+
+        *self.globals.entry(form_id).or_insert_with(|| {
+            let record = load_order.record(form_id).filter(|record| &record.code == b"GLOB")?;
+            record.subrecord(*b"FLTV")?.as_f32()
+        })
+    }
+}
+
+/// A xorshift generator, which is plenty for sampling loot.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, bound: usize) -> usize {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+
+        (x % bound as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esp::{group_types, LeveledEntry, Plugin, Record};
+
+    fn entry(level: u16, form_id: u32, count: u16) -> LeveledEntry {
+        LeveledEntry {
+            level,
+            form_id,
+            count,
+            ..LeveledEntry::default()
+        }
+    }
+
+    fn load_order() -> LoadOrder {
+        let mut nested = LeveledList::new(0x0000_0900, *b"LVLI");
+        nested.entries = vec![entry(1, 0x0000_0A00, 2)];
+        let record = Record::new(*b"LVLI", nested.form_id, &nested.subrecords().unwrap());
+
+        let mut plugin = Plugin::new("Master.esm", &[]);
+        plugin.add_record(&[(*b"LVLI", group_types::TOP)], record, &mut |_| None);
+        LoadOrder::new(vec![plugin], None)
+    }
+
+    #[test]
+    fn picks_entries_by_level() {
+        let load_order = load_order();
+        let mut list = LeveledList::new(0x0000_0800, *b"LVLI");
+        list.entries = vec![entry(1, 0x0000_0900, 3), entry(10, 0x0000_0B00, 1)];
+
+        let low = Simulation::run(&load_order, &list, 5, 100, 1);
+        assert_eq!(low.empty, 0);
+        assert_eq!(
+            low.items,
+            vec![SimulatedItem {
+                form_id: 0x0000_0A00,
+                rolls: 100,
+                count: 600
+            }]
+        );
+
+        let high = Simulation::run(&load_order, &list, 20, 100, 1);
+        assert_eq!(high.items.len(), 1);
+        assert_eq!(high.items[0].form_id, 0x0000_0B00);
+
+        list.flags = leveled_flags::CALCULATE_FROM_ALL_LEVELS;
+        assert_eq!(Simulation::run(&load_order, &list, 20, 1000, 1).items.len(), 2);
+
+        list.chance_none = 100;
+        assert_eq!(Simulation::run(&load_order, &list, 20, 100, 1).empty, 100);
+    }
+
+    #[test]
+    fn saturates_the_count_of_nested_lists() {
+        // A list that gives itself is rolled until the depth limit, multiplying the count each time.
+        let mut looping = LeveledList::new(0x0000_0900, *b"LVLI");
+        looping.entries = vec![entry(1, 0x0000_0900, u16::MAX)];
+        let record = Record::new(*b"LVLI", looping.form_id, &looping.subrecords().unwrap());
+
+        let mut plugin = Plugin::new("Master.esm", &[]);
+        plugin.add_record(&[(*b"LVLI", group_types::TOP)], record, &mut |_| None);
+        let load_order = LoadOrder::new(vec![plugin], None);
+
+        let simulation = Simulation::run(&load_order, &looping, 1, 1, 1);
+        assert_eq!(simulation.items[0].count, u32::MAX as u64);
+    }
+}
@@ -0,0 +1,14 @@
+pub mod bsa;
+pub mod dds;
+pub mod esp;
+pub mod leveled_patch;
+pub mod leveled_simulation;
+pub mod map;
+pub mod nif;
+pub mod papyrus;
+pub mod path;
+pub mod pex;
+pub mod png;
+pub mod script_library;
+pub mod vfs;
+pub mod voice_script;
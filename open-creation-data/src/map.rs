@@ -0,0 +1,642 @@
+//! Top-down maps of worldspaces, rendered on the CPU from terrain heights and vertex colours.
+//!
+//! Maps are drawn north up with a fixed number of pixels to a cell, and lit from the north-west so hills stand
+//! out. Terrain below the water level is tinted blue. Overlays can mark the cell grid, map markers (references
+//! with XMRK), the locations cells belong to (XLCN) and the areas regions cover (REGN RPLD). Overlays are drawn
+//! as colours only; names are not written on the map.
+
+use crate::{
+    esp::{
+        invalid,
+        land::{CELL_SIZE, LAND_SIZE},
+        Land, LoadOrder, Reference, Subrecord,
+    },
+    png,
+};
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+/// Maps larger than this many pixels are refused rather than exhausting memory.
+const MAX_PIXELS: u64 = 1 << 28;
+const MAX_PIXELS_PER_CELL: u32 = 256;
+/// A cell's XCLW at or above this height means the cell has no water of its own.
+const NO_WATER: f32 = 1.0e6;
+
+/// Ground colours by height in game units, blended between neighbouring stops.
+const HEIGHT_COLORS: [(f32, [f32; 3]); 5] = [
+    (-2048.0, [0.55, 0.52, 0.40]),
+    (0.0, [0.36, 0.46, 0.28]),
+    (4096.0, [0.42, 0.45, 0.30]),
+    (12288.0, [0.52, 0.47, 0.40]),
+    (20480.0, [0.92, 0.93, 0.95]),
+];
+const WATER_COLOR: [f32; 3] = [0.16, 0.28, 0.44];
+/// How deep water has to be to hide the ground beneath it completely, in game units.
+const WATER_DEPTH: f32 = 2048.0;
+/// The direction towards the light, from the north-west and above.
+const LIGHT: [f32; 3] = [-0.5, 0.5, 0.707];
+const AMBIENT: f32 = 0.45;
+
+const GRID_COLOR: [u8; 3] = [0, 0, 0];
+const GRID_ALPHA: f32 = 0.25;
+const LOCATION_ALPHA: f32 = 0.3;
+const BORDER_ALPHA: f32 = 0.8;
+
+/// What to draw on a worldspace map.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapOptions {
+    /// Pixels along each side of a cell. 32 gives one pixel to each terrain vertex.
+    pub pixels_per_cell: u32,
+    pub grid: bool,
+    pub markers: bool,
+    pub locations: bool,
+    pub regions: bool,
+}
+
+impl Default for MapOptions {
+    fn default() -> Self {
+        Self {
+            pixels_per_cell: (LAND_SIZE - 1) as u32,
+            grid: false,
+            markers: false,
+            locations: false,
+            regions: false,
+        }
+    }
+}
+
+/// An RGBA8 image, row by row from the top.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl MapImage {
+    /// A transparent image.
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+            self.pixels[index + 3],
+        ]
+    }
+
+    /// Blends a colour over a pixel, ignoring pixels outside the image.
+    fn blend(&mut self, x: i64, y: i64, color: [u8; 3], alpha: f32) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        let pixel = &mut self.pixels[index..index + 4];
+
+        for (channel, &target) in pixel.iter_mut().zip(color.iter()) {
+            *channel = (*channel as f32 + (target as f32 - *channel as f32) * alpha).round() as u8;
+        }
+
+        pixel[3] = (pixel[3] as f32 + (255.0 - pixel[3] as f32) * alpha).round() as u8;
+    }
+
+    fn line(&mut self, from: (f32, f32), to: (f32, f32), color: [u8; 3], alpha: f32) {
+        let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil().max(1.0) as usize;
+
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let x = from.0 + (to.0 - from.0) * t;
+            let y = from.1 + (to.1 - from.1) * t;
+            self.blend(x.floor() as i64, y.floor() as i64, color, alpha);
+        }
+    }
+
+    fn dot(&mut self, center: (f32, f32), radius: f32, color: [u8; 3]) {
+        let reach = radius.ceil() as i64;
+        let (center_x, center_y) = (center.0.floor() as i64, center.1.floor() as i64);
+
+        for y in -reach..=reach {
+            for x in -reach..=reach {
+                if ((x * x + y * y) as f32) <= radius * radius {
+                    self.blend(center_x + x, center_y + y, color, 1.0);
+                }
+            }
+        }
+    }
+
+    pub fn write_png<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        png::write_rgba(writer, self.width, self.height, &self.pixels)
+    }
+}
+
+/// A rendered map, with counts of what its overlays found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldMap {
+    pub image: MapImage,
+    /// Cells drawn with terrain.
+    pub cells: usize,
+    pub markers: usize,
+    pub locations: usize,
+    pub regions: usize,
+}
+
+/// The exterior cells covered by a map and how game positions land on its pixels.
+struct MapArea {
+    min: (i32, i32),
+    max: (i32, i32),
+    pixels_per_cell: u32,
+}
+
+impl MapArea {
+    /// The pixel at a position in game units. The image's top row is the area's northern edge.
+    fn pixel(&self, x: f32, y: f32) -> (f32, f32) {
+        let scale = self.pixels_per_cell as f32;
+        (
+            (x / CELL_SIZE - self.min.0 as f32) * scale,
+            ((self.max.1 + 1) as f32 - y / CELL_SIZE) * scale,
+        )
+    }
+
+    /// The top-left pixel of a cell.
+    fn cell_corner(&self, grid: (i32, i32)) -> (u32, u32) {
+        (
+            (grid.0 - self.min.0) as u32 * self.pixels_per_cell,
+            (self.max.1 - grid.1) as u32 * self.pixels_per_cell,
+        )
+    }
+}
+
+/// A colour for an overlay item, spread around the colour wheel by form ID so neighbours rarely match.
+fn overlay_color(form_id: u32) -> [u8; 3] {
+    let hue = form_id.wrapping_mul(2_654_435_761) as f32 / u32::MAX as f32 * 6.0;
+    let fraction = hue.fract();
+    let (high, low) = (0.95, 0.35);
+    let rising = low + (high - low) * fraction;
+    let falling = high - (high - low) * fraction;
+
+    let rgb = match hue as u32 {
+        0 => [high, rising, low],
+        1 => [falling, high, low],
+        2 => [low, high, rising],
+        3 => [low, falling, high],
+        4 => [rising, low, high],
+        _ => [high, low, falling],
+    };
+
+    [(rgb[0] * 255.0) as u8, (rgb[1] * 255.0) as u8, (rgb[2] * 255.0) as u8]
+}
+
+fn height_color(height: f32) -> [f32; 3] {
+    let last = HEIGHT_COLORS.len() - 1;
+
+    if height <= HEIGHT_COLORS[0].0 {
+        return HEIGHT_COLORS[0].1;
+    }
+
+    for pair in HEIGHT_COLORS.windows(2) {
+        let ((low, low_color), (high, high_color)) = (pair[0], pair[1]);
+
+        if height <= high {
+            let t = (height - low) / (high - low);
+            return [
+                low_color[0] + (high_color[0] - low_color[0]) * t,
+                low_color[1] + (high_color[1] - low_color[1]) * t,
+                low_color[2] + (high_color[2] - low_color[2]) * t,
+            ];
+        }
+    }
+
+    HEIGHT_COLORS[last].1
+}
+
+/// Samples per-vertex data at a position in vertex steps from a cell's south-west corner.
+fn bilinear<T: Copy>(values: &[T], x: f32, y: f32, mix: impl Fn(T, T, f32) -> T) -> T {
+    let last = LAND_SIZE - 1;
+    let (column, row) = ((x.floor() as usize).min(last - 1), (y.floor() as usize).min(last - 1));
+    let (tx, ty) = (x - column as f32, y - row as f32);
+    let at = |column: usize, row: usize| values[row * LAND_SIZE + column];
+
+    let south = mix(at(column, row), at(column + 1, row), tx);
+    let north = mix(at(column, row + 1), at(column + 1, row + 1), tx);
+    mix(south, north, ty)
+}
+
+fn f32_at(subrecord: &Subrecord, index: usize) -> Option<f32> {
+    let bytes = subrecord.data.get(index * 4..index * 4 + 4)?;
+    Some(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Renders a worldspace's exterior cells.
+pub fn render_world_map(load_order: &LoadOrder, world: u32, options: &MapOptions) -> io::Result<WorldMap> {
+    let world_record = match load_order.record(world) {
+        Some(record) if &record.code == b"WRLD" => record,
+        _ => return Err(invalid(&format!("{:08X} is not a worldspace", world))),
+    };
+
+    // DNAM holds the default land height and then the default water height.
+    let default_water = world_record
+        .subrecord(*b"DNAM")
+        .and_then(|dnam| f32_at(&dnam, 1))
+        .unwrap_or(0.0);
+
+    let cells: Vec<_> = load_order
+        .cells()
+        .into_iter()
+        .filter(|cell| cell.world == Some(world))
+        .collect();
+    let exteriors: Vec<(u32, (i32, i32))> = cells
+        .iter()
+        .filter_map(|cell| Some((cell.form_id, cell.grid?)))
+        .collect();
+
+    if exteriors.is_empty() {
+        return Err(invalid(&format!("Worldspace {:08X} has no exterior cells", world)));
+    }
+
+    let area = MapArea {
+        min: (
+            exteriors.iter().map(|(_, grid)| grid.0).min().unwrap_or(0),
+            exteriors.iter().map(|(_, grid)| grid.1).min().unwrap_or(0),
+        ),
+        max: (
+            exteriors.iter().map(|(_, grid)| grid.0).max().unwrap_or(0),
+            exteriors.iter().map(|(_, grid)| grid.1).max().unwrap_or(0),
+        ),
+        pixels_per_cell: options.pixels_per_cell.clamp(1, MAX_PIXELS_PER_CELL),
+    };
+
+    let per_cell = area.pixels_per_cell;
+    let width = (area.max.0 - area.min.0 + 1) as u64 * per_cell as u64;
+    let height = (area.max.1 - area.min.1 + 1) as u64 * per_cell as u64;
+
+    if width * height > MAX_PIXELS {
+        return Err(invalid(&format!(
+            "A {} by {} pixel map is too large; use fewer pixels per cell",
+            width, height
+        )));
+    }
+
+    let mut image = MapImage::new(width as u32, height as u32);
+    let pixel_count = image.pixels.len() / 4;
+    let mut heights = vec![f32::NAN; pixel_count];
+    let mut colors = vec![[1.0f32; 3]; pixel_count];
+    let mut waters = vec![default_water; pixel_count];
+    let mut locations: HashMap<(i32, i32), u32> = HashMap::new();
+    let mut drawn = 0;
+
+    for &(cell, grid) in &exteriors {
+        let record = load_order.record(cell);
+        let water = record
+            .and_then(|record| record.subrecord(*b"XCLW"))
+            .and_then(|xclw| f32_at(&xclw, 0))
+            .filter(|&water| water < NO_WATER)
+            .unwrap_or(default_water);
+
+        if let Some(location) = record
+            .and_then(|record| record.subrecord(*b"XLCN"))
+            .and_then(|xlcn| xlcn.as_u32())
+        {
+            locations.insert(grid, location);
+        }
+
+        let land = match load_order.cell_land(cell).map(Land::from_record) {
+            Some(Ok(land)) => land,
+            Some(Err(e)) => {
+                log::warn!("Error reading the terrain of cell {:08X}: {}", cell, e);
+                continue;
+            }
+            None => continue,
+        };
+
+        let land_heights = match &land.heights {
+            Some(heights) => heights,
+            None => continue,
+        };
+
+        let (left, top) = area.cell_corner(grid);
+        let steps = (LAND_SIZE - 1) as f32;
+
+        for row in 0..per_cell {
+            for column in 0..per_cell {
+                let x = (column as f32 + 0.5) / per_cell as f32 * steps;
+                let y = (1.0 - (row as f32 + 0.5) / per_cell as f32) * steps;
+                let index = (top + row) as usize * image.width as usize + (left + column) as usize;
+
+                heights[index] = bilinear(land_heights, x, y, |a, b, t| a + (b - a) * t);
+                waters[index] = water;
+
+                if let Some(land_colors) = &land.colors {
+                    let color = bilinear(land_colors, x, y, |a, b, t| {
+                        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+                        [mix(a[0], b[0]), mix(a[1], b[1]), mix(a[2], b[2])]
+                    });
+                    colors[index] = [
+                        color[0] as f32 / 255.0,
+                        color[1] as f32 / 255.0,
+                        color[2] as f32 / 255.0,
+                    ];
+                }
+            }
+        }
+
+        drawn += 1;
+    }
+
+    shade(&mut image, &heights, &colors, &waters, CELL_SIZE / per_cell as f32);
+
+    let mut map = WorldMap {
+        image,
+        cells: drawn,
+        markers: 0,
+        locations: 0,
+        regions: 0,
+    };
+
+    if options.locations {
+        map.locations = draw_locations(&mut map.image, &area, &locations);
+    }
+
+    if options.regions {
+        map.regions = draw_regions(&mut map.image, &area, load_order, world);
+    }
+
+    if options.grid {
+        let (width, height) = (map.image.width as f32, map.image.height as f32);
+
+        for column in 0..=(area.max.0 - area.min.0 + 1) {
+            let x = (column as u32 * per_cell) as f32;
+            map.image.line((x, 0.0), (x, height), GRID_COLOR, GRID_ALPHA);
+        }
+
+        for row in 0..=(area.max.1 - area.min.1 + 1) {
+            let y = (row as u32 * per_cell) as f32;
+            map.image.line((0.0, y), (width, y), GRID_COLOR, GRID_ALPHA);
+        }
+    }
+
+    if options.markers {
+        // Map markers are usually persistent, so the worldspace's persistent cell is searched too.
+        let radius = (per_cell as f32 / 8.0).max(2.0);
+
+        for cell in &cells {
+            for record in load_order.cell_references(cell.form_id) {
+                if record.subrecord(*b"XMRK").is_none() {
+                    continue;
+                }
+
+                if let Some(reference) = Reference::from_record(record) {
+                    let position = area.pixel(reference.placement.position[0], reference.placement.position[1]);
+                    map.image.dot(position, radius + 1.0, [0, 0, 0]);
+                    map.image.dot(position, radius, [255, 255, 255]);
+                    map.markers += 1;
+                }
+            }
+        }
+    }
+
+    Ok(map)
+}
+
+/// Colours each pixel with terrain from its height, vertex colour and slope, covering it with water where it is
+/// below the water level. `spacing` is the width of a pixel in game units.
+fn shade(image: &mut MapImage, heights: &[f32], colors: &[[f32; 3]], waters: &[f32], spacing: f32) {
+    let (width, height) = (image.width as usize, image.height as usize);
+    let flat = LIGHT[2];
+
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            let center = heights[index];
+
+            if center.is_nan() {
+                continue;
+            }
+
+            let at = |x: usize, y: usize| {
+                let value = heights[y * width + x];
+                if value.is_nan() {
+                    center
+                } else {
+                    value
+                }
+            };
+
+            let east = at((x + 1).min(width - 1), y) - at(x.saturating_sub(1), y);
+            let north = at(x, y.saturating_sub(1)) - at(x, (y + 1).min(height - 1));
+            let normal = [-east / (2.0 * spacing), -north / (2.0 * spacing), 1.0];
+            let length = (normal[0] * normal[0] + normal[1] * normal[1] + 1.0).sqrt();
+            let lit = (normal[0] * LIGHT[0] + normal[1] * LIGHT[1] + normal[2] * LIGHT[2]) / length;
+            let brightness = AMBIENT + (1.0 - AMBIENT) * (lit.max(0.0) / flat);
+
+            let ground = height_color(center);
+            let mut color = [
+                ground[0] * colors[index][0] * brightness,
+                ground[1] * colors[index][1] * brightness,
+                ground[2] * colors[index][2] * brightness,
+            ];
+
+            if center < waters[index] {
+                let depth = ((waters[index] - center) / WATER_DEPTH).min(1.0);
+                let cover = 0.55 + 0.4 * depth;
+
+                for (channel, water) in color.iter_mut().zip(WATER_COLOR.iter()) {
+                    *channel += (water - *channel) * cover;
+                }
+            }
+
+            let pixel = &mut image.pixels[index * 4..index * 4 + 4];
+            for (channel, value) in pixel.iter_mut().zip(color.iter()) {
+                *channel = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+            pixel[3] = 255;
+        }
+    }
+}
+
+/// Tints each cell with its location's colour and outlines where locations meet. Returns how many locations
+/// were drawn.
+fn draw_locations(image: &mut MapImage, area: &MapArea, locations: &HashMap<(i32, i32), u32>) -> usize {
+    let per_cell = area.pixels_per_cell;
+
+    for (&grid, &location) in locations {
+        let color = overlay_color(location);
+        let (left, top) = area.cell_corner(grid);
+
+        for y in top..top + per_cell {
+            for x in left..left + per_cell {
+                image.blend(x as i64, y as i64, color, LOCATION_ALPHA);
+            }
+        }
+
+        let (left, top, size) = (left as f32, top as f32, per_cell as f32);
+        // Each cell draws the borders on its own side of its western, eastern, southern and northern edges.
+        let edges = [
+            ((-1, 0), (left, top), (left, top + size - 1.0)),
+            ((1, 0), (left + size - 1.0, top), (left + size - 1.0, top + size - 1.0)),
+            ((0, -1), (left, top + size - 1.0), (left + size - 1.0, top + size - 1.0)),
+            ((0, 1), (left, top), (left + size - 1.0, top)),
+        ];
+
+        for &((dx, dy), from, to) in &edges {
+            if locations.get(&(grid.0 + dx, grid.1 + dy)) != Some(&location) {
+                image.line(from, to, color, BORDER_ALPHA);
+            }
+        }
+    }
+
+    let mut distinct: Vec<u32> = locations.values().copied().collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    distinct.len()
+}
+
+/// Outlines the areas of the regions in a worldspace. Returns how many regions had areas to draw.
+fn draw_regions(image: &mut MapImage, area: &MapArea, load_order: &LoadOrder, world: u32) -> usize {
+    let mut drawn = 0;
+
+    for record in load_order.records_by_code(*b"REGN") {
+        if record.is_deleted() || record.subrecord(*b"WNAM").and_then(|wnam| wnam.as_u32()) != Some(world) {
+            continue;
+        }
+
+        let subrecords = match record.subrecords() {
+            Ok(subrecords) => subrecords,
+            Err(e) => {
+                log::warn!("Error reading region {:08X}: {}", record.form_id, e);
+                continue;
+            }
+        };
+
+        let color = overlay_color(record.form_id);
+        let mut has_area = false;
+
+        // Each RPLD is one area: a closed outline of points in game units.
+        for rpld in subrecords.iter().filter(|subrecord| &subrecord.code == b"RPLD") {
+            let points: Vec<(f32, f32)> = (0..rpld.data.len() / 8)
+                .filter_map(|index| Some(area.pixel(f32_at(rpld, index * 2)?, f32_at(rpld, index * 2 + 1)?)))
+                .collect();
+
+            for (index, &point) in points.iter().enumerate() {
+                image.line(point, points[(index + 1) % points.len()], color, BORDER_ALPHA);
+            }
+
+            has_area |= points.len() > 1;
+        }
+
+        if has_area {
+            drawn += 1;
+        }
+    }
+
+    drawn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esp::{group_types, Plugin, Record};
+
+    const WORLD: u32 = 0x0000_0D00;
+    const WATER_HEIGHT: f32 = 100.0;
+
+    /// A worldspace with a flat cell below the water level at (0, 0), a cell above it at (1, 0) with a map
+    /// marker, and a cell without terrain at (0, 1).
+    fn load_order() -> LoadOrder {
+        let mut dnam = 0.0f32.to_le_bytes().to_vec();
+        dnam.extend_from_slice(&WATER_HEIGHT.to_le_bytes());
+        let world = Record::new(*b"WRLD", WORLD, &[Subrecord::new(*b"DNAM", dnam)]);
+
+        let mut plugin = Plugin::new("Master.esm", &[]);
+        let world_path = [
+            (*b"WRLD", group_types::TOP),
+            (WORLD.to_le_bytes(), group_types::WORLD_CHILDREN),
+            ([0; 4], group_types::EXTERIOR_CELL_BLOCK),
+            ([0; 4], group_types::EXTERIOR_CELL_SUB_BLOCK),
+        ];
+
+        for (index, (grid, height)) in [((0i32, 0i32), Some(0.0)), ((1, 0), Some(1000.0)), ((0, 1), None)]
+            .iter()
+            .enumerate()
+        {
+            let cell_id = 0x0000_1000 + index as u32 * 0x10;
+            let mut xclc = grid.0.to_le_bytes().to_vec();
+            xclc.extend_from_slice(&grid.1.to_le_bytes());
+            let cell = Record::new(*b"CELL", cell_id, &[Subrecord::new(*b"XCLC", xclc)]);
+            plugin.add_record(&world_path, cell, &mut |_| Some(world.clone()));
+
+            let mut children_path = world_path.to_vec();
+            children_path.push((cell_id.to_le_bytes(), group_types::CELL_CHILDREN));
+            children_path.push((cell_id.to_le_bytes(), group_types::CELL_TEMPORARY_CHILDREN));
+
+            if let Some(height) = height {
+                let land = Land {
+                    form_id: cell_id + 1,
+                    heights: Some(vec![*height; LAND_SIZE * LAND_SIZE]),
+                    ..Land::default()
+                };
+                let mut record = Record::new(*b"LAND", cell_id + 1, &[]);
+                land.write(&mut record).unwrap();
+                plugin.add_record(&children_path, record, &mut |_| None);
+            }
+
+            if *grid == (1, 0) {
+                let mut data = vec![];
+                for value in &[CELL_SIZE * 1.5, CELL_SIZE * 0.5, 0.0, 0.0, 0.0, 0.0] {
+                    data.extend_from_slice(&f32::to_le_bytes(*value));
+                }
+
+                let marker = Record::new(
+                    *b"REFR",
+                    cell_id + 2,
+                    &[
+                        Subrecord::new(*b"NAME", 0x0000_0010u32.to_le_bytes().to_vec()),
+                        Subrecord::new(*b"XMRK", vec![]),
+                        Subrecord::new(*b"DATA", data),
+                    ],
+                );
+                plugin.add_record(&children_path, marker, &mut |_| None);
+            }
+        }
+
+        LoadOrder::new(vec![plugin], None)
+    }
+
+    #[test]
+    fn render_world_map() {
+        let load_order = load_order();
+        let options = MapOptions {
+            pixels_per_cell: 8,
+            markers: true,
+            ..MapOptions::default()
+        };
+        let map = super::render_world_map(&load_order, WORLD, &options).unwrap();
+
+        assert_eq!((map.image.width, map.image.height), (16, 16));
+        assert_eq!((map.cells, map.markers), (2, 1));
+
+        // The cell without terrain is left transparent.
+        assert_eq!(map.image.pixel(4, 4)[3], 0);
+
+        let water = map.image.pixel(2, 10);
+        let land = map.image.pixel(9, 15);
+        assert_eq!((water[3], land[3]), (255, 255));
+        assert!(water[2] > water[1] && water[2] > water[0]);
+        assert!(land[1] > land[2]);
+
+        assert_eq!(map.image.pixel(12, 12), [255, 255, 255, 255]);
+
+        assert!(super::render_world_map(&load_order, 0x0000_1000, &options).is_err());
+    }
+}
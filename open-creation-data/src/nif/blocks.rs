@@ -0,0 +1,581 @@
+use super::{reader::Reader, Header};
+
+use std::io;
+
+/// A node's transform relative to its parent: `p' = translation + rotation * (scale * p)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: [f32; 3],
+    /// Row-major, as stored in the file.
+    pub rotation: [[f32; 3]; 3],
+    pub scale: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: [0.0; 3],
+            rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            scale: 1.0,
+        }
+    }
+}
+
+impl Transform {
+    pub fn rotate(&self, v: [f32; 3]) -> [f32; 3] {
+        let r = &self.rotation;
+        [
+            r[0][0] * v[0] + r[0][1] * v[1] + r[0][2] * v[2],
+            r[1][0] * v[0] + r[1][1] * v[1] + r[1][2] * v[2],
+            r[2][0] * v[0] + r[2][1] * v[1] + r[2][2] * v[2],
+        ]
+    }
+
+    pub fn apply(&self, point: [f32; 3]) -> [f32; 3] {
+        let rotated = self.rotate([point[0] * self.scale, point[1] * self.scale, point[2] * self.scale]);
+        [
+            rotated[0] + self.translation[0],
+            rotated[1] + self.translation[1],
+            rotated[2] + self.translation[2],
+        ]
+    }
+
+    /// The transform that applies `child` and then `self`.
+    pub fn then(&self, child: &Transform) -> Transform {
+        let mut rotation = [[0.0; 3]; 3];
+
+        for (row, output) in rotation.iter_mut().enumerate() {
+            for (column, value) in output.iter_mut().enumerate() {
+                *value = (0..3).map(|k| self.rotation[row][k] * child.rotation[k][column]).sum();
+            }
+        }
+
+        Transform {
+            translation: self.apply(child.translation),
+            rotation,
+            scale: self.scale * child.scale,
+        }
+    }
+}
+
+/// Geometry in a form ready for rendering. Normals, UVs and colours are either empty or one per position.
+#[derive(Clone, Debug, Default)]
+pub struct Vertices {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub colors: Vec<[f32; 4]>,
+    pub triangles: Vec<[u16; 3]>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AvObject {
+    pub name: String,
+    pub flags: u32,
+    pub transform: Transform,
+}
+
+impl AvObject {
+    pub fn is_hidden(&self) -> bool {
+        self.flags & 1 != 0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum GeometrySource {
+    /// A reference to a `NiTriShapeData` or `NiTriStripsData` block.
+    Data(Option<usize>),
+    /// Vertex data stored in the shape itself, as in `BSTriShape`. Empty for skinned shapes, whose vertices
+    /// live in the skin partition.
+    Inline(Vertices),
+}
+
+#[derive(Clone, Debug)]
+pub struct Geometry {
+    pub object: AvObject,
+    pub source: GeometrySource,
+    pub skin: Option<usize>,
+    pub shader: Option<usize>,
+    pub alpha: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LightingShader {
+    pub shader_type: u32,
+    pub flags1: u32,
+    pub flags2: u32,
+    pub uv_offset: [f32; 2],
+    pub uv_scale: [f32; 2],
+    pub texture_set: Option<usize>,
+    pub emissive_color: [f32; 3],
+    pub emissive_multiple: f32,
+    pub alpha: f32,
+    pub glossiness: f32,
+    pub specular_color: [f32; 3],
+    pub specular_strength: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct EffectShader {
+    pub flags1: u32,
+    pub flags2: u32,
+    pub uv_offset: [f32; 2],
+    pub uv_scale: [f32; 2],
+    pub source_texture: String,
+    pub emissive_color: [f32; 4],
+    pub emissive_multiple: f32,
+}
+
+#[derive(Clone, Debug)]
+pub enum Block {
+    Node {
+        object: AvObject,
+        children: Vec<Option<usize>>,
+    },
+    Geometry(Geometry),
+    GeometryData(Vertices),
+    SkinInstance {
+        partition: Option<usize>,
+    },
+    /// The shared vertex buffer and triangles of a Special Edition skin partition.
+    SkinPartition(Vertices),
+    LightingShader(LightingShader),
+    EffectShader(EffectShader),
+    TextureSet(Vec<String>),
+    Alpha {
+        flags: u16,
+        threshold: u8,
+    },
+    /// A block the editor does not need, by type name.
+    Other(String),
+}
+
+const NODE_TYPES: &[&str] = &[
+    "NiNode",
+    "BSFadeNode",
+    "BSLeafAnimNode",
+    "BSMultiBoundNode",
+    "BSOrderedNode",
+    "BSValueNode",
+    "BSTreeNode",
+    "BSBlastNode",
+    "BSDamageStage",
+    "BSDebrisNode",
+    "BSRangeNode",
+    "NiBillboardNode",
+    "NiSwitchNode",
+    "NiLODNode",
+    "NiBSAnimationNode",
+    "NiBSParticleNode",
+];
+
+const TRI_SHAPE_TYPES: &[&str] = &[
+    "BSTriShape",
+    "BSMeshLODTriShape",
+    "BSSubIndexTriShape",
+    "BSDynamicTriShape",
+];
+
+// Vertex attribute flags, stored in the top 20 bits of a vertex descriptor.
+const VF_VERTEX: u64 = 0x001;
+const VF_UV: u64 = 0x002;
+const VF_NORMAL: u64 = 0x008;
+const VF_TANGENT: u64 = 0x010;
+const VF_COLORS: u64 = 0x020;
+const VF_FULL_PRECISION: u64 = 0x400;
+
+/// Parses the block at the reader. Every subclass of a handled type is read as its base class, which works
+/// because fields are only ever appended by subclasses and the caller knows each block's size.
+pub(crate) fn parse(type_name: &str, reader: &mut Reader, header: &Header) -> io::Result<Block> {
+    Ok(match type_name {
+        _ if NODE_TYPES.contains(&type_name) => {
+            let object = av_object(reader, header)?;
+            let children = reader.references()?;
+            Block::Node { object, children }
+        }
+        "NiTriShape" | "NiTriStrips" | "BSSegmentedTriShape" | "BSLODTriShape" => {
+            Block::Geometry(ni_geometry(reader, header)?)
+        }
+        _ if TRI_SHAPE_TYPES.contains(&type_name) => Block::Geometry(bs_tri_shape(type_name, reader, header)?),
+        "NiTriShapeData" => Block::GeometryData(tri_shape_data(reader, header, false)?),
+        "NiTriStripsData" => Block::GeometryData(tri_shape_data(reader, header, true)?),
+        "NiSkinInstance" | "BSDismemberSkinInstance" => {
+            let _data = reader.reference()?;
+            Block::SkinInstance {
+                partition: reader.reference()?,
+            }
+        }
+        "NiSkinPartition" if header.bs_version == 100 => Block::SkinPartition(skin_partition(reader, header)?),
+        "BSLightingShaderProperty" => Block::LightingShader(lighting_shader(reader, header)?),
+        "BSEffectShaderProperty" => Block::EffectShader(effect_shader(reader, header)?),
+        "BSShaderTextureSet" => {
+            let count = reader.u32()? as usize;
+            Block::TextureSet((0..count).map(|_| reader.sized_string()).collect::<io::Result<_>>()?)
+        }
+        "NiAlphaProperty" => {
+            object_net(reader, header)?;
+            Block::Alpha {
+                flags: reader.u16()?,
+                threshold: reader.u8()?,
+            }
+        }
+        _ => Block::Other(type_name.to_string()),
+    })
+}
+
+fn object_net(reader: &mut Reader, header: &Header) -> io::Result<String> {
+    let name = header.string(reader)?;
+    let _extra_data = reader.references()?;
+    let _controller = reader.reference()?;
+    Ok(name)
+}
+
+fn av_object(reader: &mut Reader, header: &Header) -> io::Result<AvObject> {
+    let name = object_net(reader, header)?;
+    let flags = if header.bs_version > 26 {
+        reader.u32()?
+    } else {
+        reader.u16()? as u32
+    };
+
+    let transform = Transform {
+        translation: reader.vec3()?,
+        rotation: reader.matrix3()?,
+        scale: reader.f32()?,
+    };
+
+    if header.bs_version <= 34 {
+        let _properties = reader.references()?;
+    }
+
+    let _collision = reader.reference()?;
+
+    Ok(AvObject { name, flags, transform })
+}
+
+fn ni_geometry(reader: &mut Reader, header: &Header) -> io::Result<Geometry> {
+    let object = av_object(reader, header)?;
+    let data = reader.reference()?;
+    let skin = reader.reference()?;
+
+    let materials = reader.u32()? as usize;
+    reader.skip(materials * 8)?;
+    let _active_material = reader.u32()?;
+    let _material_needs_update = reader.bool()?;
+
+    let (shader, alpha) = if header.bs_version > 34 {
+        (reader.reference()?, reader.reference()?)
+    } else {
+        (None, None)
+    };
+
+    Ok(Geometry {
+        object,
+        source: GeometrySource::Data(data),
+        skin,
+        shader,
+        alpha,
+    })
+}
+
+fn bs_tri_shape(type_name: &str, reader: &mut Reader, header: &Header) -> io::Result<Geometry> {
+    let object = av_object(reader, header)?;
+    let _bounding_sphere = reader.vec4()?;
+    let skin = reader.reference()?;
+    let shader = reader.reference()?;
+    let alpha = reader.reference()?;
+    let descriptor = reader.u64()?;
+
+    let triangle_count = if header.bs_version >= 130 {
+        reader.u32()? as usize
+    } else {
+        reader.u16()? as usize
+    };
+    let vertex_count = reader.u16()? as usize;
+    let data_size = reader.u32()?;
+
+    let mut vertices = Vertices::default();
+
+    if data_size > 0 {
+        let stride = (descriptor & 0xf) as usize * 4;
+        vertices = vertex_data(reader, header, descriptor, stride, vertex_count)?;
+        vertices.triangles = triangles(reader, triangle_count)?;
+    }
+
+    if type_name == "BSDynamicTriShape" {
+        // Facegen heads keep their morphed positions after the shape; the vertex data holds placeholders.
+        let _particle_data_size = if header.bs_version == 100 { reader.u32()? } else { 0 };
+        let dynamic_size = reader.u32()? as usize;
+
+        for position in vertices.positions.iter_mut().take(dynamic_size / 16) {
+            let dynamic = reader.vec4()?;
+            *position = [dynamic[0], dynamic[1], dynamic[2]];
+        }
+    }
+
+    Ok(Geometry {
+        object,
+        source: GeometrySource::Inline(vertices),
+        skin,
+        shader,
+        alpha,
+    })
+}
+
+/// Reads packed `BSVertexData`, one `stride`-sized entry per vertex.
+fn vertex_data(
+    reader: &mut Reader,
+    header: &Header,
+    descriptor: u64,
+    stride: usize,
+    count: usize,
+) -> io::Result<Vertices> {
+    let flags = descriptor >> 44;
+    let full_precision = flags & VF_FULL_PRECISION != 0 || header.bs_version == 100;
+    let mut vertices = Vertices::default();
+
+    for _ in 0..count {
+        let mut vertex = Reader::new(reader.bytes(stride)?);
+
+        if flags & VF_VERTEX != 0 {
+            if full_precision {
+                vertices.positions.push(vertex.vec3()?);
+                vertex.skip(4)?;
+            } else {
+                vertices
+                    .positions
+                    .push([vertex.half()?, vertex.half()?, vertex.half()?]);
+                vertex.skip(2)?;
+            }
+        }
+
+        if flags & VF_UV != 0 {
+            vertices.uvs.push([vertex.half()?, vertex.half()?]);
+        }
+
+        if flags & VF_NORMAL != 0 {
+            vertices
+                .normals
+                .push([vertex.normal_byte()?, vertex.normal_byte()?, vertex.normal_byte()?]);
+            vertex.skip(1)?;
+
+            if flags & VF_TANGENT != 0 {
+                vertex.skip(4)?;
+            }
+        }
+
+        if flags & VF_COLORS != 0 {
+            let color = vertex.bytes(4)?;
+            vertices.colors.push([
+                color[0] as f32 / 255.0,
+                color[1] as f32 / 255.0,
+                color[2] as f32 / 255.0,
+                color[3] as f32 / 255.0,
+            ]);
+        }
+    }
+
+    Ok(vertices)
+}
+
+fn triangles(reader: &mut Reader, count: usize) -> io::Result<Vec<[u16; 3]>> {
+    (0..count)
+        .map(|_| Ok([reader.u16()?, reader.u16()?, reader.u16()?]))
+        .collect()
+}
+
+fn tri_shape_data(reader: &mut Reader, header: &Header, strips: bool) -> io::Result<Vertices> {
+    let mut vertices = Vertices::default();
+
+    let _group_id = reader.u32()?;
+    let count = reader.u16()? as usize;
+    let _keep_flags = reader.u8()?;
+    let _compress_flags = reader.u8()?;
+
+    if reader.bool()? {
+        vertices.positions = (0..count).map(|_| reader.vec3()).collect::<io::Result<_>>()?;
+    }
+
+    let data_flags = reader.u16()?;
+
+    if header.bs_version > 34 {
+        let _material_crc = reader.u32()?;
+    }
+
+    if reader.bool()? {
+        vertices.normals = (0..count).map(|_| reader.vec3()).collect::<io::Result<_>>()?;
+
+        if data_flags & 0x1000 != 0 {
+            reader.skip(count * 24)?;
+        }
+    }
+
+    let _bounding_sphere = reader.vec4()?;
+
+    if reader.bool()? {
+        vertices.colors = (0..count).map(|_| reader.vec4()).collect::<io::Result<_>>()?;
+    }
+
+    // Bethesda files store a single "has UVs" bit where other games store a UV set count.
+    let uv_sets = if header.bs_version > 0 {
+        data_flags & 1
+    } else {
+        data_flags & 0x3f
+    };
+
+    for set in 0..uv_sets {
+        let uvs = (0..count).map(|_| reader.vec2()).collect::<io::Result<Vec<_>>>()?;
+
+        if set == 0 {
+            vertices.uvs = uvs;
+        }
+    }
+
+    let _consistency_flags = reader.u16()?;
+    let _additional_data = reader.reference()?;
+    let triangle_count = reader.u16()? as usize;
+
+    if strips {
+        let strip_count = reader.u16()? as usize;
+        let lengths = (0..strip_count).map(|_| reader.u16()).collect::<io::Result<Vec<_>>>()?;
+
+        if reader.bool()? {
+            for length in lengths {
+                let points = (0..length).map(|_| reader.u16()).collect::<io::Result<Vec<_>>>()?;
+                vertices.triangles.extend(triangulate_strip(&points));
+            }
+        }
+    } else {
+        let _triangle_points = reader.u32()?;
+
+        if reader.bool()? {
+            vertices.triangles = triangles(reader, triangle_count)?;
+        }
+    }
+
+    Ok(vertices)
+}
+
+/// Converts a triangle strip to a list, flipping every other triangle to keep the winding consistent.
+pub(crate) fn triangulate_strip(points: &[u16]) -> Vec<[u16; 3]> {
+    points
+        .windows(3)
+        .enumerate()
+        .filter(|(_, w)| w[0] != w[1] && w[1] != w[2] && w[0] != w[2])
+        .map(|(i, w)| {
+            if i % 2 == 0 {
+                [w[0], w[1], w[2]]
+            } else {
+                [w[0], w[2], w[1]]
+            }
+        })
+        .collect()
+}
+
+fn skin_partition(reader: &mut Reader, header: &Header) -> io::Result<Vertices> {
+    let partition_count = reader.u32()? as usize;
+    let data_size = reader.u32()? as usize;
+    let vertex_size = reader.u32()? as usize;
+    let descriptor = reader.u64()?;
+
+    let vertex_count = data_size.checked_div(vertex_size).unwrap_or(0);
+    let mut vertices = vertex_data(reader, header, descriptor, vertex_size, vertex_count)?;
+
+    for _ in 0..partition_count {
+        let partition_vertices = reader.u16()? as usize;
+        let partition_triangles = reader.u16()? as usize;
+        let bones = reader.u16()? as usize;
+        let strips = reader.u16()? as usize;
+        let weights_per_vertex = reader.u16()? as usize;
+        reader.skip(bones * 2)?;
+
+        if reader.bool()? {
+            reader.skip(partition_vertices * 2)?;
+        }
+
+        if reader.bool()? {
+            reader.skip(partition_vertices * weights_per_vertex * 4)?;
+        }
+
+        let strip_lengths = (0..strips).map(|_| reader.u16()).collect::<io::Result<Vec<_>>>()?;
+
+        if reader.bool()? {
+            if strips == 0 {
+                reader.skip(partition_triangles * 6)?;
+            } else {
+                reader.skip(strip_lengths.iter().map(|&length| length as usize * 2).sum())?;
+            }
+        }
+
+        if reader.bool()? {
+            reader.skip(partition_vertices * weights_per_vertex)?;
+        }
+
+        let _lod_level = reader.u8()?;
+        let _global_vertex_buffer = reader.bool()?;
+        let _descriptor = reader.u64()?;
+
+        // Unlike the partition's own triangles, these index the shared vertex buffer.
+        vertices.triangles.extend(triangles(reader, partition_triangles)?);
+    }
+
+    Ok(vertices)
+}
+
+fn lighting_shader(reader: &mut Reader, header: &Header) -> io::Result<LightingShader> {
+    let shader_type = reader.u32()?;
+    object_net(reader, header)?;
+
+    let flags1 = reader.u32()?;
+    let flags2 = reader.u32()?;
+    let uv_offset = reader.vec2()?;
+    let uv_scale = reader.vec2()?;
+    let texture_set = reader.reference()?;
+    let emissive_color = reader.vec3()?;
+    let emissive_multiple = reader.f32()?;
+    let _texture_clamp_mode = reader.u32()?;
+    let alpha = reader.f32()?;
+    let _refraction_strength = reader.f32()?;
+    let glossiness = reader.f32()?;
+    let specular_color = reader.vec3()?;
+    let specular_strength = reader.f32()?;
+
+    Ok(LightingShader {
+        shader_type,
+        flags1,
+        flags2,
+        uv_offset,
+        uv_scale,
+        texture_set,
+        emissive_color,
+        emissive_multiple,
+        alpha,
+        glossiness,
+        specular_color,
+        specular_strength,
+    })
+}
+
+fn effect_shader(reader: &mut Reader, header: &Header) -> io::Result<EffectShader> {
+    object_net(reader, header)?;
+
+    let flags1 = reader.u32()?;
+    let flags2 = reader.u32()?;
+    let uv_offset = reader.vec2()?;
+    let uv_scale = reader.vec2()?;
+    let source_texture = reader.sized_string()?;
+    let _clamp_and_lighting = reader.u32()?;
+    let _falloff = reader.vec4()?;
+    let emissive_color = reader.vec4()?;
+    let emissive_multiple = reader.f32()?;
+
+    Ok(EffectShader {
+        flags1,
+        flags2,
+        uv_offset,
+        uv_scale,
+        source_texture,
+        emissive_color,
+        emissive_multiple,
+    })
+}
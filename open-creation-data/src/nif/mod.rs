@@ -0,0 +1,492 @@
+//! Reading Skyrim meshes (`.nif`, version 20.2.0.7) into flat, textured shapes.
+//!
+//! Only the blocks needed to draw a model are decoded: the node hierarchy, `NiTriShape`/`NiTriStrips` (Legendary
+//! Edition), `BSTriShape` (Special Edition), skin partitions holding skinned SE vertices, and the shader and alpha
+//! properties that carry textures. Everything else is skipped using the block sizes in the header.
+
+mod blocks;
+mod reader;
+
+pub use blocks::{AvObject, Block, EffectShader, Geometry, GeometrySource, LightingShader, Transform, Vertices};
+pub use reader::half_to_f32;
+
+use crate::esp::invalid;
+use reader::Reader;
+
+use std::io;
+
+pub const VERSION: u32 = 0x1402_0007;
+
+// Flags from `BSLightingShaderProperty` and `BSEffectShaderProperty`.
+const SLSF2_DOUBLE_SIDED: u32 = 0x10;
+const SLSF2_VERTEX_COLORS: u32 = 0x20;
+
+// Flags from `NiAlphaProperty`.
+const ALPHA_BLEND: u16 = 0x0001;
+const ALPHA_TEST: u16 = 0x0200;
+
+#[derive(Clone, Debug)]
+pub struct Header {
+    pub version: u32,
+    pub user_version: u32,
+    /// 83 for Skyrim, 100 for Skyrim Special Edition.
+    pub bs_version: u32,
+    pub block_types: Vec<String>,
+    pub block_type_indices: Vec<u16>,
+    pub block_sizes: Vec<u32>,
+    pub strings: Vec<String>,
+}
+
+impl Header {
+    fn parse(reader: &mut Reader) -> io::Result<Self> {
+        let line = reader.line()?;
+
+        if !line.starts_with("Gamebryo File Format") {
+            return Err(invalid("not a NIF file"));
+        }
+
+        let version = reader.u32()?;
+
+        if version != VERSION {
+            return Err(invalid(&format!("unsupported NIF version {}", line)));
+        }
+
+        let _endian = reader.u8()?;
+        let user_version = reader.u32()?;
+        let block_count = reader.u32()? as usize;
+        let bs_version = reader.u32()?;
+
+        let _author = reader.export_string()?;
+        if bs_version > 130 {
+            let _unknown = reader.u32()?;
+        }
+        let _process_script = reader.export_string()?;
+        let _export_script = reader.export_string()?;
+        if bs_version == 130 {
+            let _max_filepath = reader.export_string()?;
+        }
+
+        let type_count = reader.u16()? as usize;
+        let block_types = (0..type_count)
+            .map(|_| reader.sized_string())
+            .collect::<io::Result<_>>()?;
+        let block_type_indices = (0..block_count).map(|_| reader.u16()).collect::<io::Result<_>>()?;
+        let block_sizes = (0..block_count).map(|_| reader.u32()).collect::<io::Result<_>>()?;
+
+        let string_count = reader.u32()? as usize;
+        let _max_string_length = reader.u32()?;
+        let strings = (0..string_count)
+            .map(|_| reader.sized_string())
+            .collect::<io::Result<_>>()?;
+
+        let group_count = reader.u32()? as usize;
+        reader.skip(group_count * 4)?;
+
+        Ok(Self {
+            version,
+            user_version,
+            bs_version,
+            block_types,
+            block_type_indices,
+            block_sizes,
+            strings,
+        })
+    }
+
+    /// Reads an index into the string table.
+    fn string(&self, reader: &mut Reader) -> io::Result<String> {
+        let index = reader.u32()?;
+        Ok(self.strings.get(index as usize).cloned().unwrap_or_default())
+    }
+
+    pub fn block_type(&self, block: usize) -> &str {
+        self.block_type_indices
+            .get(block)
+            .and_then(|&index| self.block_types.get((index & 0x7fff) as usize))
+            .map(|name| name.as_str())
+            .unwrap_or("")
+    }
+}
+
+/// The textures and render state of one shape. Texture paths are normalized data paths (`textures\...`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub diffuse: Option<String>,
+    pub normal: Option<String>,
+    pub glow: Option<String>,
+    pub alpha: f32,
+    pub alpha_blend: bool,
+    /// The cutoff below which pixels are discarded, from 0 to 1, if alpha testing is enabled.
+    pub alpha_test: Option<f32>,
+    pub double_sided: bool,
+    pub vertex_colors: bool,
+    pub emissive: [f32; 3],
+    pub glossiness: f32,
+    pub uv_offset: [f32; 2],
+    pub uv_scale: [f32; 2],
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            diffuse: None,
+            normal: None,
+            glow: None,
+            alpha: 1.0,
+            alpha_blend: false,
+            alpha_test: None,
+            double_sided: false,
+            vertex_colors: false,
+            emissive: [0.0; 3],
+            glossiness: 1.0,
+            uv_offset: [0.0; 2],
+            uv_scale: [1.0; 2],
+        }
+    }
+}
+
+/// A drawable piece of a model, with its vertices already moved into model space.
+#[derive(Clone, Debug)]
+pub struct Shape {
+    pub name: String,
+    pub vertices: Vertices,
+    pub material: Material,
+}
+
+pub struct Nif {
+    pub header: Header,
+    pub blocks: Vec<Block>,
+    pub roots: Vec<usize>,
+}
+
+impl Nif {
+    pub fn parse(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let header = Header::parse(&mut reader)?;
+        let mut blocks = Vec::with_capacity(header.block_sizes.len());
+
+        for (index, &size) in header.block_sizes.iter().enumerate() {
+            let type_name = header.block_type(index);
+            let mut block_reader = Reader::new(reader.bytes(size as usize)?);
+
+            let block = blocks::parse(type_name, &mut block_reader, &header).unwrap_or_else(|e| {
+                log::warn!("Skipping unreadable NIF block {} ({}): {}", index, type_name, e);
+                Block::Other(type_name.to_string())
+            });
+
+            blocks.push(block);
+        }
+
+        let roots = reader
+            .references()
+            .map(|roots| roots.into_iter().flatten().collect())
+            .unwrap_or_else(|_| vec![0]);
+
+        Ok(Self { header, blocks, roots })
+    }
+
+    /// Every visible shape in the model, in model space.
+    pub fn shapes(&self) -> Vec<Shape> {
+        let mut shapes = vec![];
+
+        for &root in &self.roots {
+            self.collect_shapes(root, &Transform::default(), &mut shapes, 0);
+        }
+
+        shapes
+    }
+
+    fn collect_shapes(&self, index: usize, parent: &Transform, shapes: &mut Vec<Shape>, depth: usize) {
+        // Guards against reference cycles in malformed files.
+        if depth > 64 {
+            return;
+        }
+
+        match self.blocks.get(index) {
+            Some(Block::Node { object, children }) if !object.is_hidden() => {
+                let transform = parent.then(&object.transform);
+
+                for &child in children.iter().flatten() {
+                    self.collect_shapes(child, &transform, shapes, depth + 1);
+                }
+            }
+            Some(Block::Geometry(geometry)) if !geometry.object.is_hidden() => {
+                if let Some(shape) = self.shape(geometry, &parent.then(&geometry.object.transform)) {
+                    shapes.push(shape);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn shape(&self, geometry: &Geometry, transform: &Transform) -> Option<Shape> {
+        let source = match &geometry.source {
+            GeometrySource::Inline(vertices) if !vertices.positions.is_empty() => Some(vertices),
+            GeometrySource::Inline(_) => self.skin_vertices(geometry.skin),
+            GeometrySource::Data(data) => match data.and_then(|data| self.blocks.get(data)) {
+                Some(Block::GeometryData(vertices)) => Some(vertices),
+                _ => None,
+            },
+        }?;
+
+        if source.positions.is_empty() || source.triangles.is_empty() {
+            return None;
+        }
+
+        let mut vertices = source.clone();
+
+        for position in &mut vertices.positions {
+            *position = transform.apply(*position);
+        }
+
+        for normal in &mut vertices.normals {
+            let [x, y, z] = transform.rotate(*normal);
+            let length = (x * x + y * y + z * z).sqrt().max(f32::EPSILON);
+            *normal = [x / length, y / length, z / length];
+        }
+
+        let count = vertices.positions.len();
+        let max_index = vertices
+            .triangles
+            .iter()
+            .flatten()
+            .map(|&i| i as usize)
+            .max()
+            .unwrap_or(0);
+
+        if max_index >= count {
+            log::warn!(
+                "Shape {} has triangles referencing missing vertices",
+                geometry.object.name
+            );
+            return None;
+        }
+
+        for len in [vertices.normals.len(), vertices.uvs.len(), vertices.colors.len()].iter() {
+            if *len != 0 && *len != count {
+                log::warn!("Shape {} has mismatched vertex attribute counts", geometry.object.name);
+                return None;
+            }
+        }
+
+        Some(Shape {
+            name: geometry.object.name.clone(),
+            vertices,
+            material: self.material(geometry),
+        })
+    }
+
+    fn skin_vertices(&self, skin: Option<usize>) -> Option<&Vertices> {
+        let partition = match self.blocks.get(skin?)? {
+            Block::SkinInstance { partition } => (*partition)?,
+            _ => return None,
+        };
+
+        match self.blocks.get(partition)? {
+            Block::SkinPartition(vertices) => Some(vertices),
+            _ => None,
+        }
+    }
+
+    fn material(&self, geometry: &Geometry) -> Material {
+        let mut material = Material::default();
+
+        match geometry.shader.and_then(|shader| self.blocks.get(shader)) {
+            Some(Block::LightingShader(shader)) => {
+                if let Some(Block::TextureSet(textures)) = shader.texture_set.and_then(|set| self.blocks.get(set)) {
+                    let texture = |slot: usize| textures.get(slot).and_then(|path| texture_path(path));
+                    material.diffuse = texture(0);
+                    material.normal = texture(1);
+                    material.glow = texture(2).filter(|_| shader.shader_type == 2);
+                }
+
+                material.alpha = shader.alpha;
+                material.double_sided = shader.flags2 & SLSF2_DOUBLE_SIDED != 0;
+                material.vertex_colors = shader.flags2 & SLSF2_VERTEX_COLORS != 0;
+                material.glossiness = shader.glossiness;
+                material.uv_offset = shader.uv_offset;
+                material.uv_scale = shader.uv_scale;
+                material.emissive = [
+                    shader.emissive_color[0] * shader.emissive_multiple,
+                    shader.emissive_color[1] * shader.emissive_multiple,
+                    shader.emissive_color[2] * shader.emissive_multiple,
+                ];
+            }
+            Some(Block::EffectShader(shader)) => {
+                material.diffuse = texture_path(&shader.source_texture);
+                material.double_sided = shader.flags2 & SLSF2_DOUBLE_SIDED != 0;
+                material.vertex_colors = shader.flags2 & SLSF2_VERTEX_COLORS != 0;
+                material.uv_offset = shader.uv_offset;
+                material.uv_scale = shader.uv_scale;
+                material.alpha_blend = true;
+                material.emissive = [
+                    shader.emissive_color[0] * shader.emissive_multiple,
+                    shader.emissive_color[1] * shader.emissive_multiple,
+                    shader.emissive_color[2] * shader.emissive_multiple,
+                ];
+            }
+            _ => {}
+        }
+
+        if let Some(Block::Alpha { flags, threshold }) = geometry.alpha.and_then(|alpha| self.blocks.get(alpha)) {
+            material.alpha_blend = flags & ALPHA_BLEND != 0;
+
+            if flags & ALPHA_TEST != 0 {
+                material.alpha_test = Some(*threshold as f32 / 255.0);
+            }
+        }
+
+        material
+    }
+}
+
+/// Normalizes a texture path from a NIF, which may or may not include the `textures\` folder.
+fn texture_path(path: &str) -> Option<String> {
+    let path = crate::path::normalize(path);
+
+    if path.is_empty() {
+        None
+    } else if path.starts_with("textures\\") {
+        Some(path)
+    } else {
+        Some(format!("textures\\{}", path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sized(bytes: &mut Vec<u8>, value: &str) {
+        bytes.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn u32s(bytes: &mut Vec<u8>, values: &[u32]) {
+        values
+            .iter()
+            .for_each(|value| bytes.extend_from_slice(&value.to_le_bytes()));
+    }
+
+    fn f32s(bytes: &mut Vec<u8>, values: &[f32]) {
+        values
+            .iter()
+            .for_each(|value| bytes.extend_from_slice(&value.to_le_bytes()));
+    }
+
+    fn av_object(bytes: &mut Vec<u8>, translation: [f32; 3]) {
+        u32s(bytes, &[0, 0, u32::MAX, 0]);
+        f32s(bytes, &translation);
+        f32s(bytes, &[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 2.0]);
+        u32s(bytes, &[u32::MAX]);
+    }
+
+    /// A Special Edition file with a translated, scaled root node and one textured triangle.
+    fn sample() -> Vec<u8> {
+        let mut node = vec![];
+        av_object(&mut node, [10.0, 0.0, 0.0]);
+        u32s(&mut node, &[1, 1, 0]);
+
+        let mut shape = vec![];
+        av_object(&mut shape, [0.0, 0.0, 0.0]);
+        f32s(&mut shape, &[0.0; 4]);
+        u32s(&mut shape, &[u32::MAX, 2, u32::MAX]);
+        // Positions and UVs, 20 bytes per vertex.
+        let descriptor: u64 = (0x003 << 44) | 5;
+        shape.extend_from_slice(&descriptor.to_le_bytes());
+        shape.extend_from_slice(&1u16.to_le_bytes());
+        shape.extend_from_slice(&3u16.to_le_bytes());
+        u32s(&mut shape, &[60]);
+        for (position, uv) in [
+            ([0.0, 0.0, 0.0], [0, 0]),
+            ([1.0, 0.0, 0.0], [0x3c00, 0]),
+            ([0.0, 1.0, 0.0], [0, 0x3c00]),
+        ]
+        .iter()
+        {
+            f32s(&mut shape, position);
+            u32s(&mut shape, &[0]);
+            shape.extend_from_slice(&(uv[0] as u16).to_le_bytes());
+            shape.extend_from_slice(&(uv[1] as u16).to_le_bytes());
+        }
+        for index in [0u16, 1, 2].iter() {
+            shape.extend_from_slice(&index.to_le_bytes());
+        }
+
+        let mut shader = vec![];
+        u32s(&mut shader, &[1, 0, 0, u32::MAX, 0, SLSF2_DOUBLE_SIDED]);
+        f32s(&mut shader, &[0.0, 0.0, 1.0, 1.0]);
+        u32s(&mut shader, &[3]);
+        f32s(&mut shader, &[0.0, 0.0, 0.0, 1.0]);
+        u32s(&mut shader, &[3]);
+        f32s(&mut shader, &[1.0, 1.0, 80.0, 1.0, 1.0, 1.0, 1.0]);
+
+        let mut textures = vec![];
+        u32s(&mut textures, &[2]);
+        sized(&mut textures, "textures\\clutter\\bucket.dds");
+        sized(&mut textures, "Clutter\\Bucket_n.dds");
+
+        let mut bytes = b"Gamebryo File Format, Version 20.2.0.7\n".to_vec();
+        u32s(&mut bytes, &[VERSION]);
+        bytes.push(1);
+        u32s(&mut bytes, &[12, 4, 100]);
+        bytes.extend_from_slice(&[1, 0, 1, 0, 1, 0]);
+        bytes.extend_from_slice(&4u16.to_le_bytes());
+        for name in [
+            "BSFadeNode",
+            "BSTriShape",
+            "BSLightingShaderProperty",
+            "BSShaderTextureSet",
+        ]
+        .iter()
+        {
+            sized(&mut bytes, name);
+        }
+        for index in 0..4u16 {
+            bytes.extend_from_slice(&index.to_le_bytes());
+        }
+        let blocks = [node, shape, shader, textures];
+        for block in blocks.iter() {
+            u32s(&mut bytes, &[block.len() as u32]);
+        }
+        u32s(&mut bytes, &[1, 5]);
+        sized(&mut bytes, "Scene");
+        u32s(&mut bytes, &[0]);
+        for block in blocks.iter() {
+            bytes.extend_from_slice(block);
+        }
+        u32s(&mut bytes, &[1, 0]);
+
+        bytes
+    }
+
+    #[test]
+    fn reads_special_edition_shape() {
+        let nif = Nif::parse(&sample()).unwrap();
+        assert_eq!(nif.header.bs_version, 100);
+
+        let shapes = nif.shapes();
+        assert_eq!(shapes.len(), 1);
+
+        let vertices = &shapes[0].vertices;
+        assert_eq!(
+            vertices.positions,
+            vec![[10.0, 0.0, 0.0], [14.0, 0.0, 0.0], [10.0, 4.0, 0.0]]
+        );
+        assert_eq!(vertices.uvs[1], [1.0, 0.0]);
+        assert_eq!(vertices.triangles, vec![[0, 1, 2]]);
+
+        let material = &shapes[0].material;
+        assert_eq!(material.diffuse.as_deref(), Some("textures\\clutter\\bucket.dds"));
+        assert_eq!(material.normal.as_deref(), Some("textures\\clutter\\bucket_n.dds"));
+        assert!(material.double_sided);
+    }
+
+    #[test]
+    fn triangulates_strips() {
+        assert_eq!(
+            blocks::triangulate_strip(&[0, 1, 2, 3, 3, 4]),
+            vec![[0, 1, 2], [1, 3, 2]]
+        );
+    }
+}
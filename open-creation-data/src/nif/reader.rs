@@ -0,0 +1,129 @@
+use std::io;
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of NIF data")
+}
+
+/// A little-endian cursor over a NIF file or one of its blocks.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    pub fn bytes(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        let end = self.position.checked_add(count).ok_or_else(truncated)?;
+        let bytes = self.bytes.get(self.position..end).ok_or_else(truncated)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    pub fn skip(&mut self, count: usize) -> io::Result<()> {
+        self.bytes(count).map(|_| ())
+    }
+
+    pub fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> io::Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn u64(&mut self) -> io::Result<u64> {
+        Ok(self.u32()? as u64 | (self.u32()? as u64) << 32)
+    }
+
+    pub fn f32(&mut self) -> io::Result<f32> {
+        self.u32().map(f32::from_bits)
+    }
+
+    pub fn half(&mut self) -> io::Result<f32> {
+        self.u16().map(half_to_f32)
+    }
+
+    /// A byte mapped from `0..=255` to `-1.0..=1.0`, as used for packed normals and tangents.
+    pub fn normal_byte(&mut self) -> io::Result<f32> {
+        Ok(self.u8()? as f32 / 255.0 * 2.0 - 1.0)
+    }
+
+    pub fn vec2(&mut self) -> io::Result<[f32; 2]> {
+        Ok([self.f32()?, self.f32()?])
+    }
+
+    pub fn vec3(&mut self) -> io::Result<[f32; 3]> {
+        Ok([self.f32()?, self.f32()?, self.f32()?])
+    }
+
+    pub fn vec4(&mut self) -> io::Result<[f32; 4]> {
+        Ok([self.f32()?, self.f32()?, self.f32()?, self.f32()?])
+    }
+
+    pub fn matrix3(&mut self) -> io::Result<[[f32; 3]; 3]> {
+        Ok([self.vec3()?, self.vec3()?, self.vec3()?])
+    }
+
+    /// A block reference, where negative values mean none.
+    pub fn reference(&mut self) -> io::Result<Option<usize>> {
+        let value = self.u32()? as i32;
+        Ok(if value < 0 { None } else { Some(value as usize) })
+    }
+
+    pub fn references(&mut self) -> io::Result<Vec<Option<usize>>> {
+        let count = self.u32()? as usize;
+        (0..count).map(|_| self.reference()).collect()
+    }
+
+    /// A string with a 32-bit length prefix.
+    pub fn sized_string(&mut self) -> io::Result<String> {
+        let length = self.u32()? as usize;
+        Ok(latin1(self.bytes(length)?))
+    }
+
+    /// A string with an 8-bit length prefix that counts its null terminator.
+    pub fn export_string(&mut self) -> io::Result<String> {
+        let length = self.u8()? as usize;
+        Ok(latin1(self.bytes(length)?))
+    }
+
+    /// The text header line, which ends with a newline.
+    pub fn line(&mut self) -> io::Result<String> {
+        let rest = &self.bytes[self.position..];
+        let end = rest.iter().position(|&c| c == b'\n').ok_or_else(truncated)?;
+        self.position += end + 1;
+        Ok(latin1(&rest[..end]))
+    }
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+    bytes[..end].iter().map(|&c| c as char).collect()
+}
+
+/// Decodes an IEEE 754 half-precision float.
+pub fn half_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
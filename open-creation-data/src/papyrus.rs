@@ -0,0 +1,284 @@
+//! Papyrus source files: splitting them into tokens for highlighting, and reading the diagnostics the compiler
+//! prints.
+//!
+//! Papyrus is case-insensitive. Comments run from `;` to the end of the line, or from `;/` to `/;`, and
+//! documentation comments are written in braces; the last two may span lines.
+
+use crate::vfs::VirtualFileSystem;
+
+/// The sources of the compiled scripts in `scripts\`, relative to the data folder.
+pub const SOURCE_FOLDER: &str = "scripts\\source";
+
+const KEYWORDS: [&str; 33] = [
+    "as",
+    "auto",
+    "autoreadonly",
+    "conditional",
+    "else",
+    "elseif",
+    "endevent",
+    "endfunction",
+    "endif",
+    "endproperty",
+    "endstate",
+    "endwhile",
+    "event",
+    "extends",
+    "false",
+    "function",
+    "global",
+    "hidden",
+    "if",
+    "import",
+    "length",
+    "native",
+    "new",
+    "none",
+    "parent",
+    "property",
+    "return",
+    "scriptname",
+    "self",
+    "state",
+    "true",
+    "while",
+    "var",
+];
+const TYPES: [&str; 4] = ["bool", "float", "int", "string"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    Type,
+    Identifier,
+    Number,
+    String,
+    Comment,
+    /// Operators, brackets and anything else.
+    Symbol,
+    Whitespace,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+}
+
+/// The names of the sources in the data files, in lowercase as stored in the archives, sorted.
+pub fn source_names(vfs: &VirtualFileSystem) -> Vec<String> {
+    let prefix = format!("{}\\", SOURCE_FOLDER);
+    let mut names: Vec<String> = vfs
+        .paths()
+        .filter_map(|path| path.strip_prefix(&prefix)?.strip_suffix(".psc"))
+        .filter(|name| !name.contains('\\'))
+        .map(String::from)
+        .collect();
+    names.sort();
+    names
+}
+
+/// Splits a source file into lines of tokens. Comments that span lines are split at the line breaks, which are
+/// not included in any token.
+pub fn highlight(source: &str) -> Vec<Vec<Token<'_>>> {
+    let mut lines = vec![vec![]];
+    let mut rest = source;
+
+    while let Some(first) = rest.chars().next() {
+        let length = match first {
+            '\n' => {
+                lines.push(vec![]);
+                rest = &rest[1..];
+                continue;
+            }
+            ';' if rest.starts_with(";/") => rest.find("/;").map_or(rest.len(), |end| end + 2),
+            ';' => rest.find('\n').unwrap_or(rest.len()),
+            '{' => rest.find('}').map_or(rest.len(), |end| end + 1),
+            '"' => string_length(rest),
+            _ => rest
+                .char_indices()
+                .skip(1)
+                .find(|&(_, c)| !continues(first, c))
+                .map_or(rest.len(), |(index, _)| index),
+        };
+
+        let kind = match first {
+            ';' | '{' => TokenKind::Comment,
+            '"' => TokenKind::String,
+            c if c.is_ascii_digit() => TokenKind::Number,
+            c if c.is_alphabetic() || c == '_' => word_kind(&rest[..length]),
+            c if c.is_whitespace() => TokenKind::Whitespace,
+            _ => TokenKind::Symbol,
+        };
+
+        for (index, text) in rest[..length].split('\n').enumerate() {
+            if index > 0 {
+                lines.push(vec![]);
+            }
+
+            if !text.is_empty() {
+                lines.last_mut().unwrap().push(Token { kind, text });
+            }
+        }
+
+        rest = &rest[length..];
+    }
+
+    lines
+}
+
+/// Whether `c` continues a token that starts with `first`.
+fn continues(first: char, c: char) -> bool {
+    if first.is_alphabetic() || first == '_' {
+        c.is_alphanumeric() || c == '_'
+    } else if first.is_ascii_digit() {
+        c.is_ascii_hexdigit() || c == '.' || c == 'x' || c == 'X'
+    } else if first.is_whitespace() {
+        c.is_whitespace() && c != '\n'
+    } else {
+        false
+    }
+}
+
+/// The length of a string literal at the start of `source`, up to its closing quote or the end of the line.
+fn string_length(source: &str) -> usize {
+    let mut escaped = false;
+
+    for (index, c) in source.char_indices().skip(1) {
+        match c {
+            '\n' => return index,
+            '"' if !escaped => return index + 1,
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+
+    source.len()
+}
+
+fn word_kind(word: &str) -> TokenKind {
+    let word = word.to_lowercase();
+
+    if TYPES.contains(&word.as_str()) {
+        TokenKind::Type
+    } else if KEYWORDS.contains(&word.as_str()) {
+        TokenKind::Keyword
+    } else {
+        TokenKind::Identifier
+    }
+}
+
+/// An error or warning printed by the compiler, as `<path>.psc(<line>,<column>): <message>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// The name of the script, without its folder or extension.
+    pub script: String,
+    /// The line, counted from 1.
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Finds a diagnostic in a line of compiler output, which may be prefixed as it is in the log.
+    pub fn parse(line: &str) -> Option<Self> {
+        let start = line.to_ascii_lowercase().find(".psc(")?;
+        let path = &line[..start];
+        let script = path.rsplit(&['\\', '/', ' ', ']'][..]).next()?;
+
+        let rest = &line[start + ".psc(".len()..];
+        let (position, message) = rest.split_at(rest.find("):")?);
+        let mut position = position.split(',');
+        let line = position.next()?.trim().parse().ok()?;
+        let column = position.next().map_or(Some(0), |column| column.trim().parse().ok())?;
+
+        if script.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            script: script.to_string(),
+            line,
+            column,
+            message: message[2..].trim().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_tokens() {
+        let lines =
+            highlight("Scriptname Foo extends Quest\n{A quest\nscript}\nInt Property Count = 0x1F Auto ; count");
+        assert_eq!(lines.len(), 4);
+
+        let kinds = |line: &[Token]| -> Vec<TokenKind> {
+            line.iter()
+                .filter(|token| token.kind != TokenKind::Whitespace)
+                .map(|token| token.kind)
+                .collect()
+        };
+
+        assert_eq!(
+            kinds(&lines[0]),
+            vec![
+                TokenKind::Keyword,
+                TokenKind::Identifier,
+                TokenKind::Keyword,
+                TokenKind::Identifier
+            ]
+        );
+        assert_eq!(
+            lines[1],
+            vec![Token {
+                kind: TokenKind::Comment,
+                text: "{A quest"
+            }]
+        );
+        assert_eq!(
+            lines[2],
+            vec![Token {
+                kind: TokenKind::Comment,
+                text: "script}"
+            }]
+        );
+        assert_eq!(
+            kinds(&lines[3]),
+            vec![
+                TokenKind::Type,
+                TokenKind::Keyword,
+                TokenKind::Identifier,
+                TokenKind::Symbol,
+                TokenKind::Number,
+                TokenKind::Keyword,
+                TokenKind::Comment
+            ]
+        );
+        assert_eq!(lines[3][8].text, "0x1F");
+
+        let string = highlight("Debug.Trace(\"a \\\"b\\\" c\")");
+        assert!(string[0].contains(&Token {
+            kind: TokenKind::String,
+            text: "\"a \\\"b\\\" c\""
+        }));
+    }
+
+    #[test]
+    fn parses_diagnostics() {
+        assert_eq!(
+            Diagnostic::parse(
+                "[ERROR][open_creation::papyrus] C:\\Data\\Scripts\\Source\\MyQuest.psc(12,5): variable x is undefined"
+            ),
+            Some(Diagnostic {
+                script: "MyQuest".to_string(),
+                line: 12,
+                column: 5,
+                message: "variable x is undefined".to_string(),
+            })
+        );
+        assert_eq!(Diagnostic::parse("Compilation succeeded."), None);
+    }
+}
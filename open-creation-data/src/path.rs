@@ -0,0 +1,75 @@
+/// Normalises a data-relative asset path to the form used inside archives and records:
+/// lowercase, backslash-separated, with no leading separator or `data\` prefix.
+pub fn normalize(path: &str) -> String {
+    let mut normalized = path.trim().replace('/', "\\").to_lowercase();
+
+    while normalized.starts_with('\\') {
+        normalized.remove(0);
+    }
+
+    if normalized.starts_with("data\\") {
+        normalized.drain(..5);
+    }
+
+    normalized
+}
+
+/// Splits a normalised path into its folder and file name components.
+pub fn split(path: &str) -> (&str, &str) {
+    match path.rfind('\\') {
+        Some(index) => (&path[..index], &path[index + 1..]),
+        None => ("", path),
+    }
+}
+
+/// Matches a path against a simple wildcard pattern, where `*` matches any run of characters (including
+/// separators) and `?` matches exactly one. Both sides are normalised first, so matching is case-insensitive
+/// and indifferent to slash direction.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let pattern = normalize(pattern).into_bytes();
+    let path = normalize(path).into_bytes();
+
+    let (mut p, mut s) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while s < path.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == path[s]) {
+            p += 1;
+            s += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, s));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            s = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    while p < pattern.len() && pattern[p] == b'*' {
+        p += 1;
+    }
+
+    p == pattern.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_path() {
+        assert_eq!(normalize("Data/Meshes/Foo.NIF"), "meshes\\foo.nif");
+        assert_eq!(normalize("\\textures\\bar.dds"), "textures\\bar.dds");
+    }
+
+    #[test]
+    fn wildcard() {
+        assert!(matches("meshes/*", "meshes\\armor\\iron.nif"));
+        assert!(matches("*.DDS", "textures/iron_d.dds"));
+        assert!(matches("textures/?ron*", "textures/iron_d.dds"));
+        assert!(!matches("scripts/source/*", "scripts/quest.pex"));
+    }
+}
@@ -0,0 +1,493 @@
+//! Reading compiled Papyrus scripts (`.pex`, as compiled for Skyrim) for what they declare: the script they
+//! extend, their variables, properties, states and the functions in each state.
+//!
+//! Function bodies are skipped. Their instructions have to be read to find where each function ends, but the
+//! code itself is not kept.
+
+use crate::esp::invalid;
+
+use std::io;
+
+const MAGIC: u32 = 0xFA57_C0DE;
+/// The property flag for a property backed by a hidden variable, without read or write functions.
+const PROPERTY_AUTO: u8 = 0x04;
+const PROPERTY_READ: u8 = 0x01;
+const PROPERTY_WRITE: u8 = 0x02;
+const FUNCTION_GLOBAL: u8 = 0x01;
+const FUNCTION_NATIVE: u8 = 0x02;
+/// The opcodes whose fixed arguments are followed by a count and that many more arguments.
+const CALL_METHOD: u8 = 0x17;
+const CALL_PARENT: u8 = 0x18;
+const CALL_STATIC: u8 = 0x19;
+
+/// A script variable, declared in the script's body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub type_name: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Property {
+    pub name: String,
+    /// The Papyrus type, such as `Int`, `Actor` or `ObjectReference[]`.
+    pub type_name: String,
+    pub docstring: String,
+    pub flags: u8,
+}
+
+impl Property {
+    /// Whether the property is an auto property, whose value is set on the records the script is attached to.
+    pub fn is_auto(&self) -> bool {
+        self.flags & PROPERTY_AUTO != 0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub return_type: String,
+    pub docstring: String,
+    pub flags: u8,
+    /// Each parameter's name and type.
+    pub params: Vec<(String, String)>,
+}
+
+impl Function {
+    pub fn is_global(&self) -> bool {
+        self.flags & FUNCTION_GLOBAL != 0
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.flags & FUNCTION_NATIVE != 0
+    }
+
+    /// The function's declaration as it would be written in Papyrus source.
+    pub fn signature(&self) -> String {
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|(name, type_name)| format!("{} {}", type_name, name))
+            .collect();
+        let return_type = match self.return_type.as_str() {
+            "" | "None" => String::new(),
+            return_type => format!("{} ", return_type),
+        };
+
+        format!("{}Function {}({})", return_type, self.name, params.join(", "))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct State {
+    /// The state's name, which is empty for the script's default state.
+    pub name: String,
+    pub functions: Vec<Function>,
+}
+
+/// A script, as declared in its compiled file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Script {
+    pub name: String,
+    /// The script this one extends, or empty if it extends none.
+    pub parent: String,
+    pub docstring: String,
+    /// The state the script starts in, or empty for the default state.
+    pub auto_state: String,
+    pub variables: Vec<Variable>,
+    pub properties: Vec<Property>,
+    pub states: Vec<State>,
+}
+
+/// A compiled script file. Each file holds one script, named after the file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pex {
+    pub source: String,
+    pub user: String,
+    pub machine: String,
+    pub scripts: Vec<Script>,
+}
+
+impl Pex {
+    pub fn parse(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader { bytes, position: 0 };
+
+        if reader.u32()? != MAGIC {
+            return Err(invalid("not a compiled Skyrim Papyrus script"));
+        }
+
+        let _major = reader.u8()?;
+        let _minor = reader.u8()?;
+        let _game = reader.u16()?;
+        let _compiled = reader.bytes(8)?;
+        let source = reader.string()?;
+        let user = reader.string()?;
+        let machine = reader.string()?;
+
+        let string_count = reader.u16()?;
+        let strings = (0..string_count)
+            .map(|_| reader.string())
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut reader = Strings { reader, strings };
+
+        if reader.reader.u8()? != 0 {
+            let _modified = reader.reader.bytes(8)?;
+            let function_count = reader.reader.u16()?;
+
+            for _ in 0..function_count {
+                // Object, state and function names, and the function type.
+                reader.reader.bytes(7)?;
+                let line_count = reader.reader.u16()? as usize;
+                reader.reader.bytes(line_count * 2)?;
+            }
+        }
+
+        let user_flag_count = reader.reader.u16()? as usize;
+        reader.reader.bytes(user_flag_count * 3)?;
+
+        let script_count = reader.reader.u16()?;
+        let scripts = (0..script_count)
+            .map(|_| read_script(&mut reader))
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self {
+            source,
+            user,
+            machine,
+            scripts,
+        })
+    }
+}
+
+/// A big-endian cursor over a script file.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + count)
+            .ok_or_else(|| invalid("compiled script is truncated"))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        self.bytes(1).map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// A string prefixed with its length.
+    fn string(&mut self) -> io::Result<String> {
+        let length = self.u16()? as usize;
+        Ok(self.bytes(length)?.iter().map(|&c| c as char).collect())
+    }
+}
+
+/// A reader that resolves indices into the file's string table.
+struct Strings<'a> {
+    reader: Reader<'a>,
+    strings: Vec<String>,
+}
+
+impl<'a> Strings<'a> {
+    fn string(&mut self) -> io::Result<String> {
+        let index = self.reader.u16()? as usize;
+        self.strings
+            .get(index)
+            .cloned()
+            .ok_or_else(|| invalid("compiled script refers to a missing string"))
+    }
+
+    /// Reads past a value: a type, then nothing, a string index, an integer, a float or a bool. Returns the
+    /// value if it is an integer, as the count of a call's extra arguments is.
+    fn skip_value(&mut self) -> io::Result<i32> {
+        match self.reader.u8()? {
+            0 => Ok(0),
+            1 | 2 => self.reader.u16().map(i32::from),
+            3 => self.reader.u32().map(|int| int as i32),
+            4 => self.reader.u32().map(|_| 0),
+            5 => self.reader.u8().map(i32::from),
+            _ => Err(invalid("compiled script has a value of an unknown type")),
+        }
+    }
+}
+
+fn read_script(reader: &mut Strings) -> io::Result<Script> {
+    let name = reader.string()?;
+    let _size = reader.reader.u32()?;
+    let parent = reader.string()?;
+    let docstring = reader.string()?;
+    let _user_flags = reader.reader.u32()?;
+    let auto_state = reader.string()?;
+
+    let variable_count = reader.reader.u16()?;
+    let mut variables = vec![];
+    for _ in 0..variable_count {
+        let name = reader.string()?;
+        let type_name = reader.string()?;
+        let _user_flags = reader.reader.u32()?;
+        reader.skip_value()?;
+        variables.push(Variable { name, type_name });
+    }
+
+    let property_count = reader.reader.u16()?;
+    let mut properties = vec![];
+    for _ in 0..property_count {
+        let name = reader.string()?;
+        let type_name = reader.string()?;
+        let docstring = reader.string()?;
+        let _user_flags = reader.reader.u32()?;
+        let flags = reader.reader.u8()?;
+
+        if flags & PROPERTY_AUTO != 0 {
+            let _variable = reader.string()?;
+        } else {
+            if flags & PROPERTY_READ != 0 {
+                read_function(reader, String::new())?;
+            }
+            if flags & PROPERTY_WRITE != 0 {
+                read_function(reader, String::new())?;
+            }
+        }
+
+        properties.push(Property {
+            name,
+            type_name,
+            docstring,
+            flags,
+        });
+    }
+
+    let state_count = reader.reader.u16()?;
+    let mut states = vec![];
+    for _ in 0..state_count {
+        let name = reader.string()?;
+        let function_count = reader.reader.u16()?;
+        let mut functions = vec![];
+
+        for _ in 0..function_count {
+            let name = reader.string()?;
+            functions.push(read_function(reader, name)?);
+        }
+
+        states.push(State { name, functions });
+    }
+
+    Ok(Script {
+        name,
+        parent,
+        docstring,
+        auto_state,
+        variables,
+        properties,
+        states,
+    })
+}
+
+fn read_function(reader: &mut Strings, name: String) -> io::Result<Function> {
+    let return_type = reader.string()?;
+    let docstring = reader.string()?;
+    let _user_flags = reader.reader.u32()?;
+    let flags = reader.reader.u8()?;
+
+    let param_count = reader.reader.u16()?;
+    let mut params = vec![];
+    for _ in 0..param_count {
+        params.push((reader.string()?, reader.string()?));
+    }
+
+    let local_count = reader.reader.u16()? as usize;
+    reader.reader.bytes(local_count * 4)?;
+
+    let instruction_count = reader.reader.u16()?;
+    for _ in 0..instruction_count {
+        let opcode = reader.reader.u8()?;
+
+        for _ in 0..argument_count(opcode)? {
+            reader.skip_value()?;
+        }
+
+        if let CALL_METHOD | CALL_PARENT | CALL_STATIC = opcode {
+            let extra = reader.skip_value()?;
+            for _ in 0..extra {
+                reader.skip_value()?;
+            }
+        }
+    }
+
+    Ok(Function {
+        name,
+        return_type,
+        docstring,
+        flags,
+        params,
+    })
+}
+
+/// The number of fixed arguments an instruction takes.
+fn argument_count(opcode: u8) -> io::Result<usize> {
+    Ok(match opcode {
+        0x00 => 0,
+        0x14 | 0x1a => 1,
+        0x0a..=0x0e | 0x15 | 0x16 | 0x18 | 0x1e | 0x1f => 2,
+        0x01..=0x09 | 0x0f..=0x13 | 0x17 | 0x19 | 0x1b..=0x1d | 0x20 | 0x21 => 3,
+        0x22 | 0x23 => 4,
+        _ => return Err(invalid("compiled script has an unknown instruction")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a script file by hand: `TestScript extends Quest` with an auto property, a full property with a
+    /// read function, and an event calling a method.
+    fn test_script() -> Vec<u8> {
+        let strings = [
+            "TestScript",   // 0
+            "Quest",        // 1
+            "",             // 2
+            "Target",       // 3
+            "Actor",        // 4
+            "::Target_var", // 5
+            "Count",        // 6
+            "Int",          // 7
+            "OnInit",       // 8
+            "None",         // 9
+            "akTarget",     // 10
+            "::temp0",      // 11
+            "Enable",       // 12
+            "::Count_var",  // 13
+        ];
+
+        let mut data = MAGIC.to_be_bytes().to_vec();
+        data.extend_from_slice(&[3, 2, 0, 1]);
+        data.extend_from_slice(&[0; 8]);
+        for header in &["TestScript.psc", "user", "machine"] {
+            data.extend_from_slice(&(header.len() as u16).to_be_bytes());
+            data.extend_from_slice(header.as_bytes());
+        }
+
+        data.extend_from_slice(&(strings.len() as u16).to_be_bytes());
+        for string in &strings {
+            data.extend_from_slice(&(string.len() as u16).to_be_bytes());
+            data.extend_from_slice(string.as_bytes());
+        }
+
+        // No debug info or user flags, and one script.
+        data.extend_from_slice(&[0, 0, 0, 0, 1]);
+        let index = |data: &mut Vec<u8>, index: u16| data.extend_from_slice(&index.to_be_bytes());
+
+        index(&mut data, 0);
+        data.extend_from_slice(&0u32.to_be_bytes());
+        index(&mut data, 1);
+        index(&mut data, 2);
+        data.extend_from_slice(&0u32.to_be_bytes());
+        index(&mut data, 2);
+
+        // One variable, an Int initialised to 5.
+        index(&mut data, 1);
+        index(&mut data, 13);
+        index(&mut data, 7);
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.push(3);
+        data.extend_from_slice(&5i32.to_be_bytes());
+
+        // Two properties: `Actor Property Target Auto`, and `Int Property Count` with a read function.
+        index(&mut data, 2);
+        index(&mut data, 3);
+        index(&mut data, 4);
+        index(&mut data, 2);
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.push(PROPERTY_AUTO | PROPERTY_READ | PROPERTY_WRITE);
+        index(&mut data, 5);
+
+        index(&mut data, 6);
+        index(&mut data, 7);
+        index(&mut data, 2);
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.push(PROPERTY_READ);
+        index(&mut data, 7);
+        index(&mut data, 2);
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.push(0);
+        // No parameters or locals, then `return ::Count_var`.
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 1, 0x1a, 1]);
+        index(&mut data, 13);
+
+        // The default state, with `Event OnInit(Actor akTarget)` calling `akTarget.Enable()`.
+        index(&mut data, 1);
+        index(&mut data, 2);
+        index(&mut data, 1);
+        index(&mut data, 8);
+        index(&mut data, 9);
+        index(&mut data, 2);
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.push(0);
+        index(&mut data, 1);
+        index(&mut data, 10);
+        index(&mut data, 4);
+        index(&mut data, 1);
+        index(&mut data, 11);
+        index(&mut data, 9);
+        index(&mut data, 1);
+        data.push(CALL_METHOD);
+        data.push(1);
+        index(&mut data, 12);
+        data.push(1);
+        index(&mut data, 10);
+        data.push(1);
+        index(&mut data, 11);
+        // One more argument, the bool `false`.
+        data.extend_from_slice(&[3, 0, 0, 0, 1, 5, 0]);
+
+        data
+    }
+
+    #[test]
+    fn reads_declarations() {
+        let pex = Pex::parse(&test_script()).unwrap();
+        assert_eq!(pex.source, "TestScript.psc");
+
+        let script = &pex.scripts[0];
+        assert_eq!(script.name, "TestScript");
+        assert_eq!(script.parent, "Quest");
+        assert_eq!(
+            script.variables,
+            vec![Variable {
+                name: "::Count_var".to_string(),
+                type_name: "Int".to_string()
+            }]
+        );
+
+        let properties: Vec<(&str, &str, bool)> = script
+            .properties
+            .iter()
+            .map(|property| (property.name.as_str(), property.type_name.as_str(), property.is_auto()))
+            .collect();
+        assert_eq!(properties, vec![("Target", "Actor", true), ("Count", "Int", false)]);
+
+        assert_eq!(script.states.len(), 1);
+        let function = &script.states[0].functions[0];
+        assert_eq!(function.signature(), "Function OnInit(Actor akTarget)");
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(Pex::parse(b"BSA\0").is_err());
+        assert!(Pex::parse(&test_script()[..40]).is_err());
+    }
+}
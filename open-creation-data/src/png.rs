@@ -0,0 +1,91 @@
+//! Writing RGBA8 images as PNG files, for exports that run without a GPU.
+//!
+//! Only what the editor needs is supported: 8-bit RGBA, no interlacing, and no filtering before compression.
+
+use std::io::{self, Write};
+
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+const BIT_DEPTH: u8 = 8;
+const COLOR_TYPE_RGBA: u8 = 6;
+const FILTER_NONE: u8 = 0;
+
+fn write_chunk<W: Write>(writer: &mut W, code: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let mut crc = flate2::Crc::new();
+    crc.update(code);
+    crc.update(data);
+
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(code)?;
+    writer.write_all(data)?;
+    writer.write_all(&crc.sum().to_be_bytes())
+}
+
+/// Writes an image given as rows of RGBA8 pixels from the top.
+pub fn write_rgba<W: Write>(writer: &mut W, width: u32, height: u32, pixels: &[u8]) -> io::Result<()> {
+    let row_size = width as usize * 4;
+
+    if pixels.len() != row_size * height as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "pixel data does not match the image size",
+        ));
+    }
+
+    let mut header = vec![];
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Compression, filter and interlace methods are all the defaults.
+    header.extend_from_slice(&[BIT_DEPTH, COLOR_TYPE_RGBA, 0, 0, 0]);
+
+    let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+
+    for row in pixels.chunks_exact(row_size.max(1)).take(height as usize) {
+        encoder.write_all(&[FILTER_NONE])?;
+        encoder.write_all(row)?;
+    }
+
+    let compressed = encoder.finish()?;
+
+    writer.write_all(SIGNATURE)?;
+    write_chunk(writer, b"IHDR", &header)?;
+    write_chunk(writer, b"IDAT", &compressed)?;
+    write_chunk(writer, b"IEND", &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn write_rgba() {
+        let pixels: Vec<u8> = (0..2 * 3 * 4).map(|value| value as u8).collect();
+        let mut bytes = vec![];
+        super::write_rgba(&mut bytes, 2, 3, &pixels).unwrap();
+
+        assert_eq!(&bytes[..8], SIGNATURE);
+        assert_eq!(&bytes[12..16], b"IHDR");
+        assert_eq!(&bytes[16..20], &2u32.to_be_bytes());
+        assert_eq!(&bytes[20..24], &3u32.to_be_bytes());
+
+        // The IHDR chunk's CRC covers its code and data.
+        let mut crc = flate2::Crc::new();
+        crc.update(&bytes[12..29]);
+        assert_eq!(&bytes[29..33], &crc.sum().to_be_bytes());
+
+        let length = u32::from_be_bytes([bytes[33], bytes[34], bytes[35], bytes[36]]) as usize;
+        assert_eq!(&bytes[37..41], b"IDAT");
+
+        let mut data = vec![];
+        flate2::read::ZlibDecoder::new(&bytes[41..41 + length])
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data.len(), 3 * (1 + 2 * 4));
+        assert_eq!(data[0], FILTER_NONE);
+        assert_eq!(&data[1..9], &pixels[..8]);
+        assert_eq!(&data[10..18], &pixels[8..16]);
+
+        assert_eq!(&bytes[bytes.len() - 8..bytes.len() - 4], b"IEND");
+        assert!(super::write_rgba(&mut vec![], 2, 2, &pixels).is_err());
+    }
+}
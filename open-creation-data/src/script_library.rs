@@ -0,0 +1,531 @@
+//! The compiled scripts in the data files, read as they are needed, for checking the properties set on attached
+//! scripts against what the scripts declare and for filling them in.
+//!
+//! Properties of object types are matched to record types through the native script the type extends, such as
+//! `Actor` for placed actors. Properties of type `Form`, or of types whose scripts are missing, may refer to any
+//! record.
+
+use crate::{
+    esp::{Code, ObjectValue, Property, PropertyValue, Script},
+    path,
+    pex::{self, Pex},
+    vfs::VirtualFileSystem,
+};
+
+use std::collections::HashMap;
+
+/// The record types each native script is attached to, for the script types that have them.
+const NATIVE_TYPES: &[(&str, &[Code])] = &[
+    ("Action", &[*b"AACT"]),
+    ("Activator", &[*b"ACTI"]),
+    ("ActiveMagicEffect", &[]),
+    ("Actor", &[*b"ACHR"]),
+    ("ActorBase", &[*b"NPC_"]),
+    ("ActorValueInfo", &[*b"AVIF"]),
+    ("Ammo", &[*b"AMMO"]),
+    ("Apparatus", &[*b"APPA"]),
+    ("Armor", &[*b"ARMO"]),
+    ("ArmorAddon", &[*b"ARMA"]),
+    ("AssociationType", &[*b"ASTP"]),
+    ("Book", &[*b"BOOK"]),
+    ("Cell", &[*b"CELL"]),
+    ("Class", &[*b"CLAS"]),
+    ("ColorForm", &[*b"CLFM"]),
+    ("CombatStyle", &[*b"CSTY"]),
+    ("ConstructibleObject", &[*b"COBJ"]),
+    ("Container", &[*b"CONT"]),
+    ("Door", &[*b"DOOR"]),
+    ("EffectShader", &[*b"EFSH"]),
+    ("Enchantment", &[*b"ENCH"]),
+    ("EncounterZone", &[*b"ECZN"]),
+    ("EquipSlot", &[*b"EQUP"]),
+    ("Explosion", &[*b"EXPL"]),
+    ("Faction", &[*b"FACT"]),
+    ("Flora", &[*b"FLOR"]),
+    ("FormList", &[*b"FLST"]),
+    ("Furniture", &[*b"FURN"]),
+    ("GlobalVariable", &[*b"GLOB"]),
+    ("Hazard", &[*b"HAZD"]),
+    ("HeadPart", &[*b"HDPT"]),
+    ("Idle", &[*b"IDLE"]),
+    ("IdleMarker", &[*b"IDLM"]),
+    ("ImageSpaceModifier", &[*b"IMAD"]),
+    ("ImpactDataSet", &[*b"IPDS"]),
+    ("Ingredient", &[*b"INGR"]),
+    ("Key", &[*b"KEYM"]),
+    ("Keyword", &[*b"KYWD"]),
+    ("LeveledActor", &[*b"LVLN"]),
+    ("LeveledItem", &[*b"LVLI"]),
+    ("LeveledSpell", &[*b"LVSP"]),
+    ("Light", &[*b"LIGH"]),
+    ("Location", &[*b"LCTN"]),
+    ("LocationAlias", &[]),
+    ("LocationRefType", &[*b"LCRT"]),
+    ("MagicEffect", &[*b"MGEF"]),
+    ("Message", &[*b"MESG"]),
+    ("MiscObject", &[*b"MISC"]),
+    ("MusicType", &[*b"MUSC"]),
+    ("ObjectReference", &[*b"REFR", *b"ACHR", *b"PGRE"]),
+    ("Outfit", &[*b"OTFT"]),
+    ("Package", &[*b"PACK"]),
+    ("Perk", &[*b"PERK"]),
+    ("Potion", &[*b"ALCH"]),
+    ("Projectile", &[*b"PROJ"]),
+    ("Quest", &[*b"QUST"]),
+    ("Race", &[*b"RACE"]),
+    ("ReferenceAlias", &[]),
+    ("Scene", &[*b"SCEN"]),
+    ("Scroll", &[*b"SCRL"]),
+    ("ShaderParticleGeometry", &[*b"SPGD"]),
+    ("Shout", &[*b"SHOU"]),
+    ("SoulGem", &[*b"SLGM"]),
+    ("Sound", &[*b"SNDR"]),
+    ("SoundCategory", &[*b"SNCT"]),
+    ("Spell", &[*b"SPEL"]),
+    ("Static", &[*b"STAT"]),
+    ("TalkingActivator", &[*b"TACT"]),
+    ("TextureSet", &[*b"TXST"]),
+    ("Topic", &[*b"DIAL"]),
+    ("TopicInfo", &[*b"INFO"]),
+    ("VisualEffect", &[*b"RFCT"]),
+    ("VoiceType", &[*b"VTYP"]),
+    ("Weapon", &[*b"WEAP"]),
+    ("Weather", &[*b"WTHR"]),
+    ("WordOfPower", &[*b"WOOP"]),
+    ("WorldSpace", &[*b"WRLD"]),
+];
+const PRIMITIVE_TYPES: [&str; 4] = ["bool", "float", "int", "string"];
+/// Scripts are not followed further up than this, in case of a loop.
+const MAX_DEPTH: usize = 32;
+/// The status of a property whose value has been set on the record.
+const PROPERTY_EDITED: u8 = 1;
+
+/// A property set on an attached script that does not match the script's declarations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyIssue {
+    pub property: String,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct ScriptLibrary {
+    /// The names of the compiled scripts in the data files, sorted.
+    names: Vec<String>,
+    /// The scripts read so far by lowercase name, or `None` for those that are missing or could not be read.
+    scripts: HashMap<String, Option<pex::Script>>,
+}
+
+impl ScriptLibrary {
+    /// Lists the compiled scripts in `scripts\`, without reading them yet.
+    pub fn new(vfs: &VirtualFileSystem) -> Self {
+        let mut names: Vec<String> = vfs
+            .paths()
+            .filter_map(|path| path.strip_prefix("scripts\\")?.strip_suffix(".pex"))
+            .filter(|name| !name.contains('\\'))
+            .map(String::from)
+            .collect();
+        names.sort();
+
+        Self {
+            names,
+            scripts: HashMap::new(),
+        }
+    }
+
+    /// The names of the compiled scripts, in lowercase as stored in the archives.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Reads a script, the scripts it extends and the types of its properties, unless they have been read
+    /// already. Errors are logged once, and the scripts treated as missing.
+    pub fn load(&mut self, vfs: &VirtualFileSystem, name: &str) {
+        self.load_ancestry(vfs, name);
+
+        let types: Vec<String> = self
+            .ancestry(name)
+            .iter()
+            .flat_map(|script| &script.properties)
+            .map(|property| property.type_name.trim_end_matches("[]").to_string())
+            .filter(|type_name| !PRIMITIVE_TYPES.contains(&type_name.to_lowercase().as_str()))
+            .collect();
+
+        for type_name in types {
+            self.load_ancestry(vfs, &type_name);
+        }
+    }
+
+    fn load_ancestry(&mut self, vfs: &VirtualFileSystem, name: &str) {
+        let mut name = name.to_lowercase();
+
+        for _ in 0..MAX_DEPTH {
+            if name.is_empty() || self.scripts.contains_key(&name) {
+                return;
+            }
+
+            let script = read_script(vfs, &name);
+            let parent = script.as_ref().map(|script| script.parent.to_lowercase());
+            self.scripts.insert(name, script);
+
+            name = match parent {
+                Some(parent) => parent,
+                None => return,
+            };
+        }
+    }
+
+    /// A script that has been read, by name in any case.
+    pub fn script(&self, name: &str) -> Option<&pex::Script> {
+        self.scripts.get(&name.to_lowercase())?.as_ref()
+    }
+
+    /// A script followed by the scripts it extends that have been read, nearest first.
+    pub fn ancestry(&self, name: &str) -> Vec<&pex::Script> {
+        let mut ancestry = vec![];
+        let mut next = self.script(name);
+
+        while let Some(script) = next {
+            if ancestry.len() == MAX_DEPTH {
+                break;
+            }
+
+            ancestry.push(script);
+            next = self.script(&script.parent);
+        }
+
+        ancestry
+    }
+
+    /// The properties a script declares, including those of the scripts it extends, or `None` if the script
+    /// has not been read or is missing.
+    pub fn properties(&self, name: &str) -> Option<Vec<&pex::Property>> {
+        self.script(name)?;
+        Some(
+            self.ancestry(name)
+                .into_iter()
+                .flat_map(|script| &script.properties)
+                .collect(),
+        )
+    }
+
+    /// The record types a property of an object type may refer to, or `None` for any record.
+    pub fn record_codes(&self, type_name: &str) -> Option<&'static [Code]> {
+        let type_name = type_name.trim_end_matches("[]");
+        let native = |name: &str| {
+            NATIVE_TYPES
+                .iter()
+                .find(|(native, _)| native.eq_ignore_ascii_case(name))
+                .map(|(_, codes)| *codes)
+        };
+
+        native(type_name).or_else(|| {
+            self.ancestry(type_name)
+                .iter()
+                .find_map(|script| native(&script.parent))
+        })
+    }
+
+    /// Checks the properties set on an attached script against those the script declares. Returns `None` if the
+    /// script has not been read or is missing. `record_code` looks up the type of the record a form ID refers to.
+    pub fn check(&self, script: &Script, record_code: impl Fn(u32) -> Option<Code>) -> Option<Vec<PropertyIssue>> {
+        let declared = self.properties(&script.name)?;
+        let mut issues = vec![];
+
+        for property in &script.properties {
+            let issue = |message: String| PropertyIssue {
+                property: property.name.clone(),
+                message,
+            };
+
+            let declaration = match declared
+                .iter()
+                .find(|declaration| declaration.name.eq_ignore_ascii_case(&property.name))
+            {
+                Some(declaration) => declaration,
+                None => {
+                    issues.push(issue(format!("{} does not declare {}", script.name, property.name)));
+                    continue;
+                }
+            };
+
+            if !value_fits(&property.value, &declaration.type_name) {
+                issues.push(issue(format!(
+                    "{} is declared as {}, not {}",
+                    property.name,
+                    declaration.type_name,
+                    property.value.type_name()
+                )));
+                continue;
+            }
+
+            let codes = match self.record_codes(&declaration.type_name) {
+                Some(codes) if !codes.is_empty() => codes,
+                _ => continue,
+            };
+
+            let objects = match &property.value {
+                PropertyValue::Object(object) => std::slice::from_ref(object),
+                PropertyValue::ObjectArray(objects) => objects.as_slice(),
+                _ => continue,
+            };
+
+            for object in objects.iter().filter(|object| object.alias < 0 && object.form_id != 0) {
+                match record_code(object.form_id) {
+                    Some(code) if codes.contains(&code) => {}
+                    Some(code) => issues.push(issue(format!(
+                        "{} refers to {:08X}, a {} record rather than a {}",
+                        property.name,
+                        object.form_id,
+                        String::from_utf8_lossy(&code),
+                        declaration.type_name
+                    ))),
+                    None => issues.push(issue(format!(
+                        "{} refers to {:08X}, which does not exist",
+                        property.name, object.form_id
+                    ))),
+                }
+            }
+        }
+
+        Some(issues)
+    }
+
+    /// Sets the object properties an attached script declares but leaves unset to the records whose editor IDs
+    /// are the properties' names, as the Creation Kit's auto-fill does. Returns the names of the properties
+    /// filled in. `find_record` looks up the form ID of a record by type and editor ID.
+    pub fn auto_fill(&self, script: &mut Script, find_record: impl Fn(Code, &str) -> Option<u32>) -> Vec<String> {
+        let declared = match self.properties(&script.name) {
+            Some(declared) => declared,
+            None => return vec![],
+        };
+
+        let mut filled = vec![];
+
+        for declaration in declared {
+            let value = script
+                .properties
+                .iter()
+                .find(|property| property.name.eq_ignore_ascii_case(&declaration.name))
+                .map(|property| &property.value);
+            let unset = match value {
+                Some(PropertyValue::Object(object)) => object.form_id == 0 && object.alias < 0,
+                Some(PropertyValue::None) | None => true,
+                Some(_) => false,
+            };
+
+            if !unset || declaration.type_name.ends_with("[]") {
+                continue;
+            }
+
+            let codes = match self.record_codes(&declaration.type_name) {
+                Some(codes) => codes,
+                None => continue,
+            };
+
+            let form_id = codes.iter().find_map(|&code| find_record(code, &declaration.name));
+
+            if let Some(form_id) = form_id {
+                let value = PropertyValue::Object(ObjectValue { form_id, alias: -1 });
+
+                match script
+                    .properties
+                    .iter_mut()
+                    .find(|property| property.name.eq_ignore_ascii_case(&declaration.name))
+                {
+                    Some(property) => {
+                        property.value = value;
+                        property.status = PROPERTY_EDITED;
+                    }
+                    None => script.properties.push(Property {
+                        name: declaration.name.clone(),
+                        status: PROPERTY_EDITED,
+                        value,
+                    }),
+                }
+
+                filled.push(declaration.name.clone());
+            }
+        }
+
+        filled
+    }
+}
+
+fn read_script(vfs: &VirtualFileSystem, name: &str) -> Option<pex::Script> {
+    let file = path::normalize(&format!("scripts\\{}.pex", name));
+    if !vfs.exists(&file) {
+        return None;
+    }
+
+    let pex = match vfs.read(&file).and_then(|bytes| Pex::parse(&bytes)) {
+        Ok(pex) => pex,
+        Err(e) => {
+            log::error!("Error reading script {}: {}", file, e);
+            return None;
+        }
+    };
+
+    pex.scripts
+        .into_iter()
+        .find(|script| script.name.eq_ignore_ascii_case(name))
+}
+
+/// Whether a property value is of a declared Papyrus type. Any object fits an object type here; the type of
+/// record it refers to is checked separately.
+fn value_fits(value: &PropertyValue, type_name: &str) -> bool {
+    let type_name = type_name.to_lowercase();
+    let is_object = |name: &str| !PRIMITIVE_TYPES.contains(&name);
+
+    match value {
+        PropertyValue::None => true,
+        PropertyValue::Object(_) => is_object(&type_name) && !type_name.ends_with("[]"),
+        PropertyValue::String(_) => type_name == "string",
+        PropertyValue::Int(_) => type_name == "int",
+        PropertyValue::Float(_) => type_name == "float",
+        PropertyValue::Bool(_) => type_name == "bool",
+        PropertyValue::ObjectArray(_) => matches!(type_name.strip_suffix("[]"), Some(element) if is_object(element)),
+        PropertyValue::StringArray(_) => type_name == "string[]",
+        PropertyValue::IntArray(_) => type_name == "int[]",
+        PropertyValue::FloatArray(_) => type_name == "float[]",
+        PropertyValue::BoolArray(_) => type_name == "bool[]",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library() -> ScriptLibrary {
+        let script = |name: &str, parent: &str, properties: &[(&str, &str)]| pex::Script {
+            name: name.to_string(),
+            parent: parent.to_string(),
+            docstring: String::new(),
+            auto_state: String::new(),
+            variables: vec![],
+            properties: properties
+                .iter()
+                .map(|(name, type_name)| pex::Property {
+                    name: name.to_string(),
+                    type_name: type_name.to_string(),
+                    docstring: String::new(),
+                    flags: 7,
+                })
+                .collect(),
+            states: vec![],
+        };
+
+        let mut library = ScriptLibrary::default();
+        let scripts = vec![
+            script("BaseQuestScript", "Quest", &[("Count", "Int")]),
+            script(
+                "TestQuestScript",
+                "BaseQuestScript",
+                &[("Guard", "Actor"), ("Ids", "Int[]")],
+            ),
+            script("GuardScript", "Actor", &[]),
+            script("Quest", "Form", &[]),
+        ];
+        for script in scripts {
+            library.scripts.insert(script.name.to_lowercase(), Some(script));
+        }
+
+        library
+    }
+
+    #[test]
+    fn resolves_inherited_properties_and_types() {
+        let library = library();
+
+        let names: Vec<&str> = library
+            .properties("testquestscript")
+            .unwrap()
+            .iter()
+            .map(|property| property.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Guard", "Ids", "Count"]);
+
+        assert_eq!(library.record_codes("Actor"), Some(&[*b"ACHR"][..]));
+        assert_eq!(library.record_codes("GuardScript[]"), Some(&[*b"ACHR"][..]));
+        assert_eq!(library.record_codes("Form"), None);
+    }
+
+    #[test]
+    fn checks_properties() {
+        let library = library();
+        let property = |name: &str, value: PropertyValue| Property {
+            name: name.to_string(),
+            status: 1,
+            value,
+        };
+
+        let script = Script {
+            name: "TestQuestScript".to_string(),
+            status: 0,
+            properties: vec![
+                property("Count", PropertyValue::Int(3)),
+                property("Ids", PropertyValue::IntArray(vec![1])),
+                property(
+                    "Guard",
+                    PropertyValue::Object(ObjectValue {
+                        form_id: 0x14,
+                        alias: -1,
+                    }),
+                ),
+                property("Missing", PropertyValue::Bool(true)),
+                property("count", PropertyValue::Float(1.0)),
+            ],
+        };
+
+        let issues: Vec<String> = library
+            .check(&script, |_| Some(*b"NPC_"))
+            .unwrap()
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                "Guard refers to 00000014, a NPC_ record rather than a Actor",
+                "TestQuestScript does not declare Missing",
+                "count is declared as Int, not Float",
+            ]
+        );
+
+        assert!(library
+            .check(
+                &Script {
+                    name: "Unknown".to_string(),
+                    ..script
+                },
+                |_| None
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn fills_properties_by_editor_id() {
+        let library = library();
+        let mut script = Script {
+            name: "TestQuestScript".to_string(),
+            status: 0,
+            properties: vec![Property {
+                name: "Count".to_string(),
+                status: 1,
+                value: PropertyValue::Int(2),
+            }],
+        };
+
+        let filled = library.auto_fill(&mut script, |code, editor_id| match (&code, editor_id) {
+            (b"ACHR", "Guard") => Some(0x0100_0800),
+            _ => None,
+        });
+        assert_eq!(filled, vec!["Guard"]);
+        assert_eq!(
+            script.properties[1].value,
+            PropertyValue::Object(ObjectValue {
+                form_id: 0x0100_0800,
+                alias: -1
+            })
+        );
+    }
+}
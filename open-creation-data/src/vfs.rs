@@ -0,0 +1,256 @@
+//! A virtual view of the game's data folder: loose files layered over the archives loaded for each plugin.
+
+use crate::{bsa::Archive, path};
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Provider {
+    Loose,
+    /// An index into [`VirtualFileSystem::archives`].
+    Archive(usize),
+}
+
+#[derive(Default)]
+pub struct VirtualFileSystem {
+    root: PathBuf,
+    archives: Vec<Archive>,
+    /// Every provider of each path, in ascending priority, so the last provider wins.
+    files: BTreeMap<String, Vec<Provider>>,
+}
+
+impl VirtualFileSystem {
+    /// Loads the archives belonging to each plugin, in load order, followed by any loose files, which always
+    /// take priority over archived ones.
+    pub fn load(root: &Path, plugins: &[String]) -> io::Result<Self> {
+        let mut vfs = Self {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+
+        let mut archive_names: Vec<String> = fs::read_dir(root)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.to_lowercase().ends_with(".bsa"))
+            .collect();
+        archive_names.sort();
+
+        for plugin in plugins {
+            let stem = Path::new(plugin)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+
+            for name in &archive_names {
+                let lower = name.to_lowercase();
+
+                if lower == format!("{}.bsa", stem) || lower.starts_with(&format!("{} - ", stem)) {
+                    match Archive::open(&root.join(name)) {
+                        Ok(archive) => vfs.add_archive(archive),
+                        Err(e) => log::error!("Error reading archive {}: {}", name, e),
+                    }
+                }
+            }
+        }
+
+        vfs.add_loose_files()?;
+        Ok(vfs)
+    }
+
+    fn add_archive(&mut self, archive: Archive) {
+        let index = self.archives.len();
+
+        for entry in archive.entries() {
+            self.files
+                .entry(entry.path.clone())
+                .or_default()
+                .push(Provider::Archive(index));
+        }
+
+        self.archives.push(archive);
+    }
+
+    /// Loose assets live in subfolders; files at the top level are plugins, archives and the like.
+    fn add_loose_files(&mut self) -> io::Result<()> {
+        let mut pending: Vec<PathBuf> = fs::read_dir(&self.root)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+
+        while let Some(directory) = pending.pop() {
+            for entry in fs::read_dir(&directory)? {
+                let file_path = entry?.path();
+
+                if file_path.is_dir() {
+                    pending.push(file_path);
+                } else if let Ok(relative) = file_path.strip_prefix(&self.root) {
+                    self.files
+                        .entry(path::normalize(&relative.to_string_lossy()))
+                        .or_default()
+                        .push(Provider::Loose);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn archives(&self) -> &[Archive] {
+        &self.archives
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &String> {
+        self.files.keys()
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        self.files.contains_key(&path::normalize(path))
+    }
+
+    pub fn providers(&self, path: &str) -> &[Provider] {
+        self.files
+            .get(&path::normalize(path))
+            .map(|p| p.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn winner(&self, path: &str) -> Option<Provider> {
+        self.providers(path).last().copied()
+    }
+
+    pub fn provider_name(&self, provider: Provider) -> String {
+        match provider {
+            Provider::Loose => "Loose file".to_string(),
+            Provider::Archive(index) => self.archives[index]
+                .source()
+                .and_then(|source| source.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| format!("Archive {}", index)),
+        }
+    }
+
+    /// Reads the winning version of a file.
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        match self.winner(path) {
+            Some(provider) => self.read_from(path, provider),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not in the data folder", path),
+            )),
+        }
+    }
+
+    pub fn read_from(&self, path: &str, provider: Provider) -> io::Result<Vec<u8>> {
+        let path = path::normalize(path);
+
+        match provider {
+            Provider::Loose => fs::read(self.root.join(path.replace('\\', "/"))),
+            Provider::Archive(index) => {
+                let archive = &self.archives[index];
+                let entry = archive
+                    .find(&path)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.clone()))?;
+
+                archive.extract(entry)
+            }
+        }
+    }
+
+    /// Writes the winning version of a file beneath `destination`, keeping its data-relative path.
+    pub fn extract(&self, path: &str, destination: &Path) -> io::Result<PathBuf> {
+        let target = destination.join(relative_path(path)?);
+        let data = self.read(path)?;
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&target, data)?;
+        Ok(target)
+    }
+
+    /// Builds a directory tree of every path, for browsing.
+    pub fn tree(&self) -> FileTree {
+        let mut tree = FileTree::default();
+
+        for path in self.files.keys() {
+            let mut directory = &mut tree;
+            let (folder, _) = path::split(path);
+
+            for component in folder.split('\\').filter(|c| !c.is_empty()) {
+                directory = directory.directories.entry(component.to_string()).or_default();
+            }
+
+            directory.files.push(path.clone());
+        }
+
+        tree
+    }
+}
+
+/// A data-relative path as a path beneath the folder files are extracted to. Archives can hold any path, so paths
+/// that would leave the folder, through `..` or by being absolute, are rejected.
+fn relative_path(path: &str) -> io::Result<PathBuf> {
+    let relative = PathBuf::from(path::normalize(path).replace('\\', "/"));
+
+    let escapes = relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+
+    if escapes {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is outside the data folder", path),
+        ));
+    }
+
+    Ok(relative)
+}
+
+#[derive(Debug, Default)]
+pub struct FileTree {
+    pub directories: BTreeMap<String, FileTree>,
+    /// Full normalised paths of the files directly in this directory.
+    pub files: Vec<String>,
+}
+
+impl FileTree {
+    /// Every file path at or below this directory.
+    pub fn all_files(&self) -> Vec<&String> {
+        let mut files: Vec<&String> = self.files.iter().collect();
+
+        for directory in self.directories.values() {
+            files.extend(directory.all_files());
+        }
+
+        files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_paths_outside_the_folder() {
+        assert_eq!(
+            relative_path("Meshes\\Clutter\\Bucket.nif").unwrap(),
+            PathBuf::from("meshes/clutter/bucket.nif")
+        );
+        assert!(relative_path("..\\..\\Windows\\evil.dll").is_err());
+        assert!(relative_path("meshes\\..\\..\\evil.nif").is_err());
+        assert_eq!(
+            relative_path("/textures/sky.dds").unwrap(),
+            PathBuf::from("textures/sky.dds")
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Recording scripts for a quest's dialogue: one row for every response and voice type that speaks it, with the
+//! voice file the game expects for it.
+//!
+//! The voice types of an INFO come from its speaker and from its subject conditions: GetIsID on an actor base,
+//! and GetIsVoiceType on a voice type or a form list of them. INFOs that neither name a speaker nor condition on
+//! one are listed once, without a voice type or path, since any voice type may say them.
+//!
+//! Voice files are named after the quest's editor ID cut to 10 characters, the topic's cut to 15, the INFO's form
+//! ID without its plugin index, and the response number, in a folder for the plugin the INFO comes from and the
+//! voice type. A line counts as recorded if the `.wav` exists, or a `.fuz` or `.xwm` converted from it.
+
+use crate::{
+    esp::{Comparison, Condition, ConditionValue, Dialogue, Info, LoadOrder, Response, Text},
+    vfs::VirtualFileSystem,
+};
+
+use std::io::{self, Write};
+
+const GET_IS_ID: u16 = 72;
+const GET_IS_VOICE_TYPE: u16 = 426;
+const RUN_ON_SUBJECT: u32 = 0;
+const QUEST_NAME_LENGTH: usize = 10;
+const TOPIC_NAME_LENGTH: usize = 15;
+/// Actor bases are followed through this many templates looking for a voice type.
+const MAX_TEMPLATE_DEPTH: usize = 8;
+const RECORDED_EXTENSIONS: [&str; 3] = ["wav", "fuz", "xwm"];
+const HEADER: [&str; 12] = [
+    "Quest",
+    "Topic",
+    "INFO",
+    "Response",
+    "Voice type",
+    "Speaker",
+    "Emotion",
+    "Emotion value",
+    "Text",
+    "Notes",
+    "Voice file",
+    "Recorded",
+];
+
+/// One response to be recorded by one voice type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoiceLine {
+    pub topic: String,
+    pub info: u32,
+    pub response: u8,
+    /// The voice type's editor ID, or empty if the INFO does not limit who says it.
+    pub voice_type: String,
+    pub speaker: String,
+    pub emotion: &'static str,
+    pub emotion_value: u32,
+    pub text: String,
+    pub notes: String,
+    /// The expected voice file, relative to the data folder, or empty without a voice type.
+    pub path: String,
+    /// Whether the voice file or a file converted from it exists.
+    pub recorded: bool,
+}
+
+/// The recording script for a quest.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VoiceScript {
+    pub quest: String,
+    pub lines: Vec<VoiceLine>,
+}
+
+impl VoiceScript {
+    /// Lists the responses of a quest's dialogue in topic and INFO order, checking for their voice files in `vfs`.
+    pub fn build(load_order: &LoadOrder, dialogue: &Dialogue, vfs: &VirtualFileSystem) -> Self {
+        let quest = load_order.editor_id(dialogue.quest).unwrap_or_default();
+        let mut lines = vec![];
+
+        for topic in &dialogue.topics {
+            for info in &topic.infos {
+                let plugin = load_order
+                    .overrides(info.form_id)
+                    .first()
+                    .map(|&(index, _)| load_order.plugins()[index].name.clone())
+                    .unwrap_or_default();
+                let speaker = info
+                    .speaker
+                    .and_then(|speaker| load_order.editor_id(speaker))
+                    .unwrap_or_default();
+
+                let mut voice_types = info_voice_types(load_order, info);
+                if voice_types.is_empty() {
+                    voice_types.push(String::new());
+                }
+
+                for voice_type in &voice_types {
+                    for response in &info.responses {
+                        let path = if voice_type.is_empty() {
+                            String::new()
+                        } else {
+                            let file = voice_file_name(&quest, &topic.editor_id, info.form_id, response.number);
+                            format!("Sound/Voice/{}/{}/{}.wav", plugin, voice_type, file)
+                        };
+
+                        lines.push(VoiceLine {
+                            topic: topic.editor_id.clone(),
+                            info: info.form_id,
+                            response: response.number,
+                            voice_type: voice_type.clone(),
+                            speaker: speaker.clone(),
+                            emotion: response.emotion_name(),
+                            emotion_value: response.emotion_value,
+                            text: response_text(info.form_id, response),
+                            notes: response.notes.clone(),
+                            recorded: !path.is_empty() && is_recorded(vfs, &path),
+                            path,
+                        });
+                    }
+                }
+            }
+        }
+
+        Self { quest, lines }
+    }
+
+    /// The expected voice files that do not exist, in script order.
+    pub fn missing(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter(|line| !line.path.is_empty() && !line.recorded)
+            .map(|line| line.path.as_str())
+            .collect()
+    }
+
+    /// Writes the script as comma-separated values with a header row, for opening in a spreadsheet.
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_row(writer, HEADER.iter().map(|field| field.to_string()))?;
+
+        for line in &self.lines {
+            write_row(
+                writer,
+                vec![
+                    self.quest.clone(),
+                    line.topic.clone(),
+                    format!("{:08X}", line.info),
+                    line.response.to_string(),
+                    line.voice_type.clone(),
+                    line.speaker.clone(),
+                    line.emotion.to_string(),
+                    line.emotion_value.to_string(),
+                    line.text.clone(),
+                    line.notes.clone(),
+                    line.path.clone(),
+                    match (line.path.is_empty(), line.recorded) {
+                        (true, _) => String::new(),
+                        (false, true) => "Yes".to_string(),
+                        (false, false) => "No".to_string(),
+                    },
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A response's text, or nothing if it is a string table ID missing from the tables, which is warned about.
+fn response_text(info: u32, response: &Response) -> String {
+    match &response.text {
+        Some(Text::Inline(text)) => text.clone(),
+        Some(Text::Localized(id)) => {
+            log::warn!(
+                "Response {} of INFO {:08X} has string {:08X}, which is missing from the string tables; its text is \
+                 left empty",
+                response.number,
+                info,
+                id
+            );
+            String::new()
+        }
+        None => String::new(),
+    }
+}
+
+/// The name of a response's voice file, without its extension.
+pub fn voice_file_name(quest: &str, topic: &str, info: u32, response: u8) -> String {
+    let quest: String = quest.chars().take(QUEST_NAME_LENGTH).collect();
+    let topic: String = topic.chars().take(TOPIC_NAME_LENGTH).collect();
+
+    format!("{}_{}_{:08X}_{}", quest, topic, info & 0x00ff_ffff, response)
+}
+
+/// The editor IDs of the voice types an INFO is limited to, without duplicates.
+fn info_voice_types(load_order: &LoadOrder, info: &Info) -> Vec<String> {
+    let mut voice_types = vec![];
+
+    let mut add = |form_id: u32| {
+        if let Some(editor_id) = load_order.editor_id(form_id) {
+            if !voice_types.contains(&editor_id) {
+                voice_types.push(editor_id);
+            }
+        }
+    };
+
+    if let Some(voice_type) = info.speaker.and_then(|speaker| actor_voice_type(load_order, speaker)) {
+        add(voice_type);
+    }
+
+    for condition in info.conditions.iter().filter(|condition| is_true_on_subject(condition)) {
+        match condition.function {
+            GET_IS_ID => {
+                if let Some(voice_type) = actor_voice_type(load_order, condition.params[0]) {
+                    add(voice_type);
+                }
+            }
+            GET_IS_VOICE_TYPE => {
+                let record = match load_order.record(condition.params[0]) {
+                    Some(record) => record,
+                    None => continue,
+                };
+
+                match &record.code {
+                    b"VTYP" => add(record.form_id),
+                    b"FLST" => {
+                        for entry in record.subrecords().unwrap_or_default() {
+                            if let (b"LNAM", Some(form_id)) = (&entry.code, entry.as_u32()) {
+                                add(form_id);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    voice_types
+}
+
+/// Whether a condition requires its function to be true of the subject, as in `GetIsID Foo == 1`.
+fn is_true_on_subject(condition: &Condition) -> bool {
+    let value = match condition.value {
+        ConditionValue::Number(value) => value,
+        ConditionValue::Global(_) => return false,
+    };
+
+    condition.run_on == RUN_ON_SUBJECT
+        && match condition.comparison {
+            Comparison::Equal => value != 0.0,
+            Comparison::NotEqual => value == 0.0,
+            _ => false,
+        }
+}
+
+/// The voice type of an actor base, from its own VTCK or, failing that, its template's.
+fn actor_voice_type(load_order: &LoadOrder, mut form_id: u32) -> Option<u32> {
+    for _ in 0..MAX_TEMPLATE_DEPTH {
+        let record = load_order.record(form_id).filter(|record| &record.code == b"NPC_")?;
+
+        if let Some(voice_type) = record.subrecord(*b"VTCK").and_then(|vtck| vtck.as_u32()) {
+            return Some(voice_type);
+        }
+
+        form_id = record.subrecord(*b"TPLT").and_then(|tplt| tplt.as_u32())?;
+    }
+
+    None
+}
+
+fn is_recorded(vfs: &VirtualFileSystem, path: &str) -> bool {
+    let stem = path.trim_end_matches(".wav");
+    RECORDED_EXTENSIONS
+        .iter()
+        .any(|extension| vfs.exists(&format!("{}.{}", stem, extension)))
+}
+
+fn write_row<W: Write>(writer: &mut W, fields: impl IntoIterator<Item = String>) -> io::Result<()> {
+    let fields: Vec<String> = fields.into_iter().map(|field| csv_field(&field)).collect();
+    writeln!(writer, "{}", fields.join(","))
+}
+
+/// Quotes a field if it holds a separator, quote or line break, doubling any quotes.
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_voice_files() {
+        assert_eq!(
+            voice_file_name("DialogueGeneric", "GenericGreetingsTopic", 0x0200_0d62, 1),
+            "DialogueGe_GenericGreeting_00000D62_1"
+        );
+        assert_eq!(voice_file_name("MQ101", "", 0x0001_2345, 3), "MQ101__00012345_3");
+    }
+
+    #[test]
+    fn leaves_missing_strings_empty() {
+        let mut response = Response {
+            number: 1,
+            text: Some(Text::Inline("Halt!".to_string())),
+            ..Response::default()
+        };
+        assert_eq!(response_text(0x0000_1000, &response), "Halt!");
+
+        response.text = Some(Text::Localized(0x42));
+        assert_eq!(response_text(0x0000_1000, &response), "");
+    }
+
+    #[test]
+    fn quotes_csv_fields() {
+        assert_eq!(csv_field("Hello"), "Hello");
+        assert_eq!(csv_field("Well, \"friend\""), "\"Well, \"\"friend\"\"\"");
+    }
+}
@@ -0,0 +1,222 @@
+use super::{View, Window};
+
+use open_creation_data::{
+    path,
+    vfs::{FileTree, Provider, VirtualFileSystem},
+};
+
+const DEFAULT_WIDTH: f32 = 720.0;
+const DEFAULT_HEIGHT: f32 = 480.0;
+const MAX_FILTER_RESULTS: usize = 1000;
+
+#[derive(Default)]
+pub struct ArchiveBrowserState {
+    pub filter: String,
+    pub selected: Option<String>,
+    pub extract_path: String,
+}
+
+pub enum ArchiveBrowserAction {
+    Extract(Vec<String>),
+    Preview(String),
+}
+
+pub struct ArchiveBrowserWindow<'a> {
+    vfs: &'a VirtualFileSystem,
+    tree: &'a FileTree,
+    state: &'a mut ArchiveBrowserState,
+    actions: Vec<ArchiveBrowserAction>,
+}
+
+impl<'a> ArchiveBrowserWindow<'a> {
+    pub fn new(vfs: &'a VirtualFileSystem, tree: &'a FileTree, state: &'a mut ArchiveBrowserState) -> Self {
+        Self {
+            vfs,
+            tree,
+            state,
+            actions: vec![],
+        }
+    }
+
+    pub fn actions(self) -> Vec<ArchiveBrowserAction> {
+        self.actions
+    }
+}
+
+fn is_previewable(path: &str) -> bool {
+    path.ends_with(".nif") || path.ends_with(".dds")
+}
+
+fn winner_label(vfs: &VirtualFileSystem, path: &str) -> String {
+    let providers = vfs.providers(path);
+
+    match providers.last() {
+        Some(&winner) if providers.len() > 1 => format!(
+            "{}  [{}, overrides {}]",
+            path::split(path).1,
+            vfs.provider_name(winner),
+            providers.len() - 1
+        ),
+        Some(&winner) => format!("{}  [{}]", path::split(path).1, vfs.provider_name(winner)),
+        None => path.to_string(),
+    }
+}
+
+fn file_ui(
+    ui: &mut egui::Ui,
+    vfs: &VirtualFileSystem,
+    path: &str,
+    label: String,
+    state: &mut ArchiveBrowserState,
+    actions: &mut Vec<ArchiveBrowserAction>,
+) {
+    let selected = state.selected.as_deref() == Some(path);
+
+    if ui.selectable_label(selected, label).clicked() && !selected {
+        state.selected = Some(path.to_string());
+
+        if is_previewable(path) && vfs.exists(path) {
+            actions.push(ArchiveBrowserAction::Preview(path.to_string()));
+        }
+    }
+}
+
+fn directory_ui(
+    ui: &mut egui::Ui,
+    vfs: &VirtualFileSystem,
+    name: &str,
+    tree: &FileTree,
+    state: &mut ArchiveBrowserState,
+    actions: &mut Vec<ArchiveBrowserAction>,
+) {
+    for (child_name, child) in &tree.directories {
+        let id = format!("{}\\{}", name, child_name);
+
+        egui::CollapsingHeader::new(child_name)
+            .id_source(&id)
+            .show(ui, |ui| directory_ui(ui, vfs, &id, child, state, actions));
+    }
+
+    for path in &tree.files {
+        file_ui(ui, vfs, path, winner_label(vfs, path), state, actions);
+    }
+}
+
+impl<'a> View for ArchiveBrowserWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let vfs = self.vfs;
+        let tree = self.tree;
+        let state = &mut *self.state;
+        let actions = &mut self.actions;
+
+        ui.columns(2, |columns| {
+            columns[0].vertical_centered_justified(|ui| {
+                ui.label("Data Files");
+                ui.separator();
+                ui.add(egui::TextEdit::singleline(&mut state.filter).hint_text("Filter"));
+                ui.separator();
+            });
+
+            egui::ScrollArea::from_max_height(DEFAULT_HEIGHT)
+                .id_source("archive_browser_tree")
+                .show(&mut columns[0], |ui| {
+                    let filter = path::normalize(&state.filter);
+
+                    if filter.is_empty() {
+                        directory_ui(ui, vfs, "", tree, state, actions);
+                    } else {
+                        let matches: Vec<&String> = vfs
+                            .paths()
+                            .filter(|path| path.contains(&filter))
+                            .take(MAX_FILTER_RESULTS)
+                            .collect();
+
+                        for path in matches {
+                            file_ui(ui, vfs, path, path.to_string(), state, actions);
+                        }
+                    }
+                });
+
+            let ui = &mut columns[1];
+
+            ui.vertical_centered_justified(|ui| {
+                ui.label("Details");
+                ui.separator();
+            });
+
+            let selected = match state.selected.clone() {
+                Some(selected) => selected,
+                None => {
+                    ui.label("Select a file to see where it is loaded from.");
+                    return;
+                }
+            };
+
+            ui.label(&selected);
+            ui.separator();
+            ui.label("Providers, lowest priority first:");
+
+            let providers = vfs.providers(&selected);
+
+            for (index, &provider) in providers.iter().enumerate() {
+                let name = vfs.provider_name(provider);
+
+                if index + 1 == providers.len() {
+                    ui.colored_label(egui::Color32::from_rgb(0, 128, 0), format!("{} (wins)", name));
+                } else {
+                    ui.label(format!("{} (overridden)", name));
+                }
+            }
+
+            if let Some(Provider::Loose) = providers.last() {
+                ui.label("Loose files always win over archives.");
+            }
+
+            ui.separator();
+            ui.label("Extract to");
+            ui.text_edit_singleline(&mut state.extract_path);
+
+            let can_extract = !state.extract_path.trim().is_empty();
+
+            ui.horizontal(|ui| {
+                if ui.add(egui::Button::new("Extract file").enabled(can_extract)).clicked() {
+                    actions.push(ArchiveBrowserAction::Extract(vec![selected.clone()]));
+                }
+
+                if ui
+                    .add(egui::Button::new("Extract folder").enabled(can_extract))
+                    .clicked()
+                {
+                    // A file at the root of the data folder extracts the whole tree.
+                    let (folder, _) = path::split(&selected);
+                    let folder = if folder.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{}\\", folder)
+                    };
+                    let files = vfs.paths().filter(|path| path.starts_with(&folder)).cloned().collect();
+
+                    actions.push(ArchiveBrowserAction::Extract(files));
+                }
+
+                if is_previewable(&selected) && ui.button("Preview").clicked() {
+                    actions.push(ArchiveBrowserAction::Preview(selected.clone()));
+                }
+            });
+        });
+    }
+}
+
+impl<'a> Window for ArchiveBrowserWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Archive Browser"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_size(egui::vec2(DEFAULT_WIDTH, DEFAULT_HEIGHT))
+            .scroll(false)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
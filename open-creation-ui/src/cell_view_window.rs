@@ -0,0 +1,203 @@
+use super::{View, Window};
+
+const DEFAULT_WIDTH: f32 = 720.0;
+const DEFAULT_HEIGHT: f32 = 480.0;
+/// Worldspaces hold thousands of exterior cells, so long lists are cut short until they are filtered.
+const MAX_LISTED_CELLS: usize = 500;
+
+pub struct CellItem {
+    pub form_id: u32,
+    pub editor_id: String,
+    pub grid: Option<(i32, i32)>,
+}
+
+impl CellItem {
+    fn label(&self) -> String {
+        match self.grid {
+            Some((x, y)) if self.editor_id.is_empty() => format!("{}, {}  [{:08X}]", x, y, self.form_id),
+            Some((x, y)) => format!("{}, {}  {}", x, y, self.editor_id),
+            None if self.editor_id.is_empty() => format!("[{:08X}]", self.form_id),
+            None => self.editor_id.clone(),
+        }
+    }
+}
+
+pub struct WorldItem {
+    pub form_id: u32,
+    pub editor_id: String,
+    /// Exterior cells sorted by grid coordinates, with the persistent cell first.
+    pub cells: Vec<CellItem>,
+}
+
+/// Interior cells and worldspaces, built once from the load order.
+#[derive(Default)]
+pub struct CellList {
+    pub interiors: Vec<CellItem>,
+    pub worlds: Vec<WorldItem>,
+}
+
+pub struct ReferenceItem {
+    pub form_id: u32,
+    /// The record type of the reference: REFR, ACHR or PGRE.
+    pub code: String,
+    /// The editor ID of the placed object, or its form ID if it has none.
+    pub base: String,
+}
+
+#[derive(Default)]
+pub struct CellViewState {
+    pub filter: String,
+    pub selected_cell: Option<u32>,
+    pub selected_reference: Option<u32>,
+    /// The editor ID or coordinates of the cell in the viewport, with its references.
+    pub loaded_cell: Option<String>,
+    pub references: Vec<ReferenceItem>,
+}
+
+pub enum CellViewAction {
+    LoadCell(u32),
+    SelectReference(u32),
+}
+
+pub struct CellViewWindow<'a> {
+    cells: &'a CellList,
+    state: &'a mut CellViewState,
+    actions: Vec<CellViewAction>,
+}
+
+impl<'a> CellViewWindow<'a> {
+    pub fn new(cells: &'a CellList, state: &'a mut CellViewState) -> Self {
+        Self {
+            cells,
+            state,
+            actions: vec![],
+        }
+    }
+
+    pub fn actions(self) -> Vec<CellViewAction> {
+        self.actions
+    }
+}
+
+fn cells_ui(
+    ui: &mut egui::Ui,
+    cells: &[CellItem],
+    filter: &str,
+    selected: &mut Option<u32>,
+    actions: &mut Vec<CellViewAction>,
+) {
+    let mut matches = cells
+        .iter()
+        .map(|cell| (cell, cell.label()))
+        .filter(|(_, label)| label.to_lowercase().contains(filter));
+    let mut shown = 0;
+
+    for (cell, label) in matches.by_ref().take(MAX_LISTED_CELLS) {
+        let response = ui.selectable_label(*selected == Some(cell.form_id), label);
+
+        if response.clicked() {
+            *selected = Some(cell.form_id);
+        }
+
+        if response.double_clicked() {
+            actions.push(CellViewAction::LoadCell(cell.form_id));
+        }
+
+        shown += 1;
+    }
+
+    let hidden = matches.count();
+
+    if hidden > 0 {
+        ui.label(format!("{} more, filter to narrow the list", hidden));
+    } else if shown == 0 {
+        ui.label("No cells");
+    }
+}
+
+impl<'a> View for CellViewWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let cells = self.cells;
+        let state = &mut *self.state;
+        let actions = &mut self.actions;
+
+        ui.columns(2, |columns| {
+            columns[0].vertical_centered_justified(|ui| {
+                ui.label("Cells");
+                ui.separator();
+                ui.add(egui::TextEdit::singleline(&mut state.filter).hint_text("Filter by editor ID or x, y"));
+                ui.separator();
+            });
+
+            egui::ScrollArea::from_max_height(DEFAULT_HEIGHT)
+                .id_source("cell_view_cells")
+                .show(&mut columns[0], |ui| {
+                    let filter = state.filter.trim().to_lowercase();
+
+                    egui::CollapsingHeader::new("Interiors")
+                        .id_source("cell_view_interiors")
+                        .show(ui, |ui| {
+                            cells_ui(ui, &cells.interiors, &filter, &mut state.selected_cell, actions)
+                        });
+
+                    for world in &cells.worlds {
+                        egui::CollapsingHeader::new(format!("{}  [{:08X}]", world.editor_id, world.form_id))
+                            .id_source(world.form_id)
+                            .show(ui, |ui| {
+                                cells_ui(ui, &world.cells, &filter, &mut state.selected_cell, actions)
+                            });
+                    }
+                });
+
+            let ui = &mut columns[1];
+
+            ui.vertical_centered_justified(|ui| {
+                ui.label("References");
+                ui.separator();
+            });
+
+            let loaded_cell = match &state.loaded_cell {
+                Some(loaded_cell) => loaded_cell,
+                None => {
+                    ui.label("Double-click a cell to load it.");
+                    return;
+                }
+            };
+
+            ui.label(format!("{}: {} references", loaded_cell, state.references.len()));
+            ui.separator();
+
+            egui::ScrollArea::from_max_height(DEFAULT_HEIGHT)
+                .id_source("cell_view_references")
+                .show(ui, |ui| {
+                    for reference in &state.references {
+                        let label = format!("{:08X}  {}  {}", reference.form_id, reference.code, reference.base);
+                        let selected = state.selected_reference == Some(reference.form_id);
+                        let response = ui.selectable_label(selected, label);
+
+                        if response.clicked() {
+                            state.selected_reference = Some(reference.form_id);
+                        }
+
+                        if response.double_clicked() {
+                            actions.push(CellViewAction::SelectReference(reference.form_id));
+                        }
+                    }
+                });
+        });
+    }
+}
+
+impl<'a> Window for CellViewWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Cell View"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_size(egui::vec2(DEFAULT_WIDTH, DEFAULT_HEIGHT))
+            .scroll(false)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
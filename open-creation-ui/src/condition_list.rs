@@ -0,0 +1,275 @@
+//! An editor for the list of conditions on a record or part of one, decoded with the condition function table.
+
+use super::widgets::{alias_edit, form_id_edit, EditorIds, RecordCodes};
+
+use open_creation_data::esp::{
+    condition::{RUN_ON_NAMES, RUN_ON_REFERENCE},
+    condition_flags, condition_function,
+    condition_functions::CONDITION_FUNCTIONS,
+    Comparison, Condition, ConditionValue, ParamType, REFERENCE_CODES,
+};
+
+use std::hash::Hash;
+
+const RUN_ON_QUEST_ALIAS: u32 = 5;
+const FUNCTION_LIST_HEIGHT: f32 = 240.0;
+const GLOBAL: ParamType = ParamType::Form("Global", &[*b"GLOB"]);
+const REFERENCE: ParamType = ParamType::Form("Object Reference", &REFERENCE_CODES);
+const ERROR_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 80, 80);
+
+/// Lists conditions for editing, with buttons to add, remove and move them. `aliases` are the aliases of the
+/// quest the conditions belong to, by ID, for conditions on aliases. Form ID parameters of the wrong type are
+/// marked, but kept. Returns whether the list was changed.
+pub fn condition_list(
+    ui: &mut egui::Ui,
+    id_source: impl Hash,
+    conditions: &mut Vec<Condition>,
+    aliases: &[(u32, String)],
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+) -> bool {
+    let mut changed = false;
+
+    ui.push_id(id_source, |ui| {
+        let mut removed = None;
+        let mut moved = None;
+
+        for index in 0..conditions.len() {
+            ui.push_id(index, |ui| {
+                ui.group(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}.", index + 1));
+
+                        if ui.add(egui::Button::new("Up").small().enabled(index > 0)).clicked() {
+                            moved = Some((index, index - 1));
+                        }
+                        let down = egui::Button::new("Down").small().enabled(index + 1 < conditions.len());
+                        if ui.add(down).clicked() {
+                            moved = Some((index, index + 1));
+                        }
+                        if ui.small_button("Remove").clicked() {
+                            removed = Some(index);
+                        }
+                    });
+
+                    changed |= condition_ui(ui, &mut conditions[index], aliases, editor_ids, record_codes);
+                });
+            });
+        }
+
+        if let Some((from, to)) = moved {
+            conditions.swap(from, to);
+            changed = true;
+        }
+
+        if let Some(index) = removed {
+            conditions.remove(index);
+            changed = true;
+        }
+
+        if ui.button("Add condition").clicked() {
+            conditions.push(Condition::default());
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+fn condition_ui(
+    ui: &mut egui::Ui,
+    condition: &mut Condition,
+    aliases: &[(u32, String)],
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        let before = condition.run_on;
+        let selected = RUN_ON_NAMES
+            .get(condition.run_on as usize)
+            .map_or_else(|| condition.run_on.to_string(), |name| name.to_string());
+        egui::combo_box_with_label(ui, "Run on", selected, |ui| {
+            for (index, name) in RUN_ON_NAMES.iter().enumerate() {
+                ui.selectable_value(&mut condition.run_on, index as u32, *name);
+            }
+        });
+        changed |= condition.run_on != before;
+
+        match condition.run_on {
+            RUN_ON_REFERENCE => {
+                changed |= form_id_edit(ui, &mut condition.reference, editor_ids);
+            }
+            RUN_ON_QUEST_ALIAS => {
+                let id = ui.make_persistent_id("run_on_alias");
+                changed |= alias_edit(ui, id, &mut condition.reference, aliases);
+            }
+            _ => {}
+        }
+    });
+
+    if condition.run_on == RUN_ON_REFERENCE {
+        check_ui(ui, REFERENCE, condition.reference, record_codes);
+    }
+
+    ui.horizontal(|ui| {
+        let before = condition.function;
+        egui::combo_box_with_label(ui, "Function", condition.function_name(), |ui| {
+            let mut functions: Vec<_> = CONDITION_FUNCTIONS.iter().collect();
+            functions.sort_by_key(|function| function.name);
+
+            egui::ScrollArea::from_max_height(FUNCTION_LIST_HEIGHT).show(ui, |ui| {
+                for function in functions {
+                    ui.selectable_value(&mut condition.function, function.index, function.name);
+                }
+            });
+        });
+
+        ui.add(egui::DragValue::new(&mut condition.function))
+            .on_hover_text("Function index");
+
+        if condition.function != before {
+            condition.params = [0; 2];
+            condition.strings = [None, None];
+            changed = true;
+        }
+    });
+
+    let params = match condition_function(condition.function) {
+        Some(function) => function.params,
+        None => [ParamType::Integer; 2],
+    };
+
+    for (index, &param) in params.iter().enumerate() {
+        if param == ParamType::None {
+            continue;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(param.name());
+            let id = ui.make_persistent_id(("param", index));
+            changed |= param_ui(
+                id,
+                ui,
+                param,
+                &mut condition.params[index],
+                &mut condition.strings[index],
+                aliases,
+                editor_ids,
+            );
+        });
+        check_ui(ui, param, condition.params[index], record_codes);
+    }
+
+    ui.horizontal(|ui| {
+        let before = condition.comparison;
+        let id = ui.make_persistent_id("comparison");
+        egui::combo_box(ui, id, condition.comparison.symbol(), |ui| {
+            for &comparison in &Comparison::ALL {
+                ui.selectable_value(&mut condition.comparison, comparison, comparison.symbol());
+            }
+        });
+        changed |= condition.comparison != before;
+
+        let mut global = matches!(condition.value, ConditionValue::Global(_));
+        if ui.checkbox(&mut global, "Global").changed() {
+            condition.value = if global {
+                ConditionValue::Global(0)
+            } else {
+                ConditionValue::Number(0.0)
+            };
+            changed = true;
+        }
+
+        match &mut condition.value {
+            ConditionValue::Number(value) => {
+                changed |= ui.add(egui::DragValue::new(value).speed(0.1)).changed();
+            }
+            ConditionValue::Global(form_id) => {
+                changed |= form_id_edit(ui, form_id, editor_ids);
+            }
+        }
+    });
+
+    if let ConditionValue::Global(form_id) = condition.value {
+        check_ui(ui, GLOBAL, form_id, record_codes);
+    }
+
+    ui.horizontal(|ui| {
+        let mut or = condition.is_or();
+        egui::combo_box_with_label(ui, "with the next", if or { "OR" } else { "AND" }, |ui| {
+            ui.selectable_value(&mut or, false, "AND");
+            ui.selectable_value(&mut or, true, "OR");
+        });
+        if or != condition.is_or() {
+            condition.flags ^= condition_flags::OR;
+            changed = true;
+        }
+
+        let mut swap = condition.flags & condition_flags::SWAP_SUBJECT_AND_TARGET != 0;
+        if ui.checkbox(&mut swap, "Swap subject and target").changed() {
+            condition.flags ^= condition_flags::SWAP_SUBJECT_AND_TARGET;
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+/// Edits a parameter as its type. Variable names are kept in `string` rather than the parameter itself.
+fn param_ui(
+    id: egui::Id,
+    ui: &mut egui::Ui,
+    param: ParamType,
+    value: &mut u32,
+    string: &mut Option<String>,
+    aliases: &[(u32, String)],
+    editor_ids: EditorIds,
+) -> bool {
+    match param {
+        ParamType::None => false,
+        ParamType::Integer | ParamType::PackageData => {
+            let mut int = *value as i32;
+            let changed = ui.add(egui::DragValue::new(&mut int)).changed();
+            *value = int as u32;
+            changed
+        }
+        ParamType::Float => {
+            let mut float = f32::from_bits(*value);
+            let changed = ui.add(egui::DragValue::new(&mut float).speed(0.1)).changed();
+            *value = float.to_bits();
+            changed
+        }
+        ParamType::Enum(_, names) => {
+            let before = *value;
+            let selected = names
+                .get(*value as usize)
+                .map_or_else(|| value.to_string(), |name| name.to_string());
+            egui::combo_box(ui, id, selected, |ui| {
+                for (index, name) in names.iter().enumerate() {
+                    ui.selectable_value(value, index as u32, *name);
+                }
+            });
+            ui.add(egui::DragValue::new(value));
+            *value != before
+        }
+        ParamType::Form(..) => form_id_edit(ui, value, editor_ids),
+        ParamType::Alias => alias_edit(ui, id, value, aliases),
+        ParamType::VariableName => {
+            let mut name = string.clone().unwrap_or_default();
+            if !ui.text_edit_singleline(&mut name).changed() {
+                return false;
+            }
+            *string = Some(name).filter(|name| !name.is_empty());
+            true
+        }
+    }
+}
+
+/// Marks a parameter of the wrong type.
+fn check_ui(ui: &mut egui::Ui, param: ParamType, value: u32, record_codes: RecordCodes) {
+    if let Err(message) = param.check(value, record_codes(value)) {
+        ui.colored_label(ERROR_COLOR, message);
+    }
+}
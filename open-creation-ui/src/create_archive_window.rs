@@ -0,0 +1,101 @@
+use super::{View, Window};
+
+const DEFAULT_WIDTH: f32 = 420.0;
+const DEFAULT_HEIGHT: f32 = 360.0;
+
+#[derive(Clone, Debug, Default)]
+pub struct CreateArchiveOptions {
+    pub archive_name: String,
+    pub source_path: String,
+    pub special_edition: bool,
+    pub compress: bool,
+    /// Include patterns, one per line.
+    pub include: String,
+    /// Exclude patterns, one per line.
+    pub exclude: String,
+}
+
+impl CreateArchiveOptions {
+    pub fn patterns(text: &str) -> Vec<String> {
+        text.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_string())
+            .collect()
+    }
+}
+
+pub struct CreateArchiveWindow<'a> {
+    options: &'a mut CreateArchiveOptions,
+    create_clicked: bool,
+}
+
+impl<'a> CreateArchiveWindow<'a> {
+    pub fn new(options: &'a mut CreateArchiveOptions) -> Self {
+        Self {
+            options,
+            create_clicked: false,
+        }
+    }
+
+    pub fn create_clicked(&self) -> bool {
+        self.create_clicked
+    }
+}
+
+impl<'a> View for CreateArchiveWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let options = &mut *self.options;
+
+        egui::Grid::new("create_archive_grid").show(ui, |ui| {
+            ui.label("Archive");
+            ui.text_edit_singleline(&mut options.archive_name);
+            ui.end_row();
+
+            ui.label("Source folder");
+            ui.text_edit_singleline(&mut options.source_path);
+            ui.end_row();
+
+            ui.label("Format");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut options.special_edition, false, "Skyrim (104)");
+                ui.radio_value(&mut options.special_edition, true, "Special Edition (105)");
+            });
+            ui.end_row();
+
+            ui.label("");
+            ui.checkbox(&mut options.compress, "Compress archive");
+            ui.end_row();
+        });
+
+        ui.separator();
+        ui.label("Include");
+        ui.add(egui::TextEdit::multiline(&mut options.include).desired_rows(4));
+        ui.label("Exclude");
+        ui.add(egui::TextEdit::multiline(&mut options.exclude).desired_rows(2));
+        ui.separator();
+
+        let can_create = !options.archive_name.trim().is_empty() && !options.source_path.trim().is_empty();
+
+        ui.vertical_centered_justified(|ui| {
+            if ui.add(egui::Button::new("Create").enabled(can_create)).clicked() {
+                self.create_clicked = true;
+            }
+        });
+    }
+}
+
+impl<'a> Window for CreateArchiveWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Create Archive"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_size(egui::vec2(DEFAULT_WIDTH, DEFAULT_HEIGHT))
+            .scroll(false)
+            .collapsible(false)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
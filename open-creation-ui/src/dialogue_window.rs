@@ -0,0 +1,778 @@
+use super::{
+    condition_list::condition_list,
+    script_list::{script_list, ScriptLookup},
+    widgets::{flag_checkbox, form_id_edit, text_edit, EditorIds, RecordCodes},
+    View, Window,
+};
+
+use open_creation_data::esp::{
+    dialogue::{info_flags, response_flags, EMOTION_NAMES, TOPIC_CATEGORY_NAMES},
+    Dialogue, Info, Response, Topic,
+};
+
+use std::collections::HashMap;
+
+const DEFAULT_WIDTH: f32 = 640.0;
+const LIST_HEIGHT: f32 = 140.0;
+const GRAPH_HEIGHT: f32 = 220.0;
+const NODE_WIDTH: f32 = 130.0;
+const NODE_HEIGHT: f32 = 22.0;
+const COLUMN_SPACING: f32 = 170.0;
+const ROW_SPACING: f32 = 34.0;
+const NODE_LABEL_LENGTH: usize = 18;
+const ARROW_SIZE: f32 = 7.0;
+const MISSING_LIST_HEIGHT: f32 = 120.0;
+
+pub struct DialogueState {
+    /// The dialogue of the loaded quest, as it will be written to the active plugin.
+    pub dialogue: Option<Dialogue>,
+    /// The quest whose dialogue the Load button loads.
+    pub quest: u32,
+    pub selected_topic: Option<usize>,
+    pub selected_info: Option<usize>,
+    /// How far the topic graph has been dragged from its starting position.
+    pub graph_offset: egui::Vec2,
+    /// The branch and editor ID of a topic to create.
+    pub new_topic_branch: u32,
+    pub new_topic: String,
+    /// The file the voice script is exported to.
+    pub voice_script_path: String,
+    /// The voice files found missing by the last export of this quest's voice script.
+    pub missing_voice_files: Option<Vec<String>>,
+}
+
+impl Default for DialogueState {
+    fn default() -> Self {
+        Self {
+            dialogue: None,
+            quest: 0,
+            selected_topic: None,
+            selected_info: None,
+            graph_offset: egui::Vec2::default(),
+            new_topic_branch: 0,
+            new_topic: String::new(),
+            voice_script_path: String::new(),
+            missing_voice_files: None,
+        }
+    }
+}
+
+impl DialogueState {
+    /// Shows another quest's dialogue.
+    pub fn open(&mut self, dialogue: Dialogue) {
+        self.quest = dialogue.quest;
+        self.new_topic_branch = dialogue.branches.first().map_or(0, |branch| branch.form_id);
+        self.dialogue = Some(dialogue);
+        self.selected_topic = None;
+        self.selected_info = None;
+        self.graph_offset = egui::Vec2::default();
+        self.missing_voice_files = None;
+    }
+
+    /// Selects a topic of the loaded dialogue by form ID.
+    pub fn select_topic(&mut self, form_id: u32) {
+        if let Some(dialogue) = &self.dialogue {
+            self.selected_topic = dialogue.topics.iter().position(|topic| topic.form_id == form_id);
+            self.selected_info = None;
+        }
+    }
+}
+
+/// Changes made in the Dialogue window, identifying topics and INFOs by their index in the loaded dialogue.
+pub enum DialogueAction {
+    /// Load the dialogue of a quest.
+    Load(u32),
+    TopicEdited(usize),
+    InfoEdited {
+        topic: usize,
+        info: usize,
+    },
+    /// The INFOs of a topic were moved, so their links to the previous INFO need updating.
+    InfosReordered(usize),
+    CreateTopic {
+        branch: u32,
+        editor_id: String,
+    },
+    CreateInfo(usize),
+    /// Export every response of the loaded dialogue as a recording script, to a CSV file at the given path.
+    ExportVoiceScript(String),
+}
+
+pub struct DialogueWindow<'a> {
+    state: &'a mut DialogueState,
+    editor_ids: EditorIds<'a>,
+    record_codes: RecordCodes<'a>,
+    scripts: ScriptLookup<'a>,
+    actions: Vec<DialogueAction>,
+}
+
+impl<'a> DialogueWindow<'a> {
+    pub fn new(
+        state: &'a mut DialogueState,
+        editor_ids: EditorIds<'a>,
+        record_codes: RecordCodes<'a>,
+        scripts: ScriptLookup<'a>,
+    ) -> Self {
+        Self {
+            state,
+            editor_ids,
+            record_codes,
+            scripts,
+            actions: vec![],
+        }
+    }
+
+    pub fn actions(self) -> Vec<DialogueAction> {
+        self.actions
+    }
+}
+
+/// Up and down buttons moving an item of a list. Returns the index it was moved to.
+fn move_buttons<T>(ui: &mut egui::Ui, items: &mut [T], index: usize) -> Option<usize> {
+    let up = ui.add(egui::Button::new("Up").small().enabled(index > 0)).clicked();
+    let down = ui
+        .add(egui::Button::new("Down").small().enabled(index + 1 < items.len()))
+        .clicked();
+
+    let to = match (up, down) {
+        (true, _) => index - 1,
+        (_, true) => index + 1,
+        _ => return None,
+    };
+
+    items.swap(index, to);
+    Some(to)
+}
+
+/// Places topics in columns by how many player choices they are from the start of their branch, or from a topic
+/// nothing links to.
+fn graph_layout(dialogue: &Dialogue, links: &[(u32, u32)]) -> HashMap<u32, egui::Pos2> {
+    let linked: Vec<u32> = links.iter().map(|&(_, to)| to).collect();
+    let mut depths: HashMap<u32, usize> = HashMap::new();
+    let mut queue: Vec<u32> = dialogue
+        .branches
+        .iter()
+        .map(|branch| branch.starting_topic)
+        .chain(
+            dialogue
+                .topics
+                .iter()
+                .map(|topic| topic.form_id)
+                .filter(|id| !linked.contains(id)),
+        )
+        .filter(|&form_id| dialogue.topic(form_id).is_some())
+        .collect();
+
+    for &root in &queue {
+        depths.entry(root).or_insert(0);
+    }
+
+    let mut next = 0;
+    while next < queue.len() {
+        let from = queue[next];
+        let depth = depths[&from];
+        next += 1;
+
+        for &(_, to) in links.iter().filter(|&&(link_from, _)| link_from == from) {
+            if dialogue.topic(to).is_some() && !depths.contains_key(&to) {
+                depths.insert(to, depth + 1);
+                queue.push(to);
+            }
+        }
+    }
+
+    // Topics only reachable through loops.
+    for topic in &dialogue.topics {
+        depths.entry(topic.form_id).or_insert(0);
+    }
+
+    let mut rows: HashMap<usize, usize> = HashMap::new();
+    let mut positions = HashMap::new();
+
+    for topic in &dialogue.topics {
+        let depth = depths[&topic.form_id];
+        let row = rows.entry(depth).or_insert(0);
+        let position = egui::pos2(depth as f32 * COLUMN_SPACING, *row as f32 * ROW_SPACING);
+        positions.insert(topic.form_id, position);
+        *row += 1;
+    }
+
+    positions
+}
+
+/// Draws the topics as nodes with arrows to the topics the player can choose after them, which can be dragged
+/// around and clicked to select a topic.
+fn graph_ui(ui: &mut egui::Ui, dialogue: &Dialogue, offset: &mut egui::Vec2, selected: &mut Option<usize>) {
+    let links = dialogue.links();
+    let layout = graph_layout(dialogue, &links);
+
+    let size = egui::vec2(ui.available_width(), GRAPH_HEIGHT);
+    let (response, painter) = ui.allocate_painter(size, egui::Sense::click_and_drag());
+    let visuals = ui.visuals();
+
+    painter.rect_filled(response.rect, 0.0, visuals.extreme_bg_color);
+
+    if response.dragged() {
+        *offset += response.drag_delta();
+    }
+
+    let origin = response.rect.min + egui::vec2(8.0, 8.0) + *offset;
+    let node_rect = |form_id: u32| {
+        layout.get(&form_id).map(|&position| {
+            egui::Rect::from_min_size(origin + position.to_vec2(), egui::vec2(NODE_WIDTH, NODE_HEIGHT))
+        })
+    };
+
+    let link_stroke = egui::Stroke::new(1.0, visuals.text_color());
+    let mut outside = 0;
+
+    for &(from, to) in &links {
+        let (from, to) = match (node_rect(from), node_rect(to)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => {
+                outside += 1;
+                continue;
+            }
+        };
+
+        let start = from.right_center();
+        let end = to.left_center();
+        painter.line_segment([start, end], link_stroke);
+
+        let direction = (end - start).normalized();
+        let back = end - direction * ARROW_SIZE;
+        let side = direction.rot90() * (ARROW_SIZE / 2.0);
+        painter.line_segment([end, back + side], link_stroke);
+        painter.line_segment([end, back - side], link_stroke);
+    }
+
+    for (index, topic) in dialogue.topics.iter().enumerate() {
+        let rect = match node_rect(topic.form_id) {
+            Some(rect) => rect,
+            None => continue,
+        };
+
+        let fill = if *selected == Some(index) {
+            visuals.selection.bg_fill
+        } else {
+            visuals.widgets.inactive.bg_fill
+        };
+
+        let label: String = topic.editor_id.chars().take(NODE_LABEL_LENGTH).collect();
+        painter.rect_filled(rect, 3.0, fill);
+        painter.rect_stroke(rect, 3.0, visuals.widgets.inactive.bg_stroke);
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            label,
+            egui::TextStyle::Small,
+            visuals.text_color(),
+        );
+    }
+
+    if response.clicked() {
+        if let Some(pointer) = response.interact_pointer_pos() {
+            let clicked = dialogue
+                .topics
+                .iter()
+                .position(|topic| node_rect(topic.form_id).map_or(false, |rect| rect.contains(pointer)));
+
+            if clicked.is_some() {
+                *selected = clicked;
+            }
+        }
+    }
+
+    if outside > 0 {
+        ui.label(format!("{} links lead to topics of other quests.", outside));
+    }
+}
+
+/// Lists the branches and the topics in them, and creates new topics.
+fn topics_ui(ui: &mut egui::Ui, state: &mut DialogueState, actions: &mut Vec<DialogueAction>) {
+    let DialogueState {
+        dialogue,
+        selected_topic,
+        selected_info,
+        new_topic_branch,
+        new_topic,
+        ..
+    } = state;
+
+    let dialogue = match dialogue {
+        Some(dialogue) => dialogue,
+        None => return,
+    };
+
+    egui::ScrollArea::from_max_height(LIST_HEIGHT * 2.0)
+        .id_source("dialogue_topics")
+        .show(ui, |ui| {
+            let groups = dialogue
+                .branches
+                .iter()
+                .map(|branch| (branch.form_id, branch.editor_id.as_str()))
+                .chain(std::iter::once((0, "No branch")));
+
+            for (branch, name) in groups {
+                let topics: Vec<usize> = (0..dialogue.topics.len())
+                    .filter(|&index| {
+                        let topic = &dialogue.topics[index];
+                        topic.branch == branch
+                            || (branch == 0 && !dialogue.branches.iter().any(|b| b.form_id == topic.branch))
+                    })
+                    .collect();
+
+                if branch == 0 && topics.is_empty() {
+                    continue;
+                }
+
+                egui::CollapsingHeader::new(name)
+                    .id_source(("dialogue_branch", branch))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        for index in topics {
+                            let topic = &dialogue.topics[index];
+                            let starting = dialogue
+                                .branches
+                                .iter()
+                                .any(|branch| branch.starting_topic == topic.form_id);
+                            let text = format!("{}{}", topic.editor_id, if starting { "  (start)" } else { "" });
+
+                            if ui.selectable_label(*selected_topic == Some(index), text).clicked() {
+                                *selected_topic = Some(index);
+                                *selected_info = None;
+                            }
+                        }
+                    });
+            }
+        });
+
+    ui.separator();
+    ui.label("New topic");
+
+    let selected_branch = dialogue
+        .branches
+        .iter()
+        .find(|branch| branch.form_id == *new_topic_branch)
+        .map_or("No branch", |branch| branch.editor_id.as_str())
+        .to_string();
+
+    egui::combo_box_with_label(ui, "Branch", selected_branch, |ui| {
+        ui.selectable_value(new_topic_branch, 0, "No branch");
+
+        for branch in &dialogue.branches {
+            ui.selectable_value(new_topic_branch, branch.form_id, &branch.editor_id);
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(new_topic).hint_text("Editor ID"));
+
+        let create = egui::Button::new("Create").enabled(!new_topic.trim().is_empty());
+        if ui.add(create).clicked() {
+            actions.push(DialogueAction::CreateTopic {
+                branch: *new_topic_branch,
+                editor_id: new_topic.trim().to_string(),
+            });
+            new_topic.clear();
+        }
+    });
+}
+
+fn topic_ui(ui: &mut egui::Ui, topic: &mut Topic, editor_ids: EditorIds) -> bool {
+    let mut changed = false;
+
+    egui::Grid::new("dialogue_topic_grid").show(ui, |ui| {
+        ui.label("Editor ID");
+        changed |= ui.text_edit_singleline(&mut topic.editor_id).changed();
+        ui.end_row();
+
+        ui.label("Player text");
+        changed |= text_edit(ui, &mut topic.name, false);
+        ui.end_row();
+
+        ui.label("Priority");
+        changed |= ui.add(egui::DragValue::new(&mut topic.priority).speed(1.0)).changed();
+        ui.end_row();
+
+        ui.label("Category");
+        let before = topic.category;
+        let selected = TOPIC_CATEGORY_NAMES
+            .get(topic.category as usize)
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| topic.category.to_string());
+        egui::combo_box_with_label(ui, "", selected, |ui| {
+            for (index, name) in TOPIC_CATEGORY_NAMES.iter().enumerate() {
+                ui.selectable_value(&mut topic.category, index as u8, *name);
+            }
+        });
+        changed |= topic.category != before;
+        ui.end_row();
+
+        ui.label("Branch");
+        changed |= form_id_edit(ui, &mut topic.branch, editor_ids);
+        ui.end_row();
+    });
+
+    changed
+}
+
+fn response_ui(ui: &mut egui::Ui, response: &mut Response) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        let before = response.emotion;
+        egui::combo_box_with_label(ui, "Emotion", response.emotion_name(), |ui| {
+            for (index, name) in EMOTION_NAMES.iter().enumerate() {
+                ui.selectable_value(&mut response.emotion, index as u32, *name);
+            }
+        });
+        changed |= response.emotion != before;
+
+        changed |= ui
+            .add(egui::Slider::new(&mut response.emotion_value, 0..=100))
+            .changed();
+        changed |= flag_checkbox(
+            ui,
+            &mut response.flags,
+            response_flags::USE_EMOTION_ANIMATION,
+            "Use emotion animation",
+        );
+    });
+
+    changed |= text_edit(ui, &mut response.text, true);
+
+    ui.horizontal(|ui| {
+        ui.label("Notes");
+        changed |= ui.text_edit_singleline(&mut response.notes).changed();
+    });
+
+    changed
+}
+
+fn info_ui(
+    ui: &mut egui::Ui,
+    info: &mut Info,
+    topics: &[(u32, String)],
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+    scripts: ScriptLookup,
+) -> bool {
+    let mut changed = false;
+
+    ui.horizontal_wrapped(|ui| {
+        let flags = &mut info.flags;
+        changed |= flag_checkbox(ui, flags, info_flags::GOODBYE, "Goodbye");
+        changed |= flag_checkbox(ui, flags, info_flags::SAY_ONCE, "Say once");
+        changed |= flag_checkbox(ui, flags, info_flags::RANDOM, "Random");
+        changed |= flag_checkbox(ui, flags, info_flags::RANDOM_END, "Random end");
+        changed |= flag_checkbox(ui, flags, info_flags::INVISIBLE_CONTINUE, "Invisible continue");
+        changed |= flag_checkbox(ui, flags, info_flags::WALK_AWAY, "Walk away");
+        changed |= flag_checkbox(ui, flags, info_flags::FORCE_SUBTITLE, "Force subtitle");
+        changed |= flag_checkbox(ui, flags, info_flags::NO_LIP_FILE, "No LIP file");
+        changed |= flag_checkbox(
+            ui,
+            flags,
+            info_flags::REQUIRES_PLAYER_ACTIVATION,
+            "Requires player activation",
+        );
+        changed |= flag_checkbox(ui, flags, info_flags::SPENDS_FAVOR_POINTS, "Spends favor points");
+    });
+
+    egui::Grid::new("dialogue_info_grid").show(ui, |ui| {
+        ui.label("Prompt");
+        changed |= text_edit(ui, &mut info.prompt, false);
+        ui.end_row();
+
+        ui.label("Speaker");
+        let mut speaker = info.speaker.unwrap_or(0);
+        if form_id_edit(ui, &mut speaker, editor_ids) {
+            info.speaker = Some(speaker).filter(|&speaker| speaker != 0);
+            changed = true;
+        }
+        ui.end_row();
+    });
+
+    ui.separator();
+    ui.label("Responses");
+
+    let mut removed = None;
+    let mut moved = false;
+
+    for index in 0..info.responses.len() {
+        ui.push_id(("dialogue_response", index), |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("Response {}", info.responses[index].number));
+                moved |= move_buttons(ui, &mut info.responses, index).is_some();
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+
+            if let Some(response) = info.responses.get_mut(index) {
+                changed |= response_ui(ui, response);
+            }
+        });
+    }
+
+    if let Some(index) = removed {
+        info.responses.remove(index);
+        moved = true;
+    }
+
+    if ui.button("Add response").clicked() {
+        info.responses.push(Response::default());
+        moved = true;
+    }
+
+    if moved {
+        info.number_responses();
+        changed = true;
+    }
+
+    ui.separator();
+    ui.label("Choices after this response");
+
+    let mut removed = None;
+    for (index, link) in info.links.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            changed |= form_id_edit(ui, link, editor_ids);
+            if ui.small_button("x").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        info.links.remove(index);
+        changed = true;
+    }
+
+    let mut added = 0;
+    egui::combo_box_with_label(ui, "Add choice", "", |ui| {
+        for (form_id, editor_id) in topics {
+            ui.selectable_value(&mut added, *form_id, editor_id);
+        }
+    });
+    if added != 0 {
+        info.links.push(added);
+        changed = true;
+    }
+
+    ui.separator();
+    // The quest's aliases are not loaded with its dialogue, so aliases are picked by ID here.
+    ui.label("Conditions");
+    changed |= condition_list(
+        ui,
+        "info_conditions",
+        &mut info.conditions,
+        &[],
+        editor_ids,
+        record_codes,
+    );
+
+    if let Some(vmad) = &mut info.scripts {
+        ui.separator();
+        ui.label("Scripts");
+        changed |= script_list(ui, "info_scripts", vmad, &[], editor_ids, record_codes, scripts);
+    }
+
+    changed
+}
+
+/// Exports the voice script to a CSV file and lists the voice files the last export found missing.
+fn voice_script_ui(
+    ui: &mut egui::Ui,
+    path: &mut String,
+    missing: &Option<Vec<String>>,
+    actions: &mut Vec<DialogueAction>,
+) {
+    ui.horizontal(|ui| {
+        ui.label("CSV file");
+        ui.text_edit_singleline(path);
+
+        let export = egui::Button::new("Export").enabled(!path.trim().is_empty());
+        if ui.add(export).clicked() {
+            actions.push(DialogueAction::ExportVoiceScript(path.trim().to_string()));
+        }
+    });
+
+    let missing = match missing {
+        Some(missing) => missing,
+        None => return,
+    };
+
+    if missing.is_empty() {
+        ui.label("Every voice file was found.");
+        return;
+    }
+
+    ui.label(format!("{} voice files are missing:", missing.len()));
+    egui::ScrollArea::from_max_height(MISSING_LIST_HEIGHT)
+        .id_source("missing_voice_files")
+        .show(ui, |ui| {
+            for path in missing {
+                ui.label(path);
+            }
+        });
+}
+
+impl<'a> View for DialogueWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let editor_ids = self.editor_ids;
+        let record_codes = self.record_codes;
+        let scripts = self.scripts;
+        let actions = &mut self.actions;
+        let state = &mut *self.state;
+
+        ui.horizontal(|ui| {
+            ui.label("Quest");
+            form_id_edit(ui, &mut state.quest, editor_ids);
+
+            if ui.add(egui::Button::new("Load").enabled(state.quest != 0)).clicked() {
+                actions.push(DialogueAction::Load(state.quest));
+            }
+        });
+
+        {
+            let DialogueState {
+                dialogue,
+                selected_topic,
+                selected_info,
+                graph_offset,
+                voice_script_path,
+                missing_voice_files,
+                ..
+            } = &mut *state;
+
+            let dialogue = match dialogue {
+                Some(dialogue) => dialogue,
+                None => {
+                    ui.label("Select a quest in the tree view, or enter its form ID, to load its dialogue.");
+                    return;
+                }
+            };
+
+            ui.label(format!(
+                "{} branches, {} topics",
+                dialogue.branches.len(),
+                dialogue.topics.len()
+            ));
+
+            egui::CollapsingHeader::new("Voice script").show(ui, |ui| {
+                voice_script_ui(ui, voice_script_path, missing_voice_files, actions);
+            });
+
+            egui::CollapsingHeader::new("Topic graph")
+                .default_open(true)
+                .show(ui, |ui| {
+                    let before = *selected_topic;
+                    graph_ui(ui, dialogue, graph_offset, selected_topic);
+                    if *selected_topic != before {
+                        *selected_info = None;
+                    }
+                });
+        }
+
+        ui.separator();
+
+        ui.columns(2, |columns| {
+            topics_ui(&mut columns[0], state, actions);
+
+            let ui = &mut columns[1];
+            let DialogueState {
+                dialogue,
+                selected_topic,
+                selected_info,
+                ..
+            } = state;
+
+            let dialogue = match dialogue {
+                Some(dialogue) => dialogue,
+                None => return,
+            };
+
+            let topic_choices: Vec<(u32, String)> = dialogue
+                .topics
+                .iter()
+                .map(|topic| (topic.form_id, topic.editor_id.clone()))
+                .collect();
+
+            let topic_index = match *selected_topic {
+                Some(index) if index < dialogue.topics.len() => index,
+                _ => {
+                    ui.label("Select a topic to edit it.");
+                    return;
+                }
+            };
+
+            let topic = &mut dialogue.topics[topic_index];
+
+            egui::ScrollArea::auto_sized().show(ui, |ui| {
+                if topic_ui(ui, topic, editor_ids) {
+                    actions.push(DialogueAction::TopicEdited(topic_index));
+                }
+
+                ui.separator();
+
+                egui::ScrollArea::from_max_height(LIST_HEIGHT)
+                    .id_source("dialogue_infos")
+                    .show(ui, |ui| {
+                        for index in 0..topic.infos.len() {
+                            ui.horizontal(|ui| {
+                                let text = topic.infos[index].summary();
+                                if ui.selectable_label(*selected_info == Some(index), text).clicked() {
+                                    *selected_info = Some(index);
+                                }
+
+                                if let Some(to) = move_buttons(ui, &mut topic.infos, index) {
+                                    if *selected_info == Some(index) {
+                                        *selected_info = Some(to);
+                                    }
+                                    actions.push(DialogueAction::InfosReordered(topic_index));
+                                }
+                            });
+                        }
+                    });
+
+                if ui.button("Add response").clicked() {
+                    actions.push(DialogueAction::CreateInfo(topic_index));
+                }
+
+                let info_index = match *selected_info {
+                    Some(index) if index < topic.infos.len() => index,
+                    _ => return,
+                };
+
+                ui.separator();
+                ui.label(format!("INFO [{:08X}]", topic.infos[info_index].form_id));
+
+                if info_ui(
+                    ui,
+                    &mut topic.infos[info_index],
+                    &topic_choices,
+                    editor_ids,
+                    record_codes,
+                    scripts,
+                ) {
+                    actions.push(DialogueAction::InfoEdited {
+                        topic: topic_index,
+                        info: info_index,
+                    });
+                }
+            });
+        });
+    }
+}
+
+impl<'a> Window for DialogueWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Dialogue"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(DEFAULT_WIDTH)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
@@ -0,0 +1,594 @@
+use super::{
+    widgets::{form_id_edit, EditorIds},
+    View, Window,
+};
+
+use open_creation_data::esp::{FactionGraph, FactionRelation, Reaction};
+
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::TAU,
+};
+
+const DEFAULT_WIDTH: f32 = 640.0;
+const GRAPH_HEIGHT: f32 = 380.0;
+const LIST_HEIGHT: f32 = 140.0;
+const NODE_RADIUS: f32 = 6.0;
+const NODE_LABEL_LENGTH: usize = 20;
+/// Space left around the circle of factions for their labels.
+const GRAPH_MARGIN: f32 = 40.0;
+/// How far edges are drawn to the side, so that two factions' reactions to each other are both seen.
+const EDGE_OFFSET: f32 = 2.5;
+/// How close a click has to be to an edge to select it.
+const EDGE_HIT_DISTANCE: f32 = 4.0;
+const ARROW_SIZE: f32 = 7.0;
+
+pub struct FactionGraphState {
+    /// The factions of the load order, with any edits made to them.
+    pub graph: Option<FactionGraph>,
+    /// Only factions whose editor ID contains this are shown.
+    pub filter: String,
+    /// Which reactions are drawn, by their position in [`Reaction::ALL`].
+    pub shown_reactions: [bool; 4],
+    /// Whether factions the filtered ones react to, or that react to them, are shown too.
+    pub show_neighbours: bool,
+    /// Whether factions without any drawn reactions are hidden.
+    pub hide_unrelated: bool,
+    pub selected_faction: Option<u32>,
+    /// The reaction being edited, as the faction reacting and the faction reacted to.
+    pub selected_relation: Option<(u32, u32)>,
+    /// How far the graph has been dragged from the middle.
+    pub graph_offset: egui::Vec2,
+    /// The faction the selected one is to be given a reaction to.
+    pub new_relation: u32,
+}
+
+impl Default for FactionGraphState {
+    fn default() -> Self {
+        Self {
+            graph: None,
+            filter: String::new(),
+            shown_reactions: [true; 4],
+            show_neighbours: true,
+            hide_unrelated: true,
+            selected_faction: None,
+            selected_relation: None,
+            graph_offset: egui::Vec2::default(),
+            new_relation: 0,
+        }
+    }
+}
+
+impl FactionGraphState {
+    /// Shows newly loaded factions, keeping the selection if the faction is still there.
+    pub fn open(&mut self, graph: FactionGraph) {
+        if let Some(selected) = self.selected_faction {
+            if graph.faction(selected).is_none() {
+                self.selected_faction = None;
+                self.selected_relation = None;
+            }
+        }
+
+        self.graph = Some(graph);
+    }
+
+    /// Selects a faction by form ID, if it is loaded.
+    pub fn select_faction(&mut self, form_id: u32) {
+        if let Some(graph) = &self.graph {
+            if graph.faction(form_id).is_some() {
+                self.selected_faction = Some(form_id);
+                self.selected_relation = None;
+            }
+        }
+    }
+}
+
+/// Changes made in the Faction Graph window.
+pub enum FactionGraphAction {
+    /// Load the factions of the load order, replacing those shown.
+    Load,
+    /// The reactions of the faction with this form ID were edited.
+    FactionEdited(u32),
+}
+
+pub struct FactionGraphWindow<'a> {
+    state: &'a mut FactionGraphState,
+    editor_ids: EditorIds<'a>,
+    actions: Vec<FactionGraphAction>,
+}
+
+impl<'a> FactionGraphWindow<'a> {
+    pub fn new(state: &'a mut FactionGraphState, editor_ids: EditorIds<'a>) -> Self {
+        Self {
+            state,
+            editor_ids,
+            actions: vec![],
+        }
+    }
+
+    pub fn actions(self) -> Vec<FactionGraphAction> {
+        self.actions
+    }
+}
+
+fn reaction_color(reaction: Reaction) -> egui::Color32 {
+    match reaction {
+        Reaction::Neutral => egui::Color32::from_rgb(150, 150, 150),
+        Reaction::Enemy => egui::Color32::from_rgb(220, 70, 60),
+        Reaction::Ally => egui::Color32::from_rgb(70, 130, 230),
+        Reaction::Friend => egui::Color32::from_rgb(80, 190, 90),
+    }
+}
+
+fn reaction_shown(shown_reactions: &[bool; 4], reaction: Reaction) -> bool {
+    Reaction::ALL
+        .iter()
+        .zip(shown_reactions)
+        .any(|(&option, &shown)| option == reaction && shown)
+}
+
+fn faction_name(form_id: u32, editor_ids: EditorIds) -> String {
+    format!(
+        "{} [{:08X}]",
+        editor_ids(form_id).unwrap_or_else(|| "(not found)".to_string()),
+        form_id
+    )
+}
+
+/// The factions to draw, in the graph's order, and the reactions to draw between them, as the faction reacting,
+/// the faction reacted to and the reaction.
+fn visible(graph: &FactionGraph, state: &FactionGraphState) -> (Vec<u32>, Vec<(u32, u32, Reaction)>) {
+    let filter = state.filter.trim().to_lowercase();
+    let nodes: HashSet<u32> = graph.factions.iter().map(|faction| faction.form_id).collect();
+
+    let relations: Vec<(u32, u32, Reaction)> = graph
+        .factions
+        .iter()
+        .flat_map(|faction| {
+            faction
+                .relations
+                .iter()
+                .map(move |relation| (faction.form_id, relation.faction, relation.reaction))
+        })
+        .filter(|&(_, to, reaction)| nodes.contains(&to) && reaction_shown(&state.shown_reactions, reaction))
+        .collect();
+
+    let mut shown: HashSet<u32> = graph
+        .factions
+        .iter()
+        .filter(|faction| filter.is_empty() || faction.editor_id.to_lowercase().contains(&filter))
+        .map(|faction| faction.form_id)
+        .collect();
+
+    if state.show_neighbours && !filter.is_empty() {
+        let neighbours: Vec<u32> = relations
+            .iter()
+            .filter_map(|&(from, to, _)| match (shown.contains(&from), shown.contains(&to)) {
+                (true, false) => Some(to),
+                (false, true) => Some(from),
+                _ => None,
+            })
+            .collect();
+        shown.extend(neighbours);
+    }
+
+    let edges: Vec<(u32, u32, Reaction)> = relations
+        .into_iter()
+        .filter(|(from, to, _)| from != to && shown.contains(from) && shown.contains(to))
+        .collect();
+
+    if state.hide_unrelated {
+        let mut related = HashSet::new();
+        for &(from, to, _) in &edges {
+            related.insert(from);
+            related.insert(to);
+        }
+
+        shown.retain(|form_id| related.contains(form_id) || state.selected_faction == Some(*form_id));
+    }
+
+    let factions = graph
+        .factions
+        .iter()
+        .map(|faction| faction.form_id)
+        .filter(|form_id| shown.contains(form_id))
+        .collect();
+
+    (factions, edges)
+}
+
+fn distance_to_segment(point: egui::Pos2, start: egui::Pos2, end: egui::Pos2) -> f32 {
+    let segment = end - start;
+    let length_sq = segment.x * segment.x + segment.y * segment.y;
+
+    if length_sq == 0.0 {
+        return (point - start).length();
+    }
+
+    let along = point - start;
+    let t = ((along.x * segment.x + along.y * segment.y) / length_sq)
+        .max(0.0)
+        .min(1.0);
+    (start + segment * t - point).length()
+}
+
+/// Draws the factions in a circle with arrows, colored by reaction, to the factions they react to. The graph can
+/// be dragged around, and factions and reactions clicked to select them.
+fn graph_ui(ui: &mut egui::Ui, state: &mut FactionGraphState) {
+    let graph = match &state.graph {
+        Some(graph) => graph,
+        None => return,
+    };
+
+    let (factions, edges) = visible(graph, state);
+
+    let size = egui::vec2(ui.available_width(), GRAPH_HEIGHT);
+    let (response, painter) = ui.allocate_painter(size, egui::Sense::click_and_drag());
+    let visuals = ui.visuals();
+
+    painter.rect_filled(response.rect, 0.0, visuals.extreme_bg_color);
+
+    if response.dragged() {
+        state.graph_offset += response.drag_delta();
+    }
+
+    let center = response.rect.center() + state.graph_offset;
+    let radius = (response.rect.width().min(response.rect.height()) / 2.0 - GRAPH_MARGIN).max(NODE_RADIUS);
+    let positions: HashMap<u32, egui::Pos2> = factions
+        .iter()
+        .enumerate()
+        .map(|(index, &form_id)| {
+            let angle = index as f32 / factions.len() as f32 * TAU - TAU / 4.0;
+            let position = match factions.len() {
+                1 => center,
+                _ => center + egui::vec2(angle.cos(), angle.sin()) * radius,
+            };
+            (form_id, position)
+        })
+        .collect();
+
+    let mut segments = vec![];
+
+    for &(from, to, reaction) in &edges {
+        let (start, end) = (positions[&from], positions[&to]);
+        let direction = (end - start).normalized();
+        let side = direction.rot90() * EDGE_OFFSET;
+        let start = start + direction * NODE_RADIUS + side;
+        let end = end - direction * NODE_RADIUS + side;
+
+        let width = if state.selected_relation == Some((from, to)) {
+            3.0
+        } else {
+            1.0
+        };
+        let stroke = egui::Stroke::new(width, reaction_color(reaction));
+        painter.line_segment([start, end], stroke);
+
+        let back = end - direction * ARROW_SIZE;
+        let arrow_side = direction.rot90() * (ARROW_SIZE / 2.0);
+        painter.line_segment([end, back + arrow_side], stroke);
+        painter.line_segment([end, back - arrow_side], stroke);
+
+        segments.push((from, to, start, end));
+    }
+
+    for &form_id in &factions {
+        let position = positions[&form_id];
+        let fill = if state.selected_faction == Some(form_id) {
+            visuals.selection.bg_fill
+        } else {
+            visuals.widgets.inactive.bg_fill
+        };
+
+        painter.circle_filled(position, NODE_RADIUS, fill);
+        painter.circle_stroke(position, NODE_RADIUS, visuals.widgets.inactive.bg_stroke);
+
+        let label: String = graph
+            .faction(form_id)
+            .map(|faction| faction.editor_id.chars().take(NODE_LABEL_LENGTH).collect())
+            .unwrap_or_default();
+        painter.text(
+            position + egui::vec2(0.0, NODE_RADIUS + 2.0),
+            egui::Align2::CENTER_TOP,
+            label,
+            egui::TextStyle::Small,
+            visuals.text_color(),
+        );
+    }
+
+    if response.clicked() {
+        if let Some(pointer) = response.interact_pointer_pos() {
+            let node = factions
+                .iter()
+                .copied()
+                .find(|form_id| (positions[form_id] - pointer).length() <= NODE_RADIUS + 2.0);
+
+            let edge = segments
+                .iter()
+                .map(|&(from, to, start, end)| (distance_to_segment(pointer, start, end), from, to))
+                .filter(|&(distance, _, _)| distance <= EDGE_HIT_DISTANCE)
+                .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+            if let Some(form_id) = node {
+                state.selected_faction = Some(form_id);
+                state.selected_relation = None;
+            } else if let Some((_, from, to)) = edge {
+                state.selected_faction = Some(from);
+                state.selected_relation = Some((from, to));
+            }
+        }
+    }
+
+    ui.label(format!(
+        "{} of {} factions, {} reactions.",
+        factions.len(),
+        graph.factions.len(),
+        edges.len()
+    ));
+}
+
+/// The filter and which reactions are drawn.
+fn filter_ui(ui: &mut egui::Ui, state: &mut FactionGraphState) {
+    ui.horizontal(|ui| {
+        ui.label("Filter");
+        ui.add(egui::TextEdit::singleline(&mut state.filter).hint_text("Editor ID"));
+        ui.checkbox(&mut state.show_neighbours, "With related factions");
+        ui.checkbox(&mut state.hide_unrelated, "Hide factions without reactions");
+    });
+
+    ui.horizontal(|ui| {
+        for (index, &reaction) in Reaction::ALL.iter().enumerate() {
+            ui.checkbox(&mut state.shown_reactions[index], "");
+            ui.colored_label(reaction_color(reaction), reaction.name());
+        }
+    });
+}
+
+/// Edits one faction's reaction to another. Returns the factions edited.
+fn relation_ui(ui: &mut egui::Ui, graph: &mut FactionGraph, from: u32, to: u32, editor_ids: EditorIds) -> Vec<u32> {
+    let mut edited = vec![];
+
+    let relation = match graph.faction(from).and_then(|faction| faction.relation(to)) {
+        Some(relation) => relation.clone(),
+        None => return edited,
+    };
+
+    ui.label(format!(
+        "{} towards {}",
+        faction_name(from, editor_ids),
+        faction_name(to, editor_ids)
+    ));
+
+    let mut reaction = relation.reaction;
+    let mut modifier = relation.modifier;
+    let mut removed = false;
+    let mut mutual = false;
+
+    ui.horizontal(|ui| {
+        egui::combo_box(ui, egui::Id::new("faction_reaction"), reaction.name(), |ui| {
+            for &option in &Reaction::ALL {
+                ui.selectable_value(&mut reaction, option, option.name());
+            }
+        });
+
+        ui.label("Modifier");
+        ui.add(egui::DragValue::new(&mut modifier));
+
+        let reverse = graph.faction(to).map(|faction| faction.relation(from));
+        let is_mutual = match reverse {
+            Some(Some(reverse)) => reverse.reaction == reaction && reverse.modifier == modifier,
+            Some(None) => false,
+            // Races can be reacted to, but are not edited here.
+            None => true,
+        };
+
+        mutual = ui.add(egui::Button::new("Make mutual").enabled(!is_mutual)).clicked();
+        removed = ui.button("Remove").clicked();
+    });
+
+    if removed {
+        if let Some(faction) = graph.faction_mut(from) {
+            faction.relations.retain(|relation| relation.faction != to);
+            edited.push(from);
+        }
+        return edited;
+    }
+
+    if reaction != relation.reaction || modifier != relation.modifier {
+        let edit = graph
+            .faction_mut(from)
+            .and_then(|faction| faction.relations.iter_mut().find(|relation| relation.faction == to));
+
+        if let Some(relation) = edit {
+            relation.reaction = reaction;
+            relation.modifier = modifier;
+            edited.push(from);
+        }
+    }
+
+    if mutual {
+        if let Some(faction) = graph.faction_mut(to) {
+            match faction.relations.iter_mut().find(|relation| relation.faction == from) {
+                Some(relation) => {
+                    relation.reaction = reaction;
+                    relation.modifier = modifier;
+                }
+                None => faction.relations.push(FactionRelation {
+                    faction: from,
+                    modifier,
+                    reaction,
+                }),
+            }
+            edited.push(to);
+        }
+    }
+
+    edited
+}
+
+/// Lists how the selected faction reacts to others and how others react to it, gives it new reactions, and
+/// lists its members. Returns whether a reaction was added.
+fn faction_ui(ui: &mut egui::Ui, state: &mut FactionGraphState, editor_ids: EditorIds) -> bool {
+    let FactionGraphState {
+        graph,
+        selected_faction,
+        selected_relation,
+        new_relation,
+        ..
+    } = state;
+
+    let (graph, form_id) = match (graph, *selected_faction) {
+        (Some(graph), Some(form_id)) => (graph, form_id),
+        _ => return false,
+    };
+
+    let faction = match graph.faction(form_id) {
+        Some(faction) => faction,
+        None => return false,
+    };
+
+    ui.label(faction_name(form_id, editor_ids));
+
+    let incoming: Vec<(u32, Reaction)> = graph
+        .factions
+        .iter()
+        .filter_map(|other| {
+            other
+                .relation(form_id)
+                .map(|relation| (other.form_id, relation.reaction))
+        })
+        .collect();
+
+    ui.columns(2, |columns| {
+        columns[0].label("Reacts to");
+        egui::ScrollArea::from_max_height(LIST_HEIGHT)
+            .id_source("faction_relations")
+            .show(&mut columns[0], |ui| {
+                for relation in &faction.relations {
+                    let text = format!(
+                        "{}: {} ({:+})",
+                        faction_name(relation.faction, editor_ids),
+                        relation.reaction.name(),
+                        relation.modifier
+                    );
+                    let selected = *selected_relation == Some((form_id, relation.faction));
+
+                    if ui.selectable_label(selected, text).clicked() {
+                        *selected_relation = Some((form_id, relation.faction));
+                    }
+                }
+            });
+
+        columns[1].label("Reacted to by");
+        egui::ScrollArea::from_max_height(LIST_HEIGHT)
+            .id_source("faction_incoming")
+            .show(&mut columns[1], |ui| {
+                for &(other, reaction) in &incoming {
+                    let text = format!("{}: {}", faction_name(other, editor_ids), reaction.name());
+                    let selected = *selected_relation == Some((other, form_id));
+
+                    if ui.selectable_label(selected, text).clicked() {
+                        *selected_relation = Some((other, form_id));
+                    }
+                }
+            });
+    });
+
+    let mut added = false;
+
+    ui.horizontal(|ui| {
+        ui.label("New reaction to");
+        form_id_edit(ui, new_relation, editor_ids);
+
+        let allowed = *new_relation != 0 && *new_relation != form_id && faction.relation(*new_relation).is_none();
+        added = ui.add(egui::Button::new("Add").enabled(allowed)).clicked();
+    });
+
+    let members = graph
+        .members
+        .get(&form_id)
+        .map_or(&[][..], |members| members.as_slice());
+
+    egui::CollapsingHeader::new(format!("Members ({})", members.len()))
+        .id_source("faction_members")
+        .show(ui, |ui| {
+            egui::ScrollArea::from_max_height(LIST_HEIGHT)
+                .id_source("faction_members_list")
+                .show(ui, |ui| {
+                    for member in members {
+                        ui.label(format!(
+                            "{}  rank {}",
+                            faction_name(member.npc, editor_ids),
+                            member.rank
+                        ));
+                    }
+                });
+        });
+
+    if !added {
+        return false;
+    }
+
+    if let Some(faction) = graph.faction_mut(form_id) {
+        faction.relations.push(FactionRelation {
+            faction: *new_relation,
+            modifier: 0,
+            reaction: Reaction::Neutral,
+        });
+    }
+
+    *selected_relation = Some((form_id, *new_relation));
+    *new_relation = 0;
+    true
+}
+
+impl<'a> View for FactionGraphWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let editor_ids = self.editor_ids;
+
+        if self.state.graph.is_none() {
+            ui.label("Load the factions of the load order to see how they react to each other.");
+            if ui.button("Load").clicked() {
+                self.actions.push(FactionGraphAction::Load);
+            }
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Reload").clicked() {
+                self.actions.push(FactionGraphAction::Load);
+            }
+            ui.label("Click a faction or a reaction to select it, and drag to move the graph.");
+        });
+
+        filter_ui(ui, self.state);
+        graph_ui(ui, self.state);
+        ui.separator();
+
+        if let (Some(graph), Some((from, to))) = (&mut self.state.graph, self.state.selected_relation) {
+            let edited = relation_ui(ui, graph, from, to, editor_ids);
+            self.actions
+                .extend(edited.into_iter().map(FactionGraphAction::FactionEdited));
+            ui.separator();
+        }
+
+        if faction_ui(ui, self.state, editor_ids) {
+            if let Some(form_id) = self.state.selected_faction {
+                self.actions.push(FactionGraphAction::FactionEdited(form_id));
+            }
+        }
+    }
+}
+
+impl<'a> Window for FactionGraphWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Faction Graph"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(DEFAULT_WIDTH)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
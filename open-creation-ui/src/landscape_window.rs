@@ -0,0 +1,154 @@
+use super::{View, Window};
+
+const DEFAULT_WIDTH: f32 = 320.0;
+const TEXTURE_LIST_HEIGHT: f32 = 200.0;
+const MIN_RADIUS: f32 = 64.0;
+const MAX_RADIUS: f32 = 8192.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BrushTool {
+    Raise,
+    Lower,
+    Flatten,
+    Smooth,
+    PaintTexture,
+    PaintColor,
+}
+
+impl BrushTool {
+    pub fn edits_heights(self) -> bool {
+        matches!(
+            self,
+            BrushTool::Raise | BrushTool::Lower | BrushTool::Flatten | BrushTool::Smooth
+        )
+    }
+}
+
+pub struct LandscapeState {
+    pub tool: BrushTool,
+    /// The brush radius in game units.
+    pub radius: f32,
+    /// How quickly the brush works, from 0 to 1.
+    pub strength: f32,
+    /// The LTEX record painted by the texture brush.
+    pub texture: Option<u32>,
+    pub texture_filter: String,
+    /// The colour painted by the colour brush, in sRGB like the vertex colours it is blended into.
+    pub color: [u8; 3],
+}
+
+impl Default for LandscapeState {
+    fn default() -> Self {
+        Self {
+            tool: BrushTool::Raise,
+            radius: 512.0,
+            strength: 0.5,
+            texture: None,
+            texture_filter: String::new(),
+            color: [255; 3],
+        }
+    }
+}
+
+pub struct LandscapeWindow<'a> {
+    state: &'a mut LandscapeState,
+    /// Form IDs and editor IDs of the LTEX records that can be painted.
+    textures: &'a [(u32, String)],
+}
+
+impl<'a> LandscapeWindow<'a> {
+    pub fn new(state: &'a mut LandscapeState, textures: &'a [(u32, String)]) -> Self {
+        Self { state, textures }
+    }
+}
+
+impl<'a> View for LandscapeWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let textures = self.textures;
+        let state = &mut *self.state;
+
+        ui.label("Drag over the terrain with the left mouse button. Close this window to select objects again.");
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut state.tool, BrushTool::Raise, "Raise");
+            ui.radio_value(&mut state.tool, BrushTool::Lower, "Lower");
+            ui.radio_value(&mut state.tool, BrushTool::Flatten, "Flatten");
+            ui.radio_value(&mut state.tool, BrushTool::Smooth, "Smooth");
+        });
+
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut state.tool, BrushTool::PaintTexture, "Paint texture");
+            ui.radio_value(&mut state.tool, BrushTool::PaintColor, "Paint colour");
+        });
+
+        ui.separator();
+
+        egui::Grid::new("landscape_grid").show(ui, |ui| {
+            ui.label("Radius");
+            if ui.add(egui::DragValue::new(&mut state.radius).speed(8.0)).changed() {
+                state.radius = state.radius.max(MIN_RADIUS).min(MAX_RADIUS);
+            }
+            ui.end_row();
+
+            ui.label("Strength");
+            ui.add(egui::Slider::new(&mut state.strength, 0.0..=1.0));
+            ui.end_row();
+
+            if state.tool == BrushTool::PaintColor {
+                ui.label("Colour");
+                ui.color_edit_button_srgb(&mut state.color);
+                ui.end_row();
+            }
+        });
+
+        if state.tool != BrushTool::PaintTexture {
+            return;
+        }
+
+        ui.separator();
+
+        let selected = state
+            .texture
+            .and_then(|texture| textures.iter().find(|(form_id, _)| *form_id == texture));
+
+        match selected {
+            Some((form_id, editor_id)) => ui.label(format!("Texture: {}  [{:08X}]", editor_id, form_id)),
+            None => ui.label("Choose a landscape texture to paint."),
+        };
+
+        ui.add(egui::TextEdit::singleline(&mut state.texture_filter).hint_text("Filter by editor ID"));
+
+        let filter = state.texture_filter.trim().to_lowercase();
+
+        egui::ScrollArea::from_max_height(TEXTURE_LIST_HEIGHT)
+            .id_source("landscape_textures")
+            .show(ui, |ui| {
+                for (form_id, editor_id) in textures {
+                    if !editor_id.to_lowercase().contains(&filter) {
+                        continue;
+                    }
+
+                    if ui
+                        .selectable_label(state.texture == Some(*form_id), editor_id)
+                        .clicked()
+                    {
+                        state.texture = Some(*form_id);
+                    }
+                }
+            });
+    }
+}
+
+impl<'a> Window for LandscapeWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Landscape"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(DEFAULT_WIDTH)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
@@ -0,0 +1,297 @@
+use super::{
+    widgets::{flag_checkbox, form_id_edit, EditorIds, RecordCodes},
+    View, Window,
+};
+
+use open_creation_data::{
+    esp::{leveled_flags, LeveledEntry, LeveledList, MAX_LEVELED_ENTRIES},
+    leveled_simulation::Simulation,
+};
+
+const DEFAULT_WIDTH: f32 = 480.0;
+const LIST_HEIGHT: f32 = 240.0;
+const RESULTS_HEIGHT: f32 = 200.0;
+const MAX_LEVEL: u16 = 255;
+const MAX_ROLLS: u32 = 1_000_000;
+
+pub struct LeveledListState {
+    /// The list being edited, as it will be written to the active plugin.
+    pub list: Option<LeveledList>,
+    /// The player level to simulate at.
+    pub level: u16,
+    pub rolls: u32,
+    /// The results of the last simulation of the list.
+    pub simulation: Option<Simulation>,
+}
+
+impl Default for LeveledListState {
+    fn default() -> Self {
+        Self {
+            list: None,
+            level: 10,
+            rolls: 10_000,
+            simulation: None,
+        }
+    }
+}
+
+impl LeveledListState {
+    /// Starts editing another list.
+    pub fn open(&mut self, list: LeveledList) {
+        self.list = Some(list);
+        self.simulation = None;
+    }
+}
+
+pub struct LeveledListWindow<'a> {
+    state: &'a mut LeveledListState,
+    editor_ids: EditorIds<'a>,
+    record_codes: RecordCodes<'a>,
+    changed: bool,
+    simulate_clicked: bool,
+}
+
+impl<'a> LeveledListWindow<'a> {
+    pub fn new(state: &'a mut LeveledListState, editor_ids: EditorIds<'a>, record_codes: RecordCodes<'a>) -> Self {
+        Self {
+            state,
+            editor_ids,
+            record_codes,
+            changed: false,
+            simulate_clicked: false,
+        }
+    }
+
+    /// Whether the list was edited and should be saved.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Whether the list should be rolled at the chosen level, replacing the last simulation.
+    pub fn simulate_clicked(&self) -> bool {
+        self.simulate_clicked
+    }
+}
+
+fn settings_ui(ui: &mut egui::Ui, list: &mut LeveledList, editor_ids: EditorIds) -> bool {
+    let mut changed = false;
+
+    egui::Grid::new("leveled_list_settings_grid").show(ui, |ui| {
+        ui.label("Editor ID");
+        changed |= ui.text_edit_singleline(&mut list.editor_id).changed();
+        ui.end_row();
+
+        ui.label("Chance none");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut list.chance_none)
+                    .clamp_range(0..=100)
+                    .suffix("%"),
+            )
+            .changed();
+        ui.end_row();
+
+        // Spell lists have no chance none global.
+        if &list.code != b"LVSP" {
+            ui.label("Chance none global");
+            let mut global = list.chance_none_global.unwrap_or(0);
+            if form_id_edit(ui, &mut global, editor_ids) {
+                list.chance_none_global = Some(global).filter(|&global| global != 0);
+                changed = true;
+            }
+            ui.end_row();
+        }
+    });
+
+    changed |= flag_checkbox(
+        ui,
+        &mut list.flags,
+        leveled_flags::CALCULATE_FROM_ALL_LEVELS,
+        "Calculate from all levels <= player's level",
+    );
+    changed |= flag_checkbox(
+        ui,
+        &mut list.flags,
+        leveled_flags::CALCULATE_FOR_EACH_ITEM,
+        "Calculate for each item in count",
+    );
+
+    if &list.code != b"LVLN" {
+        changed |= flag_checkbox(ui, &mut list.flags, leveled_flags::USE_ALL, "Use all");
+    }
+
+    if &list.code == b"LVLI" {
+        changed |= flag_checkbox(ui, &mut list.flags, leveled_flags::SPECIAL_LOOT, "Special loot");
+    }
+
+    changed
+}
+
+fn entries_ui(ui: &mut egui::Ui, list: &mut LeveledList, editor_ids: EditorIds, record_codes: RecordCodes) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+
+    egui::ScrollArea::from_max_height(LIST_HEIGHT)
+        .id_source("leveled_list_entries")
+        .show(ui, |ui| {
+            egui::Grid::new("leveled_list_entries_grid").show(ui, |ui| {
+                ui.label("Level");
+                ui.label("Reference");
+                ui.label("Type");
+                ui.label("Count");
+                ui.end_row();
+
+                for (index, entry) in list.entries.iter_mut().enumerate() {
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut entry.level).clamp_range(1..=MAX_LEVEL))
+                        .changed();
+                    changed |= form_id_edit(ui, &mut entry.form_id, editor_ids);
+
+                    match record_codes(entry.form_id) {
+                        Some(code) => ui.label(String::from_utf8_lossy(&code)),
+                        None => ui.label(""),
+                    };
+
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut entry.count).clamp_range(1..=u16::MAX))
+                        .changed();
+
+                    if ui.small_button("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+    if let Some(index) = removed {
+        list.entries.remove(index);
+        changed = true;
+    }
+
+    ui.horizontal(|ui| {
+        let can_add = list.entries.len() < MAX_LEVELED_ENTRIES;
+
+        if ui.add(egui::Button::new("Add").enabled(can_add)).clicked() {
+            list.entries.push(LeveledEntry {
+                level: 1,
+                count: 1,
+                ..LeveledEntry::default()
+            });
+            changed = true;
+        }
+
+        if !can_add {
+            ui.label(format!("A leveled list holds at most {} entries", MAX_LEVELED_ENTRIES));
+        }
+    });
+
+    changed
+}
+
+/// The level and number of rolls to simulate with, and the results of the last simulation. Returns whether
+/// Simulate was clicked.
+fn simulation_ui(
+    ui: &mut egui::Ui,
+    level: &mut u16,
+    rolls: &mut u32,
+    simulation: &Option<Simulation>,
+    editor_ids: EditorIds,
+) -> bool {
+    let mut clicked = false;
+
+    ui.horizontal(|ui| {
+        ui.label("Player level");
+        ui.add(egui::DragValue::new(level).clamp_range(1..=MAX_LEVEL));
+        ui.label("Rolls");
+        ui.add(egui::DragValue::new(rolls).clamp_range(1..=MAX_ROLLS).speed(100.0));
+        clicked = ui.button("Simulate").clicked();
+    });
+
+    let simulation = match simulation {
+        Some(simulation) if simulation.rolls > 0 => simulation,
+        _ => return clicked,
+    };
+
+    let percent = |rolls: u32| 100.0 * rolls as f32 / simulation.rolls as f32;
+
+    ui.label(format!(
+        "{} rolls at level {}: nothing given {:.1}% of the time.",
+        simulation.rolls,
+        simulation.level,
+        percent(simulation.empty)
+    ));
+
+    egui::ScrollArea::from_max_height(RESULTS_HEIGHT)
+        .id_source("leveled_list_simulation")
+        .show(ui, |ui| {
+            egui::Grid::new("leveled_list_simulation_grid").show(ui, |ui| {
+                ui.label("Item");
+                ui.label("Given");
+                ui.label("Average count");
+                ui.end_row();
+
+                for item in &simulation.items {
+                    let name = editor_ids(item.form_id).unwrap_or_else(|| "(not found)".to_string());
+                    ui.label(format!("{} ({:08X})", name, item.form_id));
+                    ui.label(format!("{:.1}%", percent(item.rolls)));
+                    ui.label(format!("{:.2}", item.count as f32 / item.rolls as f32));
+                    ui.end_row();
+                }
+            });
+        });
+
+    clicked
+}
+
+impl<'a> View for LeveledListWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let editor_ids = self.editor_ids;
+        let record_codes = self.record_codes;
+        let LeveledListState {
+            list,
+            level,
+            rolls,
+            simulation,
+        } = &mut *self.state;
+
+        let list = match list {
+            Some(list) => list,
+            None => {
+                ui.label("Select a leveled list in the records to edit it.");
+                return;
+            }
+        };
+
+        ui.label(format!("{} {:08X}", String::from_utf8_lossy(&list.code), list.form_id));
+
+        let mut changed = settings_ui(ui, list, editor_ids);
+        ui.separator();
+        changed |= entries_ui(ui, list, editor_ids, record_codes);
+        ui.separator();
+
+        let mut simulate_clicked = false;
+        egui::CollapsingHeader::new("Simulation")
+            .id_source("leveled_list_simulation_header")
+            .default_open(true)
+            .show(ui, |ui| {
+                simulate_clicked = simulation_ui(ui, level, rolls, simulation, editor_ids);
+            });
+
+        self.changed |= changed;
+        self.simulate_clicked |= simulate_clicked;
+    }
+}
+
+impl<'a> Window for LeveledListWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Leveled List"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(DEFAULT_WIDTH)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
@@ -1,12 +1,51 @@
 pub mod about_window;
+pub mod archive_browser_window;
+pub mod cell_view_window;
+pub mod condition_list;
+pub mod create_archive_window;
 pub mod data_window;
+pub mod dialogue_window;
+pub mod faction_graph_window;
 pub mod log_window;
 pub mod game_settings_window;
+pub mod landscape_window;
+pub mod leveled_list_window;
+pub mod lighting_window;
+pub mod navmesh_window;
+pub mod npc_window;
+pub mod papyrus_window;
+pub mod quest_window;
+pub mod script_browser_window;
+pub mod script_list;
+pub mod scripts_window;
+pub mod texture_preview_window;
+pub mod transform_window;
+pub mod widgets;
 
 pub use about_window::AboutWindow;
+pub use archive_browser_window::{ArchiveBrowserAction, ArchiveBrowserState, ArchiveBrowserWindow};
+pub use cell_view_window::{
+    CellItem, CellList, CellViewAction, CellViewState, CellViewWindow, ReferenceItem, WorldItem,
+};
+pub use condition_list::condition_list;
+pub use create_archive_window::{CreateArchiveOptions, CreateArchiveWindow};
 pub use data_window::DataWindow;
+pub use dialogue_window::{DialogueAction, DialogueState, DialogueWindow};
+pub use faction_graph_window::{FactionGraphAction, FactionGraphState, FactionGraphWindow};
 pub use game_settings_window::GameSettingsWindow;
+pub use landscape_window::{BrushTool, LandscapeState, LandscapeWindow};
+pub use leveled_list_window::{LeveledListState, LeveledListWindow};
+pub use lighting_window::{LightingState, LightingWindow};
+pub use navmesh_window::{NavmeshAction, NavmeshItem, NavmeshState, NavmeshWindow};
+pub use npc_window::{NpcState, NpcTab, NpcWindow};
 pub use log_window::LogWindow;
+pub use papyrus_window::{PapyrusAction, PapyrusState, PapyrusTab, PapyrusWindow};
+pub use quest_window::{QuestState, QuestTab, QuestWindow};
+pub use script_browser_window::{ScriptBrowserState, ScriptBrowserWindow};
+pub use script_list::{script_list, ScriptLookup};
+pub use scripts_window::{ScriptsState, ScriptsWindow};
+pub use texture_preview_window::{TextureInfo, TexturePreviewState, TexturePreviewWindow};
+pub use transform_window::{GizmoMode, TransformState, TransformWindow};
 
 pub trait View {
     fn ui(&mut self, ui: &mut egui::Ui);
@@ -0,0 +1,144 @@
+use super::{View, Window};
+
+const DEFAULT_WIDTH: f32 = 320.0;
+const WEATHER_LIST_HEIGHT: f32 = 160.0;
+
+pub struct LightingState {
+    /// The previewed time of day in hours, for exterior weathers.
+    pub hour: f32,
+    /// The WTHR record previewed outdoors, or `None` for the climate's likeliest weather.
+    pub weather: Option<u32>,
+    pub weather_filter: String,
+    /// Whether the loaded cell is an exterior, lit by weather rather than by its own lighting.
+    pub is_exterior: bool,
+    /// Where the lighting comes from, filled in by the viewport.
+    pub source: String,
+    /// Named colours of the current lighting.
+    pub colors: Vec<(&'static str, [u8; 3])>,
+    /// Named values of the current lighting, already formatted.
+    pub values: Vec<(&'static str, String)>,
+    pub point_lights: usize,
+}
+
+impl Default for LightingState {
+    fn default() -> Self {
+        Self {
+            hour: 12.0,
+            weather: None,
+            weather_filter: String::new(),
+            is_exterior: false,
+            source: "Default lighting".to_string(),
+            colors: vec![],
+            values: vec![],
+            point_lights: 0,
+        }
+    }
+}
+
+pub struct LightingWindow<'a> {
+    state: &'a mut LightingState,
+    /// Form IDs and editor IDs of the WTHR records that can be previewed.
+    weathers: &'a [(u32, String)],
+}
+
+impl<'a> LightingWindow<'a> {
+    pub fn new(state: &'a mut LightingState, weathers: &'a [(u32, String)]) -> Self {
+        Self { state, weathers }
+    }
+}
+
+impl<'a> View for LightingWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let weathers = self.weathers;
+        let state = &mut *self.state;
+
+        ui.label(&state.source);
+        ui.label(format!("{} placed lights", state.point_lights));
+        ui.separator();
+
+        egui::Grid::new("lighting_grid").show(ui, |ui| {
+            for (name, color) in &state.colors {
+                ui.label(*name);
+                ui.horizontal(|ui| {
+                    // A copy, so the swatch can be shown without editing the records.
+                    let mut swatch = *color;
+                    ui.color_edit_button_srgb(&mut swatch);
+                    ui.label(format!("{}, {}, {}", color[0], color[1], color[2]));
+                });
+                ui.end_row();
+            }
+
+            for (name, value) in &state.values {
+                ui.label(*name);
+                ui.label(value);
+                ui.end_row();
+            }
+        });
+
+        ui.label("Fog is listed for reference; the viewport does not draw it.");
+
+        if !state.is_exterior {
+            return;
+        }
+
+        ui.separator();
+
+        let minutes = (state.hour * 60.0).round() as u32;
+        ui.add(egui::Slider::new(&mut state.hour, 0.0..=24.0).text(format!(
+            "{:02}:{:02}",
+            minutes / 60 % 24,
+            minutes % 60
+        )));
+
+        ui.separator();
+
+        let selected = state
+            .weather
+            .and_then(|weather| weathers.iter().find(|(form_id, _)| *form_id == weather));
+
+        match selected {
+            Some((form_id, editor_id)) => ui.label(format!("Weather: {}  [{:08X}]", editor_id, form_id)),
+            None => ui.label("Weather: the climate's likeliest"),
+        };
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut state.weather_filter).hint_text("Filter by editor ID"));
+
+            if ui.button("Use climate").clicked() {
+                state.weather = None;
+            }
+        });
+
+        let filter = state.weather_filter.trim().to_lowercase();
+
+        egui::ScrollArea::from_max_height(WEATHER_LIST_HEIGHT)
+            .id_source("lighting_weathers")
+            .show(ui, |ui| {
+                for (form_id, editor_id) in weathers {
+                    if !editor_id.to_lowercase().contains(&filter) {
+                        continue;
+                    }
+
+                    if ui
+                        .selectable_label(state.weather == Some(*form_id), editor_id)
+                        .clicked()
+                    {
+                        state.weather = Some(*form_id);
+                    }
+                }
+            });
+    }
+}
+
+impl<'a> Window for LightingWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Lighting"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(DEFAULT_WIDTH)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
@@ -1,5 +1,7 @@
 use super::{View, Window};
 
+use open_creation_data::papyrus::Diagnostic;
+
 use std::collections::VecDeque;
 
 const DEFAULT_WIDTH: f32 = 600.0;
@@ -8,40 +10,62 @@ const DEFAULT_HEIGHT: f32 = 400.0;
 pub struct LogWindow<'a> {
     lines: &'a VecDeque<String>,
     scroll: bool,
+    clicked: Option<Diagnostic>,
 }
 
 impl<'a> LogWindow<'a> {
     pub fn new(lines: &'a VecDeque<String>) -> Self {
-        Self { lines, scroll: false }
+        Self {
+            lines,
+            scroll: false,
+            clicked: None,
+        }
     }
 
     pub fn scroll(mut self, scroll: bool) -> Self {
         self.scroll = scroll;
         self
     }
+
+    /// The compiler diagnostic whose line was clicked, to be shown in the source.
+    pub fn clicked(self) -> Option<Diagnostic> {
+        self.clicked
+    }
 }
 
 impl<'a> View for LogWindow<'a> {
     fn ui(&mut self, ui: &mut egui::Ui) {
-        ui.vertical_centered_justified(|ui| {
-            egui::ScrollArea::auto_sized().id_source("log_scroll").show(ui, |ui| {
-                let mut lines = self.lines.iter().fold(String::new(), |mut acc, elem| {
-                    acc.push_str(elem);
-                    acc.push_str("\n");
-                    acc
-                });
-                lines.pop();
-
-                let text = egui::widgets::TextEdit::multiline(&mut lines)
-                    .text_style(egui::TextStyle::Monospace)
-                    .enabled(false);
-
-                let response = ui.add(text);
-
-                if self.scroll {
-                    response.scroll_to_me(egui::Align::BOTTOM);
+        let lines = self.lines;
+        let scroll = self.scroll;
+        let clicked = &mut self.clicked;
+
+        egui::ScrollArea::auto_sized().id_source("log_scroll").show(ui, |ui| {
+            for (index, line) in lines.iter().enumerate() {
+                let label = egui::Label::new(line).text_style(egui::TextStyle::Monospace);
+                let last = scroll && index + 1 == lines.len();
+
+                // Compiler diagnostics link to the line they were reported on.
+                match Diagnostic::parse(line) {
+                    Some(diagnostic) => {
+                        ui.horizontal(|ui| {
+                            if ui.small_button(format!("Line {}", diagnostic.line)).clicked() {
+                                *clicked = Some(diagnostic);
+                            }
+
+                            let response = ui.add(label);
+                            if last {
+                                response.scroll_to_me(egui::Align::BOTTOM);
+                            }
+                        });
+                    }
+                    None => {
+                        let response = ui.add(label);
+                        if last {
+                            response.scroll_to_me(egui::Align::BOTTOM);
+                        }
+                    }
                 }
-            });
+            }
         });
     }
 }
@@ -0,0 +1,177 @@
+use super::{View, Window};
+
+const DEFAULT_WIDTH: f32 = 360.0;
+const REPORT_HEIGHT: f32 = 240.0;
+
+pub struct NavmeshItem {
+    pub form_id: u32,
+    pub vertices: usize,
+    pub triangles: usize,
+}
+
+pub struct NavmeshState {
+    /// The navmeshes of the loaded cell.
+    pub navmeshes: Vec<NavmeshItem>,
+    pub show_overlay: bool,
+    /// The navmesh being edited, which selections belong to.
+    pub selected_navmesh: Option<u32>,
+    /// Selected vertices in the order they were clicked, which sets the winding of a created triangle.
+    pub selected_vertices: Vec<u16>,
+    pub selected_triangles: Vec<usize>,
+    /// The position of the only selected vertex, for editing by hand.
+    pub vertex_position: Option<[f32; 3]>,
+    /// Lines of the last validation report.
+    pub report: Vec<String>,
+}
+
+impl Default for NavmeshState {
+    fn default() -> Self {
+        Self {
+            navmeshes: vec![],
+            show_overlay: true,
+            selected_navmesh: None,
+            selected_vertices: vec![],
+            selected_triangles: vec![],
+            vertex_position: None,
+            report: vec![],
+        }
+    }
+}
+
+impl NavmeshState {
+    pub fn clear_selection(&mut self) {
+        self.selected_vertices.clear();
+        self.selected_triangles.clear();
+        self.vertex_position = None;
+    }
+}
+
+pub enum NavmeshAction {
+    SelectNavmesh(u32),
+    MoveVertex(u16, [f32; 3]),
+    DeleteTriangles,
+    CreateTriangle,
+    Validate,
+}
+
+pub struct NavmeshWindow<'a> {
+    state: &'a mut NavmeshState,
+    actions: Vec<NavmeshAction>,
+}
+
+impl<'a> NavmeshWindow<'a> {
+    pub fn new(state: &'a mut NavmeshState) -> Self {
+        Self { state, actions: vec![] }
+    }
+
+    pub fn actions(self) -> Vec<NavmeshAction> {
+        self.actions
+    }
+}
+
+impl<'a> View for NavmeshWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let state = &mut *self.state;
+        let actions = &mut self.actions;
+
+        ui.label(
+            "Click vertices or triangles in the viewport to select them, holding Shift to add to the selection. \
+             Drag a vertex to move it. Close this window to select objects again.",
+        );
+        ui.checkbox(&mut state.show_overlay, "Show navmesh overlay");
+        ui.separator();
+
+        if state.navmeshes.is_empty() {
+            ui.label("The loaded cell has no navmeshes.");
+            return;
+        }
+
+        for navmesh in &state.navmeshes {
+            let label = format!(
+                "{:08X}  {} vertices, {} triangles",
+                navmesh.form_id, navmesh.vertices, navmesh.triangles
+            );
+
+            if ui
+                .selectable_label(state.selected_navmesh == Some(navmesh.form_id), label)
+                .clicked()
+            {
+                actions.push(NavmeshAction::SelectNavmesh(navmesh.form_id));
+            }
+        }
+
+        ui.separator();
+
+        ui.label(format!(
+            "{} vertices and {} triangles selected",
+            state.selected_vertices.len(),
+            state.selected_triangles.len()
+        ));
+
+        if let (Some(position), [vertex]) = (&mut state.vertex_position, &state.selected_vertices[..]) {
+            let mut changed = false;
+
+            ui.horizontal(|ui| {
+                ui.label(format!("Vertex {}", vertex));
+
+                for value in position.iter_mut() {
+                    changed |= ui.add(egui::DragValue::new(value).speed(1.0)).changed();
+                }
+            });
+
+            if changed {
+                actions.push(NavmeshAction::MoveVertex(*vertex, *position));
+            }
+        }
+
+        ui.horizontal(|ui| {
+            let can_create = state.selected_vertices.len() == 3;
+            let can_delete = !state.selected_triangles.is_empty();
+
+            if ui
+                .add(egui::Button::new("Create triangle").enabled(can_create))
+                .clicked()
+            {
+                actions.push(NavmeshAction::CreateTriangle);
+            }
+
+            if ui
+                .add(egui::Button::new("Delete triangles (Del)").enabled(can_delete))
+                .clicked()
+            {
+                actions.push(NavmeshAction::DeleteTriangles);
+            }
+
+            if ui.button("Validate").clicked() {
+                actions.push(NavmeshAction::Validate);
+            }
+        });
+
+        if state.report.is_empty() {
+            return;
+        }
+
+        ui.separator();
+
+        egui::ScrollArea::from_max_height(REPORT_HEIGHT)
+            .id_source("navmesh_report")
+            .show(ui, |ui| {
+                for line in &state.report {
+                    ui.label(line);
+                }
+            });
+    }
+}
+
+impl<'a> Window for NavmeshWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Navmesh"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(DEFAULT_WIDTH)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
@@ -0,0 +1,715 @@
+//! A tabbed editor for NPC_ records. Parts of an NPC its template flags inherit are shown as resolved through
+//! the template, and are edited on the template instead.
+
+use super::{
+    widgets::{flag_checkbox, form_id_edit, text_edit, EditorIds, RecordCodes},
+    View, Window,
+};
+
+use open_creation_data::esp::{
+    code_name,
+    npc::{
+        AGGRESSION_NAMES, ASSISTANCE_NAMES, CONFIDENCE_NAMES, MOOD_NAMES, MORALITY_NAMES, SKILL_NAMES,
+        TEMPLATE_FLAG_NAMES,
+    },
+    npc_flags, template_flags, InventoryItem, Npc, NpcFaction, NpcPerk, TemplateSource,
+};
+
+use std::hash::Hash;
+
+const DEFAULT_WIDTH: f32 = 520.0;
+const LIST_HEIGHT: f32 = 320.0;
+const INHERITED_COLOR: egui::Color32 = egui::Color32::from_rgb(120, 160, 220);
+
+/// Reads an NPC by form ID, for following templates. Gives `None` if the form ID is not an NPC.
+pub type Npcs<'a> = &'a dyn Fn(u32) -> Option<Npc>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NpcTab {
+    Traits,
+    Stats,
+    Factions,
+    Inventory,
+    Spells,
+    AiData,
+    Packages,
+}
+
+impl NpcTab {
+    /// The template flag that makes the NPC inherit what the tab shows.
+    fn template_flag(self) -> u16 {
+        match self {
+            NpcTab::Traits => template_flags::USE_TRAITS,
+            NpcTab::Stats => template_flags::USE_STATS,
+            NpcTab::Factions => template_flags::USE_FACTIONS,
+            NpcTab::Inventory => template_flags::USE_INVENTORY,
+            NpcTab::Spells => template_flags::USE_SPELL_LIST,
+            NpcTab::AiData => template_flags::USE_AI_DATA,
+            NpcTab::Packages => template_flags::USE_AI_PACKAGES,
+        }
+    }
+}
+
+pub struct NpcState {
+    /// The NPC being edited, as it will be written to the active plugin.
+    pub npc: Option<Npc>,
+    pub tab: NpcTab,
+}
+
+impl Default for NpcState {
+    fn default() -> Self {
+        Self {
+            npc: None,
+            tab: NpcTab::Traits,
+        }
+    }
+}
+
+impl NpcState {
+    /// Starts editing another NPC.
+    pub fn open(&mut self, npc: Npc) {
+        self.npc = Some(npc);
+    }
+}
+
+pub struct NpcWindow<'a> {
+    state: &'a mut NpcState,
+    editor_ids: EditorIds<'a>,
+    record_codes: RecordCodes<'a>,
+    npcs: Npcs<'a>,
+    changed: bool,
+}
+
+impl<'a> NpcWindow<'a> {
+    pub fn new(
+        state: &'a mut NpcState,
+        editor_ids: EditorIds<'a>,
+        record_codes: RecordCodes<'a>,
+        npcs: Npcs<'a>,
+    ) -> Self {
+        Self {
+            state,
+            editor_ids,
+            record_codes,
+            npcs,
+            changed: false,
+        }
+    }
+
+    /// Whether the NPC was edited and should be saved.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+/// A form ID by editor ID and form ID, for showing it read-only.
+fn form_name(form_id: u32, editor_ids: EditorIds) -> String {
+    match form_id {
+        0 => "None".to_string(),
+        form_id => format!(
+            "{}  [{:08X}]",
+            editor_ids(form_id).unwrap_or_else(|| "(not found)".to_string()),
+            form_id
+        ),
+    }
+}
+
+/// An optional form ID, which is dropped when set to 0. Returns whether it was changed.
+fn optional_form_id_edit(ui: &mut egui::Ui, form_id: &mut Option<u32>, editor_ids: EditorIds) -> bool {
+    let mut value = form_id.unwrap_or(0);
+
+    if !form_id_edit(ui, &mut value, editor_ids) {
+        return false;
+    }
+
+    *form_id = Some(value).filter(|&value| value != 0);
+    true
+}
+
+fn code_label(ui: &mut egui::Ui, form_id: u32, record_codes: RecordCodes) {
+    match record_codes(form_id) {
+        Some(code) => ui.label(format!("[{}]", code_name(code))),
+        None => ui.label(""),
+    };
+}
+
+/// A list of form IDs, such as spells or packages, with buttons to add and remove them. Returns whether it was
+/// changed.
+fn form_id_list(
+    ui: &mut egui::Ui,
+    id_source: impl Hash,
+    form_ids: &mut Vec<u32>,
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+
+    egui::Grid::new(id_source).show(ui, |ui| {
+        for (index, form_id) in form_ids.iter_mut().enumerate() {
+            changed |= form_id_edit(ui, form_id, editor_ids);
+            code_label(ui, *form_id, record_codes);
+
+            if ui.small_button("Remove").clicked() {
+                removed = Some(index);
+            }
+            ui.end_row();
+        }
+    });
+
+    if let Some(index) = removed {
+        form_ids.remove(index);
+        changed = true;
+    }
+
+    if ui.button("Add").clicked() {
+        form_ids.push(0);
+        changed = true;
+    }
+
+    changed
+}
+
+/// Picks an index into a table of names. Returns whether it was changed.
+fn name_combo(ui: &mut egui::Ui, id_source: &str, value: &mut u8, names: &[&str]) -> bool {
+    let before = *value;
+    let selected = names
+        .get(*value as usize)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| value.to_string());
+
+    egui::combo_box(ui, egui::Id::new(id_source), selected, |ui| {
+        for (index, name) in names.iter().enumerate() {
+            ui.selectable_value(value, index as u8, *name);
+        }
+    });
+
+    *value != before
+}
+
+/// What the NPC does not inherit whatever its template flags: its IDs, flags and template.
+fn base_ui(ui: &mut egui::Ui, npc: &mut Npc, editor_ids: EditorIds, record_codes: RecordCodes) -> bool {
+    let mut changed = false;
+
+    egui::Grid::new("npc_base_grid").show(ui, |ui| {
+        ui.label("Editor ID");
+        changed |= ui.text_edit_singleline(&mut npc.editor_id).changed();
+        ui.end_row();
+
+        ui.label("Name");
+        changed |= text_edit(ui, &mut npc.name, false);
+        ui.end_row();
+
+        ui.label("Template");
+        ui.horizontal(|ui| {
+            changed |= optional_form_id_edit(ui, &mut npc.template, editor_ids);
+            if let Some(template) = npc.template {
+                code_label(ui, template, record_codes);
+            }
+        });
+        ui.end_row();
+    });
+
+    ui.label("Inherit from the template:");
+    ui.horizontal_wrapped(|ui| {
+        for (flag, name) in TEMPLATE_FLAG_NAMES.iter() {
+            changed |= flag_checkbox(ui, &mut npc.template_flags, *flag, name);
+        }
+    });
+
+    ui.separator();
+    ui.horizontal_wrapped(|ui| {
+        let flags = &mut npc.flags;
+        changed |= flag_checkbox(ui, flags, npc_flags::ESSENTIAL, "Essential");
+        changed |= flag_checkbox(ui, flags, npc_flags::PROTECTED, "Protected");
+        changed |= flag_checkbox(ui, flags, npc_flags::UNIQUE, "Unique");
+        changed |= flag_checkbox(ui, flags, npc_flags::RESPAWN, "Respawn");
+        changed |= flag_checkbox(ui, flags, npc_flags::SUMMONABLE, "Summonable");
+        changed |= flag_checkbox(ui, flags, npc_flags::SIMPLE_ACTOR, "Simple actor");
+        changed |= flag_checkbox(ui, flags, npc_flags::IS_GHOST, "Is ghost");
+        changed |= flag_checkbox(ui, flags, npc_flags::INVULNERABLE, "Invulnerable");
+        changed |= flag_checkbox(ui, flags, npc_flags::DOES_NOT_BLEED, "Doesn't bleed");
+    });
+
+    changed
+}
+
+fn traits_ui(ui: &mut egui::Ui, npc: &mut Npc, editor_ids: EditorIds) -> bool {
+    let mut changed = false;
+
+    egui::Grid::new("npc_traits_grid").show(ui, |ui| {
+        ui.label("Race");
+        changed |= form_id_edit(ui, &mut npc.race, editor_ids);
+        ui.end_row();
+
+        ui.label("Sex");
+        changed |= flag_checkbox(ui, &mut npc.flags, npc_flags::FEMALE, "Female");
+        ui.end_row();
+
+        ui.label("Voice type");
+        changed |= optional_form_id_edit(ui, &mut npc.voice, editor_ids);
+        ui.end_row();
+
+        for (label, value) in [("Height", &mut npc.height), ("Weight", &mut npc.weight)].iter_mut() {
+            ui.label(*label);
+            let mut number = value.unwrap_or(1.0);
+            if ui.add(egui::DragValue::new(&mut number).speed(0.01)).changed() {
+                **value = Some(number);
+                changed = true;
+            }
+            ui.end_row();
+        }
+    });
+
+    changed
+}
+
+fn stats_ui(ui: &mut egui::Ui, npc: &mut Npc, editor_ids: EditorIds) -> bool {
+    let mut changed = false;
+
+    egui::Grid::new("npc_stats_grid").show(ui, |ui| {
+        ui.label("Class");
+        changed |= form_id_edit(ui, &mut npc.class, editor_ids);
+        ui.end_row();
+
+        ui.label("Level");
+        ui.horizontal(|ui| {
+            if npc.flags & npc_flags::PC_LEVEL_MULT != 0 {
+                let mut multiplier = npc.level as f32 / 1000.0;
+                if ui
+                    .add(egui::DragValue::new(&mut multiplier).speed(0.01).suffix(" x"))
+                    .changed()
+                {
+                    npc.level = (multiplier.max(0.0) * 1000.0).round().min(u16::MAX as f32) as u16;
+                    changed = true;
+                }
+            } else {
+                changed |= ui.add(egui::DragValue::new(&mut npc.level)).changed();
+            }
+            changed |= flag_checkbox(ui, &mut npc.flags, npc_flags::PC_LEVEL_MULT, "PC level mult");
+        });
+        ui.end_row();
+
+        ui.label("Calc min/max level");
+        ui.horizontal(|ui| {
+            changed |= ui.add(egui::DragValue::new(&mut npc.calc_min_level)).changed();
+            changed |= ui.add(egui::DragValue::new(&mut npc.calc_max_level)).changed();
+        });
+        ui.end_row();
+
+        ui.label("");
+        changed |= flag_checkbox(ui, &mut npc.flags, npc_flags::AUTO_CALC_STATS, "Auto calc stats");
+        ui.end_row();
+
+        ui.label("Speed multiplier");
+        changed |= ui.add(egui::DragValue::new(&mut npc.speed_multiplier)).changed();
+        ui.end_row();
+
+        ui.label("Health / magicka / stamina offsets");
+        ui.horizontal(|ui| {
+            changed |= ui.add(egui::DragValue::new(&mut npc.health_offset)).changed();
+            changed |= ui.add(egui::DragValue::new(&mut npc.magicka_offset)).changed();
+            changed |= ui.add(egui::DragValue::new(&mut npc.stamina_offset)).changed();
+        });
+        ui.end_row();
+
+        ui.label("Health / magicka / stamina");
+        ui.horizontal(|ui| {
+            changed |= ui.add(egui::DragValue::new(&mut npc.stats.health)).changed();
+            changed |= ui.add(egui::DragValue::new(&mut npc.stats.magicka)).changed();
+            changed |= ui.add(egui::DragValue::new(&mut npc.stats.stamina)).changed();
+        });
+        ui.end_row();
+    });
+
+    ui.separator();
+
+    egui::Grid::new("npc_skills_grid").show(ui, |ui| {
+        ui.label("Skill");
+        ui.label("Value");
+        ui.label("Offset");
+        ui.end_row();
+
+        let stats = &mut npc.stats;
+        for (index, name) in SKILL_NAMES.iter().enumerate() {
+            ui.label(*name);
+            changed |= ui.add(egui::DragValue::new(&mut stats.skills[index])).changed();
+            changed |= ui.add(egui::DragValue::new(&mut stats.skill_offsets[index])).changed();
+            ui.end_row();
+        }
+    });
+
+    changed
+}
+
+fn factions_ui(ui: &mut egui::Ui, npc: &mut Npc, editor_ids: EditorIds, record_codes: RecordCodes) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+
+    egui::Grid::new("npc_factions_grid").show(ui, |ui| {
+        ui.label("Faction");
+        ui.label("");
+        ui.label("Rank");
+        ui.end_row();
+
+        for (index, faction) in npc.factions.iter_mut().enumerate() {
+            changed |= form_id_edit(ui, &mut faction.faction, editor_ids);
+            code_label(ui, faction.faction, record_codes);
+            changed |= ui.add(egui::DragValue::new(&mut faction.rank)).changed();
+
+            if ui.small_button("Remove").clicked() {
+                removed = Some(index);
+            }
+            ui.end_row();
+        }
+    });
+
+    if let Some(index) = removed {
+        npc.factions.remove(index);
+        changed = true;
+    }
+
+    if ui.button("Add").clicked() {
+        npc.factions.push(NpcFaction::default());
+        changed = true;
+    }
+
+    changed
+}
+
+fn inventory_ui(ui: &mut egui::Ui, npc: &mut Npc, editor_ids: EditorIds, record_codes: RecordCodes) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+
+    ui.horizontal(|ui| {
+        ui.label("Default outfit");
+        changed |= optional_form_id_edit(ui, &mut npc.outfit, editor_ids);
+    });
+
+    egui::Grid::new("npc_inventory_grid").show(ui, |ui| {
+        ui.label("Item");
+        ui.label("");
+        ui.label("Count");
+        ui.end_row();
+
+        for (index, item) in npc.inventory.iter_mut().enumerate() {
+            changed |= form_id_edit(ui, &mut item.item, editor_ids);
+            code_label(ui, item.item, record_codes);
+            changed |= ui.add(egui::DragValue::new(&mut item.count)).changed();
+
+            if ui.small_button("Remove").clicked() {
+                removed = Some(index);
+            }
+            ui.end_row();
+        }
+    });
+
+    if let Some(index) = removed {
+        npc.inventory.remove(index);
+        changed = true;
+    }
+
+    if ui.button("Add").clicked() {
+        npc.inventory.push(InventoryItem {
+            count: 1,
+            ..InventoryItem::default()
+        });
+        changed = true;
+    }
+
+    changed
+}
+
+fn spells_ui(ui: &mut egui::Ui, npc: &mut Npc, editor_ids: EditorIds, record_codes: RecordCodes) -> bool {
+    ui.label("Spells");
+    let mut changed = form_id_list(ui, "npc_spells_grid", &mut npc.spells, editor_ids, record_codes);
+
+    ui.separator();
+    ui.label("Perks");
+
+    let mut removed = None;
+
+    egui::Grid::new("npc_perks_grid").show(ui, |ui| {
+        for (index, perk) in npc.perks.iter_mut().enumerate() {
+            changed |= form_id_edit(ui, &mut perk.perk, editor_ids);
+            ui.label("Rank");
+            changed |= ui.add(egui::DragValue::new(&mut perk.rank)).changed();
+
+            if ui.small_button("Remove").clicked() {
+                removed = Some(index);
+            }
+            ui.end_row();
+        }
+    });
+
+    if let Some(index) = removed {
+        npc.perks.remove(index);
+        changed = true;
+    }
+
+    if ui.button("Add perk").clicked() {
+        npc.perks.push(NpcPerk {
+            rank: 1,
+            ..NpcPerk::default()
+        });
+        changed = true;
+    }
+
+    changed
+}
+
+fn ai_data_ui(ui: &mut egui::Ui, npc: &mut Npc, editor_ids: EditorIds) -> bool {
+    let mut changed = false;
+    let ai = &mut npc.ai_data;
+
+    egui::Grid::new("npc_ai_data_grid").show(ui, |ui| {
+        ui.label("Aggression");
+        changed |= name_combo(ui, "npc_aggression", &mut ai.aggression, &AGGRESSION_NAMES);
+        ui.end_row();
+
+        ui.label("Confidence");
+        changed |= name_combo(ui, "npc_confidence", &mut ai.confidence, &CONFIDENCE_NAMES);
+        ui.end_row();
+
+        ui.label("Morality");
+        changed |= name_combo(ui, "npc_morality", &mut ai.morality, &MORALITY_NAMES);
+        ui.end_row();
+
+        ui.label("Mood");
+        changed |= name_combo(ui, "npc_mood", &mut ai.mood, &MOOD_NAMES);
+        ui.end_row();
+
+        ui.label("Assistance");
+        changed |= name_combo(ui, "npc_assistance", &mut ai.assistance, &ASSISTANCE_NAMES);
+        ui.end_row();
+
+        ui.label("Energy");
+        changed |= ui.add(egui::DragValue::new(&mut ai.energy)).changed();
+        ui.end_row();
+
+        ui.label("");
+        changed |= ui
+            .checkbox(&mut ai.aggro_radius_behavior, "Aggro radius behavior")
+            .changed();
+        ui.end_row();
+
+        ui.label("Warn / warn or attack / attack");
+        ui.horizontal(|ui| {
+            changed |= ui.add(egui::DragValue::new(&mut ai.warn)).changed();
+            changed |= ui.add(egui::DragValue::new(&mut ai.warn_or_attack)).changed();
+            changed |= ui.add(egui::DragValue::new(&mut ai.attack)).changed();
+        });
+        ui.end_row();
+
+        ui.label("Combat style");
+        changed |= optional_form_id_edit(ui, &mut npc.combat_style, editor_ids);
+        ui.end_row();
+    });
+
+    changed
+}
+
+fn packages_ui(ui: &mut egui::Ui, npc: &mut Npc, editor_ids: EditorIds, record_codes: RecordCodes) -> bool {
+    form_id_list(ui, "npc_packages_grid", &mut npc.packages, editor_ids, record_codes)
+}
+
+/// What a tab shows of the NPC, as labels and values, for showing inherited values read-only.
+fn inherited_rows(npc: &Npc, tab: NpcTab, editor_ids: EditorIds) -> Vec<(String, String)> {
+    let name = |form_id: u32| form_name(form_id, editor_ids);
+    let optional = |form_id: Option<u32>| name(form_id.unwrap_or(0));
+    let row = |label: &str, value: String| (label.to_string(), value);
+
+    match tab {
+        NpcTab::Traits => vec![
+            row("Race", name(npc.race)),
+            row(
+                "Sex",
+                if npc.flags & npc_flags::FEMALE != 0 {
+                    "Female"
+                } else {
+                    "Male"
+                }
+                .to_string(),
+            ),
+            row("Voice type", optional(npc.voice)),
+            row("Height", format!("{:.2}", npc.height.unwrap_or(1.0))),
+            row("Weight", format!("{:.2}", npc.weight.unwrap_or(1.0))),
+        ],
+        NpcTab::Stats => {
+            let mut rows = vec![
+                row("Class", name(npc.class)),
+                row("Level", npc.level_text()),
+                row(
+                    "Health / magicka / stamina offsets",
+                    format!(
+                        "{} / {} / {}",
+                        npc.health_offset, npc.magicka_offset, npc.stamina_offset
+                    ),
+                ),
+            ];
+            rows.extend(SKILL_NAMES.iter().enumerate().map(|(index, skill)| {
+                let value = npc.stats.skills[index];
+                let offset = npc.stats.skill_offsets[index];
+                row(*skill, format!("{} (+{})", value, offset))
+            }));
+            rows
+        }
+        NpcTab::Factions => npc
+            .factions
+            .iter()
+            .map(|faction| row("Faction", format!("{}  rank {}", name(faction.faction), faction.rank)))
+            .collect(),
+        NpcTab::Inventory => {
+            let mut rows = vec![row("Default outfit", optional(npc.outfit))];
+            rows.extend(
+                npc.inventory
+                    .iter()
+                    .map(|item| row("Item", format!("{}  x{}", name(item.item), item.count))),
+            );
+            rows
+        }
+        NpcTab::Spells => {
+            let spells = npc.spells.iter().map(|&spell| row("Spell", name(spell)));
+            let perks = npc
+                .perks
+                .iter()
+                .map(|perk| row("Perk", format!("{}  rank {}", name(perk.perk), perk.rank)));
+            spells.chain(perks).collect()
+        }
+        NpcTab::AiData => {
+            let ai = &npc.ai_data;
+            let named = |names: &[&str], value: u8| {
+                names
+                    .get(value as usize)
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| value.to_string())
+            };
+            vec![
+                row("Aggression", named(&AGGRESSION_NAMES, ai.aggression)),
+                row("Confidence", named(&CONFIDENCE_NAMES, ai.confidence)),
+                row("Morality", named(&MORALITY_NAMES, ai.morality)),
+                row("Mood", named(&MOOD_NAMES, ai.mood)),
+                row("Assistance", named(&ASSISTANCE_NAMES, ai.assistance)),
+                row("Energy", ai.energy.to_string()),
+                row("Combat style", optional(npc.combat_style)),
+            ]
+        }
+        NpcTab::Packages => npc
+            .packages
+            .iter()
+            .map(|&package| row("Package", name(package)))
+            .collect(),
+    }
+}
+
+/// Shows where an inherited tab's values come from, and the values if they can be known. Leveled lists have an
+/// NPC picked from them in game.
+fn inherited_ui(
+    ui: &mut egui::Ui,
+    source: &TemplateSource,
+    tab: NpcTab,
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+) {
+    match source {
+        TemplateSource::Own => {}
+        TemplateSource::Npc(template) => {
+            ui.colored_label(
+                INHERITED_COLOR,
+                format!("Inherited from {}", form_name(template.form_id, editor_ids)),
+            );
+            ui.separator();
+
+            egui::Grid::new("npc_inherited_grid").show(ui, |ui| {
+                for (label, value) in inherited_rows(template, tab, editor_ids) {
+                    ui.label(label);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+        }
+        TemplateSource::Other(form_id) => {
+            let code = record_codes(*form_id).map_or_else(|| "not found".to_string(), code_name);
+            ui.colored_label(
+                INHERITED_COLOR,
+                format!(
+                    "Inherited from {} [{}], which is resolved in game",
+                    form_name(*form_id, editor_ids),
+                    code
+                ),
+            );
+        }
+    }
+}
+
+impl<'a> View for NpcWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let NpcState { npc, tab } = &mut *self.state;
+        let editor_ids = self.editor_ids;
+        let record_codes = self.record_codes;
+        let npcs = self.npcs;
+
+        let npc = match npc {
+            Some(npc) => npc,
+            None => {
+                ui.label("Select an NPC in the tree view to edit it.");
+                return;
+            }
+        };
+
+        ui.label(format!("{}  [{:08X}]", npc.editor_id, npc.form_id));
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(tab, NpcTab::Traits, "Traits");
+            ui.selectable_value(tab, NpcTab::Stats, "Stats");
+            ui.selectable_value(tab, NpcTab::Factions, "Factions");
+            ui.selectable_value(tab, NpcTab::Inventory, "Inventory");
+            ui.selectable_value(tab, NpcTab::Spells, "Spells");
+            ui.selectable_value(tab, NpcTab::AiData, "AI Data");
+            ui.selectable_value(tab, NpcTab::Packages, "Packages");
+        });
+
+        ui.separator();
+
+        let tab = *tab;
+        let source = npc.template_source(tab.template_flag(), npcs);
+        let mut changed = false;
+
+        egui::ScrollArea::from_max_height(LIST_HEIGHT).show(ui, |ui| {
+            if tab == NpcTab::Traits {
+                changed |= base_ui(ui, npc, editor_ids, record_codes);
+                ui.separator();
+            }
+
+            if source != TemplateSource::Own {
+                inherited_ui(ui, &source, tab, editor_ids, record_codes);
+                return;
+            }
+
+            changed |= match tab {
+                NpcTab::Traits => traits_ui(ui, npc, editor_ids),
+                NpcTab::Stats => stats_ui(ui, npc, editor_ids),
+                NpcTab::Factions => factions_ui(ui, npc, editor_ids, record_codes),
+                NpcTab::Inventory => inventory_ui(ui, npc, editor_ids, record_codes),
+                NpcTab::Spells => spells_ui(ui, npc, editor_ids, record_codes),
+                NpcTab::AiData => ai_data_ui(ui, npc, editor_ids),
+                NpcTab::Packages => packages_ui(ui, npc, editor_ids, record_codes),
+            };
+        });
+
+        self.changed |= changed;
+    }
+}
+
+impl<'a> Window for NpcWindow<'a> {
+    fn name(&self) -> &'static str {
+        "NPC"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(DEFAULT_WIDTH)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
@@ -0,0 +1,244 @@
+//! An editor for Papyrus sources. egui's text editor cannot colour its text, so the source is edited plainly and
+//! read with its syntax highlighted.
+
+use super::{View, Window};
+
+use open_creation_data::papyrus::{self, TokenKind};
+
+const DEFAULT_WIDTH: f32 = 900.0;
+const DEFAULT_HEIGHT: f32 = 560.0;
+const LIST_WIDTH: f32 = 200.0;
+const MAX_FILTER_RESULTS: usize = 1000;
+const TAB_WIDTH: usize = 4;
+const LINE_NUMBER_COLOR: egui::Color32 = egui::Color32::from_rgb(120, 120, 120);
+const MARKED_LINE_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 80, 80);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PapyrusTab {
+    Highlighted,
+    Edit,
+}
+
+pub struct PapyrusState {
+    pub filter: String,
+    /// The name of the script whose source is open, in lowercase.
+    pub script: Option<String>,
+    pub source: String,
+    /// Whether the source has been edited since it was opened or saved.
+    pub modified: bool,
+    pub tab: PapyrusTab,
+    /// A line to scroll to once the source is next shown, counted from 1.
+    pub scroll_to_line: Option<usize>,
+    /// A line a diagnostic was reported on, counted from 1.
+    pub marked_line: Option<usize>,
+}
+
+impl Default for PapyrusState {
+    fn default() -> Self {
+        Self {
+            filter: String::new(),
+            script: None,
+            source: String::new(),
+            modified: false,
+            tab: PapyrusTab::Highlighted,
+            scroll_to_line: None,
+            marked_line: None,
+        }
+    }
+}
+
+impl PapyrusState {
+    /// Shows another source, or the same one reread.
+    pub fn open(&mut self, script: String, source: String) {
+        self.script = Some(script);
+        self.source = source;
+        self.modified = false;
+        self.scroll_to_line = None;
+        self.marked_line = None;
+    }
+
+    /// Shows a line of the open source highlighted, as for a diagnostic.
+    pub fn go_to_line(&mut self, line: usize) {
+        self.tab = PapyrusTab::Highlighted;
+        self.scroll_to_line = Some(line);
+        self.marked_line = Some(line);
+    }
+}
+
+pub enum PapyrusAction {
+    /// Open a source by script name.
+    Open(String),
+    Save,
+    /// Save the source and run the compiler on it.
+    Compile,
+}
+
+pub struct PapyrusWindow<'a> {
+    sources: &'a [String],
+    state: &'a mut PapyrusState,
+    actions: Vec<PapyrusAction>,
+}
+
+impl<'a> PapyrusWindow<'a> {
+    pub fn new(sources: &'a [String], state: &'a mut PapyrusState) -> Self {
+        Self {
+            sources,
+            state,
+            actions: vec![],
+        }
+    }
+
+    pub fn actions(self) -> Vec<PapyrusAction> {
+        self.actions
+    }
+}
+
+fn token_color(kind: TokenKind) -> Option<egui::Color32> {
+    match kind {
+        TokenKind::Keyword => Some(egui::Color32::from_rgb(86, 156, 214)),
+        TokenKind::Type => Some(egui::Color32::from_rgb(78, 201, 176)),
+        TokenKind::Number => Some(egui::Color32::from_rgb(181, 206, 168)),
+        TokenKind::String => Some(egui::Color32::from_rgb(206, 145, 120)),
+        TokenKind::Comment => Some(egui::Color32::from_rgb(106, 153, 85)),
+        TokenKind::Identifier | TokenKind::Symbol | TokenKind::Whitespace => None,
+    }
+}
+
+/// Shows the source line by line, with line numbers and its tokens coloured by kind.
+fn highlighted_ui(ui: &mut egui::Ui, source: &str, scroll_to_line: &mut Option<usize>, marked_line: Option<usize>) {
+    ui.style_mut().spacing.item_spacing = egui::vec2(0.0, 0.0);
+
+    for (index, tokens) in papyrus::highlight(source).iter().enumerate() {
+        let number = index + 1;
+
+        ui.horizontal(|ui| {
+            let color = if marked_line == Some(number) {
+                MARKED_LINE_COLOR
+            } else {
+                LINE_NUMBER_COLOR
+            };
+            let response = ui.add(
+                egui::Label::new(format!("{:>5}  ", number))
+                    .text_style(egui::TextStyle::Monospace)
+                    .text_color(color),
+            );
+
+            if *scroll_to_line == Some(number) {
+                response.scroll_to_me(egui::Align::Center);
+                *scroll_to_line = None;
+            }
+
+            for token in tokens {
+                let text = token.text.replace('\t', &" ".repeat(TAB_WIDTH));
+                let mut label = egui::Label::new(text).text_style(egui::TextStyle::Monospace);
+
+                if let Some(color) = token_color(token.kind) {
+                    label = label.text_color(color);
+                }
+
+                ui.add(label);
+            }
+        });
+    }
+}
+
+impl<'a> View for PapyrusWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let sources = self.sources;
+        let state = &mut *self.state;
+        let actions = &mut self.actions;
+
+        ui.horizontal(|ui| {
+            ui.vertical(|ui| {
+                ui.set_max_width(LIST_WIDTH);
+                ui.label(format!("Sources ({})", sources.len()));
+                ui.add(egui::TextEdit::singleline(&mut state.filter).hint_text("Filter"));
+                ui.separator();
+
+                egui::ScrollArea::from_max_height(DEFAULT_HEIGHT)
+                    .id_source("papyrus_sources")
+                    .show(ui, |ui| {
+                        let filter = state.filter.to_lowercase();
+
+                        for name in sources
+                            .iter()
+                            .filter(|name| name.contains(&filter))
+                            .take(MAX_FILTER_RESULTS)
+                        {
+                            let selected = state.script.as_deref() == Some(name.as_str());
+                            if ui.selectable_label(selected, name).clicked() && !selected {
+                                actions.push(PapyrusAction::Open(name.clone()));
+                            }
+                        }
+                    });
+            });
+
+            ui.separator();
+
+            ui.vertical(|ui| {
+                let script = match &state.script {
+                    Some(script) => script.clone(),
+                    None => {
+                        ui.label("Select a source to edit it.");
+                        return;
+                    }
+                };
+
+                ui.horizontal(|ui| {
+                    let modified = if state.modified { " (modified)" } else { "" };
+                    ui.label(format!("{}.psc{}", script, modified));
+
+                    if ui.button("Save").clicked() {
+                        actions.push(PapyrusAction::Save);
+                    }
+
+                    if ui.button("Compile").clicked() {
+                        actions.push(PapyrusAction::Compile);
+                    }
+
+                    if ui.button("Revert").clicked() {
+                        actions.push(PapyrusAction::Open(script.clone()));
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut state.tab, PapyrusTab::Highlighted, "Highlighted");
+                    ui.selectable_value(&mut state.tab, PapyrusTab::Edit, "Edit");
+                });
+                ui.separator();
+
+                egui::ScrollArea::from_max_height(DEFAULT_HEIGHT)
+                    .id_source("papyrus_source")
+                    .show(ui, |ui| match state.tab {
+                        PapyrusTab::Highlighted => {
+                            highlighted_ui(ui, &state.source, &mut state.scroll_to_line, state.marked_line)
+                        }
+                        PapyrusTab::Edit => {
+                            let text = egui::TextEdit::multiline(&mut state.source)
+                                .text_style(egui::TextStyle::Monospace)
+                                .desired_width(f32::INFINITY);
+
+                            if ui.add(text).changed() {
+                                state.modified = true;
+                                state.marked_line = None;
+                            }
+                        }
+                    });
+            });
+        });
+    }
+}
+
+impl<'a> Window for PapyrusWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Papyrus Editor"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_size(egui::vec2(DEFAULT_WIDTH, DEFAULT_HEIGHT))
+            .scroll(false)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
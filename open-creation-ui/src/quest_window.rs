@@ -0,0 +1,777 @@
+use super::{
+    condition_list::condition_list,
+    script_list::{script_list, ScriptLookup},
+    widgets::{flag_checkbox, form_id_edit, text_edit, EditorIds, RecordCodes},
+    View, Window,
+};
+
+use open_creation_data::esp::{
+    quest::{alias_flags, log_entry_flags, objective_flags, quest_flags, stage_flags, target_flags, QUEST_TYPE_NAMES},
+    Alias, AliasFill, LogEntry, Objective, ObjectiveTarget, Quest, Stage,
+};
+
+const DEFAULT_WIDTH: f32 = 480.0;
+const LIST_HEIGHT: f32 = 140.0;
+const CREATE_LEVEL_NAMES: [&str; 5] = ["Easy", "Medium", "Hard", "Very Hard", "None"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuestTab {
+    Data,
+    Stages,
+    Objectives,
+    Aliases,
+    Scripts,
+}
+
+pub struct QuestState {
+    /// The quest being edited, as it will be written to the active plugin.
+    pub quest: Option<Quest>,
+    pub tab: QuestTab,
+    pub selected_stage: Option<usize>,
+    pub selected_objective: Option<usize>,
+    pub selected_alias: Option<usize>,
+}
+
+impl Default for QuestState {
+    fn default() -> Self {
+        Self {
+            quest: None,
+            tab: QuestTab::Data,
+            selected_stage: None,
+            selected_objective: None,
+            selected_alias: None,
+        }
+    }
+}
+
+impl QuestState {
+    /// Starts editing another quest.
+    pub fn open(&mut self, quest: Quest) {
+        self.quest = Some(quest);
+        self.selected_stage = None;
+        self.selected_objective = None;
+        self.selected_alias = None;
+    }
+}
+
+pub struct QuestWindow<'a> {
+    state: &'a mut QuestState,
+    editor_ids: EditorIds<'a>,
+    record_codes: RecordCodes<'a>,
+    scripts: ScriptLookup<'a>,
+    changed: bool,
+}
+
+impl<'a> QuestWindow<'a> {
+    pub fn new(
+        state: &'a mut QuestState,
+        editor_ids: EditorIds<'a>,
+        record_codes: RecordCodes<'a>,
+        scripts: ScriptLookup<'a>,
+    ) -> Self {
+        Self {
+            state,
+            editor_ids,
+            record_codes,
+            scripts,
+            changed: false,
+        }
+    }
+
+    /// Whether the quest was edited and should be saved.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+/// The quest's aliases by ID, for picking one.
+fn alias_names(quest: &Quest) -> Vec<(u32, String)> {
+    quest
+        .aliases
+        .iter()
+        .map(|alias| (alias.id, alias.name.clone()))
+        .collect()
+}
+
+/// Buttons to add an item to a list and remove the selected one. Returns whether the list changed.
+fn list_buttons<T>(
+    ui: &mut egui::Ui,
+    items: &mut Vec<T>,
+    selected: &mut Option<usize>,
+    new: impl FnOnce() -> T,
+) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        if ui.button("Add").clicked() {
+            items.push(new());
+            *selected = Some(items.len() - 1);
+            changed = true;
+        }
+
+        let remove = egui::Button::new("Remove").enabled(selected.is_some());
+
+        if ui.add(remove).clicked() {
+            if let Some(index) = selected.take() {
+                if index < items.len() {
+                    items.remove(index);
+                    changed = true;
+                }
+            }
+        }
+    });
+
+    changed
+}
+
+/// Picks one of the quest's aliases by ID.
+fn alias_picker(ui: &mut egui::Ui, label: &str, alias: &mut i32, aliases: &[(u32, String)]) -> bool {
+    let before = *alias;
+    let selected = aliases
+        .iter()
+        .find(|(id, _)| *id as i32 == *alias)
+        .map(|(id, name)| format!("{} ({})", name, id))
+        .unwrap_or_else(|| format!("None ({})", alias));
+
+    egui::combo_box_with_label(ui, label, selected, |ui| {
+        ui.selectable_value(alias, -1, "None");
+
+        for (id, name) in aliases {
+            ui.selectable_value(alias, *id as i32, format!("{} ({})", name, id));
+        }
+    });
+
+    *alias != before
+}
+
+fn data_ui(ui: &mut egui::Ui, quest: &mut Quest, editor_ids: EditorIds, record_codes: RecordCodes) -> bool {
+    let mut changed = false;
+    let aliases = alias_names(quest);
+
+    egui::Grid::new("quest_data_grid").show(ui, |ui| {
+        ui.label("Editor ID");
+        changed |= ui.text_edit_singleline(&mut quest.editor_id).changed();
+        ui.end_row();
+
+        ui.label("Name");
+        changed |= text_edit(ui, &mut quest.name, false);
+        ui.end_row();
+
+        ui.label("Priority");
+        changed |= ui.add(egui::DragValue::new(&mut quest.priority)).changed();
+        ui.end_row();
+
+        ui.label("Type");
+        let before = quest.quest_type;
+        let selected = QUEST_TYPE_NAMES
+            .get(quest.quest_type as usize)
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| quest.quest_type.to_string());
+        egui::combo_box_with_label(ui, "", selected, |ui| {
+            for (index, name) in QUEST_TYPE_NAMES.iter().enumerate() {
+                ui.selectable_value(&mut quest.quest_type, index as u32, *name);
+            }
+        });
+        changed |= quest.quest_type != before;
+        ui.end_row();
+
+        ui.label("Event");
+        let mut event = quest.event.unwrap_or([0; 4]);
+        if code_edit(ui, &mut event) {
+            quest.event = Some(event).filter(|&event| event != [0; 4]);
+            changed = true;
+        }
+        ui.end_row();
+
+        ui.label("Filter");
+        changed |= ui.text_edit_singleline(&mut quest.filter).changed();
+        ui.end_row();
+    });
+
+    ui.separator();
+
+    let flags = &mut quest.flags;
+    changed |= flag_checkbox(ui, flags, quest_flags::START_GAME_ENABLED, "Start game enabled");
+    changed |= flag_checkbox(ui, flags, quest_flags::RUN_ONCE, "Run once");
+    changed |= flag_checkbox(ui, flags, quest_flags::ALLOW_REPEATED_STAGES, "Allow repeated stages");
+    changed |= flag_checkbox(
+        ui,
+        flags,
+        quest_flags::EXCLUDE_FROM_DIALOGUE_EXPORT,
+        "Exclude from dialogue export",
+    );
+    changed |= flag_checkbox(
+        ui,
+        flags,
+        quest_flags::WARN_ON_ALIAS_FILL_FAILURE,
+        "Warn on alias fill failure",
+    );
+
+    ui.separator();
+    ui.label("Text display globals");
+
+    let mut removed = None;
+    for (index, global) in quest.text_globals.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            changed |= form_id_edit(ui, global, editor_ids);
+            if ui.small_button("x").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        quest.text_globals.remove(index);
+        changed = true;
+    }
+    if ui.button("Add global").clicked() {
+        quest.text_globals.push(0);
+        changed = true;
+    }
+
+    ui.separator();
+    ui.label("Dialogue conditions");
+    changed |= condition_list(
+        ui,
+        "dialogue_conditions",
+        &mut quest.dialogue_conditions,
+        &aliases,
+        editor_ids,
+        record_codes,
+    );
+
+    ui.separator();
+    ui.label("Event conditions");
+    changed |= condition_list(
+        ui,
+        "event_conditions",
+        &mut quest.event_conditions,
+        &aliases,
+        editor_ids,
+        record_codes,
+    );
+
+    changed
+}
+
+fn stages_ui(
+    ui: &mut egui::Ui,
+    quest: &mut Quest,
+    selected: &mut Option<usize>,
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+) -> bool {
+    let mut changed = false;
+    let aliases = alias_names(quest);
+
+    egui::ScrollArea::from_max_height(LIST_HEIGHT)
+        .id_source("quest_stages")
+        .show(ui, |ui| {
+            for (index, stage) in quest.stages.iter().enumerate() {
+                let text = stage
+                    .log_entries
+                    .iter()
+                    .find_map(|entry| entry.text.as_ref())
+                    .map(|text| text.to_string())
+                    .unwrap_or_default();
+
+                if ui
+                    .selectable_label(*selected == Some(index), format!("{:>5}  {}", stage.index, text))
+                    .clicked()
+                {
+                    *selected = Some(index);
+                }
+            }
+        });
+
+    let next_index = quest.stages.iter().map(|stage| stage.index + 10).max().unwrap_or(0);
+    if list_buttons(ui, &mut quest.stages, selected, || Stage {
+        index: next_index,
+        ..Stage::default()
+    }) {
+        changed = true;
+        quest.stages.sort_by_key(|stage| stage.index);
+    }
+
+    let stage = match selected.and_then(|index| quest.stages.get_mut(index)) {
+        Some(stage) => stage,
+        None => return changed,
+    };
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.label("Index");
+        changed |= ui.add(egui::DragValue::new(&mut stage.index)).changed();
+    });
+    changed |= flag_checkbox(ui, &mut stage.flags, stage_flags::RUN_ON_START, "Run on start");
+    changed |= flag_checkbox(ui, &mut stage.flags, stage_flags::RUN_ON_STOP, "Run on stop");
+    changed |= flag_checkbox(
+        ui,
+        &mut stage.flags,
+        stage_flags::KEEP_INSTANCE_DATA,
+        "Keep instance data from here on",
+    );
+
+    let mut removed = None;
+
+    for (index, entry) in stage.log_entries.iter_mut().enumerate() {
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(format!("Log entry {}", index + 1));
+            if ui.small_button("Remove").clicked() {
+                removed = Some(index);
+            }
+        });
+
+        changed |= text_edit(ui, &mut entry.text, true);
+        ui.horizontal(|ui| {
+            changed |= flag_checkbox(ui, &mut entry.flags, log_entry_flags::COMPLETE_QUEST, "Complete quest");
+            changed |= flag_checkbox(ui, &mut entry.flags, log_entry_flags::FAIL_QUEST, "Fail quest");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Next quest");
+            let mut next_quest = entry.next_quest.unwrap_or(0);
+            if form_id_edit(ui, &mut next_quest, editor_ids) {
+                entry.next_quest = Some(next_quest).filter(|&quest| quest != 0);
+                changed = true;
+            }
+        });
+        ui.label("Conditions");
+        changed |= condition_list(
+            ui,
+            ("log_entry", index),
+            &mut entry.conditions,
+            &aliases,
+            editor_ids,
+            record_codes,
+        );
+    }
+
+    if let Some(index) = removed {
+        stage.log_entries.remove(index);
+        changed = true;
+    }
+
+    if ui.button("Add log entry").clicked() {
+        stage.log_entries.push(LogEntry::default());
+        changed = true;
+    }
+
+    changed
+}
+
+fn objectives_ui(
+    ui: &mut egui::Ui,
+    quest: &mut Quest,
+    selected: &mut Option<usize>,
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+) -> bool {
+    let mut changed = false;
+    let aliases = alias_names(quest);
+
+    egui::ScrollArea::from_max_height(LIST_HEIGHT)
+        .id_source("quest_objectives")
+        .show(ui, |ui| {
+            for (index, objective) in quest.objectives.iter().enumerate() {
+                let text = objective.text.as_ref().map(|text| text.to_string()).unwrap_or_default();
+
+                if ui
+                    .selectable_label(*selected == Some(index), format!("{:>5}  {}", objective.index, text))
+                    .clicked()
+                {
+                    *selected = Some(index);
+                }
+            }
+        });
+
+    let next_index = quest
+        .objectives
+        .iter()
+        .map(|objective| objective.index + 10)
+        .max()
+        .unwrap_or(0);
+    changed |= list_buttons(ui, &mut quest.objectives, selected, || Objective {
+        index: next_index,
+        ..Objective::default()
+    });
+
+    let objective = match selected.and_then(|index| quest.objectives.get_mut(index)) {
+        Some(objective) => objective,
+        None => return changed,
+    };
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.label("Index");
+        changed |= ui.add(egui::DragValue::new(&mut objective.index)).changed();
+    });
+    changed |= text_edit(ui, &mut objective.text, false);
+    changed |= flag_checkbox(
+        ui,
+        &mut objective.flags,
+        objective_flags::ORED_WITH_PREVIOUS,
+        "ORed with previous",
+    );
+
+    let mut removed = None;
+
+    for (index, target) in objective.targets.iter_mut().enumerate() {
+        ui.separator();
+        ui.horizontal(|ui| {
+            changed |= alias_picker(ui, &format!("Target {}", index + 1), &mut target.alias, &aliases);
+            if ui.small_button("Remove").clicked() {
+                removed = Some(index);
+            }
+        });
+        changed |= flag_checkbox(
+            ui,
+            &mut target.flags,
+            target_flags::COMPASS_MARKER_IGNORES_LOCKS,
+            "Compass marker ignores locks",
+        );
+        ui.label("Conditions");
+        changed |= condition_list(
+            ui,
+            ("target", index),
+            &mut target.conditions,
+            &aliases,
+            editor_ids,
+            record_codes,
+        );
+    }
+
+    if let Some(index) = removed {
+        objective.targets.remove(index);
+        changed = true;
+    }
+
+    if ui.button("Add target").clicked() {
+        objective.targets.push(ObjectiveTarget {
+            alias: -1,
+            ..ObjectiveTarget::default()
+        });
+        changed = true;
+    }
+
+    changed
+}
+
+/// The fill types offered for reference or location aliases, with empty values.
+fn fill_choices(is_location: bool) -> Vec<AliasFill> {
+    let mut choices = vec![AliasFill::Conditions];
+
+    if is_location {
+        choices.push(AliasFill::ForcedLocation(0));
+    } else {
+        choices.push(AliasFill::ForcedReference(0));
+        choices.push(AliasFill::UniqueActor(0));
+        choices.push(AliasFill::CreateReference {
+            object: 0,
+            at: 0,
+            level: 4,
+        });
+        choices.push(AliasFill::NearAlias { alias: 0, kind: 0 });
+    }
+
+    choices.push(AliasFill::LocationAlias { alias: 0, ref_type: 0 });
+    choices.push(AliasFill::ExternalAlias { quest: 0, alias: 0 });
+    choices.push(AliasFill::FromEvent {
+        event: [0; 4],
+        data: [0; 4],
+    });
+    choices
+}
+
+fn code_edit(ui: &mut egui::Ui, code: &mut [u8; 4]) -> bool {
+    let mut text: String = code.iter().take_while(|&&c| c != 0).map(|&c| c as char).collect();
+
+    if !ui.text_edit_singleline(&mut text).changed() {
+        return false;
+    }
+
+    *code = [0; 4];
+    for (byte, c) in code.iter_mut().zip(text.chars()) {
+        *byte = c as u8;
+    }
+    true
+}
+
+fn fill_ui(ui: &mut egui::Ui, alias: &mut Alias, aliases: &[(u32, String)], editor_ids: EditorIds) -> bool {
+    let mut changed = false;
+    let before = alias.fill.name();
+
+    egui::combo_box_with_label(ui, "Fill type", before, |ui| {
+        for choice in fill_choices(alias.is_location) {
+            if ui.selectable_label(choice.name() == before, choice.name()).clicked() && choice.name() != before {
+                alias.fill = choice;
+                changed = true;
+            }
+        }
+    });
+
+    let alias_id = |ui: &mut egui::Ui, label: &str, id: &mut u32| {
+        let mut picked = *id as i32;
+        if alias_picker(ui, label, &mut picked, aliases) {
+            *id = picked.max(0) as u32;
+            return true;
+        }
+        false
+    };
+
+    egui::Grid::new("quest_alias_fill_grid").show(ui, |ui| match &mut alias.fill {
+        AliasFill::Conditions => {
+            ui.label("Filled by the first match for the conditions below.");
+            ui.end_row();
+        }
+        AliasFill::ForcedReference(form_id) | AliasFill::ForcedLocation(form_id) | AliasFill::UniqueActor(form_id) => {
+            ui.label("Form");
+            changed |= form_id_edit(ui, form_id, editor_ids);
+            ui.end_row();
+        }
+        AliasFill::LocationAlias { alias, ref_type } => {
+            changed |= alias_id(ui, "Location alias", alias);
+            ui.end_row();
+            ui.label("Reference type");
+            changed |= form_id_edit(ui, ref_type, editor_ids);
+            ui.end_row();
+        }
+        AliasFill::ExternalAlias { quest, alias } => {
+            ui.label("Quest");
+            changed |= form_id_edit(ui, quest, editor_ids);
+            ui.end_row();
+            ui.label("Alias ID");
+            changed |= ui.add(egui::DragValue::new(alias)).changed();
+            ui.end_row();
+        }
+        AliasFill::CreateReference { object, at, level } => {
+            ui.label("Object");
+            changed |= form_id_edit(ui, object, editor_ids);
+            ui.end_row();
+            ui.label("Create at alias");
+            changed |= ui.add(egui::DragValue::new(at)).changed();
+            ui.end_row();
+            let selected = CREATE_LEVEL_NAMES.get(*level as usize).copied().unwrap_or("?");
+            egui::combo_box_with_label(ui, "Level", selected, |ui| {
+                for (index, name) in CREATE_LEVEL_NAMES.iter().enumerate() {
+                    changed |= ui.selectable_value(level, index as u32, *name).clicked();
+                }
+            });
+            ui.end_row();
+        }
+        AliasFill::NearAlias { alias, kind } => {
+            changed |= alias_id(ui, "Near alias", alias);
+            ui.end_row();
+            ui.label("Link type");
+            changed |= ui.add(egui::DragValue::new(kind)).changed();
+            ui.end_row();
+        }
+        AliasFill::FromEvent { event, data } => {
+            ui.label("Event");
+            changed |= code_edit(ui, event);
+            ui.end_row();
+            ui.label("Event data");
+            changed |= code_edit(ui, data);
+            ui.end_row();
+        }
+    });
+
+    changed
+}
+
+fn aliases_ui(
+    ui: &mut egui::Ui,
+    quest: &mut Quest,
+    selected: &mut Option<usize>,
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+) -> bool {
+    let mut changed = false;
+    let aliases = alias_names(quest);
+
+    egui::ScrollArea::from_max_height(LIST_HEIGHT)
+        .id_source("quest_aliases")
+        .show(ui, |ui| {
+            for (index, alias) in quest.aliases.iter().enumerate() {
+                let kind = if alias.is_location { "Location" } else { "Reference" };
+                let label = format!("{:>3}  {}  ({}, {})", alias.id, alias.name, kind, alias.fill.name());
+
+                if ui.selectable_label(*selected == Some(index), label).clicked() {
+                    *selected = Some(index);
+                }
+            }
+        });
+
+    ui.horizontal(|ui| {
+        for &(label, is_location) in &[("Add reference alias", false), ("Add location alias", true)] {
+            if ui.button(label).clicked() {
+                let id = quest.take_alias_id();
+                quest.aliases.push(Alias::new(id, is_location));
+                *selected = Some(quest.aliases.len() - 1);
+                changed = true;
+            }
+        }
+
+        if ui
+            .add(egui::Button::new("Remove").enabled(selected.is_some()))
+            .clicked()
+        {
+            if let Some(index) = selected.take() {
+                quest.aliases.remove(index);
+                changed = true;
+            }
+        }
+    });
+
+    let alias = match selected.and_then(|index| quest.aliases.get_mut(index)) {
+        Some(alias) => alias,
+        None => return changed,
+    };
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.label(format!("ID {}", alias.id));
+        changed |= ui.text_edit_singleline(&mut alias.name).changed();
+    });
+
+    changed |= fill_ui(ui, alias, &aliases, editor_ids);
+
+    let mut forced_into = alias.forced_into.map(|id| id as i32).unwrap_or(-1);
+    if alias_picker(ui, "Forced into", &mut forced_into, &aliases) {
+        alias.forced_into = Some(forced_into).filter(|&id| id >= 0).map(|id| id as u32);
+        changed = true;
+    }
+
+    ui.separator();
+
+    let flags = &mut alias.flags;
+    ui.columns(2, |columns| {
+        let named = [
+            (alias_flags::OPTIONAL, "Optional"),
+            (alias_flags::RESERVES, "Reserves"),
+            (alias_flags::ALLOW_RESERVED, "Allow reserved"),
+            (alias_flags::ALLOW_REUSE, "Allow reuse in quest"),
+            (alias_flags::QUEST_OBJECT, "Quest object"),
+            (alias_flags::ESSENTIAL, "Essential"),
+            (alias_flags::PROTECTED, "Protected"),
+            (alias_flags::ALLOW_DEAD, "Allow dead"),
+            (alias_flags::ALLOW_DISABLED, "Allow disabled"),
+            (alias_flags::ALLOW_DESTROYED, "Allow destroyed"),
+            (alias_flags::IN_LOADED_AREA, "In loaded area"),
+            (alias_flags::CLOSEST, "Closest"),
+            (alias_flags::INITIALLY_DISABLED, "Initially disabled"),
+            (alias_flags::ALLOW_CLEARED, "Allow cleared"),
+            (alias_flags::STORES_TEXT, "Stores text"),
+            (alias_flags::USES_STORED_TEXT, "Uses stored text"),
+            (alias_flags::CLEAR_NAMES_WHEN_REMOVED, "Clear names when removed"),
+        ];
+
+        for (index, (flag, label)) in named.iter().enumerate() {
+            changed |= flag_checkbox(&mut columns[index % 2], flags, *flag, label);
+        }
+    });
+
+    ui.separator();
+    ui.label("Conditions");
+    changed |= condition_list(
+        ui,
+        "alias_conditions",
+        &mut alias.conditions,
+        &aliases,
+        editor_ids,
+        record_codes,
+    );
+
+    if !alias.other.is_empty() {
+        let codes: Vec<String> = alias
+            .other
+            .iter()
+            .map(|subrecord| String::from_utf8_lossy(&subrecord.code).into_owned())
+            .collect();
+        ui.label(format!("Also stores: {}", codes.join(", ")));
+    }
+
+    changed
+}
+
+fn scripts_ui(
+    ui: &mut egui::Ui,
+    quest: &mut Quest,
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+    scripts: ScriptLookup,
+) -> bool {
+    let aliases = alias_names(quest);
+
+    match &mut quest.scripts {
+        Some(vmad) => script_list(ui, "quest_scripts", vmad, &aliases, editor_ids, record_codes, scripts),
+        None => {
+            ui.label("No scripts are attached.");
+            false
+        }
+    }
+}
+
+impl<'a> View for QuestWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let QuestState {
+            quest,
+            tab,
+            selected_stage,
+            selected_objective,
+            selected_alias,
+        } = &mut *self.state;
+        let editor_ids = self.editor_ids;
+        let record_codes = self.record_codes;
+        let scripts = self.scripts;
+
+        let quest = match quest {
+            Some(quest) => quest,
+            None => {
+                ui.label("Select a quest in the tree view to edit it.");
+                return;
+            }
+        };
+
+        ui.label(format!("{}  [{:08X}]", quest.editor_id, quest.form_id));
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(tab, QuestTab::Data, "Quest Data");
+            ui.selectable_value(tab, QuestTab::Stages, "Stages");
+            ui.selectable_value(tab, QuestTab::Objectives, "Objectives");
+            ui.selectable_value(tab, QuestTab::Aliases, "Aliases");
+            ui.selectable_value(tab, QuestTab::Scripts, "Scripts");
+        });
+
+        ui.separator();
+
+        let mut changed = false;
+
+        egui::ScrollArea::auto_sized().show(ui, |ui| {
+            changed = match tab {
+                QuestTab::Data => data_ui(ui, quest, editor_ids, record_codes),
+                QuestTab::Stages => stages_ui(ui, quest, selected_stage, editor_ids, record_codes),
+                QuestTab::Objectives => objectives_ui(ui, quest, selected_objective, editor_ids, record_codes),
+                QuestTab::Aliases => aliases_ui(ui, quest, selected_alias, editor_ids, record_codes),
+                QuestTab::Scripts => scripts_ui(ui, quest, editor_ids, record_codes, scripts),
+            };
+        });
+
+        self.changed |= changed;
+    }
+}
+
+impl<'a> Window for QuestWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Quest"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(DEFAULT_WIDTH)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
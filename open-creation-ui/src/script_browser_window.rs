@@ -0,0 +1,161 @@
+use super::{View, Window};
+
+use open_creation_data::{pex, script_library::ScriptLibrary};
+
+const DEFAULT_WIDTH: f32 = 720.0;
+const DEFAULT_HEIGHT: f32 = 480.0;
+const MAX_FILTER_RESULTS: usize = 1000;
+
+#[derive(Default)]
+pub struct ScriptBrowserState {
+    pub filter: String,
+    /// The lowercase name of the script shown, which must be loaded into the library before the window is shown.
+    pub selected: Option<String>,
+}
+
+pub struct ScriptBrowserWindow<'a> {
+    library: &'a ScriptLibrary,
+    state: &'a mut ScriptBrowserState,
+}
+
+impl<'a> ScriptBrowserWindow<'a> {
+    pub fn new(library: &'a ScriptLibrary, state: &'a mut ScriptBrowserState) -> Self {
+        Self { library, state }
+    }
+}
+
+fn docstring_hover(response: egui::Response, docstring: &str) {
+    if !docstring.is_empty() {
+        response.on_hover_text(docstring);
+    }
+}
+
+/// Lists a script's declarations: its properties, variables, and the functions of each of its states.
+fn script_ui(ui: &mut egui::Ui, script: &pex::Script, selected: &mut Option<String>) {
+    ui.horizontal(|ui| {
+        ui.label(format!("Scriptname {}", script.name));
+
+        if !script.parent.is_empty() {
+            ui.label("extends");
+            if ui.small_button(&script.parent).clicked() {
+                *selected = Some(script.parent.to_lowercase());
+            }
+        }
+    });
+
+    if !script.docstring.is_empty() {
+        ui.label(&script.docstring);
+    }
+
+    ui.separator();
+
+    egui::CollapsingHeader::new(format!("Properties ({})", script.properties.len()))
+        .id_source("script_properties")
+        .default_open(true)
+        .show(ui, |ui| {
+            for property in &script.properties {
+                let auto = if property.is_auto() { " Auto" } else { "" };
+                let response = ui.label(format!("{} Property {}{}", property.type_name, property.name, auto));
+                docstring_hover(response, &property.docstring);
+            }
+        });
+
+    egui::CollapsingHeader::new(format!("Variables ({})", script.variables.len()))
+        .id_source("script_variables")
+        .show(ui, |ui| {
+            for variable in &script.variables {
+                ui.label(format!("{} {}", variable.type_name, variable.name));
+            }
+        });
+
+    for (index, state) in script.states.iter().enumerate() {
+        let name = match state.name.as_str() {
+            "" => "Empty state".to_string(),
+            name if name.eq_ignore_ascii_case(&script.auto_state) => format!("Auto State {}", name),
+            name => format!("State {}", name),
+        };
+
+        egui::CollapsingHeader::new(format!("{} ({})", name, state.functions.len()))
+            .id_source(("script_state", index))
+            .default_open(state.name.is_empty())
+            .show(ui, |ui| {
+                for function in &state.functions {
+                    docstring_hover(ui.label(function.signature()), &function.docstring);
+                }
+            });
+    }
+}
+
+impl<'a> View for ScriptBrowserWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let library = self.library;
+        let state = &mut *self.state;
+
+        ui.columns(2, |columns| {
+            columns[0].vertical_centered_justified(|ui| {
+                ui.label(format!("Scripts ({})", library.names().len()));
+                ui.separator();
+                ui.add(egui::TextEdit::singleline(&mut state.filter).hint_text("Filter"));
+                ui.separator();
+            });
+
+            egui::ScrollArea::from_max_height(DEFAULT_HEIGHT)
+                .id_source("script_browser_names")
+                .show(&mut columns[0], |ui| {
+                    let filter = state.filter.to_lowercase();
+
+                    for name in library
+                        .names()
+                        .iter()
+                        .filter(|name| name.contains(&filter))
+                        .take(MAX_FILTER_RESULTS)
+                    {
+                        let selected = state.selected.as_deref() == Some(name.as_str());
+                        if ui.selectable_label(selected, name).clicked() {
+                            state.selected = Some(name.clone());
+                        }
+                    }
+                });
+
+            let ui = &mut columns[1];
+
+            ui.vertical_centered_justified(|ui| {
+                ui.label("Declarations");
+                ui.separator();
+            });
+
+            let selected = match state.selected.clone() {
+                Some(selected) => selected,
+                None => {
+                    ui.label("Select a script to see what it declares.");
+                    return;
+                }
+            };
+
+            match library.script(&selected) {
+                Some(script) => {
+                    egui::ScrollArea::from_max_height(DEFAULT_HEIGHT)
+                        .id_source("script_browser_details")
+                        .show(ui, |ui| script_ui(ui, script, &mut state.selected));
+                }
+                None => {
+                    ui.label(format!("{}.pex was not found or could not be read.", selected));
+                }
+            }
+        });
+    }
+}
+
+impl<'a> Window for ScriptBrowserWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Script Browser"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_size(egui::vec2(DEFAULT_WIDTH, DEFAULT_HEIGHT))
+            .scroll(false)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
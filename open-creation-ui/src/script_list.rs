@@ -0,0 +1,292 @@
+//! An editor for the Papyrus scripts attached to a record and the values of their properties, followed by the
+//! record's script fragments.
+//!
+//! Properties are checked against the compiled scripts read so far, which must be loaded into the library
+//! before the list is shown.
+
+use super::widgets::{alias_edit, form_id_edit, EditorIds, FindRecord, RecordCodes};
+
+use open_creation_data::{
+    esp::{
+        code_name,
+        vmad::{INFO_FRAGMENT_EVENTS, PACKAGE_FRAGMENT_EVENTS},
+        EventFragments, Fragments, ObjectValue, PropertyValue, Script, Vmad,
+    },
+    script_library::ScriptLibrary,
+};
+
+use std::hash::Hash;
+
+/// The status of a property whose value has been set on the record.
+const PROPERTY_EDITED: u8 = 1;
+const ERROR_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 80, 80);
+
+/// The compiled scripts, for checking the properties set on attached scripts and filling them in.
+#[derive(Clone, Copy)]
+pub struct ScriptLookup<'a> {
+    pub library: &'a ScriptLibrary,
+    pub find_record: FindRecord<'a>,
+}
+
+/// Shows a record's scripts with their properties for editing, and its fragments. `aliases` are the aliases
+/// of the quest the record belongs to, by ID, for object properties that refer to an alias. Returns whether a
+/// property was changed.
+pub fn script_list(
+    ui: &mut egui::Ui,
+    id_source: impl Hash,
+    vmad: &mut Vmad,
+    aliases: &[(u32, String)],
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+    lookup: ScriptLookup,
+) -> bool {
+    let mut changed = false;
+
+    ui.push_id(id_source, |ui| {
+        if vmad.scripts.is_empty() {
+            ui.label("No scripts are attached.");
+        }
+        changed |= scripts_ui(ui, &mut vmad.scripts, aliases, editor_ids, record_codes, lookup);
+
+        match &mut vmad.fragments {
+            Fragments::Raw(data) if data.is_empty() => {}
+            Fragments::Raw(data) => {
+                ui.separator();
+                ui.label(format!("{} bytes of fragment data are not decoded.", data.len()));
+            }
+            Fragments::Info(fragments) => event_fragments_ui(ui, fragments, &INFO_FRAGMENT_EVENTS),
+            Fragments::Package(fragments) => event_fragments_ui(ui, fragments, &PACKAGE_FRAGMENT_EVENTS),
+            Fragments::Quest(fragments) => {
+                ui.separator();
+                ui.label(format!("Fragments: {}", fragments.file_name));
+
+                for stage in &fragments.stages {
+                    ui.label(format!(
+                        "    Stage {}, log entry {}: {}",
+                        stage.stage, stage.log_entry, stage.fragment.function
+                    ));
+                }
+
+                for (index, alias) in fragments.aliases.iter_mut().enumerate() {
+                    let name = aliases
+                        .iter()
+                        .find(|(id, _)| *id as i16 == alias.alias.alias)
+                        .map_or("?", |(_, name)| name.as_str());
+
+                    egui::CollapsingHeader::new(format!("Alias {} ({})", name, alias.alias.alias))
+                        .id_source(("alias_scripts", index))
+                        .show(ui, |ui| {
+                            changed |= scripts_ui(ui, &mut alias.scripts, aliases, editor_ids, record_codes, lookup);
+                        });
+                }
+            }
+            Fragments::Perk(fragments) => {
+                ui.separator();
+                ui.label(format!("Fragments: {}", fragments.file_name));
+
+                for fragment in &fragments.fragments {
+                    ui.label(format!("    Entry {}: {}", fragment.index, fragment.fragment.function));
+                }
+            }
+        }
+    });
+
+    changed
+}
+
+fn scripts_ui(
+    ui: &mut egui::Ui,
+    scripts: &mut [Script],
+    aliases: &[(u32, String)],
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+    lookup: ScriptLookup,
+) -> bool {
+    let mut changed = false;
+
+    for (script_index, script) in scripts.iter_mut().enumerate() {
+        let status = match script.status {
+            0 => "",
+            1 => " (inherited, edited)",
+            3 => " (inherited, removed)",
+            _ => " (unknown status)",
+        };
+
+        egui::CollapsingHeader::new(format!("{}{}", script.name, status))
+            .id_source(("script", script_index))
+            .default_open(true)
+            .show(ui, |ui| {
+                changed |= check_ui(ui, script, record_codes, lookup);
+
+                if script.properties.is_empty() {
+                    ui.label("No properties are set.");
+                    return;
+                }
+
+                let id = ui.make_persistent_id(("properties", script_index));
+
+                egui::Grid::new(id).show(ui, |ui| {
+                    for (index, property) in script.properties.iter_mut().enumerate() {
+                        ui.label(&property.name);
+                        ui.label(property.value.type_name());
+
+                        let value_id = id.with(index);
+                        let mut edited = false;
+                        ui.vertical(|ui| {
+                            edited = value_ui(ui, value_id, &mut property.value, aliases, editor_ids, record_codes);
+                        });
+
+                        if edited {
+                            property.status = PROPERTY_EDITED;
+                            changed = true;
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    changed
+}
+
+/// Lists the problems with a script's properties, with a button to fill in the unset ones. Returns whether
+/// any were filled in.
+fn check_ui(ui: &mut egui::Ui, script: &mut Script, record_codes: RecordCodes, lookup: ScriptLookup) -> bool {
+    let issues = match lookup.library.check(script, record_codes) {
+        Some(issues) => issues,
+        None => {
+            ui.label("The compiled script was not found, so its properties are not checked.");
+            return false;
+        }
+    };
+
+    for issue in issues {
+        ui.colored_label(ERROR_COLOR, issue.message);
+    }
+
+    if !ui
+        .small_button("Auto-fill")
+        .on_hover_text("Sets unset properties to the records named after them")
+        .clicked()
+    {
+        return false;
+    }
+
+    !lookup.library.auto_fill(script, lookup.find_record).is_empty()
+}
+
+/// Edits a property's value as its type, element by element for arrays.
+fn value_ui(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    value: &mut PropertyValue,
+    aliases: &[(u32, String)],
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+) -> bool {
+    match value {
+        PropertyValue::None => {
+            ui.label("None");
+            false
+        }
+        PropertyValue::Object(object) => object_ui(ui, id, object, aliases, editor_ids, record_codes),
+        PropertyValue::String(string) => ui.text_edit_singleline(string).changed(),
+        PropertyValue::Int(int) => ui.add(egui::DragValue::new(int)).changed(),
+        PropertyValue::Float(float) => ui.add(egui::DragValue::new(float).speed(0.1)).changed(),
+        PropertyValue::Bool(bool) => ui.checkbox(bool, "").changed(),
+        PropertyValue::ObjectArray(objects) => array_ui(ui, objects, |ui, index, object| {
+            object_ui(ui, id.with(index), object, aliases, editor_ids, record_codes)
+        }),
+        PropertyValue::StringArray(strings) => {
+            array_ui(ui, strings, |ui, _, string| ui.text_edit_singleline(string).changed())
+        }
+        PropertyValue::IntArray(ints) => array_ui(ui, ints, |ui, _, int| ui.add(egui::DragValue::new(int)).changed()),
+        PropertyValue::FloatArray(floats) => array_ui(ui, floats, |ui, _, float| {
+            ui.add(egui::DragValue::new(float).speed(0.1)).changed()
+        }),
+        PropertyValue::BoolArray(bools) => array_ui(ui, bools, |ui, _, bool| ui.checkbox(bool, "").changed()),
+    }
+}
+
+/// Edits an object value: a form picked by form ID, or an alias of a quest.
+fn object_ui(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    object: &mut ObjectValue,
+    aliases: &[(u32, String)],
+    editor_ids: EditorIds,
+    record_codes: RecordCodes,
+) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        let mut is_alias = object.alias >= 0;
+        if ui.checkbox(&mut is_alias, "Alias").changed() {
+            object.alias = if is_alias { 0 } else { -1 };
+            changed = true;
+        }
+
+        if is_alias {
+            let mut alias = object.alias as u32;
+            if alias_edit(ui, id, &mut alias, aliases) {
+                object.alias = alias as i16;
+                changed = true;
+            }
+
+            ui.label("of quest");
+            changed |= form_id_edit(ui, &mut object.form_id, editor_ids);
+        } else {
+            changed |= form_id_edit(ui, &mut object.form_id, editor_ids);
+
+            if let Some(code) = record_codes(object.form_id) {
+                ui.label(format!("[{}]", code_name(code)));
+            }
+        }
+    });
+
+    changed
+}
+
+/// Lists the elements of an array property with buttons to remove them and add another.
+fn array_ui<T: Default>(
+    ui: &mut egui::Ui,
+    items: &mut Vec<T>,
+    mut item_ui: impl FnMut(&mut egui::Ui, usize, &mut T) -> bool,
+) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+
+    for (index, item) in items.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(format!("[{}]", index));
+            changed |= item_ui(ui, index, item);
+
+            if ui.small_button("Remove").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+
+    if let Some(index) = removed {
+        items.remove(index);
+        changed = true;
+    }
+
+    if ui.small_button("Add").clicked() {
+        items.push(T::default());
+        changed = true;
+    }
+
+    changed
+}
+
+/// Lists the fragment run on each of an INFO's or package's events.
+fn event_fragments_ui(ui: &mut egui::Ui, fragments: &EventFragments, events: &[&str]) {
+    ui.separator();
+    ui.label(format!("Fragments: {}", fragments.file_name));
+
+    for (event, fragment) in events.iter().zip(&fragments.events) {
+        let function = fragment.as_ref().map_or("None", |fragment| fragment.function.as_str());
+        ui.label(format!("    {}: {}", event, function));
+    }
+}
@@ -0,0 +1,115 @@
+use super::{
+    script_list::{script_list, ScriptLookup},
+    widgets::{EditorIds, RecordCodes},
+    View, Window,
+};
+
+use open_creation_data::esp::{code_name, Code, Vmad};
+
+const DEFAULT_WIDTH: f32 = 420.0;
+
+#[derive(Default)]
+pub struct ScriptsState {
+    /// The record whose scripts are shown, or 0 for none.
+    pub form_id: u32,
+    pub editor_id: String,
+    pub code: Code,
+    /// The record's scripts, as they will be written to the active plugin, or `None` if it has none.
+    pub vmad: Option<Vmad>,
+}
+
+impl ScriptsState {
+    /// Shows the scripts of another record.
+    pub fn open(&mut self, form_id: u32, editor_id: String, code: Code, vmad: Option<Vmad>) {
+        self.form_id = form_id;
+        self.editor_id = editor_id;
+        self.code = code;
+        self.vmad = vmad;
+    }
+}
+
+pub struct ScriptsWindow<'a> {
+    state: &'a mut ScriptsState,
+    editor_ids: EditorIds<'a>,
+    record_codes: RecordCodes<'a>,
+    scripts: ScriptLookup<'a>,
+    changed: bool,
+}
+
+impl<'a> ScriptsWindow<'a> {
+    pub fn new(
+        state: &'a mut ScriptsState,
+        editor_ids: EditorIds<'a>,
+        record_codes: RecordCodes<'a>,
+        scripts: ScriptLookup<'a>,
+    ) -> Self {
+        Self {
+            state,
+            editor_ids,
+            record_codes,
+            scripts,
+            changed: false,
+        }
+    }
+
+    /// Whether a property was edited and the scripts should be saved.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+impl<'a> View for ScriptsWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let editor_ids = self.editor_ids;
+        let record_codes = self.record_codes;
+        let scripts = self.scripts;
+        let state = &mut *self.state;
+
+        if state.form_id == 0 {
+            ui.label("Select a record in the tree view to see its scripts.");
+            return;
+        }
+
+        ui.label(format!(
+            "{}  [{}]  [{:08X}]",
+            state.editor_id,
+            code_name(state.code),
+            state.form_id
+        ));
+        ui.separator();
+
+        // The Quest window keeps its own copy of the quest, which would overwrite edits made here.
+        if &state.code == b"QUST" {
+            ui.label("Quest scripts are edited on the Scripts tab of the Quest window.");
+            return;
+        }
+
+        let vmad = match &mut state.vmad {
+            Some(vmad) => vmad,
+            None => {
+                ui.label("No scripts are attached.");
+                return;
+            }
+        };
+
+        let mut changed = false;
+        egui::ScrollArea::auto_sized().show(ui, |ui| {
+            changed = script_list(ui, "record_scripts", vmad, &[], editor_ids, record_codes, scripts);
+        });
+
+        self.changed |= changed;
+    }
+}
+
+impl<'a> Window for ScriptsWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Scripts"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(DEFAULT_WIDTH)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
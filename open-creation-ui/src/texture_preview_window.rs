@@ -0,0 +1,129 @@
+use super::{View, Window};
+
+const DEFAULT_WIDTH: f32 = 520.0;
+const IMAGE_HEIGHT: f32 = 480.0;
+
+/// The header of the previewed texture.
+pub struct TextureInfo {
+    pub path: String,
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    pub mip_count: usize,
+    pub is_cubemap: bool,
+}
+
+#[derive(Default)]
+pub struct TexturePreviewState {
+    pub info: Option<TextureInfo>,
+    pub error: Option<String>,
+    /// The mip level shown, which is decoded and uploaded on its own.
+    pub mip_level: usize,
+}
+
+pub struct TexturePreviewWindow<'a> {
+    state: &'a mut TexturePreviewState,
+    texture_id: egui::TextureId,
+    mip_changed: bool,
+}
+
+impl<'a> TexturePreviewWindow<'a> {
+    pub fn new(state: &'a mut TexturePreviewState, texture_id: egui::TextureId) -> Self {
+        Self {
+            state,
+            texture_id,
+            mip_changed: false,
+        }
+    }
+
+    /// Whether a different mip level was chosen, which needs decoding before it can be shown.
+    pub fn mip_changed(&self) -> bool {
+        self.mip_changed
+    }
+}
+
+impl<'a> View for TexturePreviewWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        if let Some(error) = &self.state.error {
+            ui.colored_label(egui::Color32::RED, error);
+            return;
+        }
+
+        let info = match &self.state.info {
+            Some(info) => info,
+            None => {
+                ui.label("Select a .dds file in the Archive Browser to preview it.");
+                return;
+            }
+        };
+
+        let mip_width = (info.width >> self.state.mip_level).max(1);
+        let mip_height = (info.height >> self.state.mip_level).max(1);
+
+        egui::Grid::new("texture_preview_info").show(ui, |ui| {
+            ui.label("Path");
+            ui.label(&info.path);
+            ui.end_row();
+
+            ui.label("Format");
+            ui.label(&info.format);
+            ui.end_row();
+
+            ui.label("Dimensions");
+            ui.label(format!("{} x {}", info.width, info.height));
+            ui.end_row();
+
+            ui.label("Mip levels");
+            ui.label(info.mip_count.to_string());
+            ui.end_row();
+
+            if info.is_cubemap {
+                ui.label("Cubemap");
+                ui.label("Showing the first face");
+                ui.end_row();
+            }
+        });
+
+        let (width, height, mip_count) = (info.width, info.height, info.mip_count);
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            let level = self.state.mip_level;
+
+            if ui.add(egui::Button::new("<").enabled(level > 0)).clicked() {
+                self.state.mip_level -= 1;
+                self.mip_changed = true;
+            }
+
+            ui.label(format!("Mip {} ({} x {})", level, mip_width, mip_height));
+
+            if ui.add(egui::Button::new(">").enabled(level + 1 < mip_count)).clicked() {
+                self.state.mip_level += 1;
+                self.mip_changed = true;
+            }
+        });
+
+        ui.separator();
+
+        // Every level is drawn at the same size so that the loss of detail down the chain is visible.
+        let scale = (ui.available_width() / width as f32)
+            .min(IMAGE_HEIGHT / height as f32)
+            .min(1.0);
+
+        ui.image(self.texture_id, egui::vec2(width as f32 * scale, height as f32 * scale));
+    }
+}
+
+impl<'a> Window for TexturePreviewWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Texture Preview"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(DEFAULT_WIDTH)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
@@ -0,0 +1,176 @@
+use super::{View, Window};
+
+use open_creation_data::esp::Placement;
+
+const DEFAULT_WIDTH: f32 = 320.0;
+const MIN_SCALE: f32 = 0.01;
+const MAX_SCALE: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+impl Default for GizmoMode {
+    fn default() -> Self {
+        GizmoMode::Translate
+    }
+}
+
+pub struct TransformState {
+    /// The selected reference's form ID and a description of it.
+    pub target: Option<(u32, String)>,
+    pub placement: Placement,
+    pub mode: GizmoMode,
+    pub grid_snap: bool,
+    /// The grid size in game units.
+    pub grid_size: f32,
+    pub angle_snap: bool,
+    /// The angle step in degrees.
+    pub angle_step: f32,
+}
+
+impl Default for TransformState {
+    fn default() -> Self {
+        Self {
+            target: None,
+            placement: Placement::default(),
+            mode: GizmoMode::default(),
+            grid_snap: false,
+            grid_size: 32.0,
+            angle_snap: false,
+            angle_step: 15.0,
+        }
+    }
+}
+
+impl TransformState {
+    /// Rounds a position along one axis to the grid, if grid snapping is on.
+    pub fn snap_position(&self, value: f32) -> f32 {
+        if self.grid_snap && self.grid_size > 0.0 {
+            (value / self.grid_size).round() * self.grid_size
+        } else {
+            value
+        }
+    }
+
+    /// Rounds an angle in radians to the angle step, if angle snapping is on.
+    pub fn snap_angle(&self, radians: f32) -> f32 {
+        let step = self.angle_step.to_radians();
+
+        if self.angle_snap && step > 0.0 {
+            (radians / step).round() * step
+        } else {
+            radians
+        }
+    }
+
+    pub fn clamp_scale(scale: f32) -> f32 {
+        scale.max(MIN_SCALE).min(MAX_SCALE)
+    }
+}
+
+pub struct TransformWindow<'a> {
+    state: &'a mut TransformState,
+    changed: bool,
+}
+
+impl<'a> TransformWindow<'a> {
+    pub fn new(state: &'a mut TransformState) -> Self {
+        Self { state, changed: false }
+    }
+
+    /// Whether the placement was edited, which needs applying to the reference.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+impl<'a> View for TransformWindow<'a> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let state = &mut *self.state;
+        let mut changed = false;
+
+        match &state.target {
+            Some((_, label)) => ui.label(label),
+            None => ui.label("Click an object in the viewport to select it."),
+        };
+
+        ui.separator();
+
+        if state.target.is_some() {
+            let placement = &mut state.placement;
+
+            egui::Grid::new("transform_grid").show(ui, |ui| {
+                ui.label("");
+                ui.label("X");
+                ui.label("Y");
+                ui.label("Z");
+                ui.end_row();
+
+                ui.label("Position");
+                for value in placement.position.iter_mut() {
+                    changed |= ui.add(egui::DragValue::new(value).speed(1.0)).changed();
+                }
+                ui.end_row();
+
+                // Rotations are stored in radians but edited in degrees.
+                ui.label("Rotation");
+                for value in placement.rotation.iter_mut() {
+                    let mut degrees = value.to_degrees();
+
+                    if ui.add(egui::DragValue::new(&mut degrees).speed(0.5)).changed() {
+                        *value = degrees.to_radians();
+                        changed = true;
+                    }
+                }
+                ui.end_row();
+
+                ui.label("Scale");
+                if ui.add(egui::DragValue::new(&mut placement.scale).speed(0.01)).changed() {
+                    placement.scale = TransformState::clamp_scale(placement.scale);
+                    changed = true;
+                }
+                ui.end_row();
+            });
+
+            ui.separator();
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Gizmo");
+            ui.radio_value(&mut state.mode, GizmoMode::Translate, "Move (1)");
+            ui.radio_value(&mut state.mode, GizmoMode::Rotate, "Rotate (2)");
+            ui.radio_value(&mut state.mode, GizmoMode::Scale, "Scale (3)");
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut state.grid_snap, "Snap to grid");
+            ui.add(egui::DragValue::new(&mut state.grid_size).speed(1.0));
+            ui.label("units");
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut state.angle_snap, "Snap angles");
+            ui.add(egui::DragValue::new(&mut state.angle_step).speed(0.5));
+            ui.label("degrees");
+        });
+
+        self.changed = changed;
+    }
+}
+
+impl<'a> Window for TransformWindow<'a> {
+    fn name(&self) -> &'static str {
+        "Transform"
+    }
+
+    fn show(&mut self, ctx: &egui::CtxRef, open: &mut bool) {
+        egui::Window::new(self.name())
+            .open(open)
+            .default_width(DEFAULT_WIDTH)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
@@ -0,0 +1,115 @@
+//! Small editing widgets shared by the record editors.
+
+use std::ops::{BitAnd, BitOr, Not};
+
+use open_creation_data::esp::{Code, Text};
+
+const FORM_ID_WIDTH: f32 = 72.0;
+
+/// Looks up the editor ID of a record by form ID, for showing form IDs by name.
+pub type EditorIds<'a> = &'a dyn Fn(u32) -> Option<String>;
+
+/// Looks up the type of a record by form ID, for checking what a form ID refers to.
+pub type RecordCodes<'a> = &'a dyn Fn(u32) -> Option<Code>;
+
+/// Finds the form ID of a record by type and editor ID.
+pub type FindRecord<'a> = &'a dyn Fn(Code, &str) -> Option<u32>;
+
+/// A checkbox for one bit of a set of flags. Returns whether it was changed.
+pub fn flag_checkbox<T>(ui: &mut egui::Ui, flags: &mut T, flag: T, label: &str) -> bool
+where
+    T: Copy + Default + PartialEq + BitAnd<Output = T> + BitOr<Output = T> + Not<Output = T>,
+{
+    let mut set = *flags & flag != T::default();
+
+    if !ui.checkbox(&mut set, label).changed() {
+        return false;
+    }
+
+    *flags = if set { *flags | flag } else { *flags & !flag };
+    true
+}
+
+/// A form ID typed in hex, followed by the editor ID of the record it refers to. Returns whether it was changed.
+pub fn form_id_edit(ui: &mut egui::Ui, form_id: &mut u32, editor_ids: EditorIds) -> bool {
+    let mut text = format!("{:08X}", form_id);
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        let response = ui.add(egui::TextEdit::singleline(&mut text).desired_width(FORM_ID_WIDTH));
+
+        if response.changed() {
+            let digits = text.trim();
+            let parsed = if digits.is_empty() {
+                Ok(0)
+            } else {
+                u32::from_str_radix(digits, 16)
+            };
+
+            if let Ok(value) = parsed {
+                changed = value != *form_id;
+                *form_id = value;
+            }
+        }
+
+        match *form_id {
+            0 => ui.label("None"),
+            form_id => ui.label(editor_ids(form_id).unwrap_or_else(|| "(not found)".to_string())),
+        };
+    });
+
+    changed
+}
+
+/// Picks an alias by ID, or edits the ID as a number when the aliases are not known. Returns whether it was
+/// changed.
+pub fn alias_edit(ui: &mut egui::Ui, id: egui::Id, alias: &mut u32, aliases: &[(u32, String)]) -> bool {
+    if aliases.is_empty() {
+        return ui.add(egui::DragValue::new(alias)).changed();
+    }
+
+    let before = *alias;
+    let selected = aliases.iter().find(|(id, _)| id == alias).map_or_else(
+        || format!("Missing ({})", alias),
+        |(id, name)| format!("{} ({})", name, id),
+    );
+
+    egui::combo_box(ui, id, selected, |ui| {
+        for (id, name) in aliases {
+            ui.selectable_value(alias, *id, format!("{} ({})", name, id));
+        }
+    });
+
+    *alias != before
+}
+
+/// Edits an optional text field, which is dropped when emptied. Text from string tables is resolved when the
+/// record is read; an ID missing from the tables is shown as it is and replaced by any text typed. Returns whether
+/// it was changed.
+pub fn text_edit(ui: &mut egui::Ui, text: &mut Option<Text>, multiline: bool) -> bool {
+    let mut edited = match text {
+        Some(localized @ Text::Localized(_)) => {
+            ui.label(format!("{} is missing from the string tables", localized));
+            String::new()
+        }
+        Some(Text::Inline(inline)) => inline.clone(),
+        None => String::new(),
+    };
+
+    let response = if multiline {
+        ui.add(egui::TextEdit::multiline(&mut edited))
+    } else {
+        ui.add(egui::TextEdit::singleline(&mut edited))
+    };
+
+    if !response.changed() {
+        return false;
+    }
+
+    *text = if edited.is_empty() {
+        None
+    } else {
+        Some(Text::Inline(edited))
+    };
+    true
+}
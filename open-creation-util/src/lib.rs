@@ -3,3 +3,4 @@ mod settings;
 
 pub use log;
 pub use logger::Logger;
+pub use settings::{ArchiveSettings, PapyrusSettings, Settings};
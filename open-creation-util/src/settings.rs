@@ -6,29 +6,124 @@ static SETTINGS_FILENAME: &'static str = "../config.toml";
 
 #[derive(Debug)]
 pub struct Settings {
-    data_path: String,
+    pub data_path: String,
+    pub load_order: Vec<String>,
+    pub active_plugin: Option<String>,
+    /// The language of the string tables localized plugins read their text from, e.g. `English`.
+    pub language: String,
+    pub archive: ArchiveSettings,
+    pub papyrus: PapyrusSettings,
+}
+
+#[derive(Clone, Debug)]
+pub struct ArchiveSettings {
+    pub compress: bool,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PapyrusSettings {
+    /// The Papyrus compiler executable, if one is configured.
+    pub compiler: Option<String>,
+    /// The flags file, looked for in the import folders.
+    pub flags: String,
+    /// Folders of sources to import besides `Scripts/Source` in the data folder.
+    pub imports: Vec<String>,
 }
 
 impl Default for Settings {
     fn default() -> Self {
-        Self { data_path: "/Data/".to_string() }
+        Self {
+            data_path: "/Data/".to_string(),
+            load_order: ["Skyrim.esm", "Update.esm", "Dawnguard.esm", "HearthFires.esm", "Dragonborn.esm"]
+                .iter()
+                .map(|plugin| plugin.to_string())
+                .collect(),
+            active_plugin: None,
+            language: "English".to_string(),
+            archive: ArchiveSettings::default(),
+            papyrus: PapyrusSettings::default(),
+        }
+    }
+}
+
+impl Default for ArchiveSettings {
+    fn default() -> Self {
+        Self {
+            compress: true,
+            include: vec![],
+            exclude: vec![],
+        }
+    }
+}
+
+impl Default for PapyrusSettings {
+    fn default() -> Self {
+        Self {
+            compiler: None,
+            flags: "TESV_Papyrus_Flags.flg".to_string(),
+            imports: vec![],
+        }
     }
 }
 
 impl Settings {
     pub fn load() -> Settings {
         let mut input = String::new();
-        File::open(Path::new(SETTINGS_FILENAME)).and_then(|mut f| f.read_to_string(&mut input)).unwrap();
 
+        if let Err(e) = File::open(Path::new(SETTINGS_FILENAME)).and_then(|mut f| f.read_to_string(&mut input)) {
+            log::warn!("Could not read {}, using default settings: {}", SETTINGS_FILENAME, e);
+            return Settings::default();
+        }
+
+        Settings::parse(&input)
+    }
+
+    /// Reads settings from the contents of a config file, using the defaults for any that are missing.
+    fn parse(input: &str) -> Settings {
         let mut settings = Settings::default();
 
-        if let Ok(toml) = input.parse() {
-            if let Toml::Table(toml) = toml {
-                if let Some(paths) = toml.get("paths") {
-                    if let Some(Toml::String(data_path)) = paths.get("data_path") {
-                        settings.data_path = data_path.to_string();
-                    }
+        if let Ok(Toml::Table(toml)) = input.parse() {
+            if let Some(paths) = toml.get("paths") {
+                if let Some(Toml::String(data_path)) = paths.get("data_path") {
+                    settings.data_path = data_path.to_string();
+                }
+            }
+
+            if let Some(plugins) = toml.get("plugins") {
+                if let Some(Toml::Array(_)) = plugins.get("load_order") {
+                    settings.load_order = string_list(plugins.get("load_order"));
                 }
+
+                if let Some(Toml::String(active)) = plugins.get("active") {
+                    settings.active_plugin = Some(active.to_string());
+                }
+
+                if let Some(Toml::String(language)) = plugins.get("language") {
+                    settings.language = language.to_string();
+                }
+            }
+
+            if let Some(archive) = toml.get("archive") {
+                if let Some(Toml::Boolean(compress)) = archive.get("compress") {
+                    settings.archive.compress = *compress;
+                }
+
+                settings.archive.include = string_list(archive.get("include"));
+                settings.archive.exclude = string_list(archive.get("exclude"));
+            }
+
+            if let Some(papyrus) = toml.get("papyrus") {
+                if let Some(Toml::String(compiler)) = papyrus.get("compiler") {
+                    settings.papyrus.compiler = Some(compiler.to_string());
+                }
+
+                if let Some(Toml::String(flags)) = papyrus.get("flags") {
+                    settings.papyrus.flags = flags.to_string();
+                }
+
+                settings.papyrus.imports = string_list(papyrus.get("imports"));
             }
         }
 
@@ -36,6 +131,17 @@ impl Settings {
     }
 }
 
+fn string_list(value: Option<&Toml>) -> Vec<String> {
+    match value {
+        Some(Toml::Array(values)) => values
+            .iter()
+            .filter_map(|value| value.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::Settings;
 
@@ -50,4 +156,20 @@ mod tests {
         let settings = Settings::default();
         assert_eq!(settings.data_path.as_str(), "/Data/");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn archive() {
+        let settings = Settings::parse(
+            r#"
+            [archive]
+            compress = false
+            include = ["meshes/*", "textures/*"]
+            exclude = ["scripts/source/*"]
+            "#,
+        );
+        assert!(!settings.archive.compress);
+        assert_eq!(settings.archive.include, vec!["meshes/*", "textures/*"]);
+        assert_eq!(settings.archive.exclude, vec!["scripts/source/*"]);
+        assert_eq!(settings.data_path.as_str(), "/Data/");
+    }
+}
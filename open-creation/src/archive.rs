@@ -0,0 +1,65 @@
+use std::path::{Path, PathBuf};
+
+use open_creation_data::bsa::{self, PackOptions, Version};
+use open_creation_ui::CreateArchiveOptions;
+use open_creation_util::{log, Settings};
+
+pub fn options_from_settings(settings: &Settings) -> CreateArchiveOptions {
+    CreateArchiveOptions {
+        archive_name: settings
+            .active_plugin
+            .as_deref()
+            .map(bsa::archive_name_for_plugin)
+            .unwrap_or_default(),
+        source_path: settings.data_path.clone(),
+        special_edition: true,
+        compress: settings.archive.compress,
+        include: settings.archive.include.join("\n"),
+        exclude: settings.archive.exclude.join("\n"),
+    }
+}
+
+pub fn pack_options(options: &CreateArchiveOptions) -> PackOptions {
+    PackOptions {
+        version: if options.special_edition {
+            Version::SkyrimSpecialEdition
+        } else {
+            Version::Skyrim
+        },
+        compress: options.compress,
+        include: CreateArchiveOptions::patterns(&options.include),
+        exclude: CreateArchiveOptions::patterns(&options.exclude),
+    }
+}
+
+/// The archive is written next to the plugin in the data folder, so the game picks it up.
+pub fn destination(data_path: &str, archive_name: &str) -> PathBuf {
+    let mut name = archive_name.trim().to_string();
+
+    if !name.to_lowercase().ends_with(".bsa") {
+        name = bsa::archive_name_for_plugin(&name);
+    }
+
+    Path::new(data_path).join(name)
+}
+
+/// Packs the archive on a background thread, reporting the result to the log.
+pub fn create_in_background(data_path: &str, options: &CreateArchiveOptions) {
+    let source = PathBuf::from(options.source_path.trim());
+    let destination = destination(data_path, &options.archive_name);
+    let pack_options = pack_options(options);
+
+    log::info!("Creating archive {}", destination.to_string_lossy());
+
+    std::thread::spawn(
+        move || match bsa::pack_directory(&source, &destination, &pack_options) {
+            Ok(summary) => log::info!(
+                "Created {} ({} files, {} bytes)",
+                summary.archive.to_string_lossy(),
+                summary.files,
+                summary.bytes
+            ),
+            Err(e) => log::error!("Error creating archive {}: {}", destination.to_string_lossy(), e),
+        },
+    );
+}
@@ -0,0 +1,224 @@
+//! Viewport navigation: orbit, pan, dolly, fly and frame selected.
+//!
+//! - Right drag orbits around the focus point.
+//! - Right drag while holding W/A/S/D (or Q/E for down/up) flies: the drag turns the camera where it stands.
+//!   Shift moves faster.
+//! - Middle drag pans, and the mouse wheel dollies towards or away from the focus point.
+//! - F frames the selection, which also becomes the focus point.
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::camera::PerspectiveProjection,
+};
+use bevy_egui::EguiContext;
+
+use crate::{model::Bounds, selection::Selected};
+
+const ORBIT_SPEED: f32 = 0.005;
+const PAN_SPEED: f32 = 0.0015;
+const DOLLY_SPEED: f32 = 0.1;
+const FLY_SPEED: f32 = 8.0;
+const FAST_MULTIPLIER: f32 = 4.0;
+const MIN_DISTANCE: f32 = 0.1;
+const MAX_PITCH: f32 = 1.55;
+
+/// Sent to move the focus point to a sphere and back the camera off until it fits the view.
+pub struct FrameRequest {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+/// The camera's position, stored as a point it orbits and an offset from that point.
+pub struct ViewportCamera {
+    pub focus: Vec3,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    flying: bool,
+}
+
+impl ViewportCamera {
+    fn rotation(&self) -> Quat {
+        Quat::from_rotation_y(self.yaw) * Quat::from_rotation_x(self.pitch)
+    }
+
+    fn position(&self) -> Vec3 {
+        self.focus + self.rotation() * Vec3::new(0.0, 0.0, self.distance)
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform {
+            translation: self.position(),
+            rotation: self.rotation(),
+            ..Default::default()
+        }
+    }
+
+    /// Turns the camera in place, moving the focus point instead of the camera.
+    fn look(&mut self, delta: Vec2) {
+        let position = self.position();
+        self.turn(delta);
+        self.focus = position - self.rotation() * Vec3::new(0.0, 0.0, self.distance);
+    }
+
+    fn turn(&mut self, delta: Vec2) {
+        self.yaw -= delta.x * ORBIT_SPEED;
+        self.pitch = (self.pitch - delta.y * ORBIT_SPEED).max(-MAX_PITCH).min(MAX_PITCH);
+    }
+
+    fn frame(&mut self, center: Vec3, radius: f32, field_of_view: f32) {
+        self.focus = center;
+        self.distance = (radius.max(MIN_DISTANCE) / (field_of_view * 0.5).sin()).max(MIN_DISTANCE);
+    }
+}
+
+impl Default for ViewportCamera {
+    fn default() -> Self {
+        Self {
+            focus: Vec3::default(),
+            distance: 6.0,
+            yaw: -0.38,
+            pitch: -0.43,
+            flying: false,
+        }
+    }
+}
+
+pub fn spawn_camera(mut commands: Commands) {
+    let camera = ViewportCamera::default();
+
+    commands
+        .spawn_bundle(PerspectiveCameraBundle {
+            transform: camera.transform(),
+            perspective_projection: PerspectiveProjection {
+                near: 0.01,
+                far: 10_000.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(camera);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn camera_controls(
+    egui_context: Res<EguiContext>,
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    mut motion_events: EventReader<MouseMotion>,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut frame_requests: EventReader<FrameRequest>,
+    selection: Query<(&GlobalTransform, &Bounds), With<Selected>>,
+    mut cameras: Query<(&mut ViewportCamera, &mut Transform, &PerspectiveProjection)>,
+) {
+    let ctx = egui_context.ctx();
+    let pointer = !ctx.wants_pointer_input();
+    let keyboard = !ctx.wants_keyboard_input();
+
+    let motion = motion_events
+        .iter()
+        .fold(Vec2::default(), |motion, event| motion + event.delta);
+    let scroll: f32 = wheel_events
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 40.0,
+        })
+        .sum();
+
+    let mut frame = frame_requests
+        .iter()
+        .last()
+        .map(|request| (request.center, request.radius));
+
+    if keyboard && keys.just_pressed(KeyCode::F) {
+        frame = selection_sphere(&selection).or(frame);
+    }
+
+    for (mut camera, mut transform, projection) in cameras.iter_mut() {
+        if let Some((center, radius)) = frame {
+            camera.frame(center, radius, projection.fov);
+        }
+
+        let movement_keys = [KeyCode::W, KeyCode::A, KeyCode::S, KeyCode::D, KeyCode::Q, KeyCode::E];
+
+        if !buttons.pressed(MouseButton::Right) {
+            camera.flying = false;
+        } else if pointer && keyboard && movement_keys.iter().any(|&key| keys.pressed(key)) {
+            // Like orbiting, flying only starts from a drag over the viewport, not over a window.
+            camera.flying = true;
+        }
+
+        if camera.flying {
+            if keyboard {
+                let rotation = camera.rotation();
+                let mut direction = Vec3::default();
+
+                for &(key, axis) in &[
+                    (KeyCode::W, -Vec3::unit_z()),
+                    (KeyCode::S, Vec3::unit_z()),
+                    (KeyCode::A, -Vec3::unit_x()),
+                    (KeyCode::D, Vec3::unit_x()),
+                ] {
+                    if keys.pressed(key) {
+                        direction += rotation * axis;
+                    }
+                }
+
+                if keys.pressed(KeyCode::E) {
+                    direction += Vec3::unit_y();
+                }
+
+                if keys.pressed(KeyCode::Q) {
+                    direction -= Vec3::unit_y();
+                }
+
+                let fast = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+                let speed = FLY_SPEED * if fast { FAST_MULTIPLIER } else { 1.0 };
+
+                if direction.length_squared() > 0.0 {
+                    camera.focus += direction.normalize() * speed * time.delta_seconds();
+                }
+            }
+
+            camera.look(motion);
+        } else if pointer && buttons.pressed(MouseButton::Right) {
+            camera.turn(motion);
+        } else if pointer && buttons.pressed(MouseButton::Middle) {
+            let rotation = camera.rotation();
+            let distance = camera.distance;
+            camera.focus += (rotation * Vec3::new(-motion.x, motion.y, 0.0)) * distance * PAN_SPEED;
+        }
+
+        if pointer && scroll != 0.0 {
+            camera.distance = (camera.distance * (1.0 - scroll * DOLLY_SPEED)).max(MIN_DISTANCE);
+        }
+
+        *transform = camera.transform();
+    }
+}
+
+/// A sphere around everything selected, in world space.
+fn selection_sphere(selection: &Query<(&GlobalTransform, &Bounds), With<Selected>>) -> Option<(Vec3, f32)> {
+    let spheres: Vec<(Vec3, f32)> = selection
+        .iter()
+        .map(|(transform, bounds)| {
+            let scale = transform.scale.x.max(transform.scale.y).max(transform.scale.z);
+            (transform.mul_vec3(bounds.center()), bounds.radius() * scale)
+        })
+        .collect();
+
+    if spheres.is_empty() {
+        return None;
+    }
+
+    let center = spheres.iter().fold(Vec3::default(), |sum, (center, _)| sum + *center) / spheres.len() as f32;
+    let radius = spheres
+        .iter()
+        .map(|(sphere_center, radius)| (*sphere_center - center).length() + radius)
+        .fold(0.0, f32::max);
+
+    Some((center, radius))
+}
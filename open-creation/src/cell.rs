@@ -0,0 +1,176 @@
+//! Loading a cell's placed references into the viewport.
+
+use bevy::prelude::*;
+use open_creation_data::esp::{code_name, Cell, Placement, Reference};
+use open_creation_ui::ReferenceItem;
+use open_creation_util::log;
+
+use crate::{
+    camera::FrameRequest,
+    data_files::DataFilesResource,
+    model::{self, Bounds, ModelCache},
+    preview::Preview,
+    records::{RecordSelected, RecordsResource},
+    ui_state,
+};
+
+/// Sent to replace the objects in the viewport with the references of a cell, with the cell's form ID.
+pub struct LoadCell(pub u32);
+
+/// Marks the root entity of a placed reference, with the reference's form ID.
+pub struct PlacedReference(pub u32);
+
+/// Sent when a reference is moved, rotated or scaled, to update it in the viewport and the active plugin.
+pub struct PlacementEdited {
+    pub form_id: u32,
+    pub placement: Placement,
+}
+
+/// The cell currently shown in the viewport.
+#[derive(Default)]
+pub struct LoadedCell {
+    pub form_id: Option<u32>,
+}
+
+/// Spawns a cell's references when a cell is loaded or a CELL record is selected, framing the camera on them.
+#[allow(clippy::too_many_arguments)]
+pub fn load_cell(
+    mut commands: Commands,
+    mut load_requests: EventReader<LoadCell>,
+    mut record_selections: EventReader<RecordSelected>,
+    records: Res<RecordsResource>,
+    data_files: Res<DataFilesResource>,
+    mut models: ResMut<ModelCache>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut loaded_cell: ResMut<LoadedCell>,
+    mut ui_state: ResMut<ui_state::State>,
+    mut frame_requests: EventWriter<FrameRequest>,
+    loaded: Query<Entity, Or<(With<PlacedReference>, With<Preview>)>>,
+) {
+    let load_order = &records.load_order;
+    let is_cell = |form_id: u32| {
+        load_order
+            .record(form_id)
+            .map(|record| &record.code == b"CELL")
+            .unwrap_or(false)
+    };
+    let selected_cells = record_selections
+        .iter()
+        .map(|RecordSelected(form_id)| *form_id)
+        .filter(|&form_id| is_cell(form_id));
+
+    let cell = match load_requests
+        .iter()
+        .map(|LoadCell(form_id)| *form_id)
+        .chain(selected_cells)
+        .last()
+    {
+        Some(cell) => cell,
+        None => return,
+    };
+
+    for entity in loaded.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    loaded_cell.form_id = Some(cell);
+
+    let references = load_order.cell_references(cell);
+    let mut positions = vec![];
+    let mut items = vec![];
+
+    for record in &references {
+        let reference = match Reference::from_record(record) {
+            Some(reference) => reference,
+            None => {
+                log::warn!("{} {:08X} has no base object", code_name(record.code), record.form_id);
+                continue;
+            }
+        };
+
+        items.push(ReferenceItem {
+            form_id: reference.form_id,
+            code: code_name(reference.code),
+            base: load_order
+                .editor_id(reference.base)
+                .unwrap_or_else(|| format!("{:08X}", reference.base)),
+        });
+
+        // Markers, sounds and other objects without a model have nothing to show yet.
+        let path = match load_order.record(reference.base).and_then(|base| base.model_path()) {
+            Some(path) => path,
+            None => continue,
+        };
+
+        if let Some(model) = models.load(&path, &data_files.vfs, &mut meshes, &mut materials, &mut textures) {
+            let transform = model::placement_transform(&reference.placement);
+            let entity = model::spawn(&mut commands, model, transform);
+            commands.entity(entity).insert(PlacedReference(reference.form_id));
+
+            positions.push(transform.mul_vec3(model.bounds.center()));
+        }
+    }
+
+    let label = match load_order.record(cell).map(|record| Cell::from_record(record, None)) {
+        Some(Cell {
+            editor_id: Some(editor_id),
+            ..
+        }) => editor_id,
+        Some(Cell { grid: Some((x, y)), .. }) => format!("{}, {}", x, y),
+        _ => format!("{:08X}", cell),
+    };
+
+    log::info!(
+        "Loaded cell {} ({:08X}): {} references, {} with models",
+        label,
+        cell,
+        references.len(),
+        positions.len()
+    );
+
+    let cell_view = &mut ui_state.cell_view;
+    cell_view.selected_cell = Some(cell);
+    cell_view.selected_reference = None;
+    cell_view.loaded_cell = Some(label);
+    cell_view.references = items;
+    ui_state.transform.target = None;
+
+    if !positions.is_empty() {
+        let bounds = Bounds::from_points(positions.into_iter());
+
+        frame_requests.send(FrameRequest {
+            center: bounds.center(),
+            radius: bounds.radius(),
+        });
+    }
+}
+
+/// Moves edited references in the viewport and writes their placement to the active plugin, copying the
+/// reference into it first if it comes from another plugin.
+pub fn apply_placement_edits(
+    mut edits: EventReader<PlacementEdited>,
+    mut records: ResMut<RecordsResource>,
+    mut placed: Query<(&PlacedReference, &mut Transform)>,
+) {
+    for edit in edits.iter() {
+        for (PlacedReference(form_id), mut transform) in placed.iter_mut() {
+            if *form_id == edit.form_id {
+                *transform = model::placement_transform(&edit.placement);
+            }
+        }
+
+        match records.load_order.override_record(edit.form_id) {
+            Some(record) => {
+                if let Err(e) = edit.placement.write(record) {
+                    log::error!("Error writing placement of {:08X}: {}", edit.form_id, e);
+                }
+            }
+            None => log::warn!(
+                "Cannot save the placement of {:08X} without an active plugin",
+                edit.form_id
+            ),
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Headless commands, run in place of the editor when the first argument names one.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use open_creation_data::{
+    bsa::{self, PackOptions, Version},
+    esp::LoadOrder,
+    map::{self, MapOptions},
+};
+use open_creation_util::Settings;
+
+const USAGE: &str = "\
+Usage: open_creation <command> [options]
+
+Commands:
+    create-archive    Pack loose assets into a .bsa named after the active plugin
+        --plugin <name>       Plugin the archive belongs to (defaults to the active plugin)
+        --source <dir>        Folder to pack (defaults to the data path)
+        --output <dir>        Folder to write the archive to (defaults to the data path)
+        --format <le|se>      Archive format (defaults to se)
+        --compress            Compress the archive
+        --no-compress         Store files uncompressed
+        --include <pattern>   Only pack matching files; may be repeated
+        --exclude <pattern>   Skip matching files; may be repeated
+    export-map        Render a worldspace's terrain from above to a PNG, without a GPU
+        --world <id>          Editor ID or hex form ID of the worldspace
+        --output <file>       PNG file to write (defaults to <world>.png)
+        --scale <pixels>      Pixels along each side of a cell (defaults to 32)
+        --grid                Draw cell grid lines
+        --markers             Draw map markers
+        --locations           Tint cells by location and outline where locations meet
+        --regions             Outline region areas
+    help              Show this message";
+
+/// Runs a headless command, returning its exit code, or `None` if the arguments do not name a command.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, options) = args.split_first()?;
+
+    let result = match command.as_str() {
+        "create-archive" => create_archive(&Settings::load(), options),
+        "export-map" => export_map(options),
+        "help" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => return None,
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            eprintln!("{}", USAGE);
+            Some(1)
+        }
+    }
+}
+
+/// A minimal `--flag value` parser over the arguments following a command.
+struct Options<'a> {
+    args: std::slice::Iter<'a, String>,
+}
+
+impl<'a> Options<'a> {
+    fn new(args: &'a [String]) -> Self {
+        Self { args: args.iter() }
+    }
+
+    fn next_flag(&mut self) -> Option<&'a str> {
+        self.args.next().map(|arg| arg.as_str())
+    }
+
+    fn value(&mut self, flag: &str) -> Result<String, String> {
+        self.args
+            .next()
+            .cloned()
+            .ok_or_else(|| format!("{} expects a value", flag))
+    }
+}
+
+fn create_archive(settings: &Settings, args: &[String]) -> Result<(), String> {
+    let mut plugin = settings.active_plugin.clone();
+    let mut source = PathBuf::from(&settings.data_path);
+    let mut output = PathBuf::from(&settings.data_path);
+    let mut options = PackOptions {
+        compress: settings.archive.compress,
+        ..Default::default()
+    };
+    let mut include = vec![];
+    let mut exclude = vec![];
+
+    let mut args = Options::new(args);
+
+    while let Some(flag) = args.next_flag() {
+        match flag {
+            "--plugin" => plugin = Some(args.value(flag)?),
+            "--source" => source = PathBuf::from(args.value(flag)?),
+            "--output" => output = PathBuf::from(args.value(flag)?),
+            "--format" => {
+                options.version = match args.value(flag)?.to_lowercase().as_str() {
+                    "le" | "104" => Version::Skyrim,
+                    "se" | "105" => Version::SkyrimSpecialEdition,
+                    other => return Err(format!("unknown archive format '{}'", other)),
+                }
+            }
+            "--compress" => options.compress = true,
+            "--no-compress" => options.compress = false,
+            "--include" => include.push(args.value(flag)?),
+            "--exclude" => exclude.push(args.value(flag)?),
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+
+    let plugin = plugin.ok_or_else(|| "no active plugin; pass --plugin".to_string())?;
+
+    options.include = if include.is_empty() {
+        settings.archive.include.clone()
+    } else {
+        include
+    };
+    options.exclude = if exclude.is_empty() {
+        settings.archive.exclude.clone()
+    } else {
+        exclude
+    };
+
+    let archive = output.join(bsa::archive_name_for_plugin(&plugin));
+    let summary = bsa::pack_directory(&source, &archive, &options).map_err(|e| e.to_string())?;
+
+    println!(
+        "Created {} ({} files, {} bytes)",
+        summary.archive.to_string_lossy(),
+        summary.files,
+        summary.bytes
+    );
+
+    Ok(())
+}
+
+fn export_map(args: &[String]) -> Result<(), String> {
+    let settings = Settings::load();
+
+    let mut world = None;
+    let mut output = None;
+    let mut options = MapOptions::default();
+
+    let mut args = Options::new(args);
+
+    while let Some(flag) = args.next_flag() {
+        match flag {
+            "--world" => world = Some(args.value(flag)?),
+            "--output" => output = Some(PathBuf::from(args.value(flag)?)),
+            "--scale" => {
+                let value = args.value(flag)?;
+                options.pixels_per_cell = value
+                    .parse()
+                    .map_err(|_| format!("'{}' is not a number of pixels", value))?;
+            }
+            "--grid" => options.grid = true,
+            "--markers" => options.markers = true,
+            "--locations" => options.locations = true,
+            "--regions" => options.regions = true,
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+
+    let world = world.ok_or_else(|| "no worldspace; pass --world".to_string())?;
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("{}.png", world)));
+
+    let load_order = LoadOrder::load(
+        Path::new(&settings.data_path),
+        &settings.load_order,
+        settings.active_plugin.as_deref(),
+    );
+
+    let form_id = match load_order.find_by_editor_id(*b"WRLD", &world) {
+        Some(record) => record.form_id,
+        None => u32::from_str_radix(world.trim_start_matches("0x"), 16)
+            .map_err(|_| format!("no worldspace named '{}'", world))?,
+    };
+
+    let map = map::render_world_map(&load_order, form_id, &options).map_err(|e| e.to_string())?;
+
+    let file = File::create(&output).map_err(|e| format!("cannot create {}: {}", output.to_string_lossy(), e))?;
+    let mut writer = BufWriter::new(file);
+    map.image
+        .write_png(&mut writer)
+        .and_then(|()| writer.flush())
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "Wrote {} ({} by {} pixels, {} cells with terrain)",
+        output.to_string_lossy(),
+        map.image.width,
+        map.image.height,
+        map.cells
+    );
+
+    if options.markers {
+        println!("{} map markers", map.markers);
+    }
+
+    if options.locations {
+        println!("{} locations", map.locations);
+    }
+
+    if options.regions {
+        println!("{} regions", map.regions);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use open_creation_data::bsa::Archive;
+    use std::fs;
+
+    #[test]
+    fn create_archive_packs_the_source_folder() {
+        let root = std::env::temp_dir().join(format!("open_creation_cli_{}", std::process::id()));
+        let source = root.join("Loose");
+        let output = root.join("Data");
+        fs::create_dir_all(source.join("textures")).unwrap();
+        fs::create_dir_all(&output).unwrap();
+        fs::write(source.join("textures").join("bucket.dds"), vec![7u8; 256]).unwrap();
+        fs::write(source.join("notes.txt"), b"not for release").unwrap();
+
+        let settings = Settings {
+            data_path: output.to_string_lossy().into_owned(),
+            active_plugin: Some("MyMod.esp".to_string()),
+            ..Settings::default()
+        };
+        let args: Vec<String> = vec![
+            "--source",
+            &source.to_string_lossy(),
+            "--format",
+            "le",
+            "--exclude",
+            "*.txt",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+        let result = create_archive(&settings, &args);
+        let archive = Archive::open(&output.join("MyMod.bsa"));
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(result, Ok(()));
+        let archive = archive.unwrap();
+        assert_eq!(archive.version(), Version::Skyrim);
+        assert_eq!(archive.entries().len(), 1);
+        assert!(archive.find("textures\\bucket.dds").is_some());
+    }
+}
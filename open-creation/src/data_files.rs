@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use open_creation_data::{
+    papyrus,
+    script_library::ScriptLibrary,
+    vfs::{FileTree, VirtualFileSystem},
+};
+use open_creation_ui::ArchiveBrowserAction;
+use open_creation_util::{log, Settings};
+
+pub struct DataFilesResource {
+    pub vfs: VirtualFileSystem,
+    pub tree: FileTree,
+    /// The compiled scripts, read as they are first shown.
+    pub scripts: ScriptLibrary,
+    /// The names of the Papyrus sources.
+    pub sources: Vec<String>,
+}
+
+/// Sent when an asset is chosen for previewing, with its normalised data-relative path.
+pub struct AssetSelected(pub String);
+
+impl DataFilesResource {
+    pub fn load(settings: &Settings) -> Self {
+        let mut plugins = settings.load_order.clone();
+
+        if let Some(active) = &settings.active_plugin {
+            if !plugins.iter().any(|plugin| plugin.eq_ignore_ascii_case(active)) {
+                plugins.push(active.clone());
+            }
+        }
+
+        let vfs = match VirtualFileSystem::load(Path::new(&settings.data_path), &plugins) {
+            Ok(vfs) => {
+                log::info!(
+                    "Loaded {} archives and {} files from {}",
+                    vfs.archives().len(),
+                    vfs.paths().count(),
+                    settings.data_path
+                );
+                vfs
+            }
+            Err(e) => {
+                log::error!("Error reading data folder {}: {}", settings.data_path, e);
+                VirtualFileSystem::default()
+            }
+        };
+
+        let tree = vfs.tree();
+        let scripts = ScriptLibrary::new(&vfs);
+        let sources = papyrus::source_names(&vfs);
+
+        Self {
+            vfs,
+            tree,
+            scripts,
+            sources,
+        }
+    }
+
+    /// Reads compiled scripts that are about to be shown, if they have not been read yet.
+    pub fn load_scripts<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) {
+        for name in names {
+            self.scripts.load(&self.vfs, name);
+        }
+    }
+
+    pub fn handle(&self, action: ArchiveBrowserAction, extract_path: &str, selections: &mut Vec<AssetSelected>) {
+        match action {
+            ArchiveBrowserAction::Extract(paths) => {
+                let destination = Path::new(extract_path.trim());
+                let mut extracted = 0;
+
+                for path in &paths {
+                    match self.vfs.extract(path, destination) {
+                        Ok(_) => extracted += 1,
+                        Err(e) => log::error!("Error extracting {}: {}", path, e),
+                    }
+                }
+
+                log::info!("Extracted {} of {} files to {}", extracted, paths.len(), extract_path);
+            }
+            ArchiveBrowserAction::Preview(path) => selections.push(AssetSelected(path)),
+        }
+    }
+}
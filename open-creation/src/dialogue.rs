@@ -0,0 +1,251 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+};
+
+use bevy::prelude::*;
+use open_creation_data::{
+    esp::{Dialogue, Info, LoadOrder, Response, Topic},
+    voice_script::VoiceScript,
+};
+use open_creation_ui::{DialogueAction, DialogueState};
+use open_creation_util::log;
+
+use crate::{
+    data_files::DataFilesResource,
+    records::{RecordSelected, RecordsResource},
+    ui_state,
+};
+
+/// Sent with an action from the Dialogue window.
+pub struct DialogueEdit(pub DialogueAction);
+
+fn load(state: &mut DialogueState, load_order: &LoadOrder, quest: u32) -> bool {
+    match Dialogue::load(load_order, quest) {
+        Ok(dialogue) => {
+            state.open(dialogue);
+            true
+        }
+        Err(e) => {
+            log::error!("Error reading the dialogue of quest {:08X}: {}", quest, e);
+            false
+        }
+    }
+}
+
+/// Shows the dialogue of QUST records selected in the tree view, and of the quest of selected DIAL records, in
+/// the Dialogue window once it is open.
+pub fn open_dialogue(
+    mut record_selections: EventReader<RecordSelected>,
+    records: Res<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+) {
+    let ui_state = &mut *ui_state;
+
+    for RecordSelected(form_id) in record_selections.iter() {
+        let load_order = &records.load_order;
+
+        let record = match load_order.record(*form_id) {
+            Some(record) => record,
+            None => continue,
+        };
+
+        let state = &mut ui_state.dialogue;
+
+        match &record.code {
+            b"QUST" => {
+                state.quest = *form_id;
+                if ui_state.show_dialogue {
+                    load(state, load_order, *form_id);
+                }
+            }
+            b"DIAL" => {
+                let quest = match Topic::from_record(record, false) {
+                    Ok(topic) => topic.quest,
+                    Err(e) => {
+                        log::error!("Error reading topic {:08X}: {}", form_id, e);
+                        continue;
+                    }
+                };
+
+                let loaded = state.dialogue.as_ref().map(|dialogue| dialogue.quest) == Some(quest);
+
+                if loaded || load(state, load_order, quest) {
+                    state.select_topic(*form_id);
+                    ui_state.show_dialogue = true;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Writes a quest's voice script to a CSV file, returning the voice files it found missing.
+fn export_voice_script(
+    load_order: &LoadOrder,
+    dialogue: &Dialogue,
+    data_files: &DataFilesResource,
+    path: &str,
+) -> io::Result<Vec<String>> {
+    let script = VoiceScript::build(load_order, dialogue, &data_files.vfs);
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    script.write_csv(&mut writer)?;
+
+    log::info!(
+        "Exported {} voice lines of {} to {}",
+        script.lines.len(),
+        script.quest,
+        path
+    );
+    Ok(script.missing().into_iter().map(String::from).collect())
+}
+
+/// Writes edits made in the Dialogue window to the active plugin, copying records there first if they come
+/// from another plugin, and creates new topics and INFOs there. Also exports voice scripts.
+pub fn apply_dialogue_edits(
+    mut edits: EventReader<DialogueEdit>,
+    mut records: ResMut<RecordsResource>,
+    data_files: Res<DataFilesResource>,
+    mut ui_state: ResMut<ui_state::State>,
+) {
+    let actions: Vec<&DialogueEdit> = edits.iter().collect();
+    if actions.is_empty() {
+        return;
+    }
+
+    let state = &mut ui_state.dialogue;
+
+    for DialogueEdit(action) in actions {
+        if let DialogueAction::Load(quest) = action {
+            load(state, &records.load_order, *quest);
+            continue;
+        }
+
+        let dialogue = match &mut state.dialogue {
+            Some(dialogue) => dialogue,
+            None => continue,
+        };
+
+        match action {
+            DialogueAction::Load(_) => {}
+            DialogueAction::ExportVoiceScript(path) => {
+                match export_voice_script(&records.load_order, dialogue, &data_files, path) {
+                    Ok(missing) => {
+                        if !missing.is_empty() {
+                            log::warn!("{} expected voice files are missing", missing.len());
+                        }
+                        state.missing_voice_files = Some(missing);
+                    }
+                    Err(e) => log::error!("Error exporting the voice script to {}: {}", path, e),
+                }
+            }
+            DialogueAction::TopicEdited(index) => {
+                let topic = match dialogue.topics.get(*index) {
+                    Some(topic) => topic,
+                    None => continue,
+                };
+
+                let renamed = records.load_order.editor_id(topic.form_id).as_deref() != Some(topic.editor_id.as_str());
+
+                match records.load_order.override_record(topic.form_id) {
+                    Some(record) => topic.write(record),
+                    None => {
+                        log::warn!("Cannot save topic {:08X} without an active plugin", topic.form_id);
+                        continue;
+                    }
+                }
+
+                if renamed {
+                    records.invalidate(*b"DIAL");
+                }
+            }
+            DialogueAction::InfoEdited { topic, info } => {
+                let info = match dialogue.topics.get(*topic).and_then(|topic| topic.infos.get(*info)) {
+                    Some(info) => info,
+                    None => continue,
+                };
+
+                match records.load_order.override_record(info.form_id) {
+                    Some(record) => info.write(record),
+                    None => log::warn!("Cannot save INFO {:08X} without an active plugin", info.form_id),
+                }
+            }
+            DialogueAction::InfosReordered(index) => {
+                let topic = match dialogue.topics.get_mut(*index) {
+                    Some(topic) => topic,
+                    None => continue,
+                };
+
+                for form_id in topic.link_infos() {
+                    let info = topic.infos.iter().find(|info| info.form_id == form_id);
+
+                    match (info, records.load_order.override_record(form_id)) {
+                        (Some(info), Some(record)) => info.write(record),
+                        _ => log::warn!("Cannot save INFO {:08X} without an active plugin", form_id),
+                    }
+                }
+            }
+            DialogueAction::CreateTopic { branch, editor_id } => {
+                let quest = dialogue.quest;
+                let topic = Topic::new(editor_id, quest, *branch);
+
+                let form_id = match records.load_order.add_record(*b"DIAL", &topic.subrecords()) {
+                    Some(form_id) => form_id,
+                    None => {
+                        log::warn!("Cannot create topic {} without an active plugin", editor_id);
+                        continue;
+                    }
+                };
+
+                // A new branch starts with its first topic.
+                if let Some(branch) = dialogue.branches.iter_mut().find(|b| b.form_id == *branch) {
+                    if branch.starting_topic == 0 {
+                        branch.starting_topic = form_id;
+                        if let Some(record) = records.load_order.override_record(branch.form_id) {
+                            branch.write(record);
+                        }
+                    }
+                }
+
+                log::info!("Created topic {} [{:08X}]", editor_id, form_id);
+                records.invalidate(*b"DIAL");
+
+                if load(state, &records.load_order, quest) {
+                    state.select_topic(form_id);
+                }
+            }
+            DialogueAction::CreateInfo(index) => {
+                let topic = match dialogue.topics.get_mut(*index) {
+                    Some(topic) => topic,
+                    None => continue,
+                };
+
+                let mut info = Info {
+                    previous: topic.infos.last().map_or(0, |info| info.form_id),
+                    responses: vec![Response::default()],
+                    ..Info::default()
+                };
+                info.number_responses();
+
+                info.form_id = match records.load_order.add_topic_info(topic.form_id, &info.subrecords()) {
+                    Some(form_id) => form_id,
+                    None => {
+                        log::warn!("Cannot add to topic {:08X} without an active plugin", topic.form_id);
+                        continue;
+                    }
+                };
+
+                topic.infos.push(info);
+                topic.info_count = topic.infos.len() as u32;
+
+                if let Some(record) = records.load_order.override_record(topic.form_id) {
+                    topic.write(record);
+                }
+
+                state.selected_topic = Some(*index);
+                state.selected_info = Some(topic.infos.len() - 1);
+            }
+        }
+    }
+}
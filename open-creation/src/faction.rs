@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+use open_creation_data::esp::FactionGraph;
+use open_creation_ui::FactionGraphAction;
+use open_creation_util::log;
+
+use crate::{
+    records::{RecordSelected, RecordsResource},
+    ui_state,
+};
+
+/// Sent with an action from the Faction Graph window.
+pub struct FactionGraphEdit(pub FactionGraphAction);
+
+/// Selects FACT records selected in the tree view in the Faction Graph window, once its factions are loaded.
+pub fn select_factions(
+    mut record_selections: EventReader<RecordSelected>,
+    records: Res<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+) {
+    for RecordSelected(form_id) in record_selections.iter() {
+        if matches!(records.load_order.record(*form_id), Some(record) if &record.code == b"FACT") {
+            ui_state.faction_graph.select_faction(*form_id);
+        }
+    }
+}
+
+/// Loads the factions for the Faction Graph window, and writes the factions edited in it to the active plugin,
+/// copying them there first if they come from other plugins.
+pub fn apply_faction_edits(
+    mut edits: EventReader<FactionGraphEdit>,
+    mut records: ResMut<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+) {
+    let state = &mut ui_state.faction_graph;
+
+    for FactionGraphEdit(action) in edits.iter() {
+        match action {
+            FactionGraphAction::Load => match FactionGraph::load(&records.load_order) {
+                Ok(graph) => state.open(graph),
+                Err(e) => log::error!("Error reading factions: {}", e),
+            },
+            FactionGraphAction::FactionEdited(form_id) => {
+                let faction = match state.graph.as_ref().and_then(|graph| graph.faction(*form_id)) {
+                    Some(faction) => faction,
+                    None => continue,
+                };
+
+                match records.load_order.override_record(*form_id) {
+                    Some(record) => faction.write(record),
+                    None => log::warn!("Cannot save faction {:08X} without an active plugin", form_id),
+                }
+            }
+        }
+    }
+}
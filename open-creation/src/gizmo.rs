@@ -0,0 +1,414 @@
+//! Translate, rotate and scale gizmos for the selected reference.
+//!
+//! The gizmo works in game axes, which map to viewport X, -Z and Y, so that dragging an axis changes one
+//! component of the reference's position or rotation. Keys 1, 2 and 3 switch between moving, rotating and
+//! scaling. Edits are sent as [`PlacementEdited`] when the drag ends.
+
+use std::f32::consts::PI;
+
+use bevy::{
+    prelude::*,
+    render::{camera::PerspectiveProjection, mesh::Indices, pipeline::PrimitiveTopology},
+};
+use bevy_egui::EguiContext;
+use open_creation_data::esp::Placement;
+use open_creation_ui::{GizmoMode, TransformState};
+
+use crate::{
+    camera::ViewportCamera,
+    cell::{PlacedReference, PlacementEdited},
+    model::{self, UNIT},
+    ray::Ray,
+    selection::Selected,
+    ui_state,
+};
+
+/// The gizmo's size as a fraction of its distance from the camera, so it stays the same size on screen.
+const SIZE: f32 = 0.15;
+const SHAFT_LENGTH: f32 = 1.0;
+const SHAFT_WIDTH: f32 = 0.025;
+const ARROW_SIZE: f32 = 0.08;
+const SCALE_HANDLE_SIZE: f32 = 0.12;
+const RING_RADIUS: f32 = 0.9;
+const RING_WIDTH: f32 = 0.03;
+const RING_SEGMENTS: usize = 64;
+/// How close the cursor's ray must pass to a handle to grab it, in gizmo units.
+const GRAB_DISTANCE: f32 = 0.08;
+
+const MODES: [GizmoMode; 3] = [GizmoMode::Translate, GizmoMode::Rotate, GizmoMode::Scale];
+
+/// The viewport direction of a game axis.
+fn axis_direction(axis: usize) -> Vec3 {
+    match axis {
+        0 => Vec3::unit_x(),
+        1 => -Vec3::unit_z(),
+        _ => Vec3::unit_y(),
+    }
+}
+
+/// Two directions perpendicular to a game axis, which span the plane its ring lies in.
+fn ring_plane(axis: usize) -> (Vec3, Vec3) {
+    let direction = axis_direction(axis);
+    let u = axis_direction((axis + 1) % 3);
+    (u, direction.cross(u))
+}
+
+fn axis_color(axis: usize) -> Color {
+    match axis {
+        0 => Color::rgb(0.9, 0.15, 0.15),
+        1 => Color::rgb(0.15, 0.8, 0.15),
+        _ => Color::rgb(0.2, 0.35, 0.95),
+    }
+}
+
+/// Marks the entity the gizmo's handles are children of.
+pub struct GizmoRoot;
+
+/// One handle of the gizmo: an axis of one of the modes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GizmoPart {
+    pub mode: GizmoMode,
+    pub axis: usize,
+}
+
+/// Where a drag started, to measure how far it has gone.
+enum Anchor {
+    /// A distance along the dragged axis from the gizmo's center, in viewport units.
+    Along(f32),
+    /// A direction from the center in the plane of the dragged ring.
+    Around(Vec3),
+}
+
+struct Drag {
+    part: GizmoPart,
+    form_id: u32,
+    start: Placement,
+    start_transform: Transform,
+    anchor: Anchor,
+}
+
+pub struct GizmoState {
+    hovered: Option<GizmoPart>,
+    drag: Option<Drag>,
+    materials: [Handle<StandardMaterial>; 3],
+    highlight: Handle<StandardMaterial>,
+}
+
+impl GizmoState {
+    /// Whether the cursor is over a handle or dragging one, in which case clicks belong to the gizmo.
+    pub fn is_busy(&self) -> bool {
+        self.hovered.is_some() || self.drag.is_some()
+    }
+}
+
+fn unlit(materials: &mut Assets<StandardMaterial>, color: Color) -> Handle<StandardMaterial> {
+    materials.add(StandardMaterial {
+        base_color: color,
+        unlit: true,
+        double_sided: true,
+        ..Default::default()
+    })
+}
+
+/// Handles start hidden until something is selected.
+fn hidden() -> Visible {
+    Visible {
+        is_visible: false,
+        is_transparent: false,
+    }
+}
+
+/// A flat ring around a game axis.
+fn ring_mesh(axis: usize) -> Mesh {
+    let (u, v) = ring_plane(axis);
+    let normal: [f32; 3] = axis_direction(axis).into();
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut indices = vec![];
+
+    for segment in 0..RING_SEGMENTS {
+        let angle = segment as f32 / RING_SEGMENTS as f32 * 2.0 * PI;
+        let direction = u * angle.cos() + v * angle.sin();
+        positions.push((direction * (RING_RADIUS - RING_WIDTH)).into());
+        positions.push((direction * (RING_RADIUS + RING_WIDTH)).into());
+
+        let inner = segment as u32 * 2;
+        let next = ((segment + 1) % RING_SEGMENTS) as u32 * 2;
+        indices.extend_from_slice(&[inner, inner + 1, next + 1, inner, next + 1, next]);
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, vec![normal; positions.len()]);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// A box stretched along a game axis.
+fn axis_box(axis: usize, length: f32, width: f32) -> Mesh {
+    let size: [f32; 3] = (Vec3::splat(width) + axis_direction(axis).abs() * (length - width)).into();
+    Mesh::from(shape::Box::new(size[0], size[1], size[2]))
+}
+
+pub fn spawn_gizmo(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let axis_materials = [
+        unlit(&mut materials, axis_color(0)),
+        unlit(&mut materials, axis_color(1)),
+        unlit(&mut materials, axis_color(2)),
+    ];
+
+    commands
+        .spawn_bundle((Transform::default(), GlobalTransform::default(), GizmoRoot))
+        .with_children(|parent| {
+            for &mode in &MODES {
+                for (axis, material) in axis_materials.iter().enumerate() {
+                    let direction = axis_direction(axis);
+                    let part = GizmoPart { mode, axis };
+                    let material = material.clone();
+
+                    if mode == GizmoMode::Rotate {
+                        parent
+                            .spawn_bundle(PbrBundle {
+                                mesh: meshes.add(ring_mesh(axis)),
+                                material,
+                                visible: hidden(),
+                                ..Default::default()
+                            })
+                            .insert(part);
+
+                        continue;
+                    }
+
+                    let handle_size = if mode == GizmoMode::Scale {
+                        SCALE_HANDLE_SIZE
+                    } else {
+                        ARROW_SIZE
+                    };
+
+                    parent
+                        .spawn_bundle(PbrBundle {
+                            mesh: meshes.add(axis_box(axis, SHAFT_LENGTH, SHAFT_WIDTH)),
+                            material: material.clone(),
+                            transform: Transform::from_translation(direction * SHAFT_LENGTH * 0.5),
+                            visible: hidden(),
+                            ..Default::default()
+                        })
+                        .insert(part);
+
+                    parent
+                        .spawn_bundle(PbrBundle {
+                            mesh: meshes.add(Mesh::from(shape::Cube { size: handle_size })),
+                            material,
+                            transform: Transform::from_translation(direction * SHAFT_LENGTH),
+                            visible: hidden(),
+                            ..Default::default()
+                        })
+                        .insert(part);
+                }
+            }
+        });
+
+    let highlight = unlit(&mut materials, Color::rgb(1.0, 0.85, 0.1));
+
+    commands.insert_resource(GizmoState {
+        hovered: None,
+        drag: None,
+        materials: axis_materials,
+        highlight,
+    });
+}
+
+/// The handle of the current mode closest to a ray, in the gizmo's own space.
+fn pick_part(ray: &Ray, center: Vec3, size: f32, mode: GizmoMode) -> Option<GizmoPart> {
+    let local = Ray {
+        origin: (ray.origin - center) / size,
+        direction: ray.direction,
+    };
+
+    let mut best: Option<(f32, GizmoPart)> = None;
+
+    for axis in 0..3 {
+        let direction = axis_direction(axis);
+
+        let miss = if mode == GizmoMode::Rotate {
+            local
+                .intersect_plane(Vec3::default(), direction)
+                .map(|distance| (local.at(distance).length() - RING_RADIUS).abs())
+        } else {
+            local.closest_on_line(Vec3::default(), direction).map(|along| {
+                let along = along.max(0.0).min(SHAFT_LENGTH + SCALE_HANDLE_SIZE);
+                let point = direction * along;
+                // The distance from the point to the ray.
+                let offset = point - local.origin;
+                (offset - local.direction * offset.dot(local.direction)).length()
+            })
+        };
+
+        if let Some(miss) = miss {
+            if miss < GRAB_DISTANCE && best.map(|(best, _)| miss < best).unwrap_or(true) {
+                best = Some((miss, GizmoPart { mode, axis }));
+            }
+        }
+    }
+
+    best.map(|(_, part)| part)
+}
+
+fn anchor(ray: &Ray, center: Vec3, part: GizmoPart) -> Option<Anchor> {
+    let direction = axis_direction(part.axis);
+
+    match part.mode {
+        GizmoMode::Rotate => {
+            let distance = ray.intersect_plane(center, direction)?;
+            Some(Anchor::Around(ray.at(distance) - center))
+        }
+        _ => ray.closest_on_line(center, direction).map(Anchor::Along),
+    }
+}
+
+/// The placement a drag has moved the reference to so far.
+fn dragged_placement(drag: &Drag, ray: &Ray, state: &TransformState) -> Option<Placement> {
+    let center = drag.start_transform.translation;
+    let axis = drag.part.axis;
+    let direction = axis_direction(axis);
+    let mut placement = drag.start;
+
+    match (&drag.anchor, drag.part.mode) {
+        (Anchor::Along(start), GizmoMode::Translate) => {
+            let along = ray.closest_on_line(center, direction)?;
+            let position = drag.start.position[axis] + (along - start) / UNIT;
+            placement.position[axis] = state.snap_position(position);
+        }
+        (Anchor::Along(start), GizmoMode::Scale) => {
+            let along = ray.closest_on_line(center, direction)?;
+
+            if start.abs() > f32::EPSILON {
+                placement.scale = TransformState::clamp_scale(drag.start.scale * along / start);
+            }
+        }
+        (Anchor::Around(start), GizmoMode::Rotate) => {
+            let current = ray.at(ray.intersect_plane(center, direction)?) - center;
+            let angle = direction.dot(start.cross(current)).atan2(start.dot(current));
+            let rotation = Quat::from_axis_angle(direction, state.snap_angle(angle)) * drag.start_transform.rotation;
+            placement.rotation = model::rotation_from_viewport(rotation);
+        }
+        _ => return None,
+    }
+
+    Some(placement)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn gizmo_interaction(
+    egui_context: Res<EguiContext>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    mut gizmo: ResMut<GizmoState>,
+    mut ui_state: ResMut<ui_state::State>,
+    mut edits: EventWriter<PlacementEdited>,
+    cameras: Query<(&GlobalTransform, &PerspectiveProjection), With<ViewportCamera>>,
+    mut selected: Query<(&PlacedReference, &mut Transform), (With<Selected>, Without<GizmoRoot>)>,
+    mut roots: Query<&mut Transform, (With<GizmoRoot>, Without<Selected>)>,
+    mut parts: Query<(&GizmoPart, &mut Visible, &mut Handle<StandardMaterial>)>,
+) {
+    let ctx = egui_context.ctx();
+    let gizmo = &mut *gizmo;
+    let state = &mut ui_state.transform;
+
+    if !ctx.wants_keyboard_input() && !buttons.pressed(MouseButton::Right) {
+        for (key, mode) in [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3].iter().zip(MODES.iter()) {
+            if keys.just_pressed(*key) {
+                state.mode = *mode;
+            }
+        }
+    }
+
+    let target = selected.iter_mut().next();
+    let camera = cameras.iter().next();
+
+    let (reference, mut transform, (camera_transform, projection)) = match (target, camera) {
+        (Some((PlacedReference(form_id), transform)), Some(camera)) => (*form_id, transform, camera),
+        _ => {
+            gizmo.hovered = None;
+            gizmo.drag = None;
+
+            for (_, mut visible, _) in parts.iter_mut() {
+                visible.is_visible = false;
+            }
+
+            return;
+        }
+    };
+
+    let center = transform.translation;
+    let size = (center - camera_transform.translation).length() * SIZE;
+
+    for mut root in roots.iter_mut() {
+        *root = Transform {
+            translation: center,
+            scale: Vec3::splat(size),
+            ..Default::default()
+        };
+    }
+
+    let dragging = gizmo.drag.is_some();
+    let ray = if dragging || !ctx.wants_pointer_input() {
+        Ray::from_cursor(&windows, camera_transform, projection)
+    } else {
+        None
+    };
+
+    if let Some(drag) = &gizmo.drag {
+        if !buttons.pressed(MouseButton::Left) {
+            edits.send(PlacementEdited {
+                form_id: drag.form_id,
+                placement: state.placement,
+            });
+            gizmo.drag = None;
+        } else if let Some(placement) = ray.and_then(|ray| dragged_placement(drag, &ray, state)) {
+            state.placement = placement;
+            *transform = model::placement_transform(&placement);
+        }
+    } else {
+        gizmo.hovered = ray.and_then(|ray| pick_part(&ray, center, size, state.mode));
+
+        let target_matches = state.target.as_ref().map(|(form_id, _)| *form_id) == Some(reference);
+
+        if let (Some(part), Some(ray)) = (gizmo.hovered, ray) {
+            if buttons.just_pressed(MouseButton::Left) && target_matches {
+                gizmo.drag = anchor(&ray, center, part).map(|anchor| Drag {
+                    part,
+                    form_id: reference,
+                    start: state.placement,
+                    start_transform: *transform,
+                    anchor,
+                });
+            }
+        }
+    }
+
+    let active = gizmo.drag.as_ref().map(|drag| drag.part).or(gizmo.hovered);
+
+    for (part, mut visible, mut material) in parts.iter_mut() {
+        let is_visible = part.mode == state.mode;
+        let wanted = if Some(*part) == active {
+            &gizmo.highlight
+        } else {
+            &gizmo.materials[part.axis]
+        };
+
+        // Only touch components that change, so the renderer does not rebuild them every frame.
+        if visible.is_visible != is_visible {
+            visible.is_visible = is_visible;
+        }
+
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+    }
+}
@@ -0,0 +1,379 @@
+//! Landscape brushes: sculpting terrain heights and painting texture layers and vertex colours.
+//!
+//! While the Landscape window is open, dragging over the terrain with the left mouse button applies the chosen
+//! brush. Brushes work on vertices rather than cells, so a stroke across a cell border edits both cells alike.
+//! Each edited cell's LAND record is written to the active plugin when the stroke ends.
+
+use std::{collections::HashSet, f32::consts::PI};
+
+use bevy::{
+    prelude::*,
+    render::{camera::PerspectiveProjection, mesh::Indices, pipeline::PrimitiveTopology},
+};
+use bevy_egui::EguiContext;
+use open_creation_data::esp::land::{LAND_SIZE, QUADRANT_SIZE, VERTEX_SPACING};
+use open_creation_ui::{BrushTool, LandscapeState};
+use open_creation_util::log;
+
+use crate::{
+    camera::ViewportCamera,
+    data_files::DataFilesResource,
+    gizmo::GizmoState,
+    model,
+    ray::Ray,
+    records::RecordsResource,
+    terrain::{self, LandTextureCache, LoadedTerrain},
+    ui_state,
+};
+
+/// Game units per second that raising and lowering move the terrain at full strength.
+const SCULPT_SPEED: f32 = 1024.0;
+/// How much of the way to its target a vertex moves per second at full strength when flattening, smoothing or
+/// painting.
+const BLEND_SPEED: f32 = 4.0;
+const CURSOR_SEGMENTS: usize = 64;
+/// The width of the cursor ring relative to the brush radius.
+const CURSOR_WIDTH: f32 = 0.02;
+/// Lifts the cursor ring off the terrain so it is not hidden by it.
+const CURSOR_LIFT: f32 = 0.05;
+
+/// Marks the ring showing the brush under the cursor.
+pub struct BrushCursor;
+
+/// The brush stroke in progress while the left mouse button is held.
+#[derive(Default)]
+pub struct Stroke {
+    active: bool,
+    /// The height the flatten brush levels towards: the terrain height where the stroke began.
+    flatten_height: f32,
+    /// Whether painting has already been refused because a quadrant has no room for another layer.
+    layers_full: bool,
+    /// The grid positions of the cells edited so far.
+    edited: HashSet<(i32, i32)>,
+}
+
+/// A flat ring of radius 1 around the viewport's vertical axis.
+fn cursor_mesh() -> Mesh {
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut indices = vec![];
+
+    for segment in 0..CURSOR_SEGMENTS {
+        let angle = segment as f32 / CURSOR_SEGMENTS as f32 * 2.0 * PI;
+        let direction = Vec3::new(angle.cos(), 0.0, angle.sin());
+        positions.push((direction * (1.0 - CURSOR_WIDTH)).into());
+        positions.push((direction * (1.0 + CURSOR_WIDTH)).into());
+
+        let inner = segment as u32 * 2;
+        let next = ((segment + 1) % CURSOR_SEGMENTS) as u32 * 2;
+        indices.extend_from_slice(&[inner, inner + 1, next + 1, inner, next + 1, next]);
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; positions.len()]);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+pub fn spawn_brush_cursor(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(cursor_mesh()),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgb(1.0, 0.85, 0.1),
+                unlit: true,
+                double_sided: true,
+                ..Default::default()
+            }),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: false,
+            },
+            ..Default::default()
+        })
+        .insert(BrushCursor);
+}
+
+/// The vertices under a brush centred on a point in game units, in vertex steps from the worldspace origin, with
+/// weights falling off smoothly from 1 at the centre to 0 at the edge.
+fn brush_vertices(center: [f32; 3], radius: f32) -> Vec<(i32, i32, f32)> {
+    let first = |value: f32| ((value - radius) / VERTEX_SPACING).ceil() as i32;
+    let last = |value: f32| ((value + radius) / VERTEX_SPACING).floor() as i32;
+    let mut vertices = vec![];
+
+    for y in first(center[1])..=last(center[1]) {
+        for x in first(center[0])..=last(center[0]) {
+            let (dx, dy) = (
+                x as f32 * VERTEX_SPACING - center[0],
+                y as f32 * VERTEX_SPACING - center[1],
+            );
+            let distance = (dx * dx + dy * dy).sqrt();
+
+            if distance < radius {
+                let t = 1.0 - distance / radius;
+                vertices.push((x, y, t * t * (3.0 - 2.0 * t)));
+            }
+        }
+    }
+
+    vertices
+}
+
+/// Raises, lowers, flattens or smooths the vertices under the brush. Every new height is worked out from the
+/// heights before the edit, so smoothing does not depend on the order vertices are visited.
+fn sculpt(
+    terrain: &mut LoadedTerrain,
+    state: &LandscapeState,
+    vertices: &[(i32, i32, f32)],
+    flatten_height: f32,
+    delta: f32,
+    edited: &mut HashSet<(i32, i32)>,
+) {
+    let rate = state.strength * delta;
+
+    let heights: Vec<(i32, i32, f32)> = vertices
+        .iter()
+        .filter_map(|&(x, y, weight)| {
+            let height = terrain.vertex_height(x, y)?;
+            let blend = (rate * weight * BLEND_SPEED).min(1.0);
+
+            let height = match state.tool {
+                BrushTool::Raise => height + SCULPT_SPEED * rate * weight,
+                BrushTool::Lower => height - SCULPT_SPEED * rate * weight,
+                BrushTool::Flatten => height + (flatten_height - height) * blend,
+                _ => {
+                    let neighbours: Vec<f32> = (-1..=1)
+                        .flat_map(|dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+                        .filter_map(|(x, y)| terrain.vertex_height(x, y))
+                        .collect();
+                    let average = neighbours.iter().sum::<f32>() / neighbours.len() as f32;
+
+                    height + (average - height) * blend
+                }
+            };
+
+            Some((x, y, height))
+        })
+        .collect();
+
+    for (x, y, height) in heights {
+        for (grid, index) in terrain::vertex_cells(x, y) {
+            if let Some(heights) = terrain.cells.get_mut(&grid).and_then(|cell| cell.land.heights.as_mut()) {
+                heights[index] = height;
+            }
+        }
+
+        // The normals of the vertices around an edited one change too, and they may be in a neighbouring cell.
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                edited.extend(
+                    terrain::vertex_cells(x + dx, y + dy)
+                        .into_iter()
+                        .map(|(grid, _)| grid)
+                        .filter(|grid| terrain.cells.contains_key(grid)),
+                );
+            }
+        }
+    }
+}
+
+/// The quadrant rows or columns containing a vertex row or column, with its offset in each. The middle row and
+/// column belong to both halves.
+fn quadrant_spans(offset: usize) -> Vec<(usize, usize)> {
+    let half = QUADRANT_SIZE - 1;
+    (0..2)
+        .filter(|&span| offset >= span * half && offset <= span * half + half)
+        .map(|span| (span, offset - span * half))
+        .collect()
+}
+
+/// Paints the chosen texture or colour over the vertices under the brush. Returns `false` if a quadrant had no
+/// room for another texture layer.
+fn paint(
+    terrain: &mut LoadedTerrain,
+    state: &LandscapeState,
+    vertices: &[(i32, i32, f32)],
+    delta: f32,
+    edited: &mut HashSet<(i32, i32)>,
+) -> bool {
+    let mut fits = true;
+
+    for &(x, y, weight) in vertices {
+        let amount = (state.strength * delta * weight * BLEND_SPEED).min(1.0);
+
+        for (grid, index) in terrain::vertex_cells(x, y) {
+            let land = match terrain.cells.get_mut(&grid) {
+                Some(cell) => &mut cell.land,
+                None => continue,
+            };
+
+            match (state.tool, state.texture) {
+                (BrushTool::PaintTexture, Some(texture)) => {
+                    let (column, row) = (index % LAND_SIZE, index / LAND_SIZE);
+
+                    for &(quadrant_y, offset_y) in &quadrant_spans(row) {
+                        for &(quadrant_x, offset_x) in &quadrant_spans(column) {
+                            let quadrant = quadrant_y * 2 + quadrant_x;
+                            let position = offset_y * QUADRANT_SIZE + offset_x;
+                            fits &= land.paint_layer(quadrant, position, texture, amount);
+                        }
+                    }
+                }
+                (BrushTool::PaintColor, _) => {
+                    let colors = land.colors.get_or_insert_with(|| vec![[255; 3]; LAND_SIZE * LAND_SIZE]);
+
+                    for (channel, target) in colors[index].iter_mut().zip(state.color.iter()) {
+                        let blended = *channel as f32 + (*target as f32 - *channel as f32) * amount;
+                        *channel = blended.round() as u8;
+                    }
+                }
+                _ => continue,
+            }
+
+            edited.insert(grid);
+        }
+    }
+
+    fits
+}
+
+/// Writes the LAND records of the cells a stroke edited to the active plugin.
+fn save_stroke(stroke: &mut Stroke, terrain: &LoadedTerrain, records: &mut RecordsResource) {
+    let mut saved = 0;
+
+    for grid in stroke.edited.drain() {
+        let cell = match terrain.cells.get(&grid) {
+            Some(cell) => cell,
+            None => continue,
+        };
+
+        match records.load_order.override_record(cell.land.form_id) {
+            Some(record) => match cell.land.write(record) {
+                Ok(()) => saved += 1,
+                Err(e) => log::error!("Error writing the terrain of cell {:08X}: {}", cell.cell, e),
+            },
+            None => {
+                log::warn!("Cannot save terrain edits without an active plugin");
+                break;
+            }
+        }
+    }
+
+    if saved > 0 {
+        log::info!("Saved the terrain of {} cells", saved);
+    }
+
+    stroke.edited.clear();
+}
+
+/// Applies the Landscape window's brush while the left mouse button is dragged over the terrain, and moves the
+/// brush cursor to follow the mouse.
+#[allow(clippy::too_many_arguments)]
+pub fn landscape_brush(
+    egui_context: Res<EguiContext>,
+    buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    time: Res<Time>,
+    gizmo: Res<GizmoState>,
+    mut terrain: ResMut<LoadedTerrain>,
+    mut records: ResMut<RecordsResource>,
+    data_files: Res<DataFilesResource>,
+    mut cache: ResMut<LandTextureCache>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut textures: ResMut<Assets<Texture>>,
+    ui_state: Res<ui_state::State>,
+    mut stroke: Local<Stroke>,
+    cameras: Query<(&GlobalTransform, &PerspectiveProjection), With<ViewportCamera>>,
+    mut cursors: Query<(&mut Transform, &mut Visible), With<BrushCursor>>,
+) {
+    let ctx = egui_context.ctx();
+    let state = &ui_state.landscape;
+    let stroke = &mut *stroke;
+
+    if stroke.active && !(ui_state.show_landscape && buttons.pressed(MouseButton::Left)) {
+        stroke.active = false;
+        save_stroke(stroke, &terrain, &mut records);
+    }
+
+    let ray = match cameras.iter().next() {
+        Some((transform, projection)) if ui_state.show_landscape && (stroke.active || !ctx.wants_pointer_input()) => {
+            Ray::from_cursor(&windows, transform, projection)
+        }
+        _ => None,
+    };
+
+    let hit = ray.and_then(|ray| terrain.raycast(&ray));
+
+    for (mut transform, mut visible) in cursors.iter_mut() {
+        if visible.is_visible != hit.is_some() {
+            visible.is_visible = hit.is_some();
+        }
+
+        if let Some(hit) = hit {
+            *transform = Transform {
+                translation: hit + Vec3::unit_y() * CURSOR_LIFT,
+                scale: Vec3::splat(state.radius * model::UNIT),
+                ..Default::default()
+            };
+        }
+    }
+
+    let center = match hit {
+        Some(hit) => model::from_viewport(hit),
+        None => return,
+    };
+
+    if !stroke.active {
+        if !buttons.just_pressed(MouseButton::Left) || gizmo.is_busy() {
+            return;
+        }
+
+        if state.tool == BrushTool::PaintTexture && state.texture.is_none() {
+            log::warn!("Choose a landscape texture to paint in the Landscape window");
+            return;
+        }
+
+        stroke.active = true;
+        stroke.flatten_height = center[2];
+        stroke.layers_full = false;
+    }
+
+    let vertices = brush_vertices(center, state.radius);
+    let delta = time.delta_seconds();
+    let mut edited = HashSet::new();
+
+    if state.tool.edits_heights() {
+        sculpt(
+            &mut terrain,
+            state,
+            &vertices,
+            stroke.flatten_height,
+            delta,
+            &mut edited,
+        );
+
+        for &grid in &edited {
+            terrain.update_normals(grid);
+        }
+    } else {
+        if state.tool == BrushTool::PaintTexture {
+            cache.load(state.texture, &records.load_order, &data_files.vfs);
+        }
+
+        if !paint(&mut terrain, state, &vertices, delta, &mut edited) && !stroke.layers_full {
+            log::warn!("Some quadrants already have as many texture layers as they can hold");
+            stroke.layers_full = true;
+        }
+    }
+
+    for &grid in &edited {
+        terrain.refresh(grid, !state.tool.edits_heights(), &cache, &mut meshes, &mut textures);
+    }
+
+    stroke.edited.extend(edited);
+}
@@ -0,0 +1,123 @@
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use open_creation_data::{
+    esp::{LeveledList, LoadOrder, LEVELED_CODES},
+    leveled_patch,
+    leveled_simulation::Simulation,
+};
+use open_creation_ui::LeveledListState;
+use open_creation_util::{log, Settings};
+
+use crate::{
+    records::{RecordSelected, RecordsResource},
+    ui_state,
+};
+
+/// The plugin the merged leveled lists are written to, in the data folder.
+const MERGED_PATCH_NAME: &str = "Merged Patch.esp";
+
+/// Sent when the list in the Leveled List window has been edited.
+pub struct LeveledListEdited;
+
+/// Sent to write the leveled lists that several plugins edit, merged, to a patch plugin.
+pub struct GenerateMergedPatch;
+
+/// Opens LVLI, LVLN and LVSP records selected in the tree view in the Leveled List window.
+pub fn open_leveled_lists(
+    mut record_selections: EventReader<RecordSelected>,
+    records: Res<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+) {
+    for RecordSelected(form_id) in record_selections.iter() {
+        let record = match records.load_order.record(*form_id) {
+            Some(record) if LEVELED_CODES.contains(&record.code) => record,
+            _ => continue,
+        };
+
+        match LeveledList::from_record(record) {
+            Ok(list) => {
+                ui_state.leveled_list.open(list);
+                ui_state.show_leveled_list = true;
+            }
+            Err(e) => log::error!("Error reading leveled list {:08X}: {}", form_id, e),
+        }
+    }
+}
+
+/// Writes the list being edited to the active plugin, copying it there first if it comes from another plugin.
+pub fn apply_leveled_edits(
+    mut edits: EventReader<LeveledListEdited>,
+    mut records: ResMut<RecordsResource>,
+    ui_state: Res<ui_state::State>,
+) {
+    if edits.iter().count() == 0 {
+        return;
+    }
+
+    let list = match &ui_state.leveled_list.list {
+        Some(list) => list,
+        None => return,
+    };
+
+    // Checked before overriding, so a list that cannot be saved is not copied to the active plugin.
+    let subrecords = match list.subrecords() {
+        Ok(subrecords) => subrecords,
+        Err(e) => {
+            log::error!("Cannot save leveled list {:08X}: {}", list.form_id, e);
+            return;
+        }
+    };
+
+    let renamed = records.load_order.editor_id(list.form_id).as_deref() != Some(list.editor_id.as_str());
+
+    match records.load_order.override_record(list.form_id) {
+        Some(record) => record.set_subrecords(&subrecords),
+        None => {
+            log::warn!("Cannot save leveled list {:08X} without an active plugin", list.form_id);
+            return;
+        }
+    }
+
+    if renamed {
+        records.invalidate(list.code);
+    }
+}
+
+/// Rolls the list being edited at the chosen level, with a new seed each time.
+pub fn simulate(load_order: &LoadOrder, state: &mut LeveledListState) {
+    let list = match &state.list {
+        Some(list) => list,
+        None => return,
+    };
+
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64);
+
+    state.simulation = Some(Simulation::run(load_order, list, state.level, state.rolls, seed));
+}
+
+/// Writes a patch plugin with the merged versions of the leveled lists that several plugins edit. The patch is
+/// used once it is added to the end of the load order.
+pub fn generate_merged_patch(
+    mut requests: EventReader<GenerateMergedPatch>,
+    records: Res<RecordsResource>,
+    settings: Res<Settings>,
+) {
+    if requests.iter().count() == 0 {
+        return;
+    }
+
+    let path = Path::new(&settings.data_path).join(MERGED_PATCH_NAME);
+    let result = leveled_patch::merged_patch(&records.load_order, MERGED_PATCH_NAME)
+        .and_then(|patch| patch.save(&path).map(|_| patch.records.len()));
+
+    match result {
+        Ok(merged) => log::info!("Wrote {} merged leveled lists to {}", merged, path.to_string_lossy()),
+        Err(e) => log::error!("Error generating {}: {}", MERGED_PATCH_NAME, e),
+    }
+}
@@ -0,0 +1,295 @@
+//! Scene lighting from the loaded cell's records.
+//!
+//! Interiors are lit by their XCLL and lighting template, and exteriors by a weather from their worldspace's
+//! climate at the time of day chosen in the Lighting window. Bevy 0.5 only has point lights, so the directional
+//! light is a point light placed far away towards the sun that follows the camera, and fog is not drawn. Placed
+//! LIGH references become point lights too, but the renderer only takes a handful at once, so only those nearest
+//! the camera are lit.
+
+use bevy::{pbr::AmbientLight, prelude::*};
+use open_creation_data::esp::{interior_lighting, Climate, LightBase, Lighting, Reference, Weather};
+use open_creation_ui::LightingState;
+
+use crate::{camera::ViewportCamera, cell::LoadedCell, model, records::RecordsResource, ui_state};
+
+/// How many point lights bevy_pbr draws at once. One of them is the sun.
+const MAX_LIGHTS: usize = 10;
+/// How far the sun is placed from the camera's focus, in viewport units.
+const SUN_DISTANCE: f32 = 1000.0;
+/// The sun's brightness where the camera is looking, relative to a light's colour.
+const SUN_STRENGTH: f32 = 1.5;
+const AMBIENT_BRIGHTNESS: f32 = 1.0;
+/// A placed light's brightness at the edge of its radius, relative to its colour.
+const POINT_LIGHT_STRENGTH: f32 = 1.0;
+
+/// Marks the light standing in for the sun or an interior's directional light.
+pub struct Sun {
+    /// A unit vector in viewport axes pointing towards the light.
+    direction: Vec3,
+}
+
+/// A light placed by a LIGH reference in the loaded cell, with the light it gives when it is one of those lit.
+pub struct CellLight {
+    pub form_id: u32,
+    light: Light,
+}
+
+fn color(rgb: [u8; 3]) -> Color {
+    Color::rgb_u8(rgb[0], rgb[1], rgb[2])
+}
+
+/// A direction in game axes turned into viewport axes.
+fn viewport_direction(direction: [f32; 3]) -> Vec3 {
+    Vec3::new(direction[0], direction[2], -direction[1]).normalize()
+}
+
+pub fn spawn_sun(mut commands: Commands) {
+    commands
+        .spawn_bundle(LightBundle {
+            light: Light {
+                intensity: SUN_DISTANCE * SUN_DISTANCE * SUN_STRENGTH,
+                range: SUN_DISTANCE * 2.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Sun {
+            direction: viewport_direction(Lighting::default().direction),
+        });
+}
+
+/// Spawns a point light for each lit LIGH reference in a newly loaded cell.
+pub fn spawn_cell_lights(
+    mut commands: Commands,
+    loaded_cell: Res<LoadedCell>,
+    records: Res<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+    lights: Query<Entity, With<CellLight>>,
+) {
+    if !loaded_cell.is_changed() {
+        return;
+    }
+
+    for entity in lights.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let load_order = &records.load_order;
+    let references = match loaded_cell.form_id {
+        Some(cell) => load_order.cell_references(cell),
+        None => vec![],
+    };
+
+    let mut spawned = 0;
+
+    for record in references {
+        let reference = match Reference::from_record(record) {
+            Some(reference) => reference,
+            None => continue,
+        };
+
+        let base = match load_order.record(reference.base).and_then(LightBase::from_record) {
+            Some(base) if base.is_lit() => base,
+            _ => continue,
+        };
+
+        // References can override their base light's radius.
+        let radius = record
+            .subrecord(*b"XRDS")
+            .and_then(|xrds| xrds.as_f32())
+            .unwrap_or(base.radius);
+        let range = radius * model::UNIT;
+
+        commands
+            .spawn()
+            .insert(Transform::from_translation(model::to_viewport(
+                reference.placement.position,
+            )))
+            .insert(GlobalTransform::default())
+            .insert(CellLight {
+                form_id: reference.form_id,
+                light: Light {
+                    color: color(base.color),
+                    intensity: range * range * POINT_LIGHT_STRENGTH * base.fade,
+                    range,
+                    ..Default::default()
+                },
+            });
+
+        spawned += 1;
+    }
+
+    ui_state.lighting.point_lights = spawned;
+}
+
+/// Fills the Lighting window's description of the lighting in use.
+fn describe(state: &mut LightingState, lighting: &Lighting, source: String) {
+    state.source = source;
+    state.colors = vec![
+        ("Ambient", lighting.ambient),
+        ("Directional", lighting.directional),
+        ("Fog near", lighting.fog_near_color),
+        ("Fog far", lighting.fog_far_color),
+        ("Sky", lighting.sky),
+    ];
+    state.values = vec![
+        ("Fog near distance", format!("{:.0}", lighting.fog_near)),
+        ("Fog far distance", format!("{:.0}", lighting.fog_far)),
+        ("Fog power", format!("{:.2}", lighting.fog_power)),
+        ("Fog max", format!("{:.2}", lighting.fog_max)),
+    ];
+}
+
+/// The lighting of the loaded cell, with a description of where it comes from, and whether it is an exterior.
+fn cell_lighting(records: &mut RecordsResource, cell: u32, state: &LightingState) -> (Lighting, String, bool) {
+    let world = records
+        .cells()
+        .worlds
+        .iter()
+        .find(|world| world.cells.iter().any(|item| item.form_id == cell))
+        .map(|world| world.form_id);
+
+    let load_order = &records.load_order;
+    let name = |form_id: u32| {
+        load_order
+            .editor_id(form_id)
+            .unwrap_or_else(|| format!("{:08X}", form_id))
+    };
+
+    // Only exterior cells are listed under a worldspace.
+    let world = match world {
+        Some(world) => world,
+        None => {
+            return match interior_lighting(load_order, cell) {
+                Some(lighting) => (lighting, format!("Interior lighting of {}", name(cell)), false),
+                None => (
+                    Lighting::default(),
+                    format!("{} has no lighting; using the default", name(cell)),
+                    false,
+                ),
+            };
+        }
+    };
+
+    // Worldspaces that use their parent's climate usually have none of their own.
+    let climate_of = |form_id: u32| {
+        load_order
+            .record(form_id)?
+            .subrecord(*b"CNAM")?
+            .as_u32()
+            .and_then(|climate| load_order.record(climate))
+            .and_then(Climate::from_record)
+    };
+    let parent = load_order
+        .record(world)
+        .and_then(|record| record.subrecord(*b"WNAM"))
+        .and_then(|wnam| wnam.as_u32());
+    let climate = climate_of(world)
+        .or_else(|| parent.and_then(climate_of))
+        .unwrap_or_default();
+
+    let weather = state
+        .weather
+        .or_else(|| climate.likeliest_weather())
+        .and_then(|weather| load_order.record(weather))
+        .and_then(Weather::from_record);
+
+    match weather {
+        Some(weather) => {
+            let source = match state.weather {
+                Some(_) => format!("Weather {}", name(weather.form_id)),
+                None if climate.form_id != 0 => {
+                    format!(
+                        "Weather {} from climate {}",
+                        name(weather.form_id),
+                        name(climate.form_id)
+                    )
+                }
+                None => format!("Weather {}", name(weather.form_id)),
+            };
+
+            (weather.lighting(&climate, state.hour), source, true)
+        }
+        None => (
+            Lighting::default(),
+            format!("{} has no weather to preview; using the default", name(world)),
+            true,
+        ),
+    }
+}
+
+/// Applies the loaded cell's lighting to the scene whenever the cell, the time of day or the weather changes.
+pub fn update_lighting(
+    loaded_cell: Res<LoadedCell>,
+    mut records: ResMut<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+    mut ambient: ResMut<AmbientLight>,
+    mut clear_color: ResMut<ClearColor>,
+    mut previous: Local<Option<(Option<u32>, u32, Option<u32>)>>,
+    mut suns: Query<(&mut Sun, &mut Light)>,
+) {
+    let state = &mut ui_state.lighting;
+    let key = (loaded_cell.form_id, (state.hour * 60.0).round() as u32, state.weather);
+
+    if *previous == Some(key) {
+        return;
+    }
+
+    *previous = Some(key);
+
+    let (lighting, source, is_exterior) = match loaded_cell.form_id {
+        Some(cell) => cell_lighting(&mut records, cell, state),
+        None => (Lighting::default(), "No cell is loaded".to_string(), false),
+    };
+
+    ambient.color = color(lighting.ambient);
+    ambient.brightness = AMBIENT_BRIGHTNESS;
+    clear_color.0 = color(lighting.sky);
+
+    for (mut sun, mut light) in suns.iter_mut() {
+        sun.direction = viewport_direction(lighting.direction);
+        light.color = color(lighting.directional);
+    }
+
+    state.is_exterior = is_exterior;
+    describe(state, &lighting, source);
+}
+
+/// Keeps the sun far off in its direction from the camera's focus, and lights the placed lights nearest the
+/// camera.
+pub fn place_lights(
+    mut commands: Commands,
+    cameras: Query<(&ViewportCamera, &GlobalTransform)>,
+    mut suns: Query<(&Sun, &mut Transform)>,
+    lights: Query<(Entity, &CellLight, &GlobalTransform, Option<&Light>)>,
+) {
+    let (focus, eye) = match cameras.iter().next() {
+        Some((camera, transform)) => (camera.focus, transform.translation),
+        None => return,
+    };
+
+    for (sun, mut transform) in suns.iter_mut() {
+        transform.translation = focus + sun.direction * SUN_DISTANCE;
+    }
+
+    let mut nearest: Vec<(f32, Entity)> = lights
+        .iter()
+        .map(|(entity, _, transform, _)| ((transform.translation - eye).length_squared(), entity))
+        .collect();
+    nearest.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    nearest.truncate(MAX_LIGHTS - 1);
+
+    for (entity, cell_light, _, light) in lights.iter() {
+        let lit = nearest.iter().any(|(_, nearest)| *nearest == entity);
+
+        match (lit, light.is_some()) {
+            (true, false) => {
+                commands.entity(entity).insert(cell_light.light.clone());
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Light>();
+            }
+            _ => {}
+        }
+    }
+}
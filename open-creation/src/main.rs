@@ -1,48 +1,142 @@
-use std::{borrow::Borrow, default::Default, fs::File};
-
-use open_creation_ui::{AboutWindow, DataWindow, GameSettingsWindow, LogWindow, Window};
-use open_creation_util::{log, Logger};
-
-use bevy::{prelude::*, render::camera::PerspectiveProjection, window};
+use std::{
+    cell::{Cell, RefCell},
+    default::Default,
+};
+
+use open_creation_ui::{
+    AboutWindow, ArchiveBrowserWindow, CellViewAction, CellViewWindow, CreateArchiveWindow, DataWindow, DialogueWindow,
+    FactionGraphWindow, GameSettingsWindow, LandscapeWindow, LeveledListWindow, LightingWindow, LogWindow,
+    NavmeshWindow, NpcWindow, PapyrusWindow, QuestWindow, ScriptBrowserWindow, ScriptLookup, ScriptsWindow,
+    TexturePreviewWindow, TransformWindow, Window,
+};
+use open_creation_util::{log, Logger, Settings};
+
+use bevy::{prelude::*, window};
 use bevy_egui::{egui, EguiContext, EguiPlugin, EguiSystem};
 use lazy_static::lazy_static;
-use tes_parse::{read_plugin, Plugin};
 
+mod archive;
+mod camera;
+mod cell;
+mod cli;
+mod data_files;
+mod dialogue;
+mod faction;
+mod gizmo;
+mod landscape;
+mod leveled;
+mod lighting;
+mod model;
+mod navmesh;
+mod npc;
+mod papyrus;
+mod placement;
+mod preview;
+mod quest;
+mod ray;
+mod records;
+mod scripts;
+mod selection;
+mod terrain;
+mod textures;
 mod ui_state;
 
 lazy_static! {
     static ref LOGGER: Logger = Logger::new();
 }
 
-struct PluginResource(Vec<Plugin>);
-
 fn main() {
     log::set_logger(&*LOGGER).unwrap();
     log::set_max_level(log::LevelFilter::Debug);
     LOGGER.filter(log::LevelFilter::Debug);
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
+    let settings = Settings::load();
+    let mut ui_state = ui_state::State::new();
+    ui_state.create_archive = archive::options_from_settings(&settings);
+    let data_files = data_files::DataFilesResource::load(&settings);
+    let records = records::RecordsResource::load(&settings, &data_files.vfs);
+
     App::build()
         .add_plugins(DefaultPlugins)
         .add_plugin(EguiPlugin)
-        .insert_resource(ui_state::State::new())
-        .insert_resource(PluginResource(vec![]))
+        .insert_resource(settings)
+        .insert_resource(data_files)
+        .insert_resource(records)
+        .insert_resource(model::ModelCache::default())
+        .insert_resource(terrain::LandTextureCache::default())
+        .insert_resource(terrain::LoadedTerrain::default())
+        .insert_resource(navmesh::LoadedNavmeshes::default())
+        .insert_resource(cell::LoadedCell::default())
+        .insert_resource(bevy::pbr::AmbientLight {
+            color: Color::WHITE,
+            brightness: 0.8,
+        })
+        .add_event::<data_files::AssetSelected>()
+        .add_event::<records::RecordSelected>()
+        .add_event::<camera::FrameRequest>()
+        .add_event::<cell::LoadCell>()
+        .add_event::<selection::SelectReference>()
+        .add_event::<cell::PlacementEdited>()
+        .add_event::<navmesh::NavmeshEdit>()
+        .add_event::<quest::QuestEdited>()
+        .add_event::<dialogue::DialogueEdit>()
+        .add_event::<scripts::ScriptsEdited>()
+        .add_event::<leveled::LeveledListEdited>()
+        .add_event::<leveled::GenerateMergedPatch>()
+        .add_event::<npc::NpcEdited>()
+        .add_event::<faction::FactionGraphEdit>()
+        .insert_resource(ui_state)
         .insert_resource(ClearColor(Color::rgb(0.65, 0.65, 0.65)))
+        .add_startup_system(camera::spawn_camera.system())
+        .add_startup_system(gizmo::spawn_gizmo.system())
+        .add_startup_system(landscape::spawn_brush_cursor.system())
+        .add_startup_system(lighting::spawn_sun.system())
         .add_system(setup.system())
+        .add_system(camera::camera_controls.system())
         .add_system(windows.system())
         .add_system(should_close.system())
         .add_system(top_panel.system())
         .add_system(left_panel.system())
-        .add_system(load_plugin.system())
+        .add_system(preview::preview_selection.system())
+        .add_system(cell::load_cell.system())
+        .add_system(terrain::load_terrain.system())
+        .add_system(lighting::spawn_cell_lights.system())
+        .add_system(lighting::update_lighting.system())
+        .add_system(lighting::place_lights.system())
+        .add_system(landscape::landscape_brush.system())
+        .add_system(navmesh::load_navmeshes.system())
+        .add_system(navmesh::navmesh_interaction.system())
+        .add_system(navmesh::apply_navmesh_edits.system())
+        .add_system(navmesh::draw_navmeshes.system())
+        .add_system(selection::pick_references.system())
+        .add_system(selection::select_references.system())
+        .add_system(gizmo::gizmo_interaction.system())
+        .add_system(cell::apply_placement_edits.system())
+        .add_system(placement::drop_records.system())
+        .add_system(preview::preview_texture.system())
+        .add_system(quest::open_quests.system())
+        .add_system(quest::apply_quest_edits.system())
+        .add_system(dialogue::open_dialogue.system())
+        .add_system(dialogue::apply_dialogue_edits.system())
+        .add_system(scripts::open_scripts.system())
+        .add_system(scripts::apply_script_edits.system())
+        .add_system(leveled::open_leveled_lists.system())
+        .add_system(leveled::apply_leveled_edits.system())
+        .add_system(leveled::generate_merged_patch.system())
+        .add_system(npc::open_npcs.system())
+        .add_system(npc::apply_npc_edits.system())
+        .add_system(faction::select_factions.system())
+        .add_system(faction::apply_faction_edits.system())
         .run();
 }
 
-fn setup(
-    mut commands: Commands,
-    mut windows: ResMut<Windows>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut egui_context: ResMut<EguiContext>,
-) {
+fn setup(mut windows: ResMut<Windows>, mut egui_context: ResMut<EguiContext>) {
     let mut window = windows.get_primary_mut().unwrap();
     window.set_maximized(true);
     window.set_title(String::from("Open Creation"));
@@ -57,26 +151,6 @@ fn setup(
     style.visuals.widgets.inactive.corner_radius = 0.0;
     style.visuals.window_shadow.extrusion = 10.0;
     ctx.set_style(style);
-
-    commands
-        .spawn()
-        .insert(bevy::prelude::PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
-            material: materials.add(Color::YELLOW_GREEN.into()),
-            ..Default::default()
-        })
-        .insert(LightBundle {
-            transform: Transform::from_translation(Vec3::new(4.0, 8.0, 4.0)),
-            ..Default::default()
-        })
-        .insert(PerspectiveCameraBundle {
-            transform: Transform::from_translation(Vec3::new(-2.0, 2.5, 5.0)).looking_at(Vec3::default(), Vec3::unit_y()),
-            perspective_projection: PerspectiveProjection {
-                near: 0.01,
-                ..Default::default()
-            },
-            ..Default::default()
-        });
 }
 
 fn should_close(mut exit_events: EventWriter<bevy::app::AppExit>, ui_state: Res<ui_state::State>) {
@@ -85,7 +159,13 @@ fn should_close(mut exit_events: EventWriter<bevy::app::AppExit>, ui_state: Res<
     }
 }
 
-fn top_panel(mut egui_ctx: ResMut<EguiContext>, mut ui_state: ResMut<ui_state::State>) {
+fn top_panel(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut ui_state: ResMut<ui_state::State>,
+    mut records: ResMut<records::RecordsResource>,
+    settings: Res<Settings>,
+    mut patch_requests: EventWriter<leveled::GenerateMergedPatch>,
+) {
     const MENU_WIDTH: f32 = 150.0;
 
     let ctx = &mut egui_ctx.ctx();
@@ -101,13 +181,57 @@ fn top_panel(mut egui_ctx: ResMut<EguiContext>, mut ui_state: ResMut<ui_state::S
                 if menu_button(ui, "Data").clicked() {
                     ui_state.show_data = !ui_state.show_data;
                 };
-                
+
+                if menu_button(ui, "Create Archive").clicked() {
+                    ui_state.show_create_archive = !ui_state.show_create_archive;
+                }
+
+                if menu_button(ui, "Generate Merged Patch").clicked() {
+                    patch_requests.send(leveled::GenerateMergedPatch);
+                }
+
+                if menu_button(ui, "Save").clicked() {
+                    records.save(&settings);
+                }
+
                 if menu_button(ui, "Close").clicked() {
-                    ui_state.should_close = true;
+                    if records.load_order.is_modified() {
+                        ui_state.confirm_close = true;
+                    } else {
+                        ui_state.should_close = true;
+                    }
                 }
             });
 
             egui::menu::menu(ui, "View", |ui| {
+                if menu_button(ui, "Archive Browser").clicked() {
+                    ui_state.show_archive_browser = !ui_state.show_archive_browser;
+                }
+
+                if menu_button(ui, "Cell View").clicked() {
+                    ui_state.show_cell_view = !ui_state.show_cell_view;
+                }
+
+                if menu_button(ui, "Landscape").clicked() {
+                    ui_state.show_landscape = !ui_state.show_landscape;
+                }
+
+                if menu_button(ui, "Lighting").clicked() {
+                    ui_state.show_lighting = !ui_state.show_lighting;
+                }
+
+                if menu_button(ui, "Navmesh").clicked() {
+                    ui_state.show_navmesh = !ui_state.show_navmesh;
+                }
+
+                if menu_button(ui, "Transform").clicked() {
+                    ui_state.show_transform = !ui_state.show_transform;
+                }
+
+                if menu_button(ui, "Texture Preview").clicked() {
+                    ui_state.show_texture_preview = !ui_state.show_texture_preview;
+                }
+
                 if menu_button(ui, "Show log").clicked() {
                     ui_state.show_log = !ui_state.show_log;
                 }
@@ -117,6 +241,38 @@ fn top_panel(mut egui_ctx: ResMut<EguiContext>, mut ui_state: ResMut<ui_state::S
                 if menu_button(ui, "Game Settings").clicked() {
                     ui_state.show_game_settings = !ui_state.show_game_settings;
                 }
+
+                if menu_button(ui, "Quest").clicked() {
+                    ui_state.show_quest = !ui_state.show_quest;
+                }
+
+                if menu_button(ui, "Dialogue").clicked() {
+                    ui_state.show_dialogue = !ui_state.show_dialogue;
+                }
+
+                if menu_button(ui, "Leveled List").clicked() {
+                    ui_state.show_leveled_list = !ui_state.show_leveled_list;
+                }
+
+                if menu_button(ui, "NPC").clicked() {
+                    ui_state.show_npc = !ui_state.show_npc;
+                }
+
+                if menu_button(ui, "Faction Graph").clicked() {
+                    ui_state.show_faction_graph = !ui_state.show_faction_graph;
+                }
+
+                if menu_button(ui, "Scripts").clicked() {
+                    ui_state.show_scripts = !ui_state.show_scripts;
+                }
+
+                if menu_button(ui, "Script Browser").clicked() {
+                    ui_state.show_script_browser = !ui_state.show_script_browser;
+                }
+
+                if menu_button(ui, "Papyrus Editor").clicked() {
+                    ui_state.show_papyrus = !ui_state.show_papyrus;
+                }
             });
 
             egui::menu::menu(ui, "Help", |ui| {
@@ -126,10 +282,48 @@ fn top_panel(mut egui_ctx: ResMut<EguiContext>, mut ui_state: ResMut<ui_state::S
             });
         });
     });
+
+    if ui_state.confirm_close {
+        let name = records
+            .load_order
+            .active()
+            .map(|plugin| plugin.name.clone())
+            .unwrap_or_default();
+
+        egui::Window::new("Unsaved Changes")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Save the changes to {} before closing?", name));
+
+                ui.horizontal(|ui| {
+                    // A failed save keeps the editor open, with the error in the log.
+                    if ui.button("Save").clicked() && records.save(&settings) {
+                        ui_state.should_close = true;
+                    }
+
+                    if ui.button("Don't Save").clicked() {
+                        ui_state.should_close = true;
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        ui_state.confirm_close = false;
+                    }
+                });
+            });
+    }
 }
 
-fn left_panel(mut egui_ctx: ResMut<EguiContext>, plugins: Res<PluginResource>) {
+fn left_panel(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut records: ResMut<records::RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+    mut record_selections: EventWriter<records::RecordSelected>,
+) {
     let ctx = &mut egui_ctx.ctx();
+    let records = RefCell::new(&mut *records);
+    let selected = Cell::new(ui_state.selected_record);
+    let dragged = Cell::new(ui_state.dragged_record);
 
     egui::SidePanel::left("side_panel", 360f32).show(ctx, |ui| {
         egui::ScrollArea::auto_sized().show(ui, |ui| {
@@ -139,17 +333,25 @@ fn left_panel(mut egui_ctx: ResMut<EguiContext>, plugins: Res<PluginResource>) {
             });
             ui.separator();
 
-            let plugins = &plugins.borrow().0;
-
             let populate_by_code = |ui: &mut egui::Ui, code: [u8; 4]| {
                 ui.with_layout(egui::Layout::top_down(egui::Align::TOP).with_cross_justify(true), |ui| {
                     ui.separator();
 
-                    for plugin in plugins {
-                        for editor_id in plugin.get_editor_ids_by_code(code) {
-                            ui.selectable_label(false, editor_id);
-                            ui.separator();
+                    for (form_id, editor_id) in records.borrow_mut().editor_ids(code) {
+                        let response = ui
+                            .selectable_label(selected.get() == Some(*form_id), editor_id)
+                            .interact(egui::Sense::drag());
+
+                        if response.clicked() {
+                            selected.set(Some(*form_id));
+                        }
+
+                        // Dropping the record on the viewport places it in the loaded cell.
+                        if response.drag_started() {
+                            dragged.set(Some(*form_id));
                         }
+
+                        ui.separator();
                     }
                 });
             };
@@ -224,7 +426,9 @@ fn left_panel(mut egui_ctx: ResMut<EguiContext>, plugins: Res<PluginResource>) {
             });
 
             ui.collapsing("Special Effects", |ui| {});
-            ui.collapsing("World Data", |ui| {});
+            ui.collapsing("World Data", |ui| {
+                node(ui, "Cell",                    [b'C', b'E', b'L', b'L']);
+            });
 
             ui.collapsing("World Objects", |ui| {
                 node(ui, "Activator",               [b'A', b'C', b'T', b'I']);
@@ -240,16 +444,48 @@ fn left_panel(mut egui_ctx: ResMut<EguiContext>, plugins: Res<PluginResource>) {
             });
         });
     });
+
+    ui_state.dragged_record = dragged.get();
+
+    if let Some(form_id) = ui_state.dragged_record {
+        let label = records.borrow().load_order.editor_id(form_id).unwrap_or_default();
+        egui::show_tooltip_text(ctx, egui::Id::new("dragged_record"), label);
+    }
+
+    if selected.get() != ui_state.selected_record {
+        ui_state.selected_record = selected.get();
+
+        if let Some(form_id) = ui_state.selected_record {
+            record_selections.send(records::RecordSelected(form_id));
+        }
+    }
 }
 
-fn windows(mut egui_ctx: ResMut<EguiContext>, mut ui_state: ResMut<ui_state::State>, plugins: Res<PluginResource>) {
+fn windows(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut ui_state: ResMut<ui_state::State>,
+    mut records: ResMut<records::RecordsResource>,
+    settings: Res<Settings>,
+    mut data_files: ResMut<data_files::DataFilesResource>,
+    mut asset_selections: EventWriter<data_files::AssetSelected>,
+    mut load_requests: EventWriter<cell::LoadCell>,
+    mut reference_selections: EventWriter<selection::SelectReference>,
+    mut placement_edits: EventWriter<cell::PlacementEdited>,
+    mut navmesh_edits: EventWriter<navmesh::NavmeshEdit>,
+    mut quest_edits: EventWriter<quest::QuestEdited>,
+    mut dialogue_edits: EventWriter<dialogue::DialogueEdit>,
+    mut script_edits: EventWriter<scripts::ScriptsEdited>,
+    mut leveled_edits: EventWriter<leveled::LeveledListEdited>,
+    mut npc_edits: EventWriter<npc::NpcEdited>,
+    mut faction_edits: EventWriter<faction::FactionGraphEdit>,
+) {
     let ctx = &mut egui_ctx.ctx();
+    let ui_state = &mut *ui_state;
 
     if ui_state.show_data {
         let mut data_window = DataWindow::new();
-        let files = vec!["Skyrim.esm", "Dawnguard.esm", "Dragonborn.esm", "Update.esm"];
 
-        for file in files {
+        for file in &settings.load_order {
             data_window.add_file(file.to_owned());
         }
 
@@ -262,41 +498,230 @@ fn windows(mut egui_ctx: ResMut<EguiContext>, mut ui_state: ResMut<ui_state::Sta
 
     if ui_state.show_game_settings {
         let mut game_settings_window = GameSettingsWindow::new();
-        let plugins = &plugins.borrow().0;
 
-        for plugin in plugins {
-            for editor_id in plugin.get_editor_ids_by_code([b'G', b'M', b'S', b'T']) {
-                game_settings_window.add_entry(editor_id);
-            }
+        for (_, editor_id) in records.editor_ids([b'G', b'M', b'S', b'T']) {
+            game_settings_window.add_entry(editor_id.clone());
         }
 
         game_settings_window.show(ctx, &mut ui_state.show_game_settings);
     }
 
-    if ui_state.show_log {
-        let lines = &*LOGGER.lines.lock().unwrap();
+    if ui_state.show_create_archive {
+        let mut create_archive_window = CreateArchiveWindow::new(&mut ui_state.create_archive);
+        create_archive_window.show(ctx, &mut ui_state.show_create_archive);
 
-        LogWindow::new(lines)
-            .scroll(LOGGER.updated())
-            .show(ctx, &mut ui_state.show_log);
+        if create_archive_window.create_clicked() {
+            archive::create_in_background(&settings.data_path, &ui_state.create_archive);
+        }
+    }
 
-        LOGGER.set_updated(false);
+    if ui_state.show_archive_browser {
+        let mut archive_browser_window =
+            ArchiveBrowserWindow::new(&data_files.vfs, &data_files.tree, &mut ui_state.archive_browser);
+        archive_browser_window.show(ctx, &mut ui_state.show_archive_browser);
+
+        let mut selections = vec![];
+
+        for action in archive_browser_window.actions() {
+            data_files.handle(action, &ui_state.archive_browser.extract_path, &mut selections);
+        }
+
+        asset_selections.send_batch(selections.into_iter());
+    }
+
+    if ui_state.show_cell_view {
+        let mut cell_view_window = CellViewWindow::new(records.cells(), &mut ui_state.cell_view);
+        cell_view_window.show(ctx, &mut ui_state.show_cell_view);
+
+        for action in cell_view_window.actions() {
+            match action {
+                CellViewAction::LoadCell(form_id) => load_requests.send(cell::LoadCell(form_id)),
+                CellViewAction::SelectReference(form_id) => {
+                    reference_selections.send(selection::SelectReference {
+                        form_id: Some(form_id),
+                        frame: true,
+                    })
+                }
+            }
+        }
+    }
+
+    if ui_state.show_transform {
+        let mut transform_window = TransformWindow::new(&mut ui_state.transform);
+        transform_window.show(ctx, &mut ui_state.show_transform);
+
+        if transform_window.changed() {
+            if let Some((form_id, _)) = ui_state.transform.target {
+                placement_edits.send(cell::PlacementEdited {
+                    form_id,
+                    placement: ui_state.transform.placement,
+                });
+            }
+        }
+    }
+
+    if ui_state.show_landscape {
+        let textures = records.editor_ids(*b"LTEX");
+        LandscapeWindow::new(&mut ui_state.landscape, textures).show(ctx, &mut ui_state.show_landscape);
+    }
+
+    if ui_state.show_lighting {
+        let weathers = records.editor_ids(*b"WTHR");
+        LightingWindow::new(&mut ui_state.lighting, weathers).show(ctx, &mut ui_state.show_lighting);
+    }
+
+    if ui_state.show_navmesh {
+        let mut navmesh_window = NavmeshWindow::new(&mut ui_state.navmesh);
+        navmesh_window.show(ctx, &mut ui_state.show_navmesh);
+        navmesh_edits.send_batch(navmesh_window.actions().into_iter().map(navmesh::NavmeshEdit));
+    }
+
+    if ui_state.show_quest {
+        if let Some(vmad) = ui_state.quest.quest.as_ref().and_then(|quest| quest.scripts.as_ref()) {
+            data_files.load_scripts(vmad.script_names());
+        }
+
+        let load_order = &records.load_order;
+        let editor_ids = |form_id: u32| load_order.editor_id(form_id);
+        let record_codes = |form_id: u32| load_order.record(form_id).map(|record| record.code);
+        let find_record =
+            |code, editor_id: &str| load_order.find_by_editor_id(code, editor_id).map(|record| record.form_id);
+        let scripts = ScriptLookup {
+            library: &data_files.scripts,
+            find_record: &find_record,
+        };
+        let mut quest_window = QuestWindow::new(&mut ui_state.quest, &editor_ids, &record_codes, scripts);
+        quest_window.show(ctx, &mut ui_state.show_quest);
+
+        if quest_window.changed() {
+            quest_edits.send(quest::QuestEdited);
+        }
     }
-}
 
-fn load_plugin(mut plugins: ResMut<PluginResource>) {
-    if plugins.0.is_empty() {
-        let path = std::path::Path::new("/Users/adam/dev/OpenCreation/data/Skyrim.esm");
-        let file = File::open(&path);
+    if ui_state.show_dialogue {
+        if let Some(dialogue) = &ui_state.dialogue.dialogue {
+            let infos = dialogue.topics.iter().flat_map(|topic| &topic.infos);
+            let vmads = infos.filter_map(|info| info.scripts.as_ref());
+            data_files.load_scripts(vmads.flat_map(|vmad| vmad.script_names()));
+        }
+
+        let load_order = &records.load_order;
+        let editor_ids = |form_id: u32| load_order.editor_id(form_id);
+        let record_codes = |form_id: u32| load_order.record(form_id).map(|record| record.code);
+        let find_record =
+            |code, editor_id: &str| load_order.find_by_editor_id(code, editor_id).map(|record| record.form_id);
+        let scripts = ScriptLookup {
+            library: &data_files.scripts,
+            find_record: &find_record,
+        };
+        let mut dialogue_window = DialogueWindow::new(&mut ui_state.dialogue, &editor_ids, &record_codes, scripts);
+        dialogue_window.show(ctx, &mut ui_state.show_dialogue);
+        dialogue_edits.send_batch(dialogue_window.actions().into_iter().map(dialogue::DialogueEdit));
+    }
+
+    if ui_state.show_scripts {
+        if let Some(vmad) = &ui_state.scripts.vmad {
+            data_files.load_scripts(vmad.script_names());
+        }
+
+        let load_order = &records.load_order;
+        let editor_ids = |form_id: u32| load_order.editor_id(form_id);
+        let record_codes = |form_id: u32| load_order.record(form_id).map(|record| record.code);
+        let find_record =
+            |code, editor_id: &str| load_order.find_by_editor_id(code, editor_id).map(|record| record.form_id);
+        let scripts = ScriptLookup {
+            library: &data_files.scripts,
+            find_record: &find_record,
+        };
+        let mut scripts_window = ScriptsWindow::new(&mut ui_state.scripts, &editor_ids, &record_codes, scripts);
+        scripts_window.show(ctx, &mut ui_state.show_scripts);
+
+        if scripts_window.changed() {
+            script_edits.send(scripts::ScriptsEdited);
+        }
+    }
+
+    if ui_state.show_leveled_list {
+        let load_order = &records.load_order;
+        let editor_ids = |form_id: u32| load_order.editor_id(form_id);
+        let record_codes = |form_id: u32| load_order.record(form_id).map(|record| record.code);
+        let mut leveled_list_window = LeveledListWindow::new(&mut ui_state.leveled_list, &editor_ids, &record_codes);
+        leveled_list_window.show(ctx, &mut ui_state.show_leveled_list);
+
+        if leveled_list_window.changed() {
+            leveled_edits.send(leveled::LeveledListEdited);
+        }
+
+        if leveled_list_window.simulate_clicked() {
+            leveled::simulate(load_order, &mut ui_state.leveled_list);
+        }
+    }
+
+    if ui_state.show_npc {
+        let load_order = &records.load_order;
+        let editor_ids = |form_id: u32| load_order.editor_id(form_id);
+        let record_codes = |form_id: u32| load_order.record(form_id).map(|record| record.code);
+        let npcs = |form_id: u32| npc::read_npc(load_order, form_id);
+        let mut npc_window = NpcWindow::new(&mut ui_state.npc, &editor_ids, &record_codes, &npcs);
+        npc_window.show(ctx, &mut ui_state.show_npc);
+
+        if npc_window.changed() {
+            npc_edits.send(npc::NpcEdited);
+        }
+    }
 
-        if let Ok(file) = file {
-            if let Ok(plugin) = read_plugin(file) {
-                plugins.0.push(plugin);
-            } else {
-                log::error!("Error parsing file {}", path.to_string_lossy());
+    if ui_state.show_faction_graph {
+        let load_order = &records.load_order;
+        let editor_ids = |form_id: u32| load_order.editor_id(form_id);
+        let mut faction_graph_window = FactionGraphWindow::new(&mut ui_state.faction_graph, &editor_ids);
+        faction_graph_window.show(ctx, &mut ui_state.show_faction_graph);
+        faction_edits.send_batch(faction_graph_window.actions().into_iter().map(faction::FactionGraphEdit));
+    }
+
+    if ui_state.show_script_browser {
+        if let Some(selected) = &ui_state.script_browser.selected {
+            data_files.load_scripts(Some(selected.as_str()));
+        }
+
+        ScriptBrowserWindow::new(&data_files.scripts, &mut ui_state.script_browser)
+            .show(ctx, &mut ui_state.show_script_browser);
+    }
+
+    if ui_state.show_papyrus {
+        let mut papyrus_window = PapyrusWindow::new(&data_files.sources, &mut ui_state.papyrus);
+        papyrus_window.show(ctx, &mut ui_state.show_papyrus);
+
+        for action in papyrus_window.actions() {
+            papyrus::handle(action, &mut ui_state.papyrus, &data_files.vfs, &settings);
+        }
+    }
+
+    if ui_state.show_texture_preview {
+        let texture_id = egui::TextureId::User(preview::TEXTURE_PREVIEW_ID);
+        let mut texture_preview_window = TexturePreviewWindow::new(&mut ui_state.texture_preview, texture_id);
+        texture_preview_window.show(ctx, &mut ui_state.show_texture_preview);
+
+        if texture_preview_window.mip_changed() {
+            if let Some(info) = &ui_state.texture_preview.info {
+                asset_selections.send(data_files::AssetSelected(info.path.clone()));
             }
-        } else {
-            log::error!("Error opening file {}", path.to_string_lossy());
+        }
+    }
+
+    if ui_state.show_log {
+        // The log is unlocked before the clicked diagnostic is opened, which may log an error.
+        let clicked = {
+            let lines = &*LOGGER.lines.lock().unwrap();
+            let mut log_window = LogWindow::new(lines).scroll(LOGGER.updated());
+            log_window.show(ctx, &mut ui_state.show_log);
+            log_window.clicked()
+        };
+
+        LOGGER.set_updated(false);
+
+        if let Some(diagnostic) = clicked {
+            papyrus::go_to(&diagnostic, &mut ui_state.papyrus, &data_files.vfs, &settings);
+            ui_state.show_papyrus = true;
         }
     }
 }
@@ -0,0 +1,364 @@
+//! Conversion of NIF shapes into Bevy meshes and materials.
+
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, pipeline::PrimitiveTopology},
+};
+use open_creation_data::{
+    esp::Placement,
+    nif::{self, Nif},
+    vfs::VirtualFileSystem,
+};
+use open_creation_util::log;
+
+use crate::textures::TextureCache;
+
+/// The length of one game unit in metres. The viewport works in metres so that Bevy's defaults for camera
+/// clipping and light ranges behave.
+pub const UNIT: f32 = 0.014_287_5;
+
+/// Converts a position in game units (Z up) to viewport coordinates (metres, Y up).
+pub fn to_viewport(position: [f32; 3]) -> Vec3 {
+    Vec3::new(position[0], position[2], -position[1]) * UNIT
+}
+
+/// Converts a reference's XYZ Euler rotation (radians, clockwise, applied X then Y then Z) to a viewport rotation.
+///
+/// Game X maps to viewport X, game Y to viewport -Z and game Z to viewport Y.
+pub fn rotation_to_viewport(rotation: [f32; 3]) -> Quat {
+    Quat::from_rotation_y(-rotation[2]) * Quat::from_rotation_z(rotation[1]) * Quat::from_rotation_x(-rotation[0])
+}
+
+/// The inverse of [`rotation_to_viewport`], giving XYZ Euler angles in radians.
+pub fn rotation_from_viewport(rotation: Quat) -> [f32; 3] {
+    // The columns of the same rotation expressed in game axes.
+    let from_viewport = |v: Vec3| Vec3::new(v.x, -v.z, v.y);
+    let x = from_viewport(rotation * Vec3::unit_x());
+    let y = from_viewport(rotation * -Vec3::unit_z());
+    let z = from_viewport(rotation * Vec3::unit_y());
+
+    // The matrix is Rz(-rz) * Ry(-ry) * Rx(-rx), so its bottom row gives the X and Y angles.
+    let ry = x.z.max(-1.0).min(1.0).asin();
+
+    if x.z.abs() < 0.9999 {
+        [-y.z.atan2(z.z), ry, -x.y.atan2(x.x)]
+    } else {
+        // Gimbal lock: X and Z turn about the same axis, so put it all in Z.
+        [0.0, ry, y.x.atan2(y.y)]
+    }
+}
+
+/// The viewport transform of a placed reference.
+pub fn placement_transform(placement: &Placement) -> Transform {
+    Transform {
+        translation: to_viewport(placement.position),
+        rotation: rotation_to_viewport(placement.rotation),
+        scale: Vec3::splat(placement.scale),
+    }
+}
+
+/// Converts a viewport position back to game units.
+pub fn from_viewport(position: Vec3) -> [f32; 3] {
+    [position.x / UNIT, -position.z / UNIT, position.y / UNIT]
+}
+
+/// Converts a direction from game to viewport axes without scaling it.
+fn direction_to_viewport(direction: [f32; 3]) -> [f32; 3] {
+    [direction[0], direction[2], -direction[1]]
+}
+
+#[derive(Clone)]
+pub struct ModelPart {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+    pub transparent: bool,
+}
+
+/// An axis-aligned box around a model in its own viewport-space coordinates. Also a component on spawned models.
+#[derive(Clone, Copy, Debug)]
+pub struct Bounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Bounds {
+    pub fn from_points(points: impl Iterator<Item = Vec3>) -> Self {
+        let mut bounds = Bounds {
+            min: Vec3::splat(f32::MAX),
+            max: Vec3::splat(f32::MIN),
+        };
+
+        for point in points {
+            bounds.min = bounds.min.min(point);
+            bounds.max = bounds.max.max(point);
+        }
+
+        if bounds.min.x > bounds.max.x {
+            bounds.min = Vec3::default();
+            bounds.max = Vec3::default();
+        }
+
+        bounds
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// The radius of a sphere around the box.
+    pub fn radius(&self) -> f32 {
+        (self.max - self.min).length() * 0.5
+    }
+}
+
+pub struct Model {
+    pub parts: Vec<ModelPart>,
+    pub bounds: Bounds,
+}
+
+/// Models converted so far, by normalized data path. Failed loads are cached too so they are only logged once.
+#[derive(Default)]
+pub struct ModelCache {
+    models: HashMap<String, Option<Model>>,
+    textures: TextureCache,
+}
+
+impl ModelCache {
+    pub fn load(
+        &mut self,
+        path: &str,
+        vfs: &VirtualFileSystem,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+        textures: &mut Assets<Texture>,
+    ) -> Option<&Model> {
+        let texture_cache = &mut self.textures;
+
+        self.models
+            .entry(path.to_string())
+            .or_insert_with(|| {
+                let nif = vfs.read(path).and_then(|bytes| Nif::parse(&bytes));
+
+                match nif {
+                    Ok(nif) => {
+                        let shapes = nif.shapes();
+                        let bounds = Bounds::from_points(
+                            shapes
+                                .iter()
+                                .flat_map(|shape| shape.vertices.positions.iter())
+                                .map(|&position| to_viewport(position)),
+                        );
+
+                        let parts = shapes
+                            .iter()
+                            .map(|shape| {
+                                let mut load = |path: &Option<String>, srgb: bool| {
+                                    let path = path.as_ref()?;
+                                    texture_cache.load(path, srgb, vfs, textures)
+                                };
+
+                                let shape_textures = ShapeTextures {
+                                    diffuse: load(&shape.material.diffuse, true),
+                                    normal: load(&shape.material.normal, false),
+                                    glow: load(&shape.material.glow, true),
+                                };
+
+                                let tangents = shape_textures.normal.is_some();
+
+                                ModelPart {
+                                    mesh: meshes.add(mesh(&shape.vertices, &shape.material, tangents)),
+                                    material: materials.add(material(&shape.material, shape_textures)),
+                                    transparent: shape.material.alpha_blend || shape.material.alpha < 1.0,
+                                }
+                            })
+                            .collect();
+
+                        Some(Model { parts, bounds })
+                    }
+                    Err(e) => {
+                        log::error!("Error loading model {}: {}", path, e);
+                        None
+                    }
+                }
+            })
+            .as_ref()
+    }
+}
+
+/// Spawns an entity for a model with one child per shape, returning the parent.
+pub fn spawn(commands: &mut Commands, model: &Model, transform: Transform) -> Entity {
+    commands
+        .spawn_bundle((transform, GlobalTransform::default(), model.bounds))
+        .with_children(|parent| {
+            for part in &model.parts {
+                parent.spawn_bundle(PbrBundle {
+                    mesh: part.mesh.clone(),
+                    material: part.material.clone(),
+                    visible: Visible {
+                        is_visible: true,
+                        is_transparent: part.transparent,
+                    },
+                    ..Default::default()
+                });
+            }
+        })
+        .id()
+}
+
+/// Builds a mesh in viewport coordinates. Normal-mapped materials also need `tangents`.
+pub fn mesh(vertices: &nif::Vertices, material: &nif::Material, tangents: bool) -> Mesh {
+    let positions: Vec<[f32; 3]> = vertices
+        .positions
+        .iter()
+        .map(|&position| to_viewport(position).into())
+        .collect();
+
+    let normals: Vec<[f32; 3]> = if vertices.normals.is_empty() {
+        smooth_normals(&positions, &vertices.triangles)
+    } else {
+        vertices
+            .normals
+            .iter()
+            .map(|&normal| direction_to_viewport(normal))
+            .collect()
+    };
+
+    let uvs: Vec<[f32; 2]> = if vertices.uvs.is_empty() {
+        vec![[0.0, 0.0]; positions.len()]
+    } else {
+        vertices
+            .uvs
+            .iter()
+            .map(|uv| {
+                [
+                    uv[0] * material.uv_scale[0] + material.uv_offset[0],
+                    uv[1] * material.uv_scale[1] + material.uv_offset[1],
+                ]
+            })
+            .collect()
+    };
+
+    let indices = vertices.triangles.iter().flatten().copied().collect();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+
+    if tangents {
+        mesh.set_attribute(
+            Mesh::ATTRIBUTE_TANGENT,
+            generate_tangents(&positions, &normals, &uvs, &vertices.triangles),
+        );
+    }
+
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U16(indices)));
+    mesh
+}
+
+/// Averages the normals of the faces around each vertex, for shapes stored without normals.
+fn smooth_normals(positions: &[[f32; 3]], triangles: &[[u16; 3]]) -> Vec<[f32; 3]> {
+    let mut normals = vec![Vec3::default(); positions.len()];
+
+    for triangle in triangles {
+        let a = Vec3::from(positions[triangle[0] as usize]);
+        let b = Vec3::from(positions[triangle[1] as usize]);
+        let c = Vec3::from(positions[triangle[2] as usize]);
+        let normal = (b - a).cross(c - a);
+
+        for &index in triangle {
+            normals[index as usize] += normal;
+        }
+    }
+
+    normals
+        .into_iter()
+        .map(|normal| {
+            if normal.length_squared() > 0.0 {
+                normal.normalize().into()
+            } else {
+                Vec3::unit_y().into()
+            }
+        })
+        .collect()
+}
+
+/// Per-vertex tangents with the bitangent's handedness in `w`, from each triangle's UV gradients.
+fn generate_tangents(
+    positions: &[[f32; 3]],
+    normals: &[[f32; 3]],
+    uvs: &[[f32; 2]],
+    triangles: &[[u16; 3]],
+) -> Vec<[f32; 4]> {
+    let mut tangents = vec![Vec3::default(); positions.len()];
+    let mut bitangents = vec![Vec3::default(); positions.len()];
+
+    for triangle in triangles {
+        let [i0, i1, i2] = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+        let edge1 = Vec3::from(positions[i1]) - Vec3::from(positions[i0]);
+        let edge2 = Vec3::from(positions[i2]) - Vec3::from(positions[i0]);
+        let (du1, dv1) = (uvs[i1][0] - uvs[i0][0], uvs[i1][1] - uvs[i0][1]);
+        let (du2, dv2) = (uvs[i2][0] - uvs[i0][0], uvs[i2][1] - uvs[i0][1]);
+
+        let determinant = du1 * dv2 - du2 * dv1;
+
+        if determinant.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let tangent = (edge1 * dv2 - edge2 * dv1) / determinant;
+        let bitangent = (edge2 * du1 - edge1 * du2) / determinant;
+
+        for &index in &[i0, i1, i2] {
+            tangents[index] += tangent;
+            bitangents[index] += bitangent;
+        }
+    }
+
+    tangents
+        .iter()
+        .zip(bitangents.iter())
+        .zip(normals.iter())
+        .map(|((&tangent, &bitangent), &normal)| {
+            let normal = Vec3::from(normal);
+            // Gram-Schmidt: make the tangent perpendicular to the normal.
+            let tangent = tangent - normal * normal.dot(tangent);
+
+            if tangent.length_squared() < f32::EPSILON {
+                return [1.0, 0.0, 0.0, 1.0];
+            }
+
+            let tangent = tangent.normalize();
+            let handedness = if normal.cross(tangent).dot(bitangent) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            [tangent.x, tangent.y, tangent.z, handedness]
+        })
+        .collect()
+}
+
+/// The textures of one shape that loaded successfully.
+pub struct ShapeTextures {
+    pub diffuse: Option<Handle<Texture>>,
+    pub normal: Option<Handle<Texture>>,
+    pub glow: Option<Handle<Texture>>,
+}
+
+pub fn material(material: &nif::Material, textures: ShapeTextures) -> StandardMaterial {
+    StandardMaterial {
+        base_color: Color::rgba(1.0, 1.0, 1.0, material.alpha),
+        base_color_texture: textures.diffuse,
+        normal_map: textures.normal,
+        emissive_texture: textures.glow,
+        roughness: 1.0 / (1.0 + material.glossiness / 20.0),
+        metallic: 0.0,
+        reflectance: 0.3,
+        double_sided: material.double_sided,
+        emissive: Color::rgb(material.emissive[0], material.emissive[1], material.emissive[2]),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,630 @@
+//! The navmesh overlay and navmesh editing.
+//!
+//! While the Navmesh window is open, the loaded cell's navmeshes are drawn over it as translucent triangles coloured
+//! by their flags, with edge links and door portals drawn as lines. Clicking picks vertices and triangles, and
+//! dragging a vertex moves it across the horizontal plane it sits on. Every edit is written to the active plugin
+//! straight away.
+
+use std::collections::HashSet;
+
+use bevy::{
+    prelude::*,
+    render::{camera::PerspectiveProjection, mesh::Indices, pipeline::PrimitiveTopology},
+};
+use bevy_egui::EguiContext;
+use open_creation_data::esp::{
+    navmesh::{triangle_flags, Triangle},
+    Navmesh, Reference,
+};
+use open_creation_ui::{NavmeshAction, NavmeshItem, NavmeshState};
+use open_creation_util::log;
+
+use crate::{
+    camera::ViewportCamera, cell::LoadedCell, gizmo::GizmoState, model, ray::Ray, records::RecordsResource, ui_state,
+};
+
+/// Lifts the overlay off the surfaces it follows, in game units, so it is not hidden by them.
+const OVERLAY_LIFT: f32 = 4.0;
+const TRIANGLE_ALPHA: f32 = 0.4;
+/// The height of the posts marking selected vertices, in game units.
+const VERTEX_POST_HEIGHT: f32 = 64.0;
+/// How far from the cursor's ray a vertex can be and still be picked, relative to its distance from the camera.
+const PICK_TOLERANCE: f32 = 0.01;
+/// How many triangles of each island the validation report lists.
+const REPORTED_TRIANGLES: usize = 12;
+
+/// Marks the entities drawing the navmesh overlay.
+pub struct NavmeshOverlay;
+
+/// The navmeshes of the loaded cell, as they are being edited.
+#[derive(Default)]
+pub struct LoadedNavmeshes {
+    pub navmeshes: Vec<Navmesh>,
+    /// Whether the overlay needs drawing again.
+    changed: bool,
+}
+
+impl LoadedNavmeshes {
+    fn get(&self, form_id: Option<u32>) -> Option<&Navmesh> {
+        self.navmeshes.iter().find(|navmesh| Some(navmesh.form_id) == form_id)
+    }
+
+    fn get_mut(&mut self, form_id: Option<u32>) -> Option<&mut Navmesh> {
+        self.navmeshes
+            .iter_mut()
+            .find(|navmesh| Some(navmesh.form_id) == form_id)
+    }
+}
+
+/// Sent with an action from the Navmesh window, or from the viewport's keyboard shortcuts.
+pub struct NavmeshEdit(pub NavmeshAction);
+
+/// A vertex being dragged in the viewport.
+#[derive(Default)]
+pub struct VertexDrag {
+    vertex: Option<u16>,
+    /// From the point under the cursor to the vertex, so the vertex does not jump to the cursor.
+    offset: Vec3,
+    /// The point under the cursor on the previous frame of the drag.
+    last_hit: Option<Vec3>,
+    moved: bool,
+}
+
+/// What the overlay colours a triangle by. Door triangles take precedence over water, water over preferred
+/// pathing, and so on.
+#[derive(Clone, Copy, PartialEq)]
+enum TriangleKind {
+    Walkable,
+    Preferred,
+    Water,
+    Cover,
+    Door,
+    Selected,
+}
+
+impl TriangleKind {
+    const ALL: [TriangleKind; 6] = [
+        TriangleKind::Walkable,
+        TriangleKind::Preferred,
+        TriangleKind::Water,
+        TriangleKind::Cover,
+        TriangleKind::Door,
+        TriangleKind::Selected,
+    ];
+
+    fn of(navmesh: &Navmesh, index: usize, triangle: &Triangle) -> Self {
+        let is_cover = triangle.cover != 0 || navmesh.cover_triangles.contains(&(index as i16));
+
+        if triangle.flags & triangle_flags::DOOR != 0 {
+            TriangleKind::Door
+        } else if triangle.flags & triangle_flags::WATER != 0 {
+            TriangleKind::Water
+        } else if is_cover {
+            TriangleKind::Cover
+        } else if triangle.flags & triangle_flags::PREFERRED != 0 {
+            TriangleKind::Preferred
+        } else {
+            TriangleKind::Walkable
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            TriangleKind::Walkable => Color::rgba(0.2, 0.4, 1.0, TRIANGLE_ALPHA),
+            TriangleKind::Preferred => Color::rgba(0.2, 0.8, 0.3, TRIANGLE_ALPHA),
+            TriangleKind::Water => Color::rgba(0.1, 0.8, 0.9, TRIANGLE_ALPHA),
+            TriangleKind::Cover => Color::rgba(0.9, 0.2, 0.2, TRIANGLE_ALPHA),
+            TriangleKind::Door => Color::rgba(1.0, 0.55, 0.1, TRIANGLE_ALPHA),
+            TriangleKind::Selected => Color::rgba(1.0, 0.9, 0.1, TRIANGLE_ALPHA + 0.2),
+        }
+    }
+}
+
+/// A vertex's position in the viewport, lifted with the rest of the overlay.
+fn overlay_position(vertex: [f32; 3]) -> Vec3 {
+    model::to_viewport([vertex[0], vertex[1], vertex[2] + OVERLAY_LIFT])
+}
+
+/// The corners of a triangle in the viewport, or `None` if it uses a vertex that does not exist.
+fn triangle_corners(navmesh: &Navmesh, triangle: &Triangle) -> Option<[Vec3; 3]> {
+    let corner = |index: usize| {
+        navmesh
+            .vertices
+            .get(triangle.vertices[index] as usize)
+            .map(|&vertex| overlay_position(vertex))
+    };
+
+    Some([corner(0)?, corner(1)?, corner(2)?])
+}
+
+fn overlay_mesh(topology: PrimitiveTopology, positions: Vec<[f32; 3]>) -> Mesh {
+    let indices = (0..positions.len() as u32).collect();
+    let mut mesh = Mesh::new(topology);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; positions.len()]);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// Fills the Navmesh window's list of navmeshes, and the position of the only selected vertex.
+fn update_window_state(loaded: &LoadedNavmeshes, state: &mut NavmeshState) {
+    state.navmeshes = loaded
+        .navmeshes
+        .iter()
+        .map(|navmesh| NavmeshItem {
+            form_id: navmesh.form_id,
+            vertices: navmesh.vertices.len(),
+            triangles: navmesh.triangles.len(),
+        })
+        .collect();
+
+    state.vertex_position = match (loaded.get(state.selected_navmesh), &state.selected_vertices[..]) {
+        (Some(navmesh), [vertex]) => navmesh.vertices.get(*vertex as usize).copied(),
+        _ => None,
+    };
+}
+
+/// Writes a navmesh to the active plugin.
+fn save_navmesh(navmesh: &Navmesh, records: &mut RecordsResource) {
+    match records.load_order.override_record(navmesh.form_id) {
+        Some(record) => {
+            if let Err(e) = navmesh.write(record) {
+                log::error!("Error writing navmesh {:08X}: {}", navmesh.form_id, e);
+            }
+        }
+        None => log::warn!("Cannot save navmesh edits without an active plugin"),
+    }
+}
+
+/// Lists islands and problems for each navmesh, or only the one being edited if there is one.
+fn validation_report(loaded: &LoadedNavmeshes, selected: Option<u32>, records: &RecordsResource) -> Vec<String> {
+    let mut lines = vec![];
+
+    for navmesh in &loaded.navmeshes {
+        if selected.is_some() && Some(navmesh.form_id) != selected {
+            continue;
+        }
+
+        let report = navmesh.validate();
+        let mut problems = report.problems;
+        problems.extend(navmesh.check_links(&records.load_order));
+
+        lines.push(format!(
+            "Navmesh {:08X}: {} islands, {} problems",
+            navmesh.form_id,
+            report.islands.len(),
+            problems.len()
+        ));
+
+        // A single island is what a well connected navmesh has, so only list them when there are several.
+        if report.islands.len() > 1 {
+            for (index, island) in report.islands.iter().enumerate() {
+                let mut triangles: Vec<String> = island
+                    .iter()
+                    .take(REPORTED_TRIANGLES)
+                    .map(|triangle| triangle.to_string())
+                    .collect();
+
+                if island.len() > REPORTED_TRIANGLES {
+                    triangles.push("...".to_string());
+                }
+
+                lines.push(format!(
+                    "  Island {}: {} triangles ({})",
+                    index + 1,
+                    island.len(),
+                    triangles.join(", ")
+                ));
+            }
+        }
+
+        for problem in problems {
+            lines.push(format!("  {}", problem));
+        }
+    }
+
+    if lines.is_empty() {
+        lines.push("There are no navmeshes to validate.".to_string());
+    }
+
+    lines
+}
+
+/// Reads the navmeshes of a newly loaded cell.
+pub fn load_navmeshes(
+    loaded_cell: Res<LoadedCell>,
+    records: Res<RecordsResource>,
+    mut loaded: ResMut<LoadedNavmeshes>,
+    mut ui_state: ResMut<ui_state::State>,
+) {
+    if !loaded_cell.is_changed() {
+        return;
+    }
+
+    let navmeshes = match loaded_cell.form_id {
+        Some(cell) => records.load_order.cell_navmeshes(cell),
+        None => vec![],
+    };
+
+    loaded.navmeshes = navmeshes
+        .into_iter()
+        .filter_map(|record| match Navmesh::from_record(record) {
+            Ok(navmesh) => Some(navmesh),
+            Err(e) => {
+                log::warn!("Error reading navmesh {:08X}: {}", record.form_id, e);
+                None
+            }
+        })
+        .collect();
+    loaded.changed = true;
+
+    let state = &mut ui_state.navmesh;
+    state.selected_navmesh = loaded.navmeshes.first().map(|navmesh| navmesh.form_id);
+    state.clear_selection();
+    state.report.clear();
+    update_window_state(&loaded, state);
+}
+
+/// Picks vertices and triangles under the cursor, drags vertices, and deletes the selected triangles with the
+/// Delete key. The landscape brushes keep the mouse while the Landscape window is open too.
+#[allow(clippy::too_many_arguments)]
+pub fn navmesh_interaction(
+    egui_context: Res<EguiContext>,
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    gizmo: Res<GizmoState>,
+    mut loaded: ResMut<LoadedNavmeshes>,
+    mut records: ResMut<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+    mut edits: EventWriter<NavmeshEdit>,
+    mut drag: Local<VertexDrag>,
+    cameras: Query<(&GlobalTransform, &PerspectiveProjection), With<ViewportCamera>>,
+) {
+    let ctx = egui_context.ctx();
+    let active = ui_state.show_navmesh && !ui_state.show_landscape;
+    let state = &mut ui_state.navmesh;
+
+    if !active {
+        drag.vertex = None;
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Delete) && !ctx.wants_keyboard_input() && !state.selected_triangles.is_empty() {
+        edits.send(NavmeshEdit(NavmeshAction::DeleteTriangles));
+    }
+
+    let ray = match cameras.iter().next() {
+        Some((transform, projection)) => Ray::from_cursor(&windows, transform, projection),
+        None => None,
+    };
+
+    if let Some(vertex) = drag.vertex {
+        if !buttons.pressed(MouseButton::Left) {
+            if drag.moved {
+                if let Some(navmesh) = loaded.get(state.selected_navmesh) {
+                    save_navmesh(navmesh, &mut records);
+                }
+            }
+
+            *drag = VertexDrag::default();
+            return;
+        }
+
+        let navmesh = match loaded.get_mut(state.selected_navmesh) {
+            Some(navmesh) => navmesh,
+            None => return,
+        };
+
+        let position = &mut navmesh.vertices[vertex as usize];
+        let plane = overlay_position(*position);
+        let hit = ray.and_then(|ray| {
+            ray.intersect_plane(plane, Vec3::unit_y())
+                .map(|distance| ray.at(distance))
+        });
+
+        let hit = match hit {
+            Some(hit) => hit,
+            None => return,
+        };
+
+        match drag.last_hit {
+            None => drag.offset = plane - hit,
+            Some(last_hit) if last_hit == hit => {}
+            Some(_) => {
+                let moved = model::from_viewport(hit + drag.offset);
+                position[0] = moved[0];
+                position[1] = moved[1];
+                drag.moved = true;
+                loaded.changed = true;
+                update_window_state(&loaded, state);
+            }
+        }
+
+        drag.last_hit = Some(hit);
+
+        return;
+    }
+
+    if !buttons.just_pressed(MouseButton::Left) || ctx.wants_pointer_input() || gizmo.is_busy() {
+        return;
+    }
+
+    let ray = match ray {
+        Some(ray) => ray,
+        None => return,
+    };
+
+    let nearest = |nearest: Option<(f32, u32, usize)>, hit: (f32, u32, usize)| match nearest {
+        Some(nearest) if nearest.0 <= hit.0 => Some(nearest),
+        _ => Some(hit),
+    };
+
+    let vertex_hit = loaded
+        .navmeshes
+        .iter()
+        .flat_map(|navmesh| {
+            navmesh.vertices.iter().enumerate().filter_map(move |(index, &vertex)| {
+                let (distance, offset) = ray.closest_to_point(overlay_position(vertex));
+
+                if distance > 0.0 && offset < distance * PICK_TOLERANCE {
+                    Some((distance, navmesh.form_id, index))
+                } else {
+                    None
+                }
+            })
+        })
+        .fold(None, nearest);
+
+    let triangle_hit = loaded
+        .navmeshes
+        .iter()
+        .flat_map(|navmesh| {
+            navmesh
+                .triangles
+                .iter()
+                .enumerate()
+                .filter_map(move |(index, triangle)| {
+                    let corners = triangle_corners(navmesh, triangle)?;
+                    ray.intersect_triangle(corners)
+                        .map(|distance| (distance, navmesh.form_id, index))
+                })
+        })
+        .fold(None, nearest);
+
+    let adding = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    let hit_navmesh = vertex_hit.or(triangle_hit).map(|(_, form_id, _)| form_id);
+
+    if !adding || (hit_navmesh.is_some() && hit_navmesh != state.selected_navmesh) {
+        state.clear_selection();
+    }
+
+    if let Some(form_id) = hit_navmesh {
+        state.selected_navmesh = Some(form_id);
+    }
+
+    if let Some((_, _, vertex)) = vertex_hit {
+        let vertex = vertex as u16;
+
+        match state.selected_vertices.iter().position(|&selected| selected == vertex) {
+            Some(position) if adding => {
+                state.selected_vertices.remove(position);
+            }
+            Some(_) => {}
+            None => state.selected_vertices.push(vertex),
+        }
+
+        if !adding {
+            drag.vertex = Some(vertex);
+        }
+    } else if let Some((_, _, triangle)) = triangle_hit {
+        match state
+            .selected_triangles
+            .iter()
+            .position(|&selected| selected == triangle)
+        {
+            Some(position) if adding => {
+                state.selected_triangles.remove(position);
+            }
+            Some(_) => {}
+            None => state.selected_triangles.push(triangle),
+        }
+    }
+
+    loaded.changed = true;
+    update_window_state(&loaded, state);
+}
+
+/// Carries out the Navmesh window's actions on the navmesh being edited.
+pub fn apply_navmesh_edits(
+    mut edits: EventReader<NavmeshEdit>,
+    mut loaded: ResMut<LoadedNavmeshes>,
+    mut records: ResMut<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+) {
+    let state = &mut ui_state.navmesh;
+
+    for NavmeshEdit(action) in edits.iter() {
+        if let NavmeshAction::Validate = action {
+            state.report = validation_report(&loaded, state.selected_navmesh, &records);
+            continue;
+        }
+
+        if let NavmeshAction::SelectNavmesh(form_id) = action {
+            state.selected_navmesh = Some(*form_id);
+            state.clear_selection();
+            loaded.changed = true;
+            continue;
+        }
+
+        let navmesh = match loaded.get_mut(state.selected_navmesh) {
+            Some(navmesh) => navmesh,
+            None => continue,
+        };
+
+        match action {
+            NavmeshAction::MoveVertex(vertex, position) => match navmesh.vertices.get_mut(*vertex as usize) {
+                Some(vertex) => *vertex = *position,
+                None => continue,
+            },
+            NavmeshAction::DeleteTriangles => {
+                let removed: HashSet<usize> = state.selected_triangles.drain(..).collect();
+                navmesh.delete_triangles(&removed);
+                log::info!(
+                    "Deleted {} triangles from navmesh {:08X}",
+                    removed.len(),
+                    navmesh.form_id
+                );
+            }
+            NavmeshAction::CreateTriangle => {
+                let vertices = match state.selected_vertices[..] {
+                    [a, b, c] if a != b && b != c && a != c => [a, b, c],
+                    _ => {
+                        log::warn!("Select three different vertices to create a triangle");
+                        continue;
+                    }
+                };
+
+                let triangle = navmesh.add_triangle(vertices);
+                state.selected_vertices.clear();
+                state.selected_triangles = vec![triangle];
+                log::info!("Created triangle {} in navmesh {:08X}", triangle, navmesh.form_id);
+            }
+            _ => continue,
+        }
+
+        save_navmesh(navmesh, &mut records);
+        loaded.changed = true;
+    }
+
+    if loaded.changed {
+        update_window_state(&loaded, state);
+    }
+}
+
+/// Draws the overlay again whenever the navmeshes or the selection change, or it is shown or hidden.
+pub fn draw_navmeshes(
+    mut commands: Commands,
+    records: Res<RecordsResource>,
+    mut loaded: ResMut<LoadedNavmeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    ui_state: Res<ui_state::State>,
+    mut was_shown: Local<bool>,
+    overlay: Query<Entity, With<NavmeshOverlay>>,
+) {
+    let state = &ui_state.navmesh;
+    let shown = ui_state.show_navmesh && state.show_overlay;
+
+    if !loaded.changed && shown == *was_shown {
+        return;
+    }
+
+    loaded.changed = false;
+    *was_shown = shown;
+
+    for entity in overlay.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !shown {
+        return;
+    }
+
+    let mut triangles: Vec<Vec<[f32; 3]>> = vec![vec![]; TriangleKind::ALL.len()];
+    let mut edge_links: Vec<[f32; 3]> = vec![];
+    let mut door_portals: Vec<[f32; 3]> = vec![];
+    let mut vertex_posts: Vec<[f32; 3]> = vec![];
+
+    for navmesh in &loaded.navmeshes {
+        let is_selected = Some(navmesh.form_id) == state.selected_navmesh;
+
+        for (index, triangle) in navmesh.triangles.iter().enumerate() {
+            let corners = match triangle_corners(navmesh, triangle) {
+                Some(corners) => corners,
+                None => continue,
+            };
+
+            let kind = if is_selected && state.selected_triangles.contains(&index) {
+                TriangleKind::Selected
+            } else {
+                TriangleKind::of(navmesh, index, triangle)
+            };
+
+            let kind = TriangleKind::ALL.iter().position(|&other| other == kind).unwrap();
+            triangles[kind].extend(corners.iter().map(|&corner| <[f32; 3]>::from(corner)));
+
+            for edge in 0..3 {
+                if triangle.is_edge_link(edge) {
+                    edge_links.push(corners[edge].into());
+                    edge_links.push(corners[(edge + 1) % 3].into());
+                }
+            }
+        }
+
+        // Door portals run from the middle of their triangle to the door, or straight up if the door is missing.
+        for door in &navmesh.doors {
+            let corners = match navmesh
+                .triangles
+                .get(door.triangle as usize)
+                .and_then(|triangle| triangle_corners(navmesh, triangle))
+            {
+                Some(corners) => corners,
+                None => continue,
+            };
+
+            let center = (corners[0] + corners[1] + corners[2]) / 3.0;
+            let target = records
+                .load_order
+                .record(door.door)
+                .and_then(Reference::from_record)
+                .map(|reference| model::to_viewport(reference.placement.position))
+                .unwrap_or_else(|| center + Vec3::unit_y() * VERTEX_POST_HEIGHT * model::UNIT);
+
+            door_portals.push(center.into());
+            door_portals.push(target.into());
+        }
+
+        if is_selected {
+            for &vertex in &state.selected_vertices {
+                if let Some(&position) = navmesh.vertices.get(vertex as usize) {
+                    let bottom = overlay_position(position);
+                    vertex_posts.push(bottom.into());
+                    vertex_posts.push((bottom + Vec3::unit_y() * VERTEX_POST_HEIGHT * model::UNIT).into());
+                }
+            }
+        }
+    }
+
+    let mut spawn = |topology: PrimitiveTopology, positions: Vec<[f32; 3]>, color: Color| {
+        if positions.is_empty() {
+            return;
+        }
+
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: meshes.add(overlay_mesh(topology, positions)),
+                material: materials.add(StandardMaterial {
+                    base_color: color,
+                    unlit: true,
+                    double_sided: true,
+                    ..Default::default()
+                }),
+                visible: Visible {
+                    is_visible: true,
+                    is_transparent: color.a() < 1.0,
+                },
+                ..Default::default()
+            })
+            .insert(NavmeshOverlay);
+    };
+
+    for (kind, positions) in TriangleKind::ALL.iter().zip(triangles) {
+        spawn(PrimitiveTopology::TriangleList, positions, kind.color());
+    }
+
+    spawn(PrimitiveTopology::LineList, edge_links, Color::rgb(0.9, 0.1, 0.9));
+    spawn(PrimitiveTopology::LineList, door_portals, Color::rgb(1.0, 0.55, 0.1));
+    spawn(PrimitiveTopology::LineList, vertex_posts, Color::rgb(1.0, 0.9, 0.1));
+}
@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+use open_creation_data::esp::{LoadOrder, Npc};
+use open_creation_util::log;
+
+use crate::{
+    records::{RecordSelected, RecordsResource},
+    ui_state,
+};
+
+/// Sent when the NPC in the NPC window has been edited.
+pub struct NpcEdited;
+
+/// Reads an NPC for the NPC window to follow templates through, or `None` if `form_id` is not one.
+pub fn read_npc(load_order: &LoadOrder, form_id: u32) -> Option<Npc> {
+    let record = load_order.record(form_id).filter(|record| &record.code == b"NPC_")?;
+    let mut npc = Npc::from_record(record, load_order.is_localized(form_id)).ok()?;
+    load_order.resolve_texts(form_id, npc.texts_mut());
+    Some(npc)
+}
+
+/// Opens NPC_ records selected in the tree view in the NPC window.
+pub fn open_npcs(
+    mut record_selections: EventReader<RecordSelected>,
+    records: Res<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+) {
+    for RecordSelected(form_id) in record_selections.iter() {
+        let load_order = &records.load_order;
+
+        let record = match load_order.record(*form_id) {
+            Some(record) if &record.code == b"NPC_" => record,
+            _ => continue,
+        };
+
+        match Npc::from_record(record, load_order.is_localized(*form_id)) {
+            Ok(mut npc) => {
+                load_order.resolve_texts(*form_id, npc.texts_mut());
+                ui_state.npc.open(npc);
+                ui_state.show_npc = true;
+            }
+            Err(e) => log::error!("Error reading NPC {:08X}: {}", form_id, e),
+        }
+    }
+}
+
+/// Writes the NPC being edited to the active plugin, copying it there first if it comes from another plugin.
+pub fn apply_npc_edits(
+    mut edits: EventReader<NpcEdited>,
+    mut records: ResMut<RecordsResource>,
+    ui_state: Res<ui_state::State>,
+) {
+    if edits.iter().count() == 0 {
+        return;
+    }
+
+    let npc = match &ui_state.npc.npc {
+        Some(npc) => npc,
+        None => return,
+    };
+
+    let renamed = records.load_order.editor_id(npc.form_id).as_deref() != Some(npc.editor_id.as_str());
+
+    match records.load_order.override_record(npc.form_id) {
+        Some(record) => npc.write(record),
+        None => {
+            log::warn!("Cannot save NPC {:08X} without an active plugin", npc.form_id);
+            return;
+        }
+    }
+
+    if renamed {
+        records.invalidate(*b"NPC_");
+    }
+}
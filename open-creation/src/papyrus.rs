@@ -0,0 +1,139 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use open_creation_data::{
+    papyrus::{Diagnostic, SOURCE_FOLDER},
+    vfs::VirtualFileSystem,
+};
+use open_creation_ui::{PapyrusAction, PapyrusState};
+use open_creation_util::{log, Settings};
+
+/// Sources are saved as loose files, where they take priority over those in archives.
+fn source_path(data_path: &str, script: &str) -> PathBuf {
+    Path::new(data_path)
+        .join("Scripts")
+        .join("Source")
+        .join(format!("{}.psc", script))
+}
+
+/// Reads a source, from the loose file if it has been saved since the data files were loaded.
+fn read_source(data_path: &str, vfs: &VirtualFileSystem, script: &str) -> Option<String> {
+    if let Ok(source) = std::fs::read(source_path(data_path, script)) {
+        return Some(String::from_utf8_lossy(&source).replace("\r\n", "\n"));
+    }
+
+    match vfs.read(&format!("{}\\{}.psc", SOURCE_FOLDER, script.to_lowercase())) {
+        Ok(source) => Some(String::from_utf8_lossy(&source).replace("\r\n", "\n")),
+        Err(e) => {
+            log::error!("Error reading the source of {}: {}", script, e);
+            None
+        }
+    }
+}
+
+fn save(data_path: &str, state: &mut PapyrusState) -> bool {
+    let script = match &state.script {
+        Some(script) => script,
+        None => return false,
+    };
+
+    let path = source_path(data_path, script);
+    let result = std::fs::create_dir_all(path.parent().unwrap())
+        .and_then(|_| std::fs::write(&path, state.source.replace('\n', "\r\n")));
+
+    match result {
+        Ok(_) => {
+            log::info!("Saved {}", path.to_string_lossy());
+            state.modified = false;
+            true
+        }
+        Err(e) => {
+            log::error!("Error saving {}: {}", path.to_string_lossy(), e);
+            false
+        }
+    }
+}
+
+pub fn handle(action: PapyrusAction, state: &mut PapyrusState, vfs: &VirtualFileSystem, settings: &Settings) {
+    match action {
+        PapyrusAction::Open(script) => {
+            if let Some(source) = read_source(&settings.data_path, vfs, &script) {
+                state.open(script, source);
+            }
+        }
+        PapyrusAction::Save => {
+            save(&settings.data_path, state);
+        }
+        PapyrusAction::Compile => {
+            if save(&settings.data_path, state) {
+                if let Some(script) = &state.script {
+                    compile_in_background(settings, script);
+                }
+            }
+        }
+    }
+}
+
+/// Opens the source a diagnostic was reported in at its line.
+pub fn go_to(diagnostic: &Diagnostic, state: &mut PapyrusState, vfs: &VirtualFileSystem, settings: &Settings) {
+    let script = diagnostic.script.to_lowercase();
+
+    if state.script.as_deref() != Some(script.as_str()) {
+        match read_source(&settings.data_path, vfs, &script) {
+            Some(source) => state.open(script, source),
+            None => return,
+        }
+    }
+
+    state.go_to_line(diagnostic.line);
+}
+
+/// Runs the compiler on a saved source on a background thread, logging what it prints. The compiled script is
+/// written to `Scripts` in the data folder.
+pub fn compile_in_background(settings: &Settings, script: &str) {
+    let compiler = match &settings.papyrus.compiler {
+        Some(compiler) => compiler.clone(),
+        None => {
+            log::error!("No Papyrus compiler is set in config.toml");
+            return;
+        }
+    };
+
+    let output = Path::new(&settings.data_path).join("Scripts");
+    let mut imports = vec![output.join("Source").to_string_lossy().into_owned()];
+    imports.extend(settings.papyrus.imports.iter().cloned());
+
+    let mut command = Command::new(&compiler);
+    command
+        .arg(format!("{}.psc", script))
+        .arg(format!("-f={}", settings.papyrus.flags))
+        .arg(format!("-i={}", imports.join(";")))
+        .arg(format!("-o={}", output.to_string_lossy()));
+
+    let script = script.to_string();
+    log::info!("Compiling {}", script);
+
+    std::thread::spawn(move || match command.output() {
+        Ok(result) => {
+            let stdout = String::from_utf8_lossy(&result.stdout);
+            let stderr = String::from_utf8_lossy(&result.stderr);
+
+            for line in stdout.lines().chain(stderr.lines()) {
+                if Diagnostic::parse(line).is_some() {
+                    log::error!("{}", line);
+                } else if !line.trim().is_empty() {
+                    log::info!("{}", line);
+                }
+            }
+
+            if result.status.success() {
+                log::info!("Compiled {}", script);
+            } else {
+                log::error!("Compiling {} failed ({})", script, result.status);
+            }
+        }
+        Err(e) => log::error!("Error running the Papyrus compiler {}: {}", compiler, e),
+    });
+}
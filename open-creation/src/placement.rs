@@ -0,0 +1,172 @@
+//! Placing base objects in the loaded cell by dragging them from the tree view into the viewport.
+
+use bevy::{prelude::*, render::camera::PerspectiveProjection};
+use bevy_egui::EguiContext;
+use open_creation_data::esp::{code_name, Placement, PLACEABLE_CODES};
+use open_creation_ui::ReferenceItem;
+use open_creation_util::log;
+
+use crate::{
+    camera::ViewportCamera,
+    cell::{LoadedCell, PlacedReference},
+    data_files::DataFilesResource,
+    model::{self, Bounds, ModelCache},
+    ray::Ray,
+    records::RecordsResource,
+    selection::{self, Selected},
+    terrain::LoadedTerrain,
+    ui_state,
+};
+
+/// Where the cursor's ray meets the scene: the nearest triangle of a placed object or the loaded terrain, or
+/// failing both the horizontal plane through the camera's focus point. Only objects whose bounds the ray passes
+/// through have their triangles tested.
+fn surface_point(
+    ray: &Ray,
+    camera: &ViewportCamera,
+    placed: &Query<(&PlacedReference, &GlobalTransform, &Bounds, &Children)>,
+    parts: &Query<&Handle<Mesh>>,
+    meshes: &Assets<Mesh>,
+    terrain: &LoadedTerrain,
+) -> Vec3 {
+    let objects = placed
+        .iter()
+        .filter(|(_, transform, bounds, _)| ray.intersect_bounds(transform, bounds).is_some())
+        .flat_map(|(_, transform, _, children)| {
+            children.iter().filter_map(move |&part| {
+                let mesh = meshes.get(parts.get(part).ok()?)?;
+                ray.intersect_mesh(transform, mesh)
+            })
+        });
+    let ground = terrain.raycast(ray).map(|point| (point - ray.origin).length());
+
+    let nearest = objects
+        .chain(ground)
+        .fold(None, |nearest: Option<f32>, distance| match nearest {
+            Some(nearest) if nearest <= distance => Some(nearest),
+            _ => Some(distance),
+        });
+
+    let distance = nearest
+        .or_else(|| ray.intersect_plane(camera.focus, Vec3::unit_y()))
+        .unwrap_or(camera.distance);
+
+    ray.at(distance)
+}
+
+/// Creates a reference when a record dragged from the tree view is dropped on the viewport, and selects it.
+#[allow(clippy::too_many_arguments)]
+pub fn drop_records(
+    mut commands: Commands,
+    egui_context: Res<EguiContext>,
+    buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    mut records: ResMut<RecordsResource>,
+    data_files: Res<DataFilesResource>,
+    mut models: ResMut<ModelCache>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    loaded_cell: Res<LoadedCell>,
+    mut ui_state: ResMut<ui_state::State>,
+    cameras: Query<(&GlobalTransform, &PerspectiveProjection, &ViewportCamera)>,
+    placed: Query<(&PlacedReference, &GlobalTransform, &Bounds, &Children)>,
+    selected: Query<Entity, With<Selected>>,
+    // Grouped to stay within Bevy's limit of 16 system parameters.
+    (terrain, parts): (Res<LoadedTerrain>, Query<&Handle<Mesh>>),
+) {
+    let base = match ui_state.dragged_record {
+        Some(base) if buttons.just_released(MouseButton::Left) => base,
+        _ => return,
+    };
+
+    ui_state.dragged_record = None;
+
+    // Dropped on a window or panel rather than the viewport.
+    if egui_context.ctx().is_pointer_over_area() {
+        return;
+    }
+
+    let cell = match loaded_cell.form_id {
+        Some(cell) => cell,
+        None => {
+            log::warn!("Load a cell before placing objects in it");
+            return;
+        }
+    };
+
+    let (code, path) = match records.load_order.record(base) {
+        Some(record) => (record.code, record.model_path()),
+        None => return,
+    };
+
+    if !PLACEABLE_CODES.contains(&code) {
+        log::warn!("{} records cannot be placed in a cell", code_name(code));
+        return;
+    }
+
+    let ray = cameras
+        .iter()
+        .next()
+        .and_then(|(transform, projection, camera)| Some((Ray::from_cursor(&windows, transform, projection)?, camera)));
+
+    let point = match ray {
+        Some((ray, camera)) => surface_point(&ray, camera, &placed, &parts, &meshes, &terrain),
+        None => return,
+    };
+
+    let model = match &path {
+        Some(path) => models.load(path, &data_files.vfs, &mut meshes, &mut materials, &mut textures),
+        None => None,
+    };
+
+    // Rest the object on the surface rather than sinking it halfway in.
+    let point = match model {
+        Some(model) => point - Vec3::unit_y() * model.bounds.min.y,
+        None => point,
+    };
+
+    let placement = Placement {
+        position: model::from_viewport(point),
+        ..Placement::default()
+    };
+
+    let form_id = match records.load_order.add_reference(cell, base, &placement) {
+        Some(form_id) => form_id,
+        None => {
+            log::warn!("Set an active plugin to place objects");
+            return;
+        }
+    };
+
+    log::info!("Placed {:08X} in cell {:08X} as {:08X}", base, cell, form_id);
+
+    for entity in selected.iter() {
+        commands.entity(entity).remove::<Selected>();
+    }
+
+    if let Some(model) = model {
+        let entity = model::spawn(&mut commands, model, model::placement_transform(&placement));
+        commands
+            .entity(entity)
+            .insert(PlacedReference(form_id))
+            .insert(Selected);
+    }
+
+    let ui_state = &mut *ui_state;
+
+    ui_state.cell_view.references.push(ReferenceItem {
+        form_id,
+        code: records
+            .load_order
+            .record(form_id)
+            .map(|record| code_name(record.code))
+            .unwrap_or_default(),
+        base: records
+            .load_order
+            .editor_id(base)
+            .unwrap_or_else(|| format!("{:08X}", base)),
+    });
+
+    selection::show_in_transform_panel(&records, form_id, ui_state);
+}
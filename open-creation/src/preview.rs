@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContext;
+use open_creation_data::{dds::Dds, esp::code_name};
+use open_creation_ui::TextureInfo;
+use open_creation_util::log;
+
+use crate::{
+    camera::FrameRequest,
+    data_files::{AssetSelected, DataFilesResource},
+    model::{self, ModelCache},
+    records::{RecordSelected, RecordsResource},
+    selection::Selected,
+    textures, ui_state,
+};
+
+/// The egui texture ID the texture preview window draws.
+pub const TEXTURE_PREVIEW_ID: u64 = 1;
+
+/// Marks the root entity of the model shown for the current selection.
+pub struct Preview;
+
+/// Replaces the previewed model when a record with a model, or a `.nif` file, is selected, and frames it.
+#[allow(clippy::too_many_arguments)]
+pub fn preview_selection(
+    mut commands: Commands,
+    mut record_selections: EventReader<RecordSelected>,
+    mut asset_selections: EventReader<AssetSelected>,
+    records: Res<RecordsResource>,
+    data_files: Res<DataFilesResource>,
+    mut models: ResMut<ModelCache>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut frame_requests: EventWriter<FrameRequest>,
+    previews: Query<Entity, With<Preview>>,
+) {
+    let mut selected = false;
+    let mut path = None;
+
+    for RecordSelected(form_id) in record_selections.iter() {
+        match records.load_order.record(*form_id) {
+            // Cells are loaded into the viewport instead of previewed.
+            Some(record) if &record.code == b"CELL" => {}
+            Some(record) => {
+                selected = true;
+                path = record.model_path();
+
+                if path.is_none() {
+                    log::debug!("{} {:08X} has no model", code_name(record.code), form_id);
+                }
+            }
+            None => {
+                selected = true;
+                path = None;
+                log::warn!("Selected record {:08X} was not found", form_id);
+            }
+        }
+    }
+
+    for AssetSelected(asset) in asset_selections.iter() {
+        if asset.ends_with(".nif") {
+            selected = true;
+            path = Some(asset.clone());
+        }
+    }
+
+    if !selected {
+        return;
+    }
+
+    for entity in previews.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let model = match &path {
+        Some(path) => models.load(path, &data_files.vfs, &mut meshes, &mut materials, &mut textures),
+        None => None,
+    };
+
+    if let Some(model) = model {
+        let entity = model::spawn(&mut commands, model, Transform::default());
+        commands.entity(entity).insert(Preview).insert(Selected);
+
+        frame_requests.send(FrameRequest {
+            center: model.bounds.center(),
+            radius: model.bounds.radius(),
+        });
+    }
+}
+
+/// Decodes a selected `.dds` file at the chosen mip level and shows it in the texture preview window.
+pub fn preview_texture(
+    mut asset_selections: EventReader<AssetSelected>,
+    data_files: Res<DataFilesResource>,
+    mut ui_state: ResMut<ui_state::State>,
+    mut egui_context: ResMut<EguiContext>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut current: Local<Option<Handle<Texture>>>,
+) {
+    let path = match asset_selections
+        .iter()
+        .filter(|AssetSelected(path)| path.ends_with(".dds"))
+        .last()
+    {
+        Some(AssetSelected(path)) => path.clone(),
+        None => return,
+    };
+
+    let ui_state = &mut *ui_state;
+    let state = &mut ui_state.texture_preview;
+
+    if state.info.as_ref().map(|info| &info.path) != Some(&path) {
+        state.mip_level = 0;
+    }
+
+    let dds = match data_files.vfs.read(&path).and_then(|bytes| Dds::parse(&bytes)) {
+        Ok(dds) => dds,
+        Err(e) => {
+            log::error!("Error loading texture {}: {}", path, e);
+            state.info = None;
+            state.error = Some(format!("{}: {}", path, e));
+            ui_state.show_texture_preview = true;
+            return;
+        }
+    };
+
+    state.mip_level = state.mip_level.min(dds.mip_count() - 1);
+
+    match textures::texture(&dds, state.mip_level, true) {
+        Ok(texture) => {
+            if let Some(previous) = current.take() {
+                textures.remove(previous);
+            }
+
+            let handle = textures.add(texture);
+            egui_context.set_egui_texture(TEXTURE_PREVIEW_ID, handle.clone());
+            *current = Some(handle);
+            state.error = None;
+        }
+        Err(e) => state.error = Some(format!("{}: {}", path, e)),
+    }
+
+    state.info = Some(TextureInfo {
+        path,
+        format: dds.format.to_string(),
+        width: dds.width,
+        height: dds.height,
+        mip_count: dds.mip_count(),
+        is_cubemap: dds.is_cubemap,
+    });
+
+    ui_state.show_texture_preview = true;
+}
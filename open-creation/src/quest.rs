@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use open_creation_data::esp::Quest;
+use open_creation_util::log;
+
+use crate::{
+    records::{RecordSelected, RecordsResource},
+    ui_state,
+};
+
+/// Sent when the quest in the Quest window has been edited.
+pub struct QuestEdited;
+
+/// Opens QUST records selected in the tree view in the Quest window.
+pub fn open_quests(
+    mut record_selections: EventReader<RecordSelected>,
+    records: Res<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+) {
+    for RecordSelected(form_id) in record_selections.iter() {
+        let load_order = &records.load_order;
+
+        let record = match load_order.record(*form_id) {
+            Some(record) if &record.code == b"QUST" => record,
+            _ => continue,
+        };
+
+        match Quest::from_record(record, load_order.is_localized(*form_id)) {
+            Ok(mut quest) => {
+                load_order.resolve_texts(*form_id, quest.texts_mut());
+                ui_state.quest.open(quest);
+                ui_state.show_quest = true;
+            }
+            Err(e) => log::error!("Error reading quest {:08X}: {}", form_id, e),
+        }
+    }
+}
+
+/// Writes the quest being edited to the active plugin, copying it there first if it comes from another plugin.
+pub fn apply_quest_edits(
+    mut edits: EventReader<QuestEdited>,
+    mut records: ResMut<RecordsResource>,
+    ui_state: Res<ui_state::State>,
+) {
+    if edits.iter().count() == 0 {
+        return;
+    }
+
+    let quest = match &ui_state.quest.quest {
+        Some(quest) => quest,
+        None => return,
+    };
+
+    let renamed = records.load_order.editor_id(quest.form_id).as_deref() != Some(quest.editor_id.as_str());
+
+    match records.load_order.override_record(quest.form_id) {
+        Some(record) => quest.write(record),
+        None => {
+            log::warn!("Cannot save quest {:08X} without an active plugin", quest.form_id);
+            return;
+        }
+    }
+
+    if renamed {
+        records.invalidate(*b"QUST");
+    }
+}
@@ -0,0 +1,184 @@
+//! Rays from the camera through the cursor, for picking objects in the viewport.
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::PerspectiveProjection,
+        mesh::{Indices, VertexAttributeValues},
+    },
+};
+
+use crate::model::Bounds;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Vec3,
+    /// A unit vector.
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// The ray from a camera through the cursor, or `None` if the cursor is outside the primary window.
+    pub fn from_cursor(
+        windows: &Windows,
+        camera: &GlobalTransform,
+        projection: &PerspectiveProjection,
+    ) -> Option<Self> {
+        let window = windows.get_primary()?;
+        let cursor = window.cursor_position()?;
+
+        // Bevy puts the cursor origin at the bottom left, like normalized device coordinates.
+        let x = cursor.x / window.width() * 2.0 - 1.0;
+        let y = cursor.y / window.height() * 2.0 - 1.0;
+        let half_height = (projection.fov * 0.5).tan();
+        let direction = Vec3::new(x * half_height * projection.aspect_ratio, y * half_height, -1.0);
+
+        Some(Self {
+            origin: camera.translation,
+            direction: (camera.rotation * direction).normalize(),
+        })
+    }
+
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// The distance along the ray to an object's bounds, if it hits them.
+    pub fn intersect_bounds(&self, transform: &GlobalTransform, bounds: &Bounds) -> Option<f32> {
+        // Work in the object's space, where the bounds are an axis-aligned box. Distances along the ray stay the
+        // same because the direction is transformed without being normalized again.
+        let inverse = transform.rotation.conjugate();
+        let origin: [f32; 3] = ((inverse * (self.origin - transform.translation)) / transform.scale).into();
+        let direction: [f32; 3] = ((inverse * self.direction) / transform.scale).into();
+        let min: [f32; 3] = bounds.min.into();
+        let max: [f32; 3] = bounds.max.into();
+
+        let mut near = 0.0f32;
+        let mut far = f32::MAX;
+
+        for ((&origin, &direction), (&min, &max)) in origin.iter().zip(&direction).zip(min.iter().zip(&max)) {
+            if direction.abs() < f32::EPSILON {
+                if origin < min || origin > max {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let a = (min - origin) / direction;
+            let b = (max - origin) / direction;
+            near = near.max(a.min(b));
+            far = far.min(a.max(b));
+
+            if near > far {
+                return None;
+            }
+        }
+
+        Some(near)
+    }
+
+    /// The distance along the ray to where it crosses a plane, if it does so in front of the origin.
+    pub fn intersect_plane(&self, point: Vec3, normal: Vec3) -> Option<f32> {
+        let denominator = self.direction.dot(normal);
+
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let distance = (point - self.origin).dot(normal) / denominator;
+
+        if distance >= 0.0 {
+            Some(distance)
+        } else {
+            None
+        }
+    }
+
+    /// The distance along the ray to a triangle, if it hits either side of it in front of the origin.
+    pub fn intersect_triangle(&self, corners: [Vec3; 3]) -> Option<f32> {
+        let edge1 = corners[1] - corners[0];
+        let edge2 = corners[2] - corners[0];
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let offset = self.origin - corners[0];
+        let u = offset.dot(p) / determinant;
+
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = offset.cross(edge1);
+        let v = self.direction.dot(q) / determinant;
+
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge2.dot(q) / determinant;
+
+        if distance >= 0.0 {
+            Some(distance)
+        } else {
+            None
+        }
+    }
+
+    /// The distance along the ray to the nearest triangle of a mesh drawn with `transform`, if it hits one.
+    pub fn intersect_mesh(&self, transform: &GlobalTransform, mesh: &Mesh) -> Option<f32> {
+        let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float3(positions)) => positions,
+            _ => return None,
+        };
+
+        let indices: Vec<usize> = match mesh.indices() {
+            Some(Indices::U16(indices)) => indices.iter().map(|&index| index as usize).collect(),
+            Some(Indices::U32(indices)) => indices.iter().map(|&index| index as usize).collect(),
+            None => (0..positions.len()).collect(),
+        };
+        let corner = |index: usize| {
+            positions
+                .get(index)
+                .map(|&position| transform.mul_vec3(position.into()))
+        };
+
+        indices
+            .chunks_exact(3)
+            .filter_map(|triangle| {
+                let corners = [corner(triangle[0])?, corner(triangle[1])?, corner(triangle[2])?];
+                self.intersect_triangle(corners)
+            })
+            .fold(None, |nearest: Option<f32>, distance| match nearest {
+                Some(nearest) if nearest <= distance => Some(nearest),
+                _ => Some(distance),
+            })
+    }
+
+    /// The distance along the ray to the point on it closest to `point`, and how far apart the two are.
+    pub fn closest_to_point(&self, point: Vec3) -> (f32, f32) {
+        let distance = (point - self.origin).dot(self.direction);
+        (distance, (self.at(distance) - point).length())
+    }
+
+    /// The position along a line closest to the ray, as a distance from `point` in units of `direction`.
+    pub fn closest_on_line(&self, point: Vec3, direction: Vec3) -> Option<f32> {
+        let offset = point - self.origin;
+        let a = direction.dot(direction);
+        let b = direction.dot(self.direction);
+        let d = direction.dot(offset);
+        let e = self.direction.dot(offset);
+        let denominator = a - b * b;
+
+        // Parallel lines have no single closest point.
+        if denominator.abs() < 1e-6 {
+            return None;
+        }
+
+        Some((b * e - d) / denominator)
+    }
+}
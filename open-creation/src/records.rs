@@ -0,0 +1,112 @@
+use std::{collections::HashMap, path::Path};
+
+use open_creation_data::{
+    esp::{Code, LoadOrder},
+    vfs::VirtualFileSystem,
+};
+use open_creation_ui::{CellItem, CellList, WorldItem};
+use open_creation_util::{log, Settings};
+
+pub struct RecordsResource {
+    pub load_order: LoadOrder,
+    editor_ids: HashMap<Code, Vec<(u32, String)>>,
+    cells: Option<CellList>,
+}
+
+/// Sent when a record is chosen in the tree view or another window, with its form ID.
+pub struct RecordSelected(pub u32);
+
+impl RecordsResource {
+    /// Loads the plugins, and the string tables of localized ones from the data folder.
+    pub fn load(settings: &Settings, vfs: &VirtualFileSystem) -> Self {
+        let mut load_order = LoadOrder::load(
+            Path::new(&settings.data_path),
+            &settings.load_order,
+            settings.active_plugin.as_deref(),
+        );
+        load_order.load_strings(vfs, &settings.language);
+
+        Self {
+            load_order,
+            editor_ids: HashMap::new(),
+            cells: None,
+        }
+    }
+
+    /// Writes the active plugin to the data folder, logging the result. Returns whether it was saved.
+    pub fn save(&mut self, settings: &Settings) -> bool {
+        match self.load_order.save_active(Path::new(&settings.data_path)) {
+            Ok(path) => {
+                log::info!("Saved {}", path.to_string_lossy());
+                true
+            }
+            Err(e) => {
+                log::error!("Error saving the active plugin: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Form IDs and editor IDs of the winning records with the given code, sorted by editor ID.
+    ///
+    /// Reading editor IDs means decompressing records, so the list is built on first use and cached.
+    pub fn editor_ids(&mut self, code: Code) -> &[(u32, String)] {
+        let load_order = &self.load_order;
+
+        self.editor_ids.entry(code).or_insert_with(|| {
+            let mut editor_ids: Vec<(u32, String)> = load_order
+                .records_by_code(code)
+                .into_iter()
+                .filter_map(|record| record.editor_id().map(|editor_id| (record.form_id, editor_id)))
+                .collect();
+
+            editor_ids.sort_by_cached_key(|(_, editor_id)| editor_id.to_lowercase());
+            editor_ids
+        })
+    }
+
+    /// Drops cached editor IDs for a record type, after records of that type are added, renamed or removed.
+    pub fn invalidate(&mut self, code: Code) {
+        self.editor_ids.remove(&code);
+    }
+
+    /// Interior cells and each worldspace's exterior cells, built on first use like [`Self::editor_ids`].
+    pub fn cells(&mut self) -> &CellList {
+        let load_order = &self.load_order;
+
+        self.cells.get_or_insert_with(|| {
+            let mut list = CellList::default();
+            let mut exteriors: HashMap<u32, Vec<CellItem>> = HashMap::new();
+
+            for cell in load_order.cells() {
+                let item = CellItem {
+                    form_id: cell.form_id,
+                    editor_id: cell.editor_id.clone().unwrap_or_default(),
+                    grid: cell.grid,
+                };
+
+                match cell.world {
+                    Some(world) if !cell.is_interior() => exteriors.entry(world).or_default().push(item),
+                    _ => list.interiors.push(item),
+                }
+            }
+
+            list.interiors.sort_by_cached_key(|cell| cell.editor_id.to_lowercase());
+
+            for record in load_order.records_by_code(*b"WRLD") {
+                let mut cells = exteriors.remove(&record.form_id).unwrap_or_default();
+                // The persistent cell has no grid position, so it sorts first.
+                cells.sort_by_key(|cell| cell.grid);
+
+                list.worlds.push(WorldItem {
+                    form_id: record.form_id,
+                    editor_id: record.editor_id().unwrap_or_default(),
+                    cells,
+                });
+            }
+
+            list.worlds.sort_by_cached_key(|world| world.editor_id.to_lowercase());
+            list
+        })
+    }
+}
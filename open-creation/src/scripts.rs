@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use open_creation_data::esp::Vmad;
+use open_creation_util::log;
+
+use crate::{
+    records::{RecordSelected, RecordsResource},
+    ui_state,
+};
+
+/// Sent when a property has been edited in the Scripts window.
+pub struct ScriptsEdited;
+
+/// Shows the scripts of records selected in the tree view in the Scripts window.
+pub fn open_scripts(
+    mut record_selections: EventReader<RecordSelected>,
+    records: Res<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+) {
+    for RecordSelected(form_id) in record_selections.iter() {
+        let record = match records.load_order.record(*form_id) {
+            Some(record) => record,
+            None => continue,
+        };
+
+        let vmad = match Vmad::read(record) {
+            Ok(vmad) => vmad,
+            Err(e) => {
+                log::error!("Error reading the scripts of {:08X}: {}", form_id, e);
+                None
+            }
+        };
+
+        let editor_id = record.editor_id().unwrap_or_default();
+        ui_state.scripts.open(*form_id, editor_id, record.code, vmad);
+    }
+}
+
+/// Writes the scripts being edited to the active plugin, copying the record there first if it comes from
+/// another plugin.
+pub fn apply_script_edits(
+    mut edits: EventReader<ScriptsEdited>,
+    mut records: ResMut<RecordsResource>,
+    ui_state: Res<ui_state::State>,
+) {
+    if edits.iter().count() == 0 {
+        return;
+    }
+
+    let state = &ui_state.scripts;
+    let vmad = match &state.vmad {
+        Some(vmad) => vmad,
+        None => return,
+    };
+
+    let record = match records.load_order.override_record(state.form_id) {
+        Some(record) => record,
+        None => {
+            log::warn!(
+                "Cannot save the scripts of {:08X} without an active plugin",
+                state.form_id
+            );
+            return;
+        }
+    };
+
+    if let Err(e) = vmad.write(record) {
+        log::error!("Error saving the scripts of {:08X}: {}", state.form_id, e);
+    }
+}
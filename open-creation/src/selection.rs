@@ -0,0 +1,141 @@
+use bevy::{prelude::*, render::camera::PerspectiveProjection};
+use bevy_egui::EguiContext;
+use open_creation_data::esp::{code_name, Reference};
+use open_creation_util::log;
+
+use crate::{
+    camera::{FrameRequest, ViewportCamera},
+    cell::PlacedReference,
+    gizmo::GizmoState,
+    model::Bounds,
+    ray::Ray,
+    records::RecordsResource,
+    ui_state,
+};
+
+/// Marks the root entity of each selected object in the viewport.
+pub struct Selected;
+
+/// Sent to make a placed reference the only selected object, with its form ID, or to clear the selection.
+pub struct SelectReference {
+    pub form_id: Option<u32>,
+    /// Whether to frame the camera on the reference once it is selected.
+    pub frame: bool,
+}
+
+/// Selects the reference under the cursor when the viewport is clicked, or clears the selection on a miss.
+/// Clicks belong to the landscape brushes or navmesh editing while the Landscape or Navmesh window is open.
+#[allow(clippy::too_many_arguments)]
+pub fn pick_references(
+    egui_context: Res<EguiContext>,
+    buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    gizmo: Res<GizmoState>,
+    ui_state: Res<ui_state::State>,
+    mut selections: EventWriter<SelectReference>,
+    cameras: Query<(&GlobalTransform, &PerspectiveProjection), With<ViewportCamera>>,
+    placed: Query<(&PlacedReference, &GlobalTransform, &Bounds)>,
+) {
+    if !buttons.just_pressed(MouseButton::Left)
+        || egui_context.ctx().wants_pointer_input()
+        || gizmo.is_busy()
+        || ui_state.show_landscape
+        || ui_state.show_navmesh
+    {
+        return;
+    }
+
+    let ray = match cameras.iter().next() {
+        Some((transform, projection)) => Ray::from_cursor(&windows, transform, projection),
+        None => None,
+    };
+
+    let ray = match ray {
+        Some(ray) => ray,
+        None => return,
+    };
+
+    let nearest = placed
+        .iter()
+        .filter_map(|(PlacedReference(form_id), transform, bounds)| {
+            ray.intersect_bounds(transform, bounds)
+                .map(|distance| (distance, *form_id))
+        })
+        .fold(None, |nearest: Option<(f32, u32)>, hit| match nearest {
+            Some(nearest) if nearest.0 <= hit.0 => Some(nearest),
+            _ => Some(hit),
+        });
+
+    selections.send(SelectReference {
+        form_id: nearest.map(|(_, form_id)| form_id),
+        frame: false,
+    });
+}
+
+pub fn select_references(
+    mut commands: Commands,
+    mut requests: EventReader<SelectReference>,
+    mut frame_requests: EventWriter<FrameRequest>,
+    records: Res<RecordsResource>,
+    mut ui_state: ResMut<ui_state::State>,
+    selected: Query<Entity, With<Selected>>,
+    placed: Query<(Entity, &PlacedReference, &GlobalTransform, &Bounds)>,
+) {
+    let request = match requests.iter().last() {
+        Some(request) => request,
+        None => return,
+    };
+
+    for entity in selected.iter() {
+        commands.entity(entity).remove::<Selected>();
+    }
+
+    let ui_state = &mut *ui_state;
+    ui_state.cell_view.selected_reference = request.form_id;
+    ui_state.transform.target = None;
+
+    let form_id = match request.form_id {
+        Some(form_id) => form_id,
+        None => return,
+    };
+
+    show_in_transform_panel(&records, form_id, ui_state);
+
+    let found = placed
+        .iter()
+        .find(|(_, PlacedReference(placed_form_id), _, _)| *placed_form_id == form_id);
+
+    match found {
+        Some((entity, _, transform, bounds)) => {
+            commands.entity(entity).insert(Selected);
+
+            if request.frame {
+                let scale = transform.scale.x.max(transform.scale.y).max(transform.scale.z);
+
+                frame_requests.send(FrameRequest {
+                    center: transform.mul_vec3(bounds.center()),
+                    radius: bounds.radius() * scale,
+                });
+            }
+        }
+        None => log::debug!("Reference {:08X} has no model in the viewport", form_id),
+    }
+}
+
+/// Makes a reference the target of the transform panel, showing the panel.
+pub fn show_in_transform_panel(records: &RecordsResource, form_id: u32, ui_state: &mut ui_state::State) {
+    if let Some(reference) = records.load_order.record(form_id).and_then(Reference::from_record) {
+        let base = records
+            .load_order
+            .editor_id(reference.base)
+            .unwrap_or_else(|| format!("{:08X}", reference.base));
+
+        ui_state.cell_view.selected_reference = Some(form_id);
+        ui_state.transform.target = Some((
+            form_id,
+            format!("{} {:08X}: {}", code_name(reference.code), form_id, base),
+        ));
+        ui_state.transform.placement = reference.placement;
+        ui_state.show_transform = true;
+    }
+}
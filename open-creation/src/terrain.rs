@@ -0,0 +1,562 @@
+//! Terrain meshes for the loaded exterior cell and the cells around it, built from their LAND records.
+//!
+//! Bevy's standard material takes a single colour texture, so each cell's texture layers and vertex colours are
+//! blended on the CPU into one texture stretched over the whole cell.
+
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::Indices,
+        pipeline::PrimitiveTopology,
+        texture::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use open_creation_data::{
+    dds::Dds,
+    esp::{
+        land::{CELL_SIZE, LAND_SIZE, QUADRANT_SIZE, VERTEX_SPACING},
+        Land, LoadOrder,
+    },
+    path,
+    vfs::VirtualFileSystem,
+};
+use open_creation_ui::CellList;
+use open_creation_util::log;
+
+use crate::{
+    camera::FrameRequest,
+    cell::LoadedCell,
+    data_files::DataFilesResource,
+    model::{self, Bounds},
+    ray::Ray,
+    records::RecordsResource,
+    textures, ui_state,
+};
+
+/// How many cells around the loaded one have their terrain shown, in each direction.
+const GRID_RADIUS: i32 = 2;
+/// The width and height of each cell's blended texture.
+const BLENDED_SIZE: usize = 256;
+/// How many times landscape textures repeat across a cell, roughly as the game draws them.
+const TEXTURE_REPEATS: f32 = 6.0;
+/// Landscape textures are decoded from the mip level closest to this size, which is plenty for the blended
+/// texture and keeps decoding quick.
+const LAYER_TEXTURE_SIZE: u32 = 128;
+/// Quadrants without a BTXT use this texture, like the game.
+const DEFAULT_TEXTURE: &str = "textures\\landscape\\dirt02.dds";
+
+/// Marks a terrain mesh, with the form ID of the cell it belongs to.
+pub struct Terrain {
+    pub cell: u32,
+}
+
+/// A cell's terrain as shown in the viewport, kept so the landscape brushes can edit it.
+pub struct TerrainCell {
+    pub cell: u32,
+    pub land: Land,
+    mesh: Handle<Mesh>,
+    texture: Handle<Texture>,
+}
+
+/// The terrain in the viewport, by grid position.
+///
+/// Vertices are addressed in vertex steps from the worldspace origin. Neighbouring cells both store the vertices
+/// along their shared edge, so an edit made through a vertex's steps reaches every cell that has it.
+#[derive(Default)]
+pub struct LoadedTerrain {
+    pub cells: HashMap<(i32, i32), TerrainCell>,
+}
+
+/// A decoded landscape texture, kept in memory for blending.
+struct LayerImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl LayerImage {
+    /// The colour at texture coordinates that wrap around at 1.
+    fn sample(&self, u: f32, v: f32) -> [f32; 3] {
+        let x = ((u.rem_euclid(1.0) * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v.rem_euclid(1.0) * self.height as f32) as usize).min(self.height - 1);
+        let pixel = &self.pixels[(y * self.width + x) * 4..];
+
+        [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32]
+    }
+}
+
+/// Landscape textures decoded so far, by LTEX form ID, with `None` for the default texture. Failed loads are
+/// cached as `None` so they are only logged once.
+#[derive(Default)]
+pub struct LandTextureCache {
+    images: HashMap<Option<u32>, Option<LayerImage>>,
+}
+
+/// The diffuse texture of an LTEX record, through its TXST texture set.
+fn texture_path(load_order: &LoadOrder, texture: u32) -> Option<String> {
+    let texture_set = load_order.record(texture)?.subrecord(*b"TNAM")?.as_u32()?;
+    let path = load_order.record(texture_set)?.subrecord(*b"TX00")?.as_string();
+    let path = path::normalize(&path);
+
+    if path.starts_with("textures\\") {
+        Some(path)
+    } else {
+        Some(format!("textures\\{}", path))
+    }
+}
+
+impl LandTextureCache {
+    /// Decodes every texture a cell's terrain draws, including the default one.
+    pub fn load_land(&mut self, land: &Land, load_order: &LoadOrder, vfs: &VirtualFileSystem) {
+        self.load(None, load_order, vfs);
+
+        for texture in land.base_textures.iter().flatten() {
+            self.load(Some(*texture), load_order, vfs);
+        }
+
+        for layer in &land.layers {
+            self.load(Some(layer.texture), load_order, vfs);
+        }
+    }
+
+    pub fn load(&mut self, texture: Option<u32>, load_order: &LoadOrder, vfs: &VirtualFileSystem) {
+        self.images.entry(texture).or_insert_with(|| {
+            let path = match texture {
+                Some(texture) => match texture_path(load_order, texture) {
+                    Some(path) => path,
+                    None => {
+                        log::warn!("Landscape texture {:08X} has no diffuse texture", texture);
+                        return None;
+                    }
+                },
+                None => DEFAULT_TEXTURE.to_string(),
+            };
+
+            let image = vfs.read(&path).and_then(|bytes| Dds::parse(&bytes)).and_then(|dds| {
+                let level = textures::level_within(&dds, LAYER_TEXTURE_SIZE);
+                let (width, height) = dds.mip_dimensions(level);
+
+                Ok(LayerImage {
+                    width: width as usize,
+                    height: height as usize,
+                    pixels: dds.decode(level)?,
+                })
+            });
+
+            match image {
+                Ok(image) => Some(image),
+                Err(e) => {
+                    log::warn!("Error loading landscape texture {}: {}", path, e);
+                    None
+                }
+            }
+        });
+    }
+
+    fn get(&self, texture: Option<u32>) -> Option<&LayerImage> {
+        self.images.get(&texture).and_then(Option::as_ref)
+    }
+}
+
+/// Interpolates between the four values of a square grid around a point given in grid steps.
+fn bilinear(x: f32, y: f32, size: usize, value: impl Fn(usize) -> f32) -> f32 {
+    let x0 = (x.floor() as usize).min(size - 2);
+    let y0 = (y.floor() as usize).min(size - 2);
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+    let at = |x: usize, y: usize| value(y * size + x);
+
+    let south = at(x0, y0) * (1.0 - fx) + at(x0 + 1, y0) * fx;
+    let north = at(x0, y0 + 1) * (1.0 - fx) + at(x0 + 1, y0 + 1) * fx;
+    south * (1.0 - fy) + north * fy
+}
+
+/// Blends a cell's base textures, texture layers and vertex colours into one texture, with rows running north
+/// to south.
+fn blend_textures(land: &Land, cache: &LandTextureCache) -> Texture {
+    let default = cache.get(None);
+    let bases: Vec<Option<&LayerImage>> = land
+        .base_textures
+        .iter()
+        .map(|&texture| texture.and_then(|texture| cache.get(Some(texture))).or(default))
+        .collect();
+
+    let mut layers: [Vec<(&[f32], &LayerImage)>; 4] = Default::default();
+    for layer in &land.layers {
+        if let Some(image) = cache.get(Some(layer.texture)) {
+            layers[layer.quadrant].push((&layer.opacity[..], image));
+        }
+    }
+
+    let last = (LAND_SIZE - 1) as f32;
+    let half = (QUADRANT_SIZE - 1) as f32;
+    let mut data = Vec::with_capacity(BLENDED_SIZE * BLENDED_SIZE * 4);
+
+    for row in 0..BLENDED_SIZE {
+        // Positions in vertex steps from the south-west corner.
+        let y = (1.0 - (row as f32 + 0.5) / BLENDED_SIZE as f32) * last;
+
+        for column in 0..BLENDED_SIZE {
+            let x = (column as f32 + 0.5) / BLENDED_SIZE as f32 * last;
+            let quadrant = Land::quadrant(x as usize, y as usize);
+            let (u, v) = (x / last * TEXTURE_REPEATS, 1.0 - y / last * TEXTURE_REPEATS);
+
+            let mut color = bases[quadrant].map(|image| image.sample(u, v)).unwrap_or([128.0; 3]);
+
+            let local_x = x - half * (quadrant % 2) as f32;
+            let local_y = y - half * (quadrant / 2) as f32;
+
+            for (opacity, image) in &layers[quadrant] {
+                let opacity = bilinear(local_x, local_y, QUADRANT_SIZE, |index| opacity[index]);
+                let layer = image.sample(u, v);
+
+                for (channel, layer) in color.iter_mut().zip(layer.iter()) {
+                    *channel += (layer - *channel) * opacity;
+                }
+            }
+
+            if let Some(colors) = &land.colors {
+                for (index, channel) in color.iter_mut().enumerate() {
+                    *channel *= bilinear(x, y, LAND_SIZE, |vertex| colors[vertex][index] as f32 / 255.0);
+                }
+            }
+
+            data.extend(color.iter().map(|&channel| channel.round().clamp(0.0, 255.0) as u8));
+            data.push(255);
+        }
+    }
+
+    Texture::new(
+        Extent3d::new(BLENDED_SIZE as u32, BLENDED_SIZE as u32, 1),
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+/// A mesh of a cell's terrain in viewport coordinates, or `None` if the LAND record has no heights.
+fn terrain_mesh(land: &Land, grid: (i32, i32)) -> Option<Mesh> {
+    let heights = land.heights.as_ref()?;
+    let origin = [grid.0 as f32 * CELL_SIZE, grid.1 as f32 * CELL_SIZE];
+    let last = (LAND_SIZE - 1) as f32;
+
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(LAND_SIZE * LAND_SIZE);
+    let mut uvs = Vec::with_capacity(LAND_SIZE * LAND_SIZE);
+
+    for y in 0..LAND_SIZE {
+        for x in 0..LAND_SIZE {
+            let position = [
+                origin[0] + x as f32 * VERTEX_SPACING,
+                origin[1] + y as f32 * VERTEX_SPACING,
+                heights[y * LAND_SIZE + x],
+            ];
+
+            positions.push(model::to_viewport(position).into());
+            uvs.push([x as f32 / last, 1.0 - y as f32 / last]);
+        }
+    }
+
+    let normals: Vec<[f32; 3]> = match &land.normals {
+        Some(normals) => normals.iter().map(|n| [n[0], n[2], -n[1]]).collect(),
+        None => vec![[0.0, 1.0, 0.0]; LAND_SIZE * LAND_SIZE],
+    };
+
+    let mut indices = Vec::with_capacity((LAND_SIZE - 1) * (LAND_SIZE - 1) * 6);
+
+    for y in 0..LAND_SIZE - 1 {
+        for x in 0..LAND_SIZE - 1 {
+            let south_west = (y * LAND_SIZE + x) as u16;
+            let south_east = south_west + 1;
+            let north_west = south_west + LAND_SIZE as u16;
+            let north_east = north_west + 1;
+
+            indices.extend_from_slice(&[south_west, south_east, north_east, south_west, north_east, north_west]);
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U16(indices)));
+    Some(mesh)
+}
+
+/// The grid positions of the cells sharing a vertex, with the vertex's index in each. A vertex on a cell's edge
+/// or corner is shared with up to three neighbours.
+pub fn vertex_cells(x: i32, y: i32) -> Vec<((i32, i32), usize)> {
+    let last = LAND_SIZE as i32 - 1;
+    let spans = |step: i32| {
+        let (cell, offset) = (step.div_euclid(last), step.rem_euclid(last));
+
+        if offset == 0 {
+            vec![(cell, 0), (cell - 1, last)]
+        } else {
+            vec![(cell, offset)]
+        }
+    };
+
+    let mut cells = vec![];
+
+    for &(cell_y, offset_y) in &spans(y) {
+        for &(cell_x, offset_x) in &spans(x) {
+            cells.push(((cell_x, cell_y), offset_y as usize * LAND_SIZE + offset_x as usize));
+        }
+    }
+
+    cells
+}
+
+impl LoadedTerrain {
+    /// The height of a vertex, if a loaded cell has it.
+    pub fn vertex_height(&self, x: i32, y: i32) -> Option<f32> {
+        vertex_cells(x, y).into_iter().find_map(|(grid, index)| {
+            let heights = self.cells.get(&grid)?.land.heights.as_ref()?;
+            Some(heights[index])
+        })
+    }
+
+    /// The terrain height at a point in game units, interpolated between the vertices around it.
+    pub fn height_at(&self, x: f32, y: f32) -> Option<f32> {
+        let (x, y) = (x / VERTEX_SPACING, y / VERTEX_SPACING);
+        let (x0, y0) = (x.floor() as i32, y.floor() as i32);
+        let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+        let south = self.vertex_height(x0, y0)? * (1.0 - fx) + self.vertex_height(x0 + 1, y0)? * fx;
+        let north = self.vertex_height(x0, y0 + 1)? * (1.0 - fx) + self.vertex_height(x0 + 1, y0 + 1)? * fx;
+        Some(south * (1.0 - fy) + north * fy)
+    }
+
+    /// Where a ray first passes down through the terrain, in viewport coordinates. The ray is followed in steps
+    /// of half a vertex and the crossing is then narrowed down between the last two steps.
+    pub fn raycast(&self, ray: &Ray) -> Option<Vec3> {
+        let step = VERTEX_SPACING * 0.5 * model::UNIT;
+        let max_distance = CELL_SIZE * (2 * GRID_RADIUS + 2) as f32 * model::UNIT * 2.0;
+        let below = |distance: f32| {
+            let [x, y, z] = model::from_viewport(ray.at(distance));
+            self.height_at(x, y).map(|height| z <= height)
+        };
+
+        let mut previous = 0.0;
+        let mut was_above = below(0.0) == Some(false);
+        let mut distance = step;
+
+        while distance < max_distance {
+            match below(distance) {
+                Some(true) if was_above => {
+                    let (mut low, mut high) = (previous, distance);
+
+                    for _ in 0..8 {
+                        let middle = (low + high) * 0.5;
+
+                        if below(middle) == Some(true) {
+                            high = middle;
+                        } else {
+                            low = middle;
+                        }
+                    }
+
+                    return Some(ray.at(high));
+                }
+                Some(is_below) => was_above = !is_below,
+                None => was_above = false,
+            }
+
+            previous = distance;
+            distance += step;
+        }
+
+        None
+    }
+
+    /// Recalculates a cell's normals from its heights, using the neighbouring cells' heights along its edges so
+    /// that lighting matches across the border.
+    pub fn update_normals(&mut self, grid: (i32, i32)) {
+        let last = LAND_SIZE as i32 - 1;
+        let (origin_x, origin_y) = (grid.0 * last, grid.1 * last);
+        let mut normals = Vec::with_capacity(LAND_SIZE * LAND_SIZE);
+
+        for y in origin_y..=origin_y + last {
+            for x in origin_x..=origin_x + last {
+                let height = |x: i32, y: i32| self.vertex_height(x, y);
+                let center = height(x, y).unwrap_or(0.0);
+                let slope = |before: Option<f32>, after: Option<f32>| {
+                    let steps = before.is_some() as i32 + after.is_some() as i32;
+
+                    if steps == 0 {
+                        0.0
+                    } else {
+                        (after.unwrap_or(center) - before.unwrap_or(center)) / (steps as f32 * VERTEX_SPACING)
+                    }
+                };
+
+                let normal = Vec3::new(
+                    -slope(height(x - 1, y), height(x + 1, y)),
+                    -slope(height(x, y - 1), height(x, y + 1)),
+                    1.0,
+                )
+                .normalize();
+
+                normals.push([normal.x, normal.y, normal.z]);
+            }
+        }
+
+        if let Some(cell) = self.cells.get_mut(&grid) {
+            cell.land.normals = Some(normals);
+        }
+    }
+
+    /// Rebuilds a cell's mesh after its heights change, and its texture too if `retexture` is set.
+    pub fn refresh(
+        &self,
+        grid: (i32, i32),
+        retexture: bool,
+        cache: &LandTextureCache,
+        meshes: &mut Assets<Mesh>,
+        textures: &mut Assets<Texture>,
+    ) {
+        let cell = match self.cells.get(&grid) {
+            Some(cell) => cell,
+            None => return,
+        };
+
+        if let (Some(mesh), Some(rebuilt)) = (meshes.get_mut(&cell.mesh), terrain_mesh(&cell.land, grid)) {
+            *mesh = rebuilt;
+        }
+
+        if retexture {
+            if let Some(texture) = textures.get_mut(&cell.texture) {
+                *texture = blend_textures(&cell.land, cache);
+            }
+        }
+    }
+}
+
+/// The exterior cells within [`GRID_RADIUS`] of a cell in the same worldspace, with their grid positions. Empty
+/// for interiors and persistent cells.
+fn surrounding_cells(cells: &CellList, cell: u32) -> Vec<(u32, (i32, i32))> {
+    for world in &cells.worlds {
+        let center = world
+            .cells
+            .iter()
+            .find(|item| item.form_id == cell)
+            .and_then(|item| item.grid);
+
+        if let Some((center_x, center_y)) = center {
+            return world
+                .cells
+                .iter()
+                .filter_map(|item| Some((item.form_id, item.grid?)))
+                .filter(|(_, (x, y))| (x - center_x).abs() <= GRID_RADIUS && (y - center_y).abs() <= GRID_RADIUS)
+                .collect();
+        }
+    }
+
+    vec![]
+}
+
+/// Replaces the terrain when a cell is loaded, showing the terrain of the cells around it too. The camera is
+/// framed on the loaded cell's terrain if the cell has no references to frame.
+#[allow(clippy::too_many_arguments)]
+pub fn load_terrain(
+    mut commands: Commands,
+    loaded_cell: Res<LoadedCell>,
+    mut records: ResMut<RecordsResource>,
+    data_files: Res<DataFilesResource>,
+    mut cache: ResMut<LandTextureCache>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut loaded_terrain: ResMut<LoadedTerrain>,
+    ui_state: Res<ui_state::State>,
+    mut frame_requests: EventWriter<FrameRequest>,
+    terrain: Query<Entity, With<Terrain>>,
+) {
+    if !loaded_cell.is_changed() {
+        return;
+    }
+
+    for entity in terrain.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    loaded_terrain.cells.clear();
+
+    let loaded = match loaded_cell.form_id {
+        Some(cell) => cell,
+        None => return,
+    };
+
+    let cells = surrounding_cells(records.cells(), loaded);
+    let load_order = &records.load_order;
+    let mut spawned = 0;
+
+    for (cell, grid) in cells {
+        let land = match load_order.cell_land(cell).map(Land::from_record) {
+            Some(Ok(land)) => land,
+            Some(Err(e)) => {
+                log::warn!("Error reading the terrain of cell {:08X}: {}", cell, e);
+                continue;
+            }
+            None => continue,
+        };
+
+        let mesh = match terrain_mesh(&land, grid) {
+            Some(mesh) => mesh,
+            None => continue,
+        };
+
+        cache.load_land(&land, load_order, &data_files.vfs);
+
+        if cell == loaded && ui_state.cell_view.references.is_empty() {
+            if let Some(heights) = &land.heights {
+                let corner = |x: f32, y: f32, height: f32| {
+                    model::to_viewport([(grid.0 as f32 + x) * CELL_SIZE, (grid.1 as f32 + y) * CELL_SIZE, height])
+                };
+                let low = heights.iter().cloned().fold(f32::MAX, f32::min);
+                let high = heights.iter().cloned().fold(f32::MIN, f32::max);
+                let bounds = Bounds::from_points(vec![corner(0.0, 0.0, low), corner(1.0, 1.0, high)].into_iter());
+
+                frame_requests.send(FrameRequest {
+                    center: bounds.center(),
+                    radius: bounds.radius(),
+                });
+            }
+        }
+
+        let mesh = meshes.add(mesh);
+        let texture = textures.add(blend_textures(&land, &cache));
+        let material = StandardMaterial {
+            base_color_texture: Some(texture.clone()),
+            roughness: 1.0,
+            metallic: 0.0,
+            reflectance: 0.1,
+            ..Default::default()
+        };
+
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: mesh.clone(),
+                material: materials.add(material),
+                ..Default::default()
+            })
+            .insert(Terrain { cell });
+
+        loaded_terrain.cells.insert(
+            grid,
+            TerrainCell {
+                cell,
+                land,
+                mesh,
+                texture,
+            },
+        );
+
+        spawned += 1;
+    }
+
+    if spawned > 0 {
+        log::info!("Loaded terrain for {} cells", spawned);
+    }
+}
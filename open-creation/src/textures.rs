@@ -0,0 +1,91 @@
+//! DDS textures as Bevy textures.
+
+use std::{collections::HashMap, io};
+
+use bevy::{
+    prelude::*,
+    render::texture::{AddressMode, Extent3d, FilterMode, SamplerDescriptor, TextureDimension, TextureFormat},
+};
+use open_creation_data::{dds::Dds, vfs::VirtualFileSystem};
+use open_creation_util::log;
+
+/// Textures larger than this are shown in the viewport from a smaller mip level. Bevy uploads one level per
+/// texture, so this keeps a cell full of 4K textures from exhausting video memory.
+pub const MAX_VIEWPORT_SIZE: u32 = 1024;
+
+/// Decodes one mip level into a repeating, linearly filtered texture. Colour textures are sRGB; data textures
+/// such as normal maps must not be.
+pub fn texture(dds: &Dds, level: usize, srgb: bool) -> io::Result<Texture> {
+    let (width, height) = dds.mip_dimensions(level);
+    let format = if srgb {
+        TextureFormat::Rgba8UnormSrgb
+    } else {
+        TextureFormat::Rgba8Unorm
+    };
+
+    let mut texture = Texture::new(
+        Extent3d::new(width, height, 1),
+        TextureDimension::D2,
+        dds.decode(level)?,
+        format,
+    );
+
+    texture.sampler = SamplerDescriptor {
+        address_mode_u: AddressMode::Repeat,
+        address_mode_v: AddressMode::Repeat,
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        ..Default::default()
+    };
+
+    Ok(texture)
+}
+
+/// The largest mip level that fits within [`MAX_VIEWPORT_SIZE`].
+pub fn viewport_level(dds: &Dds) -> usize {
+    level_within(dds, MAX_VIEWPORT_SIZE)
+}
+
+/// The largest mip level no wider or taller than `size`, or the smallest level if none is.
+pub fn level_within(dds: &Dds, size: u32) -> usize {
+    (0..dds.mip_count())
+        .find(|&level| {
+            let (width, height) = dds.mip_dimensions(level);
+            width.max(height) <= size
+        })
+        .unwrap_or_else(|| dds.mip_count() - 1)
+}
+
+/// Textures uploaded so far, by normalized data path and colour space. Failed loads are cached as `None`.
+#[derive(Default)]
+pub struct TextureCache {
+    textures: HashMap<(String, bool), Option<Handle<Texture>>>,
+}
+
+impl TextureCache {
+    pub fn load(
+        &mut self,
+        path: &str,
+        srgb: bool,
+        vfs: &VirtualFileSystem,
+        textures: &mut Assets<Texture>,
+    ) -> Option<Handle<Texture>> {
+        self.textures
+            .entry((path.to_string(), srgb))
+            .or_insert_with(|| {
+                let texture = vfs
+                    .read(path)
+                    .and_then(|bytes| Dds::parse(&bytes))
+                    .and_then(|dds| texture(&dds, viewport_level(&dds), srgb));
+
+                match texture {
+                    Ok(texture) => Some(textures.add(texture)),
+                    Err(e) => {
+                        log::warn!("Error loading texture {}: {}", path, e);
+                        None
+                    }
+                }
+            })
+            .clone()
+    }
+}
@@ -1,19 +1,97 @@
+use open_creation_ui::{
+    ArchiveBrowserState, CellViewState, CreateArchiveOptions, DialogueState, FactionGraphState, LandscapeState,
+    LeveledListState, LightingState, NavmeshState, NpcState, PapyrusState, QuestState, ScriptBrowserState,
+    ScriptsState, TexturePreviewState, TransformState,
+};
+
 pub struct State {
     pub should_close: bool,
+    /// Whether to ask about saving the active plugin's unsaved changes before closing.
+    pub confirm_close: bool,
     pub show_about: bool,
+    pub show_archive_browser: bool,
+    pub show_cell_view: bool,
+    pub show_create_archive: bool,
     pub show_data: bool,
+    pub show_dialogue: bool,
+    pub show_faction_graph: bool,
     pub show_game_settings: bool,
+    pub show_landscape: bool,
+    pub show_leveled_list: bool,
+    pub show_lighting: bool,
     pub show_log: bool,
+    pub show_navmesh: bool,
+    pub show_npc: bool,
+    pub show_papyrus: bool,
+    pub show_quest: bool,
+    pub show_script_browser: bool,
+    pub show_scripts: bool,
+    pub show_texture_preview: bool,
+    pub show_transform: bool,
+    pub selected_record: Option<u32>,
+    /// A record being dragged from the tree view, to be placed where it is dropped.
+    pub dragged_record: Option<u32>,
+    pub archive_browser: ArchiveBrowserState,
+    pub cell_view: CellViewState,
+    pub create_archive: CreateArchiveOptions,
+    pub dialogue: DialogueState,
+    pub faction_graph: FactionGraphState,
+    pub landscape: LandscapeState,
+    pub leveled_list: LeveledListState,
+    pub lighting: LightingState,
+    pub navmesh: NavmeshState,
+    pub npc: NpcState,
+    pub papyrus: PapyrusState,
+    pub quest: QuestState,
+    pub script_browser: ScriptBrowserState,
+    pub scripts: ScriptsState,
+    pub texture_preview: TexturePreviewState,
+    pub transform: TransformState,
 }
 
 impl State {
     pub fn new() -> Self {
         Self {
             should_close: false,
+            confirm_close: false,
             show_about: false,
+            show_archive_browser: false,
+            show_cell_view: false,
+            show_create_archive: false,
             show_data: false,
+            show_dialogue: false,
+            show_faction_graph: false,
             show_game_settings: false,
+            show_landscape: false,
+            show_leveled_list: false,
+            show_lighting: false,
             show_log: false,
+            show_navmesh: false,
+            show_npc: false,
+            show_papyrus: false,
+            show_quest: false,
+            show_script_browser: false,
+            show_scripts: false,
+            show_texture_preview: false,
+            show_transform: false,
+            selected_record: None,
+            dragged_record: None,
+            archive_browser: ArchiveBrowserState::default(),
+            cell_view: CellViewState::default(),
+            create_archive: CreateArchiveOptions::default(),
+            dialogue: DialogueState::default(),
+            faction_graph: FactionGraphState::default(),
+            landscape: LandscapeState::default(),
+            leveled_list: LeveledListState::default(),
+            lighting: LightingState::default(),
+            navmesh: NavmeshState::default(),
+            npc: NpcState::default(),
+            papyrus: PapyrusState::default(),
+            quest: QuestState::default(),
+            script_browser: ScriptBrowserState::default(),
+            scripts: ScriptsState::default(),
+            texture_preview: TexturePreviewState::default(),
+            transform: TransformState::default(),
         }
     }
 }